@@ -0,0 +1,47 @@
+//! Search code shared by the game AIs.
+//!
+//! Turn based games are searched with `mcts`, which only needs the rules, or with `minimax`,
+//! which needs a way to score a position as well but plays small games like noughts and crosses
+//! perfectly. Anything moving around a grid finds its way with `path`.
+//!
+//! Each search has its own settings for how strongly it plays, and a `with_difficulty` that
+//! picks them from a difficulty between 0 for the easiest and 1 for the hardest.
+
+pub mod mcts;
+pub mod minimax;
+pub mod path;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// A turn based game that can be searched.
+pub trait Game: Clone {
+    type Move: Copy + PartialEq;
+
+    /// The player whose turn it is, as an index from zero.
+    fn current_player(&self) -> usize;
+
+    /// Every move the current player can make. No moves means the game is over.
+    fn moves(&self) -> Vec<Self::Move>;
+
+    fn play(&mut self, mv: Self::Move);
+
+    /// How good the game is for `player`, from 0 for a loss to 1 for a win.
+    ///
+    /// This is called on finished games, and on playouts cut short after `Mcts::max_playout_moves`.
+    fn reward(&self, player: usize) -> f32;
+
+    /// Picks a move for a random playout, or `None` if the game is over.
+    ///
+    /// Defaults to any of `moves` at random. Games can override it with something cheaper or
+    /// smarter.
+    fn playout_move<R: Rng>(&self, rng: &mut R) -> Option<Self::Move> {
+        self.moves().choose(rng).copied()
+    }
+}
+
+/// The value a `difficulty` of the way from the easiest to the hardest.
+fn lerp((easiest, hardest): (f32, f32), difficulty: f32) -> f32 {
+    let difficulty = difficulty.clamp(0.0, 1.0);
+    easiest + (hardest - easiest) * difficulty
+}
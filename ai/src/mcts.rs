@@ -0,0 +1,168 @@
+//! Monte Carlo tree search, which picks moves by playing lots of random games from each option.
+
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+pub use crate::Game;
+
+/// The settings for a search.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mcts {
+    /// How many random games to play before picking a move. More plays stronger but slower.
+    pub playouts: u32,
+    /// The longest to spend picking a move, however few playouts that leaves time for.
+    pub time_limit: Option<Duration>,
+    /// How much the search favours trying less explored moves over the best ones so far.
+    pub exploration: f32,
+    /// The longest a playout can run before it is scored as it stands.
+    pub max_playout_moves: u32,
+}
+
+impl Default for Mcts {
+    fn default() -> Self {
+        Mcts {
+            playouts: 1000,
+            time_limit: None,
+            exploration: std::f32::consts::SQRT_2,
+            max_playout_moves: 500,
+        }
+    }
+}
+
+/// How many playouts the easiest and hardest searches play.
+const DIFFICULTY_PLAYOUTS: (f32, f32) = (50.0, 3000.0);
+
+/// A position in the search tree.
+struct Node<M> {
+    /// The move that led here from the parent.
+    mv: Option<M>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<M>,
+    /// The player who made `mv`, whose rewards this node adds up.
+    mover: usize,
+    visits: f32,
+    reward: f32,
+}
+
+impl Mcts {
+    /// Creates a search that plays `playouts` random games per move.
+    pub fn new(playouts: u32) -> Self {
+        Mcts {
+            playouts,
+            ..Mcts::default()
+        }
+    }
+
+    /// Creates a search that plays more random games per move the higher `difficulty` is, from
+    /// 0 to 1.
+    pub fn with_difficulty(difficulty: f32) -> Self {
+        Mcts::new(crate::lerp(DIFFICULTY_PLAYOUTS, difficulty) as u32)
+    }
+
+    /// Picks the best move for the current player, or `None` if the game is over.
+    pub fn search<G: Game, R: Rng>(&self, game: &G, rng: &mut R) -> Option<G::Move> {
+        let moves = game.moves();
+        if moves.len() <= 1 {
+            return moves.first().copied();
+        }
+
+        let mut nodes = vec![Node {
+            mv: None,
+            parent: None,
+            children: Vec::new(),
+            untried: moves,
+            mover: game.current_player(),
+            visits: 0.0,
+            reward: 0.0,
+        }];
+
+        let started = Instant::now();
+        for _ in 0..self.playouts {
+            if self
+                .time_limit
+                .map_or(false, |limit| started.elapsed() >= limit)
+            {
+                break;
+            }
+            let mut state = game.clone();
+            let mut node = 0;
+
+            // Follow the most promising moves down to a node that hasn't been fully expanded.
+            while nodes[node].untried.is_empty() && !nodes[node].children.is_empty() {
+                node = self.select(&nodes, node);
+                if let Some(mv) = nodes[node].mv {
+                    state.play(mv);
+                }
+            }
+
+            // Try one new move from there.
+            if !nodes[node].untried.is_empty() {
+                let index = rng.gen_range(0, nodes[node].untried.len());
+                let mv = nodes[node].untried.swap_remove(index);
+                let mover = state.current_player();
+                state.play(mv);
+                nodes.push(Node {
+                    mv: Some(mv),
+                    parent: Some(node),
+                    children: Vec::new(),
+                    untried: state.moves(),
+                    mover,
+                    visits: 0.0,
+                    reward: 0.0,
+                });
+                let child = nodes.len() - 1;
+                nodes[node].children.push(child);
+                node = child;
+            }
+
+            // Play the rest of the game out at random.
+            for _ in 0..self.max_playout_moves {
+                match state.playout_move(rng) {
+                    Some(mv) => state.play(mv),
+                    None => break,
+                }
+            }
+
+            // Credit the result to every move on the way down.
+            let mut current = Some(node);
+            while let Some(index) = current {
+                let node = &mut nodes[index];
+                node.visits += 1.0;
+                node.reward += state.reward(node.mover);
+                current = node.parent;
+            }
+        }
+
+        // The most visited move is the one the search trusts most.
+        nodes[0]
+            .children
+            .iter()
+            .max_by(|&&a, &&b| {
+                nodes[a]
+                    .visits
+                    .partial_cmp(&nodes[b].visits)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .and_then(|&child| nodes[child].mv)
+    }
+
+    /// Picks the child to explore with the upper confidence bound for trees.
+    fn select<M>(&self, nodes: &[Node<M>], parent: usize) -> usize {
+        let log_visits = nodes[parent].visits.max(1.0).ln();
+        let uct = |child: usize| {
+            let node = &nodes[child];
+            node.reward / node.visits + self.exploration * (log_visits / node.visits).sqrt()
+        };
+
+        let children = &nodes[parent].children;
+        let mut best = children[0];
+        for &child in children.iter().skip(1) {
+            if uct(child) > uct(best) {
+                best = child;
+            }
+        }
+        best
+    }
+}
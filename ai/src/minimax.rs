@@ -0,0 +1,290 @@
+//! Minimax search with alpha-beta pruning, which looks a set number of moves ahead and picks
+//! the move that does best if both players make their best moves after it.
+//!
+//! Lines of play that can't matter, because one player already has something better elsewhere,
+//! are cut off without being searched. The better the moves searched first, the more gets cut
+//! off, so games can say which of their moves look promising with `Evaluate::priority`.
+//!
+//! Games where a player sometimes moves twice in a row, like dots and boxes, are fine: the
+//! search follows whoever's turn it is.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::Game;
+
+/// What a won game is worth, far more than `Evaluate::evaluate` should ever give.
+const WIN: f32 = 1_000_000.0;
+
+/// How deep the easiest and hardest searches look.
+const DIFFICULTY_DEPTH: (f32, f32) = (1.0, 6.0);
+/// How often the easiest and hardest searches play a random move instead.
+const DIFFICULTY_MISTAKES: (f32, f32) = (0.3, 0.0);
+
+/// A two player game whose positions can be scored before it's over.
+pub trait Evaluate: Game {
+    /// How good the position is for `player`, higher being better. It should be as good for one
+    /// player as it is bad for the other, so each player's score is the other's negated.
+    fn evaluate(&self, player: usize) -> f32;
+
+    /// How promising `mv` looks for the current player, for searching the best looking moves
+    /// first. Defaults to every move looking the same.
+    fn priority(&self, _mv: Self::Move) -> f32 {
+        0.0
+    }
+}
+
+/// The settings for a search.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Minimax {
+    /// How many moves ahead to look. Each one more plays stronger but is many times slower.
+    pub depth: u32,
+    /// How often to play a random move instead of searching, from 0 to 1.
+    pub mistakes: f32,
+}
+
+impl Default for Minimax {
+    fn default() -> Self {
+        Minimax {
+            depth: 4,
+            mistakes: 0.0,
+        }
+    }
+}
+
+impl Minimax {
+    /// Creates a search that looks `depth` moves ahead.
+    pub fn new(depth: u32) -> Self {
+        Minimax {
+            depth,
+            ..Minimax::default()
+        }
+    }
+
+    /// Creates a search that looks further ahead and makes fewer mistakes the higher
+    /// `difficulty` is, from 0 to 1.
+    pub fn with_difficulty(difficulty: f32) -> Self {
+        Minimax {
+            depth: crate::lerp(DIFFICULTY_DEPTH, difficulty).round() as u32,
+            mistakes: crate::lerp(DIFFICULTY_MISTAKES, difficulty),
+        }
+    }
+
+    /// Picks the best move for the current player, or `None` if the game is over. Moves that
+    /// are as good as each other are picked between at random.
+    pub fn search<G: Evaluate, R: Rng>(&self, game: &G, rng: &mut R) -> Option<G::Move> {
+        let mut moves = game.moves();
+        if moves.len() <= 1 {
+            return moves.first().copied();
+        }
+        moves.shuffle(rng);
+        if rng.gen::<f32>() < self.mistakes {
+            return moves.first().copied();
+        }
+        sort_by_priority(game, &mut moves);
+
+        let player = game.current_player();
+        let depth = self.depth.max(1);
+        let mut best = None;
+        let mut alpha = -f32::INFINITY;
+        for mv in moves {
+            let mut next = game.clone();
+            next.play(mv);
+            let score = score_for(player, &next, depth - 1, alpha, f32::INFINITY);
+            if best.is_none() || score > alpha {
+                best = Some(mv);
+                alpha = score;
+            }
+        }
+        best
+    }
+}
+
+/// Puts the most promising moves first. The sort is stable, so moves that look the same keep
+/// their order.
+fn sort_by_priority<G: Evaluate>(game: &G, moves: &mut Vec<G::Move>) {
+    let mut prioritised: Vec<(f32, G::Move)> =
+        moves.iter().map(|&mv| (game.priority(mv), mv)).collect();
+    prioritised.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    *moves = prioritised.into_iter().map(|(_, mv)| mv).collect();
+}
+
+/// The score of `game` for `player`, who may or may not be the one to move next, searched
+/// between `alpha` and `beta` as seen by `player`.
+fn score_for<G: Evaluate>(player: usize, game: &G, depth: u32, alpha: f32, beta: f32) -> f32 {
+    if game.current_player() == player {
+        negamax(game, depth, alpha, beta)
+    } else {
+        -negamax(game, depth, -beta, -alpha)
+    }
+}
+
+/// The score of `game` for the player to move, searched `depth` moves deep. Anything outside
+/// `alpha` to `beta` is only known to be outside it.
+fn negamax<G: Evaluate>(game: &G, depth: u32, mut alpha: f32, beta: f32) -> f32 {
+    let player = game.current_player();
+    let mut moves = game.moves();
+    if moves.is_empty() {
+        // Wins found sooner are worth more, and losses found later are less bad.
+        return (game.reward(player) * 2.0 - 1.0) * (WIN + depth as f32);
+    }
+    if depth == 0 {
+        return game.evaluate(player);
+    }
+    sort_by_priority(game, &mut moves);
+
+    let mut best = -f32::INFINITY;
+    for mv in moves {
+        let mut next = game.clone();
+        next.play(mv);
+        let score = score_for(player, &next, depth - 1, alpha, beta);
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Noughts and crosses, with cells numbered from 0 in the top left.
+    #[derive(Clone)]
+    struct Noughts {
+        cells: [Option<usize>; 9],
+        turn: usize,
+    }
+
+    const LINES: [[usize; 3]; 8] = [
+        [0, 1, 2],
+        [3, 4, 5],
+        [6, 7, 8],
+        [0, 3, 6],
+        [1, 4, 7],
+        [2, 5, 8],
+        [0, 4, 8],
+        [2, 4, 6],
+    ];
+
+    impl Noughts {
+        fn from(cells: &str) -> Self {
+            let mut game = Noughts {
+                cells: [None; 9],
+                turn: 0,
+            };
+            for (index, c) in cells.chars().enumerate() {
+                game.cells[index] = match c {
+                    'x' => Some(0),
+                    'o' => Some(1),
+                    _ => None,
+                };
+            }
+            game.turn = game.cells.iter().filter(|cell| cell.is_some()).count() % 2;
+            game
+        }
+
+        fn winner(&self) -> Option<usize> {
+            LINES.iter().find_map(|line| {
+                let first = self.cells[line[0]]?;
+                if line.iter().all(|&cell| self.cells[cell] == Some(first)) {
+                    Some(first)
+                } else {
+                    None
+                }
+            })
+        }
+    }
+
+    impl Game for Noughts {
+        type Move = usize;
+
+        fn current_player(&self) -> usize {
+            self.turn
+        }
+
+        fn moves(&self) -> Vec<usize> {
+            if self.winner().is_some() {
+                return Vec::new();
+            }
+            (0..9).filter(|&cell| self.cells[cell].is_none()).collect()
+        }
+
+        fn play(&mut self, mv: usize) {
+            self.cells[mv] = Some(self.turn);
+            self.turn = 1 - self.turn;
+        }
+
+        fn reward(&self, player: usize) -> f32 {
+            match self.winner() {
+                Some(winner) if winner == player => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            }
+        }
+    }
+
+    impl Evaluate for Noughts {
+        fn evaluate(&self, _player: usize) -> f32 {
+            0.0
+        }
+
+        fn priority(&self, mv: usize) -> f32 {
+            // The centre, then the corners.
+            match mv {
+                4 => 2.0,
+                0 | 2 | 6 | 8 => 1.0,
+                _ => 0.0,
+            }
+        }
+    }
+
+    fn best(cells: &str) -> Option<usize> {
+        Minimax::new(9).search(&Noughts::from(cells), &mut StdRng::seed_from_u64(0))
+    }
+
+    #[test]
+    fn takes_a_win() {
+        // x to move, and the top row is one short.
+        assert_eq!(best("xx.oo...."), Some(2));
+    }
+
+    #[test]
+    fn blocks_a_loss() {
+        // x to move, and o is one short of the middle row.
+        assert_eq!(best("x..oo...x"), Some(5));
+    }
+
+    #[test]
+    fn prefers_winning_to_blocking() {
+        // o to move, and both have a row one short.
+        assert_eq!(best("xx.oo.x.."), Some(5));
+    }
+
+    #[test]
+    fn perfect_play_draws() {
+        let mut game = Noughts::from(".........");
+        let mut rng = StdRng::seed_from_u64(1);
+        while let Some(mv) = Minimax::new(9).search(&game, &mut rng) {
+            game.play(mv);
+        }
+        assert_eq!(game.winner(), None);
+    }
+
+    #[test]
+    fn finished_games_have_no_move() {
+        assert_eq!(best("xxxoo...."), None);
+    }
+
+    #[test]
+    fn difficulty_sets_depth_and_mistakes() {
+        assert_eq!(Minimax::with_difficulty(0.0).depth, 1);
+        assert_eq!(Minimax::with_difficulty(1.0).depth, 6);
+        assert_eq!(Minimax::with_difficulty(1.0).mistakes, 0.0);
+        assert!(Minimax::with_difficulty(0.0).mistakes > 0.0);
+    }
+}
@@ -0,0 +1,269 @@
+//! Finding the way across a grid, for anything that has to get from one cell to another around
+//! walls, like a monster after the player.
+//!
+//! A grid is anything that says what each cell costs to step into, or that it can't be. `bfs`
+//! ignores the costs and finds the path with the fewest steps, while `dijkstra` and `a_star`
+//! find the cheapest, `a_star` usually looking at far fewer cells to do it. `distances` gives
+//! the cost of getting from one cell to every cell it can reach, for when lots of things are
+//! heading for the same place.
+//!
+//! Cells off the edge of the grid have to be walls, or a search for somewhere that can't be
+//! reached never ends.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+/// A cell's column and row.
+pub type Cell = (i32, i32);
+
+/// Something that can be found a way across.
+pub trait Grid {
+    /// What it costs to step into `cell`, or `None` if it's a wall. Costs less than 1 count as 1.
+    fn cost(&self, cell: Cell) -> Option<u32>;
+}
+
+impl<F: Fn(Cell) -> Option<u32>> Grid for F {
+    fn cost(&self, cell: Cell) -> Option<u32> {
+        self(cell)
+    }
+}
+
+/// Which neighbouring cells a step can go to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Steps {
+    /// Up, down, left and right.
+    Orthogonal,
+    /// The diagonals as well, costing the same as the rest. A diagonal step can cut between two
+    /// walls that only touch at their corners.
+    Diagonal,
+}
+
+const ORTHOGONAL: [Cell; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const DIAGONAL: [Cell; 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+];
+
+impl Steps {
+    fn neighbours(self, (x, y): Cell) -> impl Iterator<Item = Cell> {
+        let offsets: &'static [Cell] = match self {
+            Steps::Orthogonal => &ORTHOGONAL,
+            Steps::Diagonal => &DIAGONAL,
+        };
+        offsets.iter().map(move |(dx, dy)| (x + dx, y + dy))
+    }
+
+    /// The fewest steps from `a` to `b` if there were no walls.
+    pub fn distance(self, a: Cell, b: Cell) -> u32 {
+        let (dx, dy) = ((a.0 - b.0).abs() as u32, (a.1 - b.1).abs() as u32);
+        match self {
+            Steps::Orthogonal => dx + dy,
+            Steps::Diagonal => dx.max(dy),
+        }
+    }
+}
+
+fn step_cost<G: Grid>(grid: &G, cell: Cell) -> Option<u32> {
+    grid.cost(cell).map(|cost| cost.max(1))
+}
+
+/// The cells from `start` to `goal` with the fewest steps, not counting `start`, or `None` if
+/// there's no way there.
+pub fn bfs<G: Grid>(grid: &G, steps: Steps, start: Cell, goal: Cell) -> Option<Vec<Cell>> {
+    let mut came_from = HashMap::new();
+    let mut queue = VecDeque::new();
+    came_from.insert(start, start);
+    queue.push_back(start);
+    while let Some(cell) = queue.pop_front() {
+        if cell == goal {
+            return Some(walk_back(&came_from, start, goal));
+        }
+        for next in steps.neighbours(cell) {
+            if !came_from.contains_key(&next) && grid.cost(next).is_some() {
+                came_from.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// The cheapest cells from `start` to `goal`, not counting `start`, or `None` if there's no way
+/// there.
+pub fn dijkstra<G: Grid>(grid: &G, steps: Steps, start: Cell, goal: Cell) -> Option<Vec<Cell>> {
+    cheapest(grid, steps, start, goal, |_| 0)
+}
+
+/// The cheapest cells from `start` to `goal`, not counting `start`, or `None` if there's no way
+/// there. It finds as cheap a path as `dijkstra`, heading for the goal first.
+pub fn a_star<G: Grid>(grid: &G, steps: Steps, start: Cell, goal: Cell) -> Option<Vec<Cell>> {
+    cheapest(grid, steps, start, goal, |cell| steps.distance(cell, goal))
+}
+
+/// What it costs to get from `start` to every cell that can be reached from it, including
+/// `start` itself at 0.
+pub fn distances<G: Grid>(grid: &G, steps: Steps, start: Cell) -> HashMap<Cell, u32> {
+    let mut best = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert(start, 0);
+    open.push(Reverse((0, start)));
+    while let Some(Reverse((cost, cell))) = open.pop() {
+        if cost > best[&cell] {
+            continue;
+        }
+        for next in steps.neighbours(cell) {
+            if let Some(step) = step_cost(grid, next) {
+                let total = cost + step;
+                if best.get(&next).map_or(true, |&known| total < known) {
+                    best.insert(next, total);
+                    open.push(Reverse((total, next)));
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Searches outwards from `start`, cheapest first once `estimate` of the cost still to go is
+/// added. The estimate can't be more than the real cost, or the path found may not be the
+/// cheapest.
+fn cheapest<G: Grid>(
+    grid: &G,
+    steps: Steps,
+    start: Cell,
+    goal: Cell,
+    estimate: impl Fn(Cell) -> u32,
+) -> Option<Vec<Cell>> {
+    let mut best = HashMap::new();
+    let mut came_from = HashMap::new();
+    let mut open = BinaryHeap::new();
+    best.insert(start, 0);
+    open.push(Reverse((estimate(start), 0, start)));
+    while let Some(Reverse((_, cost, cell))) = open.pop() {
+        if cell == goal {
+            return Some(walk_back(&came_from, start, goal));
+        }
+        // Cells can be queued more than once, and only the cheapest counts.
+        if cost > best[&cell] {
+            continue;
+        }
+        for next in steps.neighbours(cell) {
+            if let Some(step) = step_cost(grid, next) {
+                let total = cost + step;
+                if best.get(&next).map_or(true, |&known| total < known) {
+                    best.insert(next, total);
+                    came_from.insert(next, cell);
+                    open.push(Reverse((total + estimate(next), total, next)));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Follows the cells back from `goal` to `start`, giving them in order from the one after
+/// `start`.
+fn walk_back(came_from: &HashMap<Cell, Cell>, start: Cell, goal: Cell) -> Vec<Cell> {
+    let mut path = Vec::new();
+    let mut cell = goal;
+    while cell != start {
+        path.push(cell);
+        cell = came_from[&cell];
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A grid drawn as rows, where `#` is a wall, a digit costs that much and anything else
+    /// costs 1.
+    fn grid(rows: &'static [&'static str]) -> impl Fn(Cell) -> Option<u32> {
+        move |(x, y)| {
+            if x < 0 || y < 0 {
+                return None;
+            }
+            let c = rows.get(y as usize)?.chars().nth(x as usize)?;
+            match c {
+                '#' => None,
+                _ => Some(c.to_digit(10).unwrap_or(1)),
+            }
+        }
+    }
+
+    const MAZE: &[&str] = &["....#...", ".##.#.#.", ".#..#.#.", ".#.##.#.", "......#."];
+
+    #[test]
+    fn bfs_goes_around_walls() {
+        let path = bfs(&grid(MAZE), Steps::Orthogonal, (0, 0), (5, 0)).unwrap();
+        assert_eq!(path.len(), 13);
+        assert_eq!(path.last(), Some(&(5, 0)));
+        assert!(path.iter().all(|&cell| grid(MAZE)(cell).is_some()));
+    }
+
+    #[test]
+    fn paths_take_single_steps() {
+        let path = a_star(&grid(MAZE), Steps::Diagonal, (0, 0), (5, 0)).unwrap();
+        let mut cell = (0, 0);
+        for &next in &path {
+            assert_eq!(Steps::Diagonal.distance(cell, next), 1);
+            cell = next;
+        }
+    }
+
+    #[test]
+    fn unreachable_goals_have_no_path() {
+        let walled = &["..#..", "..#.."];
+        assert_eq!(bfs(&grid(walled), Steps::Diagonal, (0, 0), (4, 1)), None);
+        assert_eq!(a_star(&grid(walled), Steps::Diagonal, (0, 0), (4, 1)), None);
+        assert_eq!(
+            dijkstra(&grid(walled), Steps::Diagonal, (0, 0), (4, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn start_is_already_there() {
+        assert_eq!(
+            bfs(&grid(MAZE), Steps::Orthogonal, (2, 2), (2, 2)),
+            Some(vec![])
+        );
+        assert_eq!(
+            a_star(&grid(MAZE), Steps::Orthogonal, (2, 2), (2, 2)),
+            Some(vec![])
+        );
+    }
+
+    #[test]
+    fn cheapest_paths_avoid_costly_cells() {
+        let swamp = &["....", ".99.", "....", "...."];
+        let swamp_grid = grid(swamp);
+        let cost =
+            |path: &[Cell]| -> u32 { path.iter().map(|&cell| swamp_grid(cell).unwrap()).sum() };
+
+        let fewest = bfs(&grid(swamp), Steps::Orthogonal, (0, 1), (3, 1)).unwrap();
+        let cheapest = dijkstra(&grid(swamp), Steps::Orthogonal, (0, 1), (3, 1)).unwrap();
+        let guided = a_star(&grid(swamp), Steps::Orthogonal, (0, 1), (3, 1)).unwrap();
+        assert_eq!(cost(&fewest), 19);
+        assert_eq!(cost(&cheapest), 5);
+        assert_eq!(cost(&guided), 5);
+    }
+
+    #[test]
+    fn distances_match_the_cheapest_paths() {
+        let from = distances(&grid(MAZE), Steps::Orthogonal, (0, 0));
+        assert_eq!(from[&(0, 0)], 0);
+        assert_eq!(from[&(5, 0)], 13);
+        assert!(!from.contains_key(&(4, 0)));
+        // The column on the right is only reachable round the top of the wall beside it.
+        assert_eq!(from.get(&(7, 4)), Some(&19));
+    }
+}
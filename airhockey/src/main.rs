@@ -0,0 +1,421 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, input, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::last_played;
+use common::physics::{self, Vec2};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 800.0);
+const GOAL_WIDTH: f32 = 160.0;
+
+const PUCK_RADIUS: f32 = 16.0;
+const MALLET_RADIUS: f32 = 28.0;
+/// The fraction of the puck's speed kept per second, simulating the air cushion's friction.
+const PUCK_FRICTION: f32 = 0.6;
+const MAX_PUCK_SPEED: f32 = 1600.0;
+const WALL_RESTITUTION: f32 = 0.9;
+const MALLET_RESTITUTION: f32 = 0.8;
+const SUBSTEPS: u32 = 4;
+
+const WINNING_SCORE: u32 = 7;
+
+/// How the AI opponent plays at each difficulty.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// How many seconds behind the puck the AI sees it, so harder AIs react sooner.
+    fn reaction_delay(self) -> f32 {
+        match self {
+            Difficulty::Easy => 0.3,
+            Difficulty::Medium => 0.15,
+            Difficulty::Hard => 0.05,
+        }
+    }
+
+    fn max_speed(self) -> f32 {
+        match self {
+            Difficulty::Easy => 350.0,
+            Difficulty::Medium => 550.0,
+            Difficulty::Hard => 800.0,
+        }
+    }
+
+    /// The difficulty with the given name, as recorded for the launcher.
+    fn from_name(name: &str) -> Option<Self> {
+        [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard]
+            .iter()
+            .cloned()
+            .find(|difficulty| format!("{:?}", difficulty) == name)
+    }
+
+    /// Records the difficulty so the launcher starts the game with it next time.
+    fn remember(self) {
+        let _ = last_played::set_mode("airhockey", &format!("{:?}", self));
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Medium,
+            Difficulty::Medium => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+}
+
+/// A mallet, which only moves within its own half of the table.
+#[derive(Debug, Copy, Clone)]
+struct Mallet {
+    pos: Vec2,
+    velocity: Vec2,
+    /// The top and bottom of the area the mallet is confined to.
+    min_y: f32,
+    max_y: f32,
+}
+
+impl Mallet {
+    fn new(y: f32, min_y: f32, max_y: f32) -> Self {
+        Mallet {
+            pos: Vec2::new(SCREEN_SIZE.0 / 2.0, y),
+            velocity: Vec2::ZERO,
+            min_y,
+            max_y,
+        }
+    }
+
+    /// Moves the mallet towards `target`, no faster than `max_speed`, tracking its velocity so
+    /// hits transfer momentum to the puck.
+    fn move_towards(&mut self, target: Vec2, max_speed: f32, dt: f32) {
+        let target = Vec2::new(
+            target
+                .x
+                .max(MALLET_RADIUS)
+                .min(SCREEN_SIZE.0 - MALLET_RADIUS),
+            target
+                .y
+                .max(self.min_y + MALLET_RADIUS)
+                .min(self.max_y - MALLET_RADIUS),
+        );
+        let step = (target - self.pos).clamp_length(max_speed * dt);
+        self.velocity = step * (1.0 / dt);
+        self.pos += step;
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    puck: Vec2,
+    puck_velocity: Vec2,
+    player: Mallet,
+    ai: Mallet,
+    difficulty: Difficulty,
+    /// The puck positions of the last moments, oldest first, which the AI reacts to.
+    ai_view: VecDeque<(f32, Vec2, Vec2)>,
+    time: f32,
+    player_score: u32,
+    ai_score: u32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state.
+    pub fn new(difficulty: Difficulty) -> Self {
+        let mut state = GameState {
+            puck: Vec2::ZERO,
+            puck_velocity: Vec2::ZERO,
+            player: Mallet::new(SCREEN_SIZE.1 - 80.0, SCREEN_SIZE.1 / 2.0, SCREEN_SIZE.1),
+            ai: Mallet::new(80.0, 0.0, SCREEN_SIZE.1 / 2.0),
+            difficulty,
+            ai_view: VecDeque::new(),
+            time: 0.0,
+            player_score: 0,
+            ai_score: 0,
+            stats: StatsDb::open().ok(),
+        };
+        state.face_off(rand::thread_rng().gen());
+        state
+    }
+
+    /// Places the puck on the side of the player who conceded.
+    fn face_off(&mut self, player_side: bool) {
+        let y = if player_side {
+            SCREEN_SIZE.1 * 0.65
+        } else {
+            SCREEN_SIZE.1 * 0.35
+        };
+        self.puck = Vec2::new(SCREEN_SIZE.0 / 2.0, y);
+        self.puck_velocity = Vec2::ZERO;
+    }
+
+    fn is_over(&self) -> bool {
+        self.player_score >= WINNING_SCORE || self.ai_score >= WINNING_SCORE
+    }
+
+    fn record_match(&self) {
+        let values = [
+            (
+                "won",
+                if self.player_score >= WINNING_SCORE {
+                    1.0
+                } else {
+                    0.0
+                },
+            ),
+            ("goals", f64::from(self.player_score)),
+            ("conceded", f64::from(self.ai_score)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("airhockey", &values);
+        }
+        let _ = achievements::check_run("airhockey", &values);
+    }
+
+    /// The puck's position and velocity as the AI currently perceives it.
+    fn ai_perceived_puck(&self) -> (Vec2, Vec2) {
+        let seen = self.time - self.difficulty.reaction_delay();
+        self.ai_view
+            .iter()
+            .rev()
+            .find(|(time, _, _)| *time <= seen)
+            .or_else(|| self.ai_view.front())
+            .map(|(_, pos, velocity)| (*pos, *velocity))
+            .unwrap_or((self.puck, self.puck_velocity))
+    }
+
+    fn update_ai(&mut self, dt: f32) {
+        let (puck, velocity) = self.ai_perceived_puck();
+        let home = Vec2::new(SCREEN_SIZE.0 / 2.0, 60.0);
+
+        // Attack the puck when it's in our half, otherwise guard the goal in line with it.
+        let target = if puck.y < SCREEN_SIZE.1 / 2.0 {
+            // Aim to hit the puck from behind, towards the player's goal.
+            puck + Vec2::new(0.0, -PUCK_RADIUS) + velocity * 0.05
+        } else {
+            Vec2::new(home.x + (puck.x - home.x) * 0.5, home.y)
+        };
+
+        let max_speed = self.difficulty.max_speed();
+        self.ai.move_towards(target, max_speed, dt);
+    }
+
+    fn step(&mut self, dt: f32) {
+        let friction = PUCK_FRICTION.powf(dt);
+        self.puck_velocity = (self.puck_velocity * friction).clamp_length(MAX_PUCK_SPEED);
+        self.puck += self.puck_velocity * dt;
+
+        // Bounce off the side walls.
+        if self.puck.x < PUCK_RADIUS {
+            self.puck.x = PUCK_RADIUS;
+            self.puck_velocity.x = self.puck_velocity.x.abs() * WALL_RESTITUTION;
+        } else if self.puck.x > SCREEN_SIZE.0 - PUCK_RADIUS {
+            self.puck.x = SCREEN_SIZE.0 - PUCK_RADIUS;
+            self.puck_velocity.x = -self.puck_velocity.x.abs() * WALL_RESTITUTION;
+        }
+
+        // Bounce off the end walls, except where the goals are.
+        let in_goal_mouth = (self.puck.x - SCREEN_SIZE.0 / 2.0).abs() < GOAL_WIDTH / 2.0;
+        if !in_goal_mouth {
+            if self.puck.y < PUCK_RADIUS {
+                self.puck.y = PUCK_RADIUS;
+                self.puck_velocity.y = self.puck_velocity.y.abs() * WALL_RESTITUTION;
+            } else if self.puck.y > SCREEN_SIZE.1 - PUCK_RADIUS {
+                self.puck.y = SCREEN_SIZE.1 - PUCK_RADIUS;
+                self.puck_velocity.y = -self.puck_velocity.y.abs() * WALL_RESTITUTION;
+            }
+        }
+
+        for mallet in [self.player, self.ai].iter() {
+            if let Some(contact) =
+                physics::circle_circle(self.puck, PUCK_RADIUS, mallet.pos, MALLET_RADIUS)
+            {
+                self.puck += contact.normal * contact.depth;
+                self.puck_velocity = physics::bounce(
+                    self.puck_velocity,
+                    contact.normal,
+                    MALLET_RESTITUTION,
+                    mallet.velocity,
+                );
+            }
+        }
+
+        if self.puck.y < -PUCK_RADIUS {
+            self.player_score += 1;
+            self.face_off(false);
+        } else if self.puck.y > SCREEN_SIZE.1 + PUCK_RADIUS {
+            self.ai_score += 1;
+            self.face_off(true);
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.time += dt;
+        self.ai_view
+            .push_back((self.time, self.puck, self.puck_velocity));
+        while self
+            .ai_view
+            .front()
+            .map_or(false, |(time, _, _)| *time < self.time - 1.0)
+        {
+            self.ai_view.pop_front();
+        }
+
+        // The player's mallet follows the mouse, limited to their half of the table.
+        let mouse = input::mouse::position(ctx);
+        self.player
+            .move_towards(Vec2::new(mouse.x, mouse.y), MAX_PUCK_SPEED, dt);
+        self.update_ai(dt);
+
+        let step = dt / SUBSTEPS as f32;
+        for _ in 0..SUBSTEPS {
+            self.step(step);
+            if self.is_over() {
+                self.record_match();
+                return;
+            }
+        }
+    }
+
+    fn draw_circle(
+        &self,
+        ctx: &mut Context,
+        pos: Vec2,
+        radius: f32,
+        color: [f32; 4],
+    ) -> GameResult<()> {
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            ggez::mint::Point2 { x: pos.x, y: pos.y },
+            radius,
+            0.5,
+            color.into(),
+        )?;
+        graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.is_over() && dt > 0.0 {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.85, 0.9, 0.95, 1.0].into());
+
+        let center_line = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0.0, SCREEN_SIZE.1 / 2.0 - 2.0, SCREEN_SIZE.0, 4.0),
+            [0.8, 0.2, 0.2, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &center_line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for y in [0.0, SCREEN_SIZE.1 - 8.0].iter() {
+            let goal = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new((SCREEN_SIZE.0 - GOAL_WIDTH) / 2.0, *y, GOAL_WIDTH, 8.0),
+                [0.1, 0.1, 0.1, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &goal, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        self.draw_circle(ctx, self.puck, PUCK_RADIUS, [0.1, 0.1, 0.1, 1.0])?;
+        self.draw_circle(ctx, self.ai.pos, MALLET_RADIUS, [0.9, 0.2, 0.2, 1.0])?;
+        self.draw_circle(ctx, self.player.pos, MALLET_RADIUS, [0.2, 0.4, 0.9, 1.0])?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "AI ({:?}): {}\nYou: {}",
+                self.difficulty, self.ai_score, self.player_score
+            ))
+            .color([0.1, 0.1, 0.1, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(
+            ctx,
+            &hud,
+            (ggez::mint::Point2 {
+                x: 8.0,
+                y: SCREEN_SIZE.1 / 2.0 - 56.0,
+            },),
+        )?;
+
+        if self.is_over() {
+            let message = if self.player_score >= WINNING_SCORE {
+                "YOU WIN!"
+            } else {
+                "YOU LOSE!"
+            };
+            let text = Text::new(
+                TextFragment::new(format!("{}\nEnter: rematch  D: difficulty", message))
+                    .color([0.8, 0.1, 0.1, 1.0].into())
+                    .scale(Scale::uniform(32.0)),
+            );
+            graphics::draw(
+                ctx,
+                &text,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0 + 16.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.is_over() => *self = GameState::new(self.difficulty),
+            KeyCode::D => {
+                let difficulty = self.difficulty.next();
+                difficulty.remember();
+                *self = GameState::new(difficulty);
+            }
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("airhockey", "Sprial404")
+        .window_setup(window::setup("Air Hockey"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let difficulty = last_played::requested_mode()
+        .and_then(|mode| Difficulty::from_name(&mode))
+        .unwrap_or(Difficulty::Medium);
+    difficulty.remember();
+    let mut state = App::new(GameState::new(difficulty));
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,759 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+const GRAVITY: f32 = 200.0;
+/// The strongest wind, as a horizontal acceleration on the shells.
+const MAX_WIND: f32 = 60.0;
+
+const TANK_SIZE: (f32, f32) = (30.0, 12.0);
+const BARREL_LENGTH: f32 = 20.0;
+const TANK_HP: f32 = 100.0;
+const ANGLE_SPEED: f32 = 60.0;
+const POWER_SPEED: f32 = 120.0;
+const MIN_POWER: f32 = 100.0;
+const MAX_POWER: f32 = 600.0;
+
+/// How long an explosion stays on screen, in seconds.
+const EXPLOSION_TIME: f32 = 0.5;
+/// How long the AI pretends to think before firing, in seconds.
+const AI_DELAY: f32 = 1.0;
+/// The error the AI adds to its best shot, as a fraction of its power.
+const AI_ERROR: f32 = 0.06;
+
+/// The weapons a tank can fire.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Weapon {
+    Shell,
+    BigShot,
+    /// Fires three shells spread out in a fan.
+    Triple,
+    /// Piles up dirt rather than blasting it away.
+    DirtBomb,
+}
+
+const WEAPONS: [Weapon; 4] = [
+    Weapon::Shell,
+    Weapon::BigShot,
+    Weapon::Triple,
+    Weapon::DirtBomb,
+];
+
+impl Weapon {
+    fn name(self) -> &'static str {
+        match self {
+            Weapon::Shell => "Shell",
+            Weapon::BigShot => "Big Shot",
+            Weapon::Triple => "Triple",
+            Weapon::DirtBomb => "Dirt Bomb",
+        }
+    }
+
+    /// The ammo each tank starts with, or `None` if the weapon is unlimited.
+    fn ammo(self) -> Option<u32> {
+        match self {
+            Weapon::Shell => None,
+            Weapon::BigShot => Some(2),
+            Weapon::Triple => Some(3),
+            Weapon::DirtBomb => Some(2),
+        }
+    }
+
+    fn radius(self) -> f32 {
+        match self {
+            Weapon::Shell | Weapon::Triple => 25.0,
+            Weapon::BigShot => 50.0,
+            Weapon::DirtBomb => 40.0,
+        }
+    }
+
+    /// The damage dealt to a tank at the center of the explosion.
+    fn damage(self) -> f32 {
+        match self {
+            Weapon::Shell => 35.0,
+            Weapon::BigShot => 60.0,
+            Weapon::Triple => 20.0,
+            Weapon::DirtBomb => 0.0,
+        }
+    }
+}
+
+/// A destructible heightfield, storing the height of the ground for every column of pixels.
+struct Terrain {
+    ground: Vec<f32>,
+}
+
+impl Terrain {
+    /// Generates rolling hills from a few overlapping sine waves.
+    pub fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        let waves: Vec<(f32, f32, f32)> = (0..3)
+            .map(|i| {
+                (
+                    rng.gen_range(0.005, 0.01) * (i + 1) as f32,
+                    rng.gen_range(0.0, std::f32::consts::PI * 2.0),
+                    80.0 / (i + 1) as f32,
+                )
+            })
+            .collect();
+
+        let ground = (0..SCREEN_SIZE.0 as usize)
+            .map(|x| {
+                let offset: f32 = waves
+                    .iter()
+                    .map(|(freq, phase, amp)| (x as f32 * freq + phase).sin() * amp)
+                    .sum();
+                SCREEN_SIZE.1 * 0.65 + offset
+            })
+            .collect();
+
+        Terrain { ground }
+    }
+
+    /// The height of the ground at `x`, or `None` if `x` is off the side of the screen.
+    fn height_at(&self, x: f32) -> Option<f32> {
+        if x < 0.0 || x >= SCREEN_SIZE.0 {
+            return None;
+        }
+        Some(self.ground[x as usize])
+    }
+
+    /// Blasts a circle out of the ground, letting any dirt above the hole collapse into it.
+    fn carve(&mut self, center: Vec2, radius: f32) {
+        self.for_columns(center, radius, |ground, top, bottom| {
+            let removed = (bottom.min(SCREEN_SIZE.1) - top.max(*ground)).max(0.0);
+            *ground = (*ground + removed).min(SCREEN_SIZE.1);
+        });
+    }
+
+    /// Fills a circle with dirt, raising the ground up to its top.
+    fn pile(&mut self, center: Vec2, radius: f32) {
+        self.for_columns(center, radius, |ground, top, _| {
+            *ground = ground.min(top.max(0.0));
+        });
+    }
+
+    /// Calls `f` with the ground and the top and bottom of the circle for every column it covers.
+    fn for_columns<F>(&mut self, center: Vec2, radius: f32, mut f: F)
+    where
+        F: FnMut(&mut f32, f32, f32),
+    {
+        let left = (center.x - radius).max(0.0) as usize;
+        let right = ((center.x + radius) as usize).min(self.ground.len() - 1);
+        for x in left..=right {
+            let dx = x as f32 - center.x;
+            let dy = (radius * radius - dx * dx).max(0.0).sqrt();
+            f(&mut self.ground[x], center.y - dy, center.y + dy);
+        }
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut points: Vec<ggez::mint::Point2<f32>> = self
+            .ground
+            .iter()
+            .enumerate()
+            .step_by(4)
+            .map(|(x, &y)| ggez::mint::Point2 { x: x as f32, y })
+            .collect();
+        points.push(ggez::mint::Point2 {
+            x: SCREEN_SIZE.0,
+            y: self.ground[self.ground.len() - 1],
+        });
+        points.push(ggez::mint::Point2 {
+            x: SCREEN_SIZE.0,
+            y: SCREEN_SIZE.1,
+        });
+        points.push(ggez::mint::Point2 {
+            x: 0.0,
+            y: SCREEN_SIZE.1,
+        });
+
+        let dirt = graphics::Mesh::new_polygon(
+            ctx,
+            DrawMode::fill(),
+            &points,
+            [0.4, 0.3, 0.1, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &dirt, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+/// Who is controlling a tank.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Controller {
+    Human,
+    Ai,
+}
+
+/// A player's tank.
+#[derive(Debug, Clone)]
+struct Tank {
+    name: &'static str,
+    color: [f32; 4],
+    controller: Controller,
+    x: f32,
+    y: f32,
+    hp: f32,
+    /// The angle of the barrel in degrees, with 0 pointing right and 180 pointing left.
+    angle: f32,
+    power: f32,
+    weapon: usize,
+    ammo: Vec<Option<u32>>,
+}
+
+impl Tank {
+    /// Creates a new tank at `x` with its barrel angled towards the middle of the screen.
+    pub fn new(name: &'static str, color: [f32; 4], controller: Controller, x: f32) -> Self {
+        Tank {
+            name,
+            color,
+            controller,
+            x,
+            y: 0.0,
+            hp: TANK_HP,
+            angle: if x < SCREEN_SIZE.0 / 2.0 { 45.0 } else { 135.0 },
+            power: 350.0,
+            weapon: 0,
+            ammo: WEAPONS.iter().map(|weapon| weapon.ammo()).collect(),
+        }
+    }
+
+    fn center(&self) -> Vec2 {
+        Vec2::new(self.x, self.y - TANK_SIZE.1 / 2.0)
+    }
+
+    fn muzzle(&self) -> Vec2 {
+        let radians = self.angle.to_radians();
+        self.center() + Vec2::new(radians.cos(), -radians.sin()) * BARREL_LENGTH
+    }
+
+    fn weapon(&self) -> Weapon {
+        WEAPONS[self.weapon]
+    }
+
+    /// Switches to the next weapon that still has ammo.
+    fn cycle_weapon(&mut self) {
+        loop {
+            self.weapon = (self.weapon + 1) % WEAPONS.len();
+            if self.ammo[self.weapon] != Some(0) {
+                break;
+            }
+        }
+    }
+
+    /// Fires the current weapon, using up its ammo and returning the shells it launched.
+    fn fire(&mut self) -> Vec<Shell> {
+        let weapon = self.weapon();
+        if let Some(ammo) = self.ammo[self.weapon].as_mut() {
+            *ammo -= 1;
+        }
+
+        let angles: Vec<f32> = match weapon {
+            Weapon::Triple => vec![self.angle - 6.0, self.angle, self.angle + 6.0],
+            _ => vec![self.angle],
+        };
+        let shells = angles
+            .into_iter()
+            .map(|angle| {
+                let radians = angle.to_radians();
+                Shell {
+                    pos: self.muzzle(),
+                    velocity: Vec2::new(radians.cos(), -radians.sin()) * self.power,
+                    weapon,
+                }
+            })
+            .collect();
+
+        if self.ammo[self.weapon] == Some(0) {
+            self.cycle_weapon();
+        }
+        shells
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let body = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                self.x - TANK_SIZE.0 / 2.0,
+                self.y - TANK_SIZE.1,
+                TANK_SIZE.0,
+                TANK_SIZE.1,
+            ),
+            self.color.into(),
+        )?;
+        graphics::draw(ctx, &body, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let center = self.center();
+        let muzzle = self.muzzle();
+        let barrel = graphics::Mesh::new_line(
+            ctx,
+            &[
+                ggez::mint::Point2 {
+                    x: center.x,
+                    y: center.y,
+                },
+                ggez::mint::Point2 {
+                    x: muzzle.x,
+                    y: muzzle.y,
+                },
+            ],
+            3.0,
+            self.color.into(),
+        )?;
+        graphics::draw(ctx, &barrel, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+/// A shell in flight.
+#[derive(Debug, Copy, Clone)]
+struct Shell {
+    pos: Vec2,
+    velocity: Vec2,
+    weapon: Weapon,
+}
+
+/// What a shell ran into.
+#[derive(Debug, Copy, Clone)]
+enum Impact {
+    /// The shell exploded at the given position.
+    Explode(Vec2),
+    /// The shell left the side of the screen.
+    Lost,
+}
+
+impl Shell {
+    /// Moves the shell on by `dt`, returning what it hit, if anything.
+    fn step(&mut self, dt: f32, wind: f32, terrain: &Terrain, tanks: &[Tank]) -> Option<Impact> {
+        self.velocity += Vec2::new(wind, GRAVITY) * dt;
+        self.pos += self.velocity * dt;
+
+        match terrain.height_at(self.pos.x) {
+            None => Some(Impact::Lost),
+            Some(ground) if self.pos.y >= ground => Some(Impact::Explode(self.pos)),
+            Some(_) => {
+                let hit_tank = tanks
+                    .iter()
+                    .any(|tank| (tank.center() - self.pos).length() < TANK_SIZE.0 / 2.0);
+                if hit_tank {
+                    Some(Impact::Explode(self.pos))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+/// Simulates a shot without wind error, returning where it lands.
+fn simulate(mut shell: Shell, wind: f32, terrain: &Terrain, tanks: &[Tank]) -> Option<Vec2> {
+    for _ in 0..2000 {
+        match shell.step(1.0 / 60.0, wind, terrain, tanks) {
+            Some(Impact::Explode(pos)) => return Some(pos),
+            Some(Impact::Lost) => return None,
+            None => (),
+        }
+    }
+    None
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Phase {
+    /// Choosing between hot-seat and playing against the AI.
+    Menu,
+    Aiming,
+    /// Waiting for the shells of the last shot to land.
+    Flying,
+    GameOver,
+}
+
+/// The state for the game.
+struct GameState {
+    phase: Phase,
+    terrain: Terrain,
+    tanks: Vec<Tank>,
+    turn: usize,
+    wind: f32,
+    shells: Vec<Shell>,
+    /// The position, radius and seconds left of every explosion on screen.
+    explosions: Vec<(Vec2, f32, f32)>,
+    ai_timer: f32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state, starting at the menu.
+    pub fn new() -> Self {
+        GameState {
+            phase: Phase::Menu,
+            terrain: Terrain::generate(),
+            tanks: Vec::new(),
+            turn: 0,
+            wind: 0.0,
+            shells: Vec::new(),
+            explosions: Vec::new(),
+            ai_timer: 0.0,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    /// Starts a match with the second tank controlled by `opponent`.
+    fn start(&mut self, opponent: Controller) {
+        let mut rng = rand::thread_rng();
+        self.terrain = Terrain::generate();
+        self.tanks = vec![
+            Tank::new(
+                "Player 1",
+                [0.2, 0.6, 1.0, 1.0],
+                Controller::Human,
+                rng.gen_range(60.0, 240.0),
+            ),
+            Tank::new(
+                if opponent == Controller::Ai {
+                    "Computer"
+                } else {
+                    "Player 2"
+                },
+                [1.0, 0.3, 0.2, 1.0],
+                opponent,
+                rng.gen_range(SCREEN_SIZE.0 - 240.0, SCREEN_SIZE.0 - 60.0),
+            ),
+        ];
+        self.settle_tanks();
+        self.turn = 0;
+        self.new_turn();
+    }
+
+    fn new_turn(&mut self) {
+        self.wind = rand::thread_rng().gen_range(-MAX_WIND, MAX_WIND);
+        self.ai_timer = AI_DELAY;
+        self.phase = Phase::Aiming;
+    }
+
+    /// Drops every tank onto the ground below it.
+    fn settle_tanks(&mut self) {
+        for tank in self.tanks.iter_mut() {
+            tank.y = self.terrain.height_at(tank.x).unwrap_or(SCREEN_SIZE.1);
+        }
+    }
+
+    fn fire(&mut self) {
+        self.shells = self.tanks[self.turn].fire();
+        self.phase = Phase::Flying;
+    }
+
+    /// Picks the shot landing closest to the opponent, then spoils it a little.
+    fn aim_ai(&mut self) {
+        let target = self.tanks[1 - self.turn].center();
+        let tank = &self.tanks[self.turn];
+        let facing_left = target.x < tank.x;
+
+        let mut best = (tank.angle, tank.power, std::f32::MAX);
+        for angle in (20..=80).step_by(5) {
+            let angle = if facing_left {
+                180.0 - angle as f32
+            } else {
+                angle as f32
+            };
+            for power in (MIN_POWER as u32..=MAX_POWER as u32).step_by(10) {
+                let mut aimed = tank.clone();
+                aimed.angle = angle;
+                aimed.power = power as f32;
+                let shell = Shell {
+                    pos: aimed.muzzle(),
+                    velocity: Vec2::new(angle.to_radians().cos(), -angle.to_radians().sin())
+                        * power as f32,
+                    weapon: Weapon::Shell,
+                };
+                if let Some(landing) = simulate(shell, self.wind, &self.terrain, &self.tanks) {
+                    let distance = (landing - target).length();
+                    if distance < best.2 {
+                        best = (angle, power as f32, distance);
+                    }
+                }
+            }
+        }
+
+        let mut rng = rand::thread_rng();
+        let tank = &mut self.tanks[self.turn];
+        tank.angle = best.0;
+        tank.power = (best.1 * (1.0 + rng.gen_range(-AI_ERROR, AI_ERROR)))
+            .max(MIN_POWER)
+            .min(MAX_POWER);
+
+        // Save the big guns for when the AI is close to its target.
+        tank.weapon = 0;
+        if best.2 < 40.0 && tank.ammo[1] != Some(0) {
+            tank.weapon = 1;
+        }
+    }
+
+    fn explode(&mut self, pos: Vec2, weapon: Weapon) {
+        let radius = weapon.radius();
+        if weapon == Weapon::DirtBomb {
+            self.terrain.pile(pos, radius);
+        } else {
+            self.terrain.carve(pos, radius);
+        }
+
+        for tank in self.tanks.iter_mut() {
+            let distance = (tank.center() - pos).length();
+            if distance < radius {
+                tank.hp -= weapon.damage() * (1.0 - distance / radius);
+            }
+        }
+        self.explosions.push((pos, radius, EXPLOSION_TIME));
+    }
+
+    /// Records the match as player one's run, which is won if only their tank is left.
+    fn record_match(&self) {
+        let won = self.tanks[0].hp > 0.0 && self.tanks[1].hp <= 0.0;
+        let computer = self.tanks[1].controller == Controller::Ai;
+        let values = [
+            ("won", if won { 1.0 } else { 0.0 }),
+            ("hp", f64::from(self.tanks[0].hp.max(0.0))),
+            ("computer", if computer { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("artillery", &values);
+        }
+        let _ = achievements::check_run("artillery", &values);
+    }
+
+    fn update_shells(&mut self, dt: f32) {
+        let mut impacts = Vec::new();
+        for mut shell in std::mem::replace(&mut self.shells, Vec::new()) {
+            match shell.step(dt, self.wind, &self.terrain, &self.tanks) {
+                Some(Impact::Explode(pos)) => impacts.push((pos, shell.weapon)),
+                Some(Impact::Lost) => (),
+                None => self.shells.push(shell),
+            }
+        }
+
+        for (pos, weapon) in impacts {
+            self.explode(pos, weapon);
+        }
+        self.settle_tanks();
+
+        if self.shells.is_empty() {
+            if self.tanks.iter().any(|tank| tank.hp <= 0.0) {
+                self.phase = Phase::GameOver;
+                self.record_match();
+            } else {
+                self.turn = 1 - self.turn;
+                self.new_turn();
+            }
+        }
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        for explosion in self.explosions.iter_mut() {
+            explosion.2 -= dt;
+        }
+        self.explosions.retain(|explosion| explosion.2 > 0.0);
+
+        match self.phase {
+            Phase::Aiming if self.tanks[self.turn].controller == Controller::Ai => {
+                self.ai_timer -= dt;
+                if self.ai_timer <= 0.0 {
+                    self.aim_ai();
+                    self.fire();
+                }
+            }
+            Phase::Aiming => {
+                let tank = &mut self.tanks[self.turn];
+                if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+                    tank.angle = (tank.angle + ANGLE_SPEED * dt).min(180.0);
+                }
+                if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+                    tank.angle = (tank.angle - ANGLE_SPEED * dt).max(0.0);
+                }
+                if keyboard::is_key_pressed(ctx, KeyCode::Up) {
+                    tank.power = (tank.power + POWER_SPEED * dt).min(MAX_POWER);
+                }
+                if keyboard::is_key_pressed(ctx, KeyCode::Down) {
+                    tank.power = (tank.power - POWER_SPEED * dt).max(MIN_POWER);
+                }
+            }
+            Phase::Flying => self.update_shells(dt),
+            Phase::Menu | Phase::GameOver => (),
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.3, 0.5, 0.8, 1.0].into());
+
+        if self.phase == Phase::Menu {
+            self.draw_text(ctx, "ARTILLERY", 60.0, 260.0, 180.0)?;
+            self.draw_text(
+                ctx,
+                "Press 1 for hot-seat, 2 to play the computer",
+                28.0,
+                140.0,
+                280.0,
+            )?;
+            self.draw_text(
+                ctx,
+                "Left/Right: angle  Up/Down: power  Tab: weapon  Space: fire",
+                20.0,
+                140.0,
+                330.0,
+            )?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        self.terrain.draw(ctx)?;
+        for tank in self.tanks.iter().filter(|tank| tank.hp > 0.0) {
+            tank.draw(ctx)?;
+        }
+
+        for shell in self.shells.iter() {
+            let circle = graphics::Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: shell.pos.x,
+                    y: shell.pos.y,
+                },
+                3.0,
+                0.5,
+                [0.1, 0.1, 0.1, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        for (pos, radius, left) in self.explosions.iter() {
+            let circle = graphics::Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                ggez::mint::Point2 { x: pos.x, y: pos.y },
+                radius * (1.0 - left / EXPLOSION_TIME * 0.5),
+                0.5,
+                [1.0, 0.6, 0.1, left / EXPLOSION_TIME].into(),
+            )?;
+            graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        for (i, tank) in self.tanks.iter().enumerate() {
+            let x = if i == 0 { 8.0 } else { SCREEN_SIZE.0 - 260.0 };
+            self.draw_text(
+                ctx,
+                &format!("{}: {:.0} HP", tank.name, tank.hp.max(0.0)),
+                22.0,
+                x,
+                8.0,
+            )?;
+        }
+
+        let tank = &self.tanks[self.turn];
+        let ammo = match tank.ammo[tank.weapon] {
+            Some(ammo) => format!(" ({})", ammo),
+            None => String::new(),
+        };
+        let wind = if self.wind < 0.0 { "<" } else { ">" };
+        self.draw_text(
+            ctx,
+            &format!(
+                "{}'s turn  Angle: {:.0}  Power: {:.0}  Weapon: {}{}  Wind: {} {:.0}",
+                tank.name,
+                tank.angle,
+                tank.power,
+                tank.weapon().name(),
+                ammo,
+                wind,
+                self.wind.abs()
+            ),
+            20.0,
+            8.0,
+            36.0,
+        )?;
+
+        if self.phase == Phase::GameOver {
+            let winner = self
+                .tanks
+                .iter()
+                .find(|tank| tank.hp > 0.0)
+                .map(|tank| format!("{} wins!", tank.name))
+                .unwrap_or_else(|| "Draw!".to_string());
+            self.draw_text(
+                ctx,
+                &format!("{} Press Enter to play again.", winner),
+                36.0,
+                120.0,
+                200.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        let human_turn =
+            self.phase == Phase::Aiming && self.tanks[self.turn].controller == Controller::Human;
+
+        match keycode {
+            KeyCode::Key1 if self.phase == Phase::Menu => self.start(Controller::Human),
+            KeyCode::Key2 if self.phase == Phase::Menu => self.start(Controller::Ai),
+            KeyCode::Tab if human_turn => self.tanks[self.turn].cycle_weapon(),
+            KeyCode::Space if human_turn && !repeat => self.fire(),
+            KeyCode::Return if self.phase == Phase::GameOver => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("artillery", "Sprial404")
+        .window_setup(window::setup("Artillery"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
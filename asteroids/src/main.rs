@@ -0,0 +1,662 @@
+use ggez::event::{Axis, Button, KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use common::achievements;
+use common::app::App;
+use common::input::Controllers;
+use common::locale;
+use common::particles::ParticleSystem;
+use common::physics::{self, Vec2};
+use common::spectator;
+use common::stats::StatsDb;
+use common::title;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+use std::f32::consts::PI;
+use std::process::ExitCode;
+
+const SCREEN_SIZE: (f32, f32) = (900.0, 700.0);
+
+const SHIP_RADIUS: f32 = 12.0;
+const TURN_SPEED: f32 = 4.5;
+const THRUST: f32 = 320.0;
+const MAX_SHIP_SPEED: f32 = 420.0;
+/// The fraction of its speed a ship keeps each second while coasting.
+const SHIP_DRAG: f32 = 0.6;
+/// Seconds a ship waits off screen before coming back.
+const RESPAWN_TIME: f32 = 2.0;
+/// Seconds a ship can't be hurt for after it comes back.
+const INVULNERABLE_TIME: f32 = 3.0;
+
+const BULLET_SPEED: f32 = 560.0;
+const BULLET_LIFE: f32 = 1.0;
+/// Seconds between shots while fire is held.
+const FIRE_DELAY: f32 = 0.2;
+
+/// The radius of an asteroid of each size, smallest first.
+const ASTEROID_RADII: [f32; 3] = [12.0, 24.0, 44.0];
+/// The points for shooting an asteroid of each size, smallest first.
+const ASTEROID_POINTS: [u32; 3] = [100, 50, 20];
+const ASTEROID_SPEED: (f32, f32) = (40.0, 110.0);
+/// How close to a ship a new asteroid is allowed to appear.
+const SAFE_DISTANCE: f32 = 200.0;
+
+/// The lives shared between every ship, and the combined score that earns another.
+const SOLO_LIVES: u32 = 3;
+const COOP_LIVES: u32 = 5;
+const EXTRA_LIFE_SCORE: u32 = 10000;
+
+const SHIP_COLORS: [[f32; 4]; 2] = [[0.4, 0.75, 1.0, 1.0], [1.0, 0.6, 0.3, 1.0]];
+
+fn screen() -> Vec2 {
+    Vec2::new(SCREEN_SIZE.0, SCREEN_SIZE.1)
+}
+
+/// A player's ship.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Ship {
+    pos: Vec2,
+    vel: Vec2,
+    angle: f32,
+    thrusting: bool,
+    alive: bool,
+    /// Seconds until a dead ship comes back, once a life has been spent on it.
+    respawn: Option<f32>,
+    invulnerable: f32,
+    cooldown: f32,
+}
+
+impl Ship {
+    fn new(player: usize) -> Self {
+        Ship {
+            pos: Ship::start(player),
+            vel: Vec2::ZERO,
+            angle: -PI / 2.0,
+            thrusting: false,
+            alive: true,
+            respawn: None,
+            invulnerable: INVULNERABLE_TIME,
+            cooldown: 0.0,
+        }
+    }
+
+    /// Where a player's ship starts and comes back.
+    fn start(player: usize) -> Vec2 {
+        let offset = if player == 0 { -60.0 } else { 60.0 };
+        Vec2::new(SCREEN_SIZE.0 / 2.0 + offset, SCREEN_SIZE.1 / 2.0)
+    }
+
+    /// The corners of the ship's hull.
+    fn hull(&self) -> [Vec2; 3] {
+        let forward = Vec2::from_angle(self.angle);
+        let side = forward.perp();
+        [
+            self.pos + forward * (SHIP_RADIUS * 1.4),
+            self.pos - forward * SHIP_RADIUS + side * SHIP_RADIUS,
+            self.pos - forward * SHIP_RADIUS - side * SHIP_RADIUS,
+        ]
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Bullet {
+    pos: Vec2,
+    vel: Vec2,
+    life: f32,
+    /// The player who fired it.
+    owner: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Asteroid {
+    pos: Vec2,
+    vel: Vec2,
+    /// An index into `ASTEROID_RADII`.
+    size: usize,
+    angle: f32,
+    spin: f32,
+    /// How far out each corner of the outline is, as a fraction of the radius.
+    shape: Vec<f32>,
+}
+
+impl Asteroid {
+    fn new(pos: Vec2, size: usize) -> Self {
+        let mut rng = rand::thread_rng();
+        Asteroid {
+            pos,
+            vel: Vec2::from_angle(rng.gen_range(0.0, PI * 2.0))
+                * rng.gen_range(ASTEROID_SPEED.0, ASTEROID_SPEED.1),
+            size,
+            angle: 0.0,
+            spin: rng.gen_range(-1.5, 1.5),
+            shape: (0..11).map(|_| rng.gen_range(0.75, 1.15)).collect(),
+        }
+    }
+
+    fn radius(&self) -> f32 {
+        ASTEROID_RADII[self.size]
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    input: Controllers,
+    /// Whether the players are still picking the settings.
+    menu: bool,
+    players: usize,
+    friendly_fire: bool,
+    ships: Vec<Ship>,
+    bullets: Vec<Bullet>,
+    asteroids: Vec<Asteroid>,
+    particles: ParticleSystem,
+    /// The pool of lives every ship draws from.
+    lives: u32,
+    /// Each player's share of the combined score.
+    scores: [u32; 2],
+    next_extra_life: u32,
+    wave: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the settings menu.
+    pub fn new() -> Self {
+        GameState {
+            input: Controllers::new(),
+            menu: true,
+            players: 2,
+            friendly_fire: false,
+            ships: Vec::new(),
+            bullets: Vec::new(),
+            asteroids: Vec::new(),
+            particles: ParticleSystem::new(Vec2::ZERO),
+            lives: 0,
+            scores: [0; 2],
+            next_extra_life: EXTRA_LIFE_SCORE,
+            wave: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn restart(&mut self) {
+        self.ships = (0..self.players).map(Ship::new).collect();
+        self.bullets.clear();
+        self.asteroids.clear();
+        self.particles.clear();
+        self.lives = if self.players > 1 {
+            COOP_LIVES
+        } else {
+            SOLO_LIVES
+        };
+        self.scores = [0; 2];
+        self.next_extra_life = EXTRA_LIFE_SCORE;
+        self.wave = 0;
+        self.menu = false;
+        self.gameover = false;
+        self.next_wave();
+    }
+
+    fn score(&self) -> u32 {
+        self.scores.iter().sum()
+    }
+
+    /// Fills the field with large asteroids, keeping them clear of the ships.
+    fn next_wave(&mut self) {
+        self.wave += 1;
+        let mut rng = rand::thread_rng();
+        let count = 3 + self.wave as usize;
+        while self.asteroids.len() < count {
+            let pos = Vec2::new(
+                rng.gen_range(0.0, SCREEN_SIZE.0),
+                rng.gen_range(0.0, SCREEN_SIZE.1),
+            );
+            let clear = self.ships.iter().enumerate().all(|(player, ship)| {
+                (pos - ship.pos).length() > SAFE_DISTANCE
+                    && (pos - Ship::start(player)).length() > SAFE_DISTANCE
+            });
+            if clear {
+                self.asteroids
+                    .push(Asteroid::new(pos, ASTEROID_RADII.len() - 1));
+            }
+        }
+    }
+
+    fn move_ships(&mut self, ctx: &Context, dt: f32) {
+        for (player, ship) in self.ships.iter_mut().enumerate() {
+            if !ship.alive {
+                if let Some(respawn) = ship.respawn {
+                    let respawn = respawn - dt;
+                    ship.respawn = Some(respawn);
+                    if respawn <= 0.0 {
+                        *ship = Ship::new(player);
+                    }
+                } else if self.lives > 0 {
+                    // Spend a life from the shared pool to bring the ship back.
+                    self.lives -= 1;
+                    ship.respawn = Some(RESPAWN_TIME);
+                }
+                continue;
+            }
+
+            let (turn, thrust) = self.input.direction(ctx, player);
+            ship.angle += turn * TURN_SPEED * dt;
+            ship.thrusting = thrust < -0.5;
+            if ship.thrusting {
+                ship.vel += Vec2::from_angle(ship.angle) * (THRUST * dt);
+            } else {
+                ship.vel = ship.vel * SHIP_DRAG.powf(dt);
+            }
+            ship.vel = ship.vel.clamp_length(MAX_SHIP_SPEED);
+            ship.pos = physics::wrap(ship.pos + ship.vel * dt, screen());
+            ship.invulnerable = (ship.invulnerable - dt).max(0.0);
+
+            ship.cooldown -= dt;
+            if ship.cooldown <= 0.0 && self.input.action(ctx, player) {
+                ship.cooldown = FIRE_DELAY;
+                let forward = Vec2::from_angle(ship.angle);
+                self.bullets.push(Bullet {
+                    pos: ship.pos + forward * (SHIP_RADIUS * 1.4),
+                    vel: ship.vel + forward * BULLET_SPEED,
+                    life: BULLET_LIFE,
+                    owner: player,
+                });
+            }
+        }
+    }
+
+    /// Shatters an asteroid into two smaller ones, or into dust if it is already the smallest.
+    fn break_asteroid(&mut self, index: usize, player: usize) {
+        let asteroid = self.asteroids.swap_remove(index);
+        self.scores[player] += ASTEROID_POINTS[asteroid.size];
+        self.particles.burst(
+            asteroid.pos,
+            6 + asteroid.size * 6,
+            160.0,
+            0.6,
+            [0.8, 0.8, 0.75, 1.0],
+            2.0,
+        );
+        if asteroid.size > 0 {
+            for _ in 0..2 {
+                self.asteroids
+                    .push(Asteroid::new(asteroid.pos, asteroid.size - 1));
+            }
+        }
+    }
+
+    fn destroy_ship(&mut self, player: usize) {
+        let ship = &mut self.ships[player];
+        ship.alive = false;
+        ship.respawn = None;
+        let pos = ship.pos;
+        self.particles
+            .burst(pos, 30, 220.0, 1.0, SHIP_COLORS[player], 3.0);
+    }
+
+    fn collide(&mut self) {
+        // Bullets against asteroids, and against the other ship with friendly fire on.
+        let mut i = 0;
+        while i < self.bullets.len() {
+            let bullet = self.bullets[i];
+            let rock = self
+                .asteroids
+                .iter()
+                .position(|rock| (bullet.pos - rock.pos).length() < rock.radius());
+            let ship = if self.friendly_fire {
+                (0..self.ships.len()).find(|&player| {
+                    let ship = &self.ships[player];
+                    player != bullet.owner
+                        && ship.alive
+                        && ship.invulnerable <= 0.0
+                        && (bullet.pos - ship.pos).length() < SHIP_RADIUS
+                })
+            } else {
+                None
+            };
+
+            if let Some(rock) = rock {
+                self.break_asteroid(rock, bullet.owner);
+            } else if let Some(ship) = ship {
+                self.destroy_ship(ship);
+            } else {
+                i += 1;
+                continue;
+            }
+            self.bullets.swap_remove(i);
+        }
+
+        for player in 0..self.ships.len() {
+            let ship = self.ships[player];
+            if !ship.alive || ship.invulnerable > 0.0 {
+                continue;
+            }
+            let hit = self.asteroids.iter().position(|rock| {
+                physics::circle_circle(ship.pos, SHIP_RADIUS, rock.pos, rock.radius()).is_some()
+            });
+            if let Some(rock) = hit {
+                self.break_asteroid(rock, player);
+                self.destroy_ship(player);
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.move_ships(ctx, dt);
+
+        for bullet in self.bullets.iter_mut() {
+            bullet.pos = physics::wrap(bullet.pos + bullet.vel * dt, screen());
+            bullet.life -= dt;
+        }
+        self.bullets.retain(|bullet| bullet.life > 0.0);
+
+        for rock in self.asteroids.iter_mut() {
+            rock.pos = physics::wrap(rock.pos + rock.vel * dt, screen());
+            rock.angle += rock.spin * dt;
+        }
+        self.particles.update(dt);
+
+        self.collide();
+
+        while self.score() >= self.next_extra_life {
+            self.lives += 1;
+            self.next_extra_life += EXTRA_LIFE_SCORE;
+        }
+
+        if self.asteroids.is_empty() {
+            self.next_wave();
+        }
+
+        let anyone_left = self
+            .ships
+            .iter()
+            .any(|ship| ship.alive || ship.respawn.is_some());
+        if !anyone_left && self.lives == 0 {
+            self.end_game();
+        }
+    }
+
+    fn end_game(&mut self) {
+        self.gameover = true;
+        let values = [
+            ("score", self.score() as f64),
+            ("wave", self.wave as f64),
+            ("players", self.players as f64),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("asteroids", &values);
+        }
+        let _ = achievements::check_run("asteroids", &values);
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+
+        for rock in self.asteroids.iter() {
+            let corners = rock.shape.len();
+            let points: Vec<ggez::mint::Point2<f32>> = rock
+                .shape
+                .iter()
+                .enumerate()
+                .map(|(i, scale)| {
+                    let angle = rock.angle + i as f32 / corners as f32 * PI * 2.0;
+                    let point = rock.pos + Vec2::from_angle(angle) * (rock.radius() * scale);
+                    ggez::mint::Point2 {
+                        x: point.x,
+                        y: point.y,
+                    }
+                })
+                .collect();
+            builder.polygon(DrawMode::stroke(2.0), &points, [0.8, 0.8, 0.75, 1.0].into())?;
+        }
+
+        for (player, ship) in self.ships.iter().enumerate() {
+            // Ships blink while they can't be hurt.
+            let blink = ship.invulnerable > 0.0 && (ship.invulnerable * 8.0) as u32 % 2 == 0;
+            if !ship.alive || blink {
+                continue;
+            }
+            let hull: Vec<ggez::mint::Point2<f32>> = ship
+                .hull()
+                .iter()
+                .map(|point| ggez::mint::Point2 {
+                    x: point.x,
+                    y: point.y,
+                })
+                .collect();
+            builder.polygon(DrawMode::stroke(2.0), &hull, SHIP_COLORS[player].into())?;
+            if ship.thrusting {
+                let back = ship.pos - Vec2::from_angle(ship.angle) * (SHIP_RADIUS * 1.8);
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 {
+                        x: back.x,
+                        y: back.y,
+                    },
+                    4.0,
+                    0.5,
+                    [1.0, 0.8, 0.3, 1.0].into(),
+                );
+            }
+        }
+
+        for bullet in self.bullets.iter() {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: bullet.pos.x,
+                    y: bullet.pos.y,
+                },
+                2.5,
+                0.5,
+                SHIP_COLORS[bullet.owner].into(),
+            );
+        }
+
+        for particle in self.particles.particles() {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: particle.pos.x,
+                    y: particle.pos.y,
+                },
+                particle.size,
+                0.5,
+                particle.faded_color().into(),
+            );
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_menu(&self, ctx: &mut Context) -> GameResult<()> {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        self.draw_text(ctx, "ASTEROIDS", white, 56.0, 300.0, 140.0)?;
+        let mode = if self.players > 1 { "Co-op" } else { "Solo" };
+        self.draw_text(
+            ctx,
+            &format!("Mode: {}  (1 / 2)", mode),
+            white,
+            28.0,
+            260.0,
+            240.0,
+        )?;
+        let friendly_fire = if self.friendly_fire { "on" } else { "off" };
+        self.draw_text(
+            ctx,
+            &format!("Friendly fire: {}  (F)", friendly_fire),
+            white,
+            28.0,
+            260.0,
+            280.0,
+        )?;
+        for player in 0..self.players {
+            self.draw_text(
+                ctx,
+                &format!("P{}: {}", player + 1, self.input.describe(player)),
+                SHIP_COLORS[player],
+                22.0,
+                260.0,
+                350.0 + player as f32 * 30.0,
+            )?;
+        }
+        self.draw_text(
+            ctx,
+            "Turn left/right, thrust with up, fire with the action button.",
+            [0.7, 0.7, 0.7, 1.0],
+            20.0,
+            180.0,
+            430.0,
+        )?;
+        self.draw_text(
+            ctx,
+            "Press Enter or Start to play.",
+            [1.0, 0.8, 0.3, 1.0],
+            28.0,
+            260.0,
+            490.0,
+        )
+    }
+
+    fn draw_hud(&self, ctx: &mut Context) -> GameResult<()> {
+        let hud = format!(
+            "Score: {}  Lives: {}  Wave: {}",
+            locale::integer(self.score()),
+            self.lives,
+            self.wave
+        );
+        self.draw_text(ctx, &hud, [1.0, 1.0, 1.0, 1.0], 24.0, 10.0, 10.0)?;
+
+        if self.players > 1 {
+            for player in 0..self.players {
+                self.draw_text(
+                    ctx,
+                    &format!("P{}: {}", player + 1, self.scores[player]),
+                    SHIP_COLORS[player],
+                    20.0,
+                    10.0,
+                    40.0 + player as f32 * 24.0,
+                )?;
+            }
+        }
+
+        if self.gameover {
+            self.draw_text(
+                ctx,
+                "GAME OVER! Enter: play again  M: menu",
+                [1.0, 0.4, 0.3, 1.0],
+                32.0,
+                170.0,
+                SCREEN_SIZE.1 / 2.0 - 16.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+        self.input.update(ctx);
+
+        if !self.menu && !self.gameover {
+            self.tick(ctx, dt);
+        } else if self.gameover {
+            self.particles.update(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.03, 1.0].into());
+
+        if self.menu {
+            title::clear_score();
+            self.draw_menu(ctx)?;
+        } else {
+            title::show_score(self.score());
+            self.draw_world(ctx)?;
+            spectator::world_drawn(ctx)?;
+            self.draw_hud(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        } else if self.menu {
+            match keycode {
+                KeyCode::Key1 => self.players = 1,
+                KeyCode::Key2 => self.players = 2,
+                KeyCode::F => self.friendly_fire = !self.friendly_fire,
+                KeyCode::Return => self.restart(),
+                _ => (),
+            }
+        } else if self.gameover {
+            match keycode {
+                KeyCode::Return => self.restart(),
+                KeyCode::M => self.menu = true,
+                _ => (),
+            }
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, true);
+        if btn == Button::Start && (self.menu || self.gameover) {
+            self.restart();
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, false);
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        self.input.gamepad_axis(axis, value, id);
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("asteroids", "Sprial404")
+        .window_setup(window::setup("Asteroids"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,495 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const GRID_SIZE: (i32, i32) = (15, 13);
+const CELL_SIZE: f32 = 48.0;
+const HUD_HEIGHT: f32 = 40.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    GRID_SIZE.0 as f32 * CELL_SIZE,
+    GRID_SIZE.1 as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+/// Seconds between steps while a direction is held.
+const MOVE_DELAY: f32 = 0.15;
+const BOMB_FUSE: f32 = 2.5;
+/// How long the flames of an explosion linger.
+const FLAME_TIME: f32 = 0.5;
+
+const BLOCK_CHANCE: f64 = 0.7;
+const POWER_UP_CHANCE: f64 = 0.3;
+
+const START_BOMBS: u32 = 1;
+const START_RANGE: i32 = 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum PowerUp {
+    ExtraBomb,
+    BlastRange,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Tile {
+    Empty,
+    /// An indestructible wall.
+    Wall,
+    /// A block destroyed by explosions, possibly hiding a power-up.
+    Block(Option<PowerUp>),
+    PowerUp(PowerUp),
+}
+
+/// The keys a player controls their bomber with.
+struct Controls {
+    up: KeyCode,
+    down: KeyCode,
+    left: KeyCode,
+    right: KeyCode,
+    bomb: KeyCode,
+}
+
+static CONTROLS: [Controls; 2] = [
+    Controls {
+        up: KeyCode::W,
+        down: KeyCode::S,
+        left: KeyCode::A,
+        right: KeyCode::D,
+        bomb: KeyCode::Space,
+    },
+    Controls {
+        up: KeyCode::Up,
+        down: KeyCode::Down,
+        left: KeyCode::Left,
+        right: KeyCode::Right,
+        bomb: KeyCode::Return,
+    },
+];
+
+const PLAYER_COLORS: [[f32; 4]; 2] = [[1.0, 1.0, 1.0, 1.0], [0.2, 0.4, 1.0, 1.0]];
+
+#[derive(Debug, Copy, Clone)]
+struct Player {
+    pos: (i32, i32),
+    alive: bool,
+    max_bombs: u32,
+    range: i32,
+    /// Seconds until the player can take another step.
+    move_cooldown: f32,
+    wins: u32,
+}
+
+impl Player {
+    fn new(pos: (i32, i32), wins: u32) -> Self {
+        Player {
+            pos,
+            alive: true,
+            max_bombs: START_BOMBS,
+            range: START_RANGE,
+            move_cooldown: 0.0,
+            wins,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Bomb {
+    pos: (i32, i32),
+    fuse: f32,
+    range: i32,
+    owner: usize,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Flame {
+    pos: (i32, i32),
+    time: f32,
+}
+
+/// Generates a new arena with pillars on every other cell and random blocks, keeping the corners
+/// clear so the players have room to escape their first bomb.
+fn generate_arena() -> Grid<Tile> {
+    let mut rng = rand::thread_rng();
+    let mut tiles = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, Tile::Empty);
+
+    for y in 0..GRID_SIZE.1 {
+        for x in 0..GRID_SIZE.0 {
+            let border = x == 0 || y == 0 || x == GRID_SIZE.0 - 1 || y == GRID_SIZE.1 - 1;
+            let pillar = x % 2 == 0 && y % 2 == 0;
+            let near_corner = (x <= 2 || x >= GRID_SIZE.0 - 3) && (y <= 2 || y >= GRID_SIZE.1 - 3);
+
+            let tile = if border || pillar {
+                Tile::Wall
+            } else if !near_corner && rng.gen_bool(BLOCK_CHANCE) {
+                let power_up = if rng.gen_bool(POWER_UP_CHANCE) {
+                    Some(if rng.gen() {
+                        PowerUp::ExtraBomb
+                    } else {
+                        PowerUp::BlastRange
+                    })
+                } else {
+                    None
+                };
+                Tile::Block(power_up)
+            } else {
+                Tile::Empty
+            };
+            tiles.set(x, y, tile);
+        }
+    }
+
+    tiles
+}
+
+/// The state for the game.
+struct GameState {
+    tiles: Grid<Tile>,
+    players: [Player; 2],
+    bombs: Vec<Bomb>,
+    flames: Vec<Flame>,
+    round_over: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a fresh arena.
+    pub fn new() -> Self {
+        GameState::new_round([0, 0])
+    }
+
+    fn new_round(wins: [u32; 2]) -> Self {
+        GameState {
+            tiles: generate_arena(),
+            players: [
+                Player::new((1, 1), wins[0]),
+                Player::new((GRID_SIZE.0 - 2, GRID_SIZE.1 - 2), wins[1]),
+            ],
+            bombs: Vec::new(),
+            flames: Vec::new(),
+            round_over: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn bomb_at(&self, pos: (i32, i32)) -> bool {
+        self.bombs.iter().any(|bomb| bomb.pos == pos)
+    }
+
+    fn walkable(&self, pos: (i32, i32)) -> bool {
+        match self.tiles.get(pos.0, pos.1) {
+            Some(Tile::Empty) | Some(Tile::PowerUp(_)) => !self.bomb_at(pos),
+            _ => false,
+        }
+    }
+
+    fn move_player(&mut self, ctx: &Context, index: usize, dt: f32) {
+        let controls = &CONTROLS[index];
+        let player = &mut self.players[index];
+        player.move_cooldown = (player.move_cooldown - dt).max(0.0);
+        if !player.alive || player.move_cooldown > 0.0 {
+            return;
+        }
+
+        let dir = if keyboard::is_key_pressed(ctx, controls.up) {
+            (0, -1)
+        } else if keyboard::is_key_pressed(ctx, controls.down) {
+            (0, 1)
+        } else if keyboard::is_key_pressed(ctx, controls.left) {
+            (-1, 0)
+        } else if keyboard::is_key_pressed(ctx, controls.right) {
+            (1, 0)
+        } else {
+            return;
+        };
+
+        let target = (player.pos.0 + dir.0, player.pos.1 + dir.1);
+        if !self.walkable(target) {
+            return;
+        }
+
+        let player = &mut self.players[index];
+        player.pos = target;
+        player.move_cooldown = MOVE_DELAY;
+
+        if let Some(Tile::PowerUp(power_up)) = self.tiles.get(target.0, target.1).copied() {
+            match power_up {
+                PowerUp::ExtraBomb => player.max_bombs += 1,
+                PowerUp::BlastRange => player.range += 1,
+            }
+            self.tiles.set(target.0, target.1, Tile::Empty);
+        }
+    }
+
+    fn place_bomb(&mut self, index: usize) {
+        let player = self.players[index];
+        let placed = self.bombs.iter().filter(|bomb| bomb.owner == index).count() as u32;
+        if !player.alive || placed >= player.max_bombs || self.bomb_at(player.pos) {
+            return;
+        }
+
+        self.bombs.push(Bomb {
+            pos: player.pos,
+            fuse: BOMB_FUSE,
+            range: player.range,
+            owner: index,
+        });
+    }
+
+    /// Explodes the bomb at `index` in a cross, setting off any other bombs caught in the blast.
+    fn explode(&mut self, index: usize) {
+        let bomb = self.bombs.remove(index);
+        self.flames.push(Flame {
+            pos: bomb.pos,
+            time: FLAME_TIME,
+        });
+
+        for &(dx, dy) in &[(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            for step in 1..=bomb.range {
+                let pos = (bomb.pos.0 + dx * step, bomb.pos.1 + dy * step);
+                match self.tiles.get(pos.0, pos.1).copied() {
+                    None | Some(Tile::Wall) => break,
+                    Some(Tile::Block(power_up)) => {
+                        // Blocks stop the blast, revealing whatever they were hiding.
+                        let tile = power_up.map_or(Tile::Empty, Tile::PowerUp);
+                        self.tiles.set(pos.0, pos.1, tile);
+                        self.flames.push(Flame {
+                            pos,
+                            time: FLAME_TIME,
+                        });
+                        break;
+                    }
+                    Some(Tile::PowerUp(_)) => {
+                        self.tiles.set(pos.0, pos.1, Tile::Empty);
+                        self.flames.push(Flame {
+                            pos,
+                            time: FLAME_TIME,
+                        });
+                    }
+                    Some(Tile::Empty) => self.flames.push(Flame {
+                        pos,
+                        time: FLAME_TIME,
+                    }),
+                }
+
+                // Chain reaction: the bomb will go off on the next update.
+                if let Some(other) = self.bombs.iter_mut().find(|other| other.pos == pos) {
+                    other.fuse = 0.0;
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        for index in 0..self.players.len() {
+            self.move_player(ctx, index, dt);
+        }
+
+        for bomb in self.bombs.iter_mut() {
+            bomb.fuse -= dt;
+        }
+        while let Some(index) = self.bombs.iter().position(|bomb| bomb.fuse <= 0.0) {
+            self.explode(index);
+        }
+
+        for flame in self.flames.iter_mut() {
+            flame.time -= dt;
+        }
+        self.flames.retain(|flame| flame.time > 0.0);
+
+        for player in self.players.iter_mut() {
+            if self.flames.iter().any(|flame| flame.pos == player.pos) {
+                player.alive = false;
+            }
+        }
+
+        let alive: Vec<usize> = (0..self.players.len())
+            .filter(|i| self.players[*i].alive)
+            .collect();
+        if alive.len() <= 1 {
+            if let Some(&winner) = alive.first() {
+                self.players[winner].wins += 1;
+            }
+            self.round_over = true;
+            self.record_round(alive.first() == Some(&0));
+        }
+    }
+
+    /// Records a finished round from the first player's side.
+    fn record_round(&self, won: bool) {
+        let values = [("won", if won { 1.0 } else { 0.0 })];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("bomber", &values);
+        }
+        let _ = achievements::check_run("bomber", &values);
+    }
+
+    fn draw_cell(
+        &self,
+        ctx: &mut Context,
+        pos: (i32, i32),
+        inset: f32,
+        color: [f32; 4],
+    ) -> GameResult<()> {
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                pos.0 as f32 * CELL_SIZE + inset,
+                HUD_HEIGHT + pos.1 as f32 * CELL_SIZE + inset,
+                CELL_SIZE - inset * 2.0,
+                CELL_SIZE - inset * 2.0,
+            ),
+            color.into(),
+        )?;
+        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_circle(
+        &self,
+        ctx: &mut Context,
+        pos: (i32, i32),
+        radius: f32,
+        color: [f32; 4],
+    ) -> GameResult<()> {
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            ggez::mint::Point2 {
+                x: (pos.0 as f32 + 0.5) * CELL_SIZE,
+                y: HUD_HEIGHT + (pos.1 as f32 + 0.5) * CELL_SIZE,
+            },
+            radius,
+            0.5,
+            color.into(),
+        )?;
+        graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.round_over {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.4, 0.15, 1.0].into());
+
+        for (x, y, tile) in self.tiles.iter() {
+            match tile {
+                Tile::Wall => self.draw_cell(ctx, (x, y), 0.0, [0.4, 0.4, 0.45, 1.0])?,
+                Tile::Block(_) => self.draw_cell(ctx, (x, y), 2.0, [0.6, 0.45, 0.3, 1.0])?,
+                Tile::PowerUp(PowerUp::ExtraBomb) => {
+                    self.draw_cell(ctx, (x, y), 12.0, [0.1, 0.1, 0.1, 1.0])?
+                }
+                Tile::PowerUp(PowerUp::BlastRange) => {
+                    self.draw_cell(ctx, (x, y), 12.0, [1.0, 0.5, 0.0, 1.0])?
+                }
+                Tile::Empty => (),
+            }
+        }
+
+        for bomb in self.bombs.iter() {
+            // Pulse faster as the fuse runs out.
+            let pulse = ((BOMB_FUSE - bomb.fuse) * 12.0).sin() * 2.0;
+            self.draw_circle(
+                ctx,
+                bomb.pos,
+                CELL_SIZE * 0.35 + pulse,
+                [0.0, 0.0, 0.0, 1.0],
+            )?;
+        }
+
+        for flame in self.flames.iter() {
+            self.draw_cell(ctx, flame.pos, 4.0, [1.0, 0.6, 0.1, 1.0])?;
+        }
+
+        for (player, color) in self.players.iter().zip(PLAYER_COLORS.iter()) {
+            if player.alive {
+                self.draw_circle(ctx, player.pos, CELL_SIZE * 0.3, *color)?;
+            }
+        }
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "P1 (WASD + Space): {} wins    P2 (Arrows + Enter): {} wins",
+                self.players[0].wins, self.players[1].wins
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(22.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.round_over {
+            let message = match (self.players[0].alive, self.players[1].alive) {
+                (true, false) => "PLAYER 1 WINS!",
+                (false, true) => "PLAYER 2 WINS!",
+                _ => "DRAW!",
+            };
+            let text = Text::new(
+                TextFragment::new(format!("{} Press R for the next round.", message))
+                    .color([1.0, 1.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(32.0)),
+            );
+            graphics::draw(
+                ctx,
+                &text,
+                (ggez::mint::Point2 {
+                    x: 16.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if repeat {
+            return;
+        }
+
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        } else if self.round_over {
+            if keycode == KeyCode::R {
+                *self = GameState::new_round([self.players[0].wins, self.players[1].wins]);
+            }
+        } else if let Some(index) = CONTROLS.iter().position(|c| c.bomb == keycode) {
+            self.place_bomb(index);
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("bomber", "Sprial404")
+        .window_setup(window::setup("Bomber"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,547 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::particles::ParticleSystem;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+const RADIUS: f32 = 20.0;
+/// The vertical distance between rows of tightly packed bubbles.
+const ROW_HEIGHT: f32 = RADIUS * 1.732;
+/// The board is made of rows of `GRID_SIZE.0` bubbles, with every odd row shifted right by half
+/// a bubble and one bubble shorter.
+const GRID_SIZE: (i32, i32) = (12, 18);
+const HUD_HEIGHT: f32 = 40.0;
+
+const SCREEN_SIZE: (f32, f32) = (GRID_SIZE.0 as f32 * RADIUS * 2.0, 720.0);
+const SHOOTER: Vec2 = Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 - 50.0);
+/// A bubble stuck on or below this row ends the game.
+const LOSE_ROW: i32 = 16;
+
+const BUBBLE_COLORS: [[f32; 4]; 6] = [
+    [1.0, 0.25, 0.25, 1.0],
+    [0.25, 0.85, 0.3, 1.0],
+    [0.3, 0.5, 1.0, 1.0],
+    [1.0, 0.9, 0.25, 1.0],
+    [0.8, 0.35, 1.0, 1.0],
+    [0.3, 0.9, 0.9, 1.0],
+];
+
+const START_ROWS: i32 = 6;
+const SHOT_SPEED: f32 = 900.0;
+const AIM_SPEED: f32 = 1.5;
+/// The shallowest angle the shooter can aim at, in radians above the horizontal.
+const MIN_AIM: f32 = 0.15;
+/// Shots that don't pop anything before the ceiling drops another row.
+const SHOTS_PER_ROW: u32 = 6;
+const POP_POINTS: u32 = 10;
+/// Points for each bubble dropped by cutting it off from the ceiling.
+const DROP_POINTS: u32 = 20;
+const GRAVITY: f32 = 1200.0;
+
+/// The center of the cell at `(x, y)` on the board.
+fn cell_center(x: i32, y: i32) -> Vec2 {
+    let shift = if y % 2 == 1 { RADIUS } else { 0.0 };
+    Vec2::new(
+        RADIUS + x as f32 * RADIUS * 2.0 + shift,
+        HUD_HEIGHT + RADIUS + y as f32 * ROW_HEIGHT,
+    )
+}
+
+/// Whether `(x, y)` is a usable cell. Odd rows are one bubble shorter.
+fn is_cell(x: i32, y: i32) -> bool {
+    let width = if y % 2 == 1 {
+        GRID_SIZE.0 - 1
+    } else {
+        GRID_SIZE.0
+    };
+    x >= 0 && x < width && y >= 0 && y < GRID_SIZE.1
+}
+
+/// The six cells touching `(x, y)` on the hex grid.
+fn hex_neighbours(x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> {
+    // Which of the cells above and below touch depends on whether the row is shifted.
+    let offset = if y % 2 == 1 { 0 } else { -1 };
+    vec![
+        (x - 1, y),
+        (x + 1, y),
+        (x + offset, y - 1),
+        (x + offset + 1, y - 1),
+        (x + offset, y + 1),
+        (x + offset + 1, y + 1),
+    ]
+    .into_iter()
+    .filter(|&(x, y)| is_cell(x, y))
+}
+
+/// A bubble falling off the board after being popped or cut loose.
+#[derive(Debug, Copy, Clone)]
+struct FallingBubble {
+    pos: Vec2,
+    velocity: Vec2,
+    color: usize,
+}
+
+/// The bubble in flight.
+#[derive(Debug, Copy, Clone)]
+struct Shot {
+    pos: Vec2,
+    velocity: Vec2,
+    color: usize,
+}
+
+/// The state for the game.
+struct GameState {
+    board: Grid<Option<usize>>,
+    /// The angle the shooter is aiming at, in radians, with straight up at `-PI / 2`.
+    aim: f32,
+    current: usize,
+    next: usize,
+    shot: Option<Shot>,
+    falling: Vec<FallingBubble>,
+    particles: ParticleSystem,
+    misses: u32,
+    score: u32,
+    won: bool,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a few rows of random bubbles.
+    pub fn new() -> Self {
+        let mut rng = rand::thread_rng();
+        let mut board = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, None);
+        for y in 0..START_ROWS {
+            for x in 0..GRID_SIZE.0 {
+                if is_cell(x, y) {
+                    board.set(x, y, Some(rng.gen_range(0, BUBBLE_COLORS.len())));
+                }
+            }
+        }
+
+        let mut state = GameState {
+            board,
+            aim: -std::f32::consts::FRAC_PI_2,
+            current: 0,
+            next: 0,
+            shot: None,
+            falling: Vec::new(),
+            particles: ParticleSystem::new(Vec2::new(0.0, 400.0)),
+            misses: 0,
+            score: 0,
+            won: false,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.current = state.random_color();
+        state.next = state.random_color();
+        state
+    }
+
+    /// Picks a color still on the board, so the player is never handed a useless bubble.
+    fn random_color(&self) -> usize {
+        let colors: Vec<usize> = self.board.iter().filter_map(|(_, _, c)| *c).collect();
+        if colors.is_empty() {
+            return 0;
+        }
+        *colors.choose(&mut rand::thread_rng()).unwrap()
+    }
+
+    /// Whether a bubble at `pos` has hit the ceiling or a bubble on the board.
+    fn collides(&self, pos: Vec2) -> bool {
+        if pos.y - RADIUS <= HUD_HEIGHT {
+            return true;
+        }
+        // Allow a little overlap so shots can squeeze through narrow gaps.
+        self.board
+            .iter()
+            .any(|(x, y, cell)| cell.is_some() && (cell_center(x, y) - pos).length() < RADIUS * 1.7)
+    }
+
+    /// Moves a bubble at `pos` along `velocity` for `dt`, bouncing off the side walls.
+    fn advance(pos: &mut Vec2, velocity: &mut Vec2, dt: f32) {
+        *pos += *velocity * dt;
+        if pos.x < RADIUS {
+            pos.x = RADIUS * 2.0 - pos.x;
+            velocity.x = velocity.x.abs();
+        } else if pos.x > SCREEN_SIZE.0 - RADIUS {
+            pos.x = (SCREEN_SIZE.0 - RADIUS) * 2.0 - pos.x;
+            velocity.x = -velocity.x.abs();
+        }
+    }
+
+    /// Traces the path of a shot at the current aim, returning the points where it bounces
+    /// and where it stops.
+    fn aim_path(&self) -> Vec<Vec2> {
+        let mut pos = SHOOTER;
+        let mut velocity = Vec2::from_angle(self.aim) * SHOT_SPEED;
+        let mut points = vec![pos];
+
+        for _ in 0..1000 {
+            let before = velocity.x;
+            GameState::advance(&mut pos, &mut velocity, 1.0 / 240.0);
+            if before != velocity.x {
+                points.push(pos);
+            }
+            if self.collides(pos) {
+                break;
+            }
+        }
+        points.push(pos);
+        points
+    }
+
+    fn fire(&mut self) {
+        if self.shot.is_some() || self.gameover || self.won {
+            return;
+        }
+        self.shot = Some(Shot {
+            pos: SHOOTER,
+            velocity: Vec2::from_angle(self.aim) * SHOT_SPEED,
+            color: self.current,
+        });
+        self.current = self.next;
+        self.next = self.random_color();
+    }
+
+    /// Snaps a shot that has stopped at `pos` into the nearest free cell on the board.
+    fn attach(&mut self, pos: Vec2, color: usize) {
+        let cell = self
+            .board
+            .iter()
+            .filter(|(x, y, cell)| cell.is_none() && is_cell(*x, *y))
+            .filter(|&(x, y, _)| {
+                y == 0 || hex_neighbours(x, y).any(|(nx, ny)| self.board.get(nx, ny) != Some(&None))
+            })
+            .map(|(x, y, _)| (x, y))
+            .min_by(|a, b| {
+                let da = (cell_center(a.0, a.1) - pos).length();
+                let db = (cell_center(b.0, b.1) - pos).length();
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            });
+        let (x, y) = match cell {
+            Some(cell) => cell,
+            None => return,
+        };
+        self.board.set(x, y, Some(color));
+
+        let cluster = self.connected(x, y, |cell| cell == Some(color));
+        if cluster.len() >= 3 {
+            self.score += cluster.len() as u32 * POP_POINTS;
+            for &(x, y) in cluster.iter() {
+                self.board.set(x, y, None);
+                self.particles
+                    .burst(cell_center(x, y), 10, 250.0, 0.5, BUBBLE_COLORS[color], 3.0);
+            }
+            self.drop_floating();
+        } else {
+            self.misses += 1;
+            if self.misses >= SHOTS_PER_ROW {
+                self.misses = 0;
+                self.push_row();
+            }
+        }
+
+        if self.board.iter().all(|(_, _, cell)| cell.is_none()) {
+            self.won = true;
+        }
+        if self
+            .board
+            .iter()
+            .any(|(_, y, cell)| cell.is_some() && y >= LOSE_ROW)
+        {
+            self.gameover = true;
+        }
+        if self.won || self.gameover {
+            self.record_run();
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("won", if self.won { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("bubbles", &values);
+        }
+        let _ = achievements::check_run("bubbles", &values);
+    }
+
+    /// Finds every cell connected to `(x, y)` through hex neighbours matching `predicate`.
+    fn connected<F>(&self, x: i32, y: i32, predicate: F) -> Vec<(i32, i32)>
+    where
+        F: Fn(Option<usize>) -> bool,
+    {
+        let mut seen = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, false);
+        let mut queue = VecDeque::new();
+        let mut cells = Vec::new();
+        seen.set(x, y, true);
+        queue.push_back((x, y));
+
+        while let Some((x, y)) = queue.pop_front() {
+            cells.push((x, y));
+            for (nx, ny) in hex_neighbours(x, y) {
+                if seen.get(nx, ny) == Some(&false) && predicate(*self.board.get(nx, ny).unwrap()) {
+                    seen.set(nx, ny, true);
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        cells
+    }
+
+    /// Drops every bubble no longer connected to the ceiling.
+    fn drop_floating(&mut self) {
+        let mut anchored = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, false);
+        for x in 0..GRID_SIZE.0 {
+            if self.board.get(x, 0) != Some(&None) {
+                for (cx, cy) in self.connected(x, 0, |cell| cell.is_some()) {
+                    anchored.set(cx, cy, true);
+                }
+            }
+        }
+
+        let floating: Vec<(i32, i32, usize)> = self
+            .board
+            .iter()
+            .filter_map(|(x, y, cell)| cell.map(|color| (x, y, color)))
+            .filter(|&(x, y, _)| anchored.get(x, y) == Some(&false))
+            .collect();
+        for (x, y, color) in floating {
+            self.board.set(x, y, None);
+            self.score += DROP_POINTS;
+            self.falling.push(FallingBubble {
+                pos: cell_center(x, y),
+                velocity: Vec2::new((x as f32 - GRID_SIZE.0 as f32 / 2.0) * 10.0, -100.0),
+                color,
+            });
+        }
+    }
+
+    /// Pushes the whole board down by two rows, keeping the shifted rows lined up, and fills the
+    /// top with new bubbles.
+    fn push_row(&mut self) {
+        let mut rng = rand::thread_rng();
+        let mut board = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, None);
+        for (x, y, cell) in self.board.iter() {
+            board.set(x, y + 2, *cell);
+        }
+        for y in 0..2 {
+            for x in 0..GRID_SIZE.0 {
+                if is_cell(x, y) {
+                    board.set(x, y, Some(rng.gen_range(0, BUBBLE_COLORS.len())));
+                }
+            }
+        }
+        self.board = board;
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+            self.aim -= AIM_SPEED * dt;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+            self.aim += AIM_SPEED * dt;
+        }
+        self.aim = self.aim.max(-std::f32::consts::PI + MIN_AIM).min(-MIN_AIM);
+
+        if let Some(mut shot) = self.shot {
+            // Move in small steps so fast shots can't pass through bubbles.
+            let steps = 4;
+            let mut stopped = false;
+            for _ in 0..steps {
+                GameState::advance(&mut shot.pos, &mut shot.velocity, dt / steps as f32);
+                if self.collides(shot.pos) {
+                    stopped = true;
+                    break;
+                }
+            }
+
+            if stopped {
+                self.shot = None;
+                self.attach(shot.pos, shot.color);
+            } else {
+                self.shot = Some(shot);
+            }
+        }
+
+        for bubble in self.falling.iter_mut() {
+            bubble.velocity.y += GRAVITY * dt;
+            bubble.pos += bubble.velocity * dt;
+        }
+        self.falling
+            .retain(|bubble| bubble.pos.y < SCREEN_SIZE.1 + RADIUS);
+        self.particles.update(dt);
+    }
+
+    fn draw_bubble(&self, builder: &mut graphics::MeshBuilder, pos: Vec2, color: usize) {
+        builder.circle(
+            DrawMode::fill(),
+            ggez::mint::Point2 { x: pos.x, y: pos.y },
+            RADIUS - 1.0,
+            0.5,
+            BUBBLE_COLORS[color].into(),
+        );
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover && !self.won {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.1, 1.0].into());
+
+        let mut builder = graphics::MeshBuilder::new();
+        let lose_y = cell_center(0, LOSE_ROW).y - RADIUS;
+        builder.line(
+            &[
+                ggez::mint::Point2 { x: 0.0, y: lose_y },
+                ggez::mint::Point2 {
+                    x: SCREEN_SIZE.0,
+                    y: lose_y,
+                },
+            ],
+            2.0,
+            [0.6, 0.1, 0.1, 1.0].into(),
+        )?;
+
+        if self.shot.is_none() {
+            let path: Vec<ggez::mint::Point2<f32>> = self
+                .aim_path()
+                .into_iter()
+                .map(|p| ggez::mint::Point2 { x: p.x, y: p.y })
+                .collect();
+            builder.line(&path, 2.0, [1.0, 1.0, 1.0, 0.4].into())?;
+        }
+
+        for (x, y, cell) in self.board.iter() {
+            if let Some(color) = cell {
+                self.draw_bubble(&mut builder, cell_center(x, y), *color);
+            }
+        }
+        for bubble in self.falling.iter() {
+            self.draw_bubble(&mut builder, bubble.pos, bubble.color);
+        }
+        if let Some(shot) = self.shot {
+            self.draw_bubble(&mut builder, shot.pos, shot.color);
+        }
+        self.draw_bubble(&mut builder, SHOOTER, self.current);
+        self.draw_bubble(
+            &mut builder,
+            SHOOTER + Vec2::new(-RADIUS * 4.0, 10.0),
+            self.next,
+        );
+        for particle in self.particles.particles() {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: particle.pos.x,
+                    y: particle.pos.y,
+                },
+                particle.size,
+                0.5,
+                particle.faded_color().into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Ceiling drops in: {}",
+                locale::integer(self.score),
+                SHOTS_PER_ROW - self.misses
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        let message = if self.won {
+            Some("CLEARED! Press Enter.")
+        } else if self.gameover {
+            Some("GAME OVER! Press Enter.")
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            let text = Text::new(
+                TextFragment::new(message)
+                    .color([1.0, 0.3, 0.3, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 40.0, y: 300.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let angle = (Vec2::new(x, y) - SHOOTER).angle();
+        if angle < 0.0 {
+            self.aim = angle.max(-std::f32::consts::PI + MIN_AIM).min(-MIN_AIM);
+        }
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        if button == MouseButton::Left {
+            self.fire();
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space if !repeat => self.fire(),
+            KeyCode::Return if self.gameover || self.won => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("bubbles", "Sprial404")
+        .window_setup(window::setup("Bubble Shooter"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
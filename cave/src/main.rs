@@ -0,0 +1,688 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, filesystem, graphics, timer, Context, GameError, GameResult};
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets;
+use common::grid::Grid;
+use common::level::Level;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+const CAVE_SIZE: (i32, i32) = (40, 22);
+const CELL_SIZE: f32 = 24.0;
+const HUD_HEIGHT: f32 = 40.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    CAVE_SIZE.0 as f32 * CELL_SIZE,
+    CAVE_SIZE.1 as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+const CAVES_DIR: &str = "/caves";
+
+/// Seconds between each step of the cave, in which everything moves at most one cell.
+const TICK: f32 = 0.12;
+const LIVES: u32 = 3;
+const DIAMOND_POINTS: u32 = 10;
+/// Points for every second left on the clock when reaching the exit.
+const TIME_BONUS: u32 = 5;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Dir {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Dir {
+    fn offset(self) -> (i32, i32) {
+        match self {
+            Dir::Up => (0, -1),
+            Dir::Right => (1, 0),
+            Dir::Down => (0, 1),
+            Dir::Left => (-1, 0),
+        }
+    }
+
+    fn turn_left(self) -> Dir {
+        match self {
+            Dir::Up => Dir::Left,
+            Dir::Right => Dir::Up,
+            Dir::Down => Dir::Right,
+            Dir::Left => Dir::Down,
+        }
+    }
+
+    fn turn_right(self) -> Dir {
+        match self {
+            Dir::Up => Dir::Right,
+            Dir::Right => Dir::Down,
+            Dir::Down => Dir::Left,
+            Dir::Left => Dir::Up,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Cell {
+    Empty,
+    Dirt,
+    /// A brick wall. Objects roll off it and explosions destroy it.
+    Wall,
+    /// The indestructible wall around the cave.
+    Steel,
+    Boulder {
+        falling: bool,
+    },
+    Diamond {
+        falling: bool,
+    },
+    /// An enemy that hugs the wall on its left and explodes into nothing.
+    Firefly(Dir),
+    /// An enemy that hugs the wall on its right and explodes into diamonds.
+    Butterfly(Dir),
+    Exit,
+    /// An explosion at the given stage, turning into the given cell when it finishes.
+    Explosion(u32, Explodes),
+}
+
+/// What an explosion leaves behind.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Explodes {
+    Empty,
+    Diamonds,
+}
+
+impl Cell {
+    /// Parses a tile character from a cave file.
+    fn from_char(tile: char) -> Cell {
+        match tile {
+            '.' => Cell::Dirt,
+            'w' => Cell::Wall,
+            'W' => Cell::Steel,
+            'O' => Cell::Boulder { falling: false },
+            '*' => Cell::Diamond { falling: false },
+            'F' => Cell::Firefly(Dir::Left),
+            'B' => Cell::Butterfly(Dir::Down),
+            'X' => Cell::Exit,
+            _ => Cell::Empty,
+        }
+    }
+
+    /// Whether boulders and diamonds resting on top of this cell roll off it.
+    fn is_rounded(self) -> bool {
+        match self {
+            Cell::Wall | Cell::Boulder { .. } | Cell::Diamond { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn is_enemy(self) -> bool {
+        match self {
+            Cell::Firefly(_) | Cell::Butterfly(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Loads every cave in the assets `caves` directory, in file name order.
+fn load_caves(ctx: &mut Context) -> GameResult<Vec<Level>> {
+    let paths = assets::read_dir(ctx, CAVES_DIR)?;
+
+    let mut caves = Vec::new();
+    for path in paths {
+        let mut contents = String::new();
+        filesystem::open(ctx, &path)?.read_to_string(&mut contents)?;
+
+        let cave = Level::parse(&contents)
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path.display(), e)))?;
+        caves.push(cave);
+    }
+
+    if caves.is_empty() {
+        return Err(GameError::ResourceLoadError(format!(
+            "no caves found in {}",
+            CAVES_DIR
+        )));
+    }
+    Ok(caves)
+}
+
+/// The state for the game.
+struct GameState {
+    caves: Vec<Level>,
+    current: usize,
+    grid: Grid<Cell>,
+    player: (i32, i32),
+    alive: bool,
+    /// The diamonds needed to open the exit in the current cave.
+    quota: u32,
+    collected: u32,
+    time_left: f32,
+    tick_timer: f32,
+    lives: u32,
+    score: u32,
+    /// Whether the last cave has been completed.
+    won: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state starting at the first cave.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let mut state = GameState {
+            caves: load_caves(ctx)?,
+            current: 0,
+            grid: Grid::new(CAVE_SIZE.0 as usize, CAVE_SIZE.1 as usize, Cell::Empty),
+            player: (0, 0),
+            alive: true,
+            quota: 0,
+            collected: 0,
+            time_left: 0.0,
+            tick_timer: 0.0,
+            lives: LIVES,
+            score: 0,
+            won: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.load_cave(0);
+        Ok(state)
+    }
+
+    fn restart(&mut self) {
+        self.lives = LIVES;
+        self.score = 0;
+        self.won = false;
+        self.load_cave(0);
+    }
+
+    fn load_cave(&mut self, index: usize) {
+        let cave = &self.caves[index];
+        self.current = index;
+        self.grid = Grid::new(cave.tiles.width(), cave.tiles.height(), Cell::Empty);
+        for (x, y, tile) in cave.tiles.iter() {
+            self.grid.set(x, y, Cell::from_char(*tile));
+        }
+
+        self.player = cave.find('@').unwrap_or((1, 1));
+        self.quota = cave
+            .property("diamonds")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(0);
+        self.time_left = cave
+            .property("time")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(150.0);
+        self.collected = 0;
+        self.alive = true;
+        self.tick_timer = TICK;
+    }
+
+    fn cell(&self, pos: (i32, i32)) -> Cell {
+        self.grid.get(pos.0, pos.1).cloned().unwrap_or(Cell::Steel)
+    }
+
+    /// Whether `pos` is free for a falling object or an enemy to move into.
+    fn is_free(&self, pos: (i32, i32)) -> bool {
+        self.cell(pos) == Cell::Empty && pos != self.player
+    }
+
+    fn exit_open(&self) -> bool {
+        self.collected >= self.quota
+    }
+
+    /// Blows up the 3x3 area around `center`, taking the player with it if they are caught.
+    fn explode(&mut self, center: (i32, i32), into: Explodes, moved: &mut Grid<bool>) {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let pos = (center.0 + dx, center.1 + dy);
+                match self.cell(pos) {
+                    Cell::Steel | Cell::Exit => continue,
+                    _ => (),
+                }
+                if pos == self.player {
+                    self.alive = false;
+                }
+                self.grid.set(pos.0, pos.1, Cell::Explosion(0, into));
+                moved.set(pos.0, pos.1, true);
+            }
+        }
+    }
+
+    /// Applies the falling rules to the boulder or diamond at `pos`.
+    fn update_falling(&mut self, pos: (i32, i32), falling: bool, moved: &mut Grid<bool>) {
+        let cell = self.cell(pos);
+        let with_falling = |falling| match cell {
+            Cell::Diamond { .. } => Cell::Diamond { falling },
+            _ => Cell::Boulder { falling },
+        };
+        let below = (pos.0, pos.1 + 1);
+
+        if self.is_free(below) {
+            self.grid.set(pos.0, pos.1, Cell::Empty);
+            self.grid.set(below.0, below.1, with_falling(true));
+            moved.set(below.0, below.1, true);
+            return;
+        }
+
+        if falling && below == self.player {
+            self.explode(below, Explodes::Empty, moved);
+            return;
+        }
+        if falling && self.cell(below).is_enemy() {
+            let into = match self.cell(below) {
+                Cell::Butterfly(_) => Explodes::Diamonds,
+                _ => Explodes::Empty,
+            };
+            self.explode(below, into, moved);
+            return;
+        }
+
+        // Roll off anything rounded, preferring to the left.
+        if self.cell(below).is_rounded() {
+            for &dx in [-1, 1].iter() {
+                let side = (pos.0 + dx, pos.1);
+                if self.is_free(side) && self.is_free((side.0, side.1 + 1)) {
+                    self.grid.set(pos.0, pos.1, Cell::Empty);
+                    self.grid.set(side.0, side.1, with_falling(true));
+                    moved.set(side.0, side.1, true);
+                    return;
+                }
+            }
+        }
+
+        self.grid.set(pos.0, pos.1, with_falling(false));
+    }
+
+    /// Moves the enemy at `pos` along the wall, exploding it if it touches the player.
+    fn update_enemy(&mut self, pos: (i32, i32), moved: &mut Grid<bool>) {
+        let cell = self.cell(pos);
+        let (dir, into) = match cell {
+            Cell::Firefly(dir) => (dir, Explodes::Empty),
+            Cell::Butterfly(dir) => (dir, Explodes::Diamonds),
+            _ => return,
+        };
+
+        let touching = [Dir::Up, Dir::Right, Dir::Down, Dir::Left]
+            .iter()
+            .any(|d| (pos.0 + d.offset().0, pos.1 + d.offset().1) == self.player);
+        if touching {
+            self.explode(pos, into, moved);
+            return;
+        }
+
+        // Fireflies keep the wall on their left, butterflies on their right.
+        let (preferred, fallback) = match cell {
+            Cell::Firefly(_) => (dir.turn_left(), dir.turn_right()),
+            _ => (dir.turn_right(), dir.turn_left()),
+        };
+        let with_dir = |dir| match cell {
+            Cell::Firefly(_) => Cell::Firefly(dir),
+            _ => Cell::Butterfly(dir),
+        };
+
+        for &dir in [preferred, dir].iter() {
+            let next = (pos.0 + dir.offset().0, pos.1 + dir.offset().1);
+            if self.is_free(next) {
+                self.grid.set(pos.0, pos.1, Cell::Empty);
+                self.grid.set(next.0, next.1, with_dir(dir));
+                moved.set(next.0, next.1, true);
+                return;
+            }
+        }
+        self.grid.set(pos.0, pos.1, with_dir(fallback));
+    }
+
+    /// Moves the player one cell in `dir`, or digs without moving if `snatch` is held.
+    fn move_player(&mut self, dir: Dir, snatch: bool) {
+        let (dx, dy) = dir.offset();
+        let next = (self.player.0 + dx, self.player.1 + dy);
+
+        let enter = match self.cell(next) {
+            Cell::Empty | Cell::Dirt => true,
+            Cell::Diamond { .. } => {
+                self.collected += 1;
+                self.score += DIAMOND_POINTS;
+                true
+            }
+            Cell::Boulder { falling: false } if dy == 0 && !snatch => {
+                // Boulders can be pushed sideways into empty space.
+                let beyond = (next.0 + dx, next.1);
+                if self.is_free(beyond) {
+                    self.grid
+                        .set(beyond.0, beyond.1, Cell::Boulder { falling: false });
+                    true
+                } else {
+                    false
+                }
+            }
+            Cell::Exit if self.exit_open() && !snatch => {
+                self.complete_cave();
+                return;
+            }
+            _ => false,
+        };
+
+        if enter {
+            self.grid.set(next.0, next.1, Cell::Empty);
+            if !snatch {
+                self.player = next;
+            }
+        }
+    }
+
+    fn complete_cave(&mut self) {
+        self.score += self.time_left as u32 * TIME_BONUS;
+        if self.current + 1 < self.caves.len() {
+            self.load_cave(self.current + 1);
+        } else {
+            self.won = true;
+            self.record_run();
+        }
+    }
+
+    fn lose_life(&mut self) {
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives > 0 {
+            self.load_cave(self.current);
+        } else {
+            self.record_run();
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("cave", (self.current + 1) as f64),
+            ("won", if self.won { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("cave", &values);
+        }
+        let _ = achievements::check_run("cave", &values);
+    }
+
+    /// Steps the whole cave once, scanning from the top left like the original.
+    fn step(&mut self, dir: Option<Dir>, snatch: bool) {
+        if let Some(dir) = dir {
+            self.move_player(dir, snatch);
+        }
+        if self.won {
+            return;
+        }
+
+        let mut moved = Grid::new(self.grid.width(), self.grid.height(), false);
+        for y in 0..self.grid.height() as i32 {
+            for x in 0..self.grid.width() as i32 {
+                if moved.get(x, y) == Some(&true) {
+                    continue;
+                }
+
+                match self.cell((x, y)) {
+                    Cell::Boulder { falling } | Cell::Diamond { falling } => {
+                        self.update_falling((x, y), falling, &mut moved)
+                    }
+                    Cell::Firefly(_) | Cell::Butterfly(_) => self.update_enemy((x, y), &mut moved),
+                    Cell::Explosion(stage, into) if stage >= 2 => {
+                        let cell = match into {
+                            Explodes::Empty => Cell::Empty,
+                            Explodes::Diamonds => Cell::Diamond { falling: false },
+                        };
+                        self.grid.set(x, y, cell);
+                    }
+                    Cell::Explosion(stage, into) => {
+                        self.grid.set(x, y, Cell::Explosion(stage + 1, into))
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.time_left -= dt;
+        if self.time_left <= 0.0 {
+            self.time_left = 0.0;
+            self.alive = false;
+            return;
+        }
+
+        self.tick_timer -= dt;
+        if self.tick_timer > 0.0 {
+            return;
+        }
+        self.tick_timer += TICK;
+
+        let dir = if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+            Some(Dir::Left)
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+            Some(Dir::Right)
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Up) {
+            Some(Dir::Up)
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Down) {
+            Some(Dir::Down)
+        } else {
+            None
+        };
+        let snatch = keyboard::is_key_pressed(ctx, KeyCode::Space);
+        self.step(dir, snatch);
+    }
+
+    fn draw_cell(
+        &self,
+        builder: &mut graphics::MeshBuilder,
+        x: i32,
+        y: i32,
+        cell: Cell,
+    ) -> GameResult<()> {
+        let rect = Rect::new(
+            x as f32 * CELL_SIZE,
+            HUD_HEIGHT + y as f32 * CELL_SIZE,
+            CELL_SIZE,
+            CELL_SIZE,
+        );
+        let center = ggez::mint::Point2 {
+            x: rect.x + CELL_SIZE / 2.0,
+            y: rect.y + CELL_SIZE / 2.0,
+        };
+
+        match cell {
+            Cell::Empty => (),
+            Cell::Dirt => {
+                builder.rectangle(DrawMode::fill(), rect, [0.45, 0.3, 0.15, 1.0].into());
+            }
+            Cell::Wall => {
+                builder.rectangle(DrawMode::fill(), rect, [0.6, 0.2, 0.15, 1.0].into());
+                builder.rectangle(DrawMode::stroke(1.0), rect, [0.3, 0.1, 0.1, 1.0].into());
+            }
+            Cell::Steel => {
+                builder.rectangle(DrawMode::fill(), rect, [0.5, 0.5, 0.55, 1.0].into());
+            }
+            Cell::Boulder { .. } => {
+                builder.circle(
+                    DrawMode::fill(),
+                    center,
+                    CELL_SIZE * 0.45,
+                    0.5,
+                    [0.6, 0.55, 0.5, 1.0].into(),
+                );
+            }
+            Cell::Diamond { .. } => {
+                let half = CELL_SIZE * 0.4;
+                builder
+                    .polygon(
+                        DrawMode::fill(),
+                        &[
+                            ggez::mint::Point2 {
+                                x: center.x,
+                                y: center.y - half,
+                            },
+                            ggez::mint::Point2 {
+                                x: center.x + half,
+                                y: center.y,
+                            },
+                            ggez::mint::Point2 {
+                                x: center.x,
+                                y: center.y + half,
+                            },
+                            ggez::mint::Point2 {
+                                x: center.x - half,
+                                y: center.y,
+                            },
+                        ],
+                        [0.4, 0.9, 1.0, 1.0].into(),
+                    )
+                    .expect("diamond polygon has enough points");
+            }
+            Cell::Firefly(_) => {
+                builder.rectangle(
+                    DrawMode::stroke(3.0),
+                    Rect::new(rect.x + 4.0, rect.y + 4.0, CELL_SIZE - 8.0, CELL_SIZE - 8.0),
+                    [1.0, 0.6, 0.1, 1.0].into(),
+                );
+            }
+            Cell::Butterfly(_) => {
+                builder.circle(
+                    DrawMode::stroke(3.0),
+                    center,
+                    CELL_SIZE * 0.35,
+                    0.5,
+                    [0.4, 0.5, 1.0, 1.0].into(),
+                );
+            }
+            Cell::Exit => {
+                let color = if self.exit_open() {
+                    [1.0, 1.0, 1.0, 1.0]
+                } else {
+                    [0.5, 0.5, 0.55, 1.0]
+                };
+                builder.rectangle(DrawMode::fill(), rect, color.into());
+            }
+            Cell::Explosion(stage, _) => {
+                builder.circle(
+                    DrawMode::fill(),
+                    center,
+                    CELL_SIZE * (0.3 + stage as f32 * 0.1),
+                    0.5,
+                    [1.0, 0.9 - stage as f32 * 0.3, 0.3, 1.0].into(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(TICK);
+
+        if self.alive && !self.won && self.lives > 0 {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y, cell) in self.grid.iter() {
+            self.draw_cell(&mut builder, x, y, *cell)?;
+        }
+        if self.alive {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    self.player.0 as f32 * CELL_SIZE + 4.0,
+                    HUD_HEIGHT + self.player.1 as f32 * CELL_SIZE + 2.0,
+                    CELL_SIZE - 8.0,
+                    CELL_SIZE - 4.0,
+                ),
+                [1.0, 1.0, 0.3, 1.0].into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let cave = &self.caves[self.current];
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "{}  Diamonds: {}/{}  Time: {:.0}  Lives: {}  Score: {}",
+                cave.name(),
+                self.collected,
+                self.quota,
+                self.time_left.ceil(),
+                self.lives,
+                locale::integer(self.score)
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        let message = if self.won {
+            Some("ALL CAVES CLEARED! Press Enter to play again.")
+        } else if self.lives == 0 {
+            Some("GAME OVER! Press Enter to play again.")
+        } else if !self.alive {
+            Some("Press Space to try again.")
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            let text = Text::new(
+                TextFragment::new(message)
+                    .color([1.0, 0.2, 0.2, 1.0].into())
+                    .scale(Scale::uniform(36.0)),
+            );
+            graphics::draw(
+                ctx,
+                &text,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        let over = self.won || self.lives == 0;
+        match keycode {
+            KeyCode::Space if !repeat && !self.alive && !over => self.lose_life(),
+            KeyCode::Return if over => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("cave", "Sprial404")
+        .window_setup(window::setup("Cave"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "cave", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,766 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const COLS: i32 = 30;
+const ROWS: i32 = 30;
+const CELL: f32 = 20.0;
+const HUD_HEIGHT: f32 = 40.0;
+const SCREEN_SIZE: (f32, f32) = (COLS as f32 * CELL, ROWS as f32 * CELL + HUD_HEIGHT);
+
+/// The bottom rows the player is allowed to move around in.
+const PLAYER_ZONE: i32 = 6;
+
+const LIVES: u32 = 3;
+const PLAYER_SPEED: f32 = 220.0;
+const PLAYER_SIZE: f32 = 14.0;
+const BULLET_SPEED: f32 = 900.0;
+
+const START_MUSHROOMS: usize = 45;
+/// How many shots a mushroom takes to destroy.
+const MUSHROOM_HEALTH: u8 = 4;
+
+const CENTIPEDE_LENGTH: usize = 12;
+/// Seconds a segment takes to move one cell, getting faster every wave.
+const START_STEP_TIME: f32 = 0.08;
+const MIN_STEP_TIME: f32 = 0.04;
+const STEP_TIME_DECREASE: f32 = 0.005;
+
+const SPIDER_SPEED: (f32, f32) = (120.0, 160.0);
+/// Seconds between spiders, picked at random in this range.
+const SPIDER_INTERVAL: (f32, f32) = (4.0, 9.0);
+
+const FLEA_SPEED: f32 = 260.0;
+const FLEA_HIT_SPEED: f32 = 520.0;
+/// Fleas drop when there are fewer mushrooms than this in the player zone.
+const FLEA_THRESHOLD: usize = 5;
+const FLEA_INTERVAL: f32 = 3.0;
+
+const HEAD_POINTS: u32 = 100;
+const BODY_POINTS: u32 = 10;
+const MUSHROOM_POINTS: u32 = 1;
+const FLEA_POINTS: u32 = 200;
+/// Points for a restored mushroom after losing a life.
+const RESTORE_POINTS: u32 = 5;
+
+fn cell_center(x: i32, y: i32) -> (f32, f32) {
+    (
+        (x as f32 + 0.5) * CELL,
+        HUD_HEIGHT + (y as f32 + 0.5) * CELL,
+    )
+}
+
+fn cell_at(x: f32, y: f32) -> (i32, i32) {
+    (
+        (x / CELL).floor() as i32,
+        ((y - HUD_HEIGHT) / CELL).floor() as i32,
+    )
+}
+
+/// One segment of a centipede, moving from cell to cell.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Segment {
+    x: i32,
+    y: i32,
+    /// The cell the segment is moving from, for drawing it between cells.
+    from: (i32, i32),
+    dx: i32,
+    /// Whether the segment is working its way down (`1`) or back up (`-1`).
+    dy: i32,
+}
+
+impl Segment {
+    /// Moves the segment one cell along, dropping a row and turning when it is blocked.
+    fn step(&mut self, mushrooms: &Grid<u8>) {
+        self.from = (self.x, self.y);
+
+        let nx = self.x + self.dx;
+        let blocked = nx < 0 || nx >= COLS || mushrooms.get(nx, self.y).map_or(false, |&m| m > 0);
+        if !blocked {
+            self.x = nx;
+            return;
+        }
+
+        // Bounce between the bottom row and the top of the player zone once at the bottom.
+        if self.y + self.dy >= ROWS {
+            self.dy = -1;
+        } else if self.dy < 0 && self.y + self.dy < ROWS - PLAYER_ZONE {
+            self.dy = 1;
+        }
+        self.y += self.dy;
+        self.dx = -self.dx;
+    }
+
+    /// Where to draw the segment, `t` of the way from its last cell to its current one.
+    fn position(&self, t: f32) -> (f32, f32) {
+        let (fx, fy) = cell_center(self.from.0, self.from.1);
+        let (tx, ty) = cell_center(self.x, self.y);
+        (fx + (tx - fx) * t, fy + (ty - fy) * t)
+    }
+}
+
+/// A chain of segments with the head first.
+#[derive(Debug, Clone)]
+struct Centipede {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Spider {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Flea {
+    x: f32,
+    y: f32,
+    /// Whether it has been shot once, which makes it fall faster.
+    hit: bool,
+    /// The last row it considered leaving a mushroom on.
+    last_row: i32,
+}
+
+/// The state for the game.
+struct GameState {
+    mushrooms: Grid<u8>,
+    centipedes: Vec<Centipede>,
+    player: (f32, f32),
+    bullet: Option<(f32, f32)>,
+    spider: Option<Spider>,
+    spider_timer: f32,
+    flea: Option<Flea>,
+    flea_timer: f32,
+    step_timer: f32,
+    step_time: f32,
+    wave: u32,
+    lives: u32,
+    score: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a fresh mushroom field.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            mushrooms: Grid::new(COLS as usize, ROWS as usize, 0),
+            centipedes: Vec::new(),
+            player: (0.0, 0.0),
+            bullet: None,
+            spider: None,
+            spider_timer: 0.0,
+            flea: None,
+            flea_timer: 0.0,
+            step_timer: 0.0,
+            step_time: START_STEP_TIME,
+            wave: 0,
+            lives: LIVES,
+            score: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.mushrooms.fill(0);
+        let mut placed = 0;
+        while placed < START_MUSHROOMS {
+            // Leave the top row and the bottom row clear.
+            let x = rng.gen_range(0, COLS);
+            let y = rng.gen_range(1, ROWS - 1);
+            if self.mushrooms.get(x, y) == Some(&0) {
+                self.mushrooms.set(x, y, MUSHROOM_HEALTH);
+                placed += 1;
+            }
+        }
+
+        self.wave = 0;
+        self.step_time = START_STEP_TIME;
+        self.lives = LIVES;
+        self.score = 0;
+        self.gameover = false;
+        self.reset_round();
+    }
+
+    /// Puts the player back at the bottom and sends in a fresh centipede.
+    fn reset_round(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.player = (SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 - CELL);
+        self.bullet = None;
+        self.spider = None;
+        self.flea = None;
+        self.spider_timer = rng.gen_range(SPIDER_INTERVAL.0, SPIDER_INTERVAL.1);
+        self.flea_timer = FLEA_INTERVAL;
+        self.spawn_centipede();
+    }
+
+    fn spawn_centipede(&mut self) {
+        let start = COLS / 2;
+        let segments = (0..CENTIPEDE_LENGTH as i32)
+            .map(|i| {
+                // The body starts out in a line along the top row behind the head.
+                let x = start - i;
+                Segment {
+                    x,
+                    y: 0,
+                    from: (x, 0),
+                    dx: 1,
+                    dy: 1,
+                }
+            })
+            .collect();
+        self.centipedes = vec![Centipede { segments }];
+
+        // Later waves also bring in lone heads from the sides.
+        let mut rng = rand::thread_rng();
+        for _ in 0..self.wave.min(5) {
+            let (x, dx) = if rng.gen() { (0, 1) } else { (COLS - 1, -1) };
+            let y = rng.gen_range(0, 4);
+            self.centipedes.push(Centipede {
+                segments: vec![Segment {
+                    x,
+                    y,
+                    from: (x, y),
+                    dx,
+                    dy: 1,
+                }],
+            });
+        }
+    }
+
+    fn step_progress(&self) -> f32 {
+        1.0 - (self.step_timer / self.step_time).max(0.0).min(1.0)
+    }
+
+    fn is_mushroom(&self, x: i32, y: i32) -> bool {
+        self.mushrooms.get(x, y).map_or(false, |&m| m > 0)
+    }
+
+    /// Whether the player's box at the point overlaps a mushroom.
+    fn player_blocked(&self, x: f32, y: f32) -> bool {
+        let half = PLAYER_SIZE / 2.0;
+        let (x0, y0) = cell_at(x - half, y - half);
+        let (x1, y1) = cell_at(x + half - 0.01, y + half - 0.01);
+        (y0..=y1).any(|cy| (x0..=x1).any(|cx| self.is_mushroom(cx, cy)))
+    }
+
+    fn move_player(&mut self, ctx: &Context, dt: f32) {
+        let pressed = |a: KeyCode, b: KeyCode| {
+            keyboard::is_key_pressed(ctx, a) || keyboard::is_key_pressed(ctx, b)
+        };
+        let mut dir = (0.0, 0.0);
+        if pressed(KeyCode::Left, KeyCode::A) {
+            dir.0 -= 1.0;
+        }
+        if pressed(KeyCode::Right, KeyCode::D) {
+            dir.0 += 1.0;
+        }
+        if pressed(KeyCode::Up, KeyCode::W) {
+            dir.1 -= 1.0;
+        }
+        if pressed(KeyCode::Down, KeyCode::S) {
+            dir.1 += 1.0;
+        }
+
+        let half = PLAYER_SIZE / 2.0;
+        let zone_top = HUD_HEIGHT + (ROWS - PLAYER_ZONE) as f32 * CELL + half;
+        let (x, y) = self.player;
+
+        // Move each axis separately so the player slides along mushrooms.
+        let nx = (x + dir.0 * PLAYER_SPEED * dt)
+            .max(half)
+            .min(SCREEN_SIZE.0 - half);
+        if !self.player_blocked(nx, y) {
+            self.player.0 = nx;
+        }
+        let ny = (y + dir.1 * PLAYER_SPEED * dt)
+            .max(zone_top)
+            .min(SCREEN_SIZE.1 - half);
+        if !self.player_blocked(self.player.0, ny) {
+            self.player.1 = ny;
+        }
+    }
+
+    fn step_centipedes(&mut self) {
+        for centipede in self.centipedes.iter_mut() {
+            for segment in centipede.segments.iter_mut() {
+                segment.step(&self.mushrooms);
+            }
+        }
+    }
+
+    /// Damages the mushroom at the cell, returning whether there was one.
+    fn hit_mushroom(&mut self, x: i32, y: i32) -> bool {
+        match self.mushrooms.get_mut(x, y) {
+            Some(health) if *health > 0 => {
+                *health -= 1;
+                if *health == 0 {
+                    self.score += MUSHROOM_POINTS;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Shoots the segment near the point, splitting its centipede in two.
+    fn hit_segment(&mut self, x: f32, y: f32) -> bool {
+        let t = self.step_progress();
+        let hit = self
+            .centipedes
+            .iter()
+            .enumerate()
+            .find_map(|(c, centipede)| {
+                centipede
+                    .segments
+                    .iter()
+                    .position(|segment| {
+                        let (sx, sy) = segment.position(t);
+                        (sx - x).abs() < CELL / 2.0 && (sy - y).abs() < CELL / 2.0
+                    })
+                    .map(|s| (c, s))
+            });
+
+        let (c, s) = match hit {
+            Some(hit) => hit,
+            None => return false,
+        };
+        let mut segments = self.centipedes.remove(c).segments;
+        let tail = segments.split_off(s + 1);
+        let shot = segments.pop();
+        if let Some(shot) = shot {
+            self.score += if s == 0 { HEAD_POINTS } else { BODY_POINTS };
+            // A shot segment leaves a mushroom behind, which the rest have to go around.
+            self.mushrooms.set(shot.x, shot.y, MUSHROOM_HEALTH);
+        }
+        if !segments.is_empty() {
+            self.centipedes.push(Centipede { segments });
+        }
+        if !tail.is_empty() {
+            self.centipedes.push(Centipede { segments: tail });
+        }
+        true
+    }
+
+    fn move_bullet(&mut self, dt: f32) {
+        let (x, mut y) = match self.bullet {
+            Some(bullet) => bullet,
+            None => return,
+        };
+
+        // Move in small steps so the bullet can't skip over a mushroom.
+        let mut remaining = BULLET_SPEED * dt;
+        while remaining > 0.0 {
+            let step = remaining.min(CELL / 4.0);
+            remaining -= step;
+            y -= step;
+            if y < HUD_HEIGHT {
+                self.bullet = None;
+                return;
+            }
+
+            let (cx, cy) = cell_at(x, y);
+            if self.hit_segment(x, y) || self.hit_mushroom(cx, cy) || self.hit_flea(x, y) {
+                self.bullet = None;
+                return;
+            }
+            if let Some(spider) = self.spider {
+                if (spider.x - x).abs() < CELL && (spider.y - y).abs() < CELL * 0.6 {
+                    // Spiders are worth more the closer they are to the player.
+                    let distance = (spider.y - self.player.1).abs();
+                    self.score += if distance < CELL * 2.0 {
+                        900
+                    } else if distance < CELL * 4.0 {
+                        600
+                    } else {
+                        300
+                    };
+                    self.spider = None;
+                    self.bullet = None;
+                    return;
+                }
+            }
+        }
+        self.bullet = Some((x, y));
+    }
+
+    fn hit_flea(&mut self, x: f32, y: f32) -> bool {
+        let flea = match &mut self.flea {
+            Some(flea) if (flea.x - x).abs() < CELL / 2.0 && (flea.y - y).abs() < CELL => flea,
+            _ => return false,
+        };
+        // Fleas take two shots, speeding up after the first.
+        if flea.hit {
+            self.flea = None;
+            self.score += FLEA_POINTS;
+        } else {
+            flea.hit = true;
+        }
+        true
+    }
+
+    fn update_spider(&mut self, dt: f32) {
+        let mut rng = rand::thread_rng();
+        let spider = match &mut self.spider {
+            Some(spider) => spider,
+            None => {
+                self.spider_timer -= dt;
+                if self.spider_timer <= 0.0 {
+                    self.spider_timer = rng.gen_range(SPIDER_INTERVAL.0, SPIDER_INTERVAL.1);
+                    let from_left = rng.gen();
+                    self.spider = Some(Spider {
+                        x: if from_left { 0.0 } else { SCREEN_SIZE.0 },
+                        y: SCREEN_SIZE.1 - CELL * 3.0,
+                        vx: if from_left {
+                            SPIDER_SPEED.0
+                        } else {
+                            -SPIDER_SPEED.0
+                        },
+                        vy: -SPIDER_SPEED.1,
+                    });
+                }
+                return;
+            }
+        };
+
+        spider.x += spider.vx * dt;
+        spider.y += spider.vy * dt;
+
+        // Zigzag up and down through the player zone.
+        let top = HUD_HEIGHT + (ROWS - PLAYER_ZONE - 2) as f32 * CELL;
+        let bottom = SCREEN_SIZE.1 - CELL / 2.0;
+        if spider.y < top {
+            spider.y = top;
+            spider.vy = SPIDER_SPEED.1;
+        } else if spider.y > bottom {
+            spider.y = bottom;
+            spider.vy = -SPIDER_SPEED.1;
+        } else if rng.gen_range(0, 120) == 0 {
+            spider.vy = -spider.vy;
+        }
+
+        // Spiders eat the mushrooms they crawl over.
+        let (cx, cy) = cell_at(spider.x, spider.y);
+        if rng.gen_range(0, 8) == 0 {
+            self.mushrooms.set(cx, cy, 0);
+        }
+
+        if spider.x < -CELL || spider.x > SCREEN_SIZE.0 + CELL {
+            self.spider = None;
+        }
+    }
+
+    fn update_flea(&mut self, dt: f32) {
+        let mut rng = rand::thread_rng();
+        if self.flea.is_none() {
+            let zone_mushrooms = self
+                .mushrooms
+                .iter()
+                .filter(|&(_, y, &m)| y >= ROWS - PLAYER_ZONE && m > 0)
+                .count();
+            if zone_mushrooms >= FLEA_THRESHOLD || self.wave == 0 {
+                return;
+            }
+            self.flea_timer -= dt;
+            if self.flea_timer <= 0.0 {
+                self.flea_timer = FLEA_INTERVAL;
+                let (x, _) = cell_center(rng.gen_range(0, COLS), 0);
+                self.flea = Some(Flea {
+                    x,
+                    y: HUD_HEIGHT,
+                    hit: false,
+                    last_row: -1,
+                });
+            }
+            return;
+        }
+
+        let mut landed = false;
+        if let Some(flea) = &mut self.flea {
+            let speed = if flea.hit { FLEA_HIT_SPEED } else { FLEA_SPEED };
+            flea.y += speed * dt;
+
+            // Drop mushrooms on the way down.
+            let (cx, cy) = cell_at(flea.x, flea.y);
+            if cy != flea.last_row {
+                flea.last_row = cy;
+                if cy < ROWS - 1
+                    && rng.gen_range(0, 4) == 0
+                    && self.mushrooms.get(cx, cy) == Some(&0)
+                {
+                    self.mushrooms.set(cx, cy, MUSHROOM_HEALTH);
+                }
+            }
+            landed = flea.y > SCREEN_SIZE.1;
+        }
+        if landed {
+            self.flea = None;
+        }
+    }
+
+    fn player_hit(&self) -> bool {
+        let (px, py) = self.player;
+        let reach = PLAYER_SIZE / 2.0 + CELL * 0.4;
+        let touches = |x: f32, y: f32| (x - px).abs() < reach && (y - py).abs() < reach;
+
+        let t = self.step_progress();
+        self.centipedes
+            .iter()
+            .flat_map(|centipede| centipede.segments.iter())
+            .any(|segment| {
+                let (x, y) = segment.position(t);
+                touches(x, y)
+            })
+            || self
+                .spider
+                .map_or(false, |spider| touches(spider.x, spider.y))
+            || self.flea.map_or(false, |flea| touches(flea.x, flea.y))
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("wave", f64::from(self.wave + 1)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("centipede", &values);
+        }
+        let _ = achievements::check_run("centipede", &values);
+    }
+
+    fn lose_life(&mut self) {
+        self.lives = self.lives.saturating_sub(1);
+        if self.lives == 0 {
+            self.gameover = true;
+            self.record_run();
+            return;
+        }
+
+        // Damaged mushrooms are restored between lives, for a few points each.
+        let damaged: Vec<(i32, i32)> = self
+            .mushrooms
+            .iter()
+            .filter(|&(_, _, &m)| m > 0 && m < MUSHROOM_HEALTH)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        for (x, y) in damaged {
+            self.mushrooms.set(x, y, MUSHROOM_HEALTH);
+            self.score += RESTORE_POINTS;
+        }
+        self.reset_round();
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.move_player(ctx, dt);
+
+        if self.bullet.is_none() && keyboard::is_key_pressed(ctx, KeyCode::Space) {
+            self.bullet = Some((self.player.0, self.player.1 - PLAYER_SIZE / 2.0));
+        }
+        self.move_bullet(dt);
+
+        self.step_timer -= dt;
+        if self.step_timer <= 0.0 {
+            self.step_timer += self.step_time;
+            self.step_centipedes();
+        }
+
+        self.update_spider(dt);
+        self.update_flea(dt);
+
+        if self.centipedes.is_empty() {
+            self.wave += 1;
+            self.step_time = (self.step_time - STEP_TIME_DECREASE).max(MIN_STEP_TIME);
+            self.spawn_centipede();
+        }
+
+        if self.player_hit() {
+            self.lose_life();
+        }
+    }
+
+    fn build_mesh(&self, ctx: &mut Context) -> GameResult<graphics::Mesh> {
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                0.0,
+                HUD_HEIGHT + (ROWS - PLAYER_ZONE) as f32 * CELL,
+                SCREEN_SIZE.0,
+                PLAYER_ZONE as f32 * CELL,
+            ),
+            [0.06, 0.06, 0.1, 1.0].into(),
+        );
+
+        for (x, y, &health) in self.mushrooms.iter() {
+            if health == 0 {
+                continue;
+            }
+            // Damaged mushrooms shrink from the bottom.
+            let (cx, cy) = cell_center(x, y);
+            let height = (CELL - 4.0) * health as f32 / MUSHROOM_HEALTH as f32;
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    cx - CELL / 2.0 + 2.0,
+                    cy - CELL / 2.0 + 2.0,
+                    CELL - 4.0,
+                    height,
+                ),
+                [0.9, 0.3, 0.6, 1.0].into(),
+            );
+        }
+
+        let t = self.step_progress();
+        for centipede in self.centipedes.iter() {
+            for (i, segment) in centipede.segments.iter().enumerate() {
+                let (x, y) = segment.position(t);
+                let color = if i == 0 {
+                    [1.0, 0.8, 0.1, 1.0]
+                } else {
+                    [0.3, 0.9, 0.3, 1.0]
+                };
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 { x, y },
+                    CELL * 0.45,
+                    0.5,
+                    color.into(),
+                );
+            }
+        }
+
+        if let Some(spider) = self.spider {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    spider.x - CELL,
+                    spider.y - CELL * 0.4,
+                    CELL * 2.0,
+                    CELL * 0.8,
+                ),
+                [0.6, 0.3, 1.0, 1.0].into(),
+            );
+        }
+        if let Some(flea) = self.flea {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(flea.x - CELL * 0.4, flea.y - CELL * 0.5, CELL * 0.8, CELL),
+                [0.3, 0.7, 1.0, 1.0].into(),
+            );
+        }
+
+        let (px, py) = self.player;
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                px - PLAYER_SIZE / 2.0,
+                py - PLAYER_SIZE / 2.0,
+                PLAYER_SIZE,
+                PLAYER_SIZE,
+            ),
+            [1.0, 1.0, 1.0, 1.0].into(),
+        );
+        if let Some((bx, by)) = self.bullet {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(bx - 1.0, by - 6.0, 2.0, 8.0),
+                [1.0, 1.0, 0.4, 1.0].into(),
+            );
+        }
+
+        builder.build(ctx)
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        let mesh = self.build_mesh(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Lives: {}  Wave: {}",
+                locale::integer(self.score),
+                self.lives,
+                self.wave + 1
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER! Press Enter.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 100.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.gameover => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("centipede", "Sprial404")
+        .window_setup(window::setup("Centipede"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,399 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 720.0);
+
+const PLAYER_SIZE: (f32, f32) = (36.0, 40.0);
+const MOVE_SPEED: f32 = 320.0;
+const GRAVITY: f32 = 1400.0;
+const JUMP_SPEED: f32 = 720.0;
+const SPRING_SPEED: f32 = 1300.0;
+
+const PLATFORM_SIZE: (f32, f32) = (70.0, 14.0);
+const SPRING_SIZE: (f32, f32) = (20.0, 12.0);
+const MOVING_SPEED: f32 = 90.0;
+const BROKEN_FALL_SPEED: f32 = 400.0;
+
+/// The vertical gap between platforms starts out small and grows with height, staying below
+/// the height of a normal jump so there is always a way up.
+const MIN_GAP: f32 = 40.0;
+const START_MAX_GAP: f32 = 80.0;
+const MAX_GAP: f32 = 170.0;
+/// The height over which the gaps grow to their largest.
+const GAP_RAMP_HEIGHT: f32 = 20000.0;
+
+/// Where the camera keeps the player while climbing, from the top of the screen.
+const CAMERA_LINE: f32 = SCREEN_SIZE.1 * 0.4;
+/// Pixels of height per point of score.
+const HEIGHT_PER_POINT: f32 = 10.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PlatformKind {
+    Static,
+    /// Slides back and forth across the screen.
+    Moving,
+    /// Breaks and falls away when landed on, without bouncing the player.
+    Breaking,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Platform {
+    kind: PlatformKind,
+    x: f32,
+    /// The top of the platform in world coordinates, which go up as `y` goes down.
+    y: f32,
+    vx: f32,
+    spring: bool,
+    broken: bool,
+}
+
+impl Platform {
+    fn rect(&self) -> Rect {
+        Rect::new(self.x, self.y, PLATFORM_SIZE.0, PLATFORM_SIZE.1)
+    }
+
+    fn spring_rect(&self) -> Rect {
+        Rect::new(
+            self.x + (PLATFORM_SIZE.0 - SPRING_SIZE.0) / 2.0,
+            self.y - SPRING_SIZE.1,
+            SPRING_SIZE.0,
+            SPRING_SIZE.1,
+        )
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    platforms: Vec<Platform>,
+    /// The world `y` at the top of the screen.
+    camera: f32,
+    /// The world `y` of the highest platform placed so far.
+    highest: f32,
+    /// The highest the player has been, as a world `y`.
+    peak: f32,
+    best: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the bottom of a fresh tower.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            x: 0.0,
+            y: 0.0,
+            vx: 0.0,
+            vy: 0.0,
+            platforms: Vec::new(),
+            camera: 0.0,
+            highest: 0.0,
+            peak: 0.0,
+            best: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        // Start on a wide floor of platforms.
+        let floor = SCREEN_SIZE.1 - PLATFORM_SIZE.1;
+        self.platforms = (0..(SCREEN_SIZE.0 / PLATFORM_SIZE.0).ceil() as usize)
+            .map(|i| Platform {
+                kind: PlatformKind::Static,
+                x: i as f32 * PLATFORM_SIZE.0,
+                y: floor,
+                vx: 0.0,
+                spring: false,
+                broken: false,
+            })
+            .collect();
+        self.highest = floor;
+
+        self.x = (SCREEN_SIZE.0 - PLAYER_SIZE.0) / 2.0;
+        self.y = floor - PLAYER_SIZE.1;
+        self.vx = 0.0;
+        self.vy = -JUMP_SPEED;
+        self.camera = 0.0;
+        self.peak = self.y;
+        self.gameover = false;
+        self.generate();
+    }
+
+    fn height(&self) -> f32 {
+        SCREEN_SIZE.1 - PLAYER_SIZE.1 - PLATFORM_SIZE.1 - self.peak
+    }
+
+    fn score(&self) -> u32 {
+        (self.height() / HEIGHT_PER_POINT).max(0.0) as u32
+    }
+
+    /// Places platforms up to a screen above the camera, and forgets those below the screen.
+    fn generate(&mut self) {
+        let mut rng = rand::thread_rng();
+        let top = self.camera - SCREEN_SIZE.1;
+
+        while self.highest > top {
+            let height = SCREEN_SIZE.1 - self.highest;
+            let ramp = (height / GAP_RAMP_HEIGHT).min(1.0);
+            let max_gap = START_MAX_GAP + (MAX_GAP - START_MAX_GAP) * ramp;
+            let y = self.highest - rng.gen_range(MIN_GAP, max_gap);
+            let x = rng.gen_range(0.0, SCREEN_SIZE.0 - PLATFORM_SIZE.0);
+
+            // Tricky platforms get more common the higher up the tower.
+            let roll = rng.gen_range(0.0, 1.0);
+            let kind = if roll < 0.1 + 0.25 * ramp {
+                PlatformKind::Moving
+            } else {
+                PlatformKind::Static
+            };
+            let vx = if kind == PlatformKind::Moving {
+                if rng.gen() {
+                    MOVING_SPEED
+                } else {
+                    -MOVING_SPEED
+                }
+            } else {
+                0.0
+            };
+            self.platforms.push(Platform {
+                kind,
+                x,
+                y,
+                vx,
+                spring: kind == PlatformKind::Static && rng.gen_range(0, 12) == 0,
+                broken: false,
+            });
+
+            // Breaking platforms are extras between the real ones, as they can't be used to climb.
+            if rng.gen_range(0.0, 1.0) < 0.15 + 0.2 * ramp {
+                self.platforms.push(Platform {
+                    kind: PlatformKind::Breaking,
+                    x: rng.gen_range(0.0, SCREEN_SIZE.0 - PLATFORM_SIZE.0),
+                    y: rng.gen_range(y, self.highest),
+                    vx: 0.0,
+                    spring: false,
+                    broken: false,
+                });
+            }
+            self.highest = y;
+        }
+
+        let bottom = self.camera + SCREEN_SIZE.1;
+        self.platforms
+            .retain(|platform| platform.y < bottom + PLATFORM_SIZE.1);
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.vx = 0.0;
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) || keyboard::is_key_pressed(ctx, KeyCode::A)
+        {
+            self.vx -= MOVE_SPEED;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right)
+            || keyboard::is_key_pressed(ctx, KeyCode::D)
+        {
+            self.vx += MOVE_SPEED;
+        }
+
+        // Wrap around the sides of the screen.
+        self.x += self.vx * dt;
+        if self.x + PLAYER_SIZE.0 / 2.0 < 0.0 {
+            self.x += SCREEN_SIZE.0;
+        } else if self.x + PLAYER_SIZE.0 / 2.0 > SCREEN_SIZE.0 {
+            self.x -= SCREEN_SIZE.0;
+        }
+
+        for platform in self.platforms.iter_mut() {
+            if platform.broken {
+                platform.y += BROKEN_FALL_SPEED * dt;
+            } else if platform.kind == PlatformKind::Moving {
+                platform.x += platform.vx * dt;
+                if platform.x < 0.0 || platform.x + PLATFORM_SIZE.0 > SCREEN_SIZE.0 {
+                    platform.vx = -platform.vx;
+                    platform.x = platform.x.max(0.0).min(SCREEN_SIZE.0 - PLATFORM_SIZE.0);
+                }
+            }
+        }
+
+        let previous_bottom = self.y + PLAYER_SIZE.1;
+        self.vy += GRAVITY * dt;
+        self.y += self.vy * dt;
+        if self.vy > 0.0 {
+            self.land(previous_bottom);
+        }
+
+        // The camera only ever follows the player up.
+        self.peak = self.peak.min(self.y);
+        self.camera = self.camera.min(self.y - CAMERA_LINE);
+        self.generate();
+
+        if self.y > self.camera + SCREEN_SIZE.1 {
+            self.gameover = true;
+            self.best = self.best.max(self.score());
+            let values = [("score", self.score() as f64)];
+            if let Some(stats) = &self.stats {
+                let _ = stats.record("climber", &values);
+            }
+            let _ = achievements::check_run("climber", &values);
+        }
+    }
+
+    /// Bounces off a platform or spring the player's feet passed through this frame.
+    fn land(&mut self, previous_bottom: f32) {
+        let bottom = self.y + PLAYER_SIZE.1;
+        let (left, right) = (self.x + 6.0, self.x + PLAYER_SIZE.0 - 6.0);
+        let crossed = |top: f32, rect: Rect| {
+            previous_bottom <= top && bottom >= top && right > rect.x && left < rect.x + rect.w
+        };
+
+        for platform in self
+            .platforms
+            .iter_mut()
+            .filter(|platform| !platform.broken)
+        {
+            if platform.spring && crossed(platform.spring_rect().y, platform.spring_rect()) {
+                self.y = platform.spring_rect().y - PLAYER_SIZE.1;
+                self.vy = -SPRING_SPEED;
+                return;
+            }
+            if crossed(platform.y, platform.rect()) {
+                if platform.kind == PlatformKind::Breaking {
+                    platform.broken = true;
+                    continue;
+                }
+                self.y = platform.y - PLAYER_SIZE.1;
+                self.vy = -JUMP_SPEED;
+                return;
+            }
+        }
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        for platform in self.platforms.iter() {
+            let color = match (platform.kind, platform.broken) {
+                (_, true) => [0.5, 0.35, 0.2, 0.6],
+                (PlatformKind::Static, _) => [0.3, 0.8, 0.3, 1.0],
+                (PlatformKind::Moving, _) => [0.3, 0.6, 1.0, 1.0],
+                (PlatformKind::Breaking, _) => [0.6, 0.4, 0.2, 1.0],
+            };
+            let mut rect = platform.rect();
+            rect.y -= self.camera;
+            builder.rectangle(DrawMode::fill(), rect, color.into());
+
+            if platform.spring {
+                let mut spring = platform.spring_rect();
+                spring.y -= self.camera;
+                builder.rectangle(DrawMode::fill(), spring, [0.8, 0.8, 0.85, 1.0].into());
+            }
+        }
+
+        // Draw the player twice while wrapping around the edge.
+        for offset in &[-SCREEN_SIZE.0, 0.0, SCREEN_SIZE.0] {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    self.x + offset,
+                    self.y - self.camera,
+                    PLAYER_SIZE.0,
+                    PLAYER_SIZE.1,
+                ),
+                [1.0, 0.85, 0.2, 1.0].into(),
+            );
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.95, 0.95, 0.88, 1.0].into());
+
+        self.draw_world(ctx)?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Best: {}",
+                locale::integer(self.score()),
+                locale::integer(self.best)
+            ))
+            .color([0.1, 0.1, 0.1, 1.0].into())
+            .scale(Scale::uniform(28.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("You fell!\nPress Enter to climb again.")
+                    .color([0.8, 0.1, 0.1, 1.0].into())
+                    .scale(Scale::uniform(36.0)),
+            );
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0 - 40.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.gameover => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("climber", "Sprial404")
+        .window_setup(window::setup("Climber"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
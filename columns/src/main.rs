@@ -0,0 +1,415 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::matching::{self, ALL_LINES};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const BOARD_SIZE: (i32, i32) = (6, 13);
+const CELL_SIZE: f32 = 40.0;
+/// The space to the right of the board for the next piece and the score.
+const SIDEBAR_WIDTH: f32 = 200.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    BOARD_SIZE.0 as f32 * CELL_SIZE + SIDEBAR_WIDTH,
+    BOARD_SIZE.1 as f32 * CELL_SIZE,
+);
+
+const JEWEL_COLORS: [[f32; 4]; 6] = [
+    [1.0, 0.2, 0.2, 1.0],
+    [0.2, 0.8, 0.2, 1.0],
+    [0.2, 0.4, 1.0, 1.0],
+    [1.0, 0.9, 0.2, 1.0],
+    [0.8, 0.3, 1.0, 1.0],
+    [1.0, 0.6, 0.1, 1.0],
+];
+
+/// Seconds between each step of the falling piece on the first level.
+const START_DROP_INTERVAL: f32 = 0.8;
+const MIN_DROP_INTERVAL: f32 = 0.08;
+/// How much faster the piece falls each level.
+const DROP_SPEEDUP: f32 = 0.85;
+const SOFT_DROP_INTERVAL: f32 = 0.04;
+/// Jewels to clear before moving up a level.
+const JEWELS_PER_LEVEL: u32 = 30;
+/// How long matched jewels flash before they are cleared.
+const CLEAR_TIME: f32 = 0.35;
+const JEWEL_POINTS: u32 = 10;
+
+/// A column of three jewels falling down the board.
+#[derive(Debug, Copy, Clone)]
+struct Piece {
+    x: i32,
+    /// The row of the bottom jewel. The rest of the piece can be above the board.
+    y: i32,
+    /// The jewels from top to bottom.
+    jewels: [usize; 3],
+}
+
+impl Piece {
+    /// Creates a new piece of random jewels above the middle of the board.
+    pub fn random() -> Self {
+        let mut rng = rand::thread_rng();
+        Piece {
+            x: BOARD_SIZE.0 / 2,
+            y: -1,
+            jewels: [
+                rng.gen_range(0, JEWEL_COLORS.len()),
+                rng.gen_range(0, JEWEL_COLORS.len()),
+                rng.gen_range(0, JEWEL_COLORS.len()),
+            ],
+        }
+    }
+
+    /// The position of each jewel along with the jewel, from top to bottom.
+    fn cells(&self) -> impl Iterator<Item = ((i32, i32), usize)> + '_ {
+        self.jewels
+            .iter()
+            .enumerate()
+            .map(move |(i, jewel)| ((self.x, self.y - 2 + i as i32), *jewel))
+    }
+
+    /// Cycles the jewels downwards, moving the bottom jewel to the top.
+    fn cycle(&mut self) {
+        self.jewels.rotate_right(1);
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    board: Grid<Option<usize>>,
+    piece: Option<Piece>,
+    next: Piece,
+    drop_timer: f32,
+    /// The jewels flashing before they are cleared, and the seconds left until they go.
+    clearing: Option<(Vec<(i32, i32)>, f32)>,
+    /// How many times in a row the board has cascaded into another match since the last drop.
+    chain: u32,
+    cleared: u32,
+    score: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state.
+    pub fn new() -> Self {
+        GameState {
+            board: Grid::new(BOARD_SIZE.0 as usize, BOARD_SIZE.1 as usize, None),
+            piece: Some(Piece::random()),
+            next: Piece::random(),
+            drop_timer: START_DROP_INTERVAL,
+            clearing: None,
+            chain: 0,
+            cleared: 0,
+            score: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn level(&self) -> u32 {
+        self.cleared / JEWELS_PER_LEVEL + 1
+    }
+
+    fn drop_interval(&self) -> f32 {
+        (START_DROP_INTERVAL * DROP_SPEEDUP.powi(self.level() as i32 - 1)).max(MIN_DROP_INTERVAL)
+    }
+
+    /// Whether `piece` is inside the board and clear of any landed jewels.
+    fn fits(&self, piece: &Piece) -> bool {
+        piece.cells().all(|((x, y), _)| {
+            x >= 0
+                && x < BOARD_SIZE.0
+                && y < BOARD_SIZE.1
+                && (y < 0 || self.board.get(x, y) == Some(&None))
+        })
+    }
+
+    fn try_move(&mut self, dx: i32, dy: i32) -> bool {
+        if let Some(mut piece) = self.piece {
+            piece.x += dx;
+            piece.y += dy;
+            if self.fits(&piece) {
+                self.piece = Some(piece);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Locks the falling piece onto the board and checks for matches.
+    fn lock_piece(&mut self) {
+        let piece = match self.piece.take() {
+            Some(piece) => piece,
+            None => return,
+        };
+
+        for ((x, y), jewel) in piece.cells() {
+            if y < 0 {
+                // Landed without fitting on the board.
+                self.game_over();
+                return;
+            }
+            self.board.set(x, y, Some(jewel));
+        }
+
+        self.chain = 0;
+        self.find_matches();
+    }
+
+    /// Starts clearing any matches on the board, or brings in the next piece if there are none.
+    fn find_matches(&mut self) {
+        let runs = matching::find_runs(&self.board, 3, &ALL_LINES);
+        if runs.is_empty() {
+            self.spawn_piece();
+            return;
+        }
+
+        self.chain += 1;
+        let cells = matching::matched_cells(&runs);
+        // Every step of a cascade multiplies the points for the jewels it clears.
+        self.score += cells.len() as u32 * JEWEL_POINTS * self.chain * self.level();
+        self.cleared += cells.len() as u32;
+        self.clearing = Some((cells, CLEAR_TIME));
+    }
+
+    fn spawn_piece(&mut self) {
+        let piece = std::mem::replace(&mut self.next, Piece::random());
+        if self.fits(&piece) {
+            self.piece = Some(piece);
+            self.drop_timer = self.drop_interval();
+        } else {
+            self.game_over();
+        }
+    }
+
+    fn game_over(&mut self) {
+        self.gameover = true;
+        let values = [
+            ("score", f64::from(self.score)),
+            ("cleared", f64::from(self.cleared)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("columns", &values);
+        }
+        let _ = achievements::check_run("columns", &values);
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        if let Some((cells, time)) = self.clearing.as_mut() {
+            *time -= dt;
+            if *time > 0.0 {
+                return;
+            }
+
+            for &(x, y) in cells.iter() {
+                self.board.set(x, y, None);
+            }
+            self.clearing = None;
+            matching::apply_gravity(&mut self.board);
+            self.find_matches();
+            return;
+        }
+
+        let interval = if keyboard::is_key_pressed(ctx, KeyCode::Down) {
+            SOFT_DROP_INTERVAL
+        } else {
+            self.drop_interval()
+        };
+
+        self.drop_timer -= dt;
+        if self.drop_timer <= 0.0 {
+            self.drop_timer = interval;
+            if !self.try_move(0, 1) {
+                self.lock_piece();
+            }
+        } else if self.drop_timer > interval {
+            self.drop_timer = interval;
+        }
+    }
+
+    fn draw_jewel(
+        &self,
+        builder: &mut graphics::MeshBuilder,
+        x: f32,
+        y: f32,
+        jewel: usize,
+        flash: bool,
+    ) {
+        let color = if flash {
+            [1.0, 1.0, 1.0, 1.0]
+        } else {
+            JEWEL_COLORS[jewel]
+        };
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(x + 2.0, y + 2.0, CELL_SIZE - 4.0, CELL_SIZE - 4.0),
+            color.into(),
+        );
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                0.0,
+                0.0,
+                BOARD_SIZE.0 as f32 * CELL_SIZE,
+                BOARD_SIZE.1 as f32 * CELL_SIZE,
+            ),
+            [0.1, 0.1, 0.15, 1.0].into(),
+        );
+
+        // Flash the matched jewels on and off while they clear.
+        let flashing: &[(i32, i32)] = match &self.clearing {
+            Some((cells, time)) if (time * 10.0) as i32 % 2 == 0 => cells.as_slice(),
+            _ => &[],
+        };
+        for (x, y, jewel) in self.board.iter() {
+            if let Some(jewel) = jewel {
+                self.draw_jewel(
+                    &mut builder,
+                    x as f32 * CELL_SIZE,
+                    y as f32 * CELL_SIZE,
+                    *jewel,
+                    flashing.contains(&(x, y)),
+                );
+            }
+        }
+
+        if let Some(piece) = self.piece {
+            for ((x, y), jewel) in piece.cells().filter(|((_, y), _)| *y >= 0) {
+                self.draw_jewel(
+                    &mut builder,
+                    x as f32 * CELL_SIZE,
+                    y as f32 * CELL_SIZE,
+                    jewel,
+                    false,
+                );
+            }
+        }
+
+        let sidebar = BOARD_SIZE.0 as f32 * CELL_SIZE + 40.0;
+        for (i, jewel) in self.next.jewels.iter().enumerate() {
+            self.draw_jewel(
+                &mut builder,
+                sidebar,
+                60.0 + i as f32 * CELL_SIZE,
+                *jewel,
+                false,
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Next\n\n\n\n\n\n\nScore: {}\nLevel: {}\nJewels: {}",
+                locale::integer(self.score),
+                self.level(),
+                self.cleared
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(
+            ctx,
+            &hud,
+            (ggez::mint::Point2 {
+                x: sidebar,
+                y: 20.0,
+            },),
+        )?;
+
+        if self.chain > 1 {
+            let chain = Text::new(
+                TextFragment::new(format!("Chain x{}", self.chain))
+                    .color([1.0, 0.8, 0.0, 1.0].into())
+                    .scale(Scale::uniform(28.0)),
+            );
+            graphics::draw(
+                ctx,
+                &chain,
+                (ggez::mint::Point2 {
+                    x: sidebar,
+                    y: 360.0,
+                },),
+            )?;
+        }
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER!\nPress Enter.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(36.0)),
+            );
+
+            graphics::draw(ctx, &game_over, (ggez::mint::Point2 { x: 20.0, y: 200.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Left => {
+                self.try_move(-1, 0);
+            }
+            KeyCode::Right => {
+                self.try_move(1, 0);
+            }
+            KeyCode::Up | KeyCode::Space => {
+                if let Some(piece) = self.piece.as_mut() {
+                    piece.cycle();
+                }
+            }
+            KeyCode::Return if self.gameover => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("columns", "Sprial404")
+        .window_setup(window::setup("Columns"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
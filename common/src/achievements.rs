@@ -0,0 +1,209 @@
+//! Achievements, which each game defines in the registry and unlocks from the runs it records.
+//!
+//! Unlocks are kept per profile, one per line of tab separated fields: `timestamp game id`.
+//! Unlocking one, or beating a personal best, earns tokens for the shop and is announced with
+//! `notify`.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::migrate::{self, ACHIEVEMENTS};
+use crate::notify;
+use crate::profile;
+use crate::registry::{self, GameInfo, ScoreKey, GAMES};
+use crate::stats::{self, StatsDb};
+use crate::tokens::{self, Wallet};
+
+const ACHIEVEMENTS_FILE: &str = "achievements.tsv";
+
+/// Something to aim for in a game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Achievement {
+    /// Identifies the achievement within its game. Changing it loses the unlock.
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub goal: Goal,
+}
+
+/// What a single run has to record to unlock an achievement.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Goal {
+    /// The value under the key is at least this much.
+    AtLeast(&'static str, f64),
+    /// The value under the key is at most this much.
+    AtMost(&'static str, f64),
+}
+
+impl Goal {
+    /// Whether a run with these values reaches the goal.
+    pub fn reached(&self, values: &[(&str, f64)]) -> bool {
+        let get = |key: &str| {
+            values
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, value)| *value)
+        };
+        match *self {
+            Goal::AtLeast(key, goal) => get(key).map_or(false, |value| value >= goal),
+            Goal::AtMost(key, goal) => get(key).map_or(false, |value| value <= goal),
+        }
+    }
+}
+
+/// An achievement unlocked in the current profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unlock {
+    /// Seconds since the unix epoch when it was unlocked.
+    pub timestamp: u64,
+    pub game: String,
+    pub id: String,
+}
+
+impl Unlock {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        Some(Unlock {
+            timestamp: fields.next()?.parse().ok()?,
+            game: fields.next()?.to_string(),
+            id: fields.next()?.to_string(),
+        })
+    }
+
+    /// The definition of the unlocked achievement, if its game still defines it.
+    pub fn achievement(&self) -> Option<&'static Achievement> {
+        registry::find(&self.game)?
+            .achievements
+            .iter()
+            .find(|achievement| achievement.id == self.id)
+    }
+}
+
+/// The achievements unlocked in the current profile.
+#[derive(Debug, Clone)]
+pub struct Unlocks {
+    path: PathBuf,
+    /// Oldest first.
+    unlocks: Vec<Unlock>,
+}
+
+impl Unlocks {
+    /// Loads the current profile's unlocks.
+    pub fn load() -> io::Result<Self> {
+        let path = profile::profile_dir()?.join(ACHIEVEMENTS_FILE);
+        let unlocks = match migrate::read(&path, &ACHIEVEMENTS) {
+            Ok(contents) => contents.lines().filter_map(Unlock::parse).collect(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Unlocks { path, unlocks })
+    }
+
+    pub fn is_unlocked(&self, game: &str, id: &str) -> bool {
+        self.unlocks
+            .iter()
+            .any(|unlock| unlock.game == game && unlock.id == id)
+    }
+
+    /// Unlocks an achievement, returning whether it wasn't already unlocked.
+    pub fn unlock(&mut self, game: &str, id: &str) -> io::Result<bool> {
+        if self.is_unlocked(game, id) {
+            return Ok(false);
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let line = format!("{}\t{}\t{}", timestamp, game, id);
+        migrate::append(&self.path, &ACHIEVEMENTS, &line)?;
+        self.unlocks.push(Unlock {
+            timestamp,
+            game: game.to_string(),
+            id: id.to_string(),
+        });
+        Ok(true)
+    }
+
+    /// The most recent unlocks, newest first.
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &Unlock> {
+        self.unlocks.iter().rev().take(count)
+    }
+
+    /// How many of the collection's achievements are unlocked, and how many there are.
+    pub fn completion(&self) -> (usize, usize) {
+        let achievements = GAMES.iter().flat_map(|game| {
+            game.achievements
+                .iter()
+                .map(move |achievement| (game.name, achievement.id))
+        });
+        achievements.fold((0, 0), |(unlocked, total), (game, id)| {
+            let unlocked = unlocked + self.is_unlocked(game, id) as usize;
+            (unlocked, total + 1)
+        })
+    }
+}
+
+/// Unlocks whichever of a game's achievements a finished run reaches, returning those that
+/// are newly unlocked, and awards tokens for them and for beating the game's personal best,
+/// announcing each. Call it with the same values the run is recorded with, after recording it.
+pub fn check_run(game: &str, values: &[(&str, f64)]) -> io::Result<Vec<&'static Achievement>> {
+    let info = match registry::find(game) {
+        Some(info) => info,
+        None => return Ok(Vec::new()),
+    };
+    let mut unlocks = Unlocks::load()?;
+    let mut unlocked = Vec::new();
+    for achievement in info.achievements {
+        if achievement.goal.reached(values) && unlocks.unlock(game, achievement.id)? {
+            unlocked.push(achievement);
+        }
+    }
+
+    let mut wallet = Wallet::load()?;
+    for achievement in unlocked.iter() {
+        let reason = format!("achievement:{}:{}", game, achievement.id);
+        wallet.earn(tokens::ACHIEVEMENT_TOKENS, &reason)?;
+        notify::announce(
+            "Achievement unlocked",
+            &format!("{} in {}", achievement.title, info.title),
+        );
+    }
+    if let Some((key, score)) = personal_best(info, values) {
+        wallet.earn(tokens::PERSONAL_BEST_TOKENS, &format!("best:{}", game))?;
+        notify::announce(
+            "New personal best",
+            &format!(
+                "{} {} in {}",
+                key.label,
+                stats::format_value(score),
+                info.title
+            ),
+        );
+    }
+    Ok(unlocked)
+}
+
+/// The score a run ranks by and its key, if it beats every earlier run of a game that ranks
+/// its runs. A game's first run has nothing to beat, so it doesn't count.
+fn personal_best(info: &GameInfo, values: &[(&str, f64)]) -> Option<(ScoreKey, f64)> {
+    let key = info.score?;
+    let score = values.iter().find(|(k, _)| *k == key.key)?.1;
+    let records = StatsDb::open()
+        .and_then(|stats| stats.records_for(info.name))
+        .ok()?;
+    let mut earlier: Vec<f64> = records
+        .iter()
+        .filter_map(|record| record.get(key.key))
+        .collect();
+    // The run itself is normally recorded already.
+    if earlier.last() == Some(&score) {
+        earlier.pop();
+    }
+    if !earlier.is_empty() && earlier.iter().all(|&other| key.beats(score, other)) {
+        Some((key, score))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,597 @@
+//! Wraps a game's state with the behaviour every game shares, so each game only has to hand
+//! its state to `App::new` before running it.
+//!
+//! That is:
+//!
+//! - The break reminders from `session`: when it is time for a break, the game is paused behind
+//!   a reminder or break screen.
+//! - Pausing with the Pause key, F1 or a gamepad's select button, which shows the pause
+//!   overlay over the game. The game isn't updated while it's paused, and is told it's lost
+//!   focus, so games with something running on their own, like music, can stop it.
+//! - Toasts for gamepads being plugged in and unplugged. The game pauses if the gamepad being
+//!   played with is unplugged, and carries on with it once it is back, or with whichever gamepad
+//!   is used next.
+//! - Counting how long the game is played for, from `playtime`.
+//! - Screenshots with F12 and clips started and stopped with F10, from `capture`.
+//! - The developer console from `console`, dropped down with the backtick key, for games that
+//!   hand their commands to `App::with_console`.
+//! - Showing the window in the profile's display mode and on its monitor, and holding it to the
+//!   profile's frame cap, from `window`. In the background it draws only a few frames a second,
+//!   and the game pauses unless the profile has turned that off. It only pauses once the game
+//!   has been played, so windows opening over it as it starts, like the spectator window,
+//!   don't pause it.
+//! - The window's icon, and its title showing the game's score and mode, from `title`.
+//! - Opening the spectator window for `--spectator`, from `spectator`.
+//! - One-switch scanning from `scanning`, which presses the keys of the actions the switch
+//!   picks, and holds the game still while one is being picked.
+//! - Writing a summary of the session's runs for `--output`, and reporting the last run to
+//!   scripts for `--script`, from `results`.
+//! - Counting the session in the anonymous play statistics from `telemetry`, for profiles that
+//!   share them.
+//! - Saving a crash report if the game panics, from `crash`.
+//! - Asking the player whether to load the backup of a saved file found damaged or start it
+//!   fresh, from `overlay`. The game is paused until they've chosen.
+//! - Drawing each frame through the profile's post-processing shader, if it has one, from
+//!   `postprocess`, which reloads it as it's changed in debug builds.
+
+use std::process::ExitCode;
+
+use ggez::event::{self, Axis, Button, EventHandler, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{self, DrawParam, Image};
+use ggez::input::gamepad::GamepadId;
+use ggez::{Context, GameResult};
+
+use crate::capture::{self, Recorder};
+use crate::console::{self, Commands, Console};
+use crate::crash;
+use crate::input::{GamepadWatch, Hotplug, Nav};
+use crate::locale;
+use crate::overlay::{DamagedFiles, Overlay};
+use crate::playtime::Tracker;
+use crate::postprocess;
+use crate::results::Results;
+use crate::scanning;
+use crate::session::{Reminder, Session};
+use crate::spectator;
+use crate::title;
+use crate::toast;
+use crate::ui;
+use crate::window::{self, DisplaySettings, FrameLimiter, BACKGROUND_FRAME_CAP};
+
+const PAUSE_ACTIONS: &[&str] = &["Resume", "Quit"];
+
+/// Whether a key pauses the game.
+fn is_pause_key(key: KeyCode) -> bool {
+    key == KeyCode::Pause || key == KeyCode::F1
+}
+
+/// The pause screen, with the last frame of the game drawn behind it if it could be captured.
+struct Pause {
+    overlay: Overlay,
+    backdrop: Option<Image>,
+}
+
+/// A game with the shared behaviour around it.
+pub struct App<S> {
+    game: S,
+    session: Session,
+    reminder: Reminder,
+    pause: Option<Pause>,
+    damaged: DamagedFiles,
+    playtime: Tracker,
+    /// The clip being recorded, if there is one.
+    recording: Option<Recorder>,
+    gamepads: GamepadWatch,
+    /// The gamepad the game was last played with.
+    active_gamepad: Option<GamepadId>,
+    /// The developer console, if the game has one and it is enabled.
+    console: Option<Console<S>>,
+    /// The profile's display settings, until they are applied along with the icon on the first
+    /// update.
+    display: Option<DisplaySettings>,
+    frames: FrameLimiter,
+    /// Whether the window has focus.
+    focused: bool,
+    /// Whether the game was last told it has focus, which it hasn't while it's paused.
+    game_focused: bool,
+    background_frames: FrameLimiter,
+    pause_unfocused: bool,
+    /// Whether a key, or a mouse or gamepad button, has been pressed yet.
+    played: bool,
+    results: Results,
+}
+
+impl<S: EventHandler> App<S> {
+    pub fn new(game: S) -> Self {
+        crash::install();
+        spectator::start_requested();
+        let display = DisplaySettings::load();
+        App {
+            game,
+            session: Session::load(),
+            reminder: Reminder::None,
+            pause: None,
+            damaged: DamagedFiles::new(),
+            playtime: Tracker::new(),
+            recording: None,
+            gamepads: GamepadWatch::new(),
+            active_gamepad: None,
+            console: None,
+            frames: FrameLimiter::new(display.frame_cap),
+            focused: true,
+            game_focused: true,
+            background_frames: FrameLimiter::new(BACKGROUND_FRAME_CAP),
+            pause_unfocused: display.pause_unfocused,
+            played: false,
+            display: Some(display),
+            results: Results::new(),
+        }
+    }
+
+    /// Gives the game a developer console running `commands`, in debug builds or when it is
+    /// started with `--console`.
+    pub fn with_console(mut self, commands: Commands<S>) -> Self {
+        if console::enabled() {
+            self.console = Some(Console::new(commands));
+        }
+        self
+    }
+
+    /// Ends the session once `event::run` has returned `result`, giving the code for `main` to
+    /// exit with. Under `--script` it says how the last run went, which is reported first;
+    /// otherwise an error is returned as it was. The app is dropped before it returns, so
+    /// everything it keeps is saved by the time the game exits.
+    pub fn exit_code(mut self, result: GameResult) -> GameResult<ExitCode> {
+        if let Err(e) = &result {
+            self.results.fail(e);
+        }
+        match self.results.report() {
+            // The codes are all small enough to fit.
+            Some(code) => Ok(ExitCode::from(code as u8)),
+            None => result.map(|()| ExitCode::SUCCESS),
+        }
+    }
+
+    /// Whether it is time for a break, which is shown even over the pause screen.
+    fn is_resting(&self) -> bool {
+        self.reminder != Reminder::None
+    }
+
+    fn is_console_open(&self) -> bool {
+        self.console.as_ref().map_or(false, |console| console.open)
+    }
+
+    /// Whether the game is paused, behind the pause screen, the console, a damaged file or for
+    /// a break.
+    fn is_paused(&self) -> bool {
+        self.is_resting()
+            || self.damaged.is_asking()
+            || self.pause.is_some()
+            || self.is_console_open()
+    }
+
+    /// Tells the game it has lost focus when the window loses it or the game is paused, and
+    /// that it has it back once neither is true.
+    fn update_game_focus(&mut self, ctx: &mut Context) {
+        let focused = self.focused && !self.is_paused();
+        if focused != self.game_focused {
+            self.game_focused = focused;
+            self.game.focus_event(ctx, focused);
+        }
+    }
+
+    fn pause(&mut self, ctx: &mut Context, title: &str) {
+        self.pause = Some(Pause {
+            overlay: Overlay::new(title, Vec::new(), PAUSE_ACTIONS),
+            backdrop: graphics::screenshot(ctx).ok(),
+        });
+    }
+
+    fn open_console(&mut self, ctx: &mut Context) {
+        if let Some(console) = &mut self.console {
+            console.open = true;
+            console.backdrop = graphics::screenshot(ctx).ok();
+        }
+    }
+
+    fn draw_console(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let console = match &mut self.console {
+            Some(console) => console,
+            None => return Ok(()),
+        };
+        // A command may have changed the game, so draw it for a frame to take a new backdrop.
+        if console.stale {
+            console.stale = false;
+            self.game.draw(ctx)?;
+            console.backdrop = graphics::screenshot(ctx).ok();
+            return Ok(());
+        }
+
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        console.draw(ctx)?;
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn screenshot(&mut self, ctx: &mut Context) {
+        if let Err(e) = capture::screenshot(ctx) {
+            eprintln!("Couldn't save the screenshot: {}", e);
+        }
+    }
+
+    /// Starts recording a clip, or saves the one being recorded.
+    fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(recorder) => {
+                if let Err(e) = recorder.save() {
+                    eprintln!("Couldn't save the clip: {}", e);
+                }
+            }
+            None => self.recording = Some(Recorder::new()),
+        }
+    }
+
+    /// Adds to the clip being recorded, saving it once it is as long as it can be.
+    fn record(&mut self, ctx: &mut Context) {
+        let recorder = match &mut self.recording {
+            Some(recorder) => recorder,
+            None => return,
+        };
+        match recorder.update(ctx) {
+            Ok(true) => (),
+            Ok(false) => self.toggle_recording(),
+            Err(e) => {
+                eprintln!("Couldn't record the clip: {}", e);
+                self.recording = None;
+            }
+        }
+    }
+
+    /// Shows a toast for a gamepad being used for the first time, or again after being
+    /// unplugged.
+    fn notice_gamepad(&mut self, id: GamepadId) {
+        if let Some(change) = self.gamepads.used(id) {
+            toast::show(change.message());
+        }
+    }
+
+    /// Shows a toast for any gamepad unplugged or plugged back in, pausing the game if it was
+    /// being played with the one unplugged.
+    fn check_gamepads(&mut self, ctx: &mut Context) {
+        for change in self.gamepads.update(ctx) {
+            toast::show(change.message());
+            if let Hotplug::Disconnected(id) = change {
+                if self.active_gamepad == Some(id) && !self.is_paused() {
+                    self.pause(ctx, change.message());
+                }
+            }
+        }
+    }
+
+    /// Handles a menu step on the pause screen.
+    fn navigate_pause(&mut self, ctx: &mut Context, nav: Nav) {
+        let action = match (&mut self.pause, nav) {
+            (Some(_), Nav::Back) => Some(0),
+            (Some(pause), nav) => pause.overlay.navigate(nav),
+            (None, _) => None,
+        };
+        self.pause_action(ctx, action);
+    }
+
+    fn pause_action(&mut self, ctx: &mut Context, action: Option<usize>) {
+        match action {
+            Some(0) => self.pause = None,
+            Some(_) => event::quit(ctx),
+            None => (),
+        }
+    }
+
+    /// Handles a menu step on the break screen: a reminder can be put off, and either screen
+    /// can be used to leave the game.
+    fn navigate_break(&mut self, ctx: &mut Context, nav: Nav) {
+        match (nav, self.reminder) {
+            (Nav::Back, _) => event::quit(ctx),
+            (Nav::Confirm, Reminder::Remind) => {
+                self.session.snooze();
+                self.reminder = Reminder::None;
+            }
+            _ => (),
+        }
+    }
+
+    /// Handles a key going down, whether it was pressed or picked with the switch.
+    fn press_key(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods, repeat: bool) {
+        if self.is_resting() {
+            if let Some(nav) = Nav::from_key(keycode) {
+                self.navigate_break(ctx, nav);
+            }
+        } else if self.damaged.is_asking() {
+            if let Some(nav) = Nav::from_key(keycode) {
+                self.damaged.navigate(nav);
+            }
+        } else if self.pause.is_some() {
+            if is_pause_key(keycode) {
+                self.pause = None;
+            } else if let Some(nav) = Nav::from_key(keycode) {
+                self.navigate_pause(ctx, nav);
+            }
+        } else if let Some(console) = self.console.as_mut().filter(|console| console.open) {
+            if console::is_toggle_key(keycode) {
+                console.open = false;
+            } else {
+                console.key_down(&mut self.game, keycode);
+            }
+        } else if is_pause_key(keycode) && !repeat {
+            self.pause(ctx, "Paused");
+        } else if console::is_toggle_key(keycode) && self.console.is_some() && !repeat {
+            self.open_console(ctx);
+        } else if keycode == KeyCode::F12 && !repeat {
+            self.screenshot(ctx);
+        } else if keycode == KeyCode::F10 && !repeat {
+            self.toggle_recording();
+        } else {
+            self.game.key_down_event(ctx, keycode, keymods, repeat);
+        }
+    }
+
+    fn draw_break(&self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.08, 0.1, 0.14, 1.0].into());
+        let (width, height) = graphics::drawable_size(ctx);
+        let x = width * 0.1;
+        let mut y = height * 0.3;
+
+        ui::draw_text(ctx, "Time for a break", [1.0, 1.0, 1.0, 1.0], 40.0, x, y)?;
+        y += 60.0;
+        let body = match self.reminder {
+            Reminder::Break(remaining) => format!(
+                "The games are paused while you rest. Back in {}.",
+                locale::duration(remaining)
+            ),
+            _ => format!(
+                "You've been playing for {} minutes. Stretch, blink and have a drink of water.",
+                self.session.played() / 60
+            ),
+        };
+        ui::draw_wrapped(ctx, &body, [0.85, 0.85, 0.9, 1.0], 22.0, x, y, width * 0.8)?;
+        y += 90.0;
+        let hint = match self.reminder {
+            Reminder::Remind => "Enter or A to keep playing, Escape or B to stop.",
+            _ => "Escape or B to close the game.",
+        };
+        ui::draw_text(ctx, hint, [0.6, 0.6, 0.65, 1.0], 18.0, x, y)?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn draw_damaged(&self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        self.damaged.draw(ctx)?;
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn draw_pause(&self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        if let Some(pause) = &self.pause {
+            if let Some(backdrop) = &pause.backdrop {
+                let screen = graphics::screen_coordinates(ctx);
+                let scale = ggez::mint::Vector2 {
+                    x: screen.w / backdrop.width() as f32,
+                    y: screen.h / backdrop.height() as f32,
+                };
+                graphics::draw(
+                    ctx,
+                    backdrop,
+                    DrawParam::new()
+                        .dest(ggez::mint::Point2 {
+                            x: screen.x,
+                            y: screen.y,
+                        })
+                        .scale(scale),
+                )?;
+            }
+            pause.overlay.draw(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+}
+
+impl<S: EventHandler> EventHandler for App<S> {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.focused {
+            self.frames.wait();
+        } else {
+            self.background_frames.wait();
+        }
+        if let Some(display) = self.display.take() {
+            if let Err(e) = window::apply(ctx, &display) {
+                eprintln!("Couldn't change the display mode: {}", e);
+            }
+            if let Err(e) = title::set_icon(ctx) {
+                eprintln!("Couldn't set the window icon: {}", e);
+            }
+        }
+        title::update(ctx);
+        if let Some(key) = scanning::update() {
+            self.press_key(ctx, key, KeyMods::NONE, false);
+            self.game.key_up_event(ctx, key, KeyMods::NONE);
+        }
+        self.reminder = self.session.update();
+        self.damaged.update();
+        self.check_gamepads(ctx);
+        self.playtime.update(!self.is_paused());
+        self.update_game_focus(ctx);
+        if self.is_paused() || scanning::is_choosing() {
+            return Ok(());
+        }
+        self.record(ctx);
+        let result = self.game.update(ctx);
+        if let Err(e) = &result {
+            self.results.fail(e);
+        }
+        result
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        postprocess::begin(ctx)?;
+        if self.is_resting() {
+            return self.draw_break(ctx);
+        }
+        if self.damaged.is_asking() {
+            return self.draw_damaged(ctx);
+        }
+        if self.pause.is_some() {
+            return self.draw_pause(ctx);
+        }
+        if self.is_console_open() {
+            return self.draw_console(ctx);
+        }
+        let result = self.game.draw(ctx);
+        if let Err(e) = &result {
+            self.results.fail(e);
+        }
+        result
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        self.played = true;
+        if !self.is_paused() {
+            self.game.mouse_button_down_event(ctx, button, x, y);
+        } else if button == MouseButton::Left && self.damaged.is_asking() {
+            self.damaged.click(ctx, x, y);
+        } else if button == MouseButton::Left && !self.is_resting() {
+            let action = self
+                .pause
+                .as_ref()
+                .and_then(|pause| pause.overlay.action_at(ctx, x, y));
+            self.pause_action(ctx, action);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        self.game.mouse_button_up_event(ctx, button, x, y);
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, dx: f32, dy: f32) {
+        if !self.is_paused() {
+            self.game.mouse_motion_event(ctx, x, y, dx, dy);
+        } else if self.damaged.is_asking() {
+            self.damaged.mouse_motion(ctx, x, y);
+        } else if let Some(pause) = &mut self.pause {
+            pause.overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        if !self.is_paused() {
+            self.game.mouse_wheel_event(ctx, x, y);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        self.played = true;
+        if !self.is_console_open() && scanning::key_down(keycode) {
+            return;
+        }
+        self.press_key(ctx, keycode, keymods, repeat);
+    }
+
+    // Releases are always passed on so the game doesn't think keys and buttons are still held
+    // after the break.
+    fn key_up_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
+        if !self.is_console_open() && scanning::key_up(keycode) {
+            return;
+        }
+        self.game.key_up_event(ctx, keycode, keymods);
+    }
+
+    fn text_input_event(&mut self, ctx: &mut Context, character: char) {
+        if let Some(console) = self.console.as_mut().filter(|console| console.open) {
+            console.text_input(character);
+        } else if !self.is_paused() {
+            self.game.text_input_event(ctx, character);
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.played = true;
+        self.notice_gamepad(id);
+        if scanning::button_down(btn) {
+            return;
+        }
+        if self.is_resting() {
+            if let Some(nav) = Nav::from_button(btn) {
+                self.navigate_break(ctx, nav);
+            }
+        } else if self.damaged.is_asking() {
+            if let Some(nav) = Nav::from_button(btn) {
+                self.damaged.navigate(nav);
+            }
+        } else if self.pause.is_some() {
+            if let Some(nav) = Nav::from_button(btn) {
+                self.navigate_pause(ctx, nav);
+            }
+        } else if self.is_console_open() {
+            // The console is only used from the keyboard.
+        } else if btn == Button::Select {
+            self.pause(ctx, "Paused");
+        } else {
+            self.active_gamepad = Some(id);
+            self.game.gamepad_button_down_event(ctx, btn, id);
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.notice_gamepad(id);
+        if scanning::button_up(btn) {
+            return;
+        }
+        self.game.gamepad_button_up_event(ctx, btn, id);
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        self.notice_gamepad(id);
+        self.game.gamepad_axis_event(ctx, axis, value, id);
+    }
+
+    fn focus_event(&mut self, ctx: &mut Context, gained: bool) {
+        self.focused = gained;
+        if !gained && self.pause_unfocused && self.played && !self.is_paused() {
+            self.pause(ctx, "Paused");
+        }
+        self.update_game_focus(ctx);
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        self.game.quit_event(ctx)
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.game.resize_event(ctx, width, height);
+    }
+}
+
+/// Writes the session's results once the game closes, and counts them in the play statistics
+/// if they're shared.
+impl<S> Drop for App<S> {
+    fn drop(&mut self) {
+        spectator::stop();
+        self.results.tally();
+        if let Err(e) = self.results.write() {
+            eprintln!("Couldn't write the results: {}", e);
+        }
+    }
+}
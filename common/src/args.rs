@@ -0,0 +1,92 @@
+//! The command line shared by the games, the launcher and the `quick-games` tool.
+//!
+//! Options are given as `--name value` or `--name=value`. An option followed by another option,
+//! or by nothing, is a flag with no value. Anything else is a positional argument, like the
+//! tool's command and the game it is for.
+//!
+//! Options also override the profile's settings for a single run: `Settings::load` looks for
+//! an option of the same name before the settings file, so `--theme neon` plays with the neon
+//! theme without saving it.
+
+use std::env;
+use std::str::FromStr;
+
+/// Parsed command line arguments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    /// The arguments that aren't options, in order.
+    pub positional: Vec<String>,
+    /// Each option's name, without the dashes, and its value unless it is a flag.
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    /// Parses arguments, not including the program's name.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let option = match arg.strip_prefix("--") {
+                Some(option) => option,
+                None => {
+                    parsed.positional.push(arg);
+                    continue;
+                }
+            };
+            let (name, value) = match option.find('=') {
+                Some(equals) => (&option[..equals], Some(option[equals + 1..].to_string())),
+                None => {
+                    let takes_value = args.peek().map_or(false, |next| !next.starts_with("--"));
+                    (option, if takes_value { args.next() } else { None })
+                }
+            };
+            parsed.options.push((name.to_string(), value));
+        }
+        parsed
+    }
+
+    /// The arguments the program was started with.
+    pub fn from_env() -> Self {
+        Args::parse(env::args().skip(1))
+    }
+
+    /// The value of an option, if it was given one.
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// The value of an option parsed as a `T`, if it was given one that parses.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value(name).and_then(|value| value.parse().ok())
+    }
+
+    /// Whether an option was given, with or without a value.
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    /// Every option, in the order they were given.
+    pub fn options(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+        self.options
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_deref()))
+    }
+}
+
+/// The value of one of the program's options.
+pub fn value(name: &str) -> Option<String> {
+    Args::from_env().value(name).map(str::to_string)
+}
+
+/// The value of one of the program's options parsed as a `T`.
+pub fn get<T: FromStr>(name: &str) -> Option<T> {
+    Args::from_env().get(name)
+}
+
+/// Whether the program was given an option.
+pub fn flag(name: &str) -> bool {
+    Args::from_env().flag(name)
+}
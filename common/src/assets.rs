@@ -0,0 +1,154 @@
+//! The games' asset folders, and reloading them while a game runs.
+//!
+//! Games embed their assets in their executables, zipped by their build scripts with
+//! `quick_games_embed::assets`, so each is a single file that can't find its assets missing.
+//! `add_to` has ggez look for each asset in three places, using the first it's found in:
+//!
+//! 1. The modders' folder, `assets/<game>` in the data directory, so anything put there is used
+//!    instead of the game's own file with the same path.
+//! 2. The game's assets folder from `dir`, when there is one.
+//! 3. The assets embedded in the executable.
+//!
+//! In debug builds a `Watcher` notices when files in a game's assets change on disk, so the
+//! game can load them again without restarting, for drawing images and laying out levels
+//! while playing them. Release builds don't watch anything.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use ggez::{filesystem, Context, ContextBuilder, GameResult};
+
+/// How often the assets are checked for changes.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The folder of the running game's assets: `assets` in its crate when run through cargo, or
+/// else next to where it is run from.
+pub fn dir() -> PathBuf {
+    match env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) => PathBuf::from(manifest_dir).join("assets"),
+        Err(_) => PathBuf::from("./assets"),
+    }
+}
+
+/// The running game's assets, as embedded by its build script. It's for passing to `add_to`.
+#[macro_export]
+macro_rules! embedded_assets {
+    () => {
+        include_bytes!(concat!(env!("OUT_DIR"), "/assets.zip"))
+    };
+}
+
+/// The folder modders can put assets for `game` in, to be used instead of its own.
+pub fn override_dir(game: &str) -> io::Result<PathBuf> {
+    Ok(crate::data_dir()?.join("assets").join(game))
+}
+
+/// Has ggez find `game`'s assets in the modders' folder, then its assets folder, then the
+/// `embedded` ones from `embedded_assets!`.
+pub fn add_to(builder: ContextBuilder, game: &str, embedded: &'static [u8]) -> ContextBuilder {
+    let builder = match override_dir(game) {
+        Ok(dir) => builder.add_resource_path(dir),
+        Err(e) => {
+            eprintln!("Couldn't find the folder for modded assets: {}", e);
+            builder
+        }
+    };
+    builder.add_resource_path(dir()).add_zipfile_bytes(embedded)
+}
+
+/// The files in a folder of the game's resources, like `/levels`, sorted by path. A file in
+/// more than one of the places assets are found in is only listed once.
+pub fn read_dir(ctx: &mut Context, dir: &str) -> GameResult<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = filesystem::read_dir(ctx, dir)?
+        .map(|path| Path::new("/").join(path))
+        .collect();
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Notices changes to the files in an assets folder.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    /// The folder being watched, or `None` in release builds.
+    dir: Option<PathBuf>,
+    /// When each file was last changed, as of the last check.
+    files: HashMap<PathBuf, SystemTime>,
+    last_check: Instant,
+}
+
+impl Watcher {
+    /// Starts watching `dir` in debug builds.
+    pub fn new(dir: PathBuf) -> Self {
+        let mut watcher = Watcher {
+            dir: if cfg!(debug_assertions) {
+                Some(dir)
+            } else {
+                None
+            },
+            files: HashMap::new(),
+            last_check: Instant::now(),
+        };
+        if let Some(dir) = &watcher.dir {
+            watcher.files = scan(dir);
+        }
+        watcher
+    }
+
+    /// The files that have been changed, added or removed since the last time this was called,
+    /// as paths to load them with from the game's resources, like `/levels/01.txt`. Call it
+    /// every frame; the files are only checked every so often.
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        let dir = match &self.dir {
+            Some(dir) if self.last_check.elapsed() >= CHECK_INTERVAL => dir,
+            _ => return Vec::new(),
+        };
+        self.last_check = Instant::now();
+
+        let files = scan(dir);
+        let mut changed: Vec<PathBuf> = files
+            .iter()
+            .filter(|(path, modified)| self.files.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .chain(
+                self.files
+                    .keys()
+                    .filter(|path| !files.contains_key(*path))
+                    .cloned(),
+            )
+            .filter_map(|path| Some(Path::new("/").join(path.strip_prefix(dir).ok()?)))
+            .collect();
+        changed.sort();
+        self.files = files;
+        changed
+    }
+}
+
+/// When each file under `dir` was last changed.
+fn scan(dir: &Path) -> HashMap<PathBuf, SystemTime> {
+    let mut files = HashMap::new();
+    match scan_into(dir, &mut files) {
+        Err(ref e) if e.kind() != io::ErrorKind::NotFound => {
+            eprintln!("Couldn't watch the assets in {}: {}", dir.display(), e)
+        }
+        _ => (),
+    }
+    files
+}
+
+fn scan_into(dir: &Path, files: &mut HashMap<PathBuf, SystemTime>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            scan_into(&entry.path(), files)?;
+        } else {
+            files.insert(entry.path(), meta.modified()?);
+        }
+    }
+    Ok(())
+}
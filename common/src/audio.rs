@@ -0,0 +1,169 @@
+//! Volumes shared by every game, set on the launcher's mixer.
+//!
+//! The volumes are kept in the profile's settings as percentages under `volume_master`,
+//! `volume_music` and `volume_sfx`. Running games keep a `Mixer`, which notices when the
+//! settings change so the mixer applies straight away.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::settings::Settings;
+
+/// How often a running game checks whether the volumes have changed.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A volume that can be set on the mixer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Channel {
+    /// Scales both of the others.
+    Master,
+    Music,
+    /// Sound effects.
+    Sfx,
+}
+
+impl Channel {
+    /// Every channel, in the order the mixer lists them.
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Music, Channel::Sfx];
+
+    fn key(self) -> &'static str {
+        match self {
+            Channel::Master => "volume_master",
+            Channel::Music => "volume_music",
+            Channel::Sfx => "volume_sfx",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Channel::Master => "Master",
+            Channel::Music => "Music",
+            Channel::Sfx => "Sound effects",
+        }
+    }
+}
+
+/// The volume of each channel, as a percentage.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Volumes {
+    pub master: u32,
+    pub music: u32,
+    pub sfx: u32,
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Volumes {
+            master: 100,
+            music: 80,
+            sfx: 100,
+        }
+    }
+}
+
+impl Volumes {
+    /// Loads the current profile's volumes.
+    pub fn load() -> Self {
+        Volumes::from_settings(&Settings::load())
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        let defaults = Volumes::default();
+        let mut volumes = defaults;
+        for &channel in Channel::ALL.iter() {
+            let value = settings.get_or(channel.key(), defaults.get(channel));
+            volumes.set(channel, value);
+        }
+        volumes
+    }
+
+    pub fn get(&self, channel: Channel) -> u32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Music => self.music,
+            Channel::Sfx => self.sfx,
+        }
+    }
+
+    /// Sets a channel's volume, up to 100%.
+    pub fn set(&mut self, channel: Channel, value: u32) {
+        let value = value.min(100);
+        match channel {
+            Channel::Master => self.master = value,
+            Channel::Music => self.music = value,
+            Channel::Sfx => self.sfx = value,
+        }
+    }
+
+    /// The volume to play music at, from 0 to 1.
+    pub fn music(&self) -> f32 {
+        (self.master * self.music) as f32 / 10_000.0
+    }
+
+    /// The volume to play sound effects at, from 0 to 1.
+    pub fn sfx(&self) -> f32 {
+        (self.master * self.sfx) as f32 / 10_000.0
+    }
+
+    /// Saves the volumes into the current profile's settings.
+    pub fn save(&self) -> io::Result<()> {
+        let mut settings = Settings::load();
+        for &channel in Channel::ALL.iter() {
+            settings.set(channel.key(), self.get(channel));
+        }
+        settings.save()
+    }
+}
+
+/// A running game's volumes, kept in step with the settings.
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    volumes: Volumes,
+    path: Option<PathBuf>,
+    /// When the settings were last changed, as of the last check.
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl Mixer {
+    pub fn load() -> Self {
+        let settings = Settings::load();
+        let path = settings.path().map(PathBuf::from);
+        Mixer {
+            volumes: Volumes::from_settings(&settings),
+            modified: path.as_ref().and_then(|path| modified(path)),
+            path,
+            last_check: Instant::now(),
+        }
+    }
+
+    pub fn volumes(&self) -> Volumes {
+        self.volumes
+    }
+
+    /// Reloads the volumes if the settings have changed, returning whether the volumes did.
+    /// Call it every frame; the settings are only checked every so often.
+    pub fn update(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let modified = self.path.as_ref().and_then(|path| modified(path));
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+
+        let volumes = Volumes::load();
+        let changed = volumes != self.volumes;
+        self.volumes = volumes;
+        changed
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,337 @@
+//! The keys and gamepad buttons each player slot uses, which can be changed on the launcher's
+//! controls screen.
+//!
+//! Only changed bindings are kept, in the profile's settings as `p1_up=W`, `p2_pad_action=West`
+//! and so on. Anything not in the settings uses the slot's default scheme from `input`.
+
+use ggez::event::{Button, KeyCode};
+
+use std::io;
+
+use crate::input::{KeyScheme, KEY_SCHEMES, MAX_PLAYERS};
+use crate::settings::Settings;
+
+/// The keys that can be bound. Escape, the pause keys and the function keys are left out as
+/// they mean the same in every game.
+pub const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Return,
+    KeyCode::Tab,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadEnter,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::LBracket,
+    KeyCode::RBracket,
+    KeyCode::Minus,
+    KeyCode::Equals,
+];
+
+/// The gamepad buttons the action can be bound to. The d-pad moves and select pauses, so they
+/// are left out.
+pub const BINDABLE_BUTTONS: &[Button] = &[
+    Button::South,
+    Button::East,
+    Button::North,
+    Button::West,
+    Button::LeftTrigger,
+    Button::LeftTrigger2,
+    Button::RightTrigger,
+    Button::RightTrigger2,
+    Button::LeftThumb,
+    Button::RightThumb,
+];
+
+/// The name a key is saved and shown under.
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|&key| key_name(key) == name)
+}
+
+/// The name a gamepad button is saved and shown under.
+pub fn button_name(button: Button) -> String {
+    format!("{:?}", button)
+}
+
+pub fn button_from_name(name: &str) -> Option<Button> {
+    BINDABLE_BUTTONS
+        .iter()
+        .copied()
+        .find(|&button| button_name(button) == name)
+}
+
+/// Something a player does that can be bound.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Control {
+    Up,
+    Down,
+    Left,
+    Right,
+    Action,
+    /// The action, on the player's gamepad.
+    PadAction,
+}
+
+impl Control {
+    /// Every control, in the order they are listed.
+    pub const ALL: [Control; 6] = [
+        Control::Up,
+        Control::Down,
+        Control::Left,
+        Control::Right,
+        Control::Action,
+        Control::PadAction,
+    ];
+
+    /// The name the control is saved under, after the player.
+    fn name(self) -> &'static str {
+        match self {
+            Control::Up => "up",
+            Control::Down => "down",
+            Control::Left => "left",
+            Control::Right => "right",
+            Control::Action => "action",
+            Control::PadAction => "pad_action",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Control::Up => "Up",
+            Control::Down => "Down",
+            Control::Left => "Left",
+            Control::Right => "Right",
+            Control::Action => "Action",
+            Control::PadAction => "Action (gamepad)",
+        }
+    }
+
+    /// Whether the control is bound to a gamepad button rather than a key.
+    pub fn is_button(self) -> bool {
+        self == Control::PadAction
+    }
+}
+
+/// What a control is bound to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(Button),
+}
+
+impl Binding {
+    pub fn name(self) -> String {
+        match self {
+            Binding::Key(key) => key_name(key),
+            Binding::Button(button) => button_name(button),
+        }
+    }
+}
+
+/// The controls of one player slot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlayerBindings {
+    pub keys: KeyScheme,
+    pub button: Button,
+}
+
+impl PlayerBindings {
+    /// Describes the keys for showing on screen, by the scheme's name unless they have been
+    /// changed from the default.
+    pub fn describe(&self, player: usize) -> String {
+        match KEY_SCHEMES.get(player) {
+            Some(scheme) if *scheme == self.keys => scheme.name.to_string(),
+            _ => format!(
+                "{}/{}/{}/{} + {}",
+                key_name(self.keys.up),
+                key_name(self.keys.left),
+                key_name(self.keys.down),
+                key_name(self.keys.right),
+                key_name(self.keys.action)
+            ),
+        }
+    }
+}
+
+/// Every player slot's controls.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bindings {
+    pub players: [PlayerBindings; MAX_PLAYERS],
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut players = [PlayerBindings {
+            keys: KEY_SCHEMES[0],
+            button: Button::South,
+        }; MAX_PLAYERS];
+        for (player, bindings) in players.iter_mut().enumerate() {
+            bindings.keys = KEY_SCHEMES[player];
+        }
+        Bindings { players }
+    }
+}
+
+fn setting_key(player: usize, control: Control) -> String {
+    format!("p{}_{}", player + 1, control.name())
+}
+
+impl Bindings {
+    /// Loads the current profile's bindings.
+    pub fn load() -> Self {
+        Bindings::from_settings(&Settings::load())
+    }
+
+    /// The bindings in the given settings, with the defaults for any missing or unreadable.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mut bindings = Bindings::default();
+        for player in 0..MAX_PLAYERS {
+            for &control in Control::ALL.iter() {
+                let name = match settings.get(&setting_key(player, control)) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                let binding = if control.is_button() {
+                    button_from_name(name).map(Binding::Button)
+                } else {
+                    key_from_name(name).map(Binding::Key)
+                };
+                if let Some(binding) = binding {
+                    bindings.set(player, control, binding);
+                }
+            }
+        }
+        bindings
+    }
+
+    pub fn get(&self, player: usize, control: Control) -> Binding {
+        let bindings = &self.players[player];
+        match control {
+            Control::Up => Binding::Key(bindings.keys.up),
+            Control::Down => Binding::Key(bindings.keys.down),
+            Control::Left => Binding::Key(bindings.keys.left),
+            Control::Right => Binding::Key(bindings.keys.right),
+            Control::Action => Binding::Key(bindings.keys.action),
+            Control::PadAction => Binding::Button(bindings.button),
+        }
+    }
+
+    /// Binds a control. A key given for the gamepad control, or a button for a key, is ignored.
+    pub fn set(&mut self, player: usize, control: Control, binding: Binding) {
+        let bindings = &mut self.players[player];
+        match (control, binding) {
+            (Control::Up, Binding::Key(key)) => bindings.keys.up = key,
+            (Control::Down, Binding::Key(key)) => bindings.keys.down = key,
+            (Control::Left, Binding::Key(key)) => bindings.keys.left = key,
+            (Control::Right, Binding::Key(key)) => bindings.keys.right = key,
+            (Control::Action, Binding::Key(key)) => bindings.keys.action = key,
+            (Control::PadAction, Binding::Button(button)) => bindings.button = button,
+            _ => (),
+        }
+    }
+
+    /// The other control already using `binding`, which would clash with binding it to
+    /// `control`. Every player shares the keyboard, so keys clash across players, but each
+    /// player has their own gamepad.
+    pub fn conflict(
+        &self,
+        player: usize,
+        control: Control,
+        binding: Binding,
+    ) -> Option<(usize, Control)> {
+        let players: Vec<usize> = match binding {
+            Binding::Key(_) => (0..MAX_PLAYERS).collect(),
+            Binding::Button(_) => vec![player],
+        };
+        players.into_iter().find_map(|other| {
+            Control::ALL
+                .iter()
+                .copied()
+                .find(|&c| (other, c) != (player, control) && self.get(other, c) == binding)
+                .map(|c| (other, c))
+        })
+    }
+
+    /// Saves the bindings into the current profile's settings, leaving out any that are the
+    /// default.
+    pub fn save(&self) -> io::Result<()> {
+        let mut settings = Settings::load();
+        let defaults = Bindings::default();
+        for player in 0..MAX_PLAYERS {
+            for &control in Control::ALL.iter() {
+                let key = setting_key(player, control);
+                let binding = self.get(player, control);
+                if binding == defaults.get(player, control) {
+                    settings.remove(&key);
+                } else {
+                    settings.set(&key, binding.name());
+                }
+            }
+        }
+        settings.save()
+    }
+}
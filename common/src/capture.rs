@@ -0,0 +1,169 @@
+//! Screenshots and clips of the games, kept together in the data directory for the launcher's
+//! gallery.
+//!
+//! `App` takes a screenshot with F12 and starts or stops recording a clip with F10. Screenshots
+//! are saved as PNGs and clips as GIFs at half size, both named after the game and the time
+//! they were taken.
+
+use ggez::graphics::{self, Image};
+use ggez::{Context, GameError, GameResult};
+use image::gif;
+use image::{imageops, AnimationDecoder, Delay, DynamicImage, Frame, RgbaImage};
+
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::registry;
+
+const CAPTURES_DIR: &str = "captures";
+
+/// How often a frame is added to a clip, ten times a second.
+const CLIP_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clips stop recording after this long, as every frame is held in memory until it is saved.
+const MAX_CLIP_LENGTH: Duration = Duration::from_secs(10);
+
+/// The directory screenshots and clips are saved in, creating it if it doesn't exist yet.
+pub fn dir() -> io::Result<PathBuf> {
+    let dir = crate::data_dir()?.join(CAPTURES_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// A path for a new capture of the running game, like `snake-1580000000.png`.
+fn new_path(extension: &str) -> io::Result<PathBuf> {
+    let game = registry::running().map_or("game", |game| game.name);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(dir()?.join(format!("{}-{}.{}", game, timestamp, extension)))
+}
+
+/// The last frame drawn.
+fn grab(ctx: &mut Context) -> GameResult<RgbaImage> {
+    let screen = graphics::screenshot(ctx)?;
+    let pixels = screen.to_rgba8(ctx)?;
+    RgbaImage::from_raw(screen.width().into(), screen.height().into(), pixels)
+        .ok_or_else(|| GameError::RenderError("The screenshot was the wrong size".to_string()))
+}
+
+/// Saves the last frame drawn as a PNG, returning where it was saved.
+pub fn screenshot(ctx: &mut Context) -> GameResult<PathBuf> {
+    let path = new_path("png")?;
+    grab(ctx)?
+        .save(&path)
+        .map_err(|e| GameError::FilesystemError(e.to_string()))?;
+    Ok(path)
+}
+
+/// Whether a capture is a clip rather than a screenshot.
+pub fn is_clip(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "gif")
+}
+
+/// Whether a file is a capture, by its extension.
+pub fn is_capture(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "png" || extension == "gif")
+}
+
+fn decode(path: &Path) -> GameResult<DynamicImage> {
+    image::open(path).map_err(|e| GameError::ResourceLoadError(e.to_string()))
+}
+
+fn to_image(ctx: &mut Context, image: &DynamicImage) -> GameResult<Image> {
+    let rgba = image.to_rgba();
+    let (width, height) = rgba.dimensions();
+    Image::from_rgba8(ctx, width as u16, height as u16, &rgba.into_raw())
+}
+
+/// Loads a capture to draw, as its frames and how long each is shown for. A screenshot is a
+/// single frame. Captures are kept outside the games' resources, so `Image::new` can't find
+/// them.
+pub fn load(ctx: &mut Context, path: &Path) -> GameResult<Vec<(Image, Duration)>> {
+    if !is_clip(path) {
+        return Ok(vec![(
+            to_image(ctx, &decode(path)?)?,
+            Duration::from_secs(0),
+        )]);
+    }
+    let frames = gif::Decoder::new(File::open(path)?)
+        .and_then(|decoder| decoder.into_frames().collect_frames())
+        .map_err(|e| GameError::ResourceLoadError(e.to_string()))?;
+    frames
+        .into_iter()
+        .map(|frame| {
+            let (numerator, denominator) = frame.delay().to_numer_denom_ms();
+            let delay = Duration::from_millis(u64::from(numerator / denominator.max(1)));
+            let image = to_image(ctx, &DynamicImage::ImageRgba8(frame.into_buffer()))?;
+            Ok((image, delay))
+        })
+        .collect()
+}
+
+/// Loads a capture shrunk to fit within `width` by `height`, keeping its shape.
+pub fn load_thumbnail(
+    ctx: &mut Context,
+    path: &Path,
+    width: u32,
+    height: u32,
+) -> GameResult<Image> {
+    to_image(ctx, &decode(path)?.thumbnail(width, height))
+}
+
+/// A clip being recorded.
+pub struct Recorder {
+    frames: Vec<RgbaImage>,
+    started: Instant,
+    last_frame: Instant,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Recorder {
+            frames: Vec::new(),
+            started: Instant::now(),
+            last_frame: Instant::now(),
+        }
+    }
+
+    /// Adds the last frame drawn to the clip if one is due, returning false once the clip is as
+    /// long as it can be. Call it every update.
+    pub fn update(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        if self.started.elapsed() >= MAX_CLIP_LENGTH {
+            return Ok(false);
+        }
+        if self.frames.is_empty() || self.last_frame.elapsed() >= CLIP_FRAME_INTERVAL {
+            self.last_frame = Instant::now();
+            let frame = grab(ctx)?;
+            let (width, height) = frame.dimensions();
+            self.frames
+                .push(imageops::thumbnail(&frame, width / 2, height / 2));
+        }
+        Ok(true)
+    }
+
+    /// Saves the clip as a GIF, returning where it will be saved. Encoding takes a while, so it
+    /// is done in the background.
+    pub fn save(self) -> io::Result<PathBuf> {
+        let path = new_path("gif")?;
+        let file = File::create(&path)?;
+        let frames = self.frames;
+        thread::spawn(move || {
+            let delay = Delay::from_numer_denom_ms(CLIP_FRAME_INTERVAL.as_millis() as u32, 1);
+            let mut encoder = gif::Encoder::new(file);
+            for frame in frames {
+                if let Err(e) = encoder.encode(&Frame::from_parts(frame, 0, 0, delay)) {
+                    eprintln!("Couldn't save the clip: {}", e);
+                    return;
+                }
+            }
+        });
+        Ok(path)
+    }
+}
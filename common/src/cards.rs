@@ -0,0 +1,245 @@
+//! A standard 52 card deck and poker hand evaluation for the card games.
+
+use rand::seq::SliceRandom;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
+
+pub const SUITS: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+impl Suit {
+    pub fn symbol(self) -> char {
+        match self {
+            Suit::Clubs => '♣',
+            Suit::Diamonds => '♦',
+            Suit::Hearts => '♥',
+            Suit::Spades => '♠',
+        }
+    }
+
+    pub fn is_red(self) -> bool {
+        self == Suit::Diamonds || self == Suit::Hearts
+    }
+}
+
+/// The rank of a card, in order from ace to king.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+    Ace,
+    Two,
+    Three,
+    Four,
+    Five,
+    Six,
+    Seven,
+    Eight,
+    Nine,
+    Ten,
+    Jack,
+    Queen,
+    King,
+}
+
+pub const RANKS: [Rank; 13] = [
+    Rank::Ace,
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+];
+
+impl Rank {
+    /// The value of the rank with aces low, from 1 to 13.
+    pub fn low_value(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The value of the rank with aces high, from 2 to 14.
+    pub fn high_value(self) -> u8 {
+        if self == Rank::Ace {
+            14
+        } else {
+            self.low_value()
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Rank::Ace => "A",
+            Rank::Two => "2",
+            Rank::Three => "3",
+            Rank::Four => "4",
+            Rank::Five => "5",
+            Rank::Six => "6",
+            Rank::Seven => "7",
+            Rank::Eight => "8",
+            Rank::Nine => "9",
+            Rank::Ten => "10",
+            Rank::Jack => "J",
+            Rank::Queen => "Q",
+            Rank::King => "K",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Card {
+    pub rank: Rank,
+    pub suit: Suit,
+}
+
+impl Card {
+    pub fn new(rank: Rank, suit: Suit) -> Self {
+        Card { rank, suit }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.rank.label(), self.suit.symbol())
+    }
+}
+
+/// A pile of cards dealt from the top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    /// Creates a full deck in suit and rank order.
+    pub fn new() -> Self {
+        let cards = SUITS
+            .iter()
+            .flat_map(|&suit| RANKS.iter().map(move |&rank| Card::new(rank, suit)))
+            .collect();
+        Deck { cards }
+    }
+
+    /// Creates a full deck in a random order.
+    pub fn shuffled() -> Self {
+        let mut deck = Deck::new();
+        deck.shuffle();
+        deck
+    }
+
+    pub fn shuffle(&mut self) {
+        self.cards.shuffle(&mut rand::thread_rng());
+    }
+
+    /// Takes the top card, or `None` if the deck is empty.
+    pub fn deal(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+/// The categories of poker hand, from worst to best.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PokerHand {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    RoyalFlush,
+}
+
+impl PokerHand {
+    pub fn name(self) -> &'static str {
+        match self {
+            PokerHand::HighCard => "High Card",
+            PokerHand::Pair => "Pair",
+            PokerHand::TwoPair => "Two Pair",
+            PokerHand::ThreeOfAKind => "Three of a Kind",
+            PokerHand::Straight => "Straight",
+            PokerHand::Flush => "Flush",
+            PokerHand::FullHouse => "Full House",
+            PokerHand::FourOfAKind => "Four of a Kind",
+            PokerHand::StraightFlush => "Straight Flush",
+            PokerHand::RoyalFlush => "Royal Flush",
+        }
+    }
+}
+
+/// The value of a five card poker hand. Evaluations compare the way the hands rank.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Evaluation {
+    pub hand: PokerHand,
+    /// The aces high values that break ties, most significant first. Pairs and sets come
+    /// before the kickers, and a five high straight counts as five high.
+    pub tiebreak: Vec<u8>,
+}
+
+/// Evaluates a five card poker hand.
+pub fn evaluate(cards: &[Card; 5]) -> Evaluation {
+    // Group the values by how many times they appear, biggest groups and values first.
+    let mut counts = [0u8; 15];
+    for card in cards {
+        counts[card.rank.high_value() as usize] += 1;
+    }
+    let mut groups: Vec<(u8, u8)> = (2..15)
+        .filter(|&value| counts[value] > 0)
+        .map(|value| (counts[value], value as u8))
+        .collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    let tiebreak: Vec<u8> = groups.iter().map(|&(_, value)| value).collect();
+
+    let flush = cards.iter().all(|card| card.suit == cards[0].suit);
+    let straight_high = if groups.len() == 5 {
+        if tiebreak[0] - tiebreak[4] == 4 {
+            Some(tiebreak[0])
+        } else if tiebreak == [14, 5, 4, 3, 2] {
+            // The wheel, where the ace plays low.
+            Some(5)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let (hand, tiebreak) = match (straight_high, flush) {
+        (Some(14), true) => (PokerHand::RoyalFlush, vec![14]),
+        (Some(high), true) => (PokerHand::StraightFlush, vec![high]),
+        (Some(high), false) => (PokerHand::Straight, vec![high]),
+        (None, true) => (PokerHand::Flush, tiebreak),
+        (None, false) => {
+            let hand = match (groups[0].0, groups.get(1).map(|group| group.0)) {
+                (4, _) => PokerHand::FourOfAKind,
+                (3, Some(2)) => PokerHand::FullHouse,
+                (3, _) => PokerHand::ThreeOfAKind,
+                (2, Some(2)) => PokerHand::TwoPair,
+                (2, _) => PokerHand::Pair,
+                _ => PokerHand::HighCard,
+            };
+            (hand, tiebreak)
+        }
+    };
+    Evaluation { hand, tiebreak }
+}
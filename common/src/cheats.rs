@@ -0,0 +1,48 @@
+//! Options for getting straight to a situation in a game while developing it or reproducing a
+//! bug, rather than playing until it comes up:
+//!
+//! - `--start-length <n>`: start the snake `n` segments long.
+//! - `--invincible`: nothing the player runs into ends the game.
+//! - `--level <n>`: start on the `n`th level, or floor, counting from 1.
+//! - `--no-spawn`: nothing new appears, like food or monsters.
+//!
+//! A game reads them once into `Cheats` and keeps it with the rest of its state. They only
+//! work in debug builds, so players can't turn them on.
+
+use crate::args::Args;
+
+/// The cheats a game was started with.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Cheats {
+    pub start_length: Option<usize>,
+    pub invincible: bool,
+    /// The level to start on, counting from 1.
+    pub level: Option<usize>,
+    pub no_spawn: bool,
+}
+
+impl Cheats {
+    /// Reads the cheats from the command line in debug builds. Release builds have none.
+    pub fn requested() -> Self {
+        if cfg!(debug_assertions) {
+            Cheats::from_args(&Args::from_env())
+        } else {
+            Cheats::default()
+        }
+    }
+
+    pub fn from_args(args: &Args) -> Self {
+        Cheats {
+            start_length: args.get("start-length"),
+            invincible: args.flag("invincible"),
+            level: args.get("level").filter(|&level| level > 0),
+            no_spawn: args.flag("no-spawn"),
+        }
+    }
+
+    /// The index of the level to start on, out of `count`.
+    pub fn level_index(&self, count: usize) -> usize {
+        self.level
+            .map_or(0, |level| (level - 1).min(count.saturating_sub(1)))
+    }
+}
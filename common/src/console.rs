@@ -0,0 +1,267 @@
+//! The developer console, which drops down over a game with the backtick key to run commands
+//! against it while testing, like `set speed 12` or `grow 10`.
+//!
+//! A game hands `App::with_console` the `Commands` it understands. Each command is given the
+//! game's state and the words typed after its name, and returns a line to print or an error.
+//! `help` and `clear` are always there. The game is paused while the console is open, the same
+//! as on the pause screen.
+//!
+//! The console is only there in debug builds, or when the game is started with `--console`.
+
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use ggez::event::KeyCode;
+use ggez::graphics::{self, DrawMode, Image, Rect};
+use ggez::{Context, GameResult};
+
+use crate::args;
+use crate::ui;
+
+/// How much of the screen the console covers, from the top.
+const HEIGHT: f32 = 0.4;
+const PADDING: f32 = 10.0;
+const LINE_HEIGHT: f32 = 20.0;
+const TEXT_SIZE: f32 = 16.0;
+/// How many lines of output are kept.
+const LOG_LENGTH: usize = 100;
+
+const PANEL_COLOR: [f32; 4] = [0.05, 0.05, 0.08, 0.9];
+const INPUT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const OUTPUT_COLOR: [f32; 4] = [0.75, 0.8, 0.85, 1.0];
+
+/// Whether the console can be opened in this run.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) || args::flag("console")
+}
+
+/// Whether a key opens or closes the console.
+pub(crate) fn is_toggle_key(key: KeyCode) -> bool {
+    key == KeyCode::Grave
+}
+
+/// Parses the `index`th word given to a command as a `T`, naming it `name` if it's missing or
+/// doesn't parse.
+pub fn arg<T: FromStr>(args: &[&str], index: usize, name: &str) -> Result<T, String> {
+    let word = args.get(index).ok_or_else(|| format!("missing {}", name))?;
+    word.parse()
+        .map_err(|_| format!("`{}` isn't a valid {}", word, name))
+}
+
+type Run<S> = Box<dyn Fn(&mut S, &[&str]) -> Result<String, String>>;
+
+struct Command<S> {
+    name: &'static str,
+    /// The words the command takes, like `<x> <y>`.
+    usage: &'static str,
+    help: &'static str,
+    run: Run<S>,
+}
+
+impl<S> Command<S> {
+    /// How the command is typed, like `spawn food <x> <y>`.
+    fn syntax(&self) -> String {
+        format!("{} {}", self.name, self.usage)
+            .trim_end()
+            .to_string()
+    }
+}
+
+/// The commands a game understands.
+pub struct Commands<S> {
+    commands: Vec<Command<S>>,
+}
+
+impl<S> Default for Commands<S> {
+    fn default() -> Self {
+        Commands {
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl<S> Commands<S> {
+    pub fn new() -> Self {
+        Commands::default()
+    }
+
+    /// Adds a command, which is given the words typed after its name.
+    pub fn add<F>(
+        mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        run: F,
+    ) -> Self
+    where
+        F: Fn(&mut S, &[&str]) -> Result<String, String> + 'static,
+    {
+        self.commands.push(Command {
+            name,
+            usage,
+            help,
+            run: Box::new(run),
+        });
+        self
+    }
+
+    /// Runs a line typed into the console, returning what to print.
+    fn run(&self, game: &mut S, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (name, args) = match words.split_first() {
+            Some((name, args)) => (*name, args),
+            None => return Ok(String::new()),
+        };
+        if name == "help" {
+            let mut lines = vec![
+                "help: list the commands".to_string(),
+                "clear: empty the console".to_string(),
+            ];
+            for command in &self.commands {
+                lines.push(format!("{}: {}", command.syntax(), command.help));
+            }
+            return Ok(lines.join("\n"));
+        }
+        let command = self
+            .commands
+            .iter()
+            .find(|command| command.name == name)
+            .ok_or_else(|| format!("unknown command `{}`, try `help`", name))?;
+        (command.run)(game, args).map_err(|e| format!("{}\nusage: {}", e, command.syntax()))
+    }
+}
+
+/// The console's state while a game runs.
+pub(crate) struct Console<S> {
+    commands: Commands<S>,
+    pub open: bool,
+    input: String,
+    log: VecDeque<String>,
+    /// The lines run, oldest first, for going back through with the arrow keys.
+    history: Vec<String>,
+    /// Which line of the history is in the input, while going back through it.
+    recalled: Option<usize>,
+    /// The game as it was last drawn, shown behind the console.
+    pub backdrop: Option<Image>,
+    /// Whether the backdrop should be drawn again, after a command has changed the game.
+    pub stale: bool,
+}
+
+impl<S> Console<S> {
+    pub fn new(commands: Commands<S>) -> Self {
+        Console {
+            commands,
+            open: false,
+            input: String::new(),
+            log: VecDeque::new(),
+            history: Vec::new(),
+            recalled: None,
+            backdrop: None,
+            stale: false,
+        }
+    }
+
+    fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            self.log.push_back(line.to_string());
+        }
+        while self.log.len() > LOG_LENGTH {
+            self.log.pop_front();
+        }
+    }
+
+    /// Handles a key while the console is open.
+    pub fn key_down(&mut self, game: &mut S, key: KeyCode) {
+        match key {
+            KeyCode::Escape => self.open = false,
+            KeyCode::Back => {
+                self.input.pop();
+            }
+            KeyCode::Up if !self.history.is_empty() => {
+                let index = self
+                    .recalled
+                    .map_or(self.history.len() - 1, |index| index.saturating_sub(1));
+                self.recalled = Some(index);
+                self.input = self.history[index].clone();
+            }
+            KeyCode::Down => {
+                self.recalled = self
+                    .recalled
+                    .map(|index| index + 1)
+                    .filter(|&index| index < self.history.len());
+                self.input = self
+                    .recalled
+                    .map_or(String::new(), |index| self.history[index].clone());
+            }
+            KeyCode::Return | KeyCode::NumpadEnter => {
+                let line = std::mem::take(&mut self.input);
+                self.recalled = None;
+                if line.trim().is_empty() {
+                    return;
+                }
+                self.print(&format!("> {}", line));
+                if line.trim() == "clear" {
+                    self.log.clear();
+                } else {
+                    match self.commands.run(game, &line) {
+                        Ok(output) => self.print(&output),
+                        Err(e) => self.print(&format!("error: {}", e)),
+                    }
+                    self.stale = true;
+                }
+                self.history.push(line);
+            }
+            _ => (),
+        }
+    }
+
+    /// Types a character into the console.
+    pub fn text_input(&mut self, character: char) {
+        if !character.is_control() && character != '`' {
+            self.input.push(character);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let screen = graphics::screen_coordinates(ctx);
+        if let Some(backdrop) = &self.backdrop {
+            let scale = ggez::mint::Vector2 {
+                x: screen.w / backdrop.width() as f32,
+                y: screen.h / backdrop.height() as f32,
+            };
+            graphics::draw(
+                ctx,
+                backdrop,
+                graphics::DrawParam::new()
+                    .dest(ggez::mint::Point2 {
+                        x: screen.x,
+                        y: screen.y,
+                    })
+                    .scale(scale),
+            )?;
+        }
+
+        let panel = Rect::new(screen.x, screen.y, screen.w, screen.h * HEIGHT);
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel, PANEL_COLOR.into())?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let x = panel.x + PADDING;
+        let mut y = panel.bottom() - PADDING - LINE_HEIGHT;
+        ui::draw_text(
+            ctx,
+            &format!("> {}_", self.input),
+            INPUT_COLOR,
+            TEXT_SIZE,
+            x,
+            y,
+        )?;
+        for line in self.log.iter().rev() {
+            y -= LINE_HEIGHT;
+            if y < panel.y + PADDING {
+                break;
+            }
+            ui::draw_text(ctx, line, OUTPUT_COLOR, TEXT_SIZE, x, y)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+//! Crash reports, so crashes that are hard to make happen again can still be looked into.
+//!
+//! `install` adds to the panic hook, which still prints the panic as usual, then writes a
+//! report into the data directory's `crashes` folder: the panic's message and where it
+//! happened, the backtrace, the game, its version and the platform, and what was going on at
+//! the time. That's the arguments the game was started with, which include any seed and mode,
+//! the score and mode from the title, and whatever the game last described with `set_state`.
+//! Setting `crash_reports` to `off` stops them being written.
+//!
+//! Nothing is sent by itself. The launcher shows any reports it finds, exactly as they'd be
+//! sent, and each one is only sent if the player chooses to, with `POST` to the URL in the
+//! `crash_url` setting. That's plain HTTP like sync, so the URL has to be on this computer
+//! unless `allow_plain_http` is set. The backtrace can have the paths the games were built in,
+//! and the arguments the paths of any files they were given, which is why it's worth reading
+//! first.
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::PathBuf;
+use std::sync::Once;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::settings::Settings;
+use crate::sync::Server;
+use crate::title;
+
+/// The setting turning crash reports off.
+pub const ENABLED_SETTING: &str = "crash_reports";
+/// The setting holding the URL crash reports are sent to.
+pub const URL_SETTING: &str = "crash_url";
+
+const REPORTS_DIR: &str = "crashes";
+const REPORT_EXTENSION: &str = "txt";
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// What the game last said it was doing.
+    static STATE: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Adds writing a crash report to the panic hook. Only the first call does anything, and `App`
+/// makes it when the game is created.
+pub fn install() {
+    INSTALL.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            previous(info);
+            if !enabled() {
+                return;
+            }
+            let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = info.payload().downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "(no message)".to_string()
+            };
+            let location = info.location().map_or("unknown".to_string(), |location| {
+                format!("{}:{}", location.file(), location.line())
+            });
+            match write(&report(&message, &location)) {
+                Ok(path) => eprintln!(
+                    "A crash report was saved to {}. The launcher can send it.",
+                    path.display()
+                ),
+                Err(e) => eprintln!("Couldn't save a crash report: {}", e),
+            }
+        }));
+    });
+}
+
+/// Whether crashes write reports, which they do unless the profile has turned it off.
+pub fn enabled() -> bool {
+    Settings::load().get(ENABLED_SETTING) != Some("off")
+}
+
+/// Describes what the game is doing, like the level and what's in it, for any crash report
+/// written before it's described again.
+pub fn set_state(state: impl Into<String>) {
+    let state = state.into();
+    STATE.with(|cell| {
+        if let Ok(mut current) = cell.try_borrow_mut() {
+            *current = Some(state);
+        }
+    });
+}
+
+/// The URL crash reports are sent to, if there is one.
+pub fn url() -> Option<String> {
+    Settings::load()
+        .get(URL_SETTING)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+/// Returns the directory crash reports are kept in, creating it if it doesn't exist yet.
+pub fn dir() -> io::Result<PathBuf> {
+    let dir = crate::data_dir()?.join(REPORTS_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The name of the program that crashed, which is the game's unless it was the launcher.
+fn program() -> String {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The report for a panic with `message` at `location`, as it's saved and sent.
+fn report(message: &str, location: &str) -> String {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (score, mode) = title::status();
+    // The game may have panicked while describing itself.
+    let state = STATE
+        .try_with(|cell| cell.try_borrow().ok().and_then(|state| state.clone()))
+        .ok()
+        .flatten();
+
+    let mut report = String::new();
+    report.push_str(&format!("game: {}\n", program()));
+    report.push_str(&format!("version: {}\n", env!("CARGO_PKG_VERSION")));
+    report.push_str(&format!(
+        "platform: {} {}\n",
+        env::consts::OS,
+        env::consts::ARCH
+    ));
+    report.push_str(&format!("time: {}\n", now()));
+    report.push_str(&format!(
+        "thread: {}\n",
+        thread::current().name().unwrap_or("unnamed")
+    ));
+    report.push_str(&format!("panic: {}\n", message));
+    report.push_str(&format!("at: {}\n", location));
+    report.push_str(&format!("arguments: {}\n", args.join(" ")));
+    report.push_str(&format!("score: {}\n", score.as_deref().unwrap_or("none")));
+    report.push_str(&format!("mode: {}\n", mode.as_deref().unwrap_or("none")));
+    report.push_str(&format!(
+        "\nstate:\n{}\n",
+        state.as_deref().unwrap_or("(not described)")
+    ));
+    report.push_str(&format!("\nbacktrace:\n{}\n", Backtrace::force_capture()));
+    report
+}
+
+fn write(report: &str) -> io::Result<PathBuf> {
+    // Named starting with when it was written, so they sort oldest first.
+    let path = dir()?.join(format!(
+        "{}-{}-{}.{}",
+        now(),
+        program(),
+        std::process::id(),
+        REPORT_EXTENSION
+    ));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// A crash report that hasn't been sent or thrown away yet.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub path: PathBuf,
+    /// The report, exactly as it would be sent.
+    pub contents: String,
+}
+
+impl Report {
+    /// Sends the report to the URL in the settings, then deletes it.
+    pub fn send(&self) -> Result<(), String> {
+        let url = url().ok_or_else(|| format!("there's no {} set", URL_SETTING))?;
+        Server::parse(&url)?.post(self.contents.as_bytes())?;
+        self.discard()
+            .map_err(|e| format!("sent, but couldn't delete the report: {}", e))
+    }
+
+    /// Deletes the report without sending it.
+    pub fn discard(&self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+}
+
+/// The reports that haven't been sent or thrown away, oldest first.
+pub fn pending() -> io::Result<Vec<Report>> {
+    let mut reports = Vec::new();
+    for entry in fs::read_dir(dir()?)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .map_or(false, |ext| ext == REPORT_EXTENSION)
+        {
+            let contents = fs::read_to_string(&path)?;
+            reports.push(Report { path, contents });
+        }
+    }
+    reports.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(reports)
+}
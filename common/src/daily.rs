@@ -0,0 +1,313 @@
+//! The daily challenge: each day the launcher features one game, played with a seed and
+//! modifiers fixed for the day so every profile plays the same run.
+//!
+//! The launcher starts the featured game with `--daily <day>`. A game that can be featured
+//! looks for it with `daily::requested`, draws its random numbers from `daily::rng` and applies
+//! whichever of the challenge's modifiers it knows.
+//!
+//! Daily runs have a leaderboard of their own, kept apart from the stats store since the
+//! modifiers make them incomparable with ordinary runs. They are written to the shared data
+//! directory, one per line of tab separated fields: `day profile game score`.
+//!
+//! Each run is also submitted to the online leaderboard, if the profile has one set up. See
+//! `leaderboard`.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::args;
+use crate::leaderboard::{self, Submission};
+use crate::migrate::{self, DAILY};
+use crate::profile;
+use crate::registry::{self, ScoreKey};
+
+pub(crate) const DAILY_FILE: &str = "daily.tsv";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Spreads consecutive days out into unrelated seeds.
+const SEED_MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+/// `SEED_MULTIPLIER`'s inverse, modulo 2^64, which turns a seed back into its day.
+const SEED_INVERSE: u64 = 0xf1de_83e1_9937_733d;
+
+/// A game and the modifiers it is played with for a day.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Challenge {
+    /// The name of the featured game.
+    pub game: &'static str,
+    /// The modifiers, as the launcher describes them.
+    pub title: &'static str,
+    /// The modifiers, as the game looks for them with `Daily::has`.
+    pub modifiers: &'static [&'static str],
+    /// What the day's runs are ranked by.
+    pub score: ScoreKey,
+}
+
+const SNAKE_LENGTH: ScoreKey = ScoreKey {
+    key: "length",
+    label: "Length",
+    lower_is_better: false,
+};
+
+const COPTER_DISTANCE: ScoreKey = ScoreKey {
+    key: "distance",
+    label: "Distance (m)",
+    lower_is_better: false,
+};
+
+/// The challenges, featured one a day in turn.
+pub const CHALLENGES: &[Challenge] = &[
+    Challenge {
+        game: "snake",
+        title: "double speed, no wrap",
+        modifiers: &["double_speed", "no_wrap"],
+        score: SNAKE_LENGTH,
+    },
+    Challenge {
+        game: "copter",
+        title: "narrow cave",
+        modifiers: &["narrow"],
+        score: COPTER_DISTANCE,
+    },
+    Challenge {
+        game: "snake",
+        title: "no wrap",
+        modifiers: &["no_wrap"],
+        score: SNAKE_LENGTH,
+    },
+    Challenge {
+        game: "copter",
+        title: "full speed from the start",
+        modifiers: &["top_speed"],
+        score: COPTER_DISTANCE,
+    },
+    Challenge {
+        game: "snake",
+        title: "double speed",
+        modifiers: &["double_speed"],
+        score: SNAKE_LENGTH,
+    },
+];
+
+/// The number of days since the unix epoch, in UTC.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECONDS_PER_DAY)
+        .unwrap_or(0)
+}
+
+/// A day's challenge.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Daily {
+    pub day: u64,
+    pub challenge: &'static Challenge,
+}
+
+impl Daily {
+    pub fn for_day(day: u64) -> Self {
+        Daily {
+            day,
+            challenge: &CHALLENGES[(day % CHALLENGES.len() as u64) as usize],
+        }
+    }
+
+    pub fn today() -> Self {
+        Daily::for_day(today())
+    }
+
+    /// The challenge played with `seed`, if it's the seed of a day up to tomorrow, for checking
+    /// runs submitted with only their seed.
+    pub fn for_seed(seed: u64) -> Option<Self> {
+        let day = seed.wrapping_mul(SEED_INVERSE);
+        // Tomorrow's is allowed for players ahead of UTC.
+        if day > today() + 1 {
+            return None;
+        }
+        Some(Daily::for_day(day))
+    }
+
+    /// The game and its modifiers, like "Snake, double speed, no wrap".
+    pub fn title(&self) -> String {
+        let game =
+            registry::find(self.challenge.game).map_or(self.challenge.game, |game| game.title);
+        format!("{}, {}", game, self.challenge.title)
+    }
+
+    pub fn has(&self, modifier: &str) -> bool {
+        self.challenge.modifiers.contains(&modifier)
+    }
+
+    /// The seed every profile's run of the challenge is played with.
+    pub fn seed(&self) -> u64 {
+        self.day.wrapping_mul(SEED_MULTIPLIER)
+    }
+
+    /// The arguments to start the featured game with.
+    pub fn args(&self) -> Vec<String> {
+        vec!["--daily".to_string(), self.day.to_string()]
+    }
+
+    /// Records a run of the challenge for the current profile, and submits it to the online
+    /// leaderboard if there is one, with the run's replay if the game keeps one.
+    pub fn record(&self, score: f64, replay: Option<&str>) -> io::Result<()> {
+        let profile = profile::current();
+        leaderboard::submit_in_background(
+            Submission {
+                game: self.challenge.game.to_string(),
+                mode: "daily".to_string(),
+                player: profile.clone(),
+                seed: self.seed(),
+                score,
+                replay: replay.map(|replay| leaderboard::replay_hash(replay.as_bytes())),
+            },
+            replay.map(str::to_string),
+        );
+        let line = format!(
+            "{}\t{}\t{}\t{}",
+            self.day, profile, self.challenge.game, score
+        );
+        migrate::append(&crate::data_dir()?.join(DAILY_FILE), &DAILY, &line)
+    }
+
+    /// Each profile's best run of the challenge, best first.
+    pub fn leaderboard(&self) -> io::Result<Vec<Run>> {
+        let key = self.challenge.score;
+        let mut best: Vec<Run> = Vec::new();
+        for run in runs()? {
+            if run.day != self.day || run.game != self.challenge.game {
+                continue;
+            }
+            match best.iter_mut().find(|other| other.profile == run.profile) {
+                Some(other) if key.beats(run.score, other.score) => *other = run,
+                Some(_) => (),
+                None => best.push(run),
+            }
+        }
+        best.sort_by(|a, b| {
+            let order = a
+                .score
+                .partial_cmp(&b.score)
+                .unwrap_or(std::cmp::Ordering::Equal);
+            if key.lower_is_better {
+                order
+            } else {
+                order.reverse()
+            }
+        });
+        Ok(best)
+    }
+
+    /// The current profile's place on the leaderboard, counting from 1, and how many profiles
+    /// are on it.
+    pub fn rank(&self) -> Option<(usize, usize)> {
+        let board = self.leaderboard().ok()?;
+        let profile = profile::current();
+        let rank = board.iter().position(|run| run.profile == profile)?;
+        Some((rank + 1, board.len()))
+    }
+}
+
+/// A recorded run of a daily challenge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Run {
+    pub day: u64,
+    pub profile: String,
+    pub game: String,
+    pub score: f64,
+}
+
+impl Run {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        Some(Run {
+            day: fields.next()?.parse().ok()?,
+            profile: fields.next()?.to_string(),
+            game: fields.next()?.to_string(),
+            score: fields.next()?.parse().ok()?,
+        })
+    }
+}
+
+/// Every recorded daily run, oldest first.
+fn runs() -> io::Result<Vec<Run>> {
+    match migrate::read(&crate::data_dir()?.join(DAILY_FILE), &DAILY) {
+        Ok(contents) => Ok(contents.lines().filter_map(Run::parse).collect()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// How many days in a row the current profile has played the daily challenge, counting
+/// today's if it has been played and otherwise up to yesterday's.
+pub fn streak() -> io::Result<u32> {
+    let profile = profile::current();
+    let days: Vec<u64> = runs()?
+        .into_iter()
+        .filter(|run| run.profile == profile)
+        .map(|run| run.day)
+        .collect();
+
+    let mut day = today();
+    if !days.contains(&day) {
+        day = day.saturating_sub(1);
+    }
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        if day == 0 {
+            break;
+        }
+        day -= 1;
+    }
+    Ok(streak)
+}
+
+/// The challenge given by the `--daily` argument, if it features `game`.
+pub fn requested(game: &str) -> Option<Daily> {
+    let daily = Daily::for_day(args::get("daily")?);
+    if daily.challenge.game == game {
+        Some(daily)
+    } else {
+        None
+    }
+}
+
+/// The seed for a run: the day's for a daily challenge, so every profile gets the same run, or
+/// else the one given by the `--seed` argument, if any.
+pub fn seed(daily: Option<&Daily>) -> Option<u64> {
+    daily.map(Daily::seed).or_else(|| args::get("seed"))
+}
+
+/// The random numbers for a run, from its seed if it has one and otherwise fresh.
+pub fn rng(daily: Option<&Daily>) -> StdRng {
+    match seed(daily) {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_turned_back_into_their_day() {
+        assert_eq!(SEED_MULTIPLIER.wrapping_mul(SEED_INVERSE), 1);
+        for day in &[0, 1, 19_000, today()] {
+            assert_eq!(
+                Daily::for_seed(Daily::for_day(*day).seed()).unwrap().day,
+                *day
+            );
+        }
+    }
+
+    #[test]
+    fn other_seeds_arent_a_days() {
+        assert_eq!(Daily::for_seed(42), None);
+        assert_eq!(Daily::for_seed(Daily::for_day(today() + 30).seed()), None);
+    }
+}
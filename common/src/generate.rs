@@ -0,0 +1,320 @@
+//! Procedural level layouts, shared by the games that build their levels from a seed.
+//!
+//! A `LevelGenerator` lays out walls and open ground on a grid of the size asked for. How hard
+//! the layout is goes from `0.0`, the easiest, to `1.0`, the hardest, and each generator reads
+//! it in its own way. The same seed and parameters always give the same layout.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::grid::Grid;
+
+/// The names of the built in generators, for `by_name`.
+pub const NAMES: &[&str] = &["scatter", "maze", "rooms"];
+
+/// A cell of a layout.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Cell {
+    Open,
+    Wall,
+}
+
+/// A rectangular room.
+#[derive(Debug, Copy, Clone)]
+pub struct Room {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+}
+
+impl Room {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+
+    /// Returns whether this room overlaps `other`, keeping at least one tile of wall between them.
+    fn intersects(&self, other: &Room) -> bool {
+        self.x <= other.x + other.w
+            && self.x + self.w >= other.x
+            && self.y <= other.y + other.h
+            && self.y + self.h >= other.y
+    }
+
+    /// Returns a random floor position inside the room.
+    pub fn random_pos(&self, rng: &mut impl Rng) -> (i32, i32) {
+        (
+            rng.gen_range(self.x, self.x + self.w),
+            rng.gen_range(self.y, self.y + self.h),
+        )
+    }
+}
+
+/// A generated layout.
+pub struct Layout {
+    pub cells: Grid<Cell>,
+    /// The rooms, for generators that carve them, in the order they were carved.
+    pub rooms: Vec<Room>,
+}
+
+/// What a layout is generated for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Params {
+    pub width: usize,
+    pub height: usize,
+    /// From `0.0` for the easiest to `1.0` for the hardest.
+    pub difficulty: f32,
+}
+
+/// Something that lays out levels.
+pub trait LevelGenerator {
+    /// Generates a layout with random numbers from `rng`, for games that keep one going.
+    fn generate_with(&self, params: &Params, rng: &mut StdRng) -> Layout;
+
+    /// Generates the layout for a seed.
+    fn generate(&self, params: &Params, seed: u64) -> Layout {
+        self.generate_with(params, &mut StdRng::seed_from_u64(seed))
+    }
+}
+
+/// A built in generator by its name in `NAMES`, with its default settings.
+pub fn by_name(name: &str) -> Option<Box<dyn LevelGenerator>> {
+    match name {
+        "scatter" => Some(Box::new(Scatter)),
+        "maze" => Some(Box::new(SymmetricMaze)),
+        "rooms" => Some(Box::new(RoomsAndCorridors::default())),
+        _ => None,
+    }
+}
+
+/// Single walls scattered over open ground, more of them the harder it is. Any open ground the
+/// walls cut off is filled in, so all of it can be reached.
+pub struct Scatter;
+
+/// The share of the cells that are walls, at the easiest and the hardest.
+const SCATTER_DENSITY: (f32, f32) = (0.02, 0.15);
+
+impl LevelGenerator for Scatter {
+    fn generate_with(&self, params: &Params, rng: &mut StdRng) -> Layout {
+        let mut cells = Grid::new(params.width, params.height, Cell::Open);
+        let density = lerp(SCATTER_DENSITY, params.difficulty);
+        let walls = (density * (params.width * params.height) as f32) as usize;
+        for _ in 0..walls {
+            let x = rng.gen_range(0, params.width as i32);
+            let y = rng.gen_range(0, params.height as i32);
+            cells.set(x, y, Cell::Wall);
+        }
+        fill_unreachable(&mut cells);
+        Layout {
+            cells,
+            rooms: Vec::new(),
+        }
+    }
+}
+
+/// A maze mirrored left to right, with corridors a cell wide. The easier it is, the more walls
+/// are knocked through to make loops.
+pub struct SymmetricMaze;
+
+/// The share of the walls left between corridors that are knocked through, at the easiest and
+/// the hardest.
+const MAZE_LOOPS: (f32, f32) = (0.5, 0.05);
+
+impl LevelGenerator for SymmetricMaze {
+    fn generate_with(&self, params: &Params, rng: &mut StdRng) -> Layout {
+        let (width, height) = (params.width as i32, params.height as i32);
+        let mut cells = Grid::new(params.width, params.height, Cell::Wall);
+        // Corridors run through the odd cells of the left half, and the middle column if the
+        // width is odd, then the half is mirrored.
+        let half = (width + 1) / 2;
+        let is_room = |x: i32, y: i32| {
+            x % 2 == 1 && y % 2 == 1 && x < half && x < width - 1 && y < height - 1
+        };
+
+        if is_room(1, 1) {
+            cells.set(1, 1, Cell::Open);
+            let mut stack = vec![(1, 1)];
+            while let Some(&(x, y)) = stack.last() {
+                let unvisited: Vec<(i32, i32)> = [(2, 0), (-2, 0), (0, 2), (0, -2)]
+                    .iter()
+                    .map(|(dx, dy)| (x + dx, y + dy))
+                    .filter(|&(nx, ny)| is_room(nx, ny) && cells.get(nx, ny) == Some(&Cell::Wall))
+                    .collect();
+                if unvisited.is_empty() {
+                    stack.pop();
+                    continue;
+                }
+                let (nx, ny) = unvisited[rng.gen_range(0, unvisited.len())];
+                cells.set((x + nx) / 2, (y + ny) / 2, Cell::Open);
+                cells.set(nx, ny, Cell::Open);
+                stack.push((nx, ny));
+            }
+        }
+
+        let loops = lerp(MAZE_LOOPS, params.difficulty);
+        for y in 1..height - 1 {
+            for x in 1..half.min(width - 1) {
+                let between_rows =
+                    x % 2 == 1 && y % 2 == 0 && is_room(x, y - 1) && is_room(x, y + 1);
+                let between_columns =
+                    x % 2 == 0 && y % 2 == 1 && is_room(x - 1, y) && is_room(x + 1, y);
+                if (between_rows || between_columns) && rng.gen::<f32>() < loops {
+                    cells.set(x, y, Cell::Open);
+                }
+            }
+        }
+
+        for y in 0..height {
+            for x in half..width {
+                let mirrored = *cells.get(width - 1 - x, y).unwrap_or(&Cell::Wall);
+                cells.set(x, y, mirrored);
+            }
+        }
+        // Unless the middle column has corridors of its own, the halves are walled off from
+        // each other, so join them through some of the corridors that reach the middle.
+        if let Some(last) = (1..half.min(width - 1)).rev().find(|x| x % 2 == 1) {
+            let rows: Vec<i32> = (1..height - 1).step_by(2).collect();
+            let mut joins: Vec<i32> = rows.iter().copied().filter(|_| rng.gen()).collect();
+            if joins.is_empty() && !rows.is_empty() {
+                joins.push(rows[rng.gen_range(0, rows.len())]);
+            }
+            for y in joins {
+                for x in last + 1..width - 1 - last {
+                    cells.set(x, y, Cell::Open);
+                }
+            }
+        }
+        fill_unreachable(&mut cells);
+        Layout {
+            cells,
+            rooms: Vec::new(),
+        }
+    }
+}
+
+/// Rooms joined by L-shaped corridors, each to the one carved before it. The harder it is, the
+/// smaller the rooms.
+pub struct RoomsAndCorridors {
+    /// How many rooms to try for.
+    pub max_rooms: usize,
+    pub min_size: i32,
+    /// The size rooms can grow to at the easiest.
+    pub max_size: i32,
+}
+
+impl Default for RoomsAndCorridors {
+    fn default() -> Self {
+        RoomsAndCorridors {
+            max_rooms: 12,
+            min_size: 4,
+            max_size: 10,
+        }
+    }
+}
+
+impl LevelGenerator for RoomsAndCorridors {
+    fn generate_with(&self, params: &Params, rng: &mut StdRng) -> Layout {
+        let mut cells = Grid::new(params.width, params.height, Cell::Wall);
+        let mut rooms: Vec<Room> = Vec::new();
+        let shrink = ((self.max_size - self.min_size) as f32 * params.difficulty / 2.0) as i32;
+        let max_size = (self.max_size - shrink).max(self.min_size);
+
+        for _ in 0..self.max_rooms * 4 {
+            if rooms.len() >= self.max_rooms {
+                break;
+            }
+
+            let w = rng.gen_range(self.min_size, max_size + 1);
+            let h = rng.gen_range(self.min_size, max_size + 1);
+            if w + 2 >= params.width as i32 || h + 2 >= params.height as i32 {
+                continue;
+            }
+            let room = Room {
+                x: rng.gen_range(1, params.width as i32 - w - 1),
+                y: rng.gen_range(1, params.height as i32 - h - 1),
+                w,
+                h,
+            };
+
+            if rooms.iter().any(|other| room.intersects(other)) {
+                continue;
+            }
+
+            for y in room.y..room.y + room.h {
+                for x in room.x..room.x + room.w {
+                    cells.set(x, y, Cell::Open);
+                }
+            }
+
+            if let Some(prev) = rooms.last() {
+                let (px, py) = prev.center();
+                let (cx, cy) = room.center();
+                if rng.gen() {
+                    carve_h(&mut cells, px, cx, py);
+                    carve_v(&mut cells, py, cy, cx);
+                } else {
+                    carve_v(&mut cells, py, cy, px);
+                    carve_h(&mut cells, px, cx, cy);
+                }
+            }
+
+            rooms.push(room);
+        }
+
+        Layout { cells, rooms }
+    }
+}
+
+fn carve_h(cells: &mut Grid<Cell>, x1: i32, x2: i32, y: i32) {
+    for x in x1.min(x2)..=x1.max(x2) {
+        cells.set(x, y, Cell::Open);
+    }
+}
+
+fn carve_v(cells: &mut Grid<Cell>, y1: i32, y2: i32, x: i32) {
+    for y in y1.min(y2)..=y1.max(y2) {
+        cells.set(x, y, Cell::Open);
+    }
+}
+
+/// The value a `difficulty` of the way from the easiest to the hardest.
+fn lerp((easiest, hardest): (f32, f32), difficulty: f32) -> f32 {
+    let difficulty = difficulty.clamp(0.0, 1.0);
+    easiest + (hardest - easiest) * difficulty
+}
+
+/// Walls in all but the biggest stretch of open ground.
+fn fill_unreachable(cells: &mut Grid<Cell>) {
+    let mut reached = Grid::new(cells.width(), cells.height(), false);
+    let mut biggest = Vec::new();
+    let open: Vec<(i32, i32)> = cells
+        .iter()
+        .filter(|(_, _, cell)| **cell == Cell::Open)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    for (x, y) in open {
+        if reached.get(x, y) == Some(&true) {
+            continue;
+        }
+        let region = cells.flood_fill(x, y, |cell| *cell == Cell::Open);
+        for &(rx, ry) in &region {
+            reached.set(rx, ry, true);
+        }
+        if region.len() > biggest.len() {
+            biggest = region;
+        }
+    }
+
+    let mut keep = Grid::new(cells.width(), cells.height(), false);
+    for (x, y) in biggest {
+        keep.set(x, y, true);
+    }
+    let cut_off: Vec<(i32, i32)> = cells
+        .iter()
+        .filter(|(x, y, cell)| **cell == Cell::Open && keep.get(*x, *y) == Some(&false))
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    for (x, y) in cut_off {
+        cells.set(x, y, Cell::Wall);
+    }
+}
@@ -0,0 +1,130 @@
+//! A fixed size two dimensional grid.
+
+/// A fixed size grid of cells stored in row-major order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    /// Creates a new grid with every cell set to `fill`.
+    pub fn new(width: usize, height: usize, fill: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Sets every cell to `value`.
+    pub fn fill(&mut self, value: T) {
+        for cell in self.cells.iter_mut() {
+            *cell = value.clone();
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid from its rows. Returns `None` if the rows aren't all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if rows.iter().any(|row| row.len() != width) {
+            return None;
+        }
+
+        Some(Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns whether `(x, y)` is inside the grid.
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if self.in_bounds(x, y) {
+            Some(y as usize * self.width + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the cell at `(x, y)`, or `None` if it is out of bounds.
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.index(x, y).map(move |i| &self.cells[i])
+    }
+
+    /// Returns the cell at `(x, y)` mutably, or `None` if it is out of bounds.
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        match self.index(x, y) {
+            Some(i) => Some(&mut self.cells[i]),
+            None => None,
+        }
+    }
+
+    /// Sets the cell at `(x, y)`. Out of bounds positions are ignored.
+    pub fn set(&mut self, x: i32, y: i32, value: T) {
+        if let Some(cell) = self.get_mut(x, y) {
+            *cell = value;
+        }
+    }
+
+    /// Iterates over every cell along with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32, &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width) as i32, (i / width) as i32, cell))
+    }
+
+    /// Returns the in bounds orthogonal neighbours of `(x, y)`.
+    pub fn neighbours(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .map(move |(dx, dy)| (x + dx, y + dy))
+            .filter(move |(x, y)| self.in_bounds(*x, *y))
+    }
+
+    /// Returns every position connected to `(x, y)` through orthogonal neighbours whose cells
+    /// match `predicate`, including `(x, y)` itself. Returns nothing if the starting cell doesn't
+    /// match.
+    pub fn flood_fill<F>(&self, x: i32, y: i32, predicate: F) -> Vec<(i32, i32)>
+    where
+        F: Fn(&T) -> bool,
+    {
+        let mut region = Vec::new();
+        if !self.get(x, y).map_or(false, &predicate) {
+            return region;
+        }
+
+        let mut visited = Grid::new(self.width, self.height, false);
+        visited.set(x, y, true);
+        let mut stack = vec![(x, y)];
+        while let Some((x, y)) = stack.pop() {
+            region.push((x, y));
+            for (nx, ny) in self.neighbours(x, y) {
+                if visited.get(nx, ny) == Some(&false) && self.get(nx, ny).map_or(false, &predicate)
+                {
+                    visited.set(nx, ny, true);
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        region
+    }
+}
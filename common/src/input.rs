@@ -0,0 +1,397 @@
+//! Local multiplayer input, where each player uses their own keys on the shared keyboard or a
+//! gamepad of their own.
+//!
+//! Every player slot has a keyboard scheme, which can be changed from its default in `bindings`.
+//! Gamepads join the first time they are used and drive the slot matching the order they joined
+//! in, alongside that slot's keys. A gamepad that is unplugged keeps its slot, and the next new
+//! gamepad to be used takes it over if the old one doesn't come back first.
+
+use ggez::event::{Axis, Button, KeyCode};
+use ggez::input::gamepad::{self, GamepadId};
+use ggez::input::keyboard;
+use ggez::Context;
+
+use std::collections::HashMap;
+
+use crate::bindings::Bindings;
+
+/// The most players the keyboard schemes can cover.
+pub const MAX_PLAYERS: usize = 4;
+
+/// Stick movement below this is ignored.
+const DEADZONE: f32 = 0.25;
+
+/// The keys one player uses on the shared keyboard.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyScheme {
+    pub name: &'static str,
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub action: KeyCode,
+}
+
+/// The default keyboard scheme for each player slot, in order.
+pub const KEY_SCHEMES: [KeyScheme; MAX_PLAYERS] = [
+    KeyScheme {
+        name: "WASD + Space",
+        up: KeyCode::W,
+        down: KeyCode::S,
+        left: KeyCode::A,
+        right: KeyCode::D,
+        action: KeyCode::Space,
+    },
+    KeyScheme {
+        name: "Arrows + Right Shift",
+        up: KeyCode::Up,
+        down: KeyCode::Down,
+        left: KeyCode::Left,
+        right: KeyCode::Right,
+        action: KeyCode::RShift,
+    },
+    KeyScheme {
+        name: "IJKL + U",
+        up: KeyCode::I,
+        down: KeyCode::K,
+        left: KeyCode::J,
+        right: KeyCode::L,
+        action: KeyCode::U,
+    },
+    KeyScheme {
+        name: "Numpad 8456 + 0",
+        up: KeyCode::Numpad8,
+        down: KeyCode::Numpad5,
+        left: KeyCode::Numpad4,
+        right: KeyCode::Numpad6,
+        action: KeyCode::Numpad0,
+    },
+];
+
+/// The state of a gamepad, built up from its events.
+#[derive(Debug, Clone, Default)]
+struct Pad {
+    stick: (f32, f32),
+    buttons: Vec<Button>,
+    unplugged: bool,
+}
+
+/// Tracks every player's keys and gamepad.
+///
+/// Forward the gamepad events from the game's event handler to `gamepad_button` and
+/// `gamepad_axis` so gamepads can join in, and call `update` every update to notice them being
+/// unplugged.
+#[derive(Debug, Clone, Default)]
+pub struct Controllers {
+    /// Gamepads in the order they joined.
+    order: Vec<GamepadId>,
+    pads: HashMap<GamepadId, Pad>,
+    bindings: Bindings,
+}
+
+impl Controllers {
+    /// Creates the controllers with the current profile's bindings.
+    pub fn new() -> Self {
+        Controllers {
+            bindings: Bindings::load(),
+            ..Controllers::default()
+        }
+    }
+
+    fn pad_mut(&mut self, id: GamepadId) -> &mut Pad {
+        if !self.order.contains(&id) {
+            let pads = &self.pads;
+            let unplugged = self
+                .order
+                .iter()
+                .position(|other| pads.get(other).map_or(false, |pad| pad.unplugged));
+            match unplugged {
+                Some(slot) => {
+                    let old = std::mem::replace(&mut self.order[slot], id);
+                    self.pads.remove(&old);
+                }
+                None => self.order.push(id),
+            }
+        }
+        let pad = self.pads.entry(id).or_insert_with(Pad::default);
+        pad.unplugged = false;
+        pad
+    }
+
+    /// Notices the players' gamepads being unplugged, letting go of whatever they held.
+    pub fn update(&mut self, ctx: &Context) {
+        for (&id, pad) in &mut self.pads {
+            if !pad.unplugged && !is_connected(ctx, id) {
+                *pad = Pad {
+                    unplugged: true,
+                    ..Pad::default()
+                };
+            }
+        }
+    }
+
+    /// Records a gamepad button being pressed or released.
+    pub fn gamepad_button(&mut self, button: Button, id: GamepadId, pressed: bool) {
+        let pad = self.pad_mut(id);
+        pad.buttons.retain(|&b| b != button);
+        if pressed {
+            pad.buttons.push(button);
+        }
+    }
+
+    /// Records a gamepad stick moving.
+    pub fn gamepad_axis(&mut self, axis: Axis, value: f32, id: GamepadId) {
+        let pad = self.pad_mut(id);
+        match axis {
+            Axis::LeftStickX => pad.stick.0 = value,
+            // Sticks report up as positive, but the screen goes down.
+            Axis::LeftStickY => pad.stick.1 = -value,
+            _ => (),
+        }
+    }
+
+    /// The index of the player a gamepad plays as, if it has joined.
+    pub fn player_for(&self, id: GamepadId) -> Option<usize> {
+        self.order.iter().position(|&pad| pad == id)
+    }
+
+    fn pad(&self, player: usize) -> Option<&Pad> {
+        self.order.get(player).and_then(|id| self.pads.get(id))
+    }
+
+    /// Whether the player is using a gamepad.
+    pub fn has_gamepad(&self, player: usize) -> bool {
+        player < self.order.len()
+    }
+
+    /// The direction the player is pushing, with each axis from -1 to 1 and down being positive.
+    pub fn direction(&self, ctx: &Context, player: usize) -> (f32, f32) {
+        let mut direction = (0.0, 0.0);
+        if let Some(bindings) = self.bindings.players.get(player) {
+            let scheme = bindings.keys;
+            let pressed = |key| keyboard::is_key_pressed(ctx, key);
+            if pressed(scheme.left) {
+                direction.0 -= 1.0;
+            }
+            if pressed(scheme.right) {
+                direction.0 += 1.0;
+            }
+            if pressed(scheme.up) {
+                direction.1 -= 1.0;
+            }
+            if pressed(scheme.down) {
+                direction.1 += 1.0;
+            }
+        }
+
+        if let Some(pad) = self.pad(player) {
+            let held = |button| pad.buttons.contains(&button);
+            let dead = |value: f32| if value.abs() < DEADZONE { 0.0 } else { value };
+            direction.0 += dead(pad.stick.0);
+            direction.1 += dead(pad.stick.1);
+            if held(Button::DPadLeft) {
+                direction.0 -= 1.0;
+            }
+            if held(Button::DPadRight) {
+                direction.0 += 1.0;
+            }
+            if held(Button::DPadUp) {
+                direction.1 -= 1.0;
+            }
+            if held(Button::DPadDown) {
+                direction.1 += 1.0;
+            }
+        }
+
+        (
+            direction.0.max(-1.0).min(1.0),
+            direction.1.max(-1.0).min(1.0),
+        )
+    }
+
+    /// Whether the player is holding their action button.
+    pub fn action(&self, ctx: &Context, player: usize) -> bool {
+        let bindings = match self.bindings.players.get(player) {
+            Some(bindings) => bindings,
+            None => return false,
+        };
+        let key = keyboard::is_key_pressed(ctx, bindings.keys.action);
+        let pad = self
+            .pad(player)
+            .map_or(false, |pad| pad.buttons.contains(&bindings.button));
+        key || pad
+    }
+
+    /// Describes the controls for a player slot, for showing on screen.
+    pub fn describe(&self, player: usize) -> String {
+        let keys = self
+            .bindings
+            .players
+            .get(player)
+            .map_or_else(String::new, |bindings| bindings.describe(player));
+        if self.has_gamepad(player) {
+            format!("{} or gamepad {}", keys, player + 1)
+        } else {
+            keys
+        }
+    }
+}
+
+/// Whether a gamepad is still plugged in.
+pub fn is_connected(ctx: &Context, id: GamepadId) -> bool {
+    gamepad::gamepad(ctx, id).is_connected()
+}
+
+/// A gamepad being plugged in or unplugged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hotplug {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    /// A gamepad that was unplugged has come back.
+    Reconnected(GamepadId),
+}
+
+impl Hotplug {
+    /// What to tell the player.
+    pub fn message(self) -> &'static str {
+        match self {
+            Hotplug::Connected(_) => "Controller connected",
+            Hotplug::Disconnected(_) => "Controller disconnected",
+            Hotplug::Reconnected(_) => "Controller reconnected",
+        }
+    }
+}
+
+/// Notices gamepads being plugged in and unplugged while a game runs.
+///
+/// ggez only passes on gamepads' buttons and sticks, so a gamepad is noticed when it is first
+/// used, then checked on every update for whether it is still plugged in. A gamepad plugged back
+/// in keeps its id.
+#[derive(Debug, Clone, Default)]
+pub struct GamepadWatch {
+    /// Every gamepad noticed, and whether it was plugged in when last checked.
+    pads: Vec<(GamepadId, bool)>,
+}
+
+impl GamepadWatch {
+    pub fn new() -> Self {
+        GamepadWatch::default()
+    }
+
+    /// Notes a gamepad being used, returning how it changed if it is new or has come back.
+    pub fn used(&mut self, id: GamepadId) -> Option<Hotplug> {
+        match self.pads.iter_mut().find(|(pad, _)| *pad == id) {
+            Some((_, true)) => None,
+            Some((_, connected)) => {
+                *connected = true;
+                Some(Hotplug::Reconnected(id))
+            }
+            None => {
+                self.pads.push((id, true));
+                Some(Hotplug::Connected(id))
+            }
+        }
+    }
+
+    /// Checks the gamepads noticed so far, returning those unplugged or plugged back in since
+    /// the last check.
+    pub fn update(&mut self, ctx: &Context) -> Vec<Hotplug> {
+        let mut changes = Vec::new();
+        for (id, connected) in &mut self.pads {
+            let now = is_connected(ctx, *id);
+            if now != *connected {
+                *connected = now;
+                changes.push(if now {
+                    Hotplug::Reconnected(*id)
+                } else {
+                    Hotplug::Disconnected(*id)
+                });
+            }
+        }
+        changes
+    }
+}
+
+/// A step through a menu.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Nav {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
+
+impl Nav {
+    /// The menu step for a key, accepting the arrows as well as WASD.
+    pub fn from_key(key: KeyCode) -> Option<Nav> {
+        match key {
+            KeyCode::Up | KeyCode::W => Some(Nav::Up),
+            KeyCode::Down | KeyCode::S => Some(Nav::Down),
+            KeyCode::Left | KeyCode::A => Some(Nav::Left),
+            KeyCode::Right | KeyCode::D => Some(Nav::Right),
+            KeyCode::Return | KeyCode::NumpadEnter | KeyCode::Space => Some(Nav::Confirm),
+            KeyCode::Escape | KeyCode::Back => Some(Nav::Back),
+            _ => None,
+        }
+    }
+
+    /// The menu step for a gamepad button.
+    pub fn from_button(button: Button) -> Option<Nav> {
+        match button {
+            Button::DPadUp => Some(Nav::Up),
+            Button::DPadDown => Some(Nav::Down),
+            Button::DPadLeft => Some(Nav::Left),
+            Button::DPadRight => Some(Nav::Right),
+            Button::South | Button::Start => Some(Nav::Confirm),
+            Button::East | Button::Select => Some(Nav::Back),
+            _ => None,
+        }
+    }
+}
+
+/// How far a stick has to be pushed to step through a menu.
+const NAV_THRESHOLD: f32 = 0.6;
+
+/// Turns the left sticks of any gamepads into menu steps, one step each time a stick is pushed
+/// past the threshold.
+#[derive(Debug, Clone, Default)]
+pub struct StickNav {
+    /// Which way each gamepad's stick is pushed on each axis, if past the threshold.
+    pushed: HashMap<(GamepadId, bool), i8>,
+}
+
+impl StickNav {
+    pub fn new() -> Self {
+        StickNav::default()
+    }
+
+    /// Records a stick moving, returning a step if it has just been pushed over.
+    pub fn gamepad_axis(&mut self, axis: Axis, value: f32, id: GamepadId) -> Option<Nav> {
+        let vertical = match axis {
+            Axis::LeftStickX => false,
+            Axis::LeftStickY => true,
+            _ => return None,
+        };
+        let side = if value > NAV_THRESHOLD {
+            1
+        } else if value < -NAV_THRESHOLD {
+            -1
+        } else {
+            0
+        };
+        let previous = self.pushed.insert((id, vertical), side).unwrap_or(0);
+        if side == previous {
+            return None;
+        }
+        // Sticks report up as positive.
+        match (vertical, side) {
+            (false, -1) => Some(Nav::Left),
+            (false, 1) => Some(Nav::Right),
+            (true, 1) => Some(Nav::Up),
+            (true, -1) => Some(Nav::Down),
+            _ => None,
+        }
+    }
+}
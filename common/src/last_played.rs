@@ -0,0 +1,76 @@
+//! Remembers the game last played in each profile, and the mode it was played in, so the
+//! launcher can offer to carry on with it.
+//!
+//! The launcher records the game when starting it, and games with modes record the one chosen
+//! with `set_mode`, which also shows it in the window's title. Games are started in their
+//! remembered mode with a `--mode` argument.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::args;
+use crate::migrate::{self, LAST_PLAYED};
+use crate::profile;
+use crate::title;
+
+/// The game last played and the mode it was in, if it has modes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastPlayed {
+    /// The game's name in the registry.
+    pub game: String,
+    pub mode: Option<String>,
+}
+
+impl LastPlayed {
+    pub fn new(game: &str, mode: Option<&str>) -> Self {
+        LastPlayed {
+            game: game.to_string(),
+            mode: mode.map(str::to_string),
+        }
+    }
+
+    fn path() -> io::Result<PathBuf> {
+        Ok(profile::profile_dir()?.join("last_played.txt"))
+    }
+
+    /// Loads the current profile's last played game, if it has played one.
+    pub fn load() -> Option<LastPlayed> {
+        let contents = migrate::read(&LastPlayed::path().ok()?, &LAST_PLAYED).ok()?;
+        let mut last = LastPlayed::new("", None);
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some("game"), Some(game)) => last.game = game.trim().to_string(),
+                (Some("mode"), Some(mode)) if !mode.trim().is_empty() => {
+                    last.mode = Some(mode.trim().to_string())
+                }
+                _ => (),
+            }
+        }
+        if last.game.is_empty() {
+            None
+        } else {
+            Some(last)
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let contents = format!(
+            "game={}\nmode={}\n",
+            self.game,
+            self.mode.as_deref().unwrap_or("")
+        );
+        migrate::write(&LastPlayed::path()?, &LAST_PLAYED, &contents)
+    }
+}
+
+/// Records the mode a game is being played in, so it is started in that mode next time.
+pub fn set_mode(game: &str, mode: &str) -> io::Result<()> {
+    title::show_mode(mode);
+    LastPlayed::new(game, Some(mode)).save()
+}
+
+/// Returns the mode given by the `--mode` argument, if any.
+pub fn requested_mode() -> Option<String> {
+    args::value("mode")
+}
@@ -0,0 +1,73 @@
+//! Starting the games as processes of their own, for the launcher and the `quick-games` tool.
+
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+
+use crate::plugins::Plugin;
+use crate::profile;
+use crate::registry::GameInfo;
+
+/// Starts a game with the current profile and any extra `args`.
+///
+/// Games are looked for next to the running executable, which is where cargo puts every binary
+/// in the workspace. If a game hasn't been built there, `cargo run` builds and starts it.
+pub fn launch(game: &GameInfo, args: &[String]) -> io::Result<Child> {
+    run(game.name, args)
+}
+
+/// Starts a plugin in the `plugin` host, the same way as `launch`.
+pub fn launch_plugin(plugin: &Plugin, args: &[String]) -> io::Result<Child> {
+    let mut host_args = vec![
+        "--plugin".to_string(),
+        plugin.path.to_string_lossy().into_owned(),
+    ];
+    host_args.extend_from_slice(args);
+    run("plugin", &host_args)
+}
+
+/// Opens the launcher, like from the tray.
+pub fn launch_launcher() -> io::Result<Child> {
+    run("launcher", &[])
+}
+
+/// Puts the games' icon in the tray, from the `tray` executable.
+pub fn launch_tray() -> io::Result<Child> {
+    run("tray", &[])
+}
+
+/// Opens the `spectator` window, showing the frames a game sends on `port`.
+pub fn launch_spectator(port: u16) -> io::Result<Child> {
+    run("spectator", &["--port".to_string(), port.to_string()])
+}
+
+/// Starts one of the workspace's executables.
+fn run(name: &str, args: &[String]) -> io::Result<Child> {
+    let mut command = match sibling_executable(name) {
+        Some(path) => Command::new(path),
+        None => {
+            let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+            let mut command = Command::new(cargo);
+            command.args(&["run", "--release", "-p", name, "--"]);
+            command
+        }
+    };
+    command
+        .arg("--profile")
+        .arg(profile::current())
+        .args(args)
+        .spawn()
+}
+
+fn sibling_executable(name: &str) -> Option<PathBuf> {
+    let path =
+        env::current_exe()
+            .ok()?
+            .with_file_name(format!("{}{}", name, env::consts::EXE_SUFFIX));
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,123 @@
+//! Submitting runs to the online leaderboard, signed so the server can turn away scores that
+//! weren't played. The format and the checks the server makes are in the `quick-games-leaderboard`
+//! crate, which a server can use too.
+//!
+//! Nothing is submitted unless the profile's settings have a `leaderboard_url`, which runs are
+//! sent to with `POST` over plain HTTP like sync, and so only to a server on this computer, like
+//! a tunnel to the leaderboard, unless `allow_plain_http` is set. The first time a run is
+//! submitted the install makes itself a key, kept in the shared data directory where only the
+//! player can read it, and registers it with the server. Only the key's public half and
+//! signatures made with it are ever sent. Runs from games that keep replays are sent with the
+//! replay, so the server can play them again with `quick-games verify`. Only daily runs are
+//! submitted for now, as they're the only runs everyone plays with the same seed.
+
+pub use quick_games_leaderboard::{replay_hash, verify, Message, Submission};
+
+use quick_games_leaderboard::Key;
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+
+use crate::settings::Settings;
+use crate::sync::Server;
+
+/// The setting holding the leaderboard's URL.
+pub const URL_SETTING: &str = "leaderboard_url";
+
+/// The file the install's key is kept in. It's followed by a line saying it's registered, once
+/// it is.
+const KEY_FILE: &str = "leaderboard_key.txt";
+const REGISTERED: &str = "registered";
+
+/// The leaderboard's URL, if there is one.
+pub fn url() -> Option<String> {
+    Settings::load()
+        .get(URL_SETTING)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+/// Makes the key file readable only by the player, since anyone with the key can submit runs
+/// as the install.
+#[cfg(unix)]
+fn keep_private(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+}
+
+/// Elsewhere the data directory is in the player's own folders, which others can't read.
+#[cfg(not(unix))]
+fn keep_private(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Writes the key file, never letting anyone else read it even while it's being made.
+fn save_key(path: &Path, contents: &str) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents.as_bytes())?;
+    keep_private(path)
+}
+
+/// The install's key, made and registered with `server` if it hasn't been yet.
+fn key(server: &Server) -> Result<Key, String> {
+    let path = crate::data_dir()
+        .map_err(|e| format!("couldn't find the data directory: {}", e))?
+        .join(KEY_FILE);
+    let (key, registered) = match fs::read_to_string(&path) {
+        Ok(contents) => {
+            // Keys made by older versions were left readable by everyone.
+            keep_private(&path).map_err(|e| format!("couldn't make the key private: {}", e))?;
+            let mut lines = contents.lines();
+            let key = lines
+                .next()
+                .and_then(Key::parse)
+                .ok_or_else(|| format!("{} isn't a key", path.display()))?;
+            (key, lines.next() == Some(REGISTERED))
+        }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            let key = Key::generate();
+            save_key(&path, &format!("{}\n", key.to_hex()))
+                .map_err(|e| format!("couldn't save the key: {}", e))?;
+            (key, false)
+        }
+        Err(e) => return Err(format!("couldn't read the key: {}", e)),
+    };
+    if !registered {
+        server.post(key.register().as_bytes())?;
+        save_key(&path, &format!("{}\n{}\n", key.to_hex(), REGISTERED))
+            .map_err(|e| format!("registered, but couldn't save that it was: {}", e))?;
+    }
+    Ok(key)
+}
+
+/// Signs `submission` and sends it to the leaderboard with its replay, registering the install
+/// first if this is its first. Returns whether it was sent, which it isn't without a leaderboard
+/// to send it to.
+pub fn submit(submission: &Submission, replay: Option<&str>) -> Result<bool, String> {
+    let url = match url() {
+        Some(url) => url,
+        None => return Ok(false),
+    };
+    let server = Server::parse(&url)?;
+    let key = key(&server)?;
+    server.post(key.submit(submission, replay).as_bytes())?;
+    Ok(true)
+}
+
+/// Submits a run without holding up the game, only saying if it couldn't.
+pub fn submit_in_background(submission: Submission, replay: Option<String>) {
+    thread::spawn(move || {
+        if let Err(e) = submit(&submission, replay.as_deref()) {
+            eprintln!("Couldn't submit the run to the leaderboard: {}", e);
+        }
+    });
+}
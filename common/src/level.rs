@@ -0,0 +1,118 @@
+//! The shared text level format.
+//!
+//! A level file starts with `key: value` properties, followed by a line containing only `---`
+//! and then the tile rows. What each tile character means is up to the game loading it.
+//!
+//! ```text
+//! name: First Steps
+//! ---
+//! ##########
+//! #@   *  E#
+//! ##########
+//! ```
+
+use std::fmt;
+
+use crate::grid::Grid;
+
+/// A level loaded from the shared level format.
+#[derive(Debug, Clone)]
+pub struct Level {
+    /// The properties from the header, in file order.
+    pub properties: Vec<(String, String)>,
+    pub tiles: Grid<char>,
+}
+
+impl Level {
+    /// Parses a level. Rows shorter than the widest row are padded with spaces.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut lines = contents.lines();
+        let mut properties = Vec::new();
+
+        loop {
+            let line = lines.next().ok_or("missing `---` separator")?;
+            let line = line.trim();
+            if line == "---" {
+                break;
+            }
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts
+                .next()
+                .ok_or_else(|| format!("invalid property `{}`", line))?
+                .trim();
+            properties.push((key.to_string(), value.to_string()));
+        }
+
+        let rows: Vec<&str> = lines.map(|line| line.trim_end()).collect();
+        let rows = &rows[..rows
+            .iter()
+            .rposition(|row| !row.is_empty())
+            .map(|i| i + 1)
+            .unwrap_or(0)];
+        if rows.is_empty() {
+            return Err("level has no tiles".to_string());
+        }
+
+        let width = rows
+            .iter()
+            .map(|row| row.chars().count())
+            .max()
+            .unwrap_or(0);
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let mut row: Vec<char> = row.chars().collect();
+                row.resize(width, ' ');
+                row
+            })
+            .collect();
+
+        Ok(Level {
+            properties,
+            tiles: Grid::from_rows(rows).ok_or("level rows have different lengths")?,
+        })
+    }
+
+    /// Returns the value of the property `key`, if present.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The name of the level, or an empty string if it has none.
+    pub fn name(&self) -> &str {
+        self.property("name").unwrap_or("")
+    }
+
+    /// Returns the position of the first tile matching `tile`.
+    pub fn find(&self, tile: char) -> Option<(i32, i32)> {
+        self.tiles
+            .iter()
+            .find(|(_, _, t)| **t == tile)
+            .map(|(x, y, _)| (x, y))
+    }
+}
+
+/// Writes the level back out in the format `parse` reads, for editors to save.
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (key, value) in &self.properties {
+            writeln!(f, "{}: {}", key, value)?;
+        }
+        writeln!(f, "---")?;
+        for y in 0..self.tiles.height() as i32 {
+            let row: String = (0..self.tiles.width() as i32)
+                .filter_map(|x| self.tiles.get(x, y))
+                .collect();
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
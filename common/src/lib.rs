@@ -0,0 +1,74 @@
+//! Code shared between the quick games.
+
+pub mod achievements;
+pub mod app;
+pub mod args;
+pub mod assets;
+pub mod audio;
+pub mod bindings;
+pub mod capture;
+pub mod cards;
+pub mod cheats;
+pub mod console;
+pub mod crash;
+pub mod daily;
+pub mod generate;
+pub mod grid;
+pub mod input;
+pub mod json;
+pub mod last_played;
+pub mod launch;
+pub mod leaderboard;
+pub mod level;
+pub mod locale;
+pub mod matching;
+pub mod migrate;
+pub mod mods;
+pub mod motion;
+pub mod notify;
+pub mod overlay;
+pub mod particles;
+pub mod physics;
+pub mod playtime;
+pub mod plugins;
+pub mod postprocess;
+pub mod profile;
+pub mod registry;
+pub mod results;
+pub mod rumble;
+pub mod scanning;
+pub mod session;
+pub mod settings;
+pub mod shaping;
+pub mod share;
+pub mod snapshot;
+pub mod spectator;
+pub mod speech;
+pub mod stats;
+pub mod sync;
+pub mod telemetry;
+pub mod text;
+pub mod theme;
+pub mod tiled;
+pub mod title;
+pub mod toast;
+pub mod tokens;
+pub mod tween;
+pub mod ui;
+pub mod update;
+pub mod window;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Returns the directory the games persist their data in, creating it if it doesn't exist yet.
+///
+/// Falls back to the current directory if the platform has no data directory.
+pub fn data_dir() -> io::Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("quick-games");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
@@ -0,0 +1,456 @@
+//! Writing numbers, durations and dates the way the player's locale does, so a score reads
+//! `1,234,567` in English and `1.234.567` in German.
+//!
+//! The locale is the profile's `locale` setting, a language tag like `de` or `en-GB`, and
+//! otherwise the system's, from `LC_ALL`, `LC_NUMERIC` or `LANG`. Tags are matched on their
+//! language and region, then on their language alone, and anything unknown is written like
+//! `en-US`. It's looked up once, the first time something is formatted, so a change is picked
+//! up the next time a game starts.
+//!
+//! Durations are `m:ss`, or `h:mm:ss` from an hour on, whatever the locale. Dates are in UTC,
+//! since the games have no way to find the local time zone.
+
+use std::env;
+use std::fmt::Display;
+
+use crate::settings::Settings;
+
+const LOCALE_KEY: &str = "locale";
+
+/// The order a date's day, month and year are written in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// How a locale writes numbers and dates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// Goes between each group of three digits.
+    pub thousands: &'static str,
+    /// Goes before the fractional part of a number.
+    pub decimal_point: &'static str,
+    pub date_order: DateOrder,
+    /// Goes between the parts of a date.
+    pub date_separator: &'static str,
+}
+
+const EN_US: Locale = Locale {
+    thousands: ",",
+    decimal_point: ".",
+    date_order: DateOrder::MonthDayYear,
+    date_separator: "/",
+};
+
+/// The locales there are, by language tag, most specific first.
+const LOCALES: &[(&str, Locale)] = &[
+    ("en-us", EN_US),
+    (
+        "en",
+        Locale {
+            date_order: DateOrder::DayMonthYear,
+            ..EN_US
+        },
+    ),
+    (
+        "de-ch",
+        Locale {
+            thousands: "'",
+            decimal_point: ".",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: ".",
+        },
+    ),
+    (
+        "de",
+        Locale {
+            thousands: ".",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: ".",
+        },
+    ),
+    (
+        "nl",
+        Locale {
+            thousands: ".",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: "-",
+        },
+    ),
+    (
+        "es",
+        Locale {
+            thousands: ".",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: "/",
+        },
+    ),
+    (
+        "it",
+        Locale {
+            thousands: ".",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: "/",
+        },
+    ),
+    (
+        "pt",
+        Locale {
+            thousands: ".",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: "/",
+        },
+    ),
+    (
+        "fr",
+        Locale {
+            thousands: " ",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: "/",
+        },
+    ),
+    (
+        "pl",
+        Locale {
+            thousands: " ",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: ".",
+        },
+    ),
+    (
+        "ru",
+        Locale {
+            thousands: " ",
+            decimal_point: ",",
+            date_order: DateOrder::DayMonthYear,
+            date_separator: ".",
+        },
+    ),
+    (
+        "sv",
+        Locale {
+            thousands: " ",
+            decimal_point: ",",
+            date_order: DateOrder::YearMonthDay,
+            date_separator: "-",
+        },
+    ),
+    (
+        "ja",
+        Locale {
+            date_order: DateOrder::YearMonthDay,
+            ..EN_US
+        },
+    ),
+    (
+        "zh",
+        Locale {
+            date_order: DateOrder::YearMonthDay,
+            ..EN_US
+        },
+    ),
+    (
+        "ko",
+        Locale {
+            date_order: DateOrder::YearMonthDay,
+            date_separator: ".",
+            ..EN_US
+        },
+    ),
+];
+
+thread_local! {
+    static CURRENT: Locale = Locale::load();
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        EN_US
+    }
+}
+
+impl Locale {
+    /// Loads the locale from the profile's settings or the system.
+    pub fn load() -> Self {
+        let tag = Settings::load()
+            .get(LOCALE_KEY)
+            .map(str::to_string)
+            .or_else(system_tag);
+        tag.map_or_else(Locale::default, |tag| Locale::from_tag(&tag))
+    }
+
+    /// The locale for a language tag, like `en-GB`, or a POSIX locale, like `de_DE.UTF-8`.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag
+            .split(['.', '@'])
+            .next()
+            .unwrap_or("")
+            .replace('_', "-")
+            .to_lowercase();
+        let language = tag.split('-').next().unwrap_or("");
+        LOCALES
+            .iter()
+            .find(|(name, _)| *name == tag)
+            .or_else(|| LOCALES.iter().find(|(name, _)| *name == language))
+            .map_or_else(Locale::default, |(_, locale)| *locale)
+    }
+
+    /// Writes a whole number with its digits grouped in threes.
+    pub fn integer(&self, value: impl Display) -> String {
+        let digits = value.to_string();
+        let (sign, digits) = match digits.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", digits.as_str()),
+        };
+        let mut grouped = sign.to_string();
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index) % 3 == 0 {
+                grouped.push_str(self.thousands);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// Writes a number with `places` digits after the decimal point.
+    pub fn decimal(&self, value: f64, places: usize) -> String {
+        let written = format!("{:.*}", places, value);
+        match written.find('.') {
+            Some(point) => format!(
+                "{}{}{}",
+                self.integer(&written[..point]),
+                self.decimal_point,
+                &written[point + 1..]
+            ),
+            None => self.integer(written),
+        }
+    }
+
+    /// Writes whole numbers without a decimal point and anything else to two places.
+    pub fn number(&self, value: f64) -> String {
+        if value.fract() == 0.0 {
+            // Written out rather than cast, which would cap it at `i64::MAX`. Adding zero makes
+            // `-0` plain `0`.
+            self.integer(format!("{:.0}", value + 0.0))
+        } else {
+            self.decimal(value, 2)
+        }
+    }
+
+    /// Writes the date of a timestamp, in seconds since the unix epoch.
+    pub fn date(&self, timestamp: u64) -> String {
+        let (year, month, day) = civil_date(timestamp / 86400);
+        let (year, month, day) = (
+            year.to_string(),
+            format!("{:02}", month),
+            format!("{:02}", day),
+        );
+        let parts = match self.date_order {
+            DateOrder::DayMonthYear => [day, month, year],
+            DateOrder::MonthDayYear => [month, day, year],
+            DateOrder::YearMonthDay => [year, month, day],
+        };
+        parts.join(self.date_separator)
+    }
+}
+
+/// The system's locale, if it has one other than the POSIX default.
+fn system_tag() -> Option<String> {
+    ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|tag| !tag.is_empty())
+        .filter(|tag| tag != "C" && tag != "POSIX")
+}
+
+/// The year, month and day `days` days after 1970-01-01.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Counted in 400 year eras from 0000-03-01, so leap days fall at the end of each year.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as u64;
+    (year, month, day)
+}
+
+/// The current locale.
+pub fn current() -> Locale {
+    CURRENT.with(|locale| *locale)
+}
+
+/// Writes a whole number, like a score, in the current locale.
+pub fn integer(value: impl Display) -> String {
+    current().integer(value)
+}
+
+/// Writes a number with `places` digits after the decimal point in the current locale.
+pub fn decimal(value: f64, places: usize) -> String {
+    current().decimal(value, places)
+}
+
+/// Writes a recorded value in the current locale, without a decimal point if it's whole.
+pub fn number(value: f64) -> String {
+    current().number(value)
+}
+
+/// Writes a date in the current locale.
+pub fn date(timestamp: u64) -> String {
+    current().date(timestamp)
+}
+
+/// Writes a number of seconds as `m:ss`, or `h:mm:ss` from an hour on.
+pub fn duration(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(tag: &str) -> Locale {
+        Locale::from_tag(tag)
+    }
+
+    #[test]
+    fn digits_are_grouped_in_threes() {
+        let en = locale("en");
+        assert_eq!(en.integer(0), "0");
+        assert_eq!(en.integer(999), "999");
+        assert_eq!(en.integer(1000), "1,000");
+        assert_eq!(en.integer(100_000), "100,000");
+        assert_eq!(en.integer(1_234_567), "1,234,567");
+        assert_eq!(en.integer(-1_234), "-1,234");
+        assert_eq!(en.integer(-999), "-999");
+        assert_eq!(en.integer(i64::MIN), "-9,223,372,036,854,775,808");
+    }
+
+    #[test]
+    fn each_locale_uses_its_own_separators() {
+        assert_eq!(locale("de").integer(1_234_567), "1.234.567");
+        assert_eq!(locale("fr").integer(1_234_567), "1 234 567");
+        assert_eq!(locale("de-CH").integer(1_234_567), "1'234'567");
+        assert_eq!(locale("de").decimal(1234.5, 2), "1.234,50");
+        assert_eq!(locale("en").decimal(1234.5, 2), "1,234.50");
+        assert_eq!(locale("de-CH").decimal(1234.5, 1), "1'234.5");
+    }
+
+    #[test]
+    fn decimals_are_rounded_to_their_places() {
+        let en = locale("en");
+        assert_eq!(en.decimal(2.345, 0), "2");
+        assert_eq!(en.decimal(1999.999, 2), "2,000.00");
+        assert_eq!(en.decimal(-1234.5, 1), "-1,234.5");
+    }
+
+    #[test]
+    fn whole_numbers_have_no_decimal_point() {
+        let de = locale("de");
+        assert_eq!(de.number(42.0), "42");
+        assert_eq!(de.number(42.5), "42,50");
+        assert_eq!(de.number(-0.0), "0");
+        assert_eq!(de.number(1e20), "100.000.000.000.000.000.000");
+    }
+
+    #[test]
+    fn numbers_that_arent_finite_are_written_as_they_are() {
+        let en = locale("en");
+        assert_eq!(en.number(f64::NAN), "NaN");
+        assert_eq!(en.number(f64::INFINITY), "inf");
+        assert_eq!(en.decimal(f64::NEG_INFINITY, 2), "-inf");
+    }
+
+    #[test]
+    fn tags_match_on_region_then_language() {
+        assert_eq!(locale("de-CH").thousands, "'");
+        assert_eq!(locale("de-AT").thousands, ".");
+        assert_eq!(locale("en-US").date_order, DateOrder::MonthDayYear);
+        assert_eq!(locale("en-GB").date_order, DateOrder::DayMonthYear);
+        assert_eq!(locale("zh-Hant-TW").date_order, DateOrder::YearMonthDay);
+    }
+
+    #[test]
+    fn posix_locales_are_read_like_tags() {
+        assert_eq!(locale("de_CH.UTF-8"), locale("de-ch"));
+        assert_eq!(locale("sv_SE.UTF-8@euro"), locale("sv"));
+        assert_eq!(locale("FR_fr"), locale("fr"));
+    }
+
+    #[test]
+    fn unknown_and_malformed_tags_are_written_like_en_us() {
+        for tag in &[
+            "",
+            "xx",
+            "-",
+            "--de",
+            "@",
+            ".UTF-8",
+            "ünï-cödé",
+            "C",
+            "en_US\u{0}",
+        ] {
+            let expected = if tag.starts_with("en") {
+                locale("en")
+            } else {
+                EN_US
+            };
+            assert_eq!(locale(tag), expected, "{:?}", tag);
+        }
+    }
+
+    #[test]
+    fn dates_are_written_in_each_order() {
+        // 2024-02-29, a leap day.
+        let leap_day = 1_709_164_800;
+        assert_eq!(locale("en-US").date(leap_day), "02/29/2024");
+        assert_eq!(locale("en-GB").date(leap_day), "29/02/2024");
+        assert_eq!(locale("de").date(leap_day), "29.02.2024");
+        assert_eq!(locale("sv").date(leap_day), "2024-02-29");
+        assert_eq!(locale("ko").date(leap_day), "2024.02.29");
+    }
+
+    #[test]
+    fn civil_dates_cross_centuries_and_leap_years() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(10_956), (1999, 12, 31));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(47_482), (2100, 1, 1));
+        // 2100 isn't a leap year.
+        assert_eq!(civil_date(47_540), (2100, 2, 28));
+        assert_eq!(civil_date(47_541), (2100, 3, 1));
+        assert_eq!(civil_date(u64::MAX / 86_400).0, 584_554_051_223);
+    }
+
+    #[test]
+    fn durations_gain_hours_from_an_hour_on() {
+        assert_eq!(duration(0), "0:00");
+        assert_eq!(duration(59), "0:59");
+        assert_eq!(duration(61), "1:01");
+        assert_eq!(duration(3599), "59:59");
+        assert_eq!(duration(3600), "1:00:00");
+        assert_eq!(duration(90_061), "25:01:01");
+    }
+}
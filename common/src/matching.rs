@@ -0,0 +1,95 @@
+//! Line matching and gravity for match-3 style puzzles played on a grid of optional pieces.
+
+use crate::grid::Grid;
+
+/// The horizontal and vertical line directions.
+pub const ORTHOGONAL: [(i32, i32); 2] = [(1, 0), (0, 1)];
+/// The horizontal, vertical and both diagonal line directions.
+pub const ALL_LINES: [(i32, i32); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+/// A straight line of equal pieces.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub start: (i32, i32),
+    pub dir: (i32, i32),
+    pub len: usize,
+}
+
+impl Run {
+    /// The positions of the pieces in the run, from its start.
+    pub fn cells(&self) -> impl Iterator<Item = (i32, i32)> {
+        let Run { start, dir, len } = *self;
+        (0..len as i32).map(move |i| (start.0 + dir.0 * i, start.1 + dir.1 * i))
+    }
+}
+
+/// Finds every run of at least `min_len` equal pieces along `directions`. Each run is only
+/// reported once, from its first piece.
+pub fn find_runs<T: PartialEq>(
+    grid: &Grid<Option<T>>,
+    min_len: usize,
+    directions: &[(i32, i32)],
+) -> Vec<Run> {
+    let mut runs = Vec::new();
+
+    for (x, y, cell) in grid.iter() {
+        let piece = match cell {
+            Some(piece) => piece,
+            None => continue,
+        };
+        let same = |x: i32, y: i32| match grid.get(x, y) {
+            Some(Some(other)) => other == piece,
+            _ => false,
+        };
+
+        for &(dx, dy) in directions {
+            // Only start counting at the first piece of a run.
+            if same(x - dx, y - dy) {
+                continue;
+            }
+
+            let mut len = 1;
+            while same(x + dx * len as i32, y + dy * len as i32) {
+                len += 1;
+            }
+            if len >= min_len {
+                runs.push(Run {
+                    start: (x, y),
+                    dir: (dx, dy),
+                    len,
+                });
+            }
+        }
+    }
+    runs
+}
+
+/// The positions covered by any of `runs`, without duplicates where runs cross.
+pub fn matched_cells(runs: &[Run]) -> Vec<(i32, i32)> {
+    let mut cells: Vec<(i32, i32)> = runs.iter().flat_map(|run| run.cells()).collect();
+    cells.sort();
+    cells.dedup();
+    cells
+}
+
+/// Drops every piece down its column to fill the gaps below it, returning where each moved
+/// piece went as `(from, to)`.
+pub fn apply_gravity<T>(grid: &mut Grid<Option<T>>) -> Vec<((i32, i32), (i32, i32))> {
+    let mut moves = Vec::new();
+
+    for x in 0..grid.width() as i32 {
+        let mut floor = grid.height() as i32 - 1;
+        for y in (0..grid.height() as i32).rev() {
+            let piece = match grid.get_mut(x, y).and_then(Option::take) {
+                Some(piece) => piece,
+                None => continue,
+            };
+            if y != floor {
+                moves.push(((x, y), (x, floor)));
+            }
+            grid.set(x, floor, Some(piece));
+            floor -= 1;
+        }
+    }
+    moves
+}
@@ -0,0 +1,544 @@
+//! Versions of the files the games save, and upgrading old files as they're loaded.
+//!
+//! Saved files start with a `#version N` line giving the version of their format. Files saved
+//! before there were versions have none, and count as version 0. Loading a file older than its
+//! format copies it to a backup beside it, like `settings.txt.v0.bak`, then runs it through
+//! each of the format's migrations in turn and saves the result, so the games only ever read
+//! the current version. A file from a newer version of the games fails to load, and is left
+//! alone rather than reset.
+//!
+//! To change a format, add a migration from the old version to the end of its list. Its
+//! version is how many migrations it has.
+//!
+//! Files that aren't meant to be edited by hand are saved compressed, as gzip, which also
+//! checks each part of them against a CRC, so damage is noticed when they're loaded. Appending
+//! to one adds another gzip member to the end. Files saved before they were compressed still
+//! load, and are compressed the next time they're saved.
+//!
+//! Saving a file writes it beside the old one first and then moves it into place, so a file is
+//! never left half saved, and keeps the old one as a backup, like `stats.tsv.bak`. A damaged
+//! file is kept beside it, like `stats.tsv.damaged.bak`, and replaced with whatever could be
+//! read before the damage, leaving the backup as it was. For a file that's only ever added to,
+//! that's everything but the last few lines, which is more than the backup from when it was
+//! last saved whole. For a file that's saved whole each time, damage leaves nothing before it.
+//!
+//! `read_checked` says how a damaged file was recovered. When its backup has more in it than
+//! could be read, the backup isn't loaded in its place without asking: `read` leaves it for
+//! `overlay::DamagedFiles` to ask the player whether to load the backup or start fresh, which
+//! `App` and the launcher do.
+
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::bufread::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+const VERSION_PREFIX: &str = "#version ";
+
+/// The bytes gzip files start with.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Upgrades a file's contents, without the version line, from one version to the next.
+pub type Migration = fn(&str) -> Result<String, String>;
+
+/// The format of a kind of saved file.
+#[derive(Debug, Copy, Clone)]
+pub struct Format {
+    pub name: &'static str,
+    /// The migrations from each version to the next, starting from version 0.
+    pub migrations: &'static [Migration],
+    /// Whether files are saved compressed and checked for damage. Files meant to be read or
+    /// edited by hand aren't.
+    pub compressed: bool,
+}
+
+impl Format {
+    /// The current version.
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32
+    }
+
+    /// The line saved files start with.
+    pub fn header(&self) -> String {
+        format!("{}{}\n", VERSION_PREFIX, self.version())
+    }
+}
+
+/// For files saved before there were versions, which only need the version line adding.
+pub fn unversioned(contents: &str) -> Result<String, String> {
+    Ok(contents.to_string())
+}
+
+pub const SETTINGS: Format = Format {
+    name: "settings",
+    migrations: &[unversioned],
+    compressed: false,
+};
+pub const STATS: Format = Format {
+    name: "stats",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const ACHIEVEMENTS: Format = Format {
+    name: "achievements",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const TOKENS: Format = Format {
+    name: "tokens",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const DAILY: Format = Format {
+    name: "daily",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const PLAYTIME: Format = Format {
+    name: "playtime",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const SESSION: Format = Format {
+    name: "session",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const LAST_PLAYED: Format = Format {
+    name: "last played",
+    migrations: &[unversioned],
+    compressed: true,
+};
+pub const SYNC_MANIFEST: Format = Format {
+    name: "sync manifest",
+    migrations: &[],
+    compressed: false,
+};
+pub const TELEMETRY: Format = Format {
+    name: "telemetry",
+    migrations: &[],
+    compressed: false,
+};
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Splits a file into its version and the rest of it.
+fn split(contents: &str) -> Result<(u32, &str), String> {
+    let first = contents.lines().next().unwrap_or("");
+    match first.strip_prefix(VERSION_PREFIX) {
+        Some(version) => {
+            let version = version
+                .trim()
+                .parse()
+                .map_err(|_| format!("bad version line `{}`", first))?;
+            let rest = &contents[first.len()..];
+            let rest = rest
+                .strip_prefix("\r\n")
+                .or_else(|| rest.strip_prefix('\n'))
+                .unwrap_or(rest);
+            Ok((version, rest))
+        }
+        None => Ok((0, contents)),
+    }
+}
+
+/// Brings a file's contents up to the current version of `format`, returning them without the
+/// version line.
+pub fn upgrade(contents: &str, format: &Format) -> Result<String, String> {
+    let (version, rest) = split(contents)?;
+    if version > format.version() {
+        return Err(format!(
+            "it's from a newer version of the games, with {} version {} rather than {}",
+            format.name,
+            version,
+            format.version()
+        ));
+    }
+    let mut upgraded = rest.to_string();
+    for migration in &format.migrations[version as usize..] {
+        upgraded = migration(&upgraded)?;
+    }
+    Ok(upgraded)
+}
+
+/// The file beside `path` with `suffix` on the end of its name.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+/// Where a file is backed up before it's upgraded from `version`.
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    sibling(path, &format!(".v{}.bak", version))
+}
+
+fn compress(text: &str) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes())?;
+    encoder.finish()
+}
+
+/// The text of a saved file, compressed or not, and whether any of it was damaged. A damaged
+/// file gives the text from before the first damaged gzip member.
+fn decode(bytes: &[u8]) -> (String, bool) {
+    if !bytes.starts_with(&GZIP_MAGIC) {
+        return match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => (text, false),
+            Err(_) => (String::new(), true),
+        };
+    }
+    let mut text = Vec::new();
+    let mut rest = bytes;
+    let mut damaged = false;
+    while !rest.is_empty() {
+        let mut decoder = GzDecoder::new(rest);
+        let mut member = Vec::new();
+        if decoder.read_to_end(&mut member).is_err() {
+            damaged = true;
+            break;
+        }
+        text.extend_from_slice(&member);
+        rest = decoder.into_inner();
+    }
+    match String::from_utf8(text) {
+        Ok(text) => (text, damaged),
+        Err(e) => (String::from_utf8_lossy(e.as_bytes()).into_owned(), true),
+    }
+}
+
+/// Whether the file at `path` is there and not compressed.
+fn is_plain(path: &Path) -> bool {
+    let mut start = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(2).read_to_end(&mut start))
+        .map_or(false, |len| len > 0 && start != GZIP_MAGIC)
+}
+
+/// Saves a file's whole text, version line and all, keeping the old file as a backup unless
+/// it's damaged.
+fn save(path: &Path, format: &Format, text: &str) -> io::Result<()> {
+    let bytes = if format.compressed {
+        compress(text)?
+    } else {
+        text.as_bytes().to_vec()
+    };
+    let saving = sibling(path, ".tmp");
+    fs::write(&saving, bytes)?;
+    if let Ok(old) = fs::read(path) {
+        if !decode(&old).1 {
+            fs::rename(path, sibling(path, ".bak"))?;
+        }
+    }
+    fs::rename(&saving, path)
+}
+
+/// How a damaged file was recovered as it was read.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Recovery {
+    /// What could be read before the damage was loaded, and anything after it is lost.
+    Salvaged,
+    /// None of it could be read and there's no backup, so it starts again empty.
+    Emptied,
+    /// What could be read was loaded, but the backup has more in it, so the player is asked
+    /// whether to load it instead or start fresh.
+    Backup,
+}
+
+/// A file that was damaged when it was read.
+#[derive(Debug, Clone)]
+pub struct Damaged {
+    pub path: PathBuf,
+    pub format: Format,
+    /// Where the damaged file was kept.
+    pub kept: PathBuf,
+    pub recovery: Recovery,
+}
+
+impl Damaged {
+    /// The file's name, to tell the player which one it was.
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Replaces the file with its backup.
+    pub fn load_backup(&self) -> io::Result<()> {
+        fs::copy(sibling(&self.path, ".bak"), &self.path).map(|_| ())
+    }
+
+    /// Starts the file again empty, as if it had never been saved.
+    pub fn start_fresh(&self) -> io::Result<()> {
+        write(&self.path, &self.format, "")
+    }
+}
+
+thread_local! {
+    /// The damaged files with backups the player hasn't been asked about yet, oldest first.
+    static DAMAGED: RefCell<Vec<Damaged>> = RefCell::new(Vec::new());
+}
+
+/// Takes the damaged files read on this thread so far that the player has to choose for.
+pub fn take_damaged() -> Vec<Damaged> {
+    DAMAGED.with(|damaged| damaged.borrow_mut().drain(..).collect())
+}
+
+/// Recovers what could be read of a damaged file from `salvaged`, the text from before the
+/// damage, saving it upgraded in the file's place and keeping the damaged file beside it.
+fn recover(
+    path: &Path,
+    format: &Format,
+    bytes: &[u8],
+    salvaged: &str,
+    upgraded: &str,
+) -> io::Result<Damaged> {
+    let kept = sibling(path, ".damaged.bak");
+    fs::write(&kept, bytes)?;
+    // Appending doesn't refresh the backup, so it can be older than what was salvaged.
+    let backup = fs::read(sibling(path, ".bak"))
+        .ok()
+        .map(|backup| decode(&backup))
+        .filter(|(backup, damaged)| !damaged && backup.len() > salvaged.len());
+    let recovery = match backup {
+        Some(_) => Recovery::Backup,
+        None if salvaged.is_empty() => Recovery::Emptied,
+        None => Recovery::Salvaged,
+    };
+    // Saved over the damaged file, which isn't kept as the backup, so the backup is still
+    // there to load.
+    write(path, format, upgraded)?;
+    Ok(Damaged {
+        path: path.to_path_buf(),
+        format: *format,
+        kept,
+        recovery,
+    })
+}
+
+/// Reads a saved file like `read`, also saying how it was recovered if it was damaged.
+pub fn read_checked(path: &Path, format: &Format) -> io::Result<(String, Option<Damaged>)> {
+    let bytes = fs::read(path)?;
+    let (contents, damaged) = decode(&bytes);
+    let error = |e| invalid(format!("{}: {}", path.display(), e));
+    let (version, _) = split(&contents).map_err(error)?;
+    let upgraded = upgrade(&contents, format).map_err(error)?;
+    if damaged {
+        let damaged = recover(path, format, &bytes, &contents, &upgraded)?;
+        return Ok((upgraded, Some(damaged)));
+    }
+    if version < format.version() {
+        fs::copy(path, backup_path(path, version))?;
+        write(path, format, &upgraded)?;
+    }
+    Ok((upgraded, None))
+}
+
+/// Reads a saved file, upgrading it first if it's old, and recovering what it can if it's
+/// damaged. Returns its contents without the version line. A damaged file with a backup that
+/// has more in it is left for `take_damaged`.
+pub fn read(path: &Path, format: &Format) -> io::Result<String> {
+    let (contents, damaged) = read_checked(path, format)?;
+    if let Some(damaged) = damaged {
+        let recovered = match damaged.recovery {
+            Recovery::Salvaged => {
+                "What could be read before the damage was loaded, and anything after it is lost"
+            }
+            Recovery::Emptied => "None of it could be read, so it starts again",
+            Recovery::Backup => "Its backup has more in it, which can be loaded instead",
+        };
+        eprintln!(
+            "{} was damaged. {}, and the damaged file was kept as {}.",
+            path.display(),
+            recovered,
+            damaged.kept.display()
+        );
+        if damaged.recovery == Recovery::Backup {
+            DAMAGED.with(|pending| pending.borrow_mut().push(damaged));
+        }
+    }
+    Ok(contents)
+}
+
+/// Saves a file in the current version of `format`.
+pub fn write(path: &Path, format: &Format, contents: &str) -> io::Result<()> {
+    save(path, format, &format!("{}{}", format.header(), contents))
+}
+
+/// Adds a line to the end of a saved file, starting it with the version line if it's new.
+pub fn append(path: &Path, format: &Format, line: &str) -> io::Result<()> {
+    // A file saved before its format was compressed is compressed first, so it isn't half and
+    // half.
+    if format.compressed && is_plain(path) {
+        let (contents, _) = decode(&fs::read(path)?);
+        save(path, format, &contents)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut text = format!("{}\n", line);
+    if file.metadata()?.len() == 0 {
+        text.insert_str(0, &format.header());
+    }
+    if format.compressed {
+        file.write_all(&compress(&text)?)
+    } else {
+        file.write_all(text.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: Format = Format {
+        name: "log",
+        migrations: &[unversioned],
+        compressed: true,
+    };
+
+    /// An empty folder of its own for a test to save files in.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "quick-games-migrate-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Cuts the last few bytes off a file, damaging its last gzip member.
+    fn truncate(path: &Path) {
+        let bytes = fs::read(path).unwrap();
+        fs::write(path, &bytes[..bytes.len() - 4]).unwrap();
+    }
+
+    #[test]
+    fn plain_text_decodes_as_it_is() {
+        assert_eq!(
+            decode(b"#version 1\nscore\t3\n"),
+            ("#version 1\nscore\t3\n".into(), false)
+        );
+        assert_eq!(decode(&[0xff, 0xfe, 0x00]), (String::new(), true));
+    }
+
+    #[test]
+    fn each_member_is_decoded_in_turn() {
+        let mut bytes = compress("one\n").unwrap();
+        bytes.extend(compress("two\n").unwrap());
+        assert_eq!(decode(&bytes), ("one\ntwo\n".into(), false));
+    }
+
+    #[test]
+    fn a_damaged_member_keeps_what_came_before_it() {
+        let mut bytes = compress("one\n").unwrap();
+        let second = compress("two\n").unwrap();
+        bytes.extend_from_slice(&second[..second.len() - 4]);
+        assert_eq!(decode(&bytes), ("one\n".into(), true));
+
+        let mut flipped = compress("one\n").unwrap();
+        let crc = flipped.len() - 8;
+        flipped[crc] ^= 0xff;
+        assert_eq!(decode(&flipped), (String::new(), true));
+    }
+
+    #[test]
+    fn appending_starts_new_files_with_the_version() {
+        let path = temp_dir("append").join("log.tsv");
+        append(&path, &LOG, "one").unwrap();
+        append(&path, &LOG, "two").unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(&GZIP_MAGIC));
+        assert_eq!(read(&path, &LOG).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn appending_compresses_plain_files_first() {
+        let path = temp_dir("append-plain").join("log.tsv");
+        fs::write(&path, "#version 1\none\n").unwrap();
+        append(&path, &LOG, "two").unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(&GZIP_MAGIC));
+        assert_eq!(read(&path, &LOG).unwrap(), "one\ntwo\n");
+    }
+
+    #[test]
+    fn damaged_appends_keep_the_lines_before_them() {
+        let dir = temp_dir("recover-append");
+        let path = dir.join("log.tsv");
+        // Saved whole, so there's a backup, then added to since.
+        write(&path, &LOG, "one\n").unwrap();
+        write(&path, &LOG, "one\ntwo\n").unwrap();
+        append(&path, &LOG, "three").unwrap();
+        append(&path, &LOG, "four").unwrap();
+        truncate(&path);
+
+        let (contents, damaged) = read_checked(&path, &LOG).unwrap();
+        assert_eq!(contents, "one\ntwo\nthree\n");
+        let damaged = damaged.unwrap();
+        assert_eq!(damaged.recovery, Recovery::Salvaged);
+        assert_eq!(damaged.kept, dir.join("log.tsv.damaged.bak"));
+        assert!(damaged.kept.exists());
+        assert_eq!(read(&path, &LOG).unwrap(), "one\ntwo\nthree\n");
+        assert!(take_damaged().is_empty());
+    }
+
+    #[test]
+    fn damaged_saves_offer_the_backup_without_loading_it() {
+        let path = temp_dir("recover-save").join("log.tsv");
+        write(&path, &LOG, "one\n").unwrap();
+        write(&path, &LOG, "one\ntwo\n").unwrap();
+        truncate(&path);
+
+        assert_eq!(read(&path, &LOG).unwrap(), "");
+        let damaged = take_damaged();
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].recovery, Recovery::Backup);
+        assert_eq!(damaged[0].name(), "log.tsv");
+        assert!(take_damaged().is_empty());
+
+        damaged[0].load_backup().unwrap();
+        let (contents, damaged) = read_checked(&path, &LOG).unwrap();
+        assert_eq!(contents, "one\n");
+        assert!(damaged.is_none());
+    }
+
+    #[test]
+    fn starting_fresh_empties_a_damaged_file() {
+        let path = temp_dir("recover-fresh").join("log.tsv");
+        write(&path, &LOG, "one\n").unwrap();
+        write(&path, &LOG, "one\ntwo\n").unwrap();
+        truncate(&path);
+
+        let (_, damaged) = read_checked(&path, &LOG).unwrap();
+        let damaged = damaged.unwrap();
+        assert_eq!(damaged.recovery, Recovery::Backup);
+        damaged.start_fresh().unwrap();
+        let (contents, damaged) = read_checked(&path, &LOG).unwrap();
+        assert_eq!(contents, "");
+        assert!(damaged.is_none());
+    }
+
+    #[test]
+    fn damage_with_nothing_to_fall_back_on_starts_again() {
+        let path = temp_dir("recover-empty").join("log.tsv");
+        write(&path, &LOG, "one\n").unwrap();
+        truncate(&path);
+        let (contents, damaged) = read_checked(&path, &LOG).unwrap();
+        assert_eq!(contents, "");
+        assert_eq!(damaged.unwrap().recovery, Recovery::Emptied);
+    }
+
+    #[test]
+    fn files_from_newer_versions_are_left_alone() {
+        let path = temp_dir("newer").join("log.tsv");
+        fs::write(&path, "#version 9\none\n").unwrap();
+        assert!(read(&path, &LOG).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "#version 9\none\n");
+    }
+}
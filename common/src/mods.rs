@@ -0,0 +1,258 @@
+//! Small Lua mods that change how a game plays, loaded from the `mods/<game>` folder in the data
+//! directory.
+//!
+//! Each `.lua` file there is a mod. A mod defines whichever of these functions it needs as
+//! globals, and the game calls them in the order the files sort in:
+//!
+//! - `spawn(kind, x, y)` is asked before something is placed, like `"food"`, and returns `false`
+//!   to have it placed somewhere else.
+//! - `score(points, x, y)` is given the points something at a place is worth and returns what
+//!   it should be worth instead.
+//! - `tick(n)` is called every time the game moves on.
+//!
+//! A `game` table holds the game's name and the numbers it shares, like the board's `width` and
+//! `height`. So apples worth double near the walls are:
+//!
+//! ```lua
+//! function score(points, x, y)
+//!   if x == 0 or y == 0 or x == game.width - 1 or y == game.height - 1 then
+//!     return points * 2
+//!   end
+//!   return points
+//! end
+//! ```
+//!
+//! Mods only get Lua's string, table and math libraries, so they can't touch files or start
+//! programs. The base library is always loaded, so its `dofile`, `loadfile` and `load` are taken
+//! away too. Each call is stopped after `INSTRUCTION_LIMIT` instructions, and a mod can't hold
+//! more than `MEMORY_LIMIT` bytes. A mod that fails, runs too long or uses too much memory is
+//! turned off for the rest of the game.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, Value};
+
+/// How many instructions a mod can run in each call before it is stopped.
+pub const INSTRUCTION_LIMIT: u32 = 100_000;
+
+/// How much memory, in bytes, a mod can hold.
+pub const MEMORY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// The base library's functions that read files or compile code, which mods don't get.
+const REMOVED_GLOBALS: &[&str] = &["dofile", "loadfile", "load"];
+
+/// How often the instruction count is checked.
+const HOOK_INTERVAL: u32 = 1_000;
+
+/// The folder a game's mods are kept in.
+pub fn dir(game: &str) -> io::Result<PathBuf> {
+    Ok(crate::data_dir()?.join("mods").join(game))
+}
+
+/// One loaded mod.
+struct Mod {
+    name: String,
+    lua: Lua,
+    /// The instructions the current call has run, roughly.
+    instructions: Arc<AtomicU32>,
+}
+
+impl Mod {
+    fn load(path: &Path, game: &str, info: &[(&str, f64)]) -> mlua::Result<Self> {
+        let source = fs::read_to_string(path).map_err(mlua::Error::external)?;
+        let lua = Lua::new_with(
+            StdLib::STRING | StdLib::TABLE | StdLib::MATH,
+            LuaOptions::default(),
+        )?;
+        lua.set_memory_limit(MEMORY_LIMIT)?;
+        for name in REMOVED_GLOBALS {
+            lua.globals().set(*name, Value::Nil)?;
+        }
+
+        let instructions = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&instructions);
+        lua.set_hook(
+            HookTriggers {
+                every_nth_instruction: Some(HOOK_INTERVAL),
+                ..HookTriggers::default()
+            },
+            move |_, _| {
+                if counter.fetch_add(HOOK_INTERVAL, Ordering::Relaxed) >= INSTRUCTION_LIMIT {
+                    Err(mlua::Error::RuntimeError(format!(
+                        "ran for more than {} instructions",
+                        INSTRUCTION_LIMIT
+                    )))
+                } else {
+                    Ok(())
+                }
+            },
+        )?;
+
+        let table = lua.create_table()?;
+        table.set("name", game)?;
+        for (key, value) in info {
+            table.set(*key, *value)?;
+        }
+        lua.globals().set("game", table)?;
+        lua.load(&source)
+            .set_name(&path.to_string_lossy().as_bytes())?
+            .exec()?;
+        instructions.store(0, Ordering::Relaxed);
+
+        Ok(Mod {
+            name: path
+                .file_stem()
+                .map_or(String::new(), |stem| stem.to_string_lossy().into_owned()),
+            lua,
+            instructions,
+        })
+    }
+
+    /// Calls one of the mod's functions, if it defines it.
+    fn call<A, R>(&self, function: &str, args: A) -> mlua::Result<Option<R>>
+    where
+        A: for<'lua> mlua::ToLuaMulti<'lua>,
+        R: for<'lua> mlua::FromLuaMulti<'lua>,
+    {
+        let hook: Function = match self.lua.globals().get(function)? {
+            Value::Function(hook) => hook,
+            _ => return Ok(None),
+        };
+        self.instructions.store(0, Ordering::Relaxed);
+        hook.call(args).map(Some)
+    }
+}
+
+/// A game's mods.
+pub struct Mods {
+    mods: Vec<Mod>,
+}
+
+impl Mods {
+    /// Loads the mods for `game`, sharing `info` with them in the `game` table. A mod that
+    /// can't be loaded is left out.
+    pub fn load(game: &str, info: &[(&str, f64)]) -> Self {
+        let mut paths: Vec<PathBuf> = match dir(game).and_then(fs::read_dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "lua"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        let mut mods = Vec::new();
+        for path in paths {
+            match Mod::load(&path, game, info) {
+                Ok(loaded) => mods.push(loaded),
+                Err(e) => eprintln!("Couldn't load the mod {}: {}", path.display(), e),
+            }
+        }
+        Mods { mods }
+    }
+
+    /// No mods, for games played where mods aren't fair, like daily challenges.
+    pub fn none() -> Self {
+        Mods { mods: Vec::new() }
+    }
+
+    /// Whether there are any mods running.
+    pub fn is_empty(&self) -> bool {
+        self.mods.is_empty()
+    }
+
+    /// The names of the mods running, in the order they are called.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.mods.iter().map(|loaded| loaded.name.as_str())
+    }
+
+    /// Calls a function in each mod, turning off any that fail.
+    fn each<F>(&mut self, function: &str, mut call: F)
+    where
+        F: FnMut(&Mod) -> mlua::Result<()>,
+    {
+        self.mods.retain(|loaded| match call(loaded) {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!(
+                    "Turned off the mod {} after `{}` failed: {}",
+                    loaded.name, function, e
+                );
+                false
+            }
+        });
+    }
+
+    /// Whether every mod lets a `kind` of thing be placed at `(x, y)`.
+    pub fn allows_spawn(&mut self, kind: &str, x: i32, y: i32) -> bool {
+        let mut allowed = true;
+        self.each("spawn", |loaded| {
+            if allowed {
+                allowed = loaded.call("spawn", (kind, x, y))?.unwrap_or(true);
+            }
+            Ok(())
+        });
+        allowed
+    }
+
+    /// The points something at `(x, y)` is worth, after each mod has changed them in turn.
+    pub fn score(&mut self, points: f64, x: i32, y: i32) -> f64 {
+        let mut points = points;
+        self.each("score", |loaded| {
+            if let Some(changed) = loaded.call("score", (points, x, y))? {
+                points = changed;
+            }
+            Ok(())
+        });
+        points
+    }
+
+    /// Tells each mod the game has moved on, for the `n`th time.
+    pub fn tick(&mut self, n: u64) {
+        self.each("tick", |loaded| loaded.call::<_, ()>("tick", n).map(|_| ()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a mod of its own for a test to load.
+    fn temp_mod(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "quick-games-mods-{}-{}.lua",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, source).unwrap();
+        path
+    }
+
+    #[test]
+    fn mods_cant_load_files_or_code() {
+        let path = temp_mod(
+            "globals",
+            "function missing() return dofile == nil and loadfile == nil and load == nil end",
+        );
+        let loaded = Mod::load(&path, "snake", &[]).unwrap();
+        assert_eq!(loaded.call::<_, bool>("missing", ()).unwrap(), Some(true));
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn mods_using_too_much_memory_fail_to_load() {
+        // A single call, so the instruction limit doesn't stop it first.
+        let path = temp_mod("memory", "local bomb = string.rep('x', 64 * 1024 * 1024)");
+        match Mod::load(&path, "snake", &[]) {
+            Err(mlua::Error::MemoryError(_)) => (),
+            Err(e) => panic!("failed for the wrong reason: {}", e),
+            Ok(_) => panic!("loaded a mod over the memory limit"),
+        }
+        let _ = fs::remove_file(&path);
+    }
+}
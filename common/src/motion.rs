@@ -0,0 +1,28 @@
+//! Reduced motion, for players who find things moving about on screen uncomfortable or
+//! distracting. It's the profile's `reduced_motion` setting, set on the launcher's display
+//! screen along with the rest of the display settings.
+//!
+//! With it on, tweens jump straight to where they end and particle bursts aren't emitted. The
+//! modules that animate check it themselves, so games don't have to. None of the games shake
+//! the screen or animate between screens yet; anything that does should check `reduced` too.
+
+use std::cell::Cell;
+
+use crate::window::DisplaySettings;
+
+thread_local! {
+    /// Whether motion is reduced, once it has been looked up.
+    static REDUCED: Cell<Option<bool>> = Cell::new(None);
+}
+
+/// Whether the profile wants as little movement as possible. It's looked up the first time
+/// it's needed, so a change is picked up the next time a game starts.
+pub fn reduced() -> bool {
+    REDUCED.with(|reduced| {
+        reduced.get().unwrap_or_else(|| {
+            let on = DisplaySettings::load().reduced_motion;
+            reduced.set(Some(on));
+            on
+        })
+    })
+}
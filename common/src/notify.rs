@@ -0,0 +1,81 @@
+//! Announcing new records: achievements unlocked and personal bests beaten.
+//!
+//! They always get a toast, and when the profile's `desktop_notifications` setting is `true`
+//! a desktop notification as well, which is seen even when the game has no window on screen,
+//! like in a run played by a script. Like any setting it can be turned on for a run with
+//! `--desktop_notifications true`. It's off unless it's turned on.
+//!
+//! Notifications are sent with the system's own tools rather than a library: `notify-send` on
+//! Linux and the BSDs, `osascript` on macOS and PowerShell on Windows.
+
+use std::io;
+use std::process::Command;
+use std::thread;
+
+use crate::settings::Settings;
+use crate::toast;
+
+const DESKTOP_KEY: &str = "desktop_notifications";
+
+/// Whether the current profile wants desktop notifications.
+pub fn desktop_enabled() -> bool {
+    Settings::load().get_or(DESKTOP_KEY, false)
+}
+
+/// Shows a toast and, if the profile wants them, a desktop notification headed `title`.
+pub fn announce(title: &str, body: &str) {
+    toast::show(&format!("{}: {}", title, body));
+    if desktop_enabled() {
+        if let Err(e) = desktop(title, body) {
+            eprintln!("Couldn't send a desktop notification: {}", e);
+        }
+    }
+}
+
+/// Sends a desktop notification without waiting for it to be shown.
+pub fn desktop(title: &str, body: &str) -> io::Result<()> {
+    let mut child = command(title, body).spawn()?;
+    // Waited for on the side, so it doesn't linger as a zombie.
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn command(title: &str, body: &str) -> Command {
+    // Single quoted strings in PowerShell only need their quotes doubling.
+    let quote = |s: &str| format!("'{}'", s.replace('\'', "''"));
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         $icon = New-Object System.Windows.Forms.NotifyIcon; \
+         $icon.Icon = [System.Drawing.SystemIcons]::Information; \
+         $icon.Visible = $true; \
+         $icon.ShowBalloonTip(5000, {}, {}, 'Info'); \
+         Start-Sleep -Seconds 6; \
+         $icon.Dispose()",
+        quote(title),
+        quote(body)
+    );
+    let mut command = Command::new("powershell");
+    command.args(&["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script]);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn command(title: &str, body: &str) -> Command {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let script = format!(
+        "display notification {} with title {}",
+        quote(body),
+        quote(title)
+    );
+    let mut command = Command::new("osascript");
+    command.args(&["-e", &script]);
+    command
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(&["--app-name", "Quick Games", title, body]);
+    command
+}
@@ -0,0 +1,254 @@
+//! The screen drawn over a game while it is paused or once a run is over, so every game's pause
+//! and game over screens look and behave the same.
+//!
+//! An overlay is a dimmed backdrop with a panel in the middle holding a title, a few lines
+//! summing up the run and a menu of actions. With speech on, it's read out when it opens and
+//! the selected action is read out as it changes.
+//!
+//! `DamagedFiles` uses one to ask the player what to do with each saved file `migrate` found
+//! damaged, which `App` and the launcher show over everything else.
+
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use crate::input::Nav;
+use crate::migrate::{self, Damaged};
+use crate::speech;
+use crate::text::TextStyle;
+use crate::ui;
+
+const BACKDROP_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+const PANEL_COLOR: [f32; 4] = [0.1, 0.1, 0.13, 0.95];
+const BUTTON_COLOR: [f32; 4] = [0.22, 0.22, 0.28, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const SUMMARY_COLOR: [f32; 4] = [0.8, 0.8, 0.85, 1.0];
+
+const PANEL_WIDTH: f32 = 420.0;
+const PADDING: f32 = 24.0;
+const TITLE_HEIGHT: f32 = 56.0;
+const LINE_HEIGHT: f32 = 30.0;
+const BUTTON_HEIGHT: f32 = 44.0;
+const BUTTON_SPACING: f32 = 10.0;
+
+/// The actions on a game over screen, in the order games should handle them.
+pub const GAME_OVER_ACTIONS: &[&str] = &["Play again", "Quit"];
+/// The actions for a damaged file.
+const DAMAGED_ACTIONS: &[&str] = &["Load the backup", "Start fresh"];
+
+/// A pause or game over screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overlay {
+    pub title: String,
+    /// Lines summing up the run, like the score, shown under the title.
+    pub summary: Vec<String>,
+    /// The menu, from top to bottom. The first action is selected to begin with.
+    pub actions: &'static [&'static str],
+    pub selected: usize,
+}
+
+impl Overlay {
+    pub fn new(title: &str, summary: Vec<String>, actions: &'static [&'static str]) -> Self {
+        let overlay = Overlay {
+            title: title.to_string(),
+            summary,
+            actions,
+            selected: 0,
+        };
+        let mut spoken = vec![overlay.title.clone()];
+        spoken.extend(overlay.summary.iter().cloned());
+        spoken.extend(overlay.actions.first().map(|action| action.to_string()));
+        speech::say(&spoken.join(". "));
+        overlay
+    }
+
+    /// Moves the selection to `index`, reading the action out if it's a different one.
+    fn select(&mut self, index: usize) {
+        if index != self.selected {
+            self.selected = index;
+            if let Some(action) = self.actions.get(index) {
+                speech::say(action);
+            }
+        }
+    }
+
+    /// The panel, centred on the screen.
+    fn panel(&self, ctx: &Context) -> Rect {
+        let screen = graphics::screen_coordinates(ctx);
+        let width = PANEL_WIDTH.min(screen.w - PADDING * 2.0);
+        let height = PADDING * 2.0
+            + TITLE_HEIGHT
+            + self.summary.len() as f32 * LINE_HEIGHT
+            + PADDING
+            + self.actions.len() as f32 * (BUTTON_HEIGHT + BUTTON_SPACING)
+            - BUTTON_SPACING;
+        Rect::new(
+            screen.x + (screen.w - width) / 2.0,
+            screen.y + (screen.h - height) / 2.0,
+            width,
+            height,
+        )
+    }
+
+    fn action_rect(&self, ctx: &Context, index: usize) -> Rect {
+        let panel = self.panel(ctx);
+        let top =
+            panel.y + PADDING + TITLE_HEIGHT + self.summary.len() as f32 * LINE_HEIGHT + PADDING;
+        Rect::new(
+            panel.x + PADDING,
+            top + index as f32 * (BUTTON_HEIGHT + BUTTON_SPACING),
+            panel.w - PADDING * 2.0,
+            BUTTON_HEIGHT,
+        )
+    }
+
+    /// The action under `(x, y)`, if any.
+    pub fn action_at(&self, ctx: &Context, x: f32, y: f32) -> Option<usize> {
+        (0..self.actions.len()).find(|&index| {
+            self.action_rect(ctx, index)
+                .contains(ggez::mint::Point2 { x, y })
+        })
+    }
+
+    /// Selects whichever action the mouse is over.
+    pub fn mouse_motion(&mut self, ctx: &Context, x: f32, y: f32) {
+        if let Some(index) = self.action_at(ctx, x, y) {
+            self.select(index);
+        }
+    }
+
+    /// Moves the selection, returning the chosen action when `nav` confirms it. Going back is
+    /// left to the caller, as what it means depends on the screen.
+    pub fn navigate(&mut self, nav: Nav) -> Option<usize> {
+        match nav {
+            Nav::Up | Nav::Left => self.select(self.selected.saturating_sub(1)),
+            Nav::Down | Nav::Right => {
+                self.select((self.selected + 1).min(self.actions.len().saturating_sub(1)))
+            }
+            Nav::Confirm if !self.actions.is_empty() => return Some(self.selected),
+            _ => (),
+        }
+        None
+    }
+
+    /// Draws the overlay over whatever has been drawn so far this frame.
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let screen = graphics::screen_coordinates(ctx);
+        let panel = self.panel(ctx);
+
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(DrawMode::fill(), screen, BACKDROP_COLOR.into());
+        builder.rectangle(DrawMode::fill(), panel, PANEL_COLOR.into());
+        for index in 0..self.actions.len() {
+            builder.rectangle(
+                DrawMode::fill(),
+                self.action_rect(ctx, index),
+                BUTTON_COLOR.into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let x = panel.x + PADDING;
+        let mut y = panel.y + PADDING;
+        TextStyle::Title.draw(ctx, self.title.as_str(), TEXT_COLOR, x, y)?;
+        y += TITLE_HEIGHT;
+        for line in self.summary.iter() {
+            TextStyle::Body.draw(ctx, line.as_str(), SUMMARY_COLOR, x, y)?;
+            y += LINE_HEIGHT;
+        }
+
+        for (index, action) in self.actions.iter().enumerate() {
+            let rect = self.action_rect(ctx, index);
+            TextStyle::Body.draw(ctx, *action, TEXT_COLOR, rect.x + 14.0, rect.y + 12.0)?;
+            if index == self.selected {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Asks the player, one file at a time, whether to load the backup of each saved file found
+/// damaged or start it fresh.
+#[derive(Debug, Default)]
+pub struct DamagedFiles {
+    /// The files still to choose for, the first being asked about.
+    damaged: Vec<Damaged>,
+    overlay: Option<Overlay>,
+}
+
+impl DamagedFiles {
+    pub fn new() -> Self {
+        DamagedFiles::default()
+    }
+
+    /// Picks up the files found damaged since, asking about the next one if nothing is being
+    /// asked about already.
+    pub fn update(&mut self) {
+        self.damaged.extend(migrate::take_damaged());
+        if self.overlay.is_some() {
+            return;
+        }
+        if let Some(damaged) = self.damaged.first() {
+            let summary = vec![
+                format!("{} was damaged.", damaged.name()),
+                "Its backup has more in it.".to_string(),
+            ];
+            self.overlay = Some(Overlay::new("Damaged save", summary, DAMAGED_ACTIONS));
+        }
+    }
+
+    /// Whether a file is being asked about, which everything else waits for.
+    pub fn is_asking(&self) -> bool {
+        self.overlay.is_some()
+    }
+
+    /// Loads the backup or starts the file fresh, as the player chose.
+    fn choose(&mut self, action: usize) {
+        self.overlay = None;
+        let damaged = self.damaged.remove(0);
+        let result = match action {
+            0 => damaged.load_backup(),
+            _ => damaged.start_fresh(),
+        };
+        if let Err(e) = result {
+            eprintln!("Couldn't recover {}: {}", damaged.path.display(), e);
+        }
+    }
+
+    /// Moves the selection, choosing when `nav` confirms it. There's no going back, as the
+    /// file has to be one or the other.
+    pub fn navigate(&mut self, nav: Nav) {
+        if let Some(action) = self
+            .overlay
+            .as_mut()
+            .and_then(|overlay| overlay.navigate(nav))
+        {
+            self.choose(action);
+        }
+    }
+
+    /// Chooses the action under `(x, y)`, if any.
+    pub fn click(&mut self, ctx: &Context, x: f32, y: f32) {
+        if let Some(action) = self
+            .overlay
+            .as_ref()
+            .and_then(|overlay| overlay.action_at(ctx, x, y))
+        {
+            self.choose(action);
+        }
+    }
+
+    pub fn mouse_motion(&mut self, ctx: &Context, x: f32, y: f32) {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        match &self.overlay {
+            Some(overlay) => overlay.draw(ctx),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Simple particle effects.
+//!
+//! The particles are only simulated here; each game draws them however suits it. With reduced
+//! motion on, bursts don't emit any.
+
+use crate::motion;
+use crate::physics::Vec2;
+use rand::Rng;
+
+/// A single particle.
+#[derive(Debug, Copy, Clone)]
+pub struct Particle {
+    pub pos: Vec2,
+    pub velocity: Vec2,
+    /// Seconds left before the particle disappears.
+    pub life: f32,
+    pub max_life: f32,
+    pub color: [f32; 4],
+    pub size: f32,
+}
+
+impl Particle {
+    /// The particle's color, fading out over its life.
+    pub fn faded_color(&self) -> [f32; 4] {
+        let [r, g, b, a] = self.color;
+        [r, g, b, a * (self.life / self.max_life).max(0.0)]
+    }
+}
+
+/// A collection of particles that all move under the same gravity.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    pub gravity: Vec2,
+}
+
+impl ParticleSystem {
+    /// Creates a new empty particle system.
+    pub fn new(gravity: Vec2) -> Self {
+        ParticleSystem {
+            particles: Vec::new(),
+            gravity,
+        }
+    }
+
+    /// Emits `count` particles from `pos` in random directions at up to `speed`, unless motion
+    /// is reduced.
+    pub fn burst(
+        &mut self,
+        pos: Vec2,
+        count: usize,
+        speed: f32,
+        life: f32,
+        color: [f32; 4],
+        size: f32,
+    ) {
+        if motion::reduced() {
+            return;
+        }
+        let mut rng = rand::thread_rng();
+        for _ in 0..count {
+            let angle = rng.gen_range(0.0, std::f32::consts::PI * 2.0);
+            let life = life * rng.gen_range(0.5, 1.0);
+            self.particles.push(Particle {
+                pos,
+                velocity: Vec2::from_angle(angle) * rng.gen_range(speed * 0.2, speed),
+                life,
+                max_life: life,
+                color,
+                size,
+            });
+        }
+    }
+
+    /// Moves every particle on by `dt` seconds, removing any that have died.
+    pub fn update(&mut self, dt: f32) {
+        let gravity = self.gravity;
+        for particle in self.particles.iter_mut() {
+            particle.velocity += gravity * dt;
+            particle.pos += particle.velocity * dt;
+            particle.life -= dt;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
@@ -0,0 +1,233 @@
+//! A small 2D physics toolkit for circles bouncing off line segments and other circles.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A 2D vector.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Vec2 {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f32, y: f32) -> Self {
+        Vec2 { x, y }
+    }
+
+    /// Creates a unit vector pointing at `angle` radians.
+    pub fn from_angle(angle: f32) -> Self {
+        Vec2::new(angle.cos(), angle.sin())
+    }
+
+    pub fn dot(self, other: Vec2) -> f32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product of the two vectors.
+    pub fn cross(self, other: Vec2) -> f32 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    /// Returns the vector scaled to a length of one, or zero if it has no length.
+    pub fn normalize(self) -> Vec2 {
+        let length = self.length();
+        if length > 0.0 {
+            self * (1.0 / length)
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    /// Returns the vector rotated 90 degrees.
+    pub fn perp(self) -> Vec2 {
+        Vec2::new(-self.y, self.x)
+    }
+
+    /// Returns the vector rotated by `angle` radians.
+    pub fn rotate(self, angle: f32) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+        Vec2::new(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+    }
+
+    /// Returns the vector with its length clamped to at most `max`.
+    pub fn clamp_length(self, max: f32) -> Vec2 {
+        let length = self.length();
+        if length > max {
+            self * (max / length)
+        } else {
+            self
+        }
+    }
+
+    /// The angle of the vector in radians.
+    pub fn angle(self) -> f32 {
+        self.y.atan2(self.x)
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<f32> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, scale: f32) -> Vec2 {
+        Vec2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl From<(f32, f32)> for Vec2 {
+    fn from(v: (f32, f32)) -> Self {
+        Vec2::new(v.0, v.1)
+    }
+}
+
+/// A line segment between two points.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Segment {
+    pub a: Vec2,
+    pub b: Vec2,
+}
+
+impl Segment {
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Segment { a, b }
+    }
+
+    /// Returns the point on the segment closest to `point`.
+    pub fn closest_point(&self, point: Vec2) -> Vec2 {
+        let ab = self.b - self.a;
+        let length_squared = ab.length_squared();
+        if length_squared == 0.0 {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(ab) / length_squared)
+            .max(0.0)
+            .min(1.0);
+        self.a + ab * t
+    }
+}
+
+/// Where two shapes touch.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contact {
+    /// The point of contact on the surface that was hit.
+    pub point: Vec2,
+    /// The unit normal pointing from the surface towards the circle.
+    pub normal: Vec2,
+    /// How far the circle has sunk into the surface.
+    pub depth: f32,
+}
+
+/// Tests a circle against a segment with an optional `thickness` radius around it.
+pub fn circle_segment(
+    center: Vec2,
+    radius: f32,
+    segment: &Segment,
+    thickness: f32,
+) -> Option<Contact> {
+    let point = segment.closest_point(center);
+    let offset = center - point;
+    let distance = offset.length();
+    let reach = radius + thickness;
+    if distance >= reach {
+        return None;
+    }
+
+    // A circle centered exactly on the segment is pushed out along the segment's normal.
+    let normal = if distance > 0.0 {
+        offset * (1.0 / distance)
+    } else {
+        (segment.b - segment.a).perp().normalize()
+    };
+
+    Some(Contact {
+        point: point + normal * thickness,
+        normal,
+        depth: reach - distance,
+    })
+}
+
+/// Tests a circle against another circle.
+pub fn circle_circle(center: Vec2, radius: f32, other: Vec2, other_radius: f32) -> Option<Contact> {
+    let offset = center - other;
+    let distance = offset.length();
+    let reach = radius + other_radius;
+    if distance >= reach {
+        return None;
+    }
+
+    let normal = if distance > 0.0 {
+        offset * (1.0 / distance)
+    } else {
+        Vec2::new(0.0, -1.0)
+    };
+
+    Some(Contact {
+        point: other + normal * other_radius,
+        normal,
+        depth: reach - distance,
+    })
+}
+
+/// Reflects `velocity` off a surface with the given `normal`, moving at `surface_velocity`.
+///
+/// `restitution` controls how much of the speed into the surface is kept, `1.0` being a
+/// perfectly elastic bounce. Velocities already moving away from the surface are unchanged.
+pub fn bounce(velocity: Vec2, normal: Vec2, restitution: f32, surface_velocity: Vec2) -> Vec2 {
+    let relative = velocity - surface_velocity;
+    let into = relative.dot(normal);
+    if into >= 0.0 {
+        return velocity;
+    }
+    velocity - normal * ((1.0 + restitution) * into)
+}
+
+/// Wraps `point` around the edges of a `size` area starting at the origin, so anything leaving one
+/// side comes back in on the other.
+pub fn wrap(point: Vec2, size: Vec2) -> Vec2 {
+    Vec2::new(point.x.rem_euclid(size.x), point.y.rem_euclid(size.y))
+}
@@ -0,0 +1,114 @@
+//! How long the current profile has played each game, for the launcher's statistics.
+//!
+//! Totals are kept in the profile's directory as `game=seconds` lines. `App` tracks the game it
+//! wraps, counting only time spent playing rather than paused or on a break, and adds it to the
+//! totals every so often and once more when the game closes.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::migrate::{self, PLAYTIME};
+use crate::profile;
+use crate::registry;
+
+const PLAYTIME_FILE: &str = "playtime.txt";
+
+/// How often the time played so far is saved.
+const SAVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Longer gaps between updates than this aren't counted, as the game was most likely suspended.
+const MAX_STEP: Duration = Duration::from_secs(1);
+
+fn path() -> io::Result<PathBuf> {
+    Ok(profile::profile_dir()?.join(PLAYTIME_FILE))
+}
+
+/// The seconds played of each game, most played first.
+pub fn load() -> io::Result<Vec<(String, u64)>> {
+    let contents = match migrate::read(&path()?, &PLAYTIME) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut totals: Vec<(String, u64)> = contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '=');
+            let game = parts.next()?.trim();
+            let seconds = parts.next()?.trim().parse().ok()?;
+            Some((game.to_string(), seconds))
+        })
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1));
+    Ok(totals)
+}
+
+/// Adds to the seconds played of a game.
+pub fn add(game: &str, seconds: u64) -> io::Result<()> {
+    let mut totals = load()?;
+    match totals.iter_mut().find(|(name, _)| name == game) {
+        Some(total) => total.1 += seconds,
+        None => totals.push((game.to_string(), seconds)),
+    }
+    let contents: String = totals
+        .iter()
+        .map(|(game, seconds)| format!("{}={}\n", game, seconds))
+        .collect();
+    migrate::write(&path()?, &PLAYTIME, &contents)
+}
+
+/// Counts the time played of the running game.
+#[derive(Debug)]
+pub struct Tracker {
+    /// The running game, unless it isn't one in the registry.
+    game: Option<&'static str>,
+    /// Time played that hasn't been saved yet.
+    unsaved: Duration,
+    last_update: Instant,
+    last_save: Instant,
+}
+
+impl Tracker {
+    /// Tracks the running game.
+    pub fn new() -> Self {
+        Tracker {
+            game: registry::running().map(|game| game.name),
+            unsaved: Duration::from_secs(0),
+            last_update: Instant::now(),
+            last_save: Instant::now(),
+        }
+    }
+
+    /// Counts the time since the last update if the game is being played. Call it every frame.
+    pub fn update(&mut self, playing: bool) {
+        let step = self.last_update.elapsed();
+        self.last_update = Instant::now();
+        if playing && step <= MAX_STEP {
+            self.unsaved += step;
+        }
+        if self.last_save.elapsed() >= SAVE_INTERVAL {
+            self.save();
+        }
+    }
+
+    /// Adds the whole seconds played since the last save to the totals.
+    pub fn save(&mut self) {
+        self.last_save = Instant::now();
+        let game = match self.game {
+            Some(game) => game,
+            None => return,
+        };
+        let seconds = self.unsaved.as_secs();
+        if seconds > 0 && add(game, seconds).is_ok() {
+            self.unsaved -= Duration::from_secs(seconds);
+        }
+    }
+}
+
+/// Saves whatever was played since the last save once the game closes.
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        self.save();
+    }
+}
@@ -0,0 +1,111 @@
+//! Games built as WebAssembly modules, which the launcher lists after the built in games and
+//! the `plugin` host runs, so new games can be added without rebuilding the workspace.
+//!
+//! A plugin is a `.wasm` file in the `plugins` folder of the data directory, with an optional
+//! `.txt` file of the same name describing it in `key: value` lines:
+//!
+//! ```text
+//! title: Breakout
+//! description: Knock out the bricks with a bouncing ball.
+//! tags: arcade, paddle
+//! ```
+//!
+//! The module talks to the host through a small interface. It exports:
+//!
+//! - `init(seed: i64)`, called once before anything else.
+//! - `update(dt: f32)`, called every frame with the seconds since the last.
+//! - `draw()`, called every frame after `update` to draw through the host's functions.
+//! - `input(button: i32, pressed: i32)`, called when one of the `BUTTONS` is pressed (`1`) or
+//!   released (`0`).
+//! - `memory`, its memory, for the host to read strings from.
+//!
+//! And it may import these from `env`, with colours packed as `0xRRGGBBAA`:
+//!
+//! - `clear(color: i32)` fills the screen.
+//! - `rect(x: f32, y: f32, w: f32, h: f32, color: i32)`
+//! - `circle(x: f32, y: f32, radius: f32, color: i32)`
+//! - `text(ptr: i32, len: i32, x: f32, y: f32, size: f32, color: i32)` draws UTF-8 text from
+//!   the module's memory.
+//!
+//! The screen is `SCREEN_SIZE` pixels, with the origin in the top left corner.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The size of the screen plugins draw to.
+pub const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+/// The buttons passed to a plugin's `input`, by their number: the directions, from the arrows,
+/// WASD or a d-pad, and the action button, from Enter, Space or A. Escape and B close the game.
+pub const BUTTONS: &[&str] = &["up", "down", "left", "right", "action"];
+
+/// The folder plugins are installed in.
+pub fn dir() -> io::Result<PathBuf> {
+    Ok(crate::data_dir()?.join("plugins"))
+}
+
+/// An installed plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    /// The file's name without the extension.
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    /// Where the module is.
+    pub path: PathBuf,
+}
+
+impl Plugin {
+    /// Reads a plugin's description, falling back to its name if it doesn't have one.
+    pub fn load(path: &Path) -> Self {
+        let name = path
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        let mut plugin = Plugin {
+            title: name.clone(),
+            name,
+            description: String::new(),
+            tags: vec!["plugin".to_string()],
+            path: path.to_path_buf(),
+        };
+
+        let contents = fs::read_to_string(path.with_extension("txt")).unwrap_or_default();
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim();
+            let value = match parts.next() {
+                Some(value) => value.trim(),
+                None => continue,
+            };
+            match key {
+                "title" => plugin.title = value.to_string(),
+                "description" => plugin.description = value.to_string(),
+                "tags" => plugin
+                    .tags
+                    .extend(value.split(',').map(|tag| tag.trim().to_string())),
+                _ => (),
+            }
+        }
+        plugin
+    }
+}
+
+/// The installed plugins, by file name.
+pub fn list() -> io::Result<Vec<Plugin>> {
+    let entries = match fs::read_dir(dir()?) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().map_or(false, |ext| ext == "wasm") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths.iter().map(|path| Plugin::load(path)).collect())
+}
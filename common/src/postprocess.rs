@@ -0,0 +1,412 @@
+//! Post-processing: drawing each frame through a fragment shader before it's shown, for effects
+//! like a CRT's scanlines.
+//!
+//! It's off unless the profile's `post_shader` setting is the path of a GLSL fragment shader.
+//! `App` has the game draw into a canvas the size of the window, and `ui::present` draws the
+//! canvas to the window through the shader, before the toasts and the switch scanning actions,
+//! which are drawn as they are. The shader is given what ggez's own is, the frame as `t_Texture`
+//! and where to read it from as `v_Uv`, and has no uniforms of its own. For example:
+//!
+//! ```glsl
+//! #version 150 core
+//!
+//! uniform sampler2D t_Texture;
+//! in vec2 v_Uv;
+//! in vec4 v_Color;
+//! out vec4 Target0;
+//!
+//! void main() {
+//!     vec4 color = texture(t_Texture, v_Uv) * v_Color;
+//!     // Every other row of pixels is darker, like a CRT's scanlines.
+//!     float scanline = mod(floor(gl_FragCoord.y), 2.0) == 0.0 ? 0.7 : 1.0;
+//!     Target0 = vec4(color.rgb * scanline, color.a);
+//! }
+//! ```
+//!
+//! In debug builds the file is watched like the assets are, and compiled again when it changes,
+//! so a shader can be tweaked without restarting the game and losing the run. A shader that
+//! doesn't compile leaves the last one that did in place, and the compiler's error is shown over
+//! the game until it's fixed. Release builds load it once, when the game starts.
+
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use ggez::conf::NumSamples;
+use ggez::graphics::{self, Canvas, DrawParam, EmptyConst, Rect, Shader};
+use ggez::{Context, GameResult};
+
+use crate::settings::Settings;
+use crate::ui;
+
+/// The setting holding the path of the fragment shader.
+pub const SHADER_SETTING: &str = "post_shader";
+
+/// How often the shader is checked for changes.
+const CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ggez's own vertex shader, which the fragment shader is paired with.
+const VERTEX_SHADER: &str = "#version 150 core
+
+in vec2 a_Pos;
+in vec2 a_Uv;
+
+in vec4 a_Src;
+in vec4 a_TCol1;
+in vec4 a_TCol2;
+in vec4 a_TCol3;
+in vec4 a_TCol4;
+in vec4 a_Color;
+
+layout (std140) uniform Globals {
+    mat4 u_MVP;
+};
+
+out vec2 v_Uv;
+out vec4 v_Color;
+
+void main() {
+    v_Uv = a_Uv * a_Src.zw + a_Src.xy;
+    v_Color = a_Color;
+    mat4 instance_transform = mat4(a_TCol1, a_TCol2, a_TCol3, a_TCol4);
+    vec4 position = instance_transform * vec4(a_Pos, 0.0, 1.0);
+    gl_Position = u_MVP * position;
+}
+";
+
+const ERROR_COLOR: [f32; 4] = [1.0, 0.35, 0.35, 1.0];
+const ERROR_SIZE: f32 = 16.0;
+const ERROR_MARGIN: f32 = 8.0;
+
+/// The last shader that compiled, and why the file last failed to compile, until it compiles.
+/// It's generic over the shader so it can be tested without a window.
+struct Compiled<S> {
+    shader: Option<S>,
+    error: Option<String>,
+}
+
+impl<S> Compiled<S> {
+    fn new() -> Self {
+        Compiled {
+            shader: None,
+            error: None,
+        }
+    }
+
+    /// Takes the result of compiling the file again, keeping the last shader if it failed.
+    fn update(&mut self, result: Result<S, String>) {
+        match result {
+            Ok(shader) => {
+                self.shader = Some(shader);
+                self.error = None;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Notices the shader's file changing, looking at most every `CHECK_INTERVAL`.
+struct Watch {
+    path: PathBuf,
+    /// When the file was last changed, as of the last look.
+    modified: Option<SystemTime>,
+    last_check: Instant,
+}
+
+impl Watch {
+    fn new(path: PathBuf) -> Self {
+        Watch {
+            modified: modified(&path),
+            path,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Whether the file has changed, or gone, since the last time it had.
+    fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_INTERVAL {
+            return false;
+        }
+        self.last_check = Instant::now();
+        let modified = modified(&self.path);
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+/// Whether the frame being drawn is going into the canvas, so that `finish` only draws out
+/// frames `begin` started, and each of them once.
+#[derive(Default)]
+struct Frame {
+    drawing: bool,
+}
+
+impl Frame {
+    fn start(&mut self) {
+        self.drawing = true;
+    }
+
+    /// Whether there's a frame to draw out, which there is once after each start.
+    fn end(&mut self) -> bool {
+        std::mem::replace(&mut self.drawing, false)
+    }
+}
+
+/// The shader and the canvas the frame is drawn into for it.
+struct Pipeline {
+    watch: Watch,
+    compiled: Compiled<Shader<EmptyConst>>,
+    canvas: Option<Canvas>,
+    frame: Frame,
+}
+
+enum State {
+    /// The settings haven't been read yet, which waits for the first frame.
+    Unloaded,
+    Off,
+    On(Box<Pipeline>),
+}
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::Unloaded);
+}
+
+/// When a file was last changed, if it can be told.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Compiles the fragment shader at `path`, saying what went wrong if it doesn't.
+fn compile(ctx: &mut Context, path: &Path) -> Result<Shader<EmptyConst>, String> {
+    let source = fs::read(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    Shader::from_u8(
+        ctx,
+        VERTEX_SHADER.as_bytes(),
+        &source,
+        EmptyConst,
+        "Empty",
+        None,
+    )
+    .map_err(|e| format!("Couldn't compile {}: {}", path.display(), e))
+}
+
+impl Pipeline {
+    fn new(ctx: &mut Context, path: PathBuf) -> Self {
+        let mut pipeline = Pipeline {
+            watch: Watch::new(path),
+            compiled: Compiled::new(),
+            canvas: None,
+            frame: Frame::default(),
+        };
+        pipeline.compile(ctx);
+        pipeline
+    }
+
+    /// Compiles the shader again, keeping the last one if it doesn't compile.
+    fn compile(&mut self, ctx: &mut Context) {
+        let result = compile(ctx, &self.watch.path);
+        self.compiled.update(result);
+    }
+
+    /// Compiles the shader again if it's changed since the last check, in debug builds.
+    fn reload_changed(&mut self, ctx: &mut Context) {
+        if cfg!(debug_assertions) && self.watch.changed() {
+            self.compile(ctx);
+        }
+    }
+
+    fn begin(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.reload_changed(ctx);
+        let (width, height) = graphics::drawable_size(ctx);
+        let size = (width.max(1.0) as u16, height.max(1.0) as u16);
+        let resized = self.canvas.as_ref().map_or(true, |canvas| {
+            (canvas.image().width(), canvas.image().height()) != size
+        });
+        if resized {
+            self.canvas = Some(Canvas::new(ctx, size.0, size.1, NumSamples::One)?);
+        }
+        graphics::set_canvas(ctx, self.canvas.as_ref());
+        self.frame.start();
+        Ok(())
+    }
+
+    fn finish(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if !self.frame.end() {
+            return Ok(());
+        }
+        let canvas = match &self.canvas {
+            Some(canvas) => canvas,
+            None => return Ok(()),
+        };
+        graphics::set_canvas(ctx, None);
+
+        let screen = graphics::screen_coordinates(ctx);
+        let (width, height) = (canvas.image().width(), canvas.image().height());
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width.into(), height.into()))?;
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        let drawn = {
+            let _shader = self
+                .compiled
+                .shader
+                .as_ref()
+                .map(|shader| graphics::use_shader(ctx, shader));
+            graphics::draw(ctx, canvas, DrawParam::default())
+        };
+        graphics::set_screen_coordinates(ctx, screen)?;
+        drawn?;
+
+        if let Some(error) = &self.compiled.error {
+            ui::draw_wrapped(
+                ctx,
+                error,
+                ERROR_COLOR,
+                ERROR_SIZE,
+                screen.x + ERROR_MARGIN,
+                screen.y + ERROR_MARGIN,
+                screen.w - ERROR_MARGIN * 2.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Has the frame drawn into the canvas for the shader, if there is one, compiling it again
+/// first if it's changed. `App` calls it before drawing each frame.
+pub fn begin(ctx: &mut Context) -> GameResult<()> {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if let State::Unloaded = *state {
+            *state = match Settings::load().get(SHADER_SETTING).map(str::trim) {
+                Some(path) if !path.is_empty() => {
+                    State::On(Box::new(Pipeline::new(ctx, PathBuf::from(path))))
+                }
+                _ => State::Off,
+            };
+        }
+        match &mut *state {
+            State::On(pipeline) => pipeline.begin(ctx),
+            _ => Ok(()),
+        }
+    })
+}
+
+/// Draws the frame from the canvas to the window through the shader, with any error compiling
+/// it over the top. `ui::present` calls it before drawing anything that isn't post-processed.
+pub fn finish(ctx: &mut Context) -> GameResult<()> {
+    STATE.with(|state| match &mut *state.borrow_mut() {
+        State::On(pipeline) => pipeline.finish(ctx),
+        _ => Ok(()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A shader file of its own for a test to change.
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "quick-games-postprocess-{}-{}.glsl",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, "void main() {}").unwrap();
+        path
+    }
+
+    /// Lets the watch look at the file again straight away.
+    fn look_now(watch: &mut Watch) {
+        watch.last_check = Instant::now()
+            .checked_sub(CHECK_INTERVAL)
+            .unwrap_or(watch.last_check);
+    }
+
+    #[test]
+    fn a_failed_recompile_keeps_the_last_shader_and_shows_why() {
+        let mut compiled = Compiled::new();
+        compiled.update(Ok("first"));
+        compiled.update(Err("line 3: expected `;`".to_string()));
+        assert_eq!(compiled.shader, Some("first"));
+        assert_eq!(compiled.error.as_deref(), Some("line 3: expected `;`"));
+
+        compiled.update(Ok("second"));
+        assert_eq!(compiled.shader, Some("second"));
+        assert_eq!(compiled.error, None);
+    }
+
+    #[test]
+    fn a_shader_that_never_compiled_leaves_the_frame_as_it_is() {
+        let mut compiled = Compiled::<&str>::new();
+        compiled.update(Err("no main".to_string()));
+        assert_eq!(compiled.shader, None);
+        assert!(compiled.error.is_some());
+    }
+
+    #[test]
+    fn the_file_is_only_looked_at_every_interval() {
+        let path = temp_file("interval");
+        let mut watch = Watch::new(path.clone());
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert!(!watch.changed());
+        look_now(&mut watch);
+        assert!(watch.changed());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn changes_are_noticed_once() {
+        let path = temp_file("once");
+        let mut watch = Watch::new(path.clone());
+        look_now(&mut watch);
+        assert!(!watch.changed());
+
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        look_now(&mut watch);
+        assert!(watch.changed());
+        look_now(&mut watch);
+        assert!(!watch.changed());
+
+        fs::remove_file(&path).unwrap();
+        look_now(&mut watch);
+        assert!(watch.changed());
+    }
+
+    #[test]
+    fn a_frame_is_only_drawn_out_after_it_was_started() {
+        let mut frame = Frame::default();
+        assert!(!frame.end());
+        frame.start();
+        assert!(frame.end());
+        assert!(!frame.end());
+    }
+
+    #[test]
+    fn a_frame_that_was_never_presented_is_drawn_out_with_the_next() {
+        // A game's draw that fails before `ui::present` leaves its frame started, and `App`
+        // starts the next one over it.
+        let mut frame = Frame::default();
+        frame.start();
+        frame.start();
+        assert!(frame.end());
+        assert!(!frame.end());
+    }
+}
@@ -0,0 +1,59 @@
+//! Player profiles, which keep each player's saved data apart.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::args;
+
+/// The directory in the shared data directory that holds each profile's directory.
+const PROFILES_DIR: &str = "profiles";
+
+/// The profile used when no `--profile` argument is given.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Returns the name of the profile given by the `--profile` argument, or the default profile.
+///
+/// Anything other than letters, digits, `-` and `_` is dropped so the name is safe to use as a
+/// directory name.
+pub fn current() -> String {
+    let name: String = args::value("profile")
+        .map(|name| {
+            name.chars()
+                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if name.is_empty() {
+        DEFAULT_PROFILE.to_string()
+    } else {
+        name
+    }
+}
+
+/// Returns the directory the current profile's data is kept in, creating it if it doesn't
+/// exist yet.
+pub fn profile_dir() -> io::Result<PathBuf> {
+    let dir = crate::data_dir()?.join(PROFILES_DIR).join(current());
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The names of the profiles that have saved anything, in alphabetical order.
+pub fn list() -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(crate::data_dir()?.join(PROFILES_DIR)) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
@@ -0,0 +1,691 @@
+//! The games in the collection, for the launcher and anything else that needs to know what is
+//! installed.
+
+use std::env;
+
+use crate::achievements::{Achievement, Goal};
+
+/// What the launcher knows about a game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GameInfo {
+    /// The crate and executable name, which is also the name its runs are recorded under.
+    pub name: &'static str,
+    pub title: &'static str,
+    /// A sentence or two for the game's card.
+    pub description: &'static str,
+    pub tags: &'static [&'static str],
+    /// The modes it can be started in with `--mode`, as the game names them.
+    pub modes: &'static [&'static str],
+    /// The thumbnail's path in the launcher's resources.
+    pub thumbnail: &'static str,
+    /// The card's accent colour, which also fills in for a missing thumbnail.
+    pub color: [f32; 4],
+    /// The recorded value runs are ranked by, for games that record their runs.
+    pub score: Option<ScoreKey>,
+    /// The achievements the game unlocks from its recorded runs.
+    pub achievements: &'static [Achievement],
+}
+
+impl GameInfo {
+    /// Whether the game is played against an opponent, the AI or another player, which the
+    /// games tagged `versus` are. Their runs record whether they were `won`.
+    pub fn has_opponents(&self) -> bool {
+        self.tags.contains(&"versus")
+    }
+}
+
+/// Which of a game's recorded values its runs are ranked by.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScoreKey {
+    /// The key the value is recorded under in the stats store.
+    pub key: &'static str,
+    pub label: &'static str,
+    pub lower_is_better: bool,
+}
+
+impl ScoreKey {
+    /// The key games record their points under. Only points count towards the arcade total,
+    /// since other measures like distances and move counts can't be added to them.
+    pub const POINTS: ScoreKey = ScoreKey {
+        key: "score",
+        label: "Score",
+        lower_is_better: false,
+    };
+
+    /// Whether the value counts towards the arcade total.
+    pub fn is_points(&self) -> bool {
+        *self == ScoreKey::POINTS
+    }
+
+    /// Whether value `a` ranks above value `b`.
+    pub fn beats(&self, a: f64, b: f64) -> bool {
+        if self.lower_is_better {
+            a < b
+        } else {
+            a > b
+        }
+    }
+}
+
+/// Every game in the collection, in alphabetical order by name.
+pub const GAMES: &[GameInfo] = &[
+    GameInfo {
+        name: "airhockey",
+        title: "Air Hockey",
+        description: "Knock the puck past the AI's mallet into its goal.",
+        tags: &["arcade", "physics", "versus"],
+        modes: &["Easy", "Medium", "Hard"],
+        thumbnail: "/thumbnails/airhockey.png",
+        color: [0.3, 0.6, 0.9, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "artillery",
+        title: "Artillery",
+        description: "Take turns lobbing shells over rolling terrain at the other tank.",
+        tags: &["strategy", "physics", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/artillery.png",
+        color: [0.55, 0.5, 0.3, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "asteroids",
+        title: "Asteroids",
+        description: "Blast drifting rocks into rubble, alone or with a friend sharing lives.",
+        tags: &["arcade", "shooter", "co-op"],
+        modes: &[],
+        thumbnail: "/thumbnails/asteroids.png",
+        color: [0.6, 0.6, 0.65, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "deep_space",
+                title: "Deep Space",
+                description: "Reach wave 5.",
+                goal: Goal::AtLeast("wave", 5.0),
+            },
+            Achievement {
+                id: "rock_breaker",
+                title: "Rock Breaker",
+                description: "Score 10,000 points.",
+                goal: Goal::AtLeast("score", 10000.0),
+            },
+            Achievement {
+                id: "wingmen",
+                title: "Wingmen",
+                description: "Play a game with a friend.",
+                goal: Goal::AtLeast("players", 2.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "bomber",
+        title: "Bomber",
+        description: "Drop bombs in a maze of crates and catch your rivals in the blast.",
+        tags: &["arcade", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/bomber.png",
+        color: [0.9, 0.4, 0.2, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "bubbles",
+        title: "Bubble Shooter",
+        description: "Aim and bank bubbles to pop groups of three or more.",
+        tags: &["puzzle", "casual"],
+        modes: &[],
+        thumbnail: "/thumbnails/bubbles.png",
+        color: [0.4, 0.75, 0.95, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "cave",
+        title: "Cave",
+        description: "Dig for diamonds while boulders tumble down around you.",
+        tags: &["arcade", "puzzle"],
+        modes: &[],
+        thumbnail: "/thumbnails/cave.png",
+        color: [0.6, 0.45, 0.3, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "centipede",
+        title: "Centipede",
+        description:
+            "Shoot the centipede apart through a field of mushrooms, watching for spiders.",
+        tags: &["arcade", "shooter"],
+        modes: &[],
+        thumbnail: "/thumbnails/centipede.png",
+        color: [0.5, 0.85, 0.35, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "climber",
+        title: "Climber",
+        description: "Bounce ever upwards on platforms that move, crumble and spring.",
+        tags: &["arcade", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/climber.png",
+        color: [0.45, 0.8, 0.6, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "off_the_ground",
+                title: "Off the Ground",
+                description: "Score 100 points.",
+                goal: Goal::AtLeast("score", 100.0),
+            },
+            Achievement {
+                id: "head_in_the_clouds",
+                title: "Head in the Clouds",
+                description: "Score 1,000 points.",
+                goal: Goal::AtLeast("score", 1000.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "columns",
+        title: "Columns",
+        description: "Drop columns of jewels and line up three of a kind in any direction.",
+        tags: &["puzzle", "falling blocks"],
+        modes: &[],
+        thumbnail: "/thumbnails/columns.png",
+        color: [0.75, 0.4, 0.85, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "coopbreakout",
+        title: "Co-op Breakout",
+        description: "Two paddles, one brick field and a shared pool of lives.",
+        tags: &["arcade", "co-op"],
+        modes: &[],
+        thumbnail: "/thumbnails/coopbreakout.png",
+        color: [0.35, 0.65, 1.0, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "brick_by_brick",
+                title: "Brick by Brick",
+                description: "Score 1,000 points.",
+                goal: Goal::AtLeast("score", 1000.0),
+            },
+            Achievement {
+                id: "teamwork",
+                title: "Teamwork",
+                description: "Reach level 3.",
+                goal: Goal::AtLeast("level", 3.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "copter",
+        title: "Cave Copter",
+        description: "Hold one button to climb through a narrowing cave.",
+        tags: &["arcade", "one button", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/copter.png",
+        color: [1.0, 0.85, 0.2, 1.0],
+        score: Some(ScoreKey {
+            key: "distance",
+            label: "Distance (m)",
+            lower_is_better: false,
+        }),
+        achievements: &[
+            Achievement {
+                id: "cave_diver",
+                title: "Cave Diver",
+                description: "Fly 500 metres.",
+                goal: Goal::AtLeast("distance", 500.0),
+            },
+            Achievement {
+                id: "long_haul",
+                title: "Long Haul",
+                description: "Fly 2,000 metres.",
+                goal: Goal::AtLeast("distance", 2000.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "digger",
+        title: "Digger",
+        description: "Tunnel through the earth and pump up the monsters chasing you.",
+        tags: &["arcade"],
+        modes: &[],
+        thumbnail: "/thumbnails/digger.png",
+        color: [0.8, 0.55, 0.25, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "dodger",
+        title: "Dodger",
+        description: "Weave between falling blocks for as long as you can.",
+        tags: &["arcade", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/dodger.png",
+        color: [0.9, 0.3, 0.35, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "dotsboxes",
+        title: "Dots and Boxes",
+        description: "Draw lines to close boxes, against a friend or a chain-counting AI.",
+        tags: &["board", "strategy", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/dotsboxes.png",
+        color: [0.95, 0.95, 0.9, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "fifteen",
+        title: "15 Puzzle",
+        description: "Slide the tiles back into order, with a solver on hand for hints.",
+        tags: &["puzzle"],
+        modes: &[],
+        thumbnail: "/thumbnails/fifteen.png",
+        color: [0.75, 0.55, 0.35, 1.0],
+        score: Some(ScoreKey {
+            key: "moves",
+            label: "Moves",
+            lower_is_better: true,
+        }),
+        achievements: &[
+            Achievement {
+                id: "no_help_needed",
+                title: "No Help Needed",
+                description: "Solve a puzzle without hints.",
+                goal: Goal::AtMost("hints", 0.0),
+            },
+            Achievement {
+                id: "efficient",
+                title: "Efficient",
+                description: "Solve a puzzle in 80 moves or fewer.",
+                goal: Goal::AtMost("moves", 80.0),
+            },
+            Achievement {
+                id: "quick_fingers",
+                title: "Quick Fingers",
+                description: "Solve a puzzle in under two minutes.",
+                goal: Goal::AtMost("seconds", 120.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "galaga",
+        title: "Galaga",
+        description: "Shoot down waves of diving alien formations.",
+        tags: &["arcade", "shooter"],
+        modes: &[],
+        thumbnail: "/thumbnails/galaga.png",
+        color: [0.3, 0.3, 0.9, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "gems",
+        title: "Gems",
+        description: "Swap neighbouring gems to make matches and set off cascades.",
+        tags: &["puzzle", "casual"],
+        modes: &[],
+        thumbnail: "/thumbnails/gems.png",
+        color: [0.95, 0.45, 0.7, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "go",
+        title: "9x9 Go",
+        description: "Surround territory on a small board, hot-seat or against a Monte Carlo AI.",
+        tags: &["board", "strategy", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/go.png",
+        color: [0.85, 0.68, 0.4, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "hopper",
+        title: "Road Hopper",
+        description: "Hop across roads, rivers and railways without getting squashed.",
+        tags: &["arcade", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/hopper.png",
+        color: [0.4, 0.8, 0.3, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "across_the_road",
+                title: "Across the Road",
+                description: "Reach row 25.",
+                goal: Goal::AtLeast("score", 25.0),
+            },
+            Achievement {
+                id: "long_way_home",
+                title: "Long Way Home",
+                description: "Reach row 100.",
+                goal: Goal::AtLeast("score", 100.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "lander",
+        title: "Lunar Lander",
+        description: "Feather the thrusters to touch down gently on the landing pads.",
+        tags: &["arcade", "physics"],
+        modes: &[],
+        thumbnail: "/thumbnails/lander.png",
+        color: [0.7, 0.7, 0.75, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "mahjong",
+        title: "Mahjong Solitaire",
+        description: "Clear the stacked tiles by pairing free matching tiles.",
+        tags: &["puzzle", "casual"],
+        modes: &[],
+        thumbnail: "/thumbnails/mahjong.png",
+        color: [0.3, 0.6, 0.4, 1.0],
+        score: Some(ScoreKey {
+            key: "time",
+            label: "Time (s)",
+            lower_is_better: true,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "minigolf",
+        title: "Minigolf",
+        description: "Putt around slopes, water and walls over a nine hole course.",
+        tags: &["physics", "casual"],
+        modes: &[],
+        thumbnail: "/thumbnails/minigolf.png",
+        color: [0.3, 0.75, 0.35, 1.0],
+        score: Some(ScoreKey {
+            key: "strokes",
+            label: "Strokes",
+            lower_is_better: true,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "nim",
+        title: "Nim",
+        description: "Take turns removing counters, with a mode that explains the winning move.",
+        tags: &["strategy", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/nim.png",
+        color: [0.6, 0.5, 0.8, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "pinball",
+        title: "Pinball",
+        description: "Keep the ball alive with the flippers and light up the bumpers.",
+        tags: &["arcade", "physics"],
+        modes: &[],
+        thumbnail: "/thumbnails/pinball.png",
+        color: [0.9, 0.3, 0.6, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "pipes",
+        title: "Pipe Mania",
+        description: "Lay pipe pieces ahead of the flowing water.",
+        tags: &["puzzle"],
+        modes: &[],
+        thumbnail: "/thumbnails/pipes.png",
+        color: [0.4, 0.85, 0.5, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "platformer",
+        title: "Platformer",
+        description: "Collect every coin to open the exit on each single screen level.",
+        tags: &["platformer"],
+        modes: &[],
+        thumbnail: "/thumbnails/platformer.png",
+        color: [0.45, 0.6, 0.95, 1.0],
+        score: Some(ScoreKey {
+            key: "time",
+            label: "Time (s)",
+            lower_is_better: true,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "poker",
+        title: "Video Poker",
+        description: "Jacks or Better: hold, draw and chase the royal flush.",
+        tags: &["cards", "casino"],
+        modes: &[],
+        thumbnail: "/thumbnails/poker.png",
+        color: [0.2, 0.55, 0.3, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "pong",
+        title: "Pong",
+        description: "The original bat and ball game for two.",
+        tags: &["arcade", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/pong.png",
+        color: [0.9, 0.9, 0.9, 1.0],
+        score: None,
+        achievements: &[],
+    },
+    GameInfo {
+        name: "qix",
+        title: "Qix",
+        description: "Claim the board by drawing lines, without being touched mid-draw.",
+        tags: &["arcade"],
+        modes: &[],
+        thumbnail: "/thumbnails/qix.png",
+        color: [0.95, 0.3, 0.3, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "quadpong",
+        title: "Quad Pong",
+        description: "Four paddles, four edges and last player standing wins.",
+        tags: &["arcade", "versus", "party"],
+        modes: &[],
+        thumbnail: "/thumbnails/quadpong.png",
+        color: [0.45, 0.9, 0.4, 1.0],
+        score: Some(ScoreKey {
+            key: "place",
+            label: "Place",
+            lower_is_better: true,
+        }),
+        achievements: &[Achievement {
+            id: "last_paddle_standing",
+            title: "Last Paddle Standing",
+            description: "Win a round.",
+            goal: Goal::AtMost("place", 1.0),
+        }],
+    },
+    GameInfo {
+        name: "rhythm",
+        title: "Rhythm Tapper",
+        description: "Hit the notes in four lanes in time with the music.",
+        tags: &["rhythm", "music"],
+        modes: &[],
+        thumbnail: "/thumbnails/rhythm.png",
+        color: [0.85, 0.35, 0.95, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "in_the_groove",
+                title: "In the Groove",
+                description: "Reach a 100 note combo.",
+                goal: Goal::AtLeast("max_combo", 100.0),
+            },
+            Achievement {
+                id: "perfectionist",
+                title: "Perfectionist",
+                description: "Finish a song with 95% accuracy.",
+                goal: Goal::AtLeast("accuracy", 95.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "roguelike",
+        title: "Roguelike",
+        description: "Descend through generated floors where death is permanent.",
+        tags: &["strategy", "turn based"],
+        modes: &[],
+        thumbnail: "/thumbnails/roguelike.png",
+        color: [0.5, 0.4, 0.35, 1.0],
+        score: Some(ScoreKey {
+            key: "depth",
+            label: "Depth",
+            lower_is_better: false,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "rps",
+        title: "Rock Paper Scissors",
+        description: "Outguess a tournament of pattern-spotting opponents.",
+        tags: &["casual", "versus"],
+        modes: &[],
+        thumbnail: "/thumbnails/rps.png",
+        color: [0.95, 0.6, 0.3, 1.0],
+        score: Some(ScoreKey {
+            key: "matches_won",
+            label: "Matches won",
+            lower_is_better: false,
+        }),
+        achievements: &[
+            Achievement {
+                id: "first_win",
+                title: "First Win",
+                description: "Win a match.",
+                goal: Goal::AtLeast("matches_won", 1.0),
+            },
+            Achievement {
+                id: "champion",
+                title: "Champion",
+                description: "Win the tournament.",
+                goal: Goal::AtLeast("matches_won", 4.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "slither",
+        title: "Slither",
+        description: "Grow a snake in an open arena and cut off your rivals.",
+        tags: &["arcade", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/slither.png",
+        color: [0.6, 0.9, 0.4, 1.0],
+        score: Some(ScoreKey {
+            key: "length",
+            label: "Length",
+            lower_is_better: false,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "snake",
+        title: "Snake",
+        description: "Eat the food, grow longer and don't bite your own tail.",
+        tags: &["arcade", "classic"],
+        modes: &[],
+        thumbnail: "/thumbnails/snake.png",
+        color: [0.3, 0.8, 0.0, 1.0],
+        score: Some(ScoreKey {
+            key: "length",
+            label: "Length",
+            lower_is_better: false,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "snakeblocks",
+        title: "Snake vs. Blocks",
+        description: "Steer a snake of balls through walls of numbered blocks.",
+        tags: &["arcade", "endless"],
+        modes: &[],
+        thumbnail: "/thumbnails/snakeblocks.png",
+        color: [0.95, 0.8, 0.3, 1.0],
+        score: Some(ScoreKey {
+            key: "distance",
+            label: "Distance",
+            lower_is_better: false,
+        }),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "stacker",
+        title: "Tower Stacker",
+        description: "Drop each sliding block on the last and build as high as you can.",
+        tags: &["arcade", "one button"],
+        modes: &[],
+        thumbnail: "/thumbnails/stacker.png",
+        color: [0.3, 0.85, 0.85, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+    GameInfo {
+        name: "typing",
+        title: "Typing Trainer",
+        description: "Type the falling words before they land and track your speed.",
+        tags: &["typing", "casual"],
+        modes: &[],
+        thumbnail: "/thumbnails/typing.png",
+        color: [0.85, 0.85, 0.5, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[
+            Achievement {
+                id: "wordsmith",
+                title: "Wordsmith",
+                description: "Type 100 words in one game.",
+                goal: Goal::AtLeast("score", 100.0),
+            },
+            Achievement {
+                id: "speed_typist",
+                title: "Speed Typist",
+                description: "Type at 60 words per minute.",
+                goal: Goal::AtLeast("wpm", 60.0),
+            },
+        ],
+    },
+    GameInfo {
+        name: "yahtzee",
+        title: "Yahtzee",
+        description: "Roll five dice and fill the scorecard, with expected value hints.",
+        tags: &["dice", "strategy"],
+        modes: &[],
+        thumbnail: "/thumbnails/yahtzee.png",
+        color: [0.95, 0.95, 0.95, 1.0],
+        score: Some(ScoreKey::POINTS),
+        achievements: &[],
+    },
+];
+
+/// Looks up a game by its name.
+pub fn find(name: &str) -> Option<&'static GameInfo> {
+    GAMES.iter().find(|game| game.name == name)
+}
+
+/// The game this process is running, found by the name of its executable.
+pub fn running() -> Option<&'static GameInfo> {
+    let exe = env::current_exe().ok()?;
+    find(&exe.file_stem()?.to_string_lossy())
+}
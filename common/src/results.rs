@@ -0,0 +1,324 @@
+//! A summary of a session's runs, written as JSON when the game closes for streaming overlays
+//! and tournament scripts to read.
+//!
+//! A game started with `--output <path>` writes one. `App` notes when the game started, and once
+//! it closes gathers the runs it recorded to the stats store and the achievements unlocked
+//! since. Games have to record each run with `StatsDb::record` as it ends, and then check it
+//! with `achievements::check_run`. A game that records none writes no events and a null score.
+//! The summary looks like:
+//!
+//! ```json
+//! {
+//!   "game": "asteroids",
+//!   "profile": "default",
+//!   "mode": null,
+//!   "seed": null,
+//!   "started": 1580000000,
+//!   "duration": 95.2,
+//!   "score": {"key": "score", "label": "Score", "value": 2150},
+//!   "events": [
+//!     {"time": 1580000042, "type": "run", "values": {"score": 2150, "wave": 5, "players": 1}},
+//!     {"time": 1580000042, "type": "achievement", "id": "deep_space", "title": "Deep Space"}
+//!   ]
+//! }
+//! ```
+//!
+//! Seeds are written as strings since they don't all fit in a JSON number exactly. The score is
+//! the best run's, by the game's score key, and is null if the game doesn't rank its runs or
+//! none were finished.
+//!
+//! A game started with `--script` is run for a script instead, like one ranking a tournament.
+//! Once it closes it prints the last run's score as its last line of output, or its values for
+//! games that don't rank their runs, and `none` if no run was finished. It exits with
+//! `EXIT_WON` if that run was won, `EXIT_LOST` if it was lost or there wasn't one, and
+//! `EXIT_FAILED` if the game stopped with an error. Runs recording a `won` value are won if it
+//! isn't zero. Otherwise any finished run of a game without opponents is won, and a run of a
+//! game with them, one tagged `versus`, is lost. The game's `main` exits with the code
+//! `App::exit_code` gives it.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::achievements::Unlocks;
+use crate::args;
+use crate::daily;
+use crate::json::{number, optional, quote};
+use crate::last_played::{self, LastPlayed};
+use crate::profile;
+use crate::registry::{self, GameInfo};
+use crate::stats::{Record, StatsDb};
+use crate::telemetry;
+
+/// The exit code under `--script` when the last run was won.
+pub const EXIT_WON: i32 = 0;
+/// The exit code under `--script` when the last run was lost, or none was finished.
+pub const EXIT_LOST: i32 = 1;
+/// The exit code under `--script` when the game stopped with an error.
+pub const EXIT_FAILED: i32 = 2;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Something that happened during the session, in the order they happened.
+enum Event {
+    Run(Record),
+    Achievement {
+        timestamp: u64,
+        id: String,
+        title: String,
+    },
+}
+
+impl Event {
+    fn timestamp(&self) -> u64 {
+        match self {
+            Event::Run(record) => record.timestamp,
+            Event::Achievement { timestamp, .. } => *timestamp,
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Event::Run(record) => {
+                let values: Vec<String> = record
+                    .values
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", quote(key), number(*value)))
+                    .collect();
+                format!(
+                    "{{\"time\": {}, \"type\": \"run\", \"values\": {{{}}}}}",
+                    record.timestamp,
+                    values.join(", ")
+                )
+            }
+            Event::Achievement {
+                timestamp,
+                id,
+                title,
+            } => format!(
+                "{{\"time\": {}, \"type\": \"achievement\", \"id\": {}, \"title\": {}}}",
+                timestamp,
+                quote(id),
+                quote(title)
+            ),
+        }
+    }
+}
+
+/// The running game's session, to summarise to the `--output` path once it closes.
+#[derive(Debug)]
+pub struct Results {
+    /// Where to write the summary, if it was asked for.
+    output: Option<PathBuf>,
+    game: Option<&'static GameInfo>,
+    /// Seconds since the unix epoch when the game started.
+    started: u64,
+    clock: Instant,
+    /// Whether the game was started with `--script`.
+    script: bool,
+    /// The error the game stopped with, if it did.
+    error: Option<String>,
+}
+
+impl Results {
+    pub fn new() -> Self {
+        Results {
+            output: args::value("output").map(PathBuf::from),
+            game: registry::running(),
+            started: now(),
+            clock: Instant::now(),
+            script: args::flag("script"),
+            error: None,
+        }
+    }
+
+    /// Notes the error the game stopped with, for `--script`.
+    pub fn fail(&mut self, error: impl fmt::Display) {
+        self.error = Some(error.to_string());
+    }
+
+    /// The runs recorded and achievements unlocked since the game started, oldest first.
+    fn events(&self, game: &GameInfo) -> io::Result<Vec<Event>> {
+        let mut events: Vec<Event> = StatsDb::open()?
+            .records_for(game.name)?
+            .into_iter()
+            .filter(|record| record.timestamp >= self.started)
+            .map(Event::Run)
+            .collect();
+        let unlocks = Unlocks::load()?;
+        events.extend(
+            unlocks
+                .recent(usize::max_value())
+                .filter(|unlock| unlock.game == game.name && unlock.timestamp >= self.started)
+                .map(|unlock| Event::Achievement {
+                    timestamp: unlock.timestamp,
+                    id: unlock.id.clone(),
+                    title: unlock
+                        .achievement()
+                        .map_or(unlock.id.clone(), |achievement| {
+                            achievement.title.to_string()
+                        }),
+                }),
+        );
+        events.sort_by_key(Event::timestamp);
+        Ok(events)
+    }
+
+    /// The best run's score, by the game's score key.
+    fn score(game: &GameInfo, events: &[Event]) -> String {
+        let key = match game.score {
+            Some(key) => key,
+            None => return "null".to_string(),
+        };
+        let best = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Run(record) => record.get(key.key),
+                _ => None,
+            })
+            .fold(None, |best: Option<f64>, value| match best {
+                Some(best) if !key.beats(value, best) => Some(best),
+                _ => Some(value),
+            });
+        match best {
+            Some(value) => format!(
+                "{{\"key\": {}, \"label\": {}, \"value\": {}}}",
+                quote(key.key),
+                quote(key.label),
+                number(value)
+            ),
+            None => "null".to_string(),
+        }
+    }
+
+    /// The mode the game was last switched to, or else the one it was started in.
+    fn mode(game: &GameInfo) -> Option<String> {
+        LastPlayed::load()
+            .filter(|last| last.game == game.name)
+            .and_then(|last| last.mode)
+            .or_else(last_played::requested_mode)
+    }
+
+    /// The summary as JSON.
+    fn to_json(&self, game: &GameInfo) -> io::Result<String> {
+        let events = self.events(game)?;
+        let mode = Results::mode(game);
+        let seed = daily::seed(daily::requested(game.name).as_ref()).map(|seed| seed.to_string());
+        let lines: Vec<String> = events
+            .iter()
+            .map(|event| format!("    {}", event.to_json()))
+            .collect();
+        let list = if lines.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n{}\n  ]", lines.join(",\n"))
+        };
+        Ok(format!(
+            "{{\n  \"game\": {},\n  \"profile\": {},\n  \"mode\": {},\n  \"seed\": {},\n  \
+             \"started\": {},\n  \"duration\": {:.1},\n  \"score\": {},\n  \"events\": {}\n}}\n",
+            quote(game.name),
+            quote(&profile::current()),
+            optional(mode.as_deref()),
+            optional(seed.as_deref()),
+            self.started,
+            self.clock.elapsed().as_secs_f64(),
+            Results::score(game, &events),
+            list
+        ))
+    }
+
+    /// Writes the summary, if it was asked for and the game is in the registry.
+    pub fn write(&self) -> io::Result<()> {
+        let (output, game) = match (&self.output, self.game) {
+            (Some(output), Some(game)) => (output, game),
+            _ => return Ok(()),
+        };
+        fs::write(output, self.to_json(game)?)
+    }
+
+    /// Counts the session in the anonymous play statistics, if the profile shares them.
+    pub fn tally(&self) {
+        let game = match self.game {
+            Some(game) if telemetry::enabled() => game,
+            _ => return,
+        };
+        let scores: Vec<f64> = match (game.score, self.events(game)) {
+            (Some(key), Ok(events)) => events
+                .iter()
+                .filter_map(|event| match event {
+                    Event::Run(record) => record.get(key.key),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        if let Err(e) = telemetry::record(game.name, Results::mode(game).as_deref(), &scores) {
+            eprintln!("Couldn't record the play statistics: {}", e);
+        }
+    }
+
+    /// The last run's score, or all its values if the game doesn't rank its runs.
+    fn describe_run(game: &GameInfo, run: &Record) -> String {
+        if let Some(value) = game.score.and_then(|key| run.get(key.key)) {
+            return value.to_string();
+        }
+        let values: Vec<String> = run
+            .values
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect();
+        values.join(" ")
+    }
+
+    /// Under `--script`, prints the last run's score and returns the code to exit with.
+    pub fn report(&self) -> Option<i32> {
+        if !self.script {
+            return None;
+        }
+        if let Some(error) = &self.error {
+            eprintln!("{}", error);
+            println!("none");
+            return Some(EXIT_FAILED);
+        }
+        let last = self.game.map(|game| {
+            self.events(game).map(|events| {
+                let run = events.into_iter().rev().find_map(|event| match event {
+                    Event::Run(record) => Some(record),
+                    _ => None,
+                });
+                (game, run)
+            })
+        });
+        let code = match last {
+            Some(Ok((game, Some(run)))) => {
+                println!("{}", Results::describe_run(game, &run));
+                let won = match run.get("won") {
+                    Some(won) => won != 0.0,
+                    None => !game.has_opponents(),
+                };
+                if won {
+                    EXIT_WON
+                } else {
+                    EXIT_LOST
+                }
+            }
+            Some(Ok((_, None))) | None => {
+                println!("none");
+                EXIT_LOST
+            }
+            Some(Err(e)) => {
+                eprintln!("Couldn't read the runs: {}", e);
+                println!("none");
+                EXIT_FAILED
+            }
+        };
+        Some(code)
+    }
+}
@@ -0,0 +1,194 @@
+//! Rumble on gamepads that have it, for games to give a feel to what happens, like a short pulse
+//! on picking something up or a strong buzz on crashing.
+//!
+//! Each profile can turn rumble off, or turn it down, from the launcher's controls screen. The
+//! settings are kept as `rumble`, which is `true` or `false`, and `rumble_intensity`, a
+//! percentage.
+//!
+//! ggez doesn't give access to rumble, so `Rumble` opens the gamepads again through gilrs to
+//! play effects on them. Games tell it which gamepad is being played with, and it rumbles that
+//! one; a game played on the keyboard doesn't rumble.
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use ggez::input::gamepad::{self, GamepadId};
+use ggez::Context;
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::Gilrs;
+
+use crate::settings::Settings;
+
+const ENABLED_KEY: &str = "rumble";
+const INTENSITY_KEY: &str = "rumble_intensity";
+
+/// A buzz in a rumble pattern.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pulse {
+    /// From 0 to 1, before the profile's intensity is applied.
+    pub strength: f32,
+    /// When it starts, in milliseconds after the pattern does.
+    pub delay: u32,
+    /// How long it lasts, in milliseconds.
+    pub length: u32,
+}
+
+/// A short, light pulse, like for eating or picking something up.
+pub const TAP: &[Pulse] = &[Pulse {
+    strength: 0.3,
+    delay: 0,
+    length: 60,
+}];
+
+/// A strong buzz, like for dying.
+pub const CRASH: &[Pulse] = &[Pulse {
+    strength: 1.0,
+    delay: 0,
+    length: 450,
+}];
+
+/// The profile's rumble settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RumbleSettings {
+    pub enabled: bool,
+    /// How strong rumble is, as a percentage.
+    pub intensity: u32,
+}
+
+impl Default for RumbleSettings {
+    fn default() -> Self {
+        RumbleSettings {
+            enabled: true,
+            intensity: 100,
+        }
+    }
+}
+
+impl RumbleSettings {
+    /// Loads the current profile's rumble settings.
+    pub fn load() -> Self {
+        RumbleSettings::from_settings(&Settings::load())
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        let defaults = RumbleSettings::default();
+        RumbleSettings {
+            enabled: settings.get_or(ENABLED_KEY, defaults.enabled),
+            intensity: settings.get_or(INTENSITY_KEY, defaults.intensity).min(100),
+        }
+    }
+
+    /// How much to scale each pulse's strength by, from 0 to 1.
+    pub fn scale(&self) -> f32 {
+        if self.enabled {
+            self.intensity as f32 / 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Saves the settings into the current profile's settings.
+    pub fn save(&self) -> io::Result<()> {
+        let mut settings = Settings::load();
+        settings.set(ENABLED_KEY, self.enabled);
+        settings.set(INTENSITY_KEY, self.intensity);
+        settings.save()
+    }
+}
+
+/// Plays rumble patterns on the gamepad a game is being played with.
+pub struct Rumble {
+    /// The gamepads opened for rumble, unless rumble is off or they couldn't be opened.
+    gilrs: Option<Gilrs>,
+    scale: f32,
+    /// The gamepad being played with, if there is one.
+    gamepad: Option<GamepadId>,
+    /// The effects playing and when each ends, since they stop when they are dropped.
+    playing: Vec<(Effect, Instant)>,
+}
+
+impl Rumble {
+    /// Opens the gamepads with the current profile's settings.
+    pub fn load() -> Self {
+        let scale = RumbleSettings::load().scale();
+        let gilrs = if scale > 0.0 {
+            Gilrs::new()
+                .map_err(|e| eprintln!("Couldn't open the gamepads for rumble: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        Rumble {
+            gilrs,
+            scale,
+            gamepad: None,
+            playing: Vec::new(),
+        }
+    }
+
+    /// Notes the gamepad being played with. Call it when a gamepad's buttons are pressed.
+    pub fn gamepad_used(&mut self, id: GamepadId) {
+        self.gamepad = Some(id);
+    }
+
+    /// Plays a pattern on the gamepad being played with, if it can rumble.
+    pub fn play(&mut self, ctx: &Context, pattern: &[Pulse]) {
+        let now = Instant::now();
+        self.playing.retain(|(_, ends)| *ends > now);
+
+        let (gilrs, id) = match (&mut self.gilrs, self.gamepad) {
+            (Some(gilrs), Some(id)) => (gilrs, id),
+            _ => return,
+        };
+        // Catch up on gamepads being plugged in, so they can be found.
+        while gilrs.next_event().is_some() {}
+        // ggez's gamepads and these are opened separately, so they're matched up by their ids
+        // from the system.
+        let uuid = gamepad::gamepad(ctx, id).uuid();
+        let target = gilrs
+            .gamepads()
+            .find(|(_, pad)| pad.uuid() == uuid && pad.is_ff_supported())
+            .map(|(target, _)| target);
+        let target = match target {
+            Some(target) => target,
+            None => return,
+        };
+
+        let mut builder = EffectBuilder::new();
+        let mut length = 0;
+        for pulse in pattern {
+            let magnitude = (pulse.strength * self.scale).max(0.0).min(1.0) * f32::from(u16::MAX);
+            let scheduling = Replay {
+                play_for: Ticks::from_ms(pulse.length),
+                with_delay: Ticks::from_ms(pulse.delay),
+                ..Replay::default()
+            };
+            builder.add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude as u16,
+                },
+                scheduling,
+                ..BaseEffect::default()
+            });
+            builder.add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude as u16,
+                },
+                scheduling,
+                ..BaseEffect::default()
+            });
+            length = length.max(pulse.delay + pulse.length);
+        }
+        let effect = builder
+            .gamepads(&[target])
+            .repeat(Repeat::For(Ticks::from_ms(length)))
+            .finish(gilrs)
+            .and_then(|effect| effect.play().map(|()| effect));
+        match effect {
+            Ok(effect) => self
+                .playing
+                .push((effect, now + Duration::from_millis(length.into()))),
+            Err(e) => eprintln!("Couldn't rumble: {}", e),
+        }
+    }
+}
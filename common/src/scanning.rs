@@ -0,0 +1,293 @@
+//! One-switch scanning, for players using switch-access hardware, or anything else that gives
+//! them a single button. The actions a game or menu takes, the four directions, select and
+//! back, are highlighted one after another along the bottom of the screen, and the switch picks
+//! the highlighted one, which is passed on as though its key had been pressed.
+//!
+//! It's set up in the profile's settings, and like any setting can be changed for a run:
+//!
+//! - `switch_scanning`: `true` to turn it on.
+//! - `scan_confirm`: `press`, where pressing the switch starts a scan and pressing it again
+//!   picks the highlighted action, or `hold`, where the scan keeps going and holding the switch
+//!   down picks the highlighted action, so a stray tap doesn't.
+//! - `scan_interval`: how long each action is highlighted for, in milliseconds.
+//! - `switch_key`: the key the switch presses, named as on the controls screen. It's `Space`
+//!   unless it's set, and a gamepad's A button works as a switch as well.
+//!
+//! While a scan started with `press` is going, `App` holds the game still, so a game like snake
+//! waits for the player to choose where to turn.
+//!
+//! Highlighted actions are read out if speech is on.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use ggez::event::{Button, KeyCode};
+use ggez::graphics::{self, DrawMode, Rect, Text};
+use ggez::{Context, GameResult};
+
+use crate::bindings;
+use crate::settings::Settings;
+use crate::speech;
+use crate::text;
+use crate::ui;
+
+const ENABLED_KEY: &str = "switch_scanning";
+const CONFIRM_KEY: &str = "scan_confirm";
+const INTERVAL_KEY: &str = "scan_interval";
+const SWITCH_KEY: &str = "switch_key";
+
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+/// How long the switch is held to pick an action with `scan_confirm=hold`.
+const HOLD_TIME: Duration = Duration::from_millis(600);
+
+/// The actions a scan goes through, in order, and the key each one presses.
+const ACTIONS: [(&str, KeyCode); 6] = [
+    ("Up", KeyCode::Up),
+    ("Right", KeyCode::Right),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Select", KeyCode::Return),
+    ("Back", KeyCode::Escape),
+];
+
+const PANEL_COLOR: [f32; 4] = [0.1, 0.1, 0.13, 0.9];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const IDLE_TEXT_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+const TEXT_SIZE: f32 = 18.0;
+const CELL_SIZE: (f32, f32) = (84.0, 36.0);
+const CELL_SPACING: f32 = 8.0;
+const MARGIN: f32 = 12.0;
+
+/// How the switch picks an action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Confirm {
+    /// One press starts a scan and the next picks.
+    Press,
+    /// The scan runs all the time and a long press picks.
+    Hold,
+}
+
+struct Scanner {
+    confirm: Confirm,
+    interval: Duration,
+    switch: KeyCode,
+    /// The highlighted action and when it was highlighted, while scanning.
+    scan: Option<(usize, Instant)>,
+    /// When the switch was pressed, while it's held down.
+    pressed: Option<Instant>,
+    /// Whether the press being held has already picked an action.
+    hold_picked: bool,
+    /// The key of an action that has been picked, until it's passed on.
+    picked: Option<KeyCode>,
+}
+
+thread_local! {
+    /// The scanner, if scanning is on. It's loaded the first time it's needed.
+    static SCANNER: RefCell<Option<Option<Scanner>>> = RefCell::new(None);
+}
+
+impl Scanner {
+    fn load() -> Option<Scanner> {
+        let settings = Settings::load();
+        if !settings.get_or(ENABLED_KEY, false) {
+            return None;
+        }
+        let confirm = match settings.get(CONFIRM_KEY) {
+            Some("hold") => Confirm::Hold,
+            _ => Confirm::Press,
+        };
+        let interval = settings.get_or(INTERVAL_KEY, DEFAULT_INTERVAL_MS).max(100);
+        let switch = settings
+            .get(SWITCH_KEY)
+            .and_then(bindings::key_from_name)
+            .unwrap_or(KeyCode::Space);
+        let mut scanner = Scanner {
+            confirm,
+            interval: Duration::from_millis(interval),
+            switch,
+            scan: None,
+            pressed: None,
+            hold_picked: false,
+            picked: None,
+        };
+        if confirm == Confirm::Hold {
+            scanner.highlight(0);
+        }
+        Some(scanner)
+    }
+
+    fn highlight(&mut self, index: usize) {
+        self.scan = Some((index, Instant::now()));
+        speech::say(ACTIONS[index].0);
+    }
+
+    fn pick(&mut self, index: usize) {
+        self.picked = Some(ACTIONS[index].1);
+        match self.confirm {
+            Confirm::Press => self.scan = None,
+            // Back to the start, where the directions are.
+            Confirm::Hold => self.highlight(0),
+        }
+    }
+
+    fn press(&mut self) {
+        // A held switch repeats, and only the first press counts.
+        if self.pressed.is_some() {
+            return;
+        }
+        self.pressed = Some(Instant::now());
+        if self.confirm == Confirm::Press {
+            match self.scan {
+                Some((index, _)) => self.pick(index),
+                None => self.highlight(0),
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        self.pressed = None;
+        self.hold_picked = false;
+    }
+
+    fn update(&mut self) -> Option<KeyCode> {
+        if let (Confirm::Hold, Some(pressed), false) =
+            (self.confirm, self.pressed, self.hold_picked)
+        {
+            if pressed.elapsed() >= HOLD_TIME {
+                self.hold_picked = true;
+                if let Some((index, _)) = self.scan {
+                    self.pick(index);
+                }
+            }
+        }
+        // The highlight stays put while the switch is held, so it picks what was highlighted.
+        if let (Some((index, since)), None) = (self.scan, self.pressed) {
+            if since.elapsed() >= self.interval {
+                self.highlight((index + 1) % ACTIONS.len());
+            }
+        }
+        self.picked.take()
+    }
+}
+
+fn with_scanner<T>(f: impl FnOnce(&mut Scanner) -> T) -> Option<T> {
+    SCANNER.with(|scanner| {
+        let mut scanner = scanner.borrow_mut();
+        scanner.get_or_insert_with(Scanner::load).as_mut().map(f)
+    })
+}
+
+/// Whether scanning is on.
+pub fn enabled() -> bool {
+    with_scanner(|_| ()).is_some()
+}
+
+/// Handles a key going down, returning whether it was the switch, which isn't passed on.
+pub fn key_down(key: KeyCode) -> bool {
+    with_scanner(|scanner| {
+        if key == scanner.switch {
+            scanner.press();
+            true
+        } else {
+            false
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Handles a key coming up, returning whether it was the switch.
+pub fn key_up(key: KeyCode) -> bool {
+    with_scanner(|scanner| {
+        if key == scanner.switch {
+            scanner.release();
+            true
+        } else {
+            false
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Handles a gamepad button going down, returning whether it was the switch.
+pub fn button_down(button: Button) -> bool {
+    with_scanner(|scanner| {
+        if button == Button::South {
+            scanner.press();
+            true
+        } else {
+            false
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Handles a gamepad button coming up, returning whether it was the switch.
+pub fn button_up(button: Button) -> bool {
+    with_scanner(|scanner| {
+        if button == Button::South {
+            scanner.release();
+            true
+        } else {
+            false
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Whether the player is part way through picking an action with `scan_confirm=press`, when
+/// games wait for them.
+pub fn is_choosing() -> bool {
+    with_scanner(|scanner| scanner.confirm == Confirm::Press && scanner.scan.is_some())
+        .unwrap_or(false)
+}
+
+/// Moves the highlight on when it's time, returning the key of an action once it's picked.
+/// Call it every update.
+pub fn update() -> Option<KeyCode> {
+    with_scanner(Scanner::update).flatten()
+}
+
+/// Draws the actions along the bottom of the screen, with the highlighted one outlined. It's
+/// drawn by `ui::present`.
+pub fn draw(ctx: &mut Context) -> GameResult<()> {
+    let highlighted = match with_scanner(|scanner| scanner.scan.map(|(index, _)| index)) {
+        Some(highlighted) => highlighted,
+        None => return Ok(()),
+    };
+
+    let screen = graphics::screen_coordinates(ctx);
+    let count = ACTIONS.len() as f32;
+    let width = count * CELL_SIZE.0 + (count - 1.0) * CELL_SPACING;
+    let left = screen.x + (screen.w - width) / 2.0;
+    let top = screen.y + screen.h - MARGIN - CELL_SIZE.1;
+    for (index, (label, _)) in ACTIONS.iter().enumerate() {
+        let rect = Rect::new(
+            left + index as f32 * (CELL_SIZE.0 + CELL_SPACING),
+            top,
+            CELL_SIZE.0,
+            CELL_SIZE.1,
+        );
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), rect, PANEL_COLOR.into())?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let color = if Some(index) == highlighted {
+            TEXT_COLOR
+        } else {
+            IDLE_TEXT_COLOR
+        };
+        let text = Text::new(text::fragment(ctx, *label, color, TEXT_SIZE));
+        let (width, height) = text.dimensions(ctx);
+        graphics::draw(
+            ctx,
+            &text,
+            (ggez::mint::Point2 {
+                x: rect.x + (rect.w - width as f32) / 2.0,
+                y: rect.y + (rect.h - height as f32) / 2.0,
+            },),
+        )?;
+        if Some(index) == highlighted {
+            ui::draw_focus(ctx, rect)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Tracks how long the current profile has been playing without a break, across every game,
+//! for the optional break reminders.
+//!
+//! The games share the session through a file in the profile directory, which each running
+//! game touches every few seconds. Being away from the games for as long as a break counts as
+//! taking one, and starts a new session.
+//!
+//! Reminders are off until the profile's settings give a play time:
+//!
+//! - `break_after_minutes`: how long to play before a break, 0 for no reminders.
+//! - `break_minutes`: how long a break lasts, 10 minutes by default.
+//! - `break_mode`: `remind` to show a reminder that can be put off, or `enforce` to pause the
+//!   games until the break is over.
+
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::migrate::{self, SESSION};
+use crate::profile;
+use crate::settings::Settings;
+
+const SESSION_FILE: &str = "session.txt";
+
+/// How often the session file is touched, in seconds.
+const HEARTBEAT: u64 = 5;
+
+/// How long a dismissed reminder is put off for, in seconds.
+const SNOOZE: u64 = 10 * 60;
+
+/// What happens when it is time for a break.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BreakMode {
+    /// A reminder the player can dismiss to keep playing for a while.
+    Remind,
+    /// A break screen that stays up until the break is over.
+    Enforce,
+}
+
+/// The profile's break reminder settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BreakSettings {
+    /// Seconds of play before a break, or zero for no reminders.
+    pub play: u64,
+    /// Seconds a break lasts.
+    pub rest: u64,
+    pub mode: BreakMode,
+}
+
+impl BreakSettings {
+    pub fn from_settings(settings: &Settings) -> Self {
+        let mode = match settings.get("break_mode") {
+            Some("enforce") => BreakMode::Enforce,
+            _ => BreakMode::Remind,
+        };
+        BreakSettings {
+            play: settings.get_or("break_after_minutes", 0u64) * 60,
+            rest: settings.get_or("break_minutes", 10u64).max(1) * 60,
+            mode,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.play > 0
+    }
+}
+
+/// What the player should be shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reminder {
+    None,
+    /// It's time for a break, but the player can keep going.
+    Remind,
+    /// The games are paused for a break with this many seconds left.
+    Break(u64),
+}
+
+/// The session as saved in the session file, with times in seconds since the unix epoch.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+struct SessionFile {
+    started: u64,
+    last_seen: u64,
+    /// Reminders are put off until this time.
+    snoozed_until: u64,
+    /// When the enforced break started, if the games are paused for one.
+    break_started: Option<u64>,
+}
+
+impl SessionFile {
+    fn parse(contents: &str) -> Self {
+        let mut file = SessionFile::default();
+        for line in contents.lines() {
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next().and_then(|v| v.parse().ok())) {
+                (Some(key), Some(value)) => (key, value),
+                _ => continue,
+            };
+            match key {
+                "started" => file.started = value,
+                "last_seen" => file.last_seen = value,
+                "snoozed_until" => file.snoozed_until = value,
+                "break_started" => file.break_started = Some(value),
+                _ => (),
+            }
+        }
+        file
+    }
+
+    fn contents(&self) -> String {
+        let mut contents = format!(
+            "started={}\nlast_seen={}\nsnoozed_until={}\n",
+            self.started, self.last_seen, self.snoozed_until
+        );
+        if let Some(break_started) = self.break_started {
+            contents.push_str(&format!("break_started={}\n", break_started));
+        }
+        contents
+    }
+
+    fn restart(&mut self, now: u64) {
+        *self = SessionFile {
+            started: now,
+            last_seen: now,
+            snoozed_until: 0,
+            break_started: None,
+        };
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The current profile's play session.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub settings: BreakSettings,
+    /// Where the session is shared, unless the profile directory couldn't be found.
+    path: Option<PathBuf>,
+    file: SessionFile,
+    /// When this game last touched the session file.
+    last_heartbeat: u64,
+}
+
+impl Session {
+    /// Loads the profile's break settings and the session shared with any other games.
+    pub fn load() -> Self {
+        let settings = BreakSettings::from_settings(&Settings::load());
+        let path = profile::profile_dir()
+            .ok()
+            .map(|dir| dir.join(SESSION_FILE));
+        let mut session = Session {
+            settings,
+            path,
+            file: SessionFile::default(),
+            last_heartbeat: 0,
+        };
+        session.reload();
+        session
+    }
+
+    fn reload(&mut self) {
+        if let Some(path) = &self.path {
+            self.file = migrate::read(path, &SESSION)
+                .map(|contents| SessionFile::parse(&contents))
+                .unwrap_or_default();
+        }
+    }
+
+    fn save(&self) {
+        if let Some(path) = &self.path {
+            let _ = migrate::write(path, &SESSION, &self.file.contents());
+        }
+    }
+
+    /// Starts a new session if a break has been taken since the session was last touched.
+    fn catch_up(&mut self, now: u64) {
+        let rested = match self.file.break_started {
+            Some(break_started) => now.saturating_sub(break_started) >= self.settings.rest,
+            None => now.saturating_sub(self.file.last_seen) >= self.settings.rest,
+        };
+        if rested {
+            self.file.restart(now);
+        }
+    }
+
+    /// Marks the profile as still playing and returns what the player should be shown. Call it
+    /// every frame; the session file is only touched every few seconds.
+    pub fn update(&mut self) -> Reminder {
+        if !self.settings.is_enabled() {
+            return Reminder::None;
+        }
+
+        let now = now();
+        if now >= self.last_heartbeat + HEARTBEAT {
+            self.last_heartbeat = now;
+            self.reload();
+            self.catch_up(now);
+            self.file.last_seen = now;
+            self.save();
+        }
+
+        if let Some(break_started) = self.file.break_started {
+            let over = break_started + self.settings.rest;
+            if now < over {
+                return Reminder::Break(over - now);
+            }
+            self.file.restart(now);
+            self.save();
+        }
+
+        let due = now >= self.file.started + self.settings.play && now >= self.file.snoozed_until;
+        match (due, self.settings.mode) {
+            (false, _) => Reminder::None,
+            (true, BreakMode::Remind) => Reminder::Remind,
+            (true, BreakMode::Enforce) => {
+                self.file.break_started = Some(now);
+                self.save();
+                Reminder::Break(self.settings.rest)
+            }
+        }
+    }
+
+    /// Puts off a reminder for a while.
+    pub fn snooze(&mut self) {
+        self.file.snoozed_until = now() + SNOOZE;
+        self.save();
+    }
+
+    /// How many seconds have been played this session.
+    pub fn played(&self) -> u64 {
+        now().saturating_sub(self.file.started)
+    }
+
+    /// The seconds left of an enforced break, if the games are paused for one.
+    pub fn break_remaining(&self) -> Option<u64> {
+        if !self.settings.is_enabled() {
+            return None;
+        }
+        let over = self.file.break_started? + self.settings.rest;
+        over.checked_sub(now()).filter(|&remaining| remaining > 0)
+    }
+}
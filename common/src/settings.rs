@@ -0,0 +1,123 @@
+//! Per profile settings, kept as `key=value` lines in the profile's directory so they can also
+//! be edited by hand.
+//!
+//! Command line options override the settings of the same name for the run, without being
+//! saved: see `args`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::args::Args;
+use crate::migrate::{self, SETTINGS};
+use crate::profile;
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+/// The current profile's settings.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// Where the settings are saved, unless the profile directory couldn't be found.
+    path: Option<PathBuf>,
+    /// In the order they appear in the file.
+    values: Vec<(String, String)>,
+    /// The command line options, which are looked at before the file's values.
+    overrides: Args,
+}
+
+impl Settings {
+    /// Loads the current profile's settings. Settings that can't be read are left at their
+    /// defaults rather than stopping the game from starting. If the file is from a newer version
+    /// of the games, it isn't saved over.
+    pub fn load() -> Self {
+        let path = match profile::profile_dir() {
+            Ok(dir) => dir.join(SETTINGS_FILE),
+            Err(_) => {
+                return Settings {
+                    overrides: Args::from_env(),
+                    ..Settings::default()
+                }
+            }
+        };
+        let contents = match migrate::read(&path, &SETTINGS) {
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidData => {
+                eprintln!("Couldn't load the settings: {}", e);
+                return Settings {
+                    overrides: Args::from_env(),
+                    ..Settings::default()
+                };
+            }
+            contents => contents,
+        };
+        let values = contents
+            .map(|contents| {
+                contents
+                    .lines()
+                    .filter_map(|line| {
+                        let mut parts = line.splitn(2, '=');
+                        let key = parts.next()?.trim();
+                        let value = parts.next()?.trim();
+                        if key.is_empty() || key.starts_with('#') {
+                            None
+                        } else {
+                            Some((key.to_string(), value.to_string()))
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Settings {
+            path: Some(path),
+            values,
+            overrides: Args::from_env(),
+        }
+    }
+
+    /// Where the settings are saved, unless the profile directory couldn't be found.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// The setting's value, from the command line if it was given there and otherwise from the
+    /// file.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.overrides.value(key).or_else(|| {
+            self.values
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+        })
+    }
+
+    /// Returns the setting parsed as a `T`, or `default` if it isn't set or doesn't parse.
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.get(key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    pub fn set<T: ToString>(&mut self, key: &str, value: T) {
+        let value = value.to_string();
+        match self.values.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((key.to_string(), value)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.values.retain(|(k, _)| k != key);
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "no profile directory to save to")
+        })?;
+        let contents: String = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{}={}\n", key, value))
+            .collect();
+        migrate::write(path, &SETTINGS, &contents)
+    }
+}
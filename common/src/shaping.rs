@@ -0,0 +1,378 @@
+//! Getting right-to-left text ready to draw. ggez lays glyphs out one after another, left to
+//! right, in the order they come, so text in Arabic or Hebrew is put into the order it's seen
+//! in, and Arabic letters are swapped for the forms that join up to their neighbours.
+//!
+//! Each line is reordered on its own with the Unicode bidirectional algorithm, so a line of
+//! Hebrew with a score in it reads right to left with the score's digits still left to right.
+//! Wrapped text is reordered before it's wrapped, so a right-to-left paragraph long enough to
+//! wrap has its lines in the wrong order; menus keep to a line each.
+
+use std::borrow::Cow;
+
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo};
+
+/// Which way a piece of text reads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft,
+}
+
+/// The direction of a piece of text, from its first letter with one. Text without any, like
+/// a number, reads left to right.
+pub fn direction(text: &str) -> Direction {
+    let strong = text
+        .chars()
+        .map(bidi_class)
+        .find(|class| matches!(class, BidiClass::L | BidiClass::R | BidiClass::AL));
+    match strong {
+        Some(BidiClass::R) | Some(BidiClass::AL) => Direction::RightToLeft,
+        _ => Direction::LeftToRight,
+    }
+}
+
+/// Whether any of the text is in a right-to-left script, and so needs shaping.
+fn has_rtl(text: &str) -> bool {
+    text.chars().any(|c| {
+        matches!(
+            c,
+            '\u{0590}'..='\u{08ff}' | '\u{fb1d}'..='\u{fdff}' | '\u{fe70}'..='\u{feff}'
+        )
+    })
+}
+
+/// Puts text into the order it's drawn in, with Arabic letters joined. Text without any
+/// right-to-left script is left as it is.
+pub fn shape(text: &str) -> Cow<'_, str> {
+    if !has_rtl(text) {
+        return Cow::Borrowed(text);
+    }
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            let joined = join_arabic(line);
+            let info = BidiInfo::new(&joined, None);
+            match info.paragraphs.first() {
+                Some(paragraph) => info
+                    .reorder_line(paragraph, paragraph.range.clone())
+                    .into_owned(),
+                None => joined,
+            }
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+/// An Arabic letter's presentation forms, which follow on from its isolated form in the order
+/// isolated, final, initial, medial. Letters that only join to the letter before them only
+/// have the first two.
+struct Forms {
+    letter: char,
+    isolated: u32,
+    /// Whether it joins to the letter after it as well as the one before.
+    dual: bool,
+}
+
+const fn forms(letter: char, isolated: u32, dual: bool) -> Forms {
+    Forms {
+        letter,
+        isolated,
+        dual,
+    }
+}
+
+const ARABIC_FORMS: [Forms; 36] = [
+    forms('\u{0622}', 0xfe81, false),
+    forms('\u{0623}', 0xfe83, false),
+    forms('\u{0624}', 0xfe85, false),
+    forms('\u{0625}', 0xfe87, false),
+    forms('\u{0626}', 0xfe89, true),
+    forms('\u{0627}', 0xfe8d, false),
+    forms('\u{0628}', 0xfe8f, true),
+    forms('\u{0629}', 0xfe93, false),
+    forms('\u{062a}', 0xfe95, true),
+    forms('\u{062b}', 0xfe99, true),
+    forms('\u{062c}', 0xfe9d, true),
+    forms('\u{062d}', 0xfea1, true),
+    forms('\u{062e}', 0xfea5, true),
+    forms('\u{062f}', 0xfea9, false),
+    forms('\u{0630}', 0xfeab, false),
+    forms('\u{0631}', 0xfead, false),
+    forms('\u{0632}', 0xfeaf, false),
+    forms('\u{0633}', 0xfeb1, true),
+    forms('\u{0634}', 0xfeb5, true),
+    forms('\u{0635}', 0xfeb9, true),
+    forms('\u{0636}', 0xfebd, true),
+    forms('\u{0637}', 0xfec1, true),
+    forms('\u{0638}', 0xfec5, true),
+    forms('\u{0639}', 0xfec9, true),
+    forms('\u{063a}', 0xfecd, true),
+    forms('\u{0641}', 0xfed1, true),
+    forms('\u{0642}', 0xfed5, true),
+    forms('\u{0643}', 0xfed9, true),
+    forms('\u{0644}', 0xfedd, true),
+    forms('\u{0645}', 0xfee1, true),
+    forms('\u{0646}', 0xfee5, true),
+    forms('\u{0647}', 0xfee9, true),
+    forms('\u{0648}', 0xfeed, false),
+    forms('\u{0649}', 0xfeef, false),
+    forms('\u{064a}', 0xfef1, true),
+    // Tatweel only stretches the join between letters, so it has no forms of its own.
+    forms('\u{0640}', 0x0640, true),
+];
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+/// Lam followed by each kind of alef is written as one ligature, isolated or final.
+const LAM_ALEF: [(char, u32); 4] = [
+    ('\u{0622}', 0xfef5),
+    ('\u{0623}', 0xfef7),
+    ('\u{0625}', 0xfef9),
+    ('\u{0627}', 0xfefb),
+];
+
+fn arabic_forms(c: char) -> Option<&'static Forms> {
+    ARABIC_FORMS.iter().find(|forms| forms.letter == c)
+}
+
+/// Vowel marks sit on their letter without breaking the join either side of it.
+fn is_transparent(c: char) -> bool {
+    matches!(c, '\u{064b}'..='\u{065f}' | '\u{0670}')
+}
+
+fn form(forms: &Forms, offset: u32) -> char {
+    if forms.letter == TATWEEL {
+        return TATWEEL;
+    }
+    std::char::from_u32(forms.isolated + offset).unwrap_or(forms.letter)
+}
+
+/// Swaps each Arabic letter for its isolated, final, initial or medial form, depending on
+/// whether it joins to the letters either side of it.
+fn join_arabic(line: &str) -> String {
+    let chars: Vec<char> = line.chars().collect();
+    // The next letter after `index` that isn't a vowel mark.
+    let next_letter = |index: usize| {
+        chars[index + 1..]
+            .iter()
+            .copied()
+            .find(|&c| !is_transparent(c))
+    };
+
+    let mut joined = String::with_capacity(line.len());
+    // Whether the letter before joins on to the next one.
+    let mut joins_before = false;
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        index += 1;
+        if is_transparent(c) {
+            joined.push(c);
+            continue;
+        }
+        let forms = match arabic_forms(c) {
+            Some(forms) => forms,
+            None => {
+                joined.push(c);
+                joins_before = false;
+                continue;
+            }
+        };
+
+        let next = next_letter(index - 1);
+        if c == LAM {
+            let ligature = LAM_ALEF
+                .iter()
+                .find(|(alef, _)| Some(*alef) == next)
+                .map(|(_, isolated)| *isolated);
+            if let Some(isolated) = ligature {
+                let ligature = isolated + joins_before as u32;
+                joined.extend(std::char::from_u32(ligature));
+                // Any vowel marks on the lam go after the ligature, then the alef is skipped.
+                while index < chars.len() && is_transparent(chars[index]) {
+                    joined.push(chars[index]);
+                    index += 1;
+                }
+                index += 1;
+                joins_before = false;
+                continue;
+            }
+        }
+
+        let joins_after = forms.dual && next.and_then(arabic_forms).is_some();
+        let offset = match (joins_before, joins_after) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        joined.push(form(forms, offset));
+        joins_before = forms.dual;
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHALOM: &str = "\u{05e9}\u{05dc}\u{05d5}\u{05dd}";
+    const BEH: char = '\u{0628}';
+    const ALEF: char = '\u{0627}';
+    const FATHA: char = '\u{064e}';
+
+    fn reversed(text: &str) -> String {
+        text.chars().rev().collect()
+    }
+
+    fn text(letters: &[char]) -> String {
+        letters.iter().collect()
+    }
+
+    fn chars(codes: &[u32]) -> String {
+        codes
+            .iter()
+            .map(|&code| std::char::from_u32(code).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn latin_text_is_left_as_it_is() {
+        for text in &["Play", "Score: 42", "Café crème", "", "\n"] {
+            match shape(text) {
+                Cow::Borrowed(shaped) => assert_eq!(shaped, *text),
+                Cow::Owned(shaped) => panic!("{:?} was shaped into {:?}", text, shaped),
+            }
+        }
+    }
+
+    #[test]
+    fn directions_come_from_the_first_letter_with_one() {
+        assert_eq!(direction("Play"), Direction::LeftToRight);
+        assert_eq!(direction(SHALOM), Direction::RightToLeft);
+        assert_eq!(direction(&format!("42 {}", SHALOM)), Direction::RightToLeft);
+        assert_eq!(
+            direction(&format!("Play {}", SHALOM)),
+            Direction::LeftToRight
+        );
+        assert_eq!(direction(&BEH.to_string()), Direction::RightToLeft);
+        assert_eq!(direction("42"), Direction::LeftToRight);
+        assert_eq!(direction(""), Direction::LeftToRight);
+    }
+
+    #[test]
+    fn hebrew_is_put_in_the_order_its_drawn() {
+        assert_eq!(shape(SHALOM), reversed(SHALOM));
+        // Reordering again gives back the original.
+        assert_eq!(reversed(&shape(SHALOM)), SHALOM);
+    }
+
+    #[test]
+    fn numbers_in_right_to_left_text_read_left_to_right() {
+        let line = format!("{} 42", SHALOM);
+        let shaped = shape(&line);
+        assert_eq!(shaped, format!("42 {}", reversed(SHALOM)));
+    }
+
+    #[test]
+    fn each_line_is_reordered_on_its_own() {
+        let lines = format!("{}\nPlay\n\n{}", SHALOM, SHALOM);
+        let shaped = shape(&lines);
+        assert_eq!(
+            shaped,
+            format!("{}\nPlay\n\n{}", reversed(SHALOM), reversed(SHALOM))
+        );
+    }
+
+    #[test]
+    fn arabic_letters_join_their_neighbours() {
+        assert_eq!(join_arabic(&BEH.to_string()), chars(&[0xfe8f]));
+        assert_eq!(
+            join_arabic(&text(&[BEH, BEH, BEH])),
+            chars(&[0xfe91, 0xfe92, 0xfe90])
+        );
+        // Alef doesn't join to the letter after it.
+        assert_eq!(
+            join_arabic(&text(&[BEH, ALEF, BEH])),
+            chars(&[0xfe91, 0xfe8e, 0xfe8f])
+        );
+    }
+
+    #[test]
+    fn lam_and_alef_make_one_letter() {
+        assert_eq!(join_arabic("\u{0644}\u{0627}"), chars(&[0xfefb]));
+        assert_eq!(
+            join_arabic(&text(&[BEH, '\u{0644}', ALEF])),
+            chars(&[0xfe91, 0xfefc])
+        );
+        // A vowel mark on the lam goes after the ligature.
+        assert_eq!(
+            join_arabic(&text(&['\u{0644}', FATHA, ALEF])),
+            chars(&[0xfefb, 0x064e])
+        );
+    }
+
+    #[test]
+    fn vowel_marks_dont_break_joins() {
+        assert_eq!(
+            join_arabic(&text(&[BEH, FATHA, BEH])),
+            chars(&[0xfe91, 0x064e, 0xfe90])
+        );
+    }
+
+    #[test]
+    fn tatweel_is_kept_and_joined_to() {
+        assert_eq!(
+            join_arabic(&text(&[BEH, '\u{0640}', BEH])),
+            chars(&[0xfe91, 0x0640, 0xfe90])
+        );
+    }
+
+    #[test]
+    fn arabic_is_joined_then_reordered() {
+        let line = text(&[BEH, BEH]);
+        let shaped = shape(&line);
+        assert_eq!(shaped, chars(&[0xfe90, 0xfe91]));
+    }
+
+    #[test]
+    fn odd_arabic_is_passed_through() {
+        // A lam with nothing after it, or only a vowel mark.
+        assert_eq!(join_arabic("\u{0644}"), chars(&[0xfedd]));
+        assert_eq!(join_arabic("\u{0644}\u{064e}"), chars(&[0xfedd, 0x064e]));
+        // Vowel marks on their own.
+        assert_eq!(join_arabic("\u{064e}\u{064f}"), "\u{064e}\u{064f}");
+        // Letters without forms here, like Persian's peh, are kept and break the join.
+        assert_eq!(
+            join_arabic(&text(&[BEH, '\u{067e}', BEH])),
+            format!("{}\u{067e}{}", chars(&[0xfe8f]), chars(&[0xfe8f]))
+        );
+        // Letters already in their presentation forms are kept as they are.
+        assert_eq!(
+            join_arabic(&chars(&[0xfe91, 0xfe90])),
+            chars(&[0xfe91, 0xfe90])
+        );
+    }
+
+    #[test]
+    fn odd_text_doesnt_panic() {
+        for text in &[
+            "\u{05d0}\n",
+            "\n\u{05d0}\n\n",
+            "\u{0644}",
+            "\u{200f}",
+            "\u{05d0}\u{0000}\u{202e}abc",
+            "\u{fe70}",
+            "\u{08ff}\u{0590}",
+        ] {
+            let shaped = shape(text);
+            assert_eq!(
+                shaped.matches('\n').count(),
+                text.matches('\n').count(),
+                "{:?}",
+                text
+            );
+        }
+    }
+}
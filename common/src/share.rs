@@ -0,0 +1,343 @@
+//! Share codes: levels and daily challenges packed into a short line of text that can be pasted
+//! anywhere, and unpacked again on another machine.
+//!
+//! A code is `qg1-` followed by URL-safe base64 without padding. What's inside starts with a
+//! line saying what is shared, `level <game>` or `daily <day>`, and a level is followed by its
+//! file in the shared level format. Runs of four or more of the same byte are packed as
+//! `0xff`, the length and the byte, which shrinks the open ground of a level a lot. `0xff` never
+//! appears in UTF-8, so it can't be confused with the text. The last four bytes are a checksum
+//! of the text, so a code that's been cut short or mistyped is turned away rather than giving a
+//! level with rows missing.
+//!
+//! Imported levels are installed in the `levels/<game>` folder of the data directory.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::daily::Daily;
+use crate::level::Level;
+use crate::registry;
+
+/// What every code starts with, numbered in case the format ever changes.
+const PREFIX: &str = "qg1-";
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Starts a packed run of bytes.
+const RUN: u8 = 0xff;
+
+/// Runs shorter than this are left as they are.
+const MIN_RUN: usize = 4;
+
+/// The FNV-1a offset basis and prime, for the checksum.
+const FNV_OFFSET: u32 = 0x811c_9dc5;
+const FNV_PRIME: u32 = 0x0100_0193;
+
+/// The folder a game's levels are installed in.
+pub fn levels_dir(game: &str) -> io::Result<PathBuf> {
+    Ok(crate::data_dir()?.join("levels").join(game))
+}
+
+/// Something that can be shared.
+#[derive(Debug, Clone)]
+pub enum Shared {
+    /// A level for a game.
+    Level { game: String, level: Level },
+    /// A day's challenge.
+    Daily(Daily),
+}
+
+impl Shared {
+    /// Packs this into a share code.
+    pub fn encode(&self) -> String {
+        let text = match self {
+            Shared::Level { game, level } => format!("level {}\n{}", game, level),
+            Shared::Daily(daily) => format!("daily {}\n", daily.day),
+        };
+        let mut bytes = pack(text.as_bytes());
+        bytes.extend_from_slice(&checksum(text.as_bytes()).to_be_bytes());
+        format!("{}{}", PREFIX, encode_base64(&bytes))
+    }
+
+    /// Unpacks a share code, checking that it's for a game there is and that a level parses.
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let invalid = || "that isn't a share code".to_string();
+        let data = code.trim().strip_prefix(PREFIX).ok_or_else(invalid)?;
+        let bytes = decode_base64(data).ok_or_else(invalid)?;
+        if bytes.len() < 4 {
+            return Err(invalid());
+        }
+        let (packed, sum) = bytes.split_at(bytes.len() - 4);
+        let bytes = unpack(packed).ok_or_else(invalid)?;
+        if checksum(&bytes).to_be_bytes() != sum {
+            return Err("the code is incomplete or mistyped".to_string());
+        }
+        let text = String::from_utf8(bytes).map_err(|_| invalid())?;
+
+        let mut parts = text.splitn(2, '\n');
+        let header = parts.next().unwrap_or("");
+        let body = parts.next().unwrap_or("");
+        let mut words = header.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some("level"), Some(game)) => {
+                registry::find(game).ok_or_else(|| {
+                    format!("the level is for `{}`, which isn't a game here", game)
+                })?;
+                let level =
+                    Level::parse(body).map_err(|e| format!("the level is broken: {}", e))?;
+                Ok(Shared::Level {
+                    game: game.to_string(),
+                    level,
+                })
+            }
+            (Some("daily"), Some(day)) => day
+                .parse()
+                .map(|day| Shared::Daily(Daily::for_day(day)))
+                .map_err(|_| format!("`{}` isn't a day", day)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Saves a level in its game's levels folder, named after the level, without replacing one
+/// that's already there. Returns where it was saved.
+pub fn install(game: &str, level: &Level) -> io::Result<PathBuf> {
+    let dir = levels_dir(game)?;
+    fs::create_dir_all(&dir)?;
+
+    let mut stem: String = level
+        .name()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    stem = stem.trim_matches('-').to_string();
+    if stem.is_empty() {
+        stem = "level".to_string();
+    }
+
+    let mut path = dir.join(format!("{}.txt", stem));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = dir.join(format!("{}-{}.txt", stem, copy));
+    }
+    fs::write(&path, level.to_string())?;
+    Ok(path)
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// Packs runs of the same byte.
+fn pack(bytes: &[u8]) -> Vec<u8> {
+    let mut packed = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let run = bytes[i..]
+            .iter()
+            .take(usize::from(u8::MAX))
+            .take_while(|&&b| b == byte)
+            .count();
+        if run >= MIN_RUN {
+            packed.extend_from_slice(&[RUN, run as u8, byte]);
+        } else {
+            packed.resize(packed.len() + run, byte);
+        }
+        i += run;
+    }
+    packed
+}
+
+fn unpack(packed: &[u8]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut rest = packed;
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == RUN {
+            let (&run, tail) = tail.split_first()?;
+            let (&byte, tail) = tail.split_first()?;
+            bytes.resize(bytes.len() + usize::from(run), byte);
+            rest = tail;
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    Some(bytes)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (u32::from(b) << (16 - 8 * i)));
+        for i in 0..=chunk.len() {
+            text.push(BASE64[((n >> (18 - 6 * i)) & 0x3f) as usize].into());
+        }
+    }
+    text
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let digit = BASE64.iter().position(|b| b == c)? as u32;
+            n |= digit << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "name: Open Ground\n---\n##########\n#@       #\n#      * #\n##########\n";
+
+    fn level() -> Shared {
+        Shared::Level {
+            game: "snake".to_string(),
+            level: Level::parse(LEVEL).unwrap(),
+        }
+    }
+
+    #[test]
+    fn levels_come_back_as_they_were() {
+        match Shared::decode(&level().encode()) {
+            Ok(Shared::Level { game, level }) => {
+                assert_eq!(game, "snake");
+                assert_eq!(level.to_string(), LEVEL);
+            }
+            other => panic!("expected a level, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dailies_come_back_as_they_were() {
+        let code = Shared::Daily(Daily::for_day(18_000)).encode();
+        match Shared::decode(&code) {
+            Ok(Shared::Daily(daily)) => assert_eq!(daily.day, 18_000),
+            other => panic!("expected a daily, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn codes_are_url_safe() {
+        let code = level().encode();
+        assert!(code.starts_with(PREFIX));
+        assert!(code[PREFIX.len()..]
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+    }
+
+    #[test]
+    fn surrounding_space_is_ignored() {
+        let code = format!("  {}\n", level().encode());
+        assert!(Shared::decode(&code).is_ok());
+    }
+
+    #[test]
+    fn runs_pack_and_unpack() {
+        let mut bytes = b"ab".to_vec();
+        bytes.extend(vec![b' '; 600]);
+        bytes.extend(b"aaab");
+        let packed = pack(&bytes);
+        assert!(packed.len() < 20);
+        assert_eq!(unpack(&packed), Some(bytes));
+    }
+
+    #[test]
+    fn base64_comes_back_at_every_length() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..10 {
+            let encoded = encode_base64(&bytes[..len]);
+            assert_eq!(decode_base64(&encoded), Some(bytes[..len].to_vec()));
+        }
+        assert_eq!(decode_base64(&encode_base64(&bytes)), Some(bytes));
+    }
+
+    #[test]
+    fn other_versions_are_turned_away() {
+        let code = level().encode().replacen("qg1-", "qg2-", 1);
+        assert!(Shared::decode(&code).is_err());
+        assert!(Shared::decode("").is_err());
+    }
+
+    #[test]
+    fn bad_characters_are_turned_away() {
+        let code = level().encode();
+        for bad in &["+", "/", "=", " ", "é"] {
+            let broken = format!("{}{}{}", &code[..8], bad, &code[8..]);
+            assert!(Shared::decode(&broken).is_err(), "{:?} was read", bad);
+        }
+    }
+
+    #[test]
+    fn truncated_codes_are_turned_away() {
+        let code = level().encode();
+        for len in PREFIX.len()..code.len() {
+            assert!(Shared::decode(&code[..len]).is_err(), "{} was read", len);
+        }
+    }
+
+    #[test]
+    fn truncated_runs_are_turned_away() {
+        assert_eq!(unpack(&[b'a', RUN]), None);
+        assert_eq!(unpack(&[b'a', RUN, 5]), None);
+    }
+
+    /// A code for any text, which needn't be something that can be shared.
+    fn code(text: &str) -> String {
+        let mut bytes = pack(text.as_bytes());
+        bytes.extend_from_slice(&checksum(text.as_bytes()).to_be_bytes());
+        format!("{}{}", PREFIX, encode_base64(&bytes))
+    }
+
+    #[test]
+    fn mistyped_codes_are_turned_away() {
+        let code = level().encode();
+        let at = code.len() / 2;
+        let typo = if &code[at..=at] == "A" { "B" } else { "A" };
+        let mistyped = format!("{}{}{}", &code[..at], typo, &code[at + 1..]);
+        assert!(Shared::decode(&mistyped).is_err());
+    }
+
+    #[test]
+    fn levels_for_other_games_are_turned_away() {
+        let code = code(&format!("level nothing\n{}", LEVEL));
+        assert!(Shared::decode(&code).unwrap_err().contains("nothing"));
+    }
+
+    #[test]
+    fn broken_levels_are_turned_away() {
+        assert!(Shared::decode(&code("level snake\nno tiles\n"))
+            .unwrap_err()
+            .starts_with("the level is broken"));
+    }
+
+    #[test]
+    fn unknown_kinds_are_turned_away() {
+        assert!(Shared::decode(&code("replay snake\n")).is_err());
+        assert!(Shared::decode(&code("daily tomorrow\n")).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Golden image tests: drawing a known game state offscreen and comparing it against a saved
+//! PNG, so changes to themes, sprites and scaling can't break how a game looks unnoticed.
+//!
+//! A game's tests draw into `render`, then check the pixels with `assert_matches`. The first
+//! run of a new test saves its golden image and fails, so it can be looked over and checked
+//! in. Running the tests with `UPDATE_SNAPSHOTS=1` saves new golden images over the old ones,
+//! for when a change to how a game looks is meant.
+//!
+//! The tests need a display to open a window on, since ggez can't draw without one, so games
+//! mark them `#[ignore]` and they are run with `cargo test -- --ignored`.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use ggez::conf::NumSamples;
+use ggez::graphics::{self, Canvas, Rect};
+use ggez::{Context, GameError, GameResult};
+use image::{imageops, RgbaImage};
+
+/// How far apart two images can be and still match.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tolerance {
+    /// How far apart a pixel's channels can be before the pixel differs, out of 255.
+    pub channel: u8,
+    /// The share of pixels that can differ, from `0.0` to `1.0`.
+    pub pixels: f32,
+}
+
+impl Default for Tolerance {
+    /// Enough for drivers smoothing edges and text a little differently.
+    fn default() -> Self {
+        Tolerance {
+            channel: 8,
+            pixels: 0.001,
+        }
+    }
+}
+
+/// Draws with `draw` into an offscreen canvas of the given size, cleared to black first, and
+/// returns what was drawn.
+pub fn render<F>(ctx: &mut Context, width: u16, height: u16, draw: F) -> GameResult<RgbaImage>
+where
+    F: FnOnce(&mut Context) -> GameResult<()>,
+{
+    let canvas = Canvas::new(ctx, width, height, NumSamples::One)?;
+    let screen = graphics::screen_coordinates(ctx);
+    graphics::set_canvas(ctx, Some(&canvas));
+    graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width.into(), height.into()))?;
+    graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+    let drawn = draw(ctx);
+    graphics::set_canvas(ctx, None);
+    graphics::set_screen_coordinates(ctx, screen)?;
+    drawn?;
+
+    let pixels = canvas.image().to_rgba8(ctx)?;
+    let image = RgbaImage::from_raw(width.into(), height.into(), pixels)
+        .ok_or_else(|| GameError::RenderError("The canvas was the wrong size".to_string()))?;
+    // Canvases are stored upside down.
+    Ok(imageops::flip_vertical(&image))
+}
+
+/// Checks `actual` against the golden image at `path`, saving it there instead if there isn't
+/// one yet or `UPDATE_SNAPSHOTS` is set. When they don't match, `actual` is saved next to the
+/// golden image with `.actual` before its extension, to compare them by eye.
+pub fn assert_matches(actual: &RgbaImage, path: &Path, tolerance: Tolerance) {
+    let update = env::var_os("UPDATE_SNAPSHOTS").is_some();
+    if update || !path.exists() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).expect("couldn't make the snapshots directory");
+        }
+        actual.save(path).expect("couldn't save the golden image");
+        if !update {
+            panic!(
+                "there was no golden image at {}, so this one was saved; check it over",
+                path.display()
+            );
+        }
+        return;
+    }
+
+    let golden = image::open(path)
+        .unwrap_or_else(|e| panic!("couldn't load {}: {}", path.display(), e))
+        .to_rgba();
+    if let Err(difference) = compare(actual, &golden, tolerance) {
+        let actual_path = actual_path(path);
+        if let Err(e) = actual.save(&actual_path) {
+            eprintln!("Couldn't save {}: {}", actual_path.display(), e);
+        }
+        panic!(
+            "{} doesn't match: {}. What was drawn is in {}",
+            path.display(),
+            difference,
+            actual_path.display()
+        );
+    }
+}
+
+/// Compares two images, describing how they differ if they don't match.
+pub fn compare(actual: &RgbaImage, golden: &RgbaImage, tolerance: Tolerance) -> Result<(), String> {
+    if actual.dimensions() != golden.dimensions() {
+        return Err(format!(
+            "it's {:?} rather than {:?}",
+            actual.dimensions(),
+            golden.dimensions()
+        ));
+    }
+    let differing = actual
+        .pixels()
+        .zip(golden.pixels())
+        .filter(|(a, b)| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .any(|(a, b)| (i16::from(*a) - i16::from(*b)).abs() > i16::from(tolerance.channel))
+        })
+        .count();
+    let total = actual.width() as usize * actual.height() as usize;
+    if differing as f32 > total as f32 * tolerance.pixels {
+        return Err(format!("{} of {} pixels differ", differing, total));
+    }
+    Ok(())
+}
+
+/// Where a test's drawing is saved when it doesn't match, like `game.actual.png`.
+fn actual_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("snapshot");
+    path.with_file_name(format!("{}.actual.png", stem))
+}
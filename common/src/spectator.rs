@@ -0,0 +1,167 @@
+//! A clean view of a game for a second screen, like a projector at a tournament or a stream,
+//! while the player's window keeps its score, timers and menus.
+//!
+//! Starting a game with `--spectator` opens the `spectator` window beside it. Games that take
+//! part call `world_drawn` once they have drawn their world and before their HUD, and the
+//! frame as it is then is sent to the spectator window, so it never shows the HUD, pause
+//! screen or toasts. Games that don't call it leave the spectator window waiting.
+//!
+//! ggez only opens one window a process, so the spectator window is a process of its own, fed
+//! frames over a socket on localhost. It's port 7879 by default, or the one given like
+//! `--spectator 7900`. Other viewers can connect to it too: each frame is `QGSF`, then its
+//! width and height as little endian `u32`s, then its pixels as RGBA, top row first.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::Child;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ggez::graphics;
+use ggez::{Context, GameResult};
+
+use crate::args;
+use crate::launch;
+
+/// The port frames are sent from when `--spectator` isn't given one.
+pub const DEFAULT_PORT: u16 = 7879;
+
+/// What each frame starts with.
+pub const MAGIC: &[u8; 4] = b"QGSF";
+
+/// How often a frame is sent, about thirty times a second, since reading the screen back
+/// slows drawing down.
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// The frames a spectator can fall behind by before frames are skipped for it.
+const BACKLOG: usize = 2;
+
+/// Frames being sent to spectators.
+struct Feed {
+    listener: TcpListener,
+    /// Each spectator's frames, written to it by a thread of its own.
+    spectators: Vec<SyncSender<Arc<Vec<u8>>>>,
+    last_frame: Option<Instant>,
+    /// The spectator window, if it was opened.
+    window: Option<Child>,
+}
+
+thread_local! {
+    static FEED: RefCell<Option<Feed>> = RefCell::new(None);
+}
+
+/// Starts sending frames and opens the spectator window, if the game was started with
+/// `--spectator`. `App::new` calls it.
+pub fn start_requested() {
+    if !args::flag("spectator") {
+        return;
+    }
+    let port = args::get("spectator").unwrap_or(DEFAULT_PORT);
+    if let Err(e) = start(port) {
+        eprintln!("Couldn't start the spectator view on port {}: {}", port, e);
+    }
+}
+
+fn start(port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    let window = launch::launch_spectator(port)
+        .map_err(|e| eprintln!("Couldn't open the spectator window: {}", e))
+        .ok();
+    FEED.with(|feed| {
+        *feed.borrow_mut() = Some(Feed {
+            listener,
+            spectators: Vec::new(),
+            last_frame: None,
+            window,
+        })
+    });
+    Ok(())
+}
+
+/// Stops sending frames and closes the spectator window. `App` calls it when it's dropped.
+pub fn stop() {
+    let feed = FEED.with(|feed| feed.borrow_mut().take());
+    if let Some(Feed {
+        window: Some(mut window),
+        ..
+    }) = feed
+    {
+        if let Err(e) = window.kill() {
+            eprintln!("Couldn't close the spectator window: {}", e);
+        }
+        let _ = window.wait();
+    }
+}
+
+/// Sends what has been drawn so far to the spectators. Games call it after drawing their world
+/// and before drawing their HUD. It does nothing unless the game was started with
+/// `--spectator` and someone is watching.
+pub fn world_drawn(ctx: &mut Context) -> GameResult<()> {
+    FEED.with(|feed| {
+        let mut feed = feed.borrow_mut();
+        let feed = match feed.as_mut() {
+            Some(feed) => feed,
+            None => return Ok(()),
+        };
+        feed.accept();
+        if feed.spectators.is_empty()
+            || feed
+                .last_frame
+                .map_or(false, |last| last.elapsed() < FRAME_INTERVAL)
+        {
+            return Ok(());
+        }
+        feed.last_frame = Some(Instant::now());
+
+        let screen = graphics::screenshot(ctx)?;
+        let pixels = screen.to_rgba8(ctx)?;
+        let mut frame = Vec::with_capacity(MAGIC.len() + 8 + pixels.len());
+        frame.extend_from_slice(MAGIC);
+        frame.extend_from_slice(&u32::from(screen.width()).to_le_bytes());
+        frame.extend_from_slice(&u32::from(screen.height()).to_le_bytes());
+        frame.extend_from_slice(&pixels);
+        let frame = Arc::new(frame);
+        feed.spectators
+            .retain(|spectator| match spectator.try_send(Arc::clone(&frame)) {
+                Ok(()) | Err(TrySendError::Full(_)) => true,
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+        Ok(())
+    })
+}
+
+impl Feed {
+    /// Takes on any spectators that have connected since the last frame.
+    fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => match stream.set_nonblocking(false) {
+                    Ok(()) => self.spectators.push(spawn_writer(stream)),
+                    Err(e) => eprintln!("Couldn't take on a spectator: {}", e),
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    eprintln!("Couldn't take on a spectator: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Writes frames to a spectator until it disconnects, so a slow one doesn't hold up the game.
+fn spawn_writer(mut stream: TcpStream) -> SyncSender<Arc<Vec<u8>>> {
+    let (sender, frames) = mpsc::sync_channel::<Arc<Vec<u8>>>(BACKLOG);
+    thread::spawn(move || {
+        for frame in frames {
+            if stream.write_all(&frame).is_err() {
+                return;
+            }
+        }
+    });
+    sender
+}
@@ -0,0 +1,124 @@
+//! Reading things out for players who can't easily see the screen: what has focus in the
+//! launcher and the pause and game over menus, scores as they change, and toasts.
+//!
+//! It's on when the profile's `speech` setting is `true`, which the launcher toggles with R,
+//! and like any setting it can be turned on for a run with `--speech true`. It speaks with the
+//! system's own voice: `spd-say` from speech-dispatcher on Linux and the BSDs, `say` on macOS
+//! and the speech synthesizer through PowerShell on Windows.
+//!
+//! `say` is for what the player just did, like moving the focus, and cuts off whatever is
+//! being said. `say_politely` is for everything else, like a score going up, and is dropped if
+//! something is still being said, so fast games don't build up a queue of stale scores.
+
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::process::{Child, Command};
+
+use crate::settings::Settings;
+
+const SPEECH_KEY: &str = "speech";
+
+thread_local! {
+    /// Whether speech is on, once it has been looked up.
+    static ENABLED: Cell<Option<bool>> = Cell::new(None);
+    /// What is being said, until it's finished.
+    static SPEAKING: RefCell<Option<Child>> = RefCell::new(None);
+}
+
+/// Whether things are read out.
+pub fn enabled() -> bool {
+    ENABLED.with(|enabled| {
+        enabled.get().unwrap_or_else(|| {
+            let on = Settings::load().get_or(SPEECH_KEY, false);
+            enabled.set(Some(on));
+            on
+        })
+    })
+}
+
+/// Turns speech on or off and saves it in the profile's settings.
+pub fn set_enabled(on: bool) -> io::Result<()> {
+    if !on {
+        stop();
+    }
+    ENABLED.with(|enabled| enabled.set(Some(on)));
+    let mut settings = Settings::load();
+    settings.set(SPEECH_KEY, on);
+    settings.save()
+}
+
+/// Says `text`, cutting off anything still being said.
+pub fn say(text: &str) {
+    if !enabled() {
+        return;
+    }
+    stop();
+    start(text);
+}
+
+/// Says `text` unless something is still being said.
+pub fn say_politely(text: &str) {
+    if enabled() && !speaking() {
+        start(text);
+    }
+}
+
+fn speaking() -> bool {
+    SPEAKING.with(|speaking| {
+        let mut speaking = speaking.borrow_mut();
+        let finished = match speaking.as_mut() {
+            Some(child) => child.try_wait().map_or(true, |status| status.is_some()),
+            None => true,
+        };
+        if finished {
+            *speaking = None;
+        }
+        !finished
+    })
+}
+
+/// Stops whatever is being said.
+pub fn stop() {
+    SPEAKING.with(|speaking| {
+        if let Some(mut child) = speaking.borrow_mut().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    });
+}
+
+fn start(text: &str) {
+    match command(text).spawn() {
+        Ok(child) => SPEAKING.with(|speaking| *speaking.borrow_mut() = Some(child)),
+        Err(e) => eprintln!("Couldn't read out \"{}\": {}", text, e),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn command(text: &str) -> Command {
+    // Single quoted strings in PowerShell only need their quotes doubling.
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; \
+         (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+        text.replace('\'', "''")
+    );
+    let mut command = Command::new("powershell");
+    command.args(&["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script]);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn command(text: &str) -> Command {
+    let mut command = Command::new("say");
+    command.arg(text);
+    command
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn command(text: &str) -> Command {
+    // Waiting keeps the process around for as long as it's speaking. Killing it doesn't stop
+    // speech-dispatcher, but at the text priority whatever is said next cuts it off.
+    let mut command = Command::new("spd-say");
+    command.args(&["--wait", "--priority", "text", "--", text]);
+    command
+}
@@ -0,0 +1,132 @@
+//! A small append-only store of per-run statistics.
+//!
+//! Every finished run is written as a single line of tab separated fields:
+//! `timestamp game key=value key=value ...`.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::locale;
+use crate::migrate::{self, STATS};
+
+pub(crate) const STATS_FILE: &str = "stats.tsv";
+
+/// A single recorded run of a game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// Seconds since the unix epoch when the run was recorded.
+    pub timestamp: u64,
+    /// The name of the game the run belongs to.
+    pub game: String,
+    /// The named values recorded for the run (e.g., `score`, `wpm`).
+    pub values: Vec<(String, f64)>,
+}
+
+impl Record {
+    /// Returns the value recorded under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<f64> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// Lists the run's values along with how long ago it was.
+    pub fn describe(&self) -> String {
+        let values: Vec<String> = self
+            .values
+            .iter()
+            .map(|(key, value)| format!("{} {}", key.replace('_', " "), format_value(*value)))
+            .collect();
+        format!("{}  ({})", values.join(", "), age(self.timestamp))
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        let timestamp = fields.next()?.parse().ok()?;
+        let game = fields.next()?.to_string();
+        let values = fields
+            .filter_map(|field| {
+                let mut parts = field.splitn(2, '=');
+                let key = parts.next()?.to_string();
+                let value = parts.next()?.parse().ok()?;
+                Some((key, value))
+            })
+            .collect();
+
+        Some(Record {
+            timestamp,
+            game,
+            values,
+        })
+    }
+}
+
+/// Handle to the statistics file in the shared data directory.
+#[derive(Debug, Clone)]
+pub struct StatsDb {
+    path: PathBuf,
+}
+
+impl StatsDb {
+    /// Opens the statistics store in the shared data directory.
+    pub fn open() -> io::Result<Self> {
+        Ok(StatsDb {
+            path: crate::data_dir()?.join(STATS_FILE),
+        })
+    }
+
+    /// Appends a run of `game` with the given named values.
+    pub fn record(&self, game: &str, values: &[(&str, f64)]) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut line = format!("{}\t{}", timestamp, game);
+        for (key, value) in values {
+            line.push_str(&format!("\t{}={}", key, value));
+        }
+
+        migrate::append(&self.path, &STATS, &line)
+    }
+
+    /// Reads every recorded run, oldest first. Lines that fail to parse are skipped.
+    pub fn records(&self) -> io::Result<Vec<Record>> {
+        match migrate::read(&self.path, &STATS) {
+            Ok(contents) => Ok(contents.lines().filter_map(Record::parse).collect()),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads every recorded run of `game`, oldest first.
+    pub fn records_for(&self, game: &str) -> io::Result<Vec<Record>> {
+        Ok(self
+            .records()?
+            .into_iter()
+            .filter(|record| record.game == game)
+            .collect())
+    }
+}
+
+/// Shows a recorded value in the player's locale, with whole numbers without a decimal point.
+pub fn format_value(value: f64) -> String {
+    locale::number(value)
+}
+
+/// How long ago a timestamp was, roughly.
+pub fn age(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let seconds = now.saturating_sub(timestamp);
+    match seconds {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{} min ago", seconds / 60),
+        3600..=86399 => format!("{} h ago", seconds / 3600),
+        _ => format!("{} days ago", seconds / 86400),
+    }
+}
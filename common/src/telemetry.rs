@@ -0,0 +1,246 @@
+//! Anonymous play statistics, which players can choose to share so it's clearer which games and
+//! modes get played.
+//!
+//! Nothing is recorded or sent unless the profile's `telemetry` setting is `on`. The launcher
+//! asks the first time it's started, and answering no sets it `off`; either can be changed on
+//! its privacy screen, which also shows exactly what would be sent.
+//!
+//! Only counts are kept, with no times, names or anything else about the player: how many times
+//! each game was played in each mode, and how many of its runs scored in each band, like
+//! 100-999. They're kept in `telemetry.tsv` in the profile's directory, as lines of the event,
+//! the game, the mode, the band and the count.
+//!
+//! The launcher sends them when it starts, as JSON with `POST` to the URL in the
+//! `telemetry_url` setting, and takes whatever was sent off the counts. Like sync it only
+//! speaks plain HTTP, so the URL has to be on this computer unless `allow_plain_http` is set;
+//! see `sync`. Without a URL they're never sent anywhere.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use std::thread;
+
+use crate::json::{optional, quote};
+use crate::migrate::{self, TELEMETRY};
+use crate::profile;
+use crate::settings::Settings;
+use crate::sync::Server;
+
+/// The setting saying whether the profile shares its play statistics.
+pub const ENABLED_SETTING: &str = "telemetry";
+/// The setting holding the URL the statistics are sent to.
+pub const URL_SETTING: &str = "telemetry_url";
+
+const TELEMETRY_FILE: &str = "telemetry.tsv";
+
+/// The highest band a score is put in, which holds everything from it up.
+const TOP_BAND: u64 = 1_000_000;
+
+/// What is being counted.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Event {
+    /// A game being started and played.
+    Played,
+    /// A run finishing with a score in a band.
+    Scored,
+}
+
+impl Event {
+    fn name(self) -> &'static str {
+        match self {
+            Event::Played => "played",
+            Event::Scored => "scored",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "played" => Some(Event::Played),
+            "scored" => Some(Event::Scored),
+            _ => None,
+        }
+    }
+}
+
+/// What a count is of: the event, the game, the mode if it has one, and the score band for
+/// `Scored`.
+pub type Key = (Event, String, Option<String>, Option<String>);
+
+/// Whether the profile has been asked about sharing its statistics, and what it said.
+pub fn consent() -> Option<bool> {
+    match Settings::load().get(ENABLED_SETTING) {
+        Some("on") => Some(true),
+        Some("off") => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the profile shares its statistics.
+pub fn enabled() -> bool {
+    consent() == Some(true)
+}
+
+/// Saves whether the profile shares its statistics. Turning it off forgets anything counted
+/// and not yet sent.
+pub fn set_enabled(on: bool) -> io::Result<()> {
+    let mut settings = Settings::load();
+    settings.set(ENABLED_SETTING, if on { "on" } else { "off" });
+    settings.save()?;
+    if !on {
+        save(&BTreeMap::new())?;
+    }
+    Ok(())
+}
+
+/// The URL the statistics are sent to, if there is one.
+pub fn url() -> Option<String> {
+    Settings::load()
+        .get(URL_SETTING)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+fn path() -> io::Result<PathBuf> {
+    Ok(profile::profile_dir()?.join(TELEMETRY_FILE))
+}
+
+/// Everything counted and not yet sent.
+pub fn load() -> io::Result<BTreeMap<Key, u64>> {
+    let contents = match migrate::read(&path()?, &TELEMETRY) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e),
+    };
+    let unless_empty = |field: &str| Some(field.to_string()).filter(|field| !field.is_empty());
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let event = Event::from_name(fields.next()?)?;
+            let game = fields.next()?.to_string();
+            let mode = unless_empty(fields.next()?);
+            let band = unless_empty(fields.next()?);
+            let count = fields.next()?.parse().ok()?;
+            Some(((event, game, mode, band), count))
+        })
+        .collect())
+}
+
+fn save(counts: &BTreeMap<Key, u64>) -> io::Result<()> {
+    let mut contents = String::new();
+    for ((event, game, mode, band), count) in counts {
+        contents.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            event.name(),
+            game,
+            mode.as_deref().unwrap_or(""),
+            band.as_deref().unwrap_or(""),
+            count
+        ));
+    }
+    migrate::write(&path()?, &TELEMETRY, &contents)
+}
+
+/// The band a score falls in, by its number of digits, like `100-999`.
+pub fn band(score: f64) -> String {
+    if score.is_nan() || score < 1.0 {
+        return "under 1".to_string();
+    }
+    let mut low = 1;
+    while low < TOP_BAND && (low * 10) as f64 <= score {
+        low *= 10;
+    }
+    if low == TOP_BAND {
+        format!("{}+", TOP_BAND)
+    } else {
+        format!("{}-{}", low, low * 10 - 1)
+    }
+}
+
+/// Counts a game being played, and the scores of the runs finished in it, if the profile
+/// shares its statistics. `App` calls it once the game closes.
+pub fn record(game: &str, mode: Option<&str>, scores: &[f64]) -> io::Result<()> {
+    if !enabled() {
+        return Ok(());
+    }
+    let mut counts = load()?;
+    let mode = mode.map(str::to_string);
+    *counts
+        .entry((Event::Played, game.to_string(), mode.clone(), None))
+        .or_insert(0) += 1;
+    for &score in scores {
+        let key = (
+            Event::Scored,
+            game.to_string(),
+            mode.clone(),
+            Some(band(score)),
+        );
+        *counts.entry(key).or_insert(0) += 1;
+    }
+    save(&counts)
+}
+
+/// The counts as the JSON that is sent.
+pub fn report(counts: &BTreeMap<Key, u64>) -> String {
+    let lines: Vec<String> = counts
+        .iter()
+        .map(|((event, game, mode, band), count)| {
+            format!(
+                "    {{\"event\": {}, \"game\": {}, \"mode\": {}, \"band\": {}, \"count\": {}}}",
+                quote(event.name()),
+                quote(game),
+                optional(mode.as_deref()),
+                optional(band.as_deref()),
+                count
+            )
+        })
+        .collect();
+    let list = if lines.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n  ]", lines.join(",\n"))
+    };
+    format!(
+        "{{\n  \"version\": {},\n  \"counts\": {}\n}}\n",
+        quote(env!("CARGO_PKG_VERSION")),
+        list
+    )
+}
+
+/// Sends everything counted to the URL in the settings, then takes it off the counts. Returns
+/// how many counts were sent, which is none if there's nothing to send or nowhere to send it.
+pub fn send() -> Result<usize, String> {
+    if !enabled() {
+        return Ok(0);
+    }
+    let url = match url() {
+        Some(url) => url,
+        None => return Ok(0),
+    };
+    let sent = load().map_err(|e| format!("couldn't read the statistics: {}", e))?;
+    if sent.is_empty() {
+        return Ok(0);
+    }
+    Server::parse(&url)?.post(report(&sent).as_bytes())?;
+
+    // Anything counted while they were being sent is kept for next time.
+    let mut counts = load().map_err(|e| format!("couldn't read the statistics: {}", e))?;
+    for (key, count) in &sent {
+        if let Some(left) = counts.get_mut(key) {
+            *left = left.saturating_sub(*count);
+        }
+    }
+    counts.retain(|_, count| *count > 0);
+    save(&counts).map_err(|e| format!("couldn't save the statistics: {}", e))?;
+    Ok(sent.len())
+}
+
+/// Sends the statistics like `send`, without waiting for them to go.
+pub fn send_in_background() {
+    thread::spawn(|| {
+        if let Err(e) = send() {
+            eprintln!("Couldn't send the play statistics: {}", e);
+        }
+    });
+}
@@ -0,0 +1,213 @@
+//! Text styles shared by the launcher and the games, so text is drawn in the same font and at
+//! the same sizes everywhere.
+//!
+//! Text uses the bundled font unless the profile's settings give another:
+//!
+//! - `font`: the path of a `.ttf` file to use instead.
+//! - `script_font`: the path of a `.ttf` file for text in scripts other than Latin, like
+//!   Arabic or Hebrew. The bundled font only has Latin letters, so without it such text is
+//!   drawn in `font` if one is given, and otherwise in ggez's own font, which has Greek and
+//!   Cyrillic but not right-to-left scripts.
+//! - `text_scale`: how much to scale every size by, instead of the window's DPI factor.
+//!
+//! Right-to-left text is shaped before it's drawn; see `shaping`. `Alignment::Start` lines
+//! text up on the side it starts from, so it's on the right for those scripts.
+
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::fs;
+
+use ggez::graphics::{self, Align, Font, Rect, Scale, Text, TextFragment};
+use ggez::{Context, GameResult};
+
+use crate::settings::Settings;
+use crate::shaping::{self, Direction};
+
+const BUNDLED_FONT: &[u8] = include_bytes!("../assets/font/square.ttf");
+
+/// The fonts and scale once they have been loaded, which needs a context.
+#[derive(Debug, Copy, Clone)]
+struct Loaded {
+    font: Font,
+    script_font: Font,
+    scale: f32,
+}
+
+thread_local! {
+    static LOADED: Cell<Option<Loaded>> = Cell::new(None);
+}
+
+/// Where a line of text goes across the width it's given.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Alignment {
+    /// The side the text starts from: the left, or the right for right-to-left text.
+    Start,
+    /// The side the text finishes on.
+    End,
+    Centre,
+}
+
+impl Alignment {
+    /// Which side of its width `text` goes on.
+    pub fn align(self, text: &str) -> Align {
+        let rtl = shaping::direction(text) == Direction::RightToLeft;
+        match (self, rtl) {
+            (Alignment::Centre, _) => Align::Center,
+            (Alignment::Start, false) | (Alignment::End, true) => Align::Left,
+            (Alignment::Start, true) | (Alignment::End, false) => Align::Right,
+        }
+    }
+}
+
+/// What a piece of text is for, which decides its size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextStyle {
+    /// Messages across the screen, like the game over message.
+    Title,
+    /// Scores and anything else kept on screen while playing.
+    Hud,
+    /// Menus, instructions and everything else.
+    Body,
+}
+
+impl TextStyle {
+    /// The size of the style in points, before it is scaled to the window.
+    pub fn points(self) -> f32 {
+        match self {
+            TextStyle::Title => 40.0,
+            TextStyle::Hud => 24.0,
+            TextStyle::Body => 20.0,
+        }
+    }
+
+    pub fn fragment<T: Into<TextFragment>>(
+        self,
+        ctx: &mut Context,
+        text: T,
+        color: [f32; 4],
+    ) -> TextFragment {
+        fragment(ctx, text, color, self.points())
+    }
+
+    pub fn text<T: Into<TextFragment>>(self, ctx: &mut Context, text: T, color: [f32; 4]) -> Text {
+        Text::new(self.fragment(ctx, text, color))
+    }
+
+    /// Draws a line of text in this style with its top left corner at `(x, y)`.
+    pub fn draw<T: Into<TextFragment>>(
+        self,
+        ctx: &mut Context,
+        text: T,
+        color: [f32; 4],
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = self.text(ctx, text, color);
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    /// Draws a line of text in this style across `area`, lined up with `alignment`.
+    pub fn draw_aligned(
+        self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        area: Rect,
+        alignment: Alignment,
+    ) -> GameResult<()> {
+        let mut drawn = self.text(ctx, text, color);
+        drawn.set_bounds(
+            ggez::mint::Point2 {
+                x: area.w,
+                y: area.h,
+            },
+            alignment.align(text),
+        );
+        graphics::draw(
+            ctx,
+            &drawn,
+            (ggez::mint::Point2 {
+                x: area.x,
+                y: area.y,
+            },),
+        )
+    }
+}
+
+/// Loads the font file a setting gives, if it's set and can be loaded.
+fn load_setting_font(ctx: &mut Context, settings: &Settings, key: &str) -> Option<Font> {
+    settings
+        .get(key)
+        .and_then(|path| fs::read(path).ok())
+        .and_then(|bytes| Font::new_glyph_font_bytes(ctx, &bytes).ok())
+}
+
+/// Loads the font from the settings, falling back to the bundled font and then ggez's own if
+/// they can't be loaded.
+fn load_font(ctx: &mut Context, settings: &Settings) -> Font {
+    load_setting_font(ctx, settings, "font")
+        .or_else(|| Font::new_glyph_font_bytes(ctx, BUNDLED_FONT).ok())
+        .unwrap_or_default()
+}
+
+fn loaded(ctx: &mut Context) -> Loaded {
+    if let Some(loaded) = LOADED.with(Cell::get) {
+        return loaded;
+    }
+
+    let settings = Settings::load();
+    let font = load_font(ctx, &settings);
+    // A font of the player's own may well have the other scripts already.
+    let script_font = load_setting_font(ctx, &settings, "script_font")
+        .or_else(|| settings.get("font").map(|_| font))
+        .unwrap_or_default();
+    let scale = settings.get_or("text_scale", graphics::os_hidpi_factor(ctx));
+    let loaded = Loaded {
+        font,
+        script_font,
+        scale: if scale > 0.0 { scale } else { 1.0 },
+    };
+    LOADED.with(|cell| cell.set(Some(loaded)));
+    loaded
+}
+
+/// Whether text has letters from outside the Latin alphabets, which the bundled font doesn't
+/// have.
+fn needs_script_font(text: &str) -> bool {
+    text.chars()
+        .any(|c| c.is_alphabetic() && c > '\u{024f}' && !('\u{1e00}'..='\u{1eff}').contains(&c))
+}
+
+/// The font all text is drawn in.
+pub fn font(ctx: &mut Context) -> Font {
+    loaded(ctx).font
+}
+
+/// The scale to draw text of `points` at on this window.
+pub fn scale(ctx: &mut Context, points: f32) -> Scale {
+    Scale::uniform(points * loaded(ctx).scale)
+}
+
+/// A piece of text in the shared font at a size that doesn't fit any of the styles. Text in
+/// other scripts is shaped and drawn in the script font.
+pub fn fragment<T: Into<TextFragment>>(
+    ctx: &mut Context,
+    text: T,
+    color: [f32; 4],
+    points: f32,
+) -> TextFragment {
+    let loaded = loaded(ctx);
+    let mut fragment = text.into();
+    if let Cow::Owned(shaped) = shaping::shape(&fragment.text) {
+        fragment.text = shaped;
+    }
+    let font = if needs_script_font(&fragment.text) {
+        loaded.script_font
+    } else {
+        loaded.font
+    };
+    fragment
+        .color(color.into())
+        .font(font)
+        .scale(Scale::uniform(points * loaded.scale))
+}
@@ -0,0 +1,286 @@
+//! Colour themes picked in the launcher and shared by every game.
+//!
+//! Each game describes its own colours as a `Palette` of what things are rather than what
+//! colour they are, and draws with whatever `theme::palette` gives back for it. The original
+//! theme leaves every game with its own colours.
+//!
+//! Some themes have to be bought with tokens in the launcher's shop before they can be picked.
+//!
+//! Unless the profile's `theme` setting names a theme, it's `system`, which follows the system's
+//! dark or light preference with the dark or light theme, and keeps the original theme when the
+//! system doesn't say. The preference is looked up once when a game starts, and again by the
+//! launcher whenever its window comes back into focus, which is usually after the system's
+//! settings have been changed. It's read with the system's own tools: `gsettings` on Linux and
+//! the BSDs, `defaults` on macOS and `reg` on Windows.
+
+use std::cell::Cell;
+use std::process::Command;
+
+use crate::settings::Settings;
+use crate::tokens::Wallet;
+
+/// The colours a game draws with, by what they are used for.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Palette {
+    pub background: [f32; 4],
+    /// Text and anything else drawn over the background that isn't part of the game.
+    pub text: [f32; 4],
+    /// What the player controls.
+    pub player: [f32; 4],
+    /// What the player has to avoid.
+    pub hazard: [f32; 4],
+    /// What the player is trying to collect.
+    pub pickup: [f32; 4],
+}
+
+/// A named set of colours.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Theme {
+    /// The name the theme is saved under.
+    pub name: &'static str,
+    pub title: &'static str,
+    /// The colours every game uses instead of its own, or `None` to keep each game's colours.
+    pub palette: Option<Palette>,
+    /// What the theme costs in the shop, or 0 if every profile has it.
+    pub price: u32,
+}
+
+impl Theme {
+    /// The colours a game should draw with, given its own.
+    pub fn apply(&self, own: Palette) -> Palette {
+        self.palette.unwrap_or(own)
+    }
+
+    /// The name the theme is bought under in the token ledger.
+    pub fn item(&self) -> String {
+        format!("theme:{}", self.name)
+    }
+
+    /// Whether the profile can pick the theme.
+    pub fn is_owned(&self, wallet: &Wallet) -> bool {
+        self.price == 0 || wallet.owns(&self.item())
+    }
+}
+
+/// Whether the system prefers dark or light colours.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Appearance {
+    Dark,
+    Light,
+}
+
+thread_local! {
+    /// The system's preference, once it has been looked up.
+    static APPEARANCE: Cell<Option<Option<Appearance>>> = Cell::new(None);
+}
+
+/// Stands in for the dark or light theme, whichever the system prefers. It isn't one of
+/// `THEMES`, since it has no colours of its own.
+pub const SYSTEM: Theme = Theme {
+    name: "system",
+    title: "System",
+    palette: None,
+    price: 0,
+};
+
+/// Every theme, in the order the launcher cycles through them and the shop lists them.
+pub const THEMES: &[Theme] = &[
+    Theme {
+        name: "original",
+        title: "Original",
+        palette: None,
+        price: 0,
+    },
+    Theme {
+        name: "dark",
+        title: "Dark",
+        palette: Some(Palette {
+            background: [0.08, 0.08, 0.1, 1.0],
+            text: [0.9, 0.9, 0.92, 1.0],
+            player: [0.35, 0.7, 1.0, 1.0],
+            hazard: [0.95, 0.35, 0.3, 1.0],
+            pickup: [0.95, 0.8, 0.3, 1.0],
+        }),
+        price: 0,
+    },
+    Theme {
+        name: "light",
+        title: "Light",
+        palette: Some(Palette {
+            background: [0.95, 0.95, 0.92, 1.0],
+            text: [0.1, 0.1, 0.12, 1.0],
+            player: [0.15, 0.4, 0.8, 1.0],
+            hazard: [0.8, 0.15, 0.15, 1.0],
+            pickup: [0.1, 0.6, 0.25, 1.0],
+        }),
+        price: 0,
+    },
+    Theme {
+        name: "neon",
+        title: "Neon",
+        palette: Some(Palette {
+            background: [0.02, 0.0, 0.06, 1.0],
+            text: [0.9, 1.0, 1.0, 1.0],
+            player: [0.0, 1.0, 0.9, 1.0],
+            hazard: [1.0, 0.1, 0.6, 1.0],
+            pickup: [0.9, 1.0, 0.1, 1.0],
+        }),
+        price: 100,
+    },
+    // The four shades of the original Game Boy's screen.
+    Theme {
+        name: "gameboy",
+        title: "Game Boy",
+        palette: Some(Palette {
+            background: [0.61, 0.74, 0.06, 1.0],
+            text: [0.06, 0.22, 0.06, 1.0],
+            player: [0.06, 0.22, 0.06, 1.0],
+            hazard: [0.19, 0.38, 0.19, 1.0],
+            pickup: [0.55, 0.67, 0.06, 1.0],
+        }),
+        price: 150,
+    },
+    Theme {
+        name: "sunset",
+        title: "Sunset",
+        palette: Some(Palette {
+            background: [0.18, 0.07, 0.2, 1.0],
+            text: [1.0, 0.88, 0.75, 1.0],
+            player: [1.0, 0.6, 0.25, 1.0],
+            hazard: [0.85, 0.2, 0.4, 1.0],
+            pickup: [1.0, 0.85, 0.35, 1.0],
+        }),
+        price: 250,
+    },
+];
+
+/// Looks up a theme, or `SYSTEM`, by the name it is saved under.
+pub fn find(name: &str) -> Option<&'static Theme> {
+    if name == SYSTEM.name {
+        return Some(&SYSTEM);
+    }
+    THEMES.iter().find(|theme| theme.name == name)
+}
+
+/// The theme chosen in the current profile's settings, which may be `SYSTEM`.
+pub fn chosen() -> &'static Theme {
+    chosen_in(&Settings::load())
+}
+
+fn chosen_in(settings: &Settings) -> &'static Theme {
+    settings.get("theme").and_then(find).unwrap_or(&SYSTEM)
+}
+
+/// The theme to draw with under the current profile's settings.
+pub fn current() -> &'static Theme {
+    from_settings(&Settings::load())
+}
+
+/// The theme to draw with under the given settings, with `SYSTEM` swapped for the theme it
+/// stands in for.
+pub fn from_settings(settings: &Settings) -> &'static Theme {
+    resolve(chosen_in(settings))
+}
+
+/// The theme `SYSTEM` stands in for right now, or any other theme as it is.
+pub fn resolve(theme: &'static Theme) -> &'static Theme {
+    if theme.name != SYSTEM.name {
+        return theme;
+    }
+    let name = match system_appearance() {
+        Some(Appearance::Dark) => "dark",
+        Some(Appearance::Light) => "light",
+        None => return &THEMES[0],
+    };
+    find(name).unwrap_or(&THEMES[0])
+}
+
+/// Whether the system prefers dark or light colours, if it says.
+pub fn system_appearance() -> Option<Appearance> {
+    APPEARANCE.with(|appearance| {
+        appearance.get().unwrap_or_else(|| {
+            let looked_up = look_up_appearance();
+            appearance.set(Some(looked_up));
+            looked_up
+        })
+    })
+}
+
+/// Forgets the system's preference, so it's looked up again the next time it's needed.
+pub fn refresh_system_appearance() {
+    APPEARANCE.with(|appearance| appearance.set(None));
+}
+
+/// Runs a command, giving back what it wrote if it succeeded.
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        None
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn look_up_appearance() -> Option<Appearance> {
+    let written = output(
+        "reg",
+        &[
+            "query",
+            r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+            "/v",
+            "AppsUseLightTheme",
+        ],
+    )?;
+    // The value is the last thing on its line, as `0x0` for dark or `0x1` for light.
+    let value = written
+        .lines()
+        .find(|line| line.contains("AppsUseLightTheme"))?
+        .split_whitespace()
+        .last()?;
+    match value {
+        "0x0" => Some(Appearance::Dark),
+        "0x1" => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn look_up_appearance() -> Option<Appearance> {
+    // Only set at all when the system is dark, so reading it fails when it's light.
+    match output("defaults", &["read", "-g", "AppleInterfaceStyle"]) {
+        Some(style) if style.trim() == "Dark" => Some(Appearance::Dark),
+        _ => Some(Appearance::Light),
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn look_up_appearance() -> Option<Appearance> {
+    const SCHEMA: &str = "org.gnome.desktop.interface";
+    // Newer desktops say outright, written back quoted, like `'prefer-dark'`. Older ones only
+    // have the name of the GTK theme, and dark ones are named that way, like `Adwaita-dark`.
+    let scheme = output("gsettings", &["get", SCHEMA, "color-scheme"]).unwrap_or_default();
+    match scheme.trim().trim_matches('\'') {
+        "prefer-dark" => return Some(Appearance::Dark),
+        "prefer-light" => return Some(Appearance::Light),
+        _ => {}
+    }
+    let gtk_theme = output("gsettings", &["get", SCHEMA, "gtk-theme"])?;
+    if gtk_theme.to_lowercase().contains("dark") {
+        Some(Appearance::Dark)
+    } else {
+        Some(Appearance::Light)
+    }
+}
+
+/// Saves the theme every game should use from now on.
+pub fn save(theme: &Theme) -> std::io::Result<()> {
+    let mut settings = Settings::load();
+    settings.set("theme", theme.name);
+    settings.save()
+}
+
+/// The colours a game should draw with under the current theme, given its own.
+pub fn palette(own: Palette) -> Palette {
+    current().apply(own)
+}
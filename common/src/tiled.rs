@@ -0,0 +1,558 @@
+//! Loading maps made in the Tiled editor (`.tmx` files), so levels can be drawn in a standard
+//! editor instead of typed out.
+//!
+//! A map is read into a `TileMap`: its tile layers, the properties of each tile in its tilesets
+//! and the objects on its object layers. `TileMap::to_level` turns that into the shared level
+//! format, with a character for each kind of tile a game cares about, so a game reading levels
+//! can take Tiled maps with a few extra lines.
+//!
+//! Tiles are told apart by a boolean custom property on the tile in its tileset, like `solid` or
+//! `hazard`, or by the tile's type (its class, in newer versions of Tiled). Objects are told
+//! apart by their type or class, like `spawn`, and land on the tile under their position.
+//!
+//! Layers have to be saved in the CSV or XML format, which is set in the map's properties in
+//! Tiled. Tilesets can be embedded in the map or saved as `.tsx` files next to it.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::grid::Grid;
+use crate::level::Level;
+
+/// The bits of a tile ID that say whether it's flipped, rather than which tile it is.
+const FLIP_FLAGS: u32 = 0xe000_0000;
+
+/// An object from an object layer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub name: String,
+    /// The object's type, or its class in newer versions of Tiled.
+    pub kind: String,
+    /// The tile the object is on.
+    pub x: i32,
+    pub y: i32,
+    pub properties: Vec<(String, String)>,
+}
+
+/// A tile layer, holding the global ID of the tile in each cell, or `0` where it's empty.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub name: String,
+    pub tiles: Grid<u32>,
+}
+
+/// A loaded Tiled map.
+#[derive(Debug, Clone)]
+pub struct TileMap {
+    pub width: usize,
+    pub height: usize,
+    /// The map's custom properties.
+    pub properties: Vec<(String, String)>,
+    /// The tile layers, from the bottom up.
+    pub layers: Vec<Layer>,
+    /// The objects from every object layer.
+    pub objects: Vec<Object>,
+    /// The type and custom properties of each tile that has any, by global ID.
+    tiles: HashMap<u32, (String, Vec<(String, String)>)>,
+}
+
+impl TileMap {
+    /// Loads a map from a file, reading any external tilesets from next to it.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        TileMap::parse(&contents, |source| {
+            fs::read_to_string(dir.join(source)).map_err(|e| format!("{}: {}", source, e))
+        })
+    }
+
+    /// Parses a map, calling `read_tileset` with the path of any external tileset, relative to
+    /// the map, for its contents.
+    pub fn parse<F>(contents: &str, mut read_tileset: F) -> Result<Self, String>
+    where
+        F: FnMut(&str) -> Result<String, String>,
+    {
+        let root = parse_xml(contents)?;
+        if root.name != "map" {
+            return Err("not a Tiled map".to_string());
+        }
+        let width: usize = root.number("width")?;
+        let height: usize = root.number("height")?;
+        if width == 0 || height == 0 {
+            return Err("the map has no tiles".to_string());
+        }
+        let tile_width: f32 = root.number("tilewidth")?;
+        let tile_height: f32 = root.number("tileheight")?;
+
+        let mut map = TileMap {
+            width,
+            height,
+            properties: properties(&root),
+            layers: Vec::new(),
+            objects: Vec::new(),
+            tiles: HashMap::new(),
+        };
+
+        for element in &root.children {
+            match element.name.as_str() {
+                "tileset" => {
+                    let first_gid: u32 = element.number("firstgid")?;
+                    let tileset = match element.attr("source") {
+                        Some(source) => parse_xml(&read_tileset(source)?)?,
+                        None => element.clone(),
+                    };
+                    for tile in tileset.children.iter().filter(|e| e.name == "tile") {
+                        let id: u32 = tile.number("id")?;
+                        let gid = first_gid
+                            .checked_add(id)
+                            .filter(|gid| gid & FLIP_FLAGS == 0)
+                            .ok_or_else(|| format!("tile {} is past the last tile ID", id))?;
+                        let kind = tile.attr("type").or_else(|| tile.attr("class"));
+                        map.tiles
+                            .insert(gid, (kind.unwrap_or("").to_string(), properties(tile)));
+                    }
+                }
+                "layer" => map.layers.push(layer(element, width, height)?),
+                "objectgroup" => {
+                    for object in element.children.iter().filter(|e| e.name == "object") {
+                        let x: f32 = object.number("x")?;
+                        let mut y: f32 = object.number("y")?;
+                        // A tile object's position is its bottom left corner.
+                        if object.attr("gid").is_some() {
+                            y -= 1.0;
+                        }
+                        let kind = object.attr("type").or_else(|| object.attr("class"));
+                        map.objects.push(Object {
+                            name: object.attr("name").unwrap_or("").to_string(),
+                            kind: kind.unwrap_or("").to_string(),
+                            x: (x / tile_width).floor() as i32,
+                            y: (y / tile_height).floor() as i32,
+                            properties: properties(object),
+                        });
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(map)
+    }
+
+    /// Returns the value of the map's property `key`, if present.
+    pub fn property(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether the tile with a global ID has the type `kind` or a custom property `kind` that is
+    /// true.
+    pub fn tile_is(&self, gid: u32, kind: &str) -> bool {
+        self.tiles
+            .get(&gid)
+            .map_or(false, |(tile_kind, properties)| {
+                tile_kind == kind || properties.iter().any(|(k, v)| k == kind && v == "true")
+            })
+    }
+
+    /// Turns the map into a level. Each cell gets the character of the first of `tiles` that
+    /// the topmost tile there is, then objects are placed with the character of the first of
+    /// `objects` matching their type. Anything else is a space.
+    pub fn to_level(&self, tiles: &[(&str, char)], objects: &[(&str, char)]) -> Level {
+        let mut grid = Grid::new(self.width, self.height, ' ');
+        for layer in &self.layers {
+            for (x, y, &gid) in layer.tiles.iter() {
+                if let Some((_, c)) = tiles.iter().find(|(kind, _)| self.tile_is(gid, kind)) {
+                    grid.set(x, y, *c);
+                }
+            }
+        }
+        for object in &self.objects {
+            if let Some((_, c)) = objects.iter().find(|(kind, _)| *kind == object.kind) {
+                grid.set(object.x, object.y, *c);
+            }
+        }
+        Level {
+            properties: self.properties.clone(),
+            tiles: grid,
+        }
+    }
+}
+
+/// Reads a tile layer's data.
+fn layer(element: &Element, width: usize, height: usize) -> Result<Layer, String> {
+    let data = element
+        .children
+        .iter()
+        .find(|e| e.name == "data")
+        .ok_or("a layer has no data")?;
+    if data.attr("compression").is_some() || data.children.iter().any(|e| e.name == "chunk") {
+        return Err("compressed and infinite maps aren't supported".to_string());
+    }
+    let gids: Vec<u32> = match data.attr("encoding") {
+        Some("csv") => data
+            .text
+            .split(',')
+            .map(|gid| {
+                gid.trim()
+                    .parse()
+                    .map_err(|_| format!("bad tile `{}`", gid))
+            })
+            .collect::<Result<_, _>>()?,
+        None => data
+            .children
+            .iter()
+            .filter(|e| e.name == "tile")
+            .map(|tile| tile.attr("gid").map_or(Ok(0), |gid| gid.parse()))
+            .collect::<Result<_, _>>()
+            .map_err(|_| "bad tile".to_string())?,
+        Some(encoding) => {
+            return Err(format!(
+                "layers saved as {} aren't supported, save them as CSV",
+                encoding
+            ))
+        }
+    };
+    let size = width
+        .checked_mul(height)
+        .ok_or("the map is too big to load")?;
+    if gids.len() != size {
+        return Err(format!(
+            "a layer has {} tiles rather than {}",
+            gids.len(),
+            size
+        ));
+    }
+
+    let rows = gids
+        .chunks(width)
+        .map(|row| row.iter().map(|gid| gid & !FLIP_FLAGS).collect())
+        .collect();
+    Ok(Layer {
+        name: element.attr("name").unwrap_or("").to_string(),
+        tiles: Grid::from_rows(rows).ok_or("a layer is the wrong size")?,
+    })
+}
+
+/// The custom properties under an element.
+fn properties(element: &Element) -> Vec<(String, String)> {
+    element
+        .children
+        .iter()
+        .filter(|e| e.name == "properties")
+        .flat_map(|e| e.children.iter().filter(|e| e.name == "property"))
+        .map(|property| {
+            let value = property.attr("value").unwrap_or(&property.text);
+            (
+                property.attr("name").unwrap_or("").to_string(),
+                value.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// An XML element, with only as much of XML as Tiled writes.
+#[derive(Debug, Clone, Default)]
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<T, String> {
+        let value = self
+            .attr(name)
+            .ok_or_else(|| format!("<{}> has no `{}`", self.name, name))?;
+        value
+            .parse()
+            .map_err(|_| format!("<{}> has a bad `{}`: {}", self.name, name, value))
+    }
+}
+
+/// Parses an XML document into its root element.
+fn parse_xml(contents: &str) -> Result<Element, String> {
+    let mut stack: Vec<Element> = Vec::new();
+    let mut rest = contents;
+    while let Some(start) = rest.find('<') {
+        if let Some(open) = stack.last_mut() {
+            open.text.push_str(&unescape(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        let (skip, end) = if rest.starts_with("<!--") {
+            (true, "-->")
+        } else if rest.starts_with("<?") {
+            (true, "?>")
+        } else if rest.starts_with("<!") {
+            (true, ">")
+        } else {
+            (false, ">")
+        };
+        let close = rest.find(end).ok_or("unclosed tag")?;
+        let tag = &rest[1..close];
+        rest = &rest[close + end.len()..];
+        if skip {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let element = stack.pop().ok_or("unexpected closing tag")?;
+            if element.name != name.trim() {
+                return Err(format!("<{}> closed by </{}>", element.name, name.trim()));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => return Ok(element),
+            }
+            continue;
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let mut parts = tag.splitn(2, char::is_whitespace);
+        let element = Element {
+            name: parts.next().unwrap_or("").to_string(),
+            attributes: attributes(parts.next().unwrap_or(""))?,
+            ..Element::default()
+        };
+        if self_closing {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => return Ok(element),
+            }
+        } else {
+            stack.push(element);
+        }
+    }
+    Err("the document ends before its root element does".to_string())
+}
+
+/// Parses a tag's `name="value"` attributes.
+fn attributes(mut rest: &str) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(attributes);
+        }
+        let equals = rest.find('=').ok_or("an attribute has no value")?;
+        let name = rest[..equals].trim().to_string();
+        rest = rest[equals + 1..].trim_start();
+        let quote = rest.chars().next().filter(|&c| c == '"' || c == '\'');
+        let quote = quote.ok_or("an attribute's value isn't quoted")?;
+        let end = rest[1..]
+            .find(quote)
+            .ok_or("an attribute's value isn't closed")?;
+        attributes.push((name, unescape(&rest[1..end + 1])));
+        rest = &rest[end + 2..];
+    }
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILESET: &str = r#"<tileset name="terrain" tilewidth="16" tileheight="16">
+  <tile id="0"><properties><property name="solid" type="bool" value="true"/></properties></tile>
+  <tile id="1" type="hazard"/>
+  <tile id="2" class="goal"/>
+</tileset>"#;
+
+    fn map(tileset: &str, layer: &str, objects: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Made in Tiled -->
+<map version="1.4" orientation="orthogonal" width="4" height="3" tilewidth="16" tileheight="16">
+ <properties>
+  <property name="name" value="Fish &amp; Chips"/>
+ </properties>
+ {}
+ <layer id="1" name="ground" width="4" height="3">
+  {}
+ </layer>
+ <objectgroup id="2" name="things">
+  {}
+ </objectgroup>
+</map>"#,
+            tileset, layer, objects
+        )
+    }
+
+    fn csv(tiles: &str) -> String {
+        format!(r#"<data encoding="csv">{}</data>"#, tiles)
+    }
+
+    const TILES: &str = "1,1,1,1,\n0,2,3,0,\n1,1,1,1";
+
+    fn no_tilesets(_: &str) -> Result<String, String> {
+        Err("there are no tilesets".to_string())
+    }
+
+    fn parse(contents: &str) -> Result<TileMap, String> {
+        TileMap::parse(contents, no_tilesets)
+    }
+
+    fn embedded() -> String {
+        TILESET.replacen("<tileset ", r#"<tileset firstgid="1" "#, 1)
+    }
+
+    fn rows(level: &Level) -> Vec<String> {
+        (0..level.tiles.height() as i32)
+            .map(|y| {
+                (0..level.tiles.width() as i32)
+                    .map(|x| *level.tiles.get(x, y).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    const KINDS: &[(&str, char)] = &[("solid", '#'), ("hazard", '^'), ("goal", 'E')];
+
+    #[test]
+    fn csv_maps_become_levels() {
+        let objects = r#"<object id="1" name="start" type="spawn" x="48" y="20"/>"#;
+        let map = parse(&map(&embedded(), &csv(TILES), objects)).unwrap();
+        assert_eq!((map.width, map.height), (4, 3));
+        assert_eq!(map.property("name"), Some("Fish & Chips"));
+        assert_eq!(map.objects[0].name, "start");
+        assert_eq!((map.objects[0].x, map.objects[0].y), (3, 1));
+
+        let level = map.to_level(KINDS, &[("spawn", '@')]);
+        assert_eq!(rows(&level), vec!["####", " ^E@", "####"]);
+        assert_eq!(level.name(), "Fish & Chips");
+    }
+
+    #[test]
+    fn xml_layers_read_the_same_as_csv() {
+        let tiles: String = TILES
+            .split(',')
+            .map(|gid| match gid.trim() {
+                "0" => "<tile/>".to_string(),
+                gid => format!(r#"<tile gid="{}"/>"#, gid),
+            })
+            .collect();
+        let xml = parse(&map(&embedded(), &format!("<data>{}</data>", tiles), "")).unwrap();
+        let csv = parse(&map(&embedded(), &csv(TILES), "")).unwrap();
+        assert_eq!(xml.layers[0].tiles, csv.layers[0].tiles);
+    }
+
+    #[test]
+    fn flipped_tiles_are_still_the_same_tile() {
+        let flipped = TILES.replacen("2", &(2 | 0x8000_0000u32).to_string(), 1);
+        let map = parse(&map(&embedded(), &csv(&flipped), "")).unwrap();
+        assert!(map.tile_is(*map.layers[0].tiles.get(1, 1).unwrap(), "hazard"));
+    }
+
+    #[test]
+    fn tile_objects_are_placed_by_their_bottom_corner() {
+        let objects = r#"<object id="1" gid="1" type="spawn" x="16" y="32"/>"#;
+        let map = parse(&map(&embedded(), &csv(TILES), objects)).unwrap();
+        assert_eq!((map.objects[0].x, map.objects[0].y), (1, 1));
+    }
+
+    #[test]
+    fn external_tilesets_are_read_from_beside_the_map() {
+        let tileset = r#"<tileset firstgid="1" source="terrain.tsx"/>"#;
+        let map = TileMap::parse(&map(tileset, &csv(TILES), ""), |source| {
+            assert_eq!(source, "terrain.tsx");
+            Ok(TILESET.to_string())
+        })
+        .unwrap();
+        assert!(map.tile_is(1, "solid"));
+        assert!(map.tile_is(3, "goal"));
+        assert!(!map.tile_is(0, "solid"));
+    }
+
+    #[test]
+    fn missing_tilesets_fail_to_load() {
+        let tileset = r#"<tileset firstgid="1" source="terrain.tsx"/>"#;
+        assert!(parse(&map(tileset, &csv(TILES), "")).is_err());
+    }
+
+    #[test]
+    fn truncated_maps_fail_to_load() {
+        let contents = map(&embedded(), &csv(TILES), "");
+        for len in (0..contents.len()).step_by(7) {
+            if contents.is_char_boundary(len) {
+                assert!(parse(&contents[..len]).is_err(), "{} was read", len);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_xml_fails_to_load() {
+        assert!(parse("<map width='1' height='1'></layer>").is_err());
+        assert!(parse("<map width=1 height=1/>").is_err());
+        assert!(parse(r#"<map width="1 height="1"/>"#).is_err());
+        assert!(parse("<map").is_err());
+        assert!(parse("</map>").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn other_documents_arent_maps() {
+        assert_eq!(parse(TILESET).unwrap_err(), "not a Tiled map".to_string());
+    }
+
+    #[test]
+    fn bad_sizes_fail_to_load() {
+        let contents = map(&embedded(), &csv(TILES), "");
+        assert!(parse(&contents.replacen(r#"width="4""#, r#"width="four""#, 1)).is_err());
+        assert!(parse(&contents.replacen(r#"width="4""#, r#"width="0""#, 1)).is_err());
+        let empty = map(&embedded(), "<data></data>", "");
+        assert!(parse(&empty.replacen(r#"width="4""#, r#"width="0""#, 1)).is_err());
+        assert!(parse(&contents.replacen(r#"height="3""#, "", 1)).is_err());
+    }
+
+    #[test]
+    fn layers_with_the_wrong_number_of_tiles_fail_to_load() {
+        assert!(parse(&map(&embedded(), &csv("1,1,1"), "")).is_err());
+        assert!(parse(&map(&embedded(), &csv(&format!("{},1", TILES)), "")).is_err());
+    }
+
+    #[test]
+    fn bad_tiles_fail_to_load() {
+        let bad = TILES.replacen("2", "x", 1);
+        assert_eq!(
+            parse(&map(&embedded(), &csv(&bad), "")).unwrap_err(),
+            "bad tile `x`"
+        );
+        let negative = TILES.replacen("2", "-2", 1);
+        assert!(parse(&map(&embedded(), &csv(&negative), "")).is_err());
+    }
+
+    #[test]
+    fn tile_ids_past_the_last_fail_to_load() {
+        let tileset = embedded().replacen(r#"firstgid="1""#, r#"firstgid="4294967295""#, 1);
+        assert!(parse(&map(&tileset, &csv(TILES), "")).is_err());
+    }
+
+    #[test]
+    fn unsupported_layers_fail_to_load() {
+        let base64 = r#"<data encoding="base64">AQAAAA==</data>"#;
+        assert!(parse(&map(&embedded(), base64, ""))
+            .unwrap_err()
+            .contains("save them as CSV"));
+        let compressed = r#"<data encoding="base64" compression="zlib">eJw=</data>"#;
+        assert!(parse(&map(&embedded(), compressed, "")).is_err());
+        assert!(parse(&map(&embedded(), "", "")).is_err());
+    }
+}
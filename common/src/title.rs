@@ -0,0 +1,150 @@
+//! The games' window titles and icons.
+//!
+//! Each game's icon is its initial on its card's colour, drawn the first time the game runs and
+//! kept in the data directory's `icons` folder. The title is the game's, followed by its score
+//! and mode while there are any, like `Snake — 42 — Hard`.
+//!
+//! Games show their score with `show_score` whenever it may have changed, and `clear_score`
+//! when there's no run going. The mode is the one the game was started in with `--mode`, or
+//! the one it recorded last with `last_played::set_mode`. `App` sets the icon and keeps the
+//! title up to date.
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::fs;
+
+use ggez::graphics::{self, DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{filesystem, Context, GameError, GameResult};
+
+use crate::last_played;
+use crate::registry::{self, GameInfo};
+use crate::snapshot;
+use crate::speech;
+use crate::text;
+
+const ICONS_DIR: &str = "icons";
+const ICON_SIZE: u16 = 64;
+const SEPARATOR: &str = " — ";
+
+/// What the title shows besides the game's name.
+#[derive(Debug, Clone, Default)]
+struct Status {
+    score: Option<String>,
+    mode: Option<String>,
+    /// The title as it was last set.
+    shown: String,
+}
+
+thread_local! {
+    static STATUS: RefCell<Status> = RefCell::new(Status {
+        mode: last_played::requested_mode(),
+        ..Status::default()
+    });
+}
+
+/// Shows the current run's score in the title, reading it out when it changes if speech is on.
+pub fn show_score(score: impl Display) {
+    let score = score.to_string();
+    STATUS.with(|status| {
+        let mut status = status.borrow_mut();
+        if status.score.as_ref() != Some(&score) {
+            speech::say_politely(&score);
+            status.score = Some(score);
+        }
+    });
+}
+
+/// Takes the score out of the title, like on a menu.
+pub fn clear_score() {
+    STATUS.with(|status| status.borrow_mut().score = None);
+}
+
+/// Shows the mode being played in the title.
+pub fn show_mode(mode: &str) {
+    STATUS.with(|status| status.borrow_mut().mode = Some(mode.to_string()));
+}
+
+/// The score and mode the title is showing, for crash reports. It's `None` for both if the
+/// panic happened while they were being changed.
+pub(crate) fn status() -> (Option<String>, Option<String>) {
+    STATUS
+        .try_with(|status| {
+            status
+                .try_borrow()
+                .map(|status| (status.score.clone(), status.mode.clone()))
+                .unwrap_or((None, None))
+        })
+        .unwrap_or((None, None))
+}
+
+/// Sets the window's title if the score or mode have changed. `App` calls it every update.
+pub fn update(ctx: &mut Context) {
+    let game = match registry::running() {
+        Some(game) => game,
+        None => return,
+    };
+    STATUS.with(|status| {
+        let mut status = status.borrow_mut();
+        let mut title = game.title.to_string();
+        for part in status.score.iter().chain(status.mode.iter()) {
+            title.push_str(SEPARATOR);
+            title.push_str(part);
+        }
+        if title != status.shown {
+            graphics::set_window_title(ctx, &title);
+            status.shown = title;
+        }
+    });
+}
+
+/// Sets the window's icon to the running game's, drawing it first if it hasn't been yet.
+pub fn set_icon(ctx: &mut Context) -> GameResult<()> {
+    let game = match registry::running() {
+        Some(game) => game,
+        None => return Ok(()),
+    };
+    let data_dir = crate::data_dir()?;
+    let file = format!("{}.png", game.name);
+    let path = data_dir.join(ICONS_DIR).join(&file);
+    if !path.exists() {
+        let icon = snapshot::render(ctx, ICON_SIZE, ICON_SIZE, |ctx| draw_icon(ctx, game))?;
+        fs::create_dir_all(data_dir.join(ICONS_DIR))?;
+        icon.save(&path)
+            .map_err(|e| GameError::FilesystemError(e.to_string()))?;
+    }
+    // Window icons are loaded through ggez's filesystem, which needs to be able to see them.
+    filesystem::mount(ctx, &data_dir, true);
+    graphics::set_window_icon(ctx, Some(format!("/{}/{}", ICONS_DIR, file)))
+}
+
+/// Draws a game's icon: a rounded square in its card's colour with its initial in the middle.
+fn draw_icon(ctx: &mut Context, game: &GameInfo) -> GameResult<()> {
+    let size = f32::from(ICON_SIZE);
+    graphics::clear(ctx, [0.0, 0.0, 0.0, 0.0].into());
+    let square = graphics::Mesh::new_rounded_rectangle(
+        ctx,
+        DrawMode::fill(),
+        Rect::new(0.0, 0.0, size, size),
+        size / 5.0,
+        game.color.into(),
+    )?;
+    graphics::draw(ctx, &square, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+    let initial: String = game.title.chars().take(1).collect();
+    let font = text::font(ctx);
+    let text = Text::new(
+        TextFragment::new(initial)
+            .font(font)
+            .scale(Scale::uniform(size * 0.7))
+            .color([1.0, 1.0, 1.0, 1.0].into()),
+    );
+    let (width, height) = text.dimensions(ctx);
+    graphics::draw(
+        ctx,
+        &text,
+        (ggez::mint::Point2 {
+            x: (size - width as f32) / 2.0,
+            y: (size - height as f32) / 2.0,
+        },),
+    )
+}
@@ -0,0 +1,89 @@
+//! Short messages shown in the corner of the screen for a few seconds over whatever is being
+//! drawn, like a controller being connected.
+//!
+//! Anything can `show` a toast. They are drawn by `ui::present`, which games finish their
+//! frames with in place of `graphics::present`, and read out if speech is on.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use ggez::graphics::{self, DrawMode, Rect, Text};
+use ggez::{Context, GameResult};
+
+use crate::speech;
+use crate::text;
+
+/// How long a toast stays up, including fading out.
+const DURATION: Duration = Duration::from_secs(3);
+const FADE: Duration = Duration::from_millis(500);
+
+const PANEL_COLOR: [f32; 4] = [0.1, 0.1, 0.13, 0.9];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const TEXT_SIZE: f32 = 18.0;
+const MARGIN: f32 = 12.0;
+const PADDING: f32 = 10.0;
+
+thread_local! {
+    /// The toasts being shown and when each was shown, oldest first.
+    static TOASTS: RefCell<Vec<(String, Instant)>> = RefCell::new(Vec::new());
+}
+
+/// Shows a message for a few seconds.
+pub fn show(message: &str) {
+    TOASTS.with(|toasts| {
+        toasts
+            .borrow_mut()
+            .push((message.to_string(), Instant::now()))
+    });
+    speech::say_politely(message);
+}
+
+/// How opaque a toast shown `age` ago is, fading out at the end.
+fn alpha(age: Duration) -> f32 {
+    let left = DURATION.checked_sub(age).unwrap_or_default();
+    (left.as_secs_f32() / FADE.as_secs_f32()).min(1.0)
+}
+
+/// Draws the toasts down the top right corner, newest at the top, dropping any that have had
+/// their time.
+pub fn draw(ctx: &mut Context) -> GameResult<()> {
+    let toasts: Vec<(String, Duration)> = TOASTS.with(|toasts| {
+        let mut toasts = toasts.borrow_mut();
+        toasts.retain(|(_, shown)| shown.elapsed() < DURATION);
+        toasts
+            .iter()
+            .rev()
+            .map(|(message, shown)| (message.clone(), shown.elapsed()))
+            .collect()
+    });
+
+    let screen = graphics::screen_coordinates(ctx);
+    let mut y = screen.y + MARGIN;
+    for (message, age) in toasts {
+        let alpha = alpha(age);
+        let mut color = TEXT_COLOR;
+        color[3] *= alpha;
+        let text = Text::new(text::fragment(ctx, message.as_str(), color, TEXT_SIZE));
+        let (width, height) = text.dimensions(ctx);
+        let panel = Rect::new(
+            screen.x + screen.w - MARGIN - width as f32 - PADDING * 2.0,
+            y,
+            width as f32 + PADDING * 2.0,
+            height as f32 + PADDING * 2.0,
+        );
+        let mut panel_color = PANEL_COLOR;
+        panel_color[3] *= alpha;
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel, panel_color.into())?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        graphics::draw(
+            ctx,
+            &text,
+            (ggez::mint::Point2 {
+                x: panel.x + PADDING,
+                y: panel.y + PADDING,
+            },),
+        )?;
+        y += panel.h + MARGIN / 2.0;
+    }
+    Ok(())
+}
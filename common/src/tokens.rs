@@ -0,0 +1,114 @@
+//! Tokens, earned for milestones in any game and spent in the launcher's shop on cosmetics that
+//! every game uses.
+//!
+//! Tokens are kept per profile as a ledger, one entry per line of tab separated fields:
+//! `timestamp amount reason`. Earnings are positive and purchases negative, with the purchase's
+//! reason naming the item bought, so the balance and what the profile owns both come from the
+//! ledger.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::migrate::{self, TOKENS};
+use crate::profile;
+
+const TOKENS_FILE: &str = "tokens.tsv";
+
+/// Tokens for unlocking an achievement.
+pub const ACHIEVEMENT_TOKENS: u32 = 50;
+
+/// Tokens for beating a game's personal best.
+pub const PERSONAL_BEST_TOKENS: u32 = 20;
+
+/// One change to the balance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    /// Seconds since the unix epoch when it was made.
+    pub timestamp: u64,
+    /// Positive for tokens earned, negative for tokens spent.
+    pub amount: i64,
+    /// What the tokens were earned for, or the item they were spent on.
+    pub reason: String,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split('\t');
+        Some(Entry {
+            timestamp: fields.next()?.parse().ok()?,
+            amount: fields.next()?.parse().ok()?,
+            reason: fields.next()?.to_string(),
+        })
+    }
+}
+
+/// The current profile's tokens.
+#[derive(Debug, Clone)]
+pub struct Wallet {
+    path: PathBuf,
+    /// Oldest first.
+    entries: Vec<Entry>,
+}
+
+impl Wallet {
+    /// Loads the current profile's ledger.
+    pub fn load() -> io::Result<Self> {
+        let path = profile::profile_dir()?.join(TOKENS_FILE);
+        let entries = match migrate::read(&path, &TOKENS) {
+            Ok(contents) => contents.lines().filter_map(Entry::parse).collect(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Wallet { path, entries })
+    }
+
+    /// How many tokens there are to spend.
+    pub fn balance(&self) -> u32 {
+        let total: i64 = self.entries.iter().map(|entry| entry.amount).sum();
+        total.max(0) as u32
+    }
+
+    /// Whether the item has been bought.
+    pub fn owns(&self, item: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.amount <= 0 && entry.reason == item)
+    }
+
+    fn append(&mut self, amount: i64, reason: &str) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let line = format!("{}\t{}\t{}", timestamp, amount, reason);
+        migrate::append(&self.path, &TOKENS, &line)?;
+        self.entries.push(Entry {
+            timestamp,
+            amount,
+            reason: reason.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Adds tokens earned for the given reason.
+    pub fn earn(&mut self, amount: u32, reason: &str) -> io::Result<()> {
+        self.append(i64::from(amount), reason)
+    }
+
+    /// Buys an item, returning false without spending anything if it is already owned or there
+    /// aren't enough tokens.
+    pub fn buy(&mut self, item: &str, price: u32) -> io::Result<bool> {
+        if self.owns(item) || self.balance() < price {
+            return Ok(false);
+        }
+        self.append(-i64::from(price), item)?;
+        Ok(true)
+    }
+
+    /// The most recent entries, newest first.
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().rev().take(count)
+    }
+}
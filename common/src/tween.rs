@@ -0,0 +1,134 @@
+//! Easing curves and tweens for animating values over time.
+//!
+//! With reduced motion on, tweens are finished as soon as they start, and `approach` goes
+//! straight to its target.
+
+use crate::motion;
+use crate::physics::Vec2;
+
+/// A curve mapping the linear progress of a tween onto the eased progress.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ease {
+    Linear,
+    /// Starts fast and slows down towards the end.
+    QuadOut,
+    /// Starts and ends slowly.
+    QuadInOut,
+    /// Overshoots the end a little before settling back.
+    BackOut,
+    /// Bounces off the end a few times like a dropped ball.
+    BounceOut,
+}
+
+impl Ease {
+    /// Eases `t`, which is clamped to between `0.0` and `1.0`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Ease::BackOut => {
+                const OVERSHOOT: f32 = 1.70158;
+                let t = t - 1.0;
+                1.0 + (OVERSHOOT + 1.0) * t * t * t + OVERSHOOT * t * t
+            }
+            Ease::BounceOut => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+/// A value that can be linearly interpolated.
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: f32, t: f32) -> f32 {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(self, to: Vec2, t: f32) -> Vec2 {
+        self + (to - self) * t
+    }
+}
+
+/// Animates a value from one point to another over a set duration.
+#[derive(Debug, Copy, Clone)]
+pub struct Tween<T> {
+    from: T,
+    to: T,
+    duration: f32,
+    elapsed: f32,
+    ease: Ease,
+}
+
+impl<T: Lerp> Tween<T> {
+    /// Creates a new tween from `from` to `to` taking `duration` seconds, or no time at all
+    /// with reduced motion on.
+    pub fn new(from: T, to: T, duration: f32, ease: Ease) -> Self {
+        Tween {
+            from,
+            to,
+            duration: if motion::reduced() { 0.0 } else { duration },
+            elapsed: 0.0,
+            ease,
+        }
+    }
+
+    /// Creates a finished tween resting at `value`.
+    pub fn at(value: T) -> Self {
+        Tween::new(value, value, 0.0, Ease::Linear)
+    }
+
+    /// Advances the tween by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// The current value of the tween.
+    pub fn value(&self) -> T {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        self.from
+            .lerp(self.to, self.ease.apply(self.elapsed / self.duration))
+    }
+
+    pub fn done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// Moves `value` part of the way to `target`, closing the gap at `rate` a second, for things
+/// that follow a moving target smoothly, like a camera. With reduced motion on it goes straight
+/// to the target.
+pub fn approach<T: Lerp>(value: T, target: T, rate: f32, dt: f32) -> T {
+    if motion::reduced() {
+        return target;
+    }
+    value.lerp(target, (rate * dt).min(1.0))
+}
@@ -0,0 +1,227 @@
+//! Widgets shared by the launcher and the games' menus.
+
+use ggez::graphics::{self, DrawMode, DrawParam, Image, Rect, Text};
+use ggez::{Context, GameResult};
+
+use crate::input::Nav;
+use crate::postprocess;
+use crate::scanning;
+use crate::text;
+use crate::toast;
+
+/// The outline drawn around whatever has focus, in every menu.
+pub const FOCUS_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+const FOCUS_WIDTH: f32 = 3.0;
+
+/// Finishes a frame, post-processing it and drawing any toasts and the switch scanning actions
+/// over it before showing it. Games call it in place of `graphics::present`.
+pub fn present(ctx: &mut Context) -> GameResult<()> {
+    postprocess::finish(ctx)?;
+    scanning::draw(ctx)?;
+    toast::draw(ctx)?;
+    graphics::present(ctx)
+}
+
+/// Draws a line of text in the shared font with its top left corner at `(x, y)`. `scale` is
+/// in points, like the text styles.
+pub fn draw_text(
+    ctx: &mut Context,
+    text: &str,
+    color: [f32; 4],
+    scale: f32,
+    x: f32,
+    y: f32,
+) -> GameResult<()> {
+    let text = Text::new(text::fragment(ctx, text, color, scale));
+    graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+}
+
+/// Draws text wrapped to `width`, returning the height it took up.
+pub fn draw_wrapped(
+    ctx: &mut Context,
+    text: &str,
+    color: [f32; 4],
+    scale: f32,
+    x: f32,
+    y: f32,
+    width: f32,
+) -> GameResult<f32> {
+    let align = text::Alignment::Start.align(text);
+    let mut text = Text::new(text::fragment(ctx, text, color, scale));
+    text.set_bounds(
+        ggez::mint::Point2 {
+            x: width,
+            y: std::f32::INFINITY,
+        },
+        align,
+    );
+    let height = text.height(ctx) as f32;
+    graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))?;
+    Ok(height)
+}
+
+/// Draws the focus outline just outside `rect`.
+pub fn draw_focus(ctx: &mut Context, rect: Rect) -> GameResult<()> {
+    let outline = Rect::new(
+        rect.x - FOCUS_WIDTH,
+        rect.y - FOCUS_WIDTH,
+        rect.w + FOCUS_WIDTH * 2.0,
+        rect.h + FOCUS_WIDTH * 2.0,
+    );
+    let mesh = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::stroke(FOCUS_WIDTH),
+        outline,
+        FOCUS_COLOR.into(),
+    )?;
+    graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+}
+
+/// Lays out equally sized cells in rows, left to right and top to bottom.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GridLayout {
+    /// The top left of the first cell.
+    pub origin: (f32, f32),
+    pub columns: usize,
+    pub cell: (f32, f32),
+    /// The space between neighbouring cells.
+    pub spacing: f32,
+}
+
+impl GridLayout {
+    /// The rectangle of the cell at `index`.
+    pub fn rect(&self, index: usize) -> Rect {
+        let column = index % self.columns;
+        let row = index / self.columns;
+        Rect::new(
+            self.origin.0 + column as f32 * (self.cell.0 + self.spacing),
+            self.origin.1 + row as f32 * (self.cell.1 + self.spacing),
+            self.cell.0,
+            self.cell.1,
+        )
+    }
+
+    /// The cell under `(x, y)`, if any. The spacing between cells belongs to none of them.
+    pub fn index_at(&self, x: f32, y: f32, count: usize) -> Option<usize> {
+        (0..count).find(|&index| self.rect(index).contains(ggez::mint::Point2 { x, y }))
+    }
+
+    /// The cell reached by stepping from `index` in the direction of `nav`, staying on the
+    /// same cell at the edges. Stepping down onto a short last row lands on its last cell.
+    pub fn step(&self, index: usize, nav: Nav, count: usize) -> usize {
+        let column = index % self.columns;
+        match nav {
+            Nav::Left if column > 0 => index - 1,
+            Nav::Right if column + 1 < self.columns && index + 1 < count => index + 1,
+            Nav::Up if index >= self.columns => index - self.columns,
+            Nav::Down if index / self.columns + 1 < self.rows(count) => {
+                (index + self.columns).min(count - 1)
+            }
+            _ => index,
+        }
+    }
+
+    /// How many rows it takes to fit `count` cells.
+    pub fn rows(&self, count: usize) -> usize {
+        (count + self.columns - 1) / self.columns
+    }
+}
+
+/// A selectable card with a picture, a title, a description and tags.
+#[derive(Debug, Clone)]
+pub struct Card<'a> {
+    pub rect: Rect,
+    pub title: &'a str,
+    pub description: &'a str,
+    pub tags: &'a [&'a str],
+    /// The picture across the top of the card. Without one the space is filled with the accent
+    /// colour and the title's first letter.
+    pub thumbnail: Option<&'a Image>,
+    pub accent: [f32; 4],
+    pub focused: bool,
+}
+
+impl<'a> Card<'a> {
+    /// The fraction of the card's height taken up by the thumbnail.
+    const THUMBNAIL_HEIGHT: f32 = 0.5;
+    const PADDING: f32 = 10.0;
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let rect = self.rect;
+        let thumbnail = Rect::new(rect.x, rect.y, rect.w, rect.h * Card::THUMBNAIL_HEIGHT);
+
+        let mut builder = graphics::MeshBuilder::new();
+        let background = if self.focused {
+            [0.22, 0.22, 0.28, 1.0]
+        } else {
+            [0.14, 0.14, 0.18, 1.0]
+        };
+        builder.rectangle(DrawMode::fill(), rect, background.into());
+        if self.thumbnail.is_none() {
+            builder.rectangle(DrawMode::fill(), thumbnail, self.accent.into());
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        match self.thumbnail {
+            Some(image) => {
+                let scale = ggez::mint::Vector2 {
+                    x: thumbnail.w / image.width() as f32,
+                    y: thumbnail.h / image.height() as f32,
+                };
+                graphics::draw(
+                    ctx,
+                    image,
+                    DrawParam::new()
+                        .dest(ggez::mint::Point2 {
+                            x: thumbnail.x,
+                            y: thumbnail.y,
+                        })
+                        .scale(scale),
+                )?;
+            }
+            None => {
+                let initial: String = self.title.chars().take(1).collect();
+                let size = thumbnail.h * 0.6;
+                draw_text(
+                    ctx,
+                    &initial,
+                    [0.0, 0.0, 0.0, 0.45],
+                    size,
+                    thumbnail.x + thumbnail.w / 2.0 - size * 0.3,
+                    thumbnail.y + thumbnail.h * 0.2,
+                )?;
+            }
+        }
+
+        let x = rect.x + Card::PADDING;
+        let width = rect.w - Card::PADDING * 2.0;
+        let mut y = thumbnail.y + thumbnail.h + Card::PADDING / 2.0;
+        draw_text(ctx, self.title, [1.0, 1.0, 1.0, 1.0], 22.0, x, y)?;
+        y += 26.0;
+        draw_wrapped(
+            ctx,
+            self.description,
+            [0.75, 0.75, 0.8, 1.0],
+            15.0,
+            x,
+            y,
+            width,
+        )?;
+
+        let tags = self.tags.join(" · ");
+        draw_text(
+            ctx,
+            &tags,
+            self.accent,
+            14.0,
+            x,
+            rect.y + rect.h - Card::PADDING - 14.0,
+        )?;
+
+        if self.focused {
+            draw_focus(ctx, rect)?;
+        }
+        Ok(())
+    }
+}
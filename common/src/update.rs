@@ -0,0 +1,96 @@
+//! Checking whether there's a newer version of the games than the one running, so the launcher
+//! can say so.
+//!
+//! The check is off unless the profile's settings have an `update_url`, pointing at the
+//! project's release feed. The feed is a text file, fetched with `GET` over plain HTTP like
+//! sync, and so from this computer unless `allow_plain_http` is set. Its first line is the
+//! newest version, like `1.3.0`, and its other lines are what changed in it, most important
+//! first. For example:
+//!
+//! ```text
+//! 1.3.0
+//! Adds a privacy screen to the launcher.
+//! Fixes snake's food appearing inside walls.
+//! Download it from http://example.com/quick-games/
+//! ```
+//!
+//! The launcher checks when it starts and shows a banner if there's a newer version. Nothing
+//! is ever downloaded or installed: the changelog says where to get it.
+
+use crate::settings::Settings;
+use crate::sync::Server;
+
+/// The setting holding the release feed's URL.
+pub const URL_SETTING: &str = "update_url";
+
+/// The version running.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The newest version in the release feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// What changed, most important first.
+    pub changes: Vec<String>,
+}
+
+impl Release {
+    /// Reads the release feed, or `None` if its first line isn't a version.
+    pub fn parse(feed: &str) -> Option<Self> {
+        let mut lines = feed.lines().map(str::trim).filter(|line| !line.is_empty());
+        let version = lines.next()?;
+        parse_version(version)?;
+        Some(Release {
+            version: version.to_string(),
+            changes: lines.map(str::to_string).collect(),
+        })
+    }
+
+    /// The most important change, to sum the release up.
+    pub fn summary(&self) -> Option<&str> {
+        self.changes.first().map(String::as_str)
+    }
+}
+
+/// A version's numbers, like `[1, 3, 0]` for `v1.3.0`. Anything after a `-` or `+`, like
+/// `-beta`, is left off.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version.split(|c| c == '-' || c == '+').next()?;
+    version.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Whether `version` is newer than `than`. Versions that can't be read are never newer.
+pub fn is_newer(version: &str, than: &str) -> bool {
+    let (mut version, mut than) = match (parse_version(version), parse_version(than)) {
+        (Some(version), Some(than)) => (version, than),
+        _ => return false,
+    };
+    // 1.3 is the same as 1.3.0.
+    let len = version.len().max(than.len());
+    version.resize(len, 0);
+    than.resize(len, 0);
+    version > than
+}
+
+/// The release feed's URL, if there is one.
+pub fn url() -> Option<String> {
+    Settings::load()
+        .get(URL_SETTING)
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+}
+
+/// Checks the release feed, returning the newest release if it's newer than the one running.
+/// It's `None` if it isn't, or there's no feed to check.
+pub fn check() -> Result<Option<Release>, String> {
+    let url = match url() {
+        Some(url) => url,
+        None => return Ok(None),
+    };
+    let feed = Server::parse(&url)?.fetch()?;
+    let release = Release::parse(&String::from_utf8_lossy(&feed))
+        .ok_or_else(|| "the release feed doesn't start with a version".to_string())?;
+    Ok(Some(release).filter(|release| is_newer(&release.version, VERSION)))
+}
@@ -0,0 +1,238 @@
+//! How the games' windows are shown: in a window, borderless over a whole monitor or in
+//! exclusive fullscreen, on which monitor when there are several, how often they draw, whether
+//! games pause in the background, and whether motion is reduced.
+//!
+//! Each profile sets them from the launcher's display screen. They are kept as `display_mode`,
+//! which is `windowed`, `borderless` or `fullscreen`, `monitor`, counting from 0 in the order
+//! the system lists them, `vsync`, which is `true` or `false`, `frame_cap`, the most frames
+//! drawn a second, or 0 for no limit, and `pause_unfocused` and `reduced_motion`, `true` or
+//! `false`. Like any setting they can be overridden for a run, like `--display_mode borderless`.
+//! Reduced motion is applied by `motion`.
+//!
+//! Whatever the frame cap, a window in the background only draws `BACKGROUND_FRAME_CAP`
+//! frames a second, so it hardly uses any power until it's brought back.
+//!
+//! ggez opens its window before a game gets to run, so every window opens as a window and
+//! `App` switches it to the profile's mode on the first frame. The launcher switches its own
+//! window as soon as the settings are changed. VSync can only be set as the window opens, so
+//! games open their windows with `setup`, and a change to it is picked up the next time one
+//! starts.
+
+use std::io;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ggez::conf::{FullscreenType, WindowSetup};
+use ggez::graphics;
+use ggez::{Context, GameResult};
+
+use crate::settings::Settings;
+
+const MODE_KEY: &str = "display_mode";
+const MONITOR_KEY: &str = "monitor";
+const VSYNC_KEY: &str = "vsync";
+const FRAME_CAP_KEY: &str = "frame_cap";
+const PAUSE_UNFOCUSED_KEY: &str = "pause_unfocused";
+const REDUCED_MOTION_KEY: &str = "reduced_motion";
+
+/// The frame caps the display screen goes through, with 0 for no limit.
+pub const FRAME_CAPS: [u32; 5] = [0, 30, 60, 120, 144];
+
+/// The frames drawn a second while a window doesn't have focus.
+pub const BACKGROUND_FRAME_CAP: u32 = 10;
+
+/// How a window is shown.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DisplayMode {
+    Windowed,
+    /// A window without borders covering the whole monitor, which is quick to switch away
+    /// from.
+    Borderless,
+    /// Exclusive fullscreen, taking over the monitor.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    /// Every mode, in the order the display screen goes through them.
+    pub const ALL: [DisplayMode; 3] = [
+        DisplayMode::Windowed,
+        DisplayMode::Borderless,
+        DisplayMode::Fullscreen,
+    ];
+
+    /// How the mode is saved in the settings.
+    fn name(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "windowed",
+            DisplayMode::Borderless => "borderless",
+            DisplayMode::Fullscreen => "fullscreen",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<DisplayMode> {
+        DisplayMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| mode.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            DisplayMode::Windowed => "Windowed",
+            DisplayMode::Borderless => "Borderless window",
+            DisplayMode::Fullscreen => "Fullscreen",
+        }
+    }
+
+    fn fullscreen_type(self) -> FullscreenType {
+        match self {
+            DisplayMode::Windowed => FullscreenType::Windowed,
+            DisplayMode::Borderless => FullscreenType::Desktop,
+            DisplayMode::Fullscreen => FullscreenType::True,
+        }
+    }
+}
+
+/// The profile's display settings.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    /// The monitor to show the window on. If it has been unplugged, the window stays on the
+    /// monitor it opened on.
+    pub monitor: usize,
+    /// Whether to wait for the monitor to refresh before showing each frame.
+    pub vsync: bool,
+    /// The most frames to draw a second, or 0 for as many as the game can.
+    pub frame_cap: u32,
+    /// Whether games pause when their window loses focus.
+    pub pause_unfocused: bool,
+    /// Whether to leave out movement that's only there for show.
+    pub reduced_motion: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings {
+            mode: DisplayMode::Windowed,
+            monitor: 0,
+            vsync: true,
+            frame_cap: 0,
+            pause_unfocused: true,
+            reduced_motion: false,
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// Loads the current profile's display settings.
+    pub fn load() -> Self {
+        DisplaySettings::from_settings(&Settings::load())
+    }
+
+    pub fn from_settings(settings: &Settings) -> Self {
+        let defaults = DisplaySettings::default();
+        DisplaySettings {
+            mode: settings
+                .get(MODE_KEY)
+                .and_then(DisplayMode::from_name)
+                .unwrap_or(defaults.mode),
+            monitor: settings.get_or(MONITOR_KEY, defaults.monitor),
+            vsync: settings.get_or(VSYNC_KEY, defaults.vsync),
+            frame_cap: settings.get_or(FRAME_CAP_KEY, defaults.frame_cap),
+            pause_unfocused: settings.get_or(PAUSE_UNFOCUSED_KEY, defaults.pause_unfocused),
+            reduced_motion: settings.get_or(REDUCED_MOTION_KEY, defaults.reduced_motion),
+        }
+    }
+
+    /// Saves the settings into the current profile's settings.
+    pub fn save(&self) -> io::Result<()> {
+        let mut settings = Settings::load();
+        settings.set(MODE_KEY, self.mode.name());
+        settings.set(MONITOR_KEY, self.monitor);
+        settings.set(VSYNC_KEY, self.vsync);
+        settings.set(FRAME_CAP_KEY, self.frame_cap);
+        settings.set(PAUSE_UNFOCUSED_KEY, self.pause_unfocused);
+        settings.set(REDUCED_MOTION_KEY, self.reduced_motion);
+        settings.save()
+    }
+}
+
+/// How a game's window is set up when it opens, with its title and the profile's vsync.
+pub fn setup(title: &str) -> WindowSetup {
+    WindowSetup::default()
+        .title(title)
+        .vsync(DisplaySettings::load().vsync)
+}
+
+/// The names of the monitors, in the order `DisplaySettings::monitor` counts them.
+pub fn monitors(ctx: &Context) -> Vec<String> {
+    graphics::window(ctx)
+        .get_available_monitors()
+        .enumerate()
+        .map(|(index, monitor)| {
+            monitor
+                .get_name()
+                .unwrap_or_else(|| format!("Monitor {}", index + 1))
+        })
+        .collect()
+}
+
+/// Shows the window the way `settings` say.
+pub fn apply(ctx: &mut Context, settings: &DisplaySettings) -> GameResult<()> {
+    let monitor = graphics::window(ctx)
+        .get_available_monitors()
+        .nth(settings.monitor);
+    if let Some(monitor) = monitor {
+        // Fullscreen windows go on the monitor they are on, so the window is moved there
+        // first, which it can only be as a window.
+        graphics::set_fullscreen(ctx, FullscreenType::Windowed)?;
+        let window = graphics::window(ctx);
+        let scale = monitor.get_hidpi_factor();
+        let mut position = monitor.get_position().to_logical(scale);
+        let area = monitor.get_dimensions().to_logical(scale);
+        if let Some(size) = window.get_outer_size() {
+            // Centred, for when it stays a window.
+            position.x += ((area.width - size.width) / 2.0).max(0.0);
+            position.y += ((area.height - size.height) / 2.0).max(0.0);
+        }
+        window.set_position(position);
+    }
+    graphics::set_fullscreen(ctx, settings.mode.fullscreen_type())
+}
+
+/// Holds a game to its frame cap, so it doesn't spend a whole core drawing frames no one sees.
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    /// How long each frame takes at the cap, unless there's no cap.
+    interval: Option<Duration>,
+    /// When the next frame can start.
+    next: Instant,
+}
+
+impl FrameLimiter {
+    /// A limiter for `frame_cap` frames a second, where 0 is no limit.
+    pub fn new(frame_cap: u32) -> Self {
+        FrameLimiter {
+            interval: if frame_cap > 0 {
+                Some(Duration::from_secs(1) / frame_cap)
+            } else {
+                None
+            },
+            next: Instant::now(),
+        }
+    }
+
+    /// Sleeps until it's time for the next frame. Call it once a frame.
+    pub fn wait(&mut self) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+        let now = Instant::now();
+        if self.next > now {
+            thread::sleep(self.next - now);
+        }
+        // A frame that ran long isn't made up for by rushing the ones after it.
+        self.next = self.next.max(now) + interval;
+    }
+}
@@ -0,0 +1,483 @@
+use ggez::event::{Axis, Button, KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::input::Controllers;
+use common::locale;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (640.0, 800.0);
+
+const PADDLE_SIZE: (f32, f32) = (110.0, 14.0);
+const PADDLE_SPEED: f32 = 560.0;
+/// How far each paddle sits from its edge of the screen.
+const PADDLE_MARGIN: f32 = 30.0;
+
+const BALL_RADIUS: f32 = 7.0;
+const BALL_SPEED: f32 = 380.0;
+/// How much faster the balls get with each cleared field.
+const LEVEL_SPEEDUP: f32 = 35.0;
+/// The steepest angle off straight up or down a paddle can send the ball, in radians.
+const MAX_BOUNCE_ANGLE: f32 = 1.1;
+/// Seconds a served ball waits on the paddle before launching by itself.
+const SERVE_TIME: f32 = 2.5;
+
+const BRICK_COLUMNS: usize = 10;
+const BRICK_ROWS: usize = 8;
+const BRICK_SIZE: (f32, f32) = (60.0, 22.0);
+const BRICK_GAP: f32 = 4.0;
+
+const START_LIVES: u32 = 5;
+
+const PLAYER_COLORS: [[f32; 4]; 2] = [[0.3, 0.7, 1.0, 1.0], [1.0, 0.55, 0.25, 1.0]];
+
+/// A ball in play, or waiting to be served from a paddle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Ball {
+    pos: Vec2,
+    vel: Vec2,
+    /// The player who last hit the ball.
+    owner: usize,
+    /// Seconds left before the ball launches by itself, while it is still on its paddle.
+    serve: Option<f32>,
+}
+
+/// The state for the game.
+struct GameState {
+    input: Controllers,
+    /// The left edge of each paddle, the bottom paddle being player one's.
+    paddles: [f32; 2],
+    balls: Vec<Ball>,
+    /// The hits each brick has left, zero being no brick.
+    bricks: Grid<u8>,
+    lives: u32,
+    score: u32,
+    level: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a full brick field and both paddles serving.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            input: Controllers::new(),
+            paddles: [0.0; 2],
+            balls: Vec::new(),
+            bricks: Grid::new(BRICK_COLUMNS, BRICK_ROWS, 0),
+            lives: START_LIVES,
+            score: 0,
+            level: 1,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        self.lives = START_LIVES;
+        self.score = 0;
+        self.level = 1;
+        self.gameover = false;
+        self.start_level();
+    }
+
+    /// Refills the brick field and puts a ball on each paddle.
+    fn start_level(&mut self) {
+        // The middle rows are toughest, so the field wears down from both sides.
+        for (x, y) in (0..BRICK_ROWS).flat_map(|y| (0..BRICK_COLUMNS).map(move |x| (x, y))) {
+            let from_edge = y.min(BRICK_ROWS - 1 - y);
+            self.bricks
+                .set(x as i32, y as i32, 1 + (from_edge as u8 + 1) / 2);
+        }
+        self.paddles = [(SCREEN_SIZE.0 - PADDLE_SIZE.0) / 2.0; 2];
+        self.balls.clear();
+        self.serve(0);
+        self.serve(1);
+    }
+
+    /// The top of the paddle for a player.
+    fn paddle_y(player: usize) -> f32 {
+        if player == 0 {
+            SCREEN_SIZE.1 - PADDLE_MARGIN - PADDLE_SIZE.1
+        } else {
+            PADDLE_MARGIN
+        }
+    }
+
+    fn paddle_rect(&self, player: usize) -> Rect {
+        Rect::new(
+            self.paddles[player],
+            GameState::paddle_y(player),
+            PADDLE_SIZE.0,
+            PADDLE_SIZE.1,
+        )
+    }
+
+    /// Which way a player's paddle sends the ball, `-1` for up and `1` for down.
+    fn facing(player: usize) -> f32 {
+        if player == 0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Where a ball sits while waiting on a player's paddle.
+    fn serve_position(&self, player: usize) -> Vec2 {
+        let paddle = self.paddle_rect(player);
+        let y = if player == 0 {
+            paddle.y - BALL_RADIUS
+        } else {
+            paddle.y + paddle.h + BALL_RADIUS
+        };
+        Vec2::new(paddle.x + paddle.w / 2.0, y)
+    }
+
+    fn serve(&mut self, player: usize) {
+        let pos = self.serve_position(player);
+        self.balls.push(Ball {
+            pos,
+            vel: Vec2::ZERO,
+            owner: player,
+            serve: Some(SERVE_TIME),
+        });
+    }
+
+    fn ball_speed(&self) -> f32 {
+        BALL_SPEED + LEVEL_SPEEDUP * (self.level - 1) as f32
+    }
+
+    fn launch(&self, ball: &mut Ball) {
+        let angle = rand::thread_rng().gen_range(-0.4, 0.4);
+        let facing = GameState::facing(ball.owner);
+        ball.vel = Vec2::new(angle.sin(), facing * angle.cos()) * self.ball_speed();
+        ball.serve = None;
+    }
+
+    fn brick_rect(x: i32, y: i32) -> Rect {
+        let field_width = BRICK_COLUMNS as f32 * (BRICK_SIZE.0 + BRICK_GAP) - BRICK_GAP;
+        let field_height = BRICK_ROWS as f32 * (BRICK_SIZE.1 + BRICK_GAP) - BRICK_GAP;
+        let left = (SCREEN_SIZE.0 - field_width) / 2.0;
+        let top = (SCREEN_SIZE.1 - field_height) / 2.0;
+        Rect::new(
+            left + x as f32 * (BRICK_SIZE.0 + BRICK_GAP),
+            top + y as f32 * (BRICK_SIZE.1 + BRICK_GAP),
+            BRICK_SIZE.0,
+            BRICK_SIZE.1,
+        )
+    }
+
+    /// Bounces the ball off the first brick it overlaps, damaging the brick.
+    fn hit_brick(&mut self, ball: &mut Ball) {
+        let hit = self.bricks.iter().find_map(|(x, y, &hp)| {
+            if hp == 0 {
+                return None;
+            }
+            let rect = GameState::brick_rect(x, y);
+            let closest = Vec2::new(
+                ball.pos.x.max(rect.x).min(rect.x + rect.w),
+                ball.pos.y.max(rect.y).min(rect.y + rect.h),
+            );
+            let offset = ball.pos - closest;
+            if offset.length_squared() < BALL_RADIUS * BALL_RADIUS {
+                Some((x, y, offset))
+            } else {
+                None
+            }
+        });
+
+        if let Some((x, y, offset)) = hit {
+            // Bounce off whichever face the ball came through. A ball that reached the middle of
+            // the brick in one step goes back the way it came.
+            let normal = if offset.length_squared() > 0.0 {
+                offset
+            } else {
+                -ball.vel
+            };
+            if normal.x.abs() > normal.y.abs() {
+                ball.vel.x = normal.x.signum() * ball.vel.x.abs();
+            } else {
+                ball.vel.y = normal.y.signum() * ball.vel.y.abs();
+            }
+
+            if let Some(hp) = self.bricks.get_mut(x, y) {
+                *hp -= 1;
+                self.score += if *hp == 0 { 10 * self.level } else { 1 };
+            }
+        }
+    }
+
+    /// Sends the ball off a paddle at an angle set by where it landed.
+    fn hit_paddle(&self, ball: &mut Ball) {
+        for player in 0..2 {
+            let facing = GameState::facing(player);
+            // Only bounce balls heading into the paddle.
+            if ball.vel.y * facing >= 0.0 {
+                continue;
+            }
+
+            let paddle = self.paddle_rect(player);
+            let touching = ball.pos.x + BALL_RADIUS > paddle.x
+                && ball.pos.x - BALL_RADIUS < paddle.x + paddle.w
+                && ball.pos.y + BALL_RADIUS > paddle.y
+                && ball.pos.y - BALL_RADIUS < paddle.y + paddle.h;
+            if touching {
+                let offset = ((ball.pos.x - (paddle.x + paddle.w / 2.0)) / (paddle.w / 2.0))
+                    .max(-1.0)
+                    .min(1.0);
+                let angle = offset * MAX_BOUNCE_ANGLE;
+                ball.vel = Vec2::new(angle.sin(), facing * angle.cos()) * self.ball_speed();
+                ball.owner = player;
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        for player in 0..2 {
+            let (dx, _) = self.input.direction(ctx, player);
+            self.paddles[player] = (self.paddles[player] + dx * PADDLE_SPEED * dt)
+                .max(0.0)
+                .min(SCREEN_SIZE.0 - PADDLE_SIZE.0);
+        }
+
+        let mut balls = std::mem::replace(&mut self.balls, Vec::new());
+        let mut lost = Vec::new();
+        for ball in balls.iter_mut() {
+            if let Some(serve) = ball.serve {
+                ball.pos = self.serve_position(ball.owner);
+                let serve = serve - dt;
+                ball.serve = Some(serve);
+                if serve <= 0.0 || self.input.action(ctx, ball.owner) {
+                    self.launch(ball);
+                }
+                continue;
+            }
+
+            // Move in steps shorter than the ball so it can't pass through a brick.
+            let travel = ball.vel.length() * dt;
+            let steps = (travel / BALL_RADIUS).ceil().max(1.0) as usize;
+            let step_dt = dt / steps as f32;
+            for _ in 0..steps {
+                ball.pos += ball.vel * step_dt;
+
+                if ball.pos.x < BALL_RADIUS {
+                    ball.pos.x = BALL_RADIUS;
+                    ball.vel.x = ball.vel.x.abs();
+                } else if ball.pos.x > SCREEN_SIZE.0 - BALL_RADIUS {
+                    ball.pos.x = SCREEN_SIZE.0 - BALL_RADIUS;
+                    ball.vel.x = -ball.vel.x.abs();
+                }
+
+                self.hit_paddle(ball);
+                self.hit_brick(ball);
+            }
+
+            // A ball past either edge is lost, and the paddle on that edge serves again.
+            if ball.pos.y > SCREEN_SIZE.1 + BALL_RADIUS {
+                lost.push(0);
+            } else if ball.pos.y < -BALL_RADIUS {
+                lost.push(1);
+            }
+        }
+        balls
+            .retain(|ball| ball.pos.y >= -BALL_RADIUS && ball.pos.y <= SCREEN_SIZE.1 + BALL_RADIUS);
+        self.balls = balls;
+
+        for player in lost {
+            self.lives = self.lives.saturating_sub(1);
+            if self.lives == 0 {
+                self.end_game();
+                return;
+            }
+            self.serve(player);
+        }
+
+        if self.bricks.iter().all(|(_, _, &hp)| hp == 0) {
+            self.level += 1;
+            self.start_level();
+        }
+    }
+
+    fn end_game(&mut self) {
+        self.gameover = true;
+        let values = [("score", self.score as f64), ("level", self.level as f64)];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("coopbreakout", &values);
+        }
+        let _ = achievements::check_run("coopbreakout", &values);
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+
+        for (x, y, &hp) in self.bricks.iter() {
+            if hp == 0 {
+                continue;
+            }
+            let color = match hp {
+                1 => [0.4, 0.8, 0.4, 1.0],
+                2 => [0.9, 0.8, 0.3, 1.0],
+                _ => [0.85, 0.35, 0.5, 1.0],
+            };
+            builder.rectangle(DrawMode::fill(), GameState::brick_rect(x, y), color.into());
+        }
+
+        for player in 0..2 {
+            builder.rectangle(
+                DrawMode::fill(),
+                self.paddle_rect(player),
+                PLAYER_COLORS[player].into(),
+            );
+        }
+
+        for ball in self.balls.iter() {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: ball.pos.x,
+                    y: ball.pos.y,
+                },
+                BALL_RADIUS,
+                0.5,
+                PLAYER_COLORS[ball.owner].into(),
+            );
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+        self.input.update(ctx);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.1, 1.0].into());
+
+        self.draw_world(ctx)?;
+
+        let hud = format!(
+            "Score: {}  Lives: {}  Level: {}",
+            locale::integer(self.score),
+            self.lives,
+            self.level
+        );
+        self.draw_text(
+            ctx,
+            &hud,
+            [1.0, 1.0, 1.0, 1.0],
+            22.0,
+            8.0,
+            SCREEN_SIZE.1 / 2.0 - 160.0,
+        )?;
+
+        // Remind each player of their controls while their ball waits to be served.
+        for player in 0..2 {
+            if self
+                .balls
+                .iter()
+                .any(|ball| ball.owner == player && ball.serve.is_some())
+            {
+                let y = if player == 0 {
+                    SCREEN_SIZE.1 - PADDLE_MARGIN - 50.0
+                } else {
+                    PADDLE_MARGIN + 30.0
+                };
+                let hint = format!("P{}: {}", player + 1, self.input.describe(player));
+                self.draw_text(ctx, &hint, PLAYER_COLORS[player], 20.0, 8.0, y)?;
+            }
+        }
+
+        if self.gameover {
+            self.draw_text(
+                ctx,
+                "GAME OVER! Press Enter to play again.",
+                [1.0, 0.4, 0.3, 1.0],
+                30.0,
+                60.0,
+                SCREEN_SIZE.1 / 2.0 + 140.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.gameover => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, true);
+        if btn == Button::Start && self.gameover {
+            self.restart();
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, false);
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        self.input.gamepad_axis(axis, value, id);
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("coopbreakout", "Sprial404")
+        .window_setup(window::setup("Co-op Breakout"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
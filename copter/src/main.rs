@@ -0,0 +1,449 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::daily::{self, Daily};
+use common::input::Nav;
+use common::overlay::{Overlay, GAME_OVER_ACTIONS};
+use common::stats::StatsDb;
+use common::text::TextStyle;
+use common::theme::{self, Palette};
+use common::ui;
+use common::window;
+use rand::rngs::StdRng;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 500.0);
+
+/// The width of each slice of cave wall.
+const COLUMN_WIDTH: f32 = 10.0;
+
+const COPTER_X: f32 = 150.0;
+const COPTER_SIZE: (f32, f32) = (40.0, 18.0);
+const GRAVITY: f32 = 900.0;
+const THRUST: f32 = 1800.0;
+const MAX_FALL_SPEED: f32 = 500.0;
+
+/// The scroll speed ramps smoothly from the start speed to the top speed over `RAMP_DISTANCE`.
+const START_SPEED: f32 = 220.0;
+const TOP_SPEED: f32 = 420.0;
+const START_GAP: f32 = 400.0;
+const MIN_GAP: f32 = 170.0;
+const RAMP_DISTANCE: f32 = 60000.0;
+/// How much narrower the cave is for the daily challenge's narrow cave.
+const NARROW_GAP: f32 = 0.75;
+
+/// How far the middle of the cave can drift from one column to the next.
+const MAX_DRIFT: f32 = 6.0;
+/// The smallest a wall can be, so the cave never touches the edge of the screen.
+const MIN_WALL: f32 = 20.0;
+
+const BLOCK_SIZE: (f32, f32) = (30.0, 80.0);
+/// The columns between obstacle blocks, picked at random in this range.
+const BLOCK_SPACING: (u32, u32) = (35, 70);
+
+/// Pixels travelled per metre of score.
+const PIXELS_PER_METRE: f32 = 10.0;
+
+/// The game's own colours, for the original theme. The cave walls are the hazard.
+const PALETTE: Palette = Palette {
+    background: [0.05, 0.05, 0.08, 1.0],
+    text: [1.0, 1.0, 1.0, 1.0],
+    player: [1.0, 0.85, 0.2, 1.0],
+    hazard: [0.25, 0.55, 0.25, 1.0],
+    pickup: [1.0, 1.0, 1.0, 1.0],
+};
+
+/// A slice of the cave, with the bottom of the ceiling and the top of the floor.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Column {
+    top: f32,
+    bottom: f32,
+}
+
+/// The state for the game.
+struct GameState {
+    columns: VecDeque<Column>,
+    /// How far the first column has scrolled off the left of the screen.
+    scroll: f32,
+    /// The middle of the cave and how it is drifting, for generating the next column.
+    center: f32,
+    drift: f32,
+    /// Obstacle blocks by their left edge in screen space and their top.
+    blocks: Vec<(f32, f32)>,
+    columns_until_block: u32,
+    y: f32,
+    vy: f32,
+    /// Whether the one button is held, from the keyboard or the mouse.
+    button: bool,
+    distance: f32,
+    best: f32,
+    /// Whether the player has pressed the button to start the run.
+    started: bool,
+    gameover: bool,
+    /// The game over screen, once the copter has crashed.
+    overlay: Option<Overlay>,
+    stats: Option<StatsDb>,
+    /// Today's challenge, when the game was started for it.
+    daily: Option<Daily>,
+    rng: StdRng,
+    palette: Palette,
+}
+
+impl GameState {
+    /// Creates a new game state at the mouth of a fresh cave, or of the daily challenge's cave
+    /// if it was started for it.
+    pub fn new() -> Self {
+        let daily = daily::requested("copter");
+        let mut state = GameState {
+            columns: VecDeque::new(),
+            scroll: 0.0,
+            center: 0.0,
+            drift: 0.0,
+            blocks: Vec::new(),
+            columns_until_block: 0,
+            y: 0.0,
+            vy: 0.0,
+            button: false,
+            distance: 0.0,
+            best: 0.0,
+            started: false,
+            gameover: false,
+            overlay: None,
+            stats: StatsDb::open().ok(),
+            daily,
+            rng: daily::rng(daily.as_ref()),
+            palette: theme::palette(PALETTE),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        self.columns.clear();
+        self.blocks.clear();
+        self.scroll = 0.0;
+        self.center = SCREEN_SIZE.1 / 2.0;
+        self.drift = 0.0;
+        self.columns_until_block = BLOCK_SPACING.1;
+        self.y = SCREEN_SIZE.1 / 2.0 - COPTER_SIZE.1 / 2.0;
+        self.vy = 0.0;
+        self.distance = 0.0;
+        self.started = false;
+        self.gameover = false;
+        self.overlay = None;
+        // A daily challenge flies through the same cave each time.
+        self.rng = daily::rng(self.daily.as_ref());
+
+        while (self.columns.len() as f32) * COLUMN_WIDTH < SCREEN_SIZE.0 + COLUMN_WIDTH {
+            self.push_column();
+        }
+    }
+
+    fn has_modifier(&self, modifier: &str) -> bool {
+        self.daily.map_or(false, |daily| daily.has(modifier))
+    }
+
+    /// How far through the difficulty ramp the run is, from 0 to 1.
+    fn ramp(&self) -> f32 {
+        if self.has_modifier("top_speed") {
+            return 1.0;
+        }
+        let t = (self.distance / RAMP_DISTANCE).min(1.0);
+        // Ease in so the first stretch stays gentle.
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn speed(&self) -> f32 {
+        START_SPEED + (TOP_SPEED - START_SPEED) * self.ramp()
+    }
+
+    fn gap(&self) -> f32 {
+        let gap = START_GAP + (MIN_GAP - START_GAP) * self.ramp();
+        if self.has_modifier("narrow") {
+            gap * NARROW_GAP
+        } else {
+            gap
+        }
+    }
+
+    /// Adds a column to the right of the cave, wandering smoothly up and down.
+    fn push_column(&mut self) {
+        let gap = self.gap();
+
+        self.drift = (self.drift + self.rng.gen_range(-1.0, 1.0))
+            .max(-MAX_DRIFT)
+            .min(MAX_DRIFT);
+        let half = gap / 2.0;
+        self.center += self.drift;
+        if self.center - half < MIN_WALL || self.center + half > SCREEN_SIZE.1 - MIN_WALL {
+            self.center = self
+                .center
+                .max(MIN_WALL + half)
+                .min(SCREEN_SIZE.1 - MIN_WALL - half);
+            self.drift = -self.drift;
+        }
+
+        self.columns.push_back(Column {
+            top: self.center - half,
+            bottom: self.center + half,
+        });
+
+        // Keep the cave open at the start so there is time to get going.
+        if self.distance > SCREEN_SIZE.0 {
+            self.columns_until_block = self.columns_until_block.saturating_sub(1);
+        }
+        if self.columns_until_block == 0 {
+            self.columns_until_block = self.rng.gen_range(BLOCK_SPACING.0, BLOCK_SPACING.1);
+            let x = (self.columns.len() - 1) as f32 * COLUMN_WIDTH - self.scroll;
+            let top = self.rng.gen_range(
+                self.center - half,
+                (self.center + half - BLOCK_SIZE.1).max(self.center - half + 1.0),
+            );
+            self.blocks.push((x, top));
+        }
+    }
+
+    fn copter_rect(&self) -> Rect {
+        Rect::new(COPTER_X, self.y, COPTER_SIZE.0, COPTER_SIZE.1)
+    }
+
+    fn crashed(&self) -> bool {
+        let copter = self.copter_rect();
+        let first = (self.scroll + copter.x) / COLUMN_WIDTH;
+        let last = (self.scroll + copter.x + copter.w) / COLUMN_WIDTH;
+        let hit_wall = (first as usize..=last as usize)
+            .filter_map(|i| self.columns.get(i))
+            .any(|column| copter.y < column.top || copter.y + copter.h > column.bottom);
+
+        hit_wall
+            || self
+                .blocks
+                .iter()
+                .any(|&(x, top)| copter.overlaps(&Rect::new(x, top, BLOCK_SIZE.0, BLOCK_SIZE.1)))
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if !self.started {
+            return;
+        }
+
+        let thrust = if self.button { THRUST } else { 0.0 };
+        self.vy = (self.vy + (GRAVITY - thrust) * dt).min(MAX_FALL_SPEED);
+        self.y += self.vy * dt;
+
+        let step = self.speed() * dt;
+        self.distance += step;
+        self.scroll += step;
+        for block in self.blocks.iter_mut() {
+            block.0 -= step;
+        }
+        self.blocks.retain(|&(x, _)| x + BLOCK_SIZE.0 > 0.0);
+
+        while self.scroll >= COLUMN_WIDTH {
+            self.scroll -= COLUMN_WIDTH;
+            self.columns.pop_front();
+        }
+        while (self.columns.len() as f32) * COLUMN_WIDTH - self.scroll
+            < SCREEN_SIZE.0 + COLUMN_WIDTH
+        {
+            self.push_column();
+        }
+
+        if self.crashed() {
+            self.gameover = true;
+            self.best = self.best.max(self.distance);
+            let metres = (self.distance / PIXELS_PER_METRE).floor() as f64;
+            let mut summary = vec![
+                format!("Distance: {:.0}m", self.distance / PIXELS_PER_METRE),
+                format!("Best: {:.0}m", self.best / PIXELS_PER_METRE),
+            ];
+            // Daily runs only count towards the daily leaderboard, as the modifiers make them
+            // unfair to compare with ordinary runs.
+            if let Some(daily) = &self.daily {
+                let _ = daily.record(metres, None);
+                if let Some((rank, total)) = daily.rank() {
+                    summary.push(format!("Daily rank: {} of {}", rank, total));
+                }
+            } else {
+                let values = [("distance", metres)];
+                if let Some(stats) = &self.stats {
+                    let _ = stats.record("copter", &values);
+                }
+                let _ = achievements::check_run("copter", &values);
+            }
+
+            self.overlay = Some(Overlay::new("Crashed!", summary, GAME_OVER_ACTIONS));
+        }
+    }
+
+    fn press(&mut self) {
+        self.button = true;
+        self.started = true;
+    }
+
+    fn game_over_action(&mut self, ctx: &mut Context, action: Option<usize>) {
+        match action {
+            Some(0) => self.restart(),
+            Some(_) => event::quit(ctx),
+            None => (),
+        }
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        let wall = self.palette.hazard;
+        for (i, column) in self.columns.iter().enumerate() {
+            let x = i as f32 * COLUMN_WIDTH - self.scroll;
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(x, 0.0, COLUMN_WIDTH + 1.0, column.top),
+                wall.into(),
+            );
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    x,
+                    column.bottom,
+                    COLUMN_WIDTH + 1.0,
+                    SCREEN_SIZE.1 - column.bottom,
+                ),
+                wall.into(),
+            );
+        }
+        for &(x, top) in self.blocks.iter() {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(x, top, BLOCK_SIZE.0, BLOCK_SIZE.1),
+                wall.into(),
+            );
+        }
+
+        let copter = self.copter_rect();
+        builder.rectangle(DrawMode::fill(), copter, self.palette.player.into());
+        // A rotor that blurs while thrusting.
+        let rotor = if self.button { 36.0 } else { 24.0 };
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                copter.x + (copter.w - rotor) / 2.0,
+                copter.y - 5.0,
+                rotor,
+                3.0,
+            ),
+            [0.8, 0.8, 0.8, 1.0].into(),
+        );
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, self.palette.background.into());
+
+        self.draw_world(ctx)?;
+
+        let hud = format!(
+            "Distance: {:.0}m  Best: {:.0}m",
+            self.distance / PIXELS_PER_METRE,
+            self.best / PIXELS_PER_METRE
+        );
+        TextStyle::Hud.draw(ctx, hud, self.palette.text, 8.0, 8.0)?;
+
+        if !self.started {
+            TextStyle::Hud.draw(
+                ctx,
+                "Hold Space or the mouse button to climb.",
+                [1.0, 0.4, 0.3, 1.0],
+                150.0,
+                SCREEN_SIZE.1 / 2.0 + 40.0,
+            )?;
+        }
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        match &self.overlay {
+            Some(overlay) => {
+                let action = overlay.action_at(ctx, x, y);
+                self.game_over_action(ctx, action);
+            }
+            None => self.press(),
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        if button == MouseButton::Left {
+            self.button = false;
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        } else if let Some(overlay) = &mut self.overlay {
+            if !repeat {
+                let action = Nav::from_key(keycode).and_then(|nav| overlay.navigate(nav));
+                self.game_over_action(ctx, action);
+            }
+        } else if !repeat
+            && (keycode == KeyCode::Space || keycode == KeyCode::Up || keycode == KeyCode::W)
+        {
+            self.press();
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
+        if keycode == KeyCode::Space || keycode == KeyCode::Up || keycode == KeyCode::W {
+            self.button = false;
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("copter", "Sprial404")
+        .window_setup(window::setup("Cave Copter"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
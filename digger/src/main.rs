@@ -0,0 +1,591 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+const GRID_SIZE: (i32, i32) = (14, 16);
+const CELL_SIZE: f32 = 40.0;
+const HUD_HEIGHT: f32 = 40.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    GRID_SIZE.0 as f32 * CELL_SIZE,
+    GRID_SIZE.1 as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+/// Seconds between steps while a direction is held.
+const MOVE_DELAY: f32 = 0.15;
+/// Seconds between enemy steps through tunnels, and while drifting through the dirt as a ghost.
+const ENEMY_DELAY: f32 = 0.35;
+const GHOST_DELAY: f32 = 0.7;
+/// How long an enemy can't reach the player before it starts ghosting through the dirt.
+const GHOST_AFTER: f32 = 6.0;
+const ENEMY_DELAY_STEP: f32 = 0.02;
+
+/// How many tiles the pump reaches.
+const PUMP_RANGE: i32 = 3;
+/// Pumps needed to pop an enemy.
+const PUMPS_TO_POP: u32 = 4;
+/// How long after the last pump before an enemy deflates a step.
+const DEFLATE_DELAY: f32 = 1.0;
+
+/// How long a rock wobbles after the dirt beneath it is dug out, before falling.
+const ROCK_WOBBLE: f32 = 0.6;
+const ROCK_FALL_DELAY: f32 = 0.08;
+const ROCKS: usize = 3;
+
+const LIVES: u32 = 3;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Tile {
+    Dirt,
+    Tunnel,
+    /// A rock, with the seconds left until it falls once it starts wobbling.
+    Rock(Option<f32>),
+}
+
+/// A rock falling down a tunnel.
+#[derive(Debug, Copy, Clone)]
+struct FallingRock {
+    pos: (i32, i32),
+    timer: f32,
+    /// The number of enemies crushed so far, for the score.
+    crushed: u32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Enemy {
+    pos: (i32, i32),
+    timer: f32,
+    /// How many times the enemy has been pumped up.
+    inflation: u32,
+    deflate_timer: f32,
+    /// Seconds since the enemy last had a path to the player through the tunnels.
+    stuck: f32,
+    /// Whether the enemy is drifting through the dirt towards the player.
+    ghost: bool,
+}
+
+impl Enemy {
+    /// Creates a new enemy at `pos`.
+    pub fn new(pos: (i32, i32)) -> Self {
+        Enemy {
+            pos,
+            timer: ENEMY_DELAY,
+            inflation: 0,
+            deflate_timer: 0.0,
+            stuck: 0.0,
+            ghost: false,
+        }
+    }
+}
+
+/// Finds the first step of the shortest path through the tunnels from `from` to `to`.
+fn path_step(grid: &Grid<Tile>, from: (i32, i32), to: (i32, i32)) -> Option<(i32, i32)> {
+    let mut came_from = Grid::new(grid.width(), grid.height(), None);
+    let mut queue = VecDeque::new();
+    came_from.set(from.0, from.1, Some(from));
+    queue.push_back(from);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == to {
+            // Walk back along the path to the step after `from`.
+            let mut step = pos;
+            while let Some(&Some(prev)) = came_from.get(step.0, step.1) {
+                if prev == from {
+                    return Some(step);
+                }
+                step = prev;
+            }
+            return None;
+        }
+
+        for next in grid.neighbours(pos.0, pos.1) {
+            if grid.get(next.0, next.1) == Some(&Tile::Tunnel)
+                && came_from.get(next.0, next.1) == Some(&None)
+            {
+                came_from.set(next.0, next.1, Some(pos));
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// The state for the game.
+struct GameState {
+    grid: Grid<Tile>,
+    player: (i32, i32),
+    facing: (i32, i32),
+    move_timer: f32,
+    enemies: Vec<Enemy>,
+    rocks: Vec<FallingRock>,
+    /// The tiles the pump reached on its last shot, drawn briefly.
+    pump: Vec<(i32, i32)>,
+    pump_timer: f32,
+    round: u32,
+    lives: u32,
+    score: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the first round.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            grid: Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, Tile::Dirt),
+            player: (0, 0),
+            facing: (1, 0),
+            move_timer: 0.0,
+            enemies: Vec::new(),
+            rocks: Vec::new(),
+            pump: Vec::new(),
+            pump_timer: 0.0,
+            round: 1,
+            lives: LIVES,
+            score: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.start_round();
+        state
+    }
+
+    /// Digs out a fresh field with a tunnel for each enemy and a few rocks.
+    fn start_round(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.grid.fill(Tile::Dirt);
+
+        // The top row is open ground, with a shaft leading down from the middle.
+        for x in 0..GRID_SIZE.0 {
+            self.grid.set(x, 0, Tile::Tunnel);
+        }
+        let centre = GRID_SIZE.0 / 2;
+        for y in 1..GRID_SIZE.1 / 2 {
+            self.grid.set(centre, y, Tile::Tunnel);
+        }
+
+        self.enemies.clear();
+        let count = (3 + self.round as usize).min(8);
+        while self.enemies.len() < count {
+            let horizontal = rng.gen_bool(0.5);
+            let (x, y) = (
+                rng.gen_range(1, GRID_SIZE.0 - 4),
+                rng.gen_range(3, GRID_SIZE.1 - 4),
+            );
+            if (x - centre).abs() < 3 && y < GRID_SIZE.1 / 2 {
+                continue;
+            }
+            for i in 0..4 {
+                let (tx, ty) = if horizontal { (x + i, y) } else { (x, y + i) };
+                self.grid.set(tx, ty, Tile::Tunnel);
+            }
+            self.enemies.push(Enemy::new((x, y)));
+        }
+
+        // Only put rocks in the dirt, with dirt below so they don't drop straight away.
+        let mut placed = 0;
+        while placed < ROCKS {
+            let (x, y) = (
+                rng.gen_range(0, GRID_SIZE.0),
+                rng.gen_range(2, GRID_SIZE.1 - 2),
+            );
+            if self.grid.get(x, y) == Some(&Tile::Dirt)
+                && self.grid.get(x, y + 1) == Some(&Tile::Dirt)
+            {
+                self.grid.set(x, y, Tile::Rock(None));
+                placed += 1;
+            }
+        }
+
+        self.rocks.clear();
+        self.reset_player();
+    }
+
+    fn reset_player(&mut self) {
+        self.player = (GRID_SIZE.0 / 2, 0);
+        self.facing = (1, 0);
+        self.pump.clear();
+    }
+
+    fn enemy_delay(&self) -> f32 {
+        (ENEMY_DELAY - self.round as f32 * ENEMY_DELAY_STEP).max(0.15)
+    }
+
+    fn move_player(&mut self, dir: (i32, i32)) {
+        self.facing = dir;
+        let next = (self.player.0 + dir.0, self.player.1 + dir.1);
+        match self.grid.get(next.0, next.1) {
+            Some(Tile::Dirt) | Some(Tile::Tunnel) => {
+                self.grid.set(next.0, next.1, Tile::Tunnel);
+                self.player = next;
+            }
+            _ => (),
+        }
+    }
+
+    /// Shoots the pump along the tunnel the player is facing, inflating the first enemy it hits.
+    fn fire_pump(&mut self) {
+        self.pump.clear();
+        self.pump_timer = 0.2;
+
+        for i in 1..=PUMP_RANGE {
+            let pos = (
+                self.player.0 + self.facing.0 * i,
+                self.player.1 + self.facing.1 * i,
+            );
+            if self.grid.get(pos.0, pos.1) != Some(&Tile::Tunnel) {
+                break;
+            }
+            self.pump.push(pos);
+
+            if let Some(enemy) = self.enemies.iter_mut().find(|enemy| enemy.pos == pos) {
+                enemy.inflation += 1;
+                enemy.deflate_timer = DEFLATE_DELAY;
+                break;
+            }
+        }
+
+        // Enemies deeper down are worth more.
+        let mut popped = 0;
+        for enemy in self.enemies.iter().filter(|e| e.inflation >= PUMPS_TO_POP) {
+            popped += 200 + enemy.pos.1 as u32 * 25;
+        }
+        self.score += popped;
+        self.enemies.retain(|enemy| enemy.inflation < PUMPS_TO_POP);
+    }
+
+    fn update_enemies(&mut self, dt: f32) {
+        let delay = self.enemy_delay();
+        let player = self.player;
+        let mut rng = rand::thread_rng();
+
+        for enemy in self.enemies.iter_mut() {
+            if enemy.inflation > 0 {
+                enemy.deflate_timer -= dt;
+                if enemy.deflate_timer <= 0.0 {
+                    enemy.inflation -= 1;
+                    enemy.deflate_timer = DEFLATE_DELAY;
+                }
+                continue;
+            }
+
+            enemy.timer -= dt;
+            if enemy.timer > 0.0 {
+                continue;
+            }
+
+            if enemy.ghost {
+                // Drift straight towards the player, becoming solid again in a tunnel.
+                let dx = (player.0 - enemy.pos.0).signum();
+                let dy = (player.1 - enemy.pos.1).signum();
+                enemy.pos = if dx != 0 && (dy == 0 || rng.gen_bool(0.5)) {
+                    (enemy.pos.0 + dx, enemy.pos.1)
+                } else {
+                    (enemy.pos.0, enemy.pos.1 + dy)
+                };
+                enemy.timer = GHOST_DELAY;
+                if self.grid.get(enemy.pos.0, enemy.pos.1) == Some(&Tile::Tunnel) {
+                    enemy.ghost = false;
+                    enemy.stuck = 0.0;
+                }
+                continue;
+            }
+
+            enemy.timer = delay;
+            match path_step(&self.grid, enemy.pos, player) {
+                Some(step) => {
+                    enemy.pos = step;
+                    enemy.stuck = 0.0;
+                }
+                None => {
+                    // Wander the tunnel until it gives up and ghosts through the dirt.
+                    enemy.stuck += delay;
+                    let options: Vec<(i32, i32)> = self
+                        .grid
+                        .neighbours(enemy.pos.0, enemy.pos.1)
+                        .filter(|&(x, y)| self.grid.get(x, y) == Some(&Tile::Tunnel))
+                        .collect();
+                    if !options.is_empty() {
+                        enemy.pos = options[rng.gen_range(0, options.len())];
+                    }
+                    if enemy.stuck >= GHOST_AFTER {
+                        enemy.ghost = true;
+                    }
+                }
+            }
+        }
+    }
+
+    fn update_rocks(&mut self, dt: f32) {
+        // Start rocks wobbling once the dirt beneath them is dug out.
+        let mut loosened = Vec::new();
+        for (x, y, tile) in self.grid.iter() {
+            if let Tile::Rock(wobble) = tile {
+                if self.grid.get(x, y + 1) == Some(&Tile::Tunnel) && (x, y + 1) != self.player {
+                    loosened.push((x, y, wobble.map_or(ROCK_WOBBLE, |t| t - dt)));
+                }
+            }
+        }
+        for (x, y, wobble) in loosened {
+            if wobble <= 0.0 {
+                self.grid.set(x, y, Tile::Tunnel);
+                self.rocks.push(FallingRock {
+                    pos: (x, y),
+                    timer: ROCK_FALL_DELAY,
+                    crushed: 0,
+                });
+            } else {
+                self.grid.set(x, y, Tile::Rock(Some(wobble)));
+            }
+        }
+
+        let mut landed = Vec::new();
+        for (i, rock) in self.rocks.iter_mut().enumerate() {
+            rock.timer -= dt;
+            if rock.timer > 0.0 {
+                continue;
+            }
+            rock.timer = ROCK_FALL_DELAY;
+
+            let below = (rock.pos.0, rock.pos.1 + 1);
+            if self.grid.get(below.0, below.1) == Some(&Tile::Tunnel) {
+                rock.pos = below;
+            } else {
+                landed.push(i);
+            }
+        }
+
+        for rock in self.rocks.iter_mut() {
+            let before = self.enemies.len();
+            self.enemies.retain(|enemy| enemy.pos != rock.pos);
+            rock.crushed += (before - self.enemies.len()) as u32;
+        }
+        if self.rocks.iter().any(|rock| rock.pos == self.player) {
+            self.lose_life();
+        }
+
+        // Landed rocks crumble away, scoring more for every enemy they took with them.
+        for i in landed.into_iter().rev() {
+            let rock = self.rocks.remove(i);
+            self.score += match rock.crushed {
+                0 => 0,
+                n => 1000 * 2u32.pow(n - 1),
+            };
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("round", f64::from(self.round)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("digger", &values);
+        }
+        let _ = achievements::check_run("digger", &values);
+    }
+
+    fn lose_life(&mut self) {
+        self.lives -= 1;
+        if self.lives == 0 {
+            self.gameover = true;
+            self.record_run();
+            return;
+        }
+
+        self.reset_player();
+        // Give the player a moment before the enemies come after them again.
+        for enemy in self.enemies.iter_mut() {
+            enemy.inflation = 0;
+            enemy.ghost = false;
+            enemy.stuck = 0.0;
+            enemy.timer = 2.0;
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.move_timer -= dt;
+        if self.move_timer <= 0.0 {
+            let dir = if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+                Some((-1, 0))
+            } else if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+                Some((1, 0))
+            } else if keyboard::is_key_pressed(ctx, KeyCode::Up) {
+                Some((0, -1))
+            } else if keyboard::is_key_pressed(ctx, KeyCode::Down) {
+                Some((0, 1))
+            } else {
+                None
+            };
+            if let Some(dir) = dir {
+                self.move_player(dir);
+                self.move_timer = MOVE_DELAY;
+            }
+        }
+
+        self.pump_timer -= dt;
+        if self.pump_timer <= 0.0 {
+            self.pump.clear();
+        }
+
+        self.update_enemies(dt);
+        self.update_rocks(dt);
+
+        let caught = self
+            .enemies
+            .iter()
+            .any(|enemy| enemy.inflation == 0 && enemy.pos == self.player);
+        if caught && !self.gameover {
+            self.lose_life();
+        }
+
+        if self.enemies.is_empty() && !self.gameover {
+            self.round += 1;
+            self.start_round();
+        }
+    }
+
+    fn draw_cell(
+        &self,
+        builder: &mut graphics::MeshBuilder,
+        pos: (i32, i32),
+        inset: f32,
+        color: [f32; 4],
+    ) {
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                pos.0 as f32 * CELL_SIZE + inset,
+                HUD_HEIGHT + pos.1 as f32 * CELL_SIZE + inset,
+                CELL_SIZE - inset * 2.0,
+                CELL_SIZE - inset * 2.0,
+            ),
+            color.into(),
+        );
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y, tile) in self.grid.iter() {
+            // Dirt gets darker the deeper it goes.
+            let depth = y as f32 / GRID_SIZE.1 as f32;
+            let dirt = [0.8 - depth * 0.4, 0.5 - depth * 0.3, 0.1, 1.0];
+            match tile {
+                Tile::Dirt => self.draw_cell(&mut builder, (x, y), 0.0, dirt),
+                Tile::Tunnel if y == 0 => {
+                    self.draw_cell(&mut builder, (x, y), 0.0, [0.3, 0.5, 0.9, 1.0])
+                }
+                Tile::Tunnel => (),
+                Tile::Rock(wobble) => {
+                    self.draw_cell(&mut builder, (x, y), 0.0, dirt);
+                    let shake = if wobble.is_some() { 3.0 } else { 4.0 };
+                    self.draw_cell(&mut builder, (x, y), shake, [0.5, 0.5, 0.5, 1.0]);
+                }
+            }
+        }
+        for rock in self.rocks.iter() {
+            self.draw_cell(&mut builder, rock.pos, 4.0, [0.5, 0.5, 0.5, 1.0]);
+        }
+        for &pos in self.pump.iter() {
+            self.draw_cell(&mut builder, pos, 16.0, [1.0, 1.0, 1.0, 1.0]);
+        }
+        for enemy in self.enemies.iter() {
+            let color = if enemy.ghost {
+                [1.0, 0.3, 0.3, 0.4]
+            } else {
+                [1.0, 0.3, 0.3, 1.0]
+            };
+            // Inflated enemies swell out of their tile.
+            let inset = 8.0 - enemy.inflation as f32 * 4.0;
+            self.draw_cell(&mut builder, enemy.pos, inset, color);
+        }
+        self.draw_cell(&mut builder, self.player, 6.0, [1.0, 1.0, 1.0, 1.0]);
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Round: {}  Lives: {}",
+                locale::integer(self.score),
+                self.round,
+                self.lives
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER! Press Enter.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space if !repeat && !self.gameover => self.fire_pump(),
+            KeyCode::Return if self.gameover => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("digger", "Sprial404")
+        .window_setup(window::setup("Digger"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,446 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::input::Nav;
+use common::locale;
+use common::overlay::{Overlay, GAME_OVER_ACTIONS};
+use common::stats::StatsDb;
+use common::text::TextStyle;
+use common::theme::{self, Palette};
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (640.0, 720.0);
+
+const PLAYER_SIZE: (f32, f32) = (48.0, 24.0);
+const PLAYER_SPEED: f32 = 420.0;
+
+const BLOCK_MIN_WIDTH: f32 = 24.0;
+const BLOCK_MAX_WIDTH: f32 = 96.0;
+const BLOCK_HEIGHT: f32 = 24.0;
+
+/// The fall speed and spawn interval of the blocks at the start of a run.
+const START_FALL_SPEED: f32 = 180.0;
+const START_SPAWN_INTERVAL: f32 = 0.8;
+/// How much faster the rain gets for every second survived.
+const FALL_ACCELERATION: f32 = 6.0;
+const MIN_SPAWN_INTERVAL: f32 = 0.15;
+
+/// Points awarded for every second survived.
+const POINTS_PER_SECOND: f32 = 10.0;
+/// A block passing the player closer than this horizontal gap counts as a near miss.
+const NEAR_MISS_DISTANCE: f32 = 12.0;
+const NEAR_MISS_BONUS: u32 = 25;
+
+/// The game's own colours, for the original theme.
+const PALETTE: Palette = Palette {
+    background: [0.0, 0.0, 0.0, 1.0],
+    text: [1.0, 1.0, 1.0, 1.0],
+    player: [0.2, 0.8, 1.0, 1.0],
+    hazard: [1.0, 0.3, 0.2, 1.0],
+    pickup: [1.0, 1.0, 1.0, 1.0],
+};
+
+/// The player controlled paddle at the bottom of the screen.
+#[derive(Debug, Copy, Clone)]
+struct Player {
+    x: f32,
+}
+
+impl Player {
+    /// Creates a new player centered at the bottom of the screen.
+    pub fn new() -> Self {
+        Player {
+            x: (SCREEN_SIZE.0 - PLAYER_SIZE.0) / 2.0,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(
+            self.x,
+            SCREEN_SIZE.1 - PLAYER_SIZE.1 * 2.0,
+            PLAYER_SIZE.0,
+            PLAYER_SIZE.1,
+        )
+    }
+
+    fn update(&mut self, ctx: &Context, dt: f32) {
+        let mut dir = 0.0;
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) || keyboard::is_key_pressed(ctx, KeyCode::A)
+        {
+            dir -= 1.0;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right)
+            || keyboard::is_key_pressed(ctx, KeyCode::D)
+        {
+            dir += 1.0;
+        }
+
+        self.x = (self.x + dir * PLAYER_SPEED * dt)
+            .max(0.0)
+            .min(SCREEN_SIZE.0 - PLAYER_SIZE.0);
+    }
+
+    fn draw(&self, ctx: &mut Context, palette: &Palette) -> GameResult<()> {
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            self.rect(),
+            palette.player.into(),
+        )?;
+        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+/// A single falling block the player must dodge.
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    rect: Rect,
+    speed: f32,
+    /// Whether this block has already been checked for a near miss, so the bonus is only awarded
+    /// once per block.
+    passed: bool,
+}
+
+impl Block {
+    /// Creates a new block just above the top of the screen at a random horizontal position.
+    pub fn random(speed: f32) -> Self {
+        let mut rng = rand::thread_rng();
+        let width = rng.gen_range(BLOCK_MIN_WIDTH, BLOCK_MAX_WIDTH);
+        let x = rng.gen_range(0.0, SCREEN_SIZE.0 - width);
+
+        Block {
+            rect: Rect::new(x, -BLOCK_HEIGHT, width, BLOCK_HEIGHT),
+            speed,
+            passed: false,
+        }
+    }
+
+    fn draw(&self, ctx: &mut Context, palette: &Palette) -> GameResult<()> {
+        let rect =
+            graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), self.rect, palette.hazard.into())?;
+        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+/// The horizontal gap between two rects, or `0.0` if they overlap horizontally.
+fn horizontal_gap(a: &Rect, b: &Rect) -> f32 {
+    if a.right() < b.left() {
+        b.left() - a.right()
+    } else if b.right() < a.left() {
+        a.left() - b.right()
+    } else {
+        0.0
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    player: Player,
+    blocks: Vec<Block>,
+    /// Seconds survived in the current run.
+    elapsed: f32,
+    /// Seconds left until the next block spawns.
+    spawn_timer: f32,
+    bonus: u32,
+    near_misses: u32,
+    gameover: bool,
+    /// The game over screen, once the run is over.
+    overlay: Option<Overlay>,
+    palette: Palette,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state.
+    pub fn new() -> Self {
+        GameState {
+            player: Player::new(),
+            blocks: Vec::new(),
+            elapsed: 0.0,
+            spawn_timer: START_SPAWN_INTERVAL,
+            bonus: 0,
+            near_misses: 0,
+            gameover: false,
+            overlay: None,
+            palette: theme::palette(PALETTE),
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    /// The current score, made up of the survival time and any near-miss bonuses.
+    fn score(&self) -> u32 {
+        (self.elapsed * POINTS_PER_SECOND) as u32 + self.bonus
+    }
+
+    fn fall_speed(&self) -> f32 {
+        START_FALL_SPEED + self.elapsed * FALL_ACCELERATION
+    }
+
+    fn spawn_interval(&self) -> f32 {
+        (START_SPAWN_INTERVAL * START_FALL_SPEED / self.fall_speed()).max(MIN_SPAWN_INTERVAL)
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.player.update(ctx, dt);
+        self.advance(dt);
+    }
+
+    /// Moves the run on by `dt` seconds after the player has moved: spawning and dropping the
+    /// blocks, counting near misses and ending the run if one hits.
+    fn advance(&mut self, dt: f32) {
+        self.elapsed += dt;
+
+        self.spawn_timer -= dt;
+        if self.spawn_timer <= 0.0 {
+            self.blocks.push(Block::random(self.fall_speed()));
+            self.spawn_timer += self.spawn_interval();
+        }
+
+        let player_rect = self.player.rect();
+        for block in self.blocks.iter_mut() {
+            block.rect.y += block.speed * dt;
+
+            if block.rect.overlaps(&player_rect) {
+                self.gameover = true;
+            }
+
+            // Once a block falls past the player without hitting them, check how close it came.
+            if !block.passed && block.rect.top() > player_rect.bottom() {
+                block.passed = true;
+                if horizontal_gap(&block.rect, &player_rect) <= NEAR_MISS_DISTANCE {
+                    self.bonus += NEAR_MISS_BONUS;
+                    self.near_misses += 1;
+                }
+            }
+        }
+
+        self.blocks.retain(|block| block.rect.top() < SCREEN_SIZE.1);
+
+        if self.gameover {
+            self.record_run();
+            let summary = vec![
+                format!("Score: {}", locale::integer(self.score())),
+                format!("Survived: {:.1}s", self.elapsed),
+                format!("Near misses: {}", self.near_misses),
+            ];
+            self.overlay = Some(Overlay::new("Game over", summary, GAME_OVER_ACTIONS));
+        }
+    }
+
+    fn record_run(&self) {
+        if let Some(stats) = &self.stats {
+            let values = [
+                ("score", f64::from(self.score())),
+                ("survived", f64::from(self.elapsed)),
+                ("near_misses", f64::from(self.near_misses)),
+            ];
+            let _ = stats.record("dodger", &values);
+            let _ = achievements::check_run("dodger", &values);
+        }
+    }
+
+    fn game_over_action(&mut self, ctx: &mut Context, action: Option<usize>) {
+        match action {
+            Some(0) => *self = GameState::new(),
+            Some(_) => event::quit(ctx),
+            None => (),
+        }
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, self.palette.background.into());
+
+        for block in self.blocks.iter() {
+            block.draw(ctx, &self.palette)?;
+        }
+        self.player.draw(ctx, &self.palette)?;
+
+        let hud = format!(
+            "Score: {}  Time: {:.1}s  Near misses: {}",
+            locale::integer(self.score()),
+            self.elapsed,
+            self.near_misses
+        );
+        TextStyle::Hud.draw(ctx, hud, self.palette.text, 8.0, 8.0)?;
+
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        if let Some(overlay) = &mut self.overlay {
+            let action = Nav::from_key(keycode).and_then(|nav| overlay.navigate(nav));
+            self.game_over_action(ctx, action);
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if let (Some(overlay), MouseButton::Left) = (&self.overlay, button) {
+            let action = overlay.action_at(ctx, x, y);
+            self.game_over_action(ctx, action);
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("dodger", "Sprial404")
+        .window_setup(window::setup("Dodger"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A run with no blocks due to spawn, and `block` falling towards the player.
+    fn run_with(block: Block) -> GameState {
+        let mut game = GameState::new();
+        // Keep the tests' runs out of the player's stats.
+        game.stats = None;
+        game.spawn_timer = f32::INFINITY;
+        game.blocks.push(block);
+        game
+    }
+
+    /// A block just above the player, `x` across.
+    fn block_above(x: f32, width: f32) -> Block {
+        let player = Player::new().rect();
+        Block {
+            rect: Rect::new(x, player.top() - BLOCK_HEIGHT - 1.0, width, BLOCK_HEIGHT),
+            speed: 600.0,
+            passed: false,
+        }
+    }
+
+    /// Steps the run until `block` has fallen past the player or the run is over.
+    fn fall(game: &mut GameState) {
+        for _ in 0..100 {
+            if game.gameover || game.blocks.iter().all(|block| block.passed) {
+                return;
+            }
+            game.advance(0.01);
+        }
+    }
+
+    #[test]
+    fn gaps_are_measured_between_edges() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(horizontal_gap(&a, &Rect::new(15.0, 50.0, 10.0, 10.0)), 5.0);
+        assert_eq!(horizontal_gap(&Rect::new(15.0, 50.0, 10.0, 10.0), &a), 5.0);
+        assert_eq!(horizontal_gap(&a, &Rect::new(5.0, 50.0, 10.0, 10.0)), 0.0);
+    }
+
+    #[test]
+    fn near_misses_earn_a_bonus_once() {
+        let player = Player::new().rect();
+        let mut game = run_with(block_above(player.right() + 5.0, 40.0));
+        fall(&mut game);
+        assert!(!game.gameover);
+        assert_eq!(game.near_misses, 1);
+        assert_eq!(game.bonus, NEAR_MISS_BONUS);
+
+        game.advance(0.01);
+        assert_eq!(game.near_misses, 1);
+    }
+
+    #[test]
+    fn distant_blocks_earn_nothing() {
+        let player = Player::new().rect();
+        let mut game = run_with(block_above(
+            player.right() + NEAR_MISS_DISTANCE + 20.0,
+            40.0,
+        ));
+        fall(&mut game);
+        assert!(!game.gameover);
+        assert_eq!(game.near_misses, 0);
+        assert_eq!(game.bonus, 0);
+    }
+
+    #[test]
+    fn blocks_landing_on_the_player_end_the_run() {
+        let player = Player::new().rect();
+        let mut game = run_with(block_above(player.left(), 40.0));
+        fall(&mut game);
+        assert!(game.gameover);
+        assert_eq!(game.near_misses, 0);
+        assert!(game.overlay.is_some());
+    }
+
+    #[test]
+    fn blocks_are_dropped_once_off_screen() {
+        let mut game = run_with(block_above(0.0, 40.0));
+        game.blocks[0].rect.y = SCREEN_SIZE.1 - 1.0;
+        game.advance(0.1);
+        assert!(game.blocks.is_empty());
+    }
+
+    #[test]
+    fn the_rain_speeds_up_to_a_limit() {
+        let mut game = GameState::new();
+        let start = (game.fall_speed(), game.spawn_interval());
+        assert_eq!(start, (START_FALL_SPEED, START_SPAWN_INTERVAL));
+        game.elapsed = 30.0;
+        assert!(game.fall_speed() > start.0);
+        assert!(game.spawn_interval() < start.1);
+        game.elapsed = 10_000.0;
+        assert_eq!(game.spawn_interval(), MIN_SPAWN_INTERVAL);
+    }
+
+    #[test]
+    fn blocks_spawn_as_time_passes() {
+        let mut game = GameState::new();
+        game.advance(START_SPAWN_INTERVAL);
+        assert_eq!(game.blocks.len(), 1);
+        let block = game.blocks[0].rect;
+        assert!(block.left() >= 0.0 && block.right() <= SCREEN_SIZE.0);
+    }
+
+    #[test]
+    fn scores_count_time_and_bonuses() {
+        let mut game = GameState::new();
+        game.elapsed = 12.5;
+        game.bonus = NEAR_MISS_BONUS * 2;
+        assert_eq!(game.score(), 125 + 50);
+    }
+}
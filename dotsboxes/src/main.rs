@@ -0,0 +1,615 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::seq::SliceRandom;
+
+const SCREEN_SIZE: (f32, f32) = (640.0, 700.0);
+const HUD_HEIGHT: f32 = 60.0;
+const MARGIN: f32 = 50.0;
+
+const MIN_SIZE: usize = 2;
+const MAX_SIZE: usize = 9;
+const DEFAULT_SIZE: usize = 4;
+
+/// How close the mouse has to be to an edge to select it.
+const PICK_DISTANCE: f32 = 16.0;
+/// How long the AI waits before each move.
+const AI_DELAY: f32 = 0.5;
+
+const PLAYER_COLORS: [[f32; 4]; 2] = [[0.3, 0.6, 1.0, 1.0], [1.0, 0.4, 0.3, 1.0]];
+
+/// A line between two neighbouring dots.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Edge {
+    horizontal: bool,
+    /// The dot the edge starts at, with horizontal edges going right and vertical edges down.
+    x: i32,
+    y: i32,
+}
+
+/// The dots, drawn edges and claimed boxes of a board.
+#[derive(Debug, Clone)]
+struct Board {
+    size: usize,
+    /// Horizontal edges by their left dot, `size` wide and `size + 1` tall.
+    horizontal: Grid<bool>,
+    /// Vertical edges by their top dot, `size + 1` wide and `size` tall.
+    vertical: Grid<bool>,
+    owners: Grid<Option<usize>>,
+}
+
+impl Board {
+    /// Creates a new empty board of `size` by `size` boxes.
+    pub fn new(size: usize) -> Self {
+        Board {
+            size,
+            horizontal: Grid::new(size, size + 1, false),
+            vertical: Grid::new(size + 1, size, false),
+            owners: Grid::new(size, size, None),
+        }
+    }
+
+    fn is_drawn(&self, edge: Edge) -> bool {
+        let edges = if edge.horizontal {
+            &self.horizontal
+        } else {
+            &self.vertical
+        };
+        edges.get(edge.x, edge.y) == Some(&true)
+    }
+
+    fn undrawn(&self) -> Vec<Edge> {
+        let horizontal =
+            self.horizontal
+                .iter()
+                .filter(|(_, _, drawn)| !**drawn)
+                .map(|(x, y, _)| Edge {
+                    horizontal: true,
+                    x,
+                    y,
+                });
+        let vertical = self
+            .vertical
+            .iter()
+            .filter(|(_, _, drawn)| !**drawn)
+            .map(|(x, y, _)| Edge {
+                horizontal: false,
+                x,
+                y,
+            });
+        horizontal.chain(vertical).collect()
+    }
+
+    /// The four edges around the box at `(x, y)`.
+    fn box_edges(&self, x: i32, y: i32) -> [Edge; 4] {
+        [
+            Edge {
+                horizontal: true,
+                x,
+                y,
+            },
+            Edge {
+                horizontal: true,
+                x,
+                y: y + 1,
+            },
+            Edge {
+                horizontal: false,
+                x,
+                y,
+            },
+            Edge {
+                horizontal: false,
+                x: x + 1,
+                y,
+            },
+        ]
+    }
+
+    fn sides(&self, x: i32, y: i32) -> usize {
+        self.box_edges(x, y)
+            .iter()
+            .filter(|edge| self.is_drawn(**edge))
+            .count()
+    }
+
+    /// The one or two boxes either side of `edge`.
+    fn edge_boxes(&self, edge: Edge) -> Vec<(i32, i32)> {
+        let candidates = if edge.horizontal {
+            [(edge.x, edge.y - 1), (edge.x, edge.y)]
+        } else {
+            [(edge.x - 1, edge.y), (edge.x, edge.y)]
+        };
+        candidates
+            .iter()
+            .cloned()
+            .filter(|&(x, y)| self.owners.in_bounds(x, y))
+            .collect()
+    }
+
+    /// Whether drawing `edge` completes a box.
+    fn completes(&self, edge: Edge) -> bool {
+        self.edge_boxes(edge)
+            .iter()
+            .any(|&(x, y)| self.sides(x, y) == 3)
+    }
+
+    /// Whether drawing `edge` leaves no box with three sides for the opponent to take.
+    fn is_safe(&self, edge: Edge) -> bool {
+        self.edge_boxes(edge)
+            .iter()
+            .all(|&(x, y)| self.sides(x, y) < 2)
+    }
+
+    /// Draws `edge` for `player`, returning how many boxes it completed.
+    fn draw_edge(&mut self, edge: Edge, player: usize) -> u32 {
+        let edges = if edge.horizontal {
+            &mut self.horizontal
+        } else {
+            &mut self.vertical
+        };
+        edges.set(edge.x, edge.y, true);
+
+        let mut completed = 0;
+        for (x, y) in self.edge_boxes(edge) {
+            if self.sides(x, y) == 4 {
+                self.owners.set(x, y, Some(player));
+                completed += 1;
+            }
+        }
+        completed
+    }
+
+    fn is_full(&self) -> bool {
+        self.owners.iter().all(|(_, _, owner)| owner.is_some())
+    }
+
+    /// How many boxes the opponent could take in a row if `edge` were drawn now.
+    fn giveaway(&self, edge: Edge) -> u32 {
+        let mut board = self.clone();
+        board.draw_edge(edge, 0);
+
+        let mut taken = 0;
+        while let Some(next) = board
+            .undrawn()
+            .into_iter()
+            .find(|edge| board.completes(*edge))
+        {
+            taken += board.draw_edge(next, 1);
+        }
+        taken
+    }
+
+    /// Looks for a double-dealing move instead of taking the box completed by `capture`.
+    ///
+    /// At the end of a chain with two boxes left, drawing the far end instead of taking them
+    /// hands both boxes over with a single line, which forces the opponent to open up the next
+    /// chain after taking them.
+    fn double_deal(&self, capture: Edge) -> Option<Edge> {
+        let boxes = self.edge_boxes(capture);
+        if boxes.len() != 2 {
+            return None;
+        }
+
+        // The box beyond the one we could take must be the last box of the chain.
+        let (x, y) = *boxes.iter().find(|&&(x, y)| self.sides(x, y) == 2)?;
+        let far_end = *self
+            .box_edges(x, y)
+            .iter()
+            .find(|edge| **edge != capture && !self.is_drawn(**edge))?;
+        let chain_continues = self
+            .edge_boxes(far_end)
+            .iter()
+            .any(|&(bx, by)| (bx, by) != (x, y) && self.sides(bx, by) >= 2);
+
+        let unclaimed = self
+            .owners
+            .iter()
+            .filter(|(_, _, owner)| owner.is_none())
+            .count();
+        if chain_continues || unclaimed <= 2 {
+            None
+        } else {
+            Some(far_end)
+        }
+    }
+
+    /// Picks a move for the AI, taking boxes, playing safe, and sacrificing as little as possible
+    /// once there are no safe moves left.
+    fn ai_move(&self) -> Option<Edge> {
+        let mut undrawn = self.undrawn();
+        undrawn.shuffle(&mut rand::thread_rng());
+        let any_safe = undrawn.iter().any(|edge| self.is_safe(*edge));
+
+        if let Some(&capture) = undrawn.iter().find(|edge| self.completes(**edge)) {
+            if !any_safe {
+                if let Some(edge) = self.double_deal(capture) {
+                    return Some(edge);
+                }
+            }
+            return Some(capture);
+        }
+
+        if any_safe {
+            return undrawn.into_iter().find(|edge| self.is_safe(*edge));
+        }
+        undrawn.into_iter().min_by_key(|edge| self.giveaway(*edge))
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Phase {
+    /// Choosing the board size and opponent.
+    Menu,
+    Playing,
+    GameOver,
+}
+
+/// The state for the game.
+struct GameState {
+    phase: Phase,
+    size: usize,
+    board: Board,
+    /// Whether the second player is the AI.
+    versus_ai: bool,
+    turn: usize,
+    scores: [u32; 2],
+    hovered: Option<Edge>,
+    ai_timer: f32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the menu.
+    pub fn new() -> Self {
+        GameState {
+            phase: Phase::Menu,
+            size: DEFAULT_SIZE,
+            board: Board::new(DEFAULT_SIZE),
+            versus_ai: false,
+            turn: 0,
+            scores: [0, 0],
+            hovered: None,
+            ai_timer: 0.0,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start(&mut self, versus_ai: bool) {
+        self.board = Board::new(self.size);
+        self.versus_ai = versus_ai;
+        self.turn = 0;
+        self.scores = [0, 0];
+        self.hovered = None;
+        self.ai_timer = AI_DELAY;
+        self.phase = Phase::Playing;
+    }
+
+    fn spacing(&self) -> f32 {
+        (SCREEN_SIZE.0 - MARGIN * 2.0) / self.size as f32
+    }
+
+    fn dot(&self, x: i32, y: i32) -> (f32, f32) {
+        (
+            MARGIN + x as f32 * self.spacing(),
+            HUD_HEIGHT + MARGIN + y as f32 * self.spacing(),
+        )
+    }
+
+    fn ai_turn(&self) -> bool {
+        self.versus_ai && self.turn == 1
+    }
+
+    /// The undrawn edge closest to the point, if it is close enough.
+    fn edge_at(&self, x: f32, y: f32) -> Option<Edge> {
+        let spacing = self.spacing();
+        self.board
+            .undrawn()
+            .into_iter()
+            .map(|edge| {
+                let (sx, sy) = self.dot(edge.x, edge.y);
+                let (mx, my) = if edge.horizontal {
+                    (sx + spacing / 2.0, sy)
+                } else {
+                    (sx, sy + spacing / 2.0)
+                };
+                (
+                    edge,
+                    ((x - mx).powi(2) + (y - my).powi(2)).sqrt(),
+                    (x - mx, y - my),
+                )
+            })
+            .filter(|(edge, _, (dx, dy))| {
+                // Stay within the length of the edge and close to it across its width.
+                let (along, across) = if edge.horizontal {
+                    (dx.abs(), dy.abs())
+                } else {
+                    (dy.abs(), dx.abs())
+                };
+                along < spacing / 2.0 && across < PICK_DISTANCE
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(edge, _, _)| edge)
+    }
+
+    fn play(&mut self, edge: Edge) {
+        let completed = self.board.draw_edge(edge, self.turn);
+        self.scores[self.turn] += completed;
+
+        // Completing a box earns another go.
+        if completed == 0 {
+            self.turn = 1 - self.turn;
+        }
+        self.ai_timer = AI_DELAY;
+
+        if self.board.is_full() {
+            self.phase = Phase::GameOver;
+            self.record_game();
+        }
+    }
+
+    /// Records a finished game from the first player's side.
+    fn record_game(&self) {
+        let won = self.scores[0] > self.scores[1];
+        let values = [
+            ("won", if won { 1.0 } else { 0.0 }),
+            ("boxes", f64::from(self.scores[0])),
+            ("computer", if self.versus_ai { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("dotsboxes", &values);
+        }
+        let _ = achievements::check_run("dotsboxes", &values);
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn player_name(&self, player: usize) -> &'static str {
+        match (player, self.versus_ai) {
+            (0, _) => "Player 1",
+            (_, true) => "Computer",
+            _ => "Player 2",
+        }
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if self.phase == Phase::Playing && self.ai_turn() {
+            self.ai_timer -= dt;
+            if self.ai_timer <= 0.0 {
+                if let Some(edge) = self.board.ai_move() {
+                    self.play(edge);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.95, 0.95, 0.9, 1.0].into());
+
+        if self.phase == Phase::Menu {
+            self.draw_text(
+                ctx,
+                "DOTS AND BOXES",
+                [0.1, 0.1, 0.1, 1.0],
+                48.0,
+                140.0,
+                160.0,
+            )?;
+            self.draw_text(
+                ctx,
+                &format!(
+                    "Board size: < {}x{} >\n\n1: Hot-seat\n2: Play the computer",
+                    self.size, self.size
+                ),
+                [0.2, 0.2, 0.2, 1.0],
+                32.0,
+                160.0,
+                260.0,
+            )?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        let spacing = self.spacing();
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y, owner) in self.board.owners.iter() {
+            if let Some(owner) = owner {
+                let (sx, sy) = self.dot(x, y);
+                let [r, g, b, _] = PLAYER_COLORS[*owner];
+                builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(sx, sy, spacing, spacing),
+                    [r, g, b, 0.4].into(),
+                );
+            }
+        }
+
+        let mut edges: Vec<(Edge, [f32; 4])> = Vec::new();
+        for (x, y, drawn) in self.board.horizontal.iter() {
+            if *drawn {
+                edges.push((
+                    Edge {
+                        horizontal: true,
+                        x,
+                        y,
+                    },
+                    [0.1, 0.1, 0.1, 1.0],
+                ));
+            }
+        }
+        for (x, y, drawn) in self.board.vertical.iter() {
+            if *drawn {
+                edges.push((
+                    Edge {
+                        horizontal: false,
+                        x,
+                        y,
+                    },
+                    [0.1, 0.1, 0.1, 1.0],
+                ));
+            }
+        }
+        if let Some(edge) = self.hovered {
+            if self.phase == Phase::Playing && !self.ai_turn() {
+                let [r, g, b, _] = PLAYER_COLORS[self.turn];
+                edges.push((edge, [r, g, b, 0.7]));
+            }
+        }
+        for (edge, color) in edges {
+            let (sx, sy) = self.dot(edge.x, edge.y);
+            let (ex, ey) = if edge.horizontal {
+                (sx + spacing, sy)
+            } else {
+                (sx, sy + spacing)
+            };
+            builder.line(
+                &[
+                    ggez::mint::Point2 { x: sx, y: sy },
+                    ggez::mint::Point2 { x: ex, y: ey },
+                ],
+                4.0,
+                color.into(),
+            )?;
+        }
+
+        for y in 0..=self.size as i32 {
+            for x in 0..=self.size as i32 {
+                let (sx, sy) = self.dot(x, y);
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 { x: sx, y: sy },
+                    6.0,
+                    0.5,
+                    [0.1, 0.1, 0.1, 1.0].into(),
+                );
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for player in 0..2 {
+            let marker = if self.phase == Phase::Playing && self.turn == player {
+                "> "
+            } else {
+                ""
+            };
+            self.draw_text(
+                ctx,
+                &format!(
+                    "{}{}: {}",
+                    marker,
+                    self.player_name(player),
+                    self.scores[player]
+                ),
+                PLAYER_COLORS[player],
+                28.0,
+                20.0 + player as f32 * 320.0,
+                16.0,
+            )?;
+        }
+
+        if self.phase == Phase::GameOver {
+            let result = if self.scores[0] == self.scores[1] {
+                "It's a draw!".to_string()
+            } else {
+                let winner = if self.scores[0] > self.scores[1] {
+                    0
+                } else {
+                    1
+                };
+                format!("{} wins!", self.player_name(winner))
+            };
+            self.draw_text(
+                ctx,
+                &format!("{} Press Enter.", result),
+                [0.1, 0.1, 0.1, 1.0],
+                32.0,
+                140.0,
+                SCREEN_SIZE.1 - 40.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if self.phase == Phase::Playing {
+            self.hovered = self.edge_at(x, y);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left || self.phase != Phase::Playing || self.ai_turn() {
+            return;
+        }
+        if let Some(edge) = self.edge_at(x, y) {
+            self.play(edge);
+            self.hovered = None;
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Left if self.phase == Phase::Menu => {
+                self.size = (self.size - 1).max(MIN_SIZE);
+            }
+            KeyCode::Right if self.phase == Phase::Menu => {
+                self.size = (self.size + 1).min(MAX_SIZE);
+            }
+            KeyCode::Key1 if self.phase == Phase::Menu => self.start(false),
+            KeyCode::Key2 if self.phase == Phase::Menu => self.start(true),
+            KeyCode::Return if self.phase == Phase::GameOver => self.phase = Phase::Menu,
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("dotsboxes", "Sprial404")
+        .window_setup(window::setup("Dots and Boxes"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,226 @@
+//! Packs a game's assets into its executable, so it can be handed out as a single file and never
+//! finds its sprites, sounds or levels missing.
+//!
+//! A game's build script calls `assets`, which zips everything in its crate's `assets` folder
+//! into cargo's output folder and has the game built again whenever any of it changes. The game
+//! includes the zip with `common::embedded_assets!` and hands it to `common::assets::add_to`.
+//!
+//! The files are stored in the zip as they are, without compressing them: most assets already
+//! are, and ggez can read stored files straight out of the executable.
+
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The name of the zip in cargo's output folder.
+pub const ZIP_NAME: &str = "assets.zip";
+
+/// The date every file is given, the first day zips can hold. Leaving the real times out means
+/// the executable is the same however the assets were checked out.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Zips the crate's `assets` folder for the game to embed. It's for build scripts, and panics
+/// if the assets can't be read, which fails the build.
+pub fn assets() {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("not run by cargo"));
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("not run by cargo"));
+    let dir = manifest_dir.join("assets");
+
+    // Watching the folder notices files being added and removed, and each file being changed.
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let files = list(&dir).unwrap_or_else(|e| panic!("Couldn't list the assets: {}", e));
+    for path in &files {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+    let zip = zip_files(&dir, &files).unwrap_or_else(|e| panic!("Couldn't zip the assets: {}", e));
+    fs::write(out_dir.join(ZIP_NAME), zip)
+        .unwrap_or_else(|e| panic!("Couldn't write the assets: {}", e));
+}
+
+/// Every file under `dir`, sorted so the zip comes out the same each time. A folder that
+/// doesn't exist has no files, for games without any assets.
+pub fn list(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(files),
+        Err(e) => return Err(e),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Zips `files`, naming each by its path from `dir`.
+fn zip_files(dir: &Path, files: &[PathBuf]) -> io::Result<Vec<u8>> {
+    let mut entries = Vec::new();
+    for path in files {
+        let name = path
+            .strip_prefix(dir)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "a file outside the assets"))?
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        entries.push((name, fs::read(path)?));
+    }
+    zip(&entries)
+}
+
+/// A zip holding each of `files`, as names like `levels/01.txt` and their contents.
+pub fn zip(files: &[(String, Vec<u8>)]) -> io::Result<Vec<u8>> {
+    let too_big = || io::Error::new(io::ErrorKind::InvalidInput, "too much to zip");
+    if files.len() > u16::MAX as usize {
+        return Err(too_big());
+    }
+
+    let mut zip = Vec::new();
+    let mut directory = Vec::new();
+    for (name, contents) in files {
+        let offset = u32::try_from(zip.len()).map_err(|_| too_big())?;
+        let size = u32::try_from(contents.len()).map_err(|_| too_big())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| too_big())?;
+        let crc = crc32(contents);
+
+        // The local header, then the file.
+        put_u32(&mut zip, 0x0403_4b50);
+        put_common(&mut zip, crc, size, name_len);
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(contents);
+
+        // Its entry in the central directory at the end.
+        put_u32(&mut directory, 0x0201_4b50);
+        put_u16(&mut directory, 20);
+        put_common(&mut directory, crc, size, name_len);
+        // The comment's length, the disk, and the internal and external attributes.
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u16(&mut directory, 0);
+        put_u32(&mut directory, 0);
+        put_u32(&mut directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = u32::try_from(zip.len()).map_err(|_| too_big())?;
+    let directory_size = u32::try_from(directory.len()).map_err(|_| too_big())?;
+    zip.extend_from_slice(&directory);
+    put_u32(&mut zip, 0x0605_4b50);
+    put_u16(&mut zip, 0);
+    put_u16(&mut zip, 0);
+    put_u16(&mut zip, files.len() as u16);
+    put_u16(&mut zip, files.len() as u16);
+    put_u32(&mut zip, directory_size);
+    put_u32(&mut zip, directory_offset);
+    put_u16(&mut zip, 0);
+    Ok(zip)
+}
+
+/// The fields a file's local header and central directory entry share, from the version
+/// needed to read it to the length of its extra field.
+fn put_common(out: &mut Vec<u8>, crc: u32, size: u32, name_len: u16) {
+    put_u16(out, 10);
+    // The name is UTF-8.
+    put_u16(out, 1 << 11);
+    // Stored, without compressing.
+    put_u16(out, 0);
+    put_u16(out, 0);
+    put_u16(out, DOS_DATE);
+    put_u32(out, crc);
+    put_u32(out, size);
+    put_u32(out, size);
+    put_u16(out, name_len);
+    put_u16(out, 0);
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+/// The CRC-32 zips check each file with.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+    }
+
+    #[test]
+    fn crc_matches_the_standard_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn empty_zips_are_just_the_end_record() {
+        let zip = zip(&[]).unwrap();
+        assert_eq!(zip.len(), 22);
+        assert_eq!(u32_at(&zip, 0), 0x0605_4b50);
+    }
+
+    #[test]
+    fn files_are_stored_whole_and_listed_at_the_end() {
+        let files = vec![
+            ("levels/01.txt".to_string(), b"#..#".to_vec()),
+            ("music.ogg".to_string(), vec![0, 1, 2]),
+        ];
+        let zip = zip(&files).unwrap();
+
+        // The first file's local header and contents.
+        assert_eq!(u32_at(&zip, 0), 0x0403_4b50);
+        assert_eq!(u32_at(&zip, 14), crc32(b"#..#"));
+        assert_eq!(u32_at(&zip, 18), 4);
+        assert_eq!(&zip[30..43], b"levels/01.txt");
+        assert_eq!(&zip[43..47], b"#..#");
+
+        // The end record points back at the central directory, which points at each file.
+        let end = zip.len() - 22;
+        assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+        assert_eq!(u16_at(&zip, end + 10), 2);
+        let directory = u32_at(&zip, end + 16) as usize;
+        assert_eq!(u32_at(&zip, directory), 0x0201_4b50);
+        assert_eq!(u32_at(&zip, directory + 42), 0);
+        let second = directory + 46 + "levels/01.txt".len();
+        assert_eq!(u32_at(&zip, second), 0x0201_4b50);
+        let offset = u32_at(&zip, second + 42) as usize;
+        assert_eq!(&zip[offset + 30..offset + 39], b"music.ogg");
+        assert_eq!(&zip[offset + 39..offset + 42], &[0, 1, 2]);
+    }
+
+    #[test]
+    fn missing_folders_have_no_files() {
+        assert!(list(Path::new("no such assets")).unwrap().is_empty());
+    }
+}
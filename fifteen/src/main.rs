@@ -0,0 +1,449 @@
+mod puzzle;
+mod solver;
+
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, DrawParam, Image, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::path::Path;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets::{self, Watcher};
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+use crate::puzzle::{Puzzle, SIZE};
+use crate::solver::Solver;
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 600.0);
+
+const TILE_SIZE: f32 = 100.0;
+const TILE_GAP: f32 = 4.0;
+const BOARD_ORIGIN: (f32, f32) = (40.0, 40.0);
+
+/// The picture used for picture mode, if there is one in the assets directory.
+const PICTURE_PATH: &str = "/picture.png";
+/// The size of the picture drawn when there isn't one to load.
+const GENERATED_PICTURE_SIZE: u16 = 400;
+
+/// The hint solver tries these weights in turn, trading the shortest solution for speed when
+/// the puzzle is too far from solved to search quickly.
+const HINT_WEIGHTS: [u32; 3] = [1, 2, 4];
+const HINT_NODE_LIMIT: u64 = 1_000_000;
+
+/// The state for the game.
+struct GameState {
+    puzzle: Puzzle,
+    moves: u32,
+    /// Seconds since the first move.
+    elapsed: f32,
+    started: bool,
+    solved: bool,
+    picture_mode: bool,
+    picture: Image,
+    /// The tiles left to slide for the last hint, while the player keeps following it.
+    solution: Vec<usize>,
+    /// The tile the last hint pointed at.
+    hint: Option<usize>,
+    hints_used: u32,
+    message: String,
+    stats: Option<StatsDb>,
+    /// Notices the picture being edited, in debug builds.
+    watcher: Watcher,
+}
+
+impl GameState {
+    /// Creates a new game state with a freshly scrambled board.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let picture = load_picture(ctx)?;
+
+        let mut state = GameState {
+            puzzle: Puzzle::solved(),
+            moves: 0,
+            elapsed: 0.0,
+            started: false,
+            solved: false,
+            picture_mode: false,
+            picture,
+            solution: Vec::new(),
+            hint: None,
+            hints_used: 0,
+            message: String::new(),
+            stats: StatsDb::open().ok(),
+            watcher: Watcher::new(assets::dir()),
+        };
+        state.scramble();
+        Ok(state)
+    }
+
+    fn scramble(&mut self) {
+        self.puzzle = Puzzle::scrambled(&mut rand::thread_rng());
+        self.moves = 0;
+        self.elapsed = 0.0;
+        self.started = false;
+        self.solved = false;
+        self.solution.clear();
+        self.hint = None;
+        self.hints_used = 0;
+        self.message.clear();
+    }
+
+    fn tile_rect(cell: usize) -> Rect {
+        Rect::new(
+            BOARD_ORIGIN.0 + (cell % SIZE) as f32 * TILE_SIZE,
+            BOARD_ORIGIN.1 + (cell / SIZE) as f32 * TILE_SIZE,
+            TILE_SIZE - TILE_GAP,
+            TILE_SIZE - TILE_GAP,
+        )
+    }
+
+    fn cell_at(x: f32, y: f32) -> Option<usize> {
+        let col = ((x - BOARD_ORIGIN.0) / TILE_SIZE).floor();
+        let row = ((y - BOARD_ORIGIN.1) / TILE_SIZE).floor();
+        if col >= 0.0 && row >= 0.0 && (col as usize) < SIZE && (row as usize) < SIZE {
+            Some(row as usize * SIZE + col as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Slides the tiles from `cell` towards the gap.
+    fn slide(&mut self, cell: usize) {
+        if self.solved {
+            return;
+        }
+
+        let moved = self.puzzle.slide_line(cell);
+        for &slid in moved.iter() {
+            // Keep the hint's solution while the player follows it.
+            if self.solution.first() == Some(&slid) {
+                self.solution.remove(0);
+            } else {
+                self.solution.clear();
+            }
+        }
+        if moved.is_empty() {
+            return;
+        }
+
+        self.hint = None;
+        self.message.clear();
+        self.started = true;
+        self.moves += moved.len() as u32;
+
+        if self.puzzle.is_solved() {
+            self.solved = true;
+            let values = [
+                ("moves", self.moves as f64),
+                ("seconds", self.elapsed as f64),
+                ("hints", self.hints_used as f64),
+            ];
+            if let Some(stats) = &self.stats {
+                let _ = stats.record("fifteen", &values);
+            }
+            let _ = achievements::check_run("fifteen", &values);
+        }
+    }
+
+    /// Slides a tile in from beside the gap in a direction, for the arrow keys.
+    fn slide_from(&mut self, dx: i32, dy: i32) {
+        let blank = self.puzzle.blank();
+        let x = (blank % SIZE) as i32 + dx;
+        let y = (blank / SIZE) as i32 + dy;
+        if x >= 0 && y >= 0 && (x as usize) < SIZE && (y as usize) < SIZE {
+            self.slide(y as usize * SIZE + x as usize);
+        }
+    }
+
+    /// Points at the next tile to slide, solving the puzzle if the player has left the last
+    /// hint's path.
+    fn show_hint(&mut self) {
+        if self.solved {
+            return;
+        }
+
+        if self.solution.is_empty() {
+            let puzzle = self.puzzle;
+            self.solution = HINT_WEIGHTS
+                .iter()
+                .find_map(|&weight| {
+                    Solver {
+                        weight,
+                        node_limit: HINT_NODE_LIMIT,
+                    }
+                    .solve(&puzzle)
+                })
+                .unwrap_or_default();
+        }
+
+        self.hint = self.solution.first().cloned();
+        if self.hint.is_some() {
+            self.hints_used += 1;
+            self.message = format!("{} moves to go.", self.solution.len());
+        } else {
+            self.message = "Couldn't find a solution in time.".to_string();
+        }
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_board(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                BOARD_ORIGIN.0 - TILE_GAP,
+                BOARD_ORIGIN.1 - TILE_GAP,
+                TILE_SIZE * SIZE as f32 + TILE_GAP,
+                TILE_SIZE * SIZE as f32 + TILE_GAP,
+            ),
+            [0.2, 0.2, 0.25, 1.0].into(),
+        );
+        for (cell, &tile) in self.puzzle.tiles().iter().enumerate() {
+            if tile == 0 {
+                continue;
+            }
+            let color = if self.hint == Some(cell) {
+                [0.95, 0.75, 0.3, 1.0]
+            } else if self.solved {
+                [0.4, 0.75, 0.45, 1.0]
+            } else {
+                [0.75, 0.55, 0.35, 1.0]
+            };
+            builder.rectangle(DrawMode::fill(), GameState::tile_rect(cell), color.into());
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for (cell, &tile) in self.puzzle.tiles().iter().enumerate() {
+            if tile == 0 {
+                continue;
+            }
+            let rect = GameState::tile_rect(cell);
+
+            if self.picture_mode {
+                self.draw_picture_tile(ctx, tile, rect)?;
+                // A small number in the corner keeps the picture solvable when it is plain.
+                self.draw_text(
+                    ctx,
+                    &tile.to_string(),
+                    [1.0, 1.0, 1.0, 0.8],
+                    18.0,
+                    rect.x + 4.0,
+                    rect.y + 2.0,
+                )?;
+            } else {
+                let label = tile.to_string();
+                let x = rect.x + rect.w / 2.0 - 11.0 * label.len() as f32;
+                self.draw_text(ctx, &label, [0.1, 0.1, 0.1, 1.0], 44.0, x, rect.y + 28.0)?;
+            }
+        }
+
+        // Outline the hinted tile on top of the picture too.
+        if let Some(cell) = self.hint {
+            let outline = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::stroke(4.0),
+                GameState::tile_rect(cell),
+                [1.0, 0.85, 0.2, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &outline, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+        Ok(())
+    }
+
+    /// Draws the part of the picture that belongs where `tile` goes in the solved puzzle.
+    fn draw_picture_tile(&self, ctx: &mut Context, tile: u8, rect: Rect) -> GameResult<()> {
+        let home = tile as usize - 1;
+        let part = 1.0 / SIZE as f32;
+        let src = Rect::new(
+            (home % SIZE) as f32 * part,
+            (home / SIZE) as f32 * part,
+            part,
+            part,
+        );
+        let scale = ggez::mint::Vector2 {
+            x: rect.w / (self.picture.width() as f32 * part),
+            y: rect.h / (self.picture.height() as f32 * part),
+        };
+        graphics::draw(
+            ctx,
+            &self.picture,
+            DrawParam::new()
+                .src(src)
+                .dest(ggez::mint::Point2 {
+                    x: rect.x,
+                    y: rect.y,
+                })
+                .scale(scale),
+        )
+    }
+}
+
+/// Loads the picture from the assets, or paints one if there isn't one.
+fn load_picture(ctx: &mut Context) -> GameResult<Image> {
+    match Image::new(ctx, PICTURE_PATH) {
+        Ok(image) => Ok(image),
+        Err(_) => generate_picture(ctx),
+    }
+}
+
+/// Paints a sunset over the sea, for picture mode when there is no picture to load.
+fn generate_picture(ctx: &mut Context) -> GameResult<Image> {
+    let size = GENERATED_PICTURE_SIZE as usize;
+    let horizon = size as f32 * 0.6;
+    let sun = (size as f32 * 0.68, size as f32 * 0.42, size as f32 * 0.13);
+    let mut pixels = Vec::with_capacity(size * size * 4);
+
+    for y in 0..size {
+        for x in 0..size {
+            let (fx, fy) = (x as f32, y as f32);
+            let sky = fy / horizon;
+            let mut color = if fy < horizon {
+                [0.25 + 0.7 * sky, 0.3 + 0.3 * sky, 0.6 - 0.3 * sky]
+            } else {
+                let depth = (fy - horizon) / (size as f32 - horizon);
+                [0.1, 0.2 + 0.1 * (1.0 - depth), 0.45 - 0.2 * depth]
+            };
+
+            let (dx, dy) = (fx - sun.0, fy - sun.1);
+            if fy < horizon && dx * dx + dy * dy < sun.2 * sun.2 {
+                color = [1.0, 0.85, 0.35];
+            }
+            // The sun's reflection breaks up into ripples on the water.
+            let reflection = sun.2 * (1.0 - (fy - horizon) / size as f32);
+            if fy > horizon && dx.abs() < reflection && (y / 6) % 2 == 0 {
+                color = [0.95, 0.7, 0.3];
+            }
+            // Hills on the left, drawn in front of the sky.
+            let hill = horizon - 90.0 * (1.0 - (fx / (size as f32 * 0.55))).max(0.0)
+                + 12.0 * (fx / 23.0).sin();
+            if fy < horizon && fy > hill {
+                color = [0.2, 0.15, 0.3];
+            }
+
+            for channel in color.iter() {
+                pixels.push((channel.max(0.0).min(1.0) * 255.0) as u8);
+            }
+            pixels.push(255);
+        }
+    }
+
+    Image::from_rgba8(ctx, GENERATED_PICTURE_SIZE, GENERATED_PICTURE_SIZE, &pixels)
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.started && !self.solved {
+            self.elapsed += timer::delta(ctx).as_secs_f32();
+        }
+
+        if self
+            .watcher
+            .changed()
+            .iter()
+            .any(|path| path == Path::new(PICTURE_PATH))
+        {
+            self.picture = load_picture(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.1, 0.12, 1.0].into());
+
+        self.draw_board(ctx)?;
+
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let top = BOARD_ORIGIN.1 + TILE_SIZE * SIZE as f32 + 16.0;
+        let seconds = self.elapsed as u64;
+        self.draw_text(
+            ctx,
+            &format!("Moves: {}  Time: {}", self.moves, locale::duration(seconds)),
+            white,
+            26.0,
+            BOARD_ORIGIN.0,
+            top,
+        )?;
+
+        let status = if self.solved {
+            "Solved! N: new puzzle".to_string()
+        } else if !self.message.is_empty() {
+            self.message.clone()
+        } else {
+            "H: hint  P: picture  N: new puzzle".to_string()
+        };
+        self.draw_text(
+            ctx,
+            &status,
+            [1.0, 0.85, 0.4, 1.0],
+            22.0,
+            BOARD_ORIGIN.0,
+            top + 36.0,
+        )?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            if let Some(cell) = GameState::cell_at(x, y) {
+                self.slide(cell);
+            }
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            // The arrows move a tile in that direction, into the gap.
+            KeyCode::Up => self.slide_from(0, 1),
+            KeyCode::Down => self.slide_from(0, -1),
+            KeyCode::Left => self.slide_from(1, 0),
+            KeyCode::Right => self.slide_from(-1, 0),
+            KeyCode::H => self.show_hint(),
+            KeyCode::P => self.picture_mode = !self.picture_mode,
+            KeyCode::N => self.scramble(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("fifteen", "Sprial404")
+        .window_setup(window::setup("15 Puzzle"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "fifteen", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
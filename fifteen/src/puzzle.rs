@@ -0,0 +1,142 @@
+//! The sliding tile board and its rules.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// The width and height of the board in tiles.
+pub const SIZE: usize = 4;
+pub const CELLS: usize = SIZE * SIZE;
+
+/// A board of numbered tiles, zero being the gap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Puzzle {
+    tiles: [u8; CELLS],
+    /// Where the gap is, kept alongside the tiles so it doesn't have to be searched for.
+    blank: usize,
+}
+
+impl Puzzle {
+    /// Creates a solved board, with the tiles in order and the gap in the bottom right.
+    pub fn solved() -> Self {
+        let mut tiles = [0; CELLS];
+        for (i, tile) in tiles.iter_mut().enumerate().take(CELLS - 1) {
+            *tile = i as u8 + 1;
+        }
+        Puzzle {
+            tiles,
+            blank: CELLS - 1,
+        }
+    }
+
+    /// Creates a random board that can be solved.
+    ///
+    /// Half of all arrangements can't be solved, so a random shuffle that lands on one of those
+    /// has two tiles swapped, which flips it over to the solvable half.
+    pub fn scrambled<R: Rng>(rng: &mut R) -> Self {
+        let mut tiles = Puzzle::solved().tiles;
+        loop {
+            tiles.shuffle(rng);
+            let mut puzzle = Puzzle::from_tiles(tiles);
+            if !puzzle.is_solvable() {
+                let (a, b) = if puzzle.blank > 1 { (0, 1) } else { (2, 3) };
+                puzzle.tiles.swap(a, b);
+            }
+            if !puzzle.is_solved() {
+                return puzzle;
+            }
+        }
+    }
+
+    fn from_tiles(tiles: [u8; CELLS]) -> Self {
+        let blank = tiles
+            .iter()
+            .position(|&tile| tile == 0)
+            .unwrap_or(CELLS - 1);
+        Puzzle { tiles, blank }
+    }
+
+    pub fn tiles(&self) -> &[u8; CELLS] {
+        &self.tiles
+    }
+
+    pub fn blank(&self) -> usize {
+        self.blank
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Puzzle::solved()
+    }
+
+    /// Whether the tiles can be slid back into order.
+    ///
+    /// Sliding a tile along a row leaves the tiles in the same order, and sliding one between
+    /// rows moves it past the `SIZE - 1` tiles in between, changing the number of out of order
+    /// pairs by an odd amount as the gap changes row. On an even width board the inversions plus
+    /// the gap's row counted from the bottom are odd when solved, so they must be odd to begin
+    /// with.
+    pub fn is_solvable(&self) -> bool {
+        let numbers: Vec<u8> = self
+            .tiles
+            .iter()
+            .cloned()
+            .filter(|&tile| tile != 0)
+            .collect();
+        let inversions = (0..numbers.len())
+            .flat_map(|i| (i + 1..numbers.len()).map(move |j| (i, j)))
+            .filter(|&(i, j)| numbers[i] > numbers[j])
+            .count();
+        let row_from_bottom = SIZE - self.blank / SIZE;
+        if SIZE % 2 == 1 {
+            inversions % 2 == 0
+        } else {
+            (inversions + row_from_bottom) % 2 == 1
+        }
+    }
+
+    /// The cells next to the gap, whose tiles can slide into it.
+    pub fn neighbours(&self) -> impl Iterator<Item = usize> {
+        let (x, y) = ((self.blank % SIZE) as i32, (self.blank / SIZE) as i32);
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .iter()
+            .map(move |(dx, dy)| (x + dx, y + dy))
+            .filter(|&(x, y)| x >= 0 && y >= 0 && (x as usize) < SIZE && (y as usize) < SIZE)
+            .map(|(x, y)| y as usize * SIZE + x as usize)
+    }
+
+    /// Slides the tile at `cell` into the gap if it is next to it.
+    pub fn slide(&mut self, cell: usize) -> bool {
+        if !self.neighbours().any(|neighbour| neighbour == cell) {
+            return false;
+        }
+        self.tiles.swap(cell, self.blank);
+        self.blank = cell;
+        true
+    }
+
+    /// Slides every tile between `cell` and the gap towards the gap, if they are in a line.
+    /// Returns the cells the gap moved through, one for each tile that slid.
+    pub fn slide_line(&mut self, cell: usize) -> Vec<usize> {
+        let (x, y) = (cell % SIZE, cell / SIZE);
+        let (bx, by) = (self.blank % SIZE, self.blank / SIZE);
+        let mut moved = Vec::new();
+        if cell == self.blank || (x != bx && y != by) {
+            return moved;
+        }
+
+        while self.blank != cell {
+            let (bx, by) = (self.blank % SIZE, self.blank / SIZE);
+            let next = if x < bx {
+                self.blank - 1
+            } else if x > bx {
+                self.blank + 1
+            } else if y < by {
+                self.blank - SIZE
+            } else {
+                self.blank + SIZE
+            };
+            self.slide(next);
+            moved.push(next);
+        }
+        moved
+    }
+}
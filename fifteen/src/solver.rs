@@ -0,0 +1,187 @@
+//! An iterative deepening A* (IDA*) solver for the hint button.
+
+use crate::puzzle::{Puzzle, CELLS, SIZE};
+
+/// How the search trades the length of the solution for speed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Solver {
+    /// How much the distance estimate is trusted. 1 finds the shortest solution, and anything
+    /// higher finds a solution much faster that may take a few more moves.
+    pub weight: u32,
+    /// The positions to look at before giving up.
+    pub node_limit: u64,
+}
+
+/// The result of a bounded depth first pass.
+enum Pass {
+    Found,
+    /// Nothing within the bound, with the smallest cost that went over it.
+    NotFound(u32),
+    OutOfNodes,
+}
+
+struct Search {
+    tiles: [u8; CELLS],
+    blank: usize,
+    /// The cells the gap moved to, which are the tiles to slide in order.
+    path: Vec<usize>,
+    nodes: u64,
+    solver: Solver,
+}
+
+impl Solver {
+    /// Finds the tiles to slide, in order, to solve the puzzle. Returns `None` if the puzzle
+    /// can't be solved or the search runs out of nodes first.
+    pub fn solve(&self, puzzle: &Puzzle) -> Option<Vec<usize>> {
+        if !puzzle.is_solvable() {
+            return None;
+        }
+
+        let mut search = Search {
+            tiles: *puzzle.tiles(),
+            blank: puzzle.blank(),
+            path: Vec::new(),
+            nodes: 0,
+            solver: *self,
+        };
+        let mut bound = estimate(&search.tiles) * self.weight;
+        loop {
+            match search.pass(0, bound, None) {
+                Pass::Found => return Some(search.path),
+                Pass::NotFound(next) => bound = next,
+                Pass::OutOfNodes => return None,
+            }
+        }
+    }
+}
+
+impl Search {
+    fn pass(&mut self, cost: u32, bound: u32, previous: Option<usize>) -> Pass {
+        self.nodes += 1;
+        if self.nodes > self.solver.node_limit {
+            return Pass::OutOfNodes;
+        }
+
+        let h = estimate(&self.tiles);
+        let f = cost + h * self.solver.weight;
+        if f > bound {
+            return Pass::NotFound(f);
+        }
+        if h == 0 {
+            return Pass::Found;
+        }
+
+        let mut smallest = u32::max_value();
+        let blank = self.blank;
+        for next in neighbours(blank) {
+            // Sliding the same tile straight back only undoes the last move.
+            if Some(next) == previous {
+                continue;
+            }
+
+            self.tiles.swap(blank, next);
+            self.blank = next;
+            self.path.push(next);
+
+            match self.pass(cost + 1, bound, Some(blank)) {
+                Pass::Found => return Pass::Found,
+                Pass::OutOfNodes => return Pass::OutOfNodes,
+                Pass::NotFound(over) => smallest = smallest.min(over),
+            }
+
+            self.path.pop();
+            self.blank = blank;
+            self.tiles.swap(blank, next);
+        }
+        Pass::NotFound(smallest)
+    }
+}
+
+fn neighbours(cell: usize) -> Vec<usize> {
+    let (x, y) = (cell % SIZE, cell / SIZE);
+    let mut cells = Vec::with_capacity(4);
+    if y > 0 {
+        cells.push(cell - SIZE);
+    }
+    if x + 1 < SIZE {
+        cells.push(cell + 1);
+    }
+    if y + 1 < SIZE {
+        cells.push(cell + SIZE);
+    }
+    if x > 0 {
+        cells.push(cell - 1);
+    }
+    cells
+}
+
+/// The cell a tile belongs in.
+fn goal(tile: u8) -> usize {
+    tile as usize - 1
+}
+
+/// A lower bound on the moves left: each tile's Manhattan distance from home, plus two moves
+/// for every tile that has to step out of its home row or column to let another past.
+fn estimate(tiles: &[u8; CELLS]) -> u32 {
+    let mut total = 0;
+    for (cell, &tile) in tiles.iter().enumerate() {
+        if tile == 0 {
+            continue;
+        }
+        let home = goal(tile);
+        let dx = (cell % SIZE) as i32 - (home % SIZE) as i32;
+        let dy = (cell / SIZE) as i32 - (home / SIZE) as i32;
+        total += (dx.abs() + dy.abs()) as u32;
+    }
+
+    for line in 0..SIZE {
+        // The homes along the line, in order, of the tiles already in their home line.
+        let mut row = [0; SIZE];
+        let mut row_len = 0;
+        let mut column = [0; SIZE];
+        let mut column_len = 0;
+        for i in 0..SIZE {
+            let tile = tiles[line * SIZE + i];
+            if tile != 0 && goal(tile) / SIZE == line {
+                row[row_len] = goal(tile) % SIZE;
+                row_len += 1;
+            }
+            let tile = tiles[i * SIZE + line];
+            if tile != 0 && goal(tile) % SIZE == line {
+                column[column_len] = goal(tile) / SIZE;
+                column_len += 1;
+            }
+        }
+        total += 2 * (conflicts(&row[..row_len]) + conflicts(&column[..column_len]));
+    }
+    total
+}
+
+/// The fewest tiles that have to leave a line so the rest are in order, given the homes of the
+/// tiles in the line in their current order.
+fn conflicts(homes: &[usize]) -> u32 {
+    if homes.len() < 2 {
+        return 0;
+    }
+    let mut homes = homes.to_vec();
+    let mut removed = 0;
+    loop {
+        // Take out whichever tile is in the way of the most others, until none are.
+        let worst = (0..homes.len())
+            .map(|i| {
+                let count = (0..homes.len())
+                    .filter(|&j| (i < j && homes[i] > homes[j]) || (j < i && homes[j] > homes[i]))
+                    .count();
+                (i, count)
+            })
+            .max_by_key(|&(_, count)| count)
+            .filter(|&(_, count)| count > 0);
+        match worst {
+            Some((i, _)) => {
+                homes.remove(i);
+                removed += 1;
+            }
+            None => return removed,
+        }
+    }
+}
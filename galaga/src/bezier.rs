@@ -0,0 +1,68 @@
+//! Cubic bezier curves the enemies fly along.
+
+use common::physics::Vec2;
+
+/// A cubic bezier curve.
+#[derive(Debug, Copy, Clone)]
+pub struct Bezier {
+    pub p0: Vec2,
+    pub p1: Vec2,
+    pub p2: Vec2,
+    pub p3: Vec2,
+}
+
+impl Bezier {
+    pub fn new(p0: Vec2, p1: Vec2, p2: Vec2, p3: Vec2) -> Self {
+        Bezier { p0, p1, p2, p3 }
+    }
+
+    /// Returns the point on the curve at `t`, from `0.0` at the start to `1.0` at the end.
+    pub fn point(&self, t: f32) -> Vec2 {
+        let u = 1.0 - t;
+        self.p0 * (u * u * u)
+            + self.p1 * (3.0 * u * u * t)
+            + self.p2 * (3.0 * u * t * t)
+            + self.p3 * (t * t * t)
+    }
+
+    /// Approximates the length of the curve by sampling it.
+    pub fn length(&self) -> f32 {
+        const SAMPLES: usize = 16;
+        let mut length = 0.0;
+        let mut prev = self.p0;
+        for i in 1..=SAMPLES {
+            let point = self.point(i as f32 / SAMPLES as f32);
+            length += (point - prev).length();
+            prev = point;
+        }
+        length
+    }
+}
+
+/// Follows a bezier curve at a constant speed.
+#[derive(Debug, Copy, Clone)]
+pub struct PathFollower {
+    pub curve: Bezier,
+    pub t: f32,
+    length: f32,
+}
+
+impl PathFollower {
+    pub fn new(curve: Bezier) -> Self {
+        PathFollower {
+            curve,
+            t: 0.0,
+            length: curve.length().max(1.0),
+        }
+    }
+
+    /// Advances along the curve by `speed * dt` pixels, returning the new position.
+    pub fn advance(&mut self, speed: f32, dt: f32) -> Vec2 {
+        self.t = (self.t + speed * dt / self.length).min(1.0);
+        self.curve.point(self.t)
+    }
+
+    pub fn done(&self) -> bool {
+        self.t >= 1.0
+    }
+}
@@ -0,0 +1,723 @@
+mod bezier;
+
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+use crate::bezier::{Bezier, PathFollower};
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 720.0);
+
+const PLAYER_Y: f32 = SCREEN_SIZE.1 - 60.0;
+const PLAYER_SPEED: f32 = 300.0;
+const SHIP_SIZE: f32 = 24.0;
+/// The horizontal offset of the rescued second ship in dual-fighter mode.
+const DUAL_OFFSET: f32 = SHIP_SIZE + 4.0;
+const MAX_PLAYER_SHOTS: usize = 2;
+const SHOT_SPEED: f32 = 600.0;
+const ENEMY_SHOT_SPEED: f32 = 260.0;
+const RESPAWN_TIME: f32 = 2.0;
+const LIVES: u32 = 3;
+
+const ENEMY_SIZE: f32 = 22.0;
+const FLY_SPEED: f32 = 260.0;
+const FORMATION_ORIGIN: (f32, f32) = (60.0, 90.0);
+const FORMATION_SPACING: (f32, f32) = (40.0, 36.0);
+const FORMATION_SWAY: f32 = 20.0;
+/// Seconds between enemies being sent in along an entry path.
+const ENTRY_INTERVAL: f32 = 0.15;
+const START_DIVE_INTERVAL: f32 = 2.5;
+const MIN_DIVE_INTERVAL: f32 = 0.8;
+/// The chance a boss's dive is an attempt to capture the player's ship.
+const CAPTURE_CHANCE: f64 = 0.4;
+const BEAM_TIME: f32 = 3.0;
+const BEAM_Y: f32 = PLAYER_Y - 220.0;
+const BEAM_HALF_WIDTH: f32 = 36.0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum EnemyKind {
+    Bee,
+    Butterfly,
+    /// The boss takes two hits and can capture the player's ship with its tractor beam.
+    Boss,
+}
+
+impl EnemyKind {
+    fn hp(self) -> u32 {
+        match self {
+            EnemyKind::Boss => 2,
+            _ => 1,
+        }
+    }
+
+    /// The points for shooting the enemy in formation and while diving.
+    fn points(self, diving: bool) -> u32 {
+        let points = match self {
+            EnemyKind::Bee => 50,
+            EnemyKind::Butterfly => 80,
+            EnemyKind::Boss => 150,
+        };
+        if diving {
+            points * 2
+        } else {
+            points
+        }
+    }
+
+    fn color(self, hp: u32) -> [f32; 4] {
+        match (self, hp) {
+            (EnemyKind::Bee, _) => [1.0, 0.9, 0.2, 1.0],
+            (EnemyKind::Butterfly, _) => [0.9, 0.2, 0.3, 1.0],
+            (EnemyKind::Boss, 2) => [0.2, 0.8, 0.4, 1.0],
+            (EnemyKind::Boss, _) => [0.6, 0.3, 0.9, 1.0],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum EnemyState {
+    /// Waiting to be sent in, counting down the seconds left.
+    Waiting(f32),
+    /// Flying in along an entry path to its slot in the formation.
+    Entering(PathFollower),
+    Formation,
+    Diving(PathFollower),
+    /// Hovering above the player, projecting a tractor beam for the seconds left.
+    Beaming(f32),
+    /// Flying back to its slot from the top of the screen or after beaming.
+    Returning(PathFollower),
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Enemy {
+    kind: EnemyKind,
+    hp: u32,
+    slot: (i32, i32),
+    pos: Vec2,
+    state: EnemyState,
+    /// Whether this boss is holding the player's captured ship.
+    captive: bool,
+    shot_timer: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Shot {
+    pos: Vec2,
+    velocity: f32,
+}
+
+/// The position in the formation of the slot `(col, row)` at `time`.
+fn slot_pos(slot: (i32, i32), time: f32) -> Vec2 {
+    Vec2::new(
+        FORMATION_ORIGIN.0
+            + slot.0 as f32 * FORMATION_SPACING.0
+            + (time * 0.8).sin() * FORMATION_SWAY,
+        FORMATION_ORIGIN.1 + slot.1 as f32 * FORMATION_SPACING.1,
+    )
+}
+
+/// Builds the enemies of a wave, each flying in from alternating sides of the screen.
+fn build_wave() -> Vec<Enemy> {
+    let mut slots = Vec::new();
+    for col in 3..7 {
+        slots.push((EnemyKind::Boss, (col, 0)));
+    }
+    for row in 1..3 {
+        for col in 1..9 {
+            slots.push((EnemyKind::Butterfly, (col, row)));
+        }
+    }
+    for row in 3..5 {
+        for col in 0..10 {
+            slots.push((EnemyKind::Bee, (col, row)));
+        }
+    }
+
+    slots
+        .into_iter()
+        .enumerate()
+        .map(|(i, (kind, slot))| Enemy {
+            kind,
+            hp: kind.hp(),
+            slot,
+            pos: Vec2::new(-100.0, -100.0),
+            // Send enemies in groups of eight with a pause between each group.
+            state: EnemyState::Waiting(i as f32 * ENTRY_INTERVAL + (i / 8) as f32 * 1.5),
+            captive: false,
+            shot_timer: rand::thread_rng().gen_range(1.0, 4.0),
+        })
+        .collect()
+}
+
+/// An entry path from the top corner of the screen, looping round towards the formation.
+fn entry_path(from_left: bool, target: Vec2) -> Bezier {
+    let side = if from_left { -1.0 } else { 1.0 };
+    let start = Vec2::new(
+        SCREEN_SIZE.0 / 2.0 + side * (SCREEN_SIZE.0 / 2.0 + 20.0),
+        40.0,
+    );
+    Bezier::new(
+        start,
+        Vec2::new(SCREEN_SIZE.0 / 2.0 - side * 200.0, 500.0),
+        Vec2::new(SCREEN_SIZE.0 / 2.0 + side * 150.0, 420.0),
+        target,
+    )
+}
+
+/// A dive from the formation swooping down through the player's position.
+fn dive_path(from: Vec2, player_x: f32) -> Bezier {
+    let swing = if from.x < SCREEN_SIZE.0 / 2.0 {
+        -80.0
+    } else {
+        80.0
+    };
+    Bezier::new(
+        from,
+        Vec2::new(from.x + swing, from.y - 60.0),
+        Vec2::new(player_x - swing * 2.0, PLAYER_Y - 100.0),
+        Vec2::new(player_x, SCREEN_SIZE.1 + 40.0),
+    )
+}
+
+/// The state for the game.
+struct GameState {
+    player_x: f32,
+    /// Whether the player has rescued their captured ship and flies two side by side.
+    dual: bool,
+    /// Seconds until the player respawns after losing a ship.
+    respawn: Option<f32>,
+    lives: u32,
+    enemies: Vec<Enemy>,
+    shots: Vec<Shot>,
+    enemy_shots: Vec<Shot>,
+    time: f32,
+    dive_timer: f32,
+    wave: u32,
+    score: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the first wave.
+    pub fn new() -> Self {
+        GameState {
+            player_x: SCREEN_SIZE.0 / 2.0,
+            dual: false,
+            respawn: None,
+            lives: LIVES,
+            enemies: build_wave(),
+            shots: Vec::new(),
+            enemy_shots: Vec::new(),
+            time: 0.0,
+            dive_timer: START_DIVE_INTERVAL,
+            wave: 1,
+            score: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    /// The x coordinates of the player's ships.
+    fn ships(&self) -> Vec<f32> {
+        if self.respawn.is_some() {
+            Vec::new()
+        } else if self.dual {
+            vec![
+                self.player_x - DUAL_OFFSET / 2.0,
+                self.player_x + DUAL_OFFSET / 2.0,
+            ]
+        } else {
+            vec![self.player_x]
+        }
+    }
+
+    fn dive_interval(&self) -> f32 {
+        (START_DIVE_INTERVAL - self.wave as f32 * 0.2).max(MIN_DIVE_INTERVAL)
+    }
+
+    fn capture_in_progress(&self) -> bool {
+        self.enemies
+            .iter()
+            .any(|enemy| enemy.captive || matches_beaming(&enemy.state))
+    }
+
+    fn fire(&mut self) {
+        let ships = self.ships();
+        if ships.is_empty() || self.shots.len() >= MAX_PLAYER_SHOTS * ships.len() {
+            return;
+        }
+        for x in ships {
+            self.shots.push(Shot {
+                pos: Vec2::new(x, PLAYER_Y - SHIP_SIZE / 2.0),
+                velocity: -SHOT_SPEED,
+            });
+        }
+    }
+
+    /// Sends a random enemy from the formation diving at the player.
+    fn start_dive(&mut self) {
+        let mut rng = rand::thread_rng();
+        let candidates: Vec<usize> = (0..self.enemies.len())
+            .filter(|i| matches_formation(&self.enemies[*i].state))
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+
+        let capture_possible = !self.dual && self.respawn.is_none() && !self.capture_in_progress();
+        let i = candidates[rng.gen_range(0, candidates.len())];
+        let enemy = &mut self.enemies[i];
+
+        enemy.state = if enemy.kind == EnemyKind::Boss
+            && !enemy.captive
+            && capture_possible
+            && rng.gen_bool(CAPTURE_CHANCE)
+        {
+            let target = Vec2::new(self.player_x, BEAM_Y);
+            EnemyState::Diving(PathFollower::new(Bezier::new(
+                enemy.pos,
+                enemy.pos + Vec2::new(0.0, -60.0),
+                Vec2::new(target.x, enemy.pos.y + 100.0),
+                target,
+            )))
+        } else {
+            EnemyState::Diving(PathFollower::new(dive_path(enemy.pos, self.player_x)))
+        };
+    }
+
+    /// Loses one of the player's ships, or a life if they only had one.
+    fn player_hit(&mut self) {
+        if self.dual {
+            self.dual = false;
+            return;
+        }
+
+        self.lives -= 1;
+        if self.lives == 0 {
+            self.gameover = true;
+            self.record_run();
+        } else {
+            self.respawn = Some(RESPAWN_TIME);
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("wave", f64::from(self.wave)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("galaga", &values);
+        }
+        let _ = achievements::check_run("galaga", &values);
+    }
+
+    fn update_enemies(&mut self, dt: f32) {
+        let time = self.time;
+        let ships = self.ships();
+        let mut captured = false;
+        let mut rng = rand::thread_rng();
+        let mut new_shots = Vec::new();
+
+        for (i, enemy) in self.enemies.iter_mut().enumerate() {
+            let slot = slot_pos(enemy.slot, time);
+
+            enemy.state = match enemy.state {
+                EnemyState::Waiting(delay) if delay - dt <= 0.0 => {
+                    let from_left = (i / 8) % 2 == 0;
+                    let path = PathFollower::new(entry_path(from_left, slot));
+                    enemy.pos = path.curve.p0;
+                    EnemyState::Entering(path)
+                }
+                EnemyState::Waiting(delay) => EnemyState::Waiting(delay - dt),
+                EnemyState::Entering(mut path) | EnemyState::Returning(mut path) => {
+                    // Keep the end of the path on the swaying formation slot.
+                    path.curve.p3 = slot;
+                    enemy.pos = path.advance(FLY_SPEED, dt);
+                    if path.done() {
+                        EnemyState::Formation
+                    } else if let EnemyState::Entering(_) = enemy.state {
+                        EnemyState::Entering(path)
+                    } else {
+                        EnemyState::Returning(path)
+                    }
+                }
+                EnemyState::Formation => {
+                    enemy.pos = slot;
+                    EnemyState::Formation
+                }
+                EnemyState::Diving(mut path) => {
+                    enemy.pos = path.advance(FLY_SPEED * 1.3, dt);
+
+                    enemy.shot_timer -= dt;
+                    if enemy.shot_timer <= 0.0 && enemy.pos.y < PLAYER_Y - 100.0 {
+                        enemy.shot_timer = rng.gen_range(1.0, 3.0);
+                        new_shots.push(Shot {
+                            pos: enemy.pos,
+                            velocity: ENEMY_SHOT_SPEED,
+                        });
+                    }
+
+                    if !path.done() {
+                        EnemyState::Diving(path)
+                    } else if enemy.pos.y < SCREEN_SIZE.1 {
+                        // A capture dive ends hovering above the player.
+                        EnemyState::Beaming(BEAM_TIME)
+                    } else {
+                        // Reappear from the top of the screen and rejoin the formation.
+                        let top = Vec2::new(enemy.pos.x, -40.0);
+                        enemy.pos = top;
+                        EnemyState::Returning(PathFollower::new(Bezier::new(
+                            top,
+                            top + Vec2::new(0.0, 80.0),
+                            slot + Vec2::new(0.0, -80.0),
+                            slot,
+                        )))
+                    }
+                }
+                EnemyState::Beaming(left) => {
+                    // Anyone caught under the beam has their ship captured.
+                    if !captured
+                        && ships
+                            .iter()
+                            .any(|x| (x - enemy.pos.x).abs() < BEAM_HALF_WIDTH)
+                    {
+                        captured = true;
+                        enemy.captive = true;
+                    }
+
+                    if enemy.captive || left - dt <= 0.0 {
+                        EnemyState::Returning(PathFollower::new(Bezier::new(
+                            enemy.pos,
+                            enemy.pos + Vec2::new(0.0, -100.0),
+                            slot + Vec2::new(0.0, 100.0),
+                            slot,
+                        )))
+                    } else {
+                        EnemyState::Beaming(left - dt)
+                    }
+                }
+            };
+        }
+
+        self.enemy_shots.extend(new_shots);
+        if captured {
+            self.player_hit();
+        }
+    }
+
+    fn handle_hits(&mut self) {
+        let mut rescued = false;
+
+        for shot in self.shots.iter_mut() {
+            let hit = self.enemies.iter().position(|enemy| {
+                !matches_waiting(&enemy.state)
+                    && (enemy.pos - shot.pos).length() < ENEMY_SIZE / 2.0 + 3.0
+            });
+
+            if let Some(i) = hit {
+                // Move the shot off screen so it gets removed.
+                shot.pos.y = -100.0;
+
+                let enemy = &mut self.enemies[i];
+                enemy.hp -= 1;
+                if enemy.hp == 0 {
+                    let diving = !matches_formation(&enemy.state);
+                    self.score += enemy.kind.points(diving);
+                    // Shooting a boss mid-dive frees the ship it captured.
+                    if enemy.captive && diving {
+                        rescued = true;
+                    }
+                    self.enemies.remove(i);
+                }
+            }
+        }
+
+        if rescued && self.respawn.is_none() {
+            self.dual = true;
+        }
+
+        let ships = self.ships();
+        let mut hit = false;
+        for shot in self.enemy_shots.iter_mut() {
+            if ships.iter().any(|x| {
+                (shot.pos.x - x).abs() < SHIP_SIZE / 2.0
+                    && (shot.pos.y - PLAYER_Y).abs() < SHIP_SIZE / 2.0
+            }) {
+                shot.pos.y = SCREEN_SIZE.1 + 100.0;
+                hit = true;
+            }
+        }
+        let collided = self.enemies.iter().any(|enemy| {
+            ships.iter().any(|x| {
+                (enemy.pos.x - x).abs() < (SHIP_SIZE + ENEMY_SIZE) / 2.0
+                    && (enemy.pos.y - PLAYER_Y).abs() < (SHIP_SIZE + ENEMY_SIZE) / 2.0
+            })
+        });
+        if hit || collided {
+            self.player_hit();
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.time += dt;
+
+        if let Some(respawn) = self.respawn {
+            self.respawn = if respawn - dt <= 0.0 {
+                self.player_x = SCREEN_SIZE.0 / 2.0;
+                None
+            } else {
+                Some(respawn - dt)
+            };
+        }
+
+        let mut dir = 0.0;
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+            dir -= 1.0;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+            dir += 1.0;
+        }
+        self.player_x = (self.player_x + dir * PLAYER_SPEED * dt)
+            .max(SHIP_SIZE)
+            .min(SCREEN_SIZE.0 - SHIP_SIZE);
+
+        self.dive_timer -= dt;
+        if self.dive_timer <= 0.0 {
+            self.start_dive();
+            self.dive_timer = self.dive_interval();
+        }
+
+        self.update_enemies(dt);
+
+        for shot in self.shots.iter_mut().chain(self.enemy_shots.iter_mut()) {
+            shot.pos.y += shot.velocity * dt;
+        }
+        self.handle_hits();
+        self.shots.retain(|shot| shot.pos.y > 0.0);
+        self.enemy_shots.retain(|shot| shot.pos.y < SCREEN_SIZE.1);
+
+        if self.enemies.is_empty() {
+            self.wave += 1;
+            self.enemies = build_wave();
+            self.enemy_shots.clear();
+        }
+    }
+
+    fn draw_ship(&self, ctx: &mut Context, pos: Vec2, color: [f32; 4]) -> GameResult<()> {
+        let half = SHIP_SIZE / 2.0;
+        let ship = graphics::Mesh::new_polygon(
+            ctx,
+            DrawMode::fill(),
+            &[
+                ggez::mint::Point2 {
+                    x: pos.x,
+                    y: pos.y - half,
+                },
+                ggez::mint::Point2 {
+                    x: pos.x + half,
+                    y: pos.y + half,
+                },
+                ggez::mint::Point2 {
+                    x: pos.x - half,
+                    y: pos.y + half,
+                },
+            ],
+            color.into(),
+        )?;
+        graphics::draw(ctx, &ship, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_enemy(&self, ctx: &mut Context, enemy: &Enemy) -> GameResult<()> {
+        if let EnemyState::Beaming(_) = enemy.state {
+            let beam = graphics::Mesh::new_polygon(
+                ctx,
+                DrawMode::fill(),
+                &[
+                    ggez::mint::Point2 {
+                        x: enemy.pos.x - 6.0,
+                        y: enemy.pos.y,
+                    },
+                    ggez::mint::Point2 {
+                        x: enemy.pos.x + 6.0,
+                        y: enemy.pos.y,
+                    },
+                    ggez::mint::Point2 {
+                        x: enemy.pos.x + BEAM_HALF_WIDTH,
+                        y: PLAYER_Y + SHIP_SIZE,
+                    },
+                    ggez::mint::Point2 {
+                        x: enemy.pos.x - BEAM_HALF_WIDTH,
+                        y: PLAYER_Y + SHIP_SIZE,
+                    },
+                ],
+                [0.3, 0.5, 1.0, 0.35].into(),
+            )?;
+            graphics::draw(ctx, &beam, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let half = ENEMY_SIZE / 2.0;
+        let body = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                enemy.pos.x - half,
+                enemy.pos.y - half,
+                ENEMY_SIZE,
+                ENEMY_SIZE,
+            ),
+            enemy.kind.color(enemy.hp).into(),
+        )?;
+        graphics::draw(ctx, &body, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        if enemy.captive {
+            self.draw_ship(
+                ctx,
+                enemy.pos + Vec2::new(0.0, -ENEMY_SIZE),
+                [1.0, 0.2, 0.2, 1.0],
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn matches_formation(state: &EnemyState) -> bool {
+    if let EnemyState::Formation = state {
+        true
+    } else {
+        false
+    }
+}
+
+fn matches_waiting(state: &EnemyState) -> bool {
+    if let EnemyState::Waiting(_) = state {
+        true
+    } else {
+        false
+    }
+}
+
+fn matches_beaming(state: &EnemyState) -> bool {
+    if let EnemyState::Beaming(_) = state {
+        true
+    } else {
+        false
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.05, 1.0].into());
+
+        for enemy in self.enemies.iter() {
+            if !matches_waiting(&enemy.state) {
+                self.draw_enemy(ctx, enemy)?;
+            }
+        }
+
+        for x in self.ships() {
+            self.draw_ship(ctx, Vec2::new(x, PLAYER_Y), [1.0, 1.0, 1.0, 1.0])?;
+        }
+
+        for (shot, color) in self
+            .shots
+            .iter()
+            .map(|shot| (shot, [0.6, 1.0, 1.0, 1.0]))
+            .chain(
+                self.enemy_shots
+                    .iter()
+                    .map(|shot| (shot, [1.0, 0.4, 0.2, 1.0])),
+            )
+        {
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(shot.pos.x - 1.5, shot.pos.y - 6.0, 3.0, 12.0),
+                color.into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Wave: {}  Lives: {}{}",
+                locale::integer(self.score),
+                self.wave,
+                self.lives,
+                if self.dual { "  DUAL FIGHTER" } else { "" }
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(22.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER! Press Enter.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(36.0)),
+            );
+
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space if !repeat && !self.gameover => self.fire(),
+            KeyCode::Return if self.gameover => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("galaga", "Sprial404")
+        .window_setup(window::setup("Galaga"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,682 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::matching::{self, ORTHOGONAL};
+use common::particles::ParticleSystem;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::tween::{Ease, Tween};
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const BOARD_SIZE: i32 = 8;
+const CELL_SIZE: f32 = 64.0;
+const HUD_HEIGHT: f32 = 48.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    BOARD_SIZE as f32 * CELL_SIZE,
+    BOARD_SIZE as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+const GEM_COLORS: [[f32; 4]; 7] = [
+    [1.0, 0.2, 0.2, 1.0],
+    [0.2, 0.8, 0.2, 1.0],
+    [0.2, 0.4, 1.0, 1.0],
+    [1.0, 0.9, 0.2, 1.0],
+    [0.8, 0.3, 1.0, 1.0],
+    [1.0, 0.6, 0.1, 1.0],
+    [0.9, 0.9, 0.9, 1.0],
+];
+
+const SWAP_TIME: f32 = 0.18;
+const FALL_TIME: f32 = 0.35;
+const GEM_POINTS: u32 = 10;
+
+/// The moves allowed in move-limited mode, and the seconds allowed in timed mode.
+const MOVE_LIMIT: u32 = 30;
+const TIME_LIMIT: f32 = 90.0;
+
+/// A power a gem gains from being made in a longer match.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Special {
+    None,
+    /// Made from four in a row. Clears its whole row or column when matched.
+    Striped {
+        horizontal: bool,
+    },
+    /// Made from five in a row. Clears every gem of the kind it is swapped with.
+    ColorBomb,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Gem {
+    kind: usize,
+    special: Special,
+    /// The gem's offset from its cell, animating back to zero after it moves.
+    offset: Tween<Vec2>,
+}
+
+impl Gem {
+    /// Creates a new plain gem of a random kind.
+    pub fn random() -> Self {
+        Gem {
+            kind: rand::thread_rng().gen_range(0, GEM_COLORS.len()),
+            special: Special::None,
+            offset: Tween::at(Vec2::ZERO),
+        }
+    }
+
+    /// The kind of gem for matching, or `None` for color bombs, which don't match anything.
+    fn match_kind(&self) -> Option<usize> {
+        match self.special {
+            Special::ColorBomb => None,
+            _ => Some(self.kind),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Mode {
+    Moves,
+    Timed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Phase {
+    /// Choosing a mode.
+    Menu,
+    /// Waiting for the player to swap two gems.
+    Idle,
+    /// Animating a swap between two cells.
+    Swapping((i32, i32), (i32, i32)),
+    /// Animating a swap that made no match back again.
+    SwappingBack,
+    /// Animating gems falling into the gaps left by a match.
+    Falling,
+    GameOver,
+}
+
+fn cell_center(pos: (i32, i32)) -> Vec2 {
+    Vec2::new(
+        (pos.0 as f32 + 0.5) * CELL_SIZE,
+        HUD_HEIGHT + (pos.1 as f32 + 0.5) * CELL_SIZE,
+    )
+}
+
+fn is_adjacent(a: (i32, i32), b: (i32, i32)) -> bool {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs() == 1
+}
+
+/// The state for the game.
+struct GameState {
+    board: Grid<Option<Gem>>,
+    mode: Mode,
+    phase: Phase,
+    phase_timer: f32,
+    selected: Option<(i32, i32)>,
+    particles: ParticleSystem,
+    /// How many matches have cascaded from the last swap.
+    cascade: u32,
+    score: u32,
+    moves_left: u32,
+    time_left: f32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the mode menu.
+    pub fn new() -> Self {
+        GameState {
+            board: Grid::new(BOARD_SIZE as usize, BOARD_SIZE as usize, None),
+            mode: Mode::Moves,
+            phase: Phase::Menu,
+            phase_timer: 0.0,
+            selected: None,
+            particles: ParticleSystem::new(Vec2::new(0.0, 600.0)),
+            cascade: 0,
+            score: 0,
+            moves_left: MOVE_LIMIT,
+            time_left: TIME_LIMIT,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.score = 0;
+        self.moves_left = MOVE_LIMIT;
+        self.time_left = TIME_LIMIT;
+        self.selected = None;
+        self.particles.clear();
+        self.deal();
+        self.phase = Phase::Idle;
+    }
+
+    /// Fills the board with gems that don't already match, with at least one move available.
+    fn deal(&mut self) {
+        loop {
+            for y in 0..BOARD_SIZE {
+                for x in 0..BOARD_SIZE {
+                    let mut gem = Gem::random();
+                    while self.makes_run(x, y, gem.kind) {
+                        gem = Gem::random();
+                    }
+                    self.board.set(x, y, Some(gem));
+                }
+            }
+            if self.has_move() {
+                break;
+            }
+        }
+    }
+
+    /// Whether placing `kind` at `(x, y)` would finish a run with the two gems above or to the
+    /// left of it.
+    fn makes_run(&self, x: i32, y: i32, kind: usize) -> bool {
+        let kind_at = |x, y| {
+            self.board
+                .get(x, y)
+                .cloned()
+                .flatten()
+                .and_then(|gem| gem.match_kind())
+        };
+        let left = x >= 2 && kind_at(x - 1, y) == Some(kind) && kind_at(x - 2, y) == Some(kind);
+        let up = y >= 2 && kind_at(x, y - 1) == Some(kind) && kind_at(x, y - 2) == Some(kind);
+        left || up
+    }
+
+    /// The board as the kinds of gem to match, with gaps and color bombs as `None`.
+    fn kinds(&self) -> Grid<Option<usize>> {
+        let mut kinds = Grid::new(BOARD_SIZE as usize, BOARD_SIZE as usize, None);
+        for (x, y, gem) in self.board.iter() {
+            kinds.set(x, y, gem.and_then(|gem| gem.match_kind()));
+        }
+        kinds
+    }
+
+    /// Whether any swap on the board would make a match.
+    fn has_move(&self) -> bool {
+        let kinds = self.kinds();
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if kinds.get(x, y) == Some(&None) {
+                    // A color bomb can always be used.
+                    return true;
+                }
+                for &(dx, dy) in ORTHOGONAL.iter() {
+                    let (ox, oy) = (x + dx, y + dy);
+                    if !kinds.in_bounds(ox, oy) {
+                        continue;
+                    }
+                    let mut swapped = kinds.clone();
+                    let a = swapped.get(x, y).cloned().flatten();
+                    let b = swapped.get(ox, oy).cloned().flatten();
+                    swapped.set(x, y, b);
+                    swapped.set(ox, oy, a);
+                    if !matching::find_runs(&swapped, 3, &ORTHOGONAL).is_empty() {
+                        return true;
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    fn swap_cells(&mut self, a: (i32, i32), b: (i32, i32)) {
+        let gem_a = self.board.get(a.0, a.1).cloned().flatten();
+        let gem_b = self.board.get(b.0, b.1).cloned().flatten();
+        self.board.set(a.0, a.1, gem_b);
+        self.board.set(b.0, b.1, gem_a);
+
+        // Animate each gem over from where the other one now is.
+        for &(pos, from) in [(a, b), (b, a)].iter() {
+            if let Some(Some(gem)) = self.board.get_mut(pos.0, pos.1) {
+                gem.offset = Tween::new(
+                    cell_center(from) - cell_center(pos),
+                    Vec2::ZERO,
+                    SWAP_TIME,
+                    Ease::QuadInOut,
+                );
+            }
+        }
+        self.phase_timer = SWAP_TIME;
+    }
+
+    fn click_cell(&mut self, pos: (i32, i32)) {
+        match self.selected {
+            Some(selected) if is_adjacent(selected, pos) => {
+                self.selected = None;
+                self.swap_cells(selected, pos);
+                self.phase = Phase::Swapping(selected, pos);
+            }
+            Some(selected) if selected == pos => self.selected = None,
+            _ => self.selected = Some(pos),
+        }
+    }
+
+    /// Finishes a swap, setting off a color bomb, resolving matches or swapping back.
+    fn finish_swap(&mut self, a: (i32, i32), b: (i32, i32)) {
+        self.cascade = 0;
+        let gem_a = self.board.get(a.0, a.1).cloned().flatten();
+        let gem_b = self.board.get(b.0, b.1).cloned().flatten();
+
+        let bomb = match (gem_a, gem_b) {
+            (Some(bomb), Some(other)) if bomb.special == Special::ColorBomb => {
+                Some((a, other.kind))
+            }
+            (Some(other), Some(bomb)) if bomb.special == Special::ColorBomb => {
+                Some((b, other.kind))
+            }
+            _ => None,
+        };
+
+        if let Some((pos, kind)) = bomb {
+            self.use_move();
+            let mut cells: Vec<(i32, i32)> = self
+                .board
+                .iter()
+                .filter(|(_, _, gem)| gem.map_or(false, |gem| gem.kind == kind))
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            cells.push(pos);
+            self.clear_cells(cells, Vec::new());
+            return;
+        }
+
+        if self.resolve_matches(&[a, b]) {
+            self.use_move();
+        } else {
+            self.swap_cells(a, b);
+            self.phase = Phase::SwappingBack;
+        }
+    }
+
+    fn use_move(&mut self) {
+        if self.mode == Mode::Moves {
+            self.moves_left = self.moves_left.saturating_sub(1);
+        }
+    }
+
+    /// Clears any matches on the board, turning long matches into special gems. Prefers to put
+    /// a new special gem at one of `swapped` if it is part of the match. Returns whether anything
+    /// matched.
+    fn resolve_matches(&mut self, swapped: &[(i32, i32)]) -> bool {
+        let runs = matching::find_runs(&self.kinds(), 3, &ORTHOGONAL);
+        if runs.is_empty() {
+            return false;
+        }
+
+        let mut specials = Vec::new();
+        for run in runs.iter().filter(|run| run.len >= 4) {
+            let cells: Vec<(i32, i32)> = run.cells().collect();
+            let pos = swapped
+                .iter()
+                .cloned()
+                .find(|pos| cells.contains(pos))
+                .unwrap_or(cells[cells.len() / 2]);
+            let kind = self
+                .board
+                .get(pos.0, pos.1)
+                .cloned()
+                .flatten()
+                .map_or(0, |gem| gem.kind);
+            let special = if run.len >= 5 {
+                Special::ColorBomb
+            } else {
+                Special::Striped {
+                    horizontal: run.dir == (1, 0),
+                }
+            };
+            specials.push((pos, kind, special));
+        }
+
+        self.clear_cells(matching::matched_cells(&runs), specials);
+        true
+    }
+
+    /// Clears `cells`, setting off any striped gems among them, then places the new `specials`
+    /// and lets the gems above fall into the gaps.
+    fn clear_cells(&mut self, cells: Vec<(i32, i32)>, specials: Vec<((i32, i32), usize, Special)>) {
+        self.cascade += 1;
+
+        let mut cleared = Vec::new();
+        let mut pending = cells;
+        while let Some((x, y)) = pending.pop() {
+            let gem = match self.board.get_mut(x, y).and_then(Option::take) {
+                Some(gem) => gem,
+                None => continue,
+            };
+            cleared.push((x, y));
+            self.particles.burst(
+                cell_center((x, y)),
+                8,
+                240.0,
+                0.6,
+                GEM_COLORS[gem.kind],
+                4.0,
+            );
+
+            if let Special::Striped { horizontal } = gem.special {
+                for i in 0..BOARD_SIZE {
+                    pending.push(if horizontal { (i, y) } else { (x, i) });
+                }
+            }
+        }
+
+        self.score += cleared.len() as u32 * GEM_POINTS * self.cascade;
+
+        for (pos, kind, special) in specials {
+            self.board.set(
+                pos.0,
+                pos.1,
+                Some(Gem {
+                    kind,
+                    special,
+                    offset: Tween::at(Vec2::ZERO),
+                }),
+            );
+        }
+
+        self.drop_gems();
+    }
+
+    /// Drops the gems into the gaps below them and fills the board back up from the top.
+    fn drop_gems(&mut self) {
+        for (from, to) in matching::apply_gravity(&mut self.board) {
+            if let Some(Some(gem)) = self.board.get_mut(to.0, to.1) {
+                gem.offset = Tween::new(
+                    cell_center(from) - cell_center(to),
+                    Vec2::ZERO,
+                    FALL_TIME,
+                    Ease::BounceOut,
+                );
+            }
+        }
+
+        for x in 0..BOARD_SIZE {
+            let empty = (0..BOARD_SIZE)
+                .filter(|&y| self.board.get(x, y) == Some(&None))
+                .count() as f32;
+            for y in 0..BOARD_SIZE {
+                if self.board.get(x, y) == Some(&None) {
+                    let mut gem = Gem::random();
+                    gem.offset = Tween::new(
+                        Vec2::new(0.0, -empty * CELL_SIZE),
+                        Vec2::ZERO,
+                        FALL_TIME,
+                        Ease::BounceOut,
+                    );
+                    self.board.set(x, y, Some(gem));
+                }
+            }
+        }
+
+        self.phase = Phase::Falling;
+        self.phase_timer = FALL_TIME;
+    }
+
+    /// Returns to waiting for a swap, ending the game if the moves or time have run out.
+    fn settle(&mut self) {
+        self.phase = match self.mode {
+            Mode::Moves if self.moves_left == 0 => Phase::GameOver,
+            Mode::Timed if self.time_left <= 0.0 => Phase::GameOver,
+            _ => Phase::Idle,
+        };
+
+        if self.phase == Phase::GameOver {
+            self.record_run();
+        }
+        if self.phase == Phase::Idle && !self.has_move() {
+            self.deal();
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("timed", if self.mode == Mode::Timed { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("gems", &values);
+        }
+        let _ = achievements::check_run("gems", &values);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        for y in 0..BOARD_SIZE {
+            for x in 0..BOARD_SIZE {
+                if let Some(Some(gem)) = self.board.get_mut(x, y) {
+                    gem.offset.update(dt);
+                }
+            }
+        }
+        self.particles.update(dt);
+
+        if self.mode == Mode::Timed {
+            self.time_left = (self.time_left - dt).max(0.0);
+        }
+
+        self.phase_timer -= dt;
+        if self.phase_timer > 0.0 {
+            return;
+        }
+
+        match self.phase {
+            Phase::Swapping(a, b) => self.finish_swap(a, b),
+            Phase::SwappingBack => self.settle(),
+            Phase::Falling => {
+                if !self.resolve_matches(&[]) {
+                    self.settle();
+                }
+            }
+            Phase::Idle if self.mode == Mode::Timed && self.time_left <= 0.0 => {
+                self.phase = Phase::GameOver;
+                self.record_run();
+            }
+            _ => (),
+        }
+    }
+
+    fn draw_gem(&self, builder: &mut graphics::MeshBuilder, pos: (i32, i32), gem: &Gem) {
+        let center = cell_center(pos) + gem.offset.value();
+        let point = ggez::mint::Point2 {
+            x: center.x,
+            y: center.y,
+        };
+        let radius = CELL_SIZE * 0.38;
+
+        match gem.special {
+            Special::ColorBomb => {
+                for (i, color) in GEM_COLORS.iter().enumerate() {
+                    builder.circle(
+                        DrawMode::fill(),
+                        point,
+                        radius * (1.0 - i as f32 / GEM_COLORS.len() as f32),
+                        0.5,
+                        (*color).into(),
+                    );
+                }
+            }
+            special => {
+                builder.circle(
+                    DrawMode::fill(),
+                    point,
+                    radius,
+                    0.5,
+                    GEM_COLORS[gem.kind].into(),
+                );
+                if let Special::Striped { horizontal } = special {
+                    let (w, h) = if horizontal {
+                        (radius * 2.0, radius * 0.4)
+                    } else {
+                        (radius * 0.4, radius * 2.0)
+                    };
+                    builder.rectangle(
+                        DrawMode::fill(),
+                        Rect::new(center.x - w / 2.0, center.y - h / 2.0, w, h),
+                        [1.0, 1.0, 1.0, 0.8].into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        match self.phase {
+            Phase::Menu | Phase::GameOver => self.particles.update(dt),
+            _ => self.tick(dt),
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.08, 0.05, 0.12, 1.0].into());
+
+        if self.phase == Phase::Menu {
+            let menu = Text::new(
+                TextFragment::new("GEMS\n\n1: Move limited\n2: Timed")
+                    .color([1.0, 1.0, 1.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(ctx, &menu, (ggez::mint::Point2 { x: 80.0, y: 160.0 },))?;
+
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y, _) in self.board.iter() {
+            let shade = if (x + y) % 2 == 0 { 0.15 } else { 0.2 };
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    x as f32 * CELL_SIZE,
+                    HUD_HEIGHT + y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                ),
+                [shade, shade, shade + 0.05, 1.0].into(),
+            );
+        }
+        if let Some((x, y)) = self.selected {
+            builder.rectangle(
+                DrawMode::stroke(3.0),
+                Rect::new(
+                    x as f32 * CELL_SIZE,
+                    HUD_HEIGHT + y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                ),
+                [1.0, 1.0, 1.0, 1.0].into(),
+            );
+        }
+        for (x, y, gem) in self.board.iter() {
+            if let Some(gem) = gem {
+                self.draw_gem(&mut builder, (x, y), gem);
+            }
+        }
+        for particle in self.particles.particles() {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: particle.pos.x,
+                    y: particle.pos.y,
+                },
+                particle.size,
+                0.5,
+                particle.faded_color().into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let remaining = match self.mode {
+            Mode::Moves => format!("Moves: {}", self.moves_left),
+            Mode::Timed => format!("Time: {:.0}", self.time_left.ceil()),
+        };
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  {}",
+                locale::integer(self.score),
+                remaining
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(28.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 10.0 },))?;
+
+        if self.phase == Phase::GameOver {
+            let game_over = Text::new(
+                TextFragment::new(format!(
+                    "GAME OVER!\nScore: {}\nPress Enter.",
+                    locale::integer(self.score)
+                ))
+                .color([1.0, 0.3, 0.3, 1.0].into())
+                .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 { x: 120.0, y: 200.0 },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left || self.phase != Phase::Idle {
+            return;
+        }
+
+        let pos = (
+            (x / CELL_SIZE).floor() as i32,
+            ((y - HUD_HEIGHT) / CELL_SIZE).floor() as i32,
+        );
+        if self.board.in_bounds(pos.0, pos.1) {
+            self.click_cell(pos);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Key1 if self.phase == Phase::Menu => self.start(Mode::Moves),
+            KeyCode::Key2 if self.phase == Phase::Menu => self.start(Mode::Timed),
+            KeyCode::Return if self.phase == Phase::GameOver => self.phase = Phase::Menu,
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("gems", "Sprial404")
+        .window_setup(window::setup("Gems"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
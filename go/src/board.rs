@@ -0,0 +1,375 @@
+//! The rules of Go: placing stones, captures, ko and scoring.
+
+use common::grid::Grid;
+use quick_games_ai::mcts::Game;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// The width and height of the board.
+pub const SIZE: usize = 9;
+/// The points given to white for moving second.
+pub const KOMI: f32 = 6.5;
+/// Games that run past this many moves are scored as they stand, so playouts always finish.
+const MAX_MOVES: u32 = (SIZE * SIZE * 3) as u32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stone {
+    Black,
+    White,
+}
+
+impl Stone {
+    pub fn other(self) -> Stone {
+        match self {
+            Stone::Black => Stone::White,
+            Stone::White => Stone::Black,
+        }
+    }
+
+    /// The index of the player, black being zero as they move first.
+    pub fn index(self) -> usize {
+        match self {
+            Stone::Black => 0,
+            Stone::White => 1,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stone::Black => "Black",
+            Stone::White => "White",
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Move {
+    Place(i32, i32),
+    Pass,
+}
+
+/// The final score for each player, indexed by `Stone::index`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Score {
+    /// The empty points each player has surrounded.
+    pub territory: [u32; 2],
+    /// The stones each player has captured or has marked dead.
+    pub prisoners: [u32; 2],
+}
+
+impl Score {
+    pub fn total(&self, stone: Stone) -> f32 {
+        let komi = if stone == Stone::White { KOMI } else { 0.0 };
+        (self.territory[stone.index()] + self.prisoners[stone.index()]) as f32 + komi
+    }
+
+    /// The winner and how many points they won by. Komi's half point means there are no ties.
+    pub fn winner(&self) -> (Stone, f32) {
+        let margin = self.total(Stone::Black) - self.total(Stone::White);
+        if margin > 0.0 {
+            (Stone::Black, margin)
+        } else {
+            (Stone::White, -margin)
+        }
+    }
+}
+
+/// A game of Go in progress.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Board {
+    cells: Grid<Option<Stone>>,
+    to_move: Stone,
+    /// The point the next player can't take back straight away.
+    ko: Option<(i32, i32)>,
+    /// How many passes have been played in a row.
+    passes: u32,
+    /// The stones each player has captured, indexed by `Stone::index`.
+    captures: [u32; 2],
+    moves: u32,
+    last: Option<Move>,
+}
+
+impl Board {
+    /// Creates an empty board with black to play.
+    pub fn new() -> Self {
+        Board {
+            cells: Grid::new(SIZE, SIZE, None),
+            to_move: Stone::Black,
+            ko: None,
+            passes: 0,
+            captures: [0; 2],
+            moves: 0,
+            last: None,
+        }
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Stone> {
+        self.cells.get(x, y).cloned().flatten()
+    }
+
+    pub fn to_move(&self) -> Stone {
+        self.to_move
+    }
+
+    pub fn captures(&self, stone: Stone) -> u32 {
+        self.captures[stone.index()]
+    }
+
+    pub fn last_move(&self) -> Option<Move> {
+        self.last
+    }
+
+    /// Whether both players have passed in a row, ending play.
+    pub fn is_over(&self) -> bool {
+        self.passes >= 2
+    }
+
+    /// Carries on playing after the players disagreed over the score.
+    pub fn resume(&mut self) {
+        self.passes = 0;
+    }
+
+    /// The stones connected to the one at `(x, y)`.
+    pub fn group(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        match self.get(x, y) {
+            Some(stone) => self.cells.flood_fill(x, y, |&cell| cell == Some(stone)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Counts the distinct empty points next to a group.
+    fn liberties(&self, group: &[(i32, i32)]) -> usize {
+        let mut liberties: Vec<(i32, i32)> = group
+            .iter()
+            .flat_map(|&(x, y)| self.cells.neighbours(x, y))
+            .filter(|&(x, y)| self.get(x, y).is_none())
+            .collect();
+        liberties.sort();
+        liberties.dedup();
+        liberties.len()
+    }
+
+    /// Whether the player to move can place a stone at `(x, y)`, checked without playing it.
+    pub fn is_legal(&self, x: i32, y: i32) -> bool {
+        if !self.cells.in_bounds(x, y) || self.get(x, y).is_some() || self.ko == Some((x, y)) {
+            return false;
+        }
+
+        // The stone lives if it has room to breathe, joins a group with liberties to spare, or
+        // captures something.
+        self.cells
+            .neighbours(x, y)
+            .any(|(nx, ny)| match self.get(nx, ny) {
+                None => true,
+                Some(stone) if stone == self.to_move => self.liberties(&self.group(nx, ny)) > 1,
+                Some(_) => self.liberties(&self.group(nx, ny)) == 1,
+            })
+    }
+
+    /// Whether `(x, y)` is an empty point walled in by `stone`, which it would be pointless to
+    /// fill.
+    pub fn is_eye(&self, x: i32, y: i32, stone: Stone) -> bool {
+        self.get(x, y).is_none()
+            && self
+                .cells
+                .neighbours(x, y)
+                .all(|(nx, ny)| self.get(nx, ny) == Some(stone))
+    }
+
+    /// Plays a move for the player to move, or explains why it isn't allowed.
+    pub fn try_play(&mut self, mv: Move) -> Result<(), &'static str> {
+        let (x, y) = match mv {
+            Move::Pass => {
+                self.passes += 1;
+                self.ko = None;
+                self.finish_move(mv);
+                return Ok(());
+            }
+            Move::Place(x, y) => (x, y),
+        };
+
+        if !self.cells.in_bounds(x, y) {
+            return Err("That's off the board.");
+        }
+        if self.get(x, y).is_some() {
+            return Err("That point is taken.");
+        }
+        if self.ko == Some((x, y)) {
+            return Err("Ko: play elsewhere before taking back.");
+        }
+        if !self.is_legal(x, y) {
+            return Err("That would be suicide.");
+        }
+
+        let stone = self.to_move;
+        self.cells.set(x, y, Some(stone));
+
+        let mut captured = Vec::new();
+        for (nx, ny) in self.cells.neighbours(x, y).collect::<Vec<_>>() {
+            if self.get(nx, ny) == Some(stone.other()) {
+                let group = self.group(nx, ny);
+                if self.liberties(&group) == 0 {
+                    for &(gx, gy) in group.iter() {
+                        self.cells.set(gx, gy, None);
+                    }
+                    captured.extend(group);
+                }
+            }
+        }
+        self.captures[stone.index()] += captured.len() as u32;
+
+        // Taking a single stone with a lone stone left in atari sets up a ko.
+        let own = self.group(x, y);
+        self.ko = if captured.len() == 1 && own.len() == 1 && self.liberties(&own) == 1 {
+            Some(captured[0])
+        } else {
+            None
+        };
+
+        self.passes = 0;
+        self.finish_move(mv);
+        Ok(())
+    }
+
+    fn finish_move(&mut self, mv: Move) {
+        self.to_move = self.to_move.other();
+        self.moves += 1;
+        self.last = Some(mv);
+    }
+
+    /// Who owns each empty point once the `dead` stones are taken off. Points touching both
+    /// colours belong to nobody.
+    pub fn territory(&self, dead: &Grid<bool>) -> Grid<Option<Stone>> {
+        let is_dead = |x: i32, y: i32| dead.get(x, y) == Some(&true);
+        let mut open = self.cells.clone();
+        for (x, y, _) in self.cells.iter() {
+            if is_dead(x, y) {
+                open.set(x, y, None);
+            }
+        }
+
+        let mut owners = Grid::new(SIZE, SIZE, None);
+        let mut visited = Grid::new(SIZE, SIZE, false);
+        for (x, y, cell) in open.iter() {
+            if cell.is_some() || visited.get(x, y) == Some(&true) {
+                continue;
+            }
+
+            let region = open.flood_fill(x, y, |cell| cell.is_none());
+            let mut borders = region
+                .iter()
+                .flat_map(|&(x, y)| open.neighbours(x, y))
+                .filter_map(|(x, y)| open.get(x, y).cloned().flatten());
+            let owner = borders
+                .next()
+                .filter(|&first| borders.all(|stone| stone == first));
+
+            for &(rx, ry) in region.iter() {
+                visited.set(rx, ry, true);
+                owners.set(rx, ry, owner);
+            }
+        }
+        owners
+    }
+
+    /// Scores the game by territory, counting captures and the `dead` stones as prisoners.
+    pub fn score(&self, dead: &Grid<bool>) -> Score {
+        let mut score = Score {
+            territory: [0; 2],
+            prisoners: self.captures,
+        };
+
+        for (_, _, owner) in self.territory(dead).iter() {
+            if let Some(owner) = owner {
+                score.territory[owner.index()] += 1;
+            }
+        }
+        for (x, y, &cell) in self.cells.iter() {
+            if let Some(stone) = cell {
+                if dead.get(x, y) == Some(&true) {
+                    score.prisoners[stone.other().index()] += 1;
+                }
+            }
+        }
+        score
+    }
+
+    /// Black's lead counting stones and surrounded points, which is quicker to work out at the
+    /// end of a playout and agrees with territory scoring once every dead stone is captured.
+    fn area_lead(&self) -> f32 {
+        let mut lead = -KOMI;
+        let owners = self.territory(&Grid::new(SIZE, SIZE, false));
+        for (x, y, &cell) in self.cells.iter() {
+            let owner = cell.or_else(|| owners.get(x, y).cloned().flatten());
+            match owner {
+                Some(Stone::Black) => lead += 1.0,
+                Some(Stone::White) => lead -= 1.0,
+                None => (),
+            }
+        }
+        lead
+    }
+
+    /// The points worth considering: legal, and not filling one of the mover's own eyes.
+    fn sensible_points(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.cells
+            .iter()
+            .map(|(x, y, _)| (x, y))
+            .filter(move |&(x, y)| self.is_legal(x, y) && !self.is_eye(x, y, self.to_move))
+    }
+}
+
+impl Game for Board {
+    type Move = Move;
+
+    fn current_player(&self) -> usize {
+        self.to_move.index()
+    }
+
+    fn moves(&self) -> Vec<Move> {
+        if self.is_over() || self.moves >= MAX_MOVES {
+            return Vec::new();
+        }
+        let mut moves: Vec<Move> = self
+            .sensible_points()
+            .map(|(x, y)| Move::Place(x, y))
+            .collect();
+        moves.push(Move::Pass);
+        moves
+    }
+
+    fn play(&mut self, mv: Move) {
+        let _ = self.try_play(mv);
+    }
+
+    fn reward(&self, player: usize) -> f32 {
+        let winner = if self.area_lead() > 0.0 {
+            Stone::Black
+        } else {
+            Stone::White
+        };
+        if winner.index() == player {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Plays anywhere sensible at random, only passing once there is nowhere left.
+    fn playout_move<R: Rng>(&self, rng: &mut R) -> Option<Move> {
+        if self.is_over() || self.moves >= MAX_MOVES {
+            return None;
+        }
+        let mut points: Vec<(i32, i32)> = self
+            .cells
+            .iter()
+            .filter(|(_, _, cell)| cell.is_none())
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        points.shuffle(rng);
+        let point = points
+            .into_iter()
+            .find(|&(x, y)| self.is_legal(x, y) && !self.is_eye(x, y, self.to_move));
+        Some(point.map_or(Move::Pass, |(x, y)| Move::Place(x, y)))
+    }
+}
@@ -0,0 +1,431 @@
+mod board;
+
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::spectator;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use quick_games_ai::mcts::Mcts;
+
+use crate::board::{Board, Move, Stone, SIZE};
+
+const SCREEN_SIZE: (f32, f32) = (600.0, 720.0);
+
+/// The distance between the lines on the board.
+const CELL: f32 = 60.0;
+/// The distance from the edge of the window to the outer lines.
+const MARGIN: f32 = 60.0;
+
+/// The random games the computer plays out before each move.
+const AI_PLAYOUTS: u32 = 1500;
+
+/// The handicap points on a 9x9 board.
+const STAR_POINTS: [(i32, i32); 5] = [(2, 2), (6, 2), (4, 4), (2, 6), (6, 6)];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Phase {
+    Menu,
+    Playing,
+    /// Both players passed, and are marking which stones are dead.
+    Scoring,
+    Over,
+}
+
+/// The state for the game.
+struct GameState {
+    board: Board,
+    phase: Phase,
+    /// The colour the computer plays, if it is playing.
+    computer: Option<Stone>,
+    /// The stones marked dead while scoring.
+    dead: Grid<bool>,
+    hover: Option<(i32, i32)>,
+    message: String,
+    /// Set for a frame before the computer moves, so "thinking" is drawn while it searches.
+    thinking: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the menu.
+    pub fn new() -> Self {
+        GameState {
+            board: Board::new(),
+            phase: Phase::Menu,
+            computer: None,
+            dead: Grid::new(SIZE, SIZE, false),
+            hover: None,
+            message: String::new(),
+            thinking: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start(&mut self, computer: Option<Stone>) {
+        self.board = Board::new();
+        self.computer = computer;
+        self.dead = Grid::new(SIZE, SIZE, false);
+        self.message.clear();
+        self.thinking = false;
+        self.phase = Phase::Playing;
+    }
+
+    fn computer_to_move(&self) -> bool {
+        self.phase == Phase::Playing && self.computer == Some(self.board.to_move())
+    }
+
+    /// The board point nearest a position on screen, if it is close enough to the board.
+    fn point_at(x: f32, y: f32) -> Option<(i32, i32)> {
+        let px = ((x - MARGIN) / CELL).round() as i32;
+        let py = ((y - MARGIN) / CELL).round() as i32;
+        if px >= 0 && py >= 0 && (px as usize) < SIZE && (py as usize) < SIZE {
+            Some((px, py))
+        } else {
+            None
+        }
+    }
+
+    fn screen_pos(x: i32, y: i32) -> ggez::mint::Point2<f32> {
+        ggez::mint::Point2 {
+            x: MARGIN + x as f32 * CELL,
+            y: MARGIN + y as f32 * CELL,
+        }
+    }
+
+    fn play(&mut self, mv: Move) {
+        match self.board.try_play(mv) {
+            Ok(()) => {
+                self.message = match mv {
+                    Move::Pass => format!("{} passed.", self.board.to_move().other().name()),
+                    Move::Place(..) => String::new(),
+                };
+                if self.board.is_over() {
+                    self.dead.fill(false);
+                    self.phase = Phase::Scoring;
+                    self.message = "Click groups to mark them dead.".to_string();
+                }
+            }
+            Err(reason) => self.message = reason.to_string(),
+        }
+    }
+
+    /// Marks or unmarks a whole group as dead.
+    fn toggle_dead(&mut self, x: i32, y: i32) {
+        let dead = self.dead.get(x, y) != Some(&true);
+        for (gx, gy) in self.board.group(x, y) {
+            self.dead.set(gx, gy, dead);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.phase = Phase::Over;
+        let score = self.board.score(&self.dead);
+        // The player is black in a two player game.
+        let player = self.computer.map_or(Stone::Black, Stone::other);
+        let won = score.winner().0 == player;
+        let values = [
+            ("black", score.total(Stone::Black) as f64),
+            ("white", score.total(Stone::White) as f64),
+            ("computer", if self.computer.is_some() { 1.0 } else { 0.0 }),
+            ("won", if won { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("go", &values);
+        }
+        let _ = achievements::check_run("go", &values);
+    }
+
+    fn draw_board(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        let far = MARGIN + (SIZE - 1) as f32 * CELL;
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(
+                MARGIN - CELL / 2.0,
+                MARGIN - CELL / 2.0,
+                far - MARGIN + CELL,
+                far - MARGIN + CELL,
+            ),
+            [0.85, 0.68, 0.4, 1.0].into(),
+        );
+
+        let line = [0.2, 0.15, 0.1, 1.0];
+        for i in 0..SIZE {
+            let offset = MARGIN + i as f32 * CELL;
+            builder.line(
+                &[
+                    ggez::mint::Point2 {
+                        x: MARGIN,
+                        y: offset,
+                    },
+                    ggez::mint::Point2 { x: far, y: offset },
+                ],
+                2.0,
+                line.into(),
+            )?;
+            builder.line(
+                &[
+                    ggez::mint::Point2 {
+                        x: offset,
+                        y: MARGIN,
+                    },
+                    ggez::mint::Point2 { x: offset, y: far },
+                ],
+                2.0,
+                line.into(),
+            )?;
+        }
+        for &(x, y) in STAR_POINTS.iter() {
+            builder.circle(
+                DrawMode::fill(),
+                GameState::screen_pos(x, y),
+                5.0,
+                0.5,
+                line.into(),
+            );
+        }
+
+        let scoring = self.phase == Phase::Scoring || self.phase == Phase::Over;
+        for y in 0..SIZE as i32 {
+            for x in 0..SIZE as i32 {
+                let pos = GameState::screen_pos(x, y);
+                if let Some(stone) = self.board.get(x, y) {
+                    let dead = scoring && self.dead.get(x, y) == Some(&true);
+                    let alpha = if dead { 0.35 } else { 1.0 };
+                    builder.circle(
+                        DrawMode::fill(),
+                        pos,
+                        CELL * 0.45,
+                        0.5,
+                        stone_color(stone, alpha).into(),
+                    );
+                }
+            }
+        }
+
+        if scoring {
+            let territory = self.board.territory(&self.dead);
+            for (x, y, owner) in territory.iter() {
+                if let Some(owner) = owner {
+                    let pos = GameState::screen_pos(x, y);
+                    builder.rectangle(
+                        DrawMode::fill(),
+                        Rect::new(pos.x - 7.0, pos.y - 7.0, 14.0, 14.0),
+                        stone_color(*owner, 1.0).into(),
+                    );
+                }
+            }
+        }
+
+        if let Some(Move::Place(x, y)) = self.board.last_move() {
+            let color = self
+                .board
+                .get(x, y)
+                .map_or(stone_color(Stone::Black, 1.0), |stone| {
+                    stone_color(stone.other(), 1.0)
+                });
+            builder.circle(
+                DrawMode::stroke(2.0),
+                GameState::screen_pos(x, y),
+                CELL * 0.2,
+                0.5,
+                color.into(),
+            );
+        }
+
+        if let (Phase::Playing, Some((x, y)), false) =
+            (self.phase, self.hover, self.computer_to_move())
+        {
+            if self.board.get(x, y).is_none() {
+                builder.circle(
+                    DrawMode::fill(),
+                    GameState::screen_pos(x, y),
+                    CELL * 0.45,
+                    0.5,
+                    stone_color(self.board.to_move(), 0.4).into(),
+                );
+            }
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_hud(&self, ctx: &mut Context) -> GameResult<()> {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let top = MARGIN + SIZE as f32 * CELL;
+
+        let status = match self.phase {
+            Phase::Playing if self.thinking => "The computer is thinking...".to_string(),
+            Phase::Playing => format!("{} to play. P: pass", self.board.to_move().name()),
+            Phase::Scoring => {
+                let score = self.board.score(&self.dead);
+                format!(
+                    "Black {}  White {}. Enter: accept  R: resume play",
+                    score.total(Stone::Black),
+                    score.total(Stone::White)
+                )
+            }
+            Phase::Over => {
+                let (winner, margin) = self.board.score(&self.dead).winner();
+                format!("{} wins by {}! Press Enter.", winner.name(), margin)
+            }
+            Phase::Menu => String::new(),
+        };
+        self.draw_text(ctx, &status, white, 22.0, 20.0, top)?;
+
+        let captures = format!(
+            "Captures - Black: {}  White: {}",
+            self.board.captures(Stone::Black),
+            self.board.captures(Stone::White)
+        );
+        self.draw_text(ctx, &captures, white, 20.0, 20.0, top + 30.0)?;
+        self.draw_text(
+            ctx,
+            &self.message,
+            [1.0, 0.8, 0.4, 1.0],
+            20.0,
+            20.0,
+            top + 60.0,
+        )
+    }
+
+    fn draw_menu(&self, ctx: &mut Context) -> GameResult<()> {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        self.draw_text(ctx, "9x9 GO", white, 56.0, 200.0, 160.0)?;
+        let options = [
+            "1: Two players on this computer",
+            "2: Play black against the computer",
+            "3: Play white against the computer",
+        ];
+        for (i, option) in options.iter().enumerate() {
+            self.draw_text(ctx, option, white, 26.0, 90.0, 280.0 + i as f32 * 40.0)?;
+        }
+        self.draw_text(
+            ctx,
+            "Territory scoring with 6.5 komi.",
+            [0.7, 0.7, 0.7, 1.0],
+            20.0,
+            90.0,
+            440.0,
+        )
+    }
+}
+
+fn stone_color(stone: Stone, alpha: f32) -> [f32; 4] {
+    match stone {
+        Stone::Black => [0.08, 0.08, 0.08, alpha],
+        Stone::White => [0.95, 0.95, 0.92, alpha],
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        if self.computer_to_move() {
+            if self.thinking {
+                let mcts = Mcts::new(AI_PLAYOUTS);
+                let mv = mcts
+                    .search(&self.board, &mut rand::thread_rng())
+                    .unwrap_or(Move::Pass);
+                self.thinking = false;
+                self.play(mv);
+            } else {
+                self.thinking = true;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.15, 0.13, 0.12, 1.0].into());
+
+        if self.phase == Phase::Menu {
+            self.draw_menu(ctx)?;
+        } else {
+            self.draw_board(ctx)?;
+            spectator::world_drawn(ctx)?;
+            self.draw_hud(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.hover = GameState::point_at(x, y);
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        if let Some((px, py)) = GameState::point_at(x, y) {
+            match self.phase {
+                Phase::Playing if !self.computer_to_move() => self.play(Move::Place(px, py)),
+                Phase::Scoring if self.board.get(px, py).is_some() => self.toggle_dead(px, py),
+                _ => (),
+            }
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match (self.phase, keycode) {
+            (_, KeyCode::Escape) => event::quit(ctx),
+            (Phase::Menu, KeyCode::Key1) => self.start(None),
+            (Phase::Menu, KeyCode::Key2) => self.start(Some(Stone::White)),
+            (Phase::Menu, KeyCode::Key3) => self.start(Some(Stone::Black)),
+            (Phase::Playing, KeyCode::P) if !self.computer_to_move() => self.play(Move::Pass),
+            (Phase::Scoring, KeyCode::Return) => self.finish(),
+            (Phase::Scoring, KeyCode::R) => {
+                self.board.resume();
+                self.message.clear();
+                self.phase = Phase::Playing;
+            }
+            (Phase::Over, KeyCode::Return) => self.phase = Phase::Menu,
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("go", "Sprial404")
+        .window_setup(window::setup("9x9 Go"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
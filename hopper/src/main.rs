@@ -0,0 +1,533 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const COLS: i32 = 13;
+const CELL: f32 = 48.0;
+const VISIBLE_ROWS: i32 = 16;
+const SCREEN_SIZE: (f32, f32) = (COLS as f32 * CELL, VISIBLE_ROWS as f32 * CELL);
+
+/// The grass rows at the start, before any hazards.
+const SAFE_ROWS: i32 = 4;
+/// How many rows past the top of the screen to keep generated.
+const LOOKAHEAD_ROWS: i32 = 4;
+
+const HOP_TIME: f32 = 0.1;
+
+/// How fast the camera creeps forward on its own, in rows per second.
+const CAMERA_CREEP: f32 = 0.35;
+/// Where the camera tries to keep the player, in rows above the bottom of the screen.
+const CAMERA_TARGET: f32 = 5.0;
+/// How quickly the camera catches up when the player is further ahead than the target.
+const CAMERA_CATCH_UP: f32 = 2.5;
+
+/// How far off screen cars and logs travel before wrapping back around. It is longer than any
+/// car or log so they never pop into view.
+const WRAP_MARGIN: f32 = 5.0;
+
+const TRAIN_LENGTH: f32 = 14.0;
+const TRAIN_SPEED: f32 = 30.0;
+/// How long the warning light flashes before a train comes through.
+const TRAIN_WARNING: f32 = 1.2;
+const TRAIN_INTERVAL: (f32, f32) = (3.0, 8.0);
+
+#[derive(Debug, Clone, PartialEq)]
+enum LaneKind {
+    /// Safe ground, with trees in the listed columns.
+    Grass(Vec<bool>),
+    Road,
+    River,
+    Rail {
+        /// Seconds until the next train, counting down through the warning.
+        timer: f32,
+    },
+}
+
+/// A row of the world, with whatever moves along it.
+#[derive(Debug, Clone, PartialEq)]
+struct Lane {
+    kind: LaneKind,
+    /// How fast the cars, logs or train move, in cells per second. Negative is to the left.
+    speed: f32,
+    /// The left edge and length of every car, log or train, in cells.
+    objects: Vec<(f32, f32)>,
+}
+
+impl Lane {
+    fn grass(trees: Vec<bool>) -> Self {
+        Lane {
+            kind: LaneKind::Grass(trees),
+            speed: 0.0,
+            objects: Vec::new(),
+        }
+    }
+
+    fn has_tree(&self, col: i32) -> bool {
+        match &self.kind {
+            LaneKind::Grass(trees) => trees.get(col as usize).cloned().unwrap_or(false),
+            _ => false,
+        }
+    }
+
+    /// Whether a point in the lane is covered by one of its objects.
+    fn covered(&self, x: f32) -> bool {
+        self.objects
+            .iter()
+            .any(|&(left, length)| x >= left && x < left + length)
+    }
+
+    fn update(&mut self, dt: f32) {
+        let width = COLS as f32;
+        let speed = self.speed;
+        for object in self.objects.iter_mut() {
+            object.0 += speed * dt;
+        }
+
+        if let LaneKind::Rail { timer } = &mut self.kind {
+            // Trains run once off the end and are gone until the next one.
+            self.objects
+                .retain(|&(left, length)| left < width + length && left + length > -length);
+            *timer -= dt;
+            if *timer <= 0.0 {
+                *timer = rand::thread_rng().gen_range(TRAIN_INTERVAL.0, TRAIN_INTERVAL.1);
+                let left = if speed > 0.0 { -TRAIN_LENGTH } else { width };
+                self.objects.push((left, TRAIN_LENGTH));
+            }
+            return;
+        }
+
+        // Cars and logs wrap around so the lane never runs dry.
+        let period = width + WRAP_MARGIN;
+        for object in self.objects.iter_mut() {
+            if speed > 0.0 && object.0 > width {
+                object.0 -= period;
+            } else if speed < 0.0 && object.0 + object.1 < 0.0 {
+                object.0 += period;
+            }
+        }
+    }
+
+    /// Whether the warning light is on for an oncoming train.
+    fn train_warning(&self) -> bool {
+        match self.kind {
+            LaneKind::Rail { timer } => timer < TRAIN_WARNING,
+            _ => false,
+        }
+    }
+}
+
+/// Generates the lane for `row`, with hazards getting faster the further along it is.
+fn generate_lane(row: i32, previous: Option<&Lane>) -> Lane {
+    let mut rng = rand::thread_rng();
+    if row < SAFE_ROWS {
+        return Lane::grass(vec![false; COLS as usize]);
+    }
+
+    let difficulty = 1.0 + row as f32 / 100.0;
+    let direction = if rng.gen() { 1.0 } else { -1.0 };
+
+    // Lanes tend to come in groups of the same kind.
+    let kind_roll = match previous.map(|lane| &lane.kind) {
+        Some(LaneKind::Road) if rng.gen_range(0, 3) > 0 => 1,
+        Some(LaneKind::River) if rng.gen_range(0, 2) > 0 => 2,
+        _ => rng.gen_range(0, 10),
+    };
+
+    match kind_roll {
+        1..=4 => {
+            let speed = direction * rng.gen_range(1.5, 3.5) * difficulty;
+            let mut objects = Vec::new();
+            let mut x = rng.gen_range(0.0, 2.0);
+            while x < COLS as f32 {
+                let length = if rng.gen_range(0, 4) == 0 { 2.0 } else { 1.0 };
+                objects.push((x, length));
+                x += length + rng.gen_range(2.5, 5.0);
+            }
+            Lane {
+                kind: LaneKind::Road,
+                speed,
+                objects,
+            }
+        }
+        // Rivers alternate direction with the one before so the logs don't all line up.
+        2 | 5 | 6 => {
+            let direction = match previous {
+                Some(lane) if lane.kind == LaneKind::River => -lane.speed.signum(),
+                _ => direction,
+            };
+            let speed = direction * rng.gen_range(1.0, 2.2) * difficulty.min(1.6);
+            let mut objects = Vec::new();
+            let mut x = rng.gen_range(0.0, 1.5);
+            while x < COLS as f32 {
+                let length = rng.gen_range(2, 5) as f32;
+                objects.push((x, length));
+                x += length + rng.gen_range(1.5, 3.0);
+            }
+            Lane {
+                kind: LaneKind::River,
+                speed,
+                objects,
+            }
+        }
+        7 => Lane {
+            kind: LaneKind::Rail {
+                timer: rng.gen_range(TRAIN_INTERVAL.0, TRAIN_INTERVAL.1),
+            },
+            speed: direction * TRAIN_SPEED,
+            objects: Vec::new(),
+        },
+        _ => {
+            // Keep at least a few gaps in every row of trees.
+            let mut trees: Vec<bool> = (0..COLS).map(|_| rng.gen_range(0, 5) == 0).collect();
+            for _ in 0..3 {
+                trees[rng.gen_range(0, COLS as usize)] = false;
+            }
+            Lane::grass(trees)
+        }
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    /// The generated lanes, starting from `first_row`.
+    lanes: VecDeque<Lane>,
+    first_row: i32,
+    /// The player's column, which is fractional while riding a log.
+    x: f32,
+    row: i32,
+    /// Where the current hop started, and how far through it the player is.
+    hop_from: (f32, i32),
+    hop_timer: f32,
+    /// The row at the bottom of the screen.
+    camera: f32,
+    score: i32,
+    best: i32,
+    /// Why the player died, once they have.
+    death: Option<&'static str>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the start of a fresh road.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            lanes: VecDeque::new(),
+            first_row: 0,
+            x: 0.0,
+            row: 0,
+            hop_from: (0.0, 0),
+            hop_timer: 0.0,
+            camera: 0.0,
+            score: 0,
+            best: 0,
+            death: None,
+            stats: StatsDb::open().ok(),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        self.lanes.clear();
+        self.first_row = 0;
+        self.x = (COLS / 2) as f32;
+        self.row = 1;
+        self.hop_from = (self.x, self.row);
+        self.hop_timer = 0.0;
+        self.camera = 0.0;
+        self.score = 0;
+        self.death = None;
+        self.generate();
+    }
+
+    fn lane(&self, row: i32) -> Option<&Lane> {
+        if row < self.first_row {
+            return None;
+        }
+        self.lanes.get((row - self.first_row) as usize)
+    }
+
+    /// Generates lanes up past the top of the screen and drops the ones far behind.
+    fn generate(&mut self) {
+        let top = self.camera as i32 + VISIBLE_ROWS + LOOKAHEAD_ROWS;
+        while self.first_row + (self.lanes.len() as i32) < top {
+            let row = self.first_row + self.lanes.len() as i32;
+            let lane = generate_lane(row, self.lanes.back());
+            self.lanes.push_back(lane);
+        }
+        while self.first_row < self.camera as i32 - 2 {
+            self.lanes.pop_front();
+            self.first_row += 1;
+        }
+    }
+
+    fn hop(&mut self, dx: i32, dy: i32) {
+        if self.death.is_some() || self.hop_timer > 0.0 {
+            return;
+        }
+
+        // Land on whole columns unless hopping along a log.
+        let target_row = self.row + dy;
+        let mut target_x = self.x + dx as f32;
+        if self
+            .lane(target_row)
+            .map_or(true, |lane| lane.kind != LaneKind::River)
+        {
+            target_x = target_x.round();
+        }
+        if target_x < 0.0 || target_x > (COLS - 1) as f32 || target_row < self.first_row {
+            return;
+        }
+        if self
+            .lane(target_row)
+            .map_or(false, |lane| lane.has_tree(target_x.round() as i32))
+        {
+            return;
+        }
+
+        self.hop_from = (self.x, self.row);
+        self.x = target_x;
+        self.row = target_row;
+        self.hop_timer = HOP_TIME;
+
+        if self.row > self.score {
+            self.score = self.row;
+        }
+    }
+
+    fn die(&mut self, reason: &'static str) {
+        if self.death.is_some() {
+            return;
+        }
+        self.death = Some(reason);
+        self.best = self.best.max(self.score);
+        let values = [("score", self.score as f64)];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("hopper", &values);
+        }
+        let _ = achievements::check_run("hopper", &values);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        for lane in self.lanes.iter_mut() {
+            lane.update(dt);
+        }
+
+        // Creep forward, speeding up to keep the player from getting too far ahead.
+        let ahead = self.row as f32 - self.camera - CAMERA_TARGET;
+        self.camera += (CAMERA_CREEP + ahead.max(0.0) * CAMERA_CATCH_UP) * dt;
+        self.generate();
+
+        self.hop_timer = (self.hop_timer - dt).max(0.0);
+        if self.hop_timer > 0.0 {
+            return;
+        }
+
+        // Check against the middle of the player's cell.
+        let center = self.x + 0.5;
+        // Logs carry the player along; everything else either kills them or leaves them be.
+        let drift = match self.lane(self.row) {
+            Some(lane) => {
+                let covered = lane.covered(center);
+                match lane.kind {
+                    LaneKind::Road if covered => Err("Squashed by a car!"),
+                    LaneKind::Rail { .. } if covered => Err("Hit by a train!"),
+                    LaneKind::River if covered => Ok(lane.speed),
+                    LaneKind::River => Err("Splash!"),
+                    _ => Ok(0.0),
+                }
+            }
+            None => return,
+        };
+        match drift {
+            Ok(speed) => {
+                self.x += speed * dt;
+                if self.x < -0.5 || self.x > COLS as f32 - 0.5 {
+                    self.die("Swept away!");
+                }
+            }
+            Err(reason) => self.die(reason),
+        }
+
+        if (self.row as f32) < self.camera - 0.5 {
+            self.die("Left behind!");
+        }
+    }
+
+    /// The screen position of the top-left of a cell.
+    fn screen_pos(&self, x: f32, row: f32) -> (f32, f32) {
+        (x * CELL, SCREEN_SIZE.1 - (row - self.camera + 1.0) * CELL)
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        for (i, lane) in self.lanes.iter().enumerate() {
+            let row = (self.first_row + i as i32) as f32;
+            let (_, y) = self.screen_pos(0.0, row);
+            if y > SCREEN_SIZE.1 || y < -CELL {
+                continue;
+            }
+
+            let ground = match lane.kind {
+                LaneKind::Grass(_) => {
+                    if (self.first_row + i as i32) % 2 == 0 {
+                        [0.45, 0.75, 0.3, 1.0]
+                    } else {
+                        [0.42, 0.7, 0.28, 1.0]
+                    }
+                }
+                LaneKind::Road => [0.3, 0.3, 0.33, 1.0],
+                LaneKind::River => [0.2, 0.45, 0.9, 1.0],
+                LaneKind::Rail { .. } => [0.45, 0.4, 0.35, 1.0],
+            };
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(0.0, y, SCREEN_SIZE.0, CELL),
+                ground.into(),
+            );
+
+            if let LaneKind::Grass(trees) = &lane.kind {
+                for (col, _) in trees.iter().enumerate().filter(|(_, &tree)| tree) {
+                    builder.circle(
+                        DrawMode::fill(),
+                        ggez::mint::Point2 {
+                            x: (col as f32 + 0.5) * CELL,
+                            y: y + CELL / 2.0,
+                        },
+                        CELL * 0.4,
+                        0.5,
+                        [0.1, 0.4, 0.15, 1.0].into(),
+                    );
+                }
+            }
+            if lane.train_warning() {
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 {
+                        x: CELL / 2.0,
+                        y: y + CELL / 2.0,
+                    },
+                    CELL * 0.2,
+                    0.5,
+                    [1.0, 0.1, 0.1, 1.0].into(),
+                );
+            }
+
+            let color = match lane.kind {
+                LaneKind::Road => [0.9, 0.3, 0.2, 1.0],
+                LaneKind::River => [0.55, 0.35, 0.15, 1.0],
+                _ => [0.8, 0.8, 0.85, 1.0],
+            };
+            for &(left, length) in lane.objects.iter() {
+                builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(left * CELL + 2.0, y + 6.0, length * CELL - 4.0, CELL - 12.0),
+                    color.into(),
+                );
+            }
+        }
+
+        // Hop in an arc between cells.
+        let t = 1.0 - self.hop_timer / HOP_TIME;
+        let x = self.hop_from.0 + (self.x - self.hop_from.0) * t;
+        let row = self.hop_from.1 as f32 + (self.row - self.hop_from.1) as f32 * t;
+        let (sx, sy) = self.screen_pos(x, row);
+        let lift = (t * std::f32::consts::PI).sin() * CELL * 0.2;
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(sx + 10.0, sy + 10.0 - lift, CELL - 20.0, CELL - 20.0),
+            [1.0, 1.0, 1.0, 1.0].into(),
+        );
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if self.death.is_none() {
+            self.tick(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        self.draw_world(ctx)?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Best: {}",
+                locale::integer(self.score),
+                locale::integer(self.best)
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(28.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if let Some(reason) = self.death {
+            let game_over = Text::new(
+                TextFragment::new(format!("{}\nPress Enter to hop again.", reason))
+                    .color([1.0, 0.2, 0.2, 1.0].into())
+                    .scale(Scale::uniform(36.0)),
+            );
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 60.0,
+                    y: SCREEN_SIZE.1 / 2.0 - 40.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Up | KeyCode::W if !repeat => self.hop(0, 1),
+            KeyCode::Down | KeyCode::S if !repeat => self.hop(0, -1),
+            KeyCode::Left | KeyCode::A if !repeat => self.hop(-1, 0),
+            KeyCode::Right | KeyCode::D if !repeat => self.hop(1, 0),
+            KeyCode::Return if self.death.is_some() => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("hopper", "Sprial404")
+        .window_setup(window::setup("Road Hopper"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
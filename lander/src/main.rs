@@ -0,0 +1,428 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::physics::Vec2;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+const GRAVITY: f32 = 25.0;
+const THRUST: f32 = 60.0;
+const ROTATE_SPEED: f32 = 2.5;
+const START_FUEL: f32 = 100.0;
+/// Fuel used for every second the engine is firing.
+const FUEL_BURN: f32 = 12.0;
+/// Fuel topped up after every successful landing.
+const FUEL_REFILL: f32 = 40.0;
+
+/// Half the width and height of the lander, measured from its center.
+const LANDER_SIZE: (f32, f32) = (12.0, 14.0);
+
+/// The horizontal distance between terrain points.
+const TERRAIN_STEP: f32 = 20.0;
+const TERRAIN_MIN_HEIGHT: f32 = 40.0;
+const TERRAIN_MAX_HEIGHT: f32 = 320.0;
+/// The pads on each terrain, as a width in terrain steps and the score multiplier for landing
+/// on it. Smaller pads are harder to land on so they score more.
+const PADS: [(usize, u32); 3] = [(4, 1), (3, 2), (2, 5)];
+
+/// The fastest the lander can be moving when it touches down without crashing.
+const SAFE_VERTICAL_SPEED: f32 = 30.0;
+const SAFE_HORIZONTAL_SPEED: f32 = 15.0;
+/// The furthest the lander can be tilted from upright when it touches down, in radians.
+const SAFE_ANGLE: f32 = 0.2;
+
+/// A flat landing pad on the terrain.
+#[derive(Debug, Copy, Clone)]
+struct Pad {
+    left: f32,
+    right: f32,
+    y: f32,
+    multiplier: u32,
+}
+
+/// A heightfield terrain with flat landing pads.
+struct Terrain {
+    /// The height of the ground at every `TERRAIN_STEP` across the screen, in screen coordinates.
+    points: Vec<f32>,
+    pads: Vec<Pad>,
+}
+
+impl Terrain {
+    /// Generates a new random terrain with the pads spread across it.
+    pub fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        let count = (SCREEN_SIZE.0 / TERRAIN_STEP) as usize + 1;
+
+        let mut height = rng.gen_range(TERRAIN_MIN_HEIGHT, TERRAIN_MAX_HEIGHT);
+        let mut points = Vec::with_capacity(count);
+        for _ in 0..count {
+            height = (height + rng.gen_range(-40.0, 40.0))
+                .max(TERRAIN_MIN_HEIGHT)
+                .min(TERRAIN_MAX_HEIGHT);
+            points.push(SCREEN_SIZE.1 - height);
+        }
+
+        // Split the screen into a section per pad so they never overlap, then flatten a random
+        // stretch of each section.
+        let section = count / PADS.len();
+        let mut pads = Vec::new();
+        for (i, &(width, multiplier)) in PADS.iter().enumerate() {
+            let start = i * section + rng.gen_range(1, section - width);
+            let y = points[start];
+            for point in points[start..=start + width].iter_mut() {
+                *point = y;
+            }
+
+            pads.push(Pad {
+                left: start as f32 * TERRAIN_STEP,
+                right: (start + width) as f32 * TERRAIN_STEP,
+                y,
+                multiplier,
+            });
+        }
+
+        Terrain { points, pads }
+    }
+
+    /// The height of the ground at `x`, interpolated between the terrain points.
+    fn height_at(&self, x: f32) -> f32 {
+        let x = x.max(0.0).min(SCREEN_SIZE.0);
+        let i = ((x / TERRAIN_STEP) as usize).min(self.points.len() - 2);
+        let t = x / TERRAIN_STEP - i as f32;
+        self.points[i] + (self.points[i + 1] - self.points[i]) * t
+    }
+
+    /// The pad both feet are standing on, if any.
+    fn pad_under(&self, left: f32, right: f32) -> Option<&Pad> {
+        self.pads
+            .iter()
+            .find(|pad| left >= pad.left && right <= pad.right)
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let points: Vec<ggez::mint::Point2<f32>> = self
+            .points
+            .iter()
+            .enumerate()
+            .map(|(i, &y)| ggez::mint::Point2 {
+                x: i as f32 * TERRAIN_STEP,
+                y,
+            })
+            .collect();
+        let ground = graphics::Mesh::new_line(ctx, &points, 2.0, [0.8, 0.8, 0.8, 1.0].into())?;
+        graphics::draw(ctx, &ground, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for pad in self.pads.iter() {
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::mint::Point2 {
+                        x: pad.left,
+                        y: pad.y,
+                    },
+                    ggez::mint::Point2 {
+                        x: pad.right,
+                        y: pad.y,
+                    },
+                ],
+                4.0,
+                [0.2, 1.0, 0.4, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+            let label = Text::new(
+                TextFragment::new(format!("x{}", pad.multiplier))
+                    .color([0.2, 1.0, 0.4, 1.0].into())
+                    .scale(Scale::uniform(18.0)),
+            );
+            graphics::draw(
+                ctx,
+                &label,
+                (ggez::mint::Point2 {
+                    x: (pad.left + pad.right) / 2.0 - 10.0,
+                    y: pad.y + 6.0,
+                },),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The lander controlled by the player.
+#[derive(Debug, Copy, Clone)]
+struct Lander {
+    pos: Vec2,
+    velocity: Vec2,
+    /// The tilt of the lander in radians, with zero being upright.
+    angle: f32,
+    fuel: f32,
+    thrusting: bool,
+}
+
+impl Lander {
+    /// Creates a new lander drifting in from the top left of the screen.
+    pub fn new(fuel: f32) -> Self {
+        Lander {
+            pos: Vec2::new(60.0, 60.0),
+            velocity: Vec2::new(40.0, 0.0),
+            angle: 0.0,
+            fuel,
+            thrusting: false,
+        }
+    }
+
+    /// The positions of the left and right feet.
+    fn feet(&self) -> (Vec2, Vec2) {
+        (
+            self.pos + Vec2::new(-LANDER_SIZE.0, LANDER_SIZE.1).rotate(self.angle),
+            self.pos + Vec2::new(LANDER_SIZE.0, LANDER_SIZE.1).rotate(self.angle),
+        )
+    }
+
+    fn update(&mut self, ctx: &Context, dt: f32) {
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+            self.angle -= ROTATE_SPEED * dt;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+            self.angle += ROTATE_SPEED * dt;
+        }
+
+        self.thrusting = keyboard::is_key_pressed(ctx, KeyCode::Up) && self.fuel > 0.0;
+        if self.thrusting {
+            // The engine pushes along the lander's up direction.
+            self.velocity += Vec2::new(0.0, -THRUST * dt).rotate(self.angle);
+            self.fuel = (self.fuel - FUEL_BURN * dt).max(0.0);
+        }
+
+        self.velocity.y += GRAVITY * dt;
+        self.pos += self.velocity * dt;
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let corner = |x: f32, y: f32| {
+            let p = self.pos + Vec2::new(x, y).rotate(self.angle);
+            ggez::mint::Point2 { x: p.x, y: p.y }
+        };
+
+        let body = graphics::Mesh::new_polygon(
+            ctx,
+            DrawMode::stroke(2.0),
+            &[
+                corner(0.0, -LANDER_SIZE.1),
+                corner(LANDER_SIZE.0 * 0.7, 0.0),
+                corner(LANDER_SIZE.0, LANDER_SIZE.1),
+                corner(-LANDER_SIZE.0, LANDER_SIZE.1),
+                corner(-LANDER_SIZE.0 * 0.7, 0.0),
+            ],
+            [1.0, 1.0, 1.0, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &body, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        if self.thrusting {
+            let flame = graphics::Mesh::new_polygon(
+                ctx,
+                DrawMode::fill(),
+                &[
+                    corner(-LANDER_SIZE.0 * 0.4, LANDER_SIZE.1),
+                    corner(LANDER_SIZE.0 * 0.4, LANDER_SIZE.1),
+                    corner(0.0, LANDER_SIZE.1 * 2.0),
+                ],
+                [1.0, 0.6, 0.1, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &flame, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// How a descent ended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Outcome {
+    Flying,
+    /// Landed safely, scoring the given points.
+    Landed(u32),
+    Crashed,
+}
+
+/// The state for the game.
+struct GameState {
+    terrain: Terrain,
+    lander: Lander,
+    outcome: Outcome,
+    score: u32,
+    landings: u32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state.
+    pub fn new() -> Self {
+        GameState {
+            terrain: Terrain::generate(),
+            lander: Lander::new(START_FUEL),
+            outcome: Outcome::Flying,
+            score: 0,
+            landings: 0,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    /// Starts the next descent on a fresh terrain, keeping the leftover fuel.
+    fn next_descent(&mut self) {
+        let fuel = (self.lander.fuel + FUEL_REFILL).min(START_FUEL);
+        self.terrain = Terrain::generate();
+        self.lander = Lander::new(fuel);
+        self.outcome = Outcome::Flying;
+    }
+
+    /// Checks whether the lander has touched the ground, and whether it did so safely.
+    fn check_touchdown(&mut self) {
+        let (left, right) = self.lander.feet();
+        let touching = left.y >= self.terrain.height_at(left.x)
+            || right.y >= self.terrain.height_at(right.x)
+            || self.lander.pos.y >= self.terrain.height_at(self.lander.pos.x);
+        if !touching {
+            return;
+        }
+
+        let velocity = self.lander.velocity;
+        let safe = velocity.y.abs() <= SAFE_VERTICAL_SPEED
+            && velocity.x.abs() <= SAFE_HORIZONTAL_SPEED
+            && self.lander.angle.abs() <= SAFE_ANGLE;
+
+        self.outcome = match self.terrain.pad_under(left.x, right.x) {
+            Some(pad) if safe => {
+                // Reward a gentle touchdown and any fuel left over.
+                let softness = (SAFE_VERTICAL_SPEED - velocity.y.abs()) * 2.0;
+                let points = (50.0 + softness + self.lander.fuel) as u32 * pad.multiplier;
+                self.score += points;
+                self.landings += 1;
+                self.lander.pos.y = pad.y - LANDER_SIZE.1;
+                Outcome::Landed(points)
+            }
+            _ => Outcome::Crashed,
+        };
+        self.lander.velocity = Vec2::ZERO;
+        self.lander.thrusting = false;
+        if self.outcome == Outcome::Crashed {
+            self.record_run();
+        }
+    }
+
+    /// Records the run once it ends in a crash, with every landing before it.
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("landings", f64::from(self.landings)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("lander", &values);
+        }
+        let _ = achievements::check_run("lander", &values);
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if self.outcome == Outcome::Flying {
+            self.lander.update(ctx, dt);
+
+            // Drifting off the side of the screen wraps around.
+            if self.lander.pos.x < 0.0 {
+                self.lander.pos.x += SCREEN_SIZE.0;
+            } else if self.lander.pos.x > SCREEN_SIZE.0 {
+                self.lander.pos.x -= SCREEN_SIZE.0;
+            }
+
+            self.check_touchdown();
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        self.terrain.draw(ctx)?;
+        self.lander.draw(ctx)?;
+
+        let velocity = self.lander.velocity;
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Landings: {}  Fuel: {:.0}  H-Speed: {:.0}  V-Speed: {:.0}",
+                locale::integer(self.score),
+                self.landings,
+                self.lander.fuel,
+                velocity.x,
+                velocity.y
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(20.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        let message = match self.outcome {
+            Outcome::Flying => None,
+            Outcome::Landed(points) => Some((
+                format!("Landed! +{} points. Press Space to continue.", points),
+                [0.2, 1.0, 0.4, 1.0],
+            )),
+            Outcome::Crashed => Some((
+                "CRASHED! Press Enter to play again.".to_string(),
+                [1.0, 0.0, 0.0, 1.0],
+            )),
+        };
+        if let Some((message, color)) = message {
+            let text = Text::new(
+                TextFragment::new(message)
+                    .color(color.into())
+                    .scale(Scale::uniform(32.0)),
+            );
+            graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 8.0, y: 40.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match (keycode, self.outcome) {
+            (KeyCode::Space, Outcome::Landed(_)) => self.next_descent(),
+            (KeyCode::Return, Outcome::Crashed) => *self = GameState::new(),
+            (KeyCode::Escape, _) => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("lander", "Sprial404")
+        .window_setup(window::setup("Lunar Lander"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
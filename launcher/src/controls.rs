@@ -0,0 +1,357 @@
+//! The controls screen, where each player's keys and gamepad action button can be rebound for
+//! every game, and gamepad rumble can be turned down or off.
+
+use ggez::event::{Button, KeyCode};
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::bindings::{self, Binding, Bindings, Control};
+use common::input::{Nav, MAX_PLAYERS};
+use common::rumble::RumbleSettings;
+use common::ui::{self, GridLayout};
+
+/// One column of bindings for each player and one row for each control.
+const LAYOUT: GridLayout = GridLayout {
+    origin: (230.0, 130.0),
+    columns: MAX_PLAYERS,
+    cell: (190.0, 44.0),
+    spacing: 10.0,
+};
+
+const LABEL_X: f32 = 40.0;
+
+const RESET_RECT: Rect = Rect {
+    x: 230.0,
+    y: 480.0,
+    w: 250.0,
+    h: 44.0,
+};
+
+const RUMBLE_RECT: Rect = Rect {
+    x: 230.0,
+    y: 534.0,
+    w: 250.0,
+    h: 44.0,
+};
+
+/// How much each step changes the rumble intensity, as a percentage.
+const RUMBLE_STEP: u32 = 10;
+
+const CELL_COLOR: [f32; 4] = [0.14, 0.14, 0.18, 1.0];
+const WAITING_COLOR: [f32; 4] = [0.45, 0.35, 0.1, 1.0];
+
+/// What is highlighted on the controls screen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Focus {
+    /// A binding, by its index in the layout.
+    Binding(usize),
+    Reset,
+    /// Left and right change the intensity, and confirming turns rumble on or off.
+    Rumble,
+}
+
+/// The controls screen.
+pub struct Controls {
+    bindings: Bindings,
+    focused: Focus,
+    /// The binding waiting for a key or button to be pressed, by its index in the layout.
+    waiting: Option<usize>,
+    rumble: RumbleSettings,
+    message: String,
+}
+
+/// The player and control of the binding at `index` in the layout.
+fn cell(index: usize) -> (usize, Control) {
+    (index % MAX_PLAYERS, Control::ALL[index / MAX_PLAYERS])
+}
+
+fn describe(player: usize, control: Control) -> String {
+    format!("Player {} {}", player + 1, control.title())
+}
+
+impl Controls {
+    /// Loads the current profile's bindings.
+    pub fn load() -> Self {
+        Controls {
+            bindings: Bindings::load(),
+            focused: Focus::Binding(0),
+            waiting: None,
+            rumble: RumbleSettings::load(),
+            message: String::new(),
+        }
+    }
+
+    fn count() -> usize {
+        MAX_PLAYERS * Control::ALL.len()
+    }
+
+    fn focus_at(x: f32, y: f32) -> Option<Focus> {
+        if RESET_RECT.contains(ggez::mint::Point2 { x, y }) {
+            return Some(Focus::Reset);
+        }
+        if RUMBLE_RECT.contains(ggez::mint::Point2 { x, y }) {
+            return Some(Focus::Rumble);
+        }
+        LAYOUT.index_at(x, y, Controls::count()).map(Focus::Binding)
+    }
+
+    /// Moves through the bindings, returning false when the player backs out of the screen.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        if self.waiting.is_some() {
+            if nav == Nav::Back {
+                self.waiting = None;
+                self.message.clear();
+            }
+            return true;
+        }
+
+        let last_row = Controls::count() - MAX_PLAYERS;
+        match (nav, self.focused) {
+            (Nav::Back, _) => return false,
+            (Nav::Confirm, focused) => self.activate(focused),
+            (Nav::Down, Focus::Binding(index)) if index >= last_row => self.focused = Focus::Reset,
+            (Nav::Up, Focus::Reset) => self.focused = Focus::Binding(last_row),
+            (Nav::Down, Focus::Reset) => self.focused = Focus::Rumble,
+            (Nav::Up, Focus::Rumble) => self.focused = Focus::Reset,
+            (Nav::Left, Focus::Rumble) => {
+                let intensity = self.rumble.intensity.saturating_sub(RUMBLE_STEP);
+                self.set_rumble_intensity(intensity);
+            }
+            (Nav::Right, Focus::Rumble) => {
+                let intensity = (self.rumble.intensity + RUMBLE_STEP).min(100);
+                self.set_rumble_intensity(intensity);
+            }
+            (_, Focus::Binding(index)) => {
+                self.focused = Focus::Binding(LAYOUT.step(index, nav, Controls::count()))
+            }
+            _ => (),
+        }
+        true
+    }
+
+    fn activate(&mut self, focus: Focus) {
+        match focus {
+            Focus::Binding(index) => {
+                let (player, control) = cell(index);
+                self.waiting = Some(index);
+                let input = if control.is_button() {
+                    "a gamepad button"
+                } else {
+                    "a key"
+                };
+                self.message = format!(
+                    "Press {} for {}, or Escape to cancel.",
+                    input,
+                    describe(player, control)
+                );
+            }
+            Focus::Reset => {
+                self.bindings = Bindings::default();
+                self.save("Every control is back to its default.".to_string());
+            }
+            Focus::Rumble => {
+                self.rumble.enabled = !self.rumble.enabled;
+                self.save_rumble();
+            }
+        }
+    }
+
+    fn set_rumble_intensity(&mut self, intensity: u32) {
+        if intensity != self.rumble.intensity {
+            self.rumble.intensity = intensity;
+            self.rumble.enabled = true;
+            self.save_rumble();
+        }
+    }
+
+    fn save_rumble(&mut self) {
+        self.message = match self.rumble.save() {
+            Ok(()) => String::new(),
+            Err(e) => format!("Couldn't save the rumble setting: {}", e),
+        };
+    }
+
+    fn rumble_label(&self) -> String {
+        if self.rumble.enabled {
+            format!("Rumble: {}%", self.rumble.intensity)
+        } else {
+            "Rumble: off".to_string()
+        }
+    }
+
+    /// Binds the waiting control, unless another control already uses the key or button.
+    fn bind(&mut self, binding: Binding) {
+        let index = match self.waiting.take() {
+            Some(index) => index,
+            None => return,
+        };
+        let (player, control) = cell(index);
+        if let Some((other, other_control)) = self.bindings.conflict(player, control, binding) {
+            self.message = format!(
+                "{} is already {}.",
+                binding.name(),
+                describe(other, other_control)
+            );
+            return;
+        }
+        self.bindings.set(player, control, binding);
+        self.save(format!("{}: {}", describe(player, control), binding.name()));
+    }
+
+    fn save(&mut self, message: String) {
+        self.message = match self.bindings.save() {
+            Ok(()) => message,
+            Err(e) => format!("Couldn't save the controls: {}", e),
+        };
+    }
+
+    /// Handles a key press, returning whether it was taken to bind a control. Escape is left to
+    /// `navigate`, which cancels.
+    pub fn key_down(&mut self, key: KeyCode) -> bool {
+        let index = match self.waiting {
+            Some(index) => index,
+            None => return false,
+        };
+        if key == KeyCode::Escape {
+            return false;
+        }
+        if cell(index).1.is_button() {
+            return true;
+        }
+        if bindings::BINDABLE_KEYS.contains(&key) {
+            self.bind(Binding::Key(key));
+        } else {
+            self.message = format!("{} can't be bound.", bindings::key_name(key));
+        }
+        true
+    }
+
+    /// Handles a gamepad button press, returning whether it was taken to bind a control. Buttons
+    /// that can't be bound are left to `navigate`, so select cancels.
+    pub fn button_down(&mut self, button: Button) -> bool {
+        match self.waiting {
+            Some(index) if cell(index).1.is_button() => {
+                if bindings::BINDABLE_BUTTONS.contains(&button) {
+                    self.bind(Binding::Button(button));
+                    true
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if self.waiting.is_none() {
+            if let Some(focus) = Controls::focus_at(x, y) {
+                self.focused = focus;
+            }
+        }
+    }
+
+    pub fn click(&mut self, x: f32, y: f32) {
+        if self.waiting.is_none() {
+            if let Some(focus) = Controls::focus_at(x, y) {
+                self.focused = focus;
+                self.activate(focus);
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Controls", [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+
+        let mut builder = graphics::MeshBuilder::new();
+        for index in 0..Controls::count() {
+            let color = if self.waiting == Some(index) {
+                WAITING_COLOR
+            } else {
+                CELL_COLOR
+            };
+            builder.rectangle(DrawMode::fill(), LAYOUT.rect(index), color.into());
+        }
+        builder.rectangle(DrawMode::fill(), RESET_RECT, CELL_COLOR.into());
+        builder.rectangle(DrawMode::fill(), RUMBLE_RECT, CELL_COLOR.into());
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for player in 0..MAX_PLAYERS {
+            let rect = LAYOUT.rect(player);
+            let title = format!("Player {}", player + 1);
+            ui::draw_text(
+                ctx,
+                &title,
+                [1.0, 1.0, 1.0, 1.0],
+                22.0,
+                rect.x,
+                rect.y - 34.0,
+            )?;
+        }
+        for (row, control) in Control::ALL.iter().enumerate() {
+            let rect = LAYOUT.rect(row * MAX_PLAYERS);
+            ui::draw_text(
+                ctx,
+                control.title(),
+                [0.8, 0.8, 0.85, 1.0],
+                20.0,
+                LABEL_X,
+                rect.y + 12.0,
+            )?;
+        }
+        for index in 0..Controls::count() {
+            let (player, control) = cell(index);
+            let rect = LAYOUT.rect(index);
+            let label = if self.waiting == Some(index) {
+                "...".to_string()
+            } else {
+                self.bindings.get(player, control).name()
+            };
+            ui::draw_text(
+                ctx,
+                &label,
+                [1.0, 1.0, 1.0, 1.0],
+                20.0,
+                rect.x + 12.0,
+                rect.y + 12.0,
+            )?;
+            if self.focused == Focus::Binding(index) {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+
+        ui::draw_text(
+            ctx,
+            "Reset to defaults",
+            [1.0, 1.0, 1.0, 1.0],
+            20.0,
+            RESET_RECT.x + 12.0,
+            RESET_RECT.y + 12.0,
+        )?;
+        if self.focused == Focus::Reset {
+            ui::draw_focus(ctx, RESET_RECT)?;
+        }
+        ui::draw_text(
+            ctx,
+            &self.rumble_label(),
+            [1.0, 1.0, 1.0, 1.0],
+            20.0,
+            RUMBLE_RECT.x + 12.0,
+            RUMBLE_RECT.y + 12.0,
+        )?;
+        if self.focused == Focus::Rumble {
+            ui::draw_focus(ctx, RUMBLE_RECT)?;
+        }
+
+        ui::draw_wrapped(
+            ctx,
+            &self.message,
+            [1.0, 0.85, 0.3, 1.0],
+            20.0,
+            LABEL_X,
+            RUMBLE_RECT.y + RUMBLE_RECT.h + 30.0,
+            960.0,
+        )?;
+        Ok(())
+    }
+}
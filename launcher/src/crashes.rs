@@ -0,0 +1,172 @@
+//! The crash reports screen, which the launcher opens when it finds reports left by a game
+//! that crashed. Each report is shown exactly as it would be sent, and is only sent if the
+//! player chooses to.
+
+use ggez::{Context, GameResult};
+
+use common::crash::{self, Report};
+use common::input::Nav;
+use common::ui;
+
+const LABEL_X: f32 = 40.0;
+const TEXT_WIDTH: f32 = 960.0;
+/// Where the report starts, with a line for each line of it below.
+const REPORT_Y: f32 = 200.0;
+const REPORT_LINE_HEIGHT: f32 = 18.0;
+/// How many of the report's lines fit above the footer.
+const REPORT_LINES: usize = 26;
+
+const EXPLANATION: &str = "A game crashed and saved a report of what happened. Sending it helps \
+                           get the crash fixed. Read it first: it has the game's arguments and \
+                           the paths it was built in, and nothing is sent unless you choose to.";
+
+/// The crash reports screen.
+pub struct CrashReports {
+    reports: Vec<Report>,
+    /// The index of the report being shown.
+    shown: usize,
+    /// How many of its lines are scrolled past.
+    scroll: usize,
+    url: Option<String>,
+    message: String,
+}
+
+impl CrashReports {
+    /// Loads the reports that haven't been sent or thrown away, or `None` if there aren't any.
+    pub fn load() -> Option<Self> {
+        let reports = crash::pending().unwrap_or_else(|e| {
+            eprintln!("Couldn't read the crash reports: {}", e);
+            Vec::new()
+        });
+        if reports.is_empty() {
+            return None;
+        }
+        Some(CrashReports {
+            reports,
+            shown: 0,
+            scroll: 0,
+            url: crash::url(),
+            message: String::new(),
+        })
+    }
+
+    fn line_count(&self) -> usize {
+        self.reports[self.shown].contents.lines().count()
+    }
+
+    /// Scrolls the report, moves between reports or sends the one shown, returning false when
+    /// the player backs out of the screen or there are no reports left. Backing out keeps the
+    /// reports for next time.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        match nav {
+            Nav::Up => self.scroll = self.scroll.saturating_sub(1),
+            Nav::Down => {
+                self.scroll = (self.scroll + 1).min(self.line_count().saturating_sub(REPORT_LINES))
+            }
+            Nav::Left | Nav::Right => {
+                let count = self.reports.len();
+                self.shown = if nav == Nav::Left {
+                    (self.shown + count - 1) % count
+                } else {
+                    (self.shown + 1) % count
+                };
+                self.scroll = 0;
+                self.message.clear();
+            }
+            Nav::Confirm => {
+                if self.url.is_none() {
+                    self.message = format!(
+                        "Set {} in the settings to send reports.",
+                        crash::URL_SETTING
+                    );
+                    return true;
+                }
+                match self.reports[self.shown].send() {
+                    Ok(()) => return self.remove("Sent. Thank you!"),
+                    Err(e) => self.message = format!("Couldn't send the report: {}", e),
+                }
+            }
+            Nav::Back => return false,
+        }
+        true
+    }
+
+    /// Deletes the report shown without sending it, returning false if there are no reports
+    /// left.
+    pub fn discard(&mut self) -> bool {
+        match self.reports[self.shown].discard() {
+            Ok(()) => self.remove("Deleted."),
+            Err(e) => {
+                self.message = format!("Couldn't delete the report: {}", e);
+                true
+            }
+        }
+    }
+
+    fn remove(&mut self, message: &str) -> bool {
+        self.reports.remove(self.shown);
+        if self.reports.is_empty() {
+            return false;
+        }
+        self.shown %= self.reports.len();
+        self.scroll = 0;
+        self.message = message.to_string();
+        true
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let title = if self.reports.len() > 1 {
+            format!("Crash report {} of {}", self.shown + 1, self.reports.len())
+        } else {
+            "Crash report".to_string()
+        };
+        ui::draw_text(ctx, &title, [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+        ui::draw_wrapped(
+            ctx,
+            EXPLANATION,
+            [0.8, 0.8, 0.85, 1.0],
+            20.0,
+            LABEL_X,
+            80.0,
+            TEXT_WIDTH,
+        )?;
+
+        let destination = if !self.message.is_empty() {
+            self.message.clone()
+        } else {
+            match &self.url {
+                Some(url) => format!("Reports are sent to {}.", url),
+                None => format!(
+                    "There's nowhere to send reports until {} is set.",
+                    crash::URL_SETTING
+                ),
+            }
+        };
+        ui::draw_text(
+            ctx,
+            &destination,
+            [1.0, 0.85, 0.3, 1.0],
+            18.0,
+            LABEL_X,
+            REPORT_Y - 30.0,
+        )?;
+
+        let report = &self.reports[self.shown].contents;
+        for (index, line) in report
+            .lines()
+            .skip(self.scroll)
+            .take(REPORT_LINES)
+            .enumerate()
+        {
+            ui::draw_text(
+                ctx,
+                line,
+                [0.7, 0.9, 0.7, 1.0],
+                14.0,
+                LABEL_X,
+                REPORT_Y + index as f32 * REPORT_LINE_HEIGHT,
+            )?;
+        }
+        Ok(())
+    }
+}
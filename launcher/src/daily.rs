@@ -0,0 +1,136 @@
+//! The daily challenge screen, showing today's featured game with its leaderboard and the
+//! profile's streak.
+
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::daily::{self, Daily, Run};
+use common::profile;
+use common::registry::{self, GameInfo};
+use common::ui;
+
+const LABEL_X: f32 = 40.0;
+
+/// Where the button to play today's challenge goes.
+const PLAY_RECT: Rect = Rect {
+    x: 40.0,
+    y: 170.0,
+    w: 250.0,
+    h: 48.0,
+};
+
+/// Where the leaderboard is listed.
+const TABLE_ORIGIN: (f32, f32) = (40.0, 270.0);
+const ROW_HEIGHT: f32 = 30.0;
+
+/// How many places on the leaderboard are listed.
+const LEADERBOARD_SIZE: usize = 10;
+
+/// The daily challenge screen.
+pub struct DailyScreen {
+    pub daily: Daily,
+    /// The featured game, if it is still in the registry.
+    pub game: Option<&'static GameInfo>,
+    leaderboard: Vec<Run>,
+    streak: u32,
+    error: Option<String>,
+}
+
+impl DailyScreen {
+    /// Loads today's challenge and its leaderboard.
+    pub fn load() -> Self {
+        let daily = Daily::today();
+        let (leaderboard, error) = match daily.leaderboard() {
+            Ok(leaderboard) => (leaderboard, None),
+            Err(e) => (
+                Vec::new(),
+                Some(format!("Couldn't read the leaderboard: {}", e)),
+            ),
+        };
+        DailyScreen {
+            daily,
+            game: registry::find(daily.challenge.game),
+            leaderboard,
+            streak: daily::streak().unwrap_or(0),
+            error,
+        }
+    }
+
+    /// Whether `(x, y)` is over the play button.
+    pub fn play_at(&self, x: f32, y: f32) -> bool {
+        PLAY_RECT.contains(ggez::mint::Point2 { x, y })
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(
+            ctx,
+            "Daily Challenge",
+            [1.0, 1.0, 1.0, 1.0],
+            44.0,
+            LABEL_X,
+            20.0,
+        )?;
+        ui::draw_text(
+            ctx,
+            &self.daily.title(),
+            [1.0, 0.85, 0.3, 1.0],
+            28.0,
+            LABEL_X,
+            90.0,
+        )?;
+        let streak = match self.streak {
+            0 => "Play every day to build a streak.".to_string(),
+            1 => "Streak: 1 day".to_string(),
+            days => format!("Streak: {} days", days),
+        };
+        ui::draw_text(ctx, &streak, [0.8, 0.8, 0.85, 1.0], 20.0, LABEL_X, 130.0)?;
+
+        let color = self.game.map_or([0.22, 0.22, 0.28, 1.0], |game| game.color);
+        let button = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), PLAY_RECT, color.into())?;
+        graphics::draw(ctx, &button, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        ui::draw_text(
+            ctx,
+            "Play",
+            [0.0, 0.0, 0.0, 1.0],
+            24.0,
+            PLAY_RECT.x + 14.0,
+            PLAY_RECT.y + 12.0,
+        )?;
+        ui::draw_focus(ctx, PLAY_RECT)?;
+
+        let (x, mut y) = TABLE_ORIGIN;
+        let heading = format!("Today's best: {}", self.daily.challenge.score.label);
+        ui::draw_text(ctx, &heading, [1.0, 1.0, 1.0, 1.0], 24.0, x, y)?;
+        y += ROW_HEIGHT + 10.0;
+
+        if let Some(error) = &self.error {
+            ui::draw_text(ctx, error, [1.0, 0.85, 0.3, 1.0], 20.0, x, y)?;
+            return Ok(());
+        }
+        if self.leaderboard.is_empty() {
+            ui::draw_text(
+                ctx,
+                "Nobody has played today's challenge yet.",
+                [0.8, 0.8, 0.85, 1.0],
+                20.0,
+                x,
+                y,
+            )?;
+        }
+        let current = profile::current();
+        for (place, run) in self.leaderboard.iter().take(LEADERBOARD_SIZE).enumerate() {
+            let color = if run.profile == current {
+                [1.0, 0.85, 0.3, 1.0]
+            } else {
+                [0.8, 0.8, 0.85, 1.0]
+            };
+            let place = format!("{}.", place + 1);
+            ui::draw_text(ctx, &place, color, 20.0, x, y)?;
+            ui::draw_text(ctx, &run.profile, color, 20.0, x + 50.0, y)?;
+            let score = run.score.to_string();
+            ui::draw_text(ctx, &score, color, 20.0, x + 350.0, y)?;
+            y += ROW_HEIGHT;
+        }
+        Ok(())
+    }
+}
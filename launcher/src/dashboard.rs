@@ -0,0 +1,358 @@
+//! The statistics dashboard: how long each game has been played, how a game's scores have gone
+//! over time and the record set in each game.
+
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::input::Nav;
+use common::locale;
+use common::playtime;
+use common::registry::{self, GameInfo, ScoreKey, GAMES};
+use common::stats::{format_value, Record, StatsDb};
+use common::ui;
+
+/// Where the playtime bars go, down the left.
+const PLAYTIME_RECT: Rect = Rect {
+    x: 40.0,
+    y: 130.0,
+    w: 420.0,
+    h: 260.0,
+};
+const BAR_HEIGHT: f32 = 18.0;
+const BAR_SPACING: f32 = 8.0;
+/// Room left of the bars for the game's title.
+const BAR_LABEL_WIDTH: f32 = 150.0;
+
+/// Where the score trend is charted, on the right.
+const CHART_RECT: Rect = Rect {
+    x: 540.0,
+    y: 140.0,
+    w: 460.0,
+    h: 230.0,
+};
+
+/// How many of a game's latest runs are charted.
+const CHART_RUNS: usize = 30;
+
+/// Where the records are listed, along the bottom.
+const RECORDS_ORIGIN: (f32, f32) = (40.0, 430.0);
+const RECORD_ROW_HEIGHT: f32 = 24.0;
+/// How many records fit in each of the two columns.
+const RECORDS_PER_COLUMN: usize = 9;
+const RECORDS_COLUMN_WIDTH: f32 = 480.0;
+
+const PANEL_COLOR: [f32; 4] = [0.14, 0.14, 0.18, 1.0];
+const HEADER_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const RECORD_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+
+/// A game that ranks its runs and has some recorded.
+struct GameRuns {
+    game: &'static GameInfo,
+    key: ScoreKey,
+    /// Oldest first.
+    runs: Vec<Record>,
+    /// The best run, by its index in `runs`.
+    best: usize,
+}
+
+impl GameRuns {
+    fn value(&self, index: usize) -> f64 {
+        self.runs[index].get(self.key.key).unwrap_or(0.0)
+    }
+}
+
+/// The statistics dashboard screen.
+pub struct Dashboard {
+    /// Seconds played of each game in the registry, most played first.
+    playtime: Vec<(&'static GameInfo, u64)>,
+    games: Vec<GameRuns>,
+    /// The game whose scores are charted, by its index in `games`.
+    charted: usize,
+    error: Option<String>,
+}
+
+/// Formats seconds as hours and minutes.
+fn format_duration(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
+
+impl Dashboard {
+    /// Loads the current profile's playtime and every recorded run.
+    pub fn load() -> Self {
+        let mut errors = Vec::new();
+        let playtime = playtime::load()
+            .unwrap_or_else(|e| {
+                errors.push(format!("Couldn't read the playtime: {}", e));
+                Vec::new()
+            })
+            .into_iter()
+            .filter_map(|(name, seconds)| Some((registry::find(&name)?, seconds)))
+            .collect();
+        let records = StatsDb::open()
+            .and_then(|stats| stats.records())
+            .unwrap_or_else(|e| {
+                errors.push(format!("Couldn't read the scores: {}", e));
+                Vec::new()
+            });
+
+        let games = GAMES
+            .iter()
+            .filter_map(|game| {
+                let key = game.score?;
+                let runs: Vec<Record> = records
+                    .iter()
+                    .filter(|record| record.game == game.name && record.get(key.key).is_some())
+                    .cloned()
+                    .collect();
+                let mut best = 0;
+                for (index, run) in runs.iter().enumerate() {
+                    let value = run.get(key.key).unwrap_or(0.0);
+                    if key.beats(value, runs[best].get(key.key).unwrap_or(0.0)) {
+                        best = index;
+                    }
+                }
+                if runs.is_empty() {
+                    None
+                } else {
+                    Some(GameRuns {
+                        game,
+                        key,
+                        runs,
+                        best,
+                    })
+                }
+            })
+            .collect();
+
+        Dashboard {
+            playtime,
+            games,
+            charted: 0,
+            error: if errors.is_empty() {
+                None
+            } else {
+                Some(errors.join(" "))
+            },
+        }
+    }
+
+    /// Picks the charted game, returning false when the player backs out of the dashboard.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        let count = self.games.len();
+        match nav {
+            Nav::Back => return false,
+            Nav::Left | Nav::Up if count > 0 => self.charted = (self.charted + count - 1) % count,
+            Nav::Right | Nav::Down if count > 0 => self.charted = (self.charted + 1) % count,
+            _ => (),
+        }
+        true
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Statistics", TEXT_COLOR, 44.0, 40.0, 20.0)?;
+        let total: u64 = self.playtime.iter().map(|(_, seconds)| seconds).sum();
+        ui::draw_text(
+            ctx,
+            &format!("Total playtime: {}", format_duration(total)),
+            RECORD_COLOR,
+            26.0,
+            CHART_RECT.x,
+            34.0,
+        )?;
+        if let Some(error) = &self.error {
+            ui::draw_text(ctx, error, [1.0, 0.4, 0.4, 1.0], 18.0, 40.0, 76.0)?;
+        }
+
+        self.draw_playtime(ctx)?;
+        self.draw_chart(ctx)?;
+        self.draw_records(ctx)
+    }
+
+    /// Draws a bar for each game played, scaled to the most played.
+    fn draw_playtime(&self, ctx: &mut Context) -> GameResult<()> {
+        let area = PLAYTIME_RECT;
+        ui::draw_text(ctx, "Playtime", HEADER_COLOR, 18.0, area.x, area.y - 30.0)?;
+        if self.playtime.is_empty() {
+            return ui::draw_text(ctx, "Nothing played yet.", TEXT_COLOR, 18.0, area.x, area.y);
+        }
+
+        let rows = ((area.h + BAR_SPACING) / (BAR_HEIGHT + BAR_SPACING)) as usize;
+        let most = self.playtime[0].1.max(1) as f32;
+        let full = area.w - BAR_LABEL_WIDTH - 70.0;
+        let mut builder = graphics::MeshBuilder::new();
+        for (row, (game, seconds)) in self.playtime.iter().take(rows).enumerate() {
+            let y = area.y + row as f32 * (BAR_HEIGHT + BAR_SPACING);
+            let width = (full * *seconds as f32 / most).max(2.0);
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(area.x + BAR_LABEL_WIDTH, y, width, BAR_HEIGHT),
+                game.color.into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for (row, (game, seconds)) in self.playtime.iter().take(rows).enumerate() {
+            let y = area.y + row as f32 * (BAR_HEIGHT + BAR_SPACING);
+            let width = (full * *seconds as f32 / most).max(2.0);
+            ui::draw_text(ctx, game.title, TEXT_COLOR, 16.0, area.x, y)?;
+            ui::draw_text(
+                ctx,
+                &format_duration(*seconds),
+                HEADER_COLOR,
+                16.0,
+                area.x + BAR_LABEL_WIDTH + width + 8.0,
+                y,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the charted game's latest runs as a line, with its best run marked.
+    fn draw_chart(&self, ctx: &mut Context) -> GameResult<()> {
+        let area = CHART_RECT;
+        let scores = match self.games.get(self.charted) {
+            Some(scores) => scores,
+            None => {
+                ui::draw_text(
+                    ctx,
+                    "Score trend",
+                    HEADER_COLOR,
+                    18.0,
+                    area.x,
+                    area.y - 40.0,
+                )?;
+                return ui::draw_text(
+                    ctx,
+                    "No scores recorded yet.",
+                    TEXT_COLOR,
+                    18.0,
+                    area.x,
+                    area.y,
+                );
+            }
+        };
+        let title = format!("Score trend: {} ({})", scores.game.title, scores.key.label);
+        ui::draw_text(ctx, &title, HEADER_COLOR, 18.0, area.x, area.y - 40.0)?;
+
+        let first = scores.runs.len().saturating_sub(CHART_RUNS);
+        let values: Vec<f64> = (first..scores.runs.len())
+            .map(|index| scores.value(index))
+            .collect();
+        let low = values.iter().cloned().fold(f64::INFINITY, f64::min);
+        let high = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let range = (high - low).max(1.0);
+        let step = area.w / (values.len().max(2) - 1) as f32;
+        let point = |index: usize, value: f64| {
+            let mut y = ((value - low) / range) as f32;
+            // Keep better runs at the top of the chart.
+            if scores.key.lower_is_better {
+                y = 1.0 - y;
+            }
+            ggez::mint::Point2 {
+                x: area.x + index as f32 * step,
+                y: area.y + area.h - y * area.h,
+            }
+        };
+        let points: Vec<ggez::mint::Point2<f32>> = values
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| point(index, value))
+            .collect();
+
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(DrawMode::fill(), area, PANEL_COLOR.into());
+        if points.len() > 1 {
+            builder.line(&points, 3.0, scores.game.color.into())?;
+        }
+        for &p in points.iter() {
+            builder.circle(DrawMode::fill(), p, 4.0, 0.5, scores.game.color.into());
+        }
+        if scores.best >= first {
+            let best = point(scores.best - first, scores.value(scores.best));
+            builder.circle(DrawMode::stroke(2.0), best, 8.0, 0.5, RECORD_COLOR.into());
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let (top, bottom) = if scores.key.lower_is_better {
+            (low, high)
+        } else {
+            (high, low)
+        };
+        ui::draw_text(
+            ctx,
+            &format_value(top),
+            HEADER_COLOR,
+            14.0,
+            area.x - 50.0,
+            area.y,
+        )?;
+        ui::draw_text(
+            ctx,
+            &format_value(bottom),
+            HEADER_COLOR,
+            14.0,
+            area.x - 50.0,
+            area.y + area.h - 16.0,
+        )?;
+        ui::draw_text(
+            ctx,
+            &format!("Last {} of {} runs", values.len(), scores.runs.len()),
+            HEADER_COLOR,
+            14.0,
+            area.x,
+            area.y + area.h + 8.0,
+        )?;
+        Ok(())
+    }
+
+    /// Lists the best run of every game with runs recorded, in two columns.
+    fn draw_records(&self, ctx: &mut Context) -> GameResult<()> {
+        let (x, y) = RECORDS_ORIGIN;
+        ui::draw_text(ctx, "Records", HEADER_COLOR, 18.0, x, y - 30.0)?;
+        if self.games.is_empty() {
+            return ui::draw_text(ctx, "No records set yet.", TEXT_COLOR, 18.0, x, y);
+        }
+        for (index, scores) in self.games.iter().take(RECORDS_PER_COLUMN * 2).enumerate() {
+            let column = (index / RECORDS_PER_COLUMN) as f32;
+            let row = (index % RECORDS_PER_COLUMN) as f32;
+            let x = x + column * RECORDS_COLUMN_WIDTH;
+            let y = y + row * RECORD_ROW_HEIGHT;
+            let best = &scores.runs[scores.best];
+            let color = if index == self.charted {
+                RECORD_COLOR
+            } else {
+                TEXT_COLOR
+            };
+            ui::draw_text(ctx, scores.game.title, color, 16.0, x, y)?;
+            ui::draw_text(
+                ctx,
+                &format!(
+                    "{} {}",
+                    format_value(scores.value(scores.best)),
+                    scores.key.label
+                ),
+                color,
+                16.0,
+                x + 160.0,
+                y,
+            )?;
+            ui::draw_text(
+                ctx,
+                &locale::date(best.timestamp),
+                HEADER_COLOR,
+                16.0,
+                x + 340.0,
+                y,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,223 @@
+//! The display screen, where the profile picks how the games' windows are shown, on which
+//! monitor, how often they draw, whether games pause in the background, and whether motion is
+//! reduced.
+//!
+//! Changes are saved and applied to the launcher's own window as soon as they are made, apart
+//! from vsync, which needs the window opening again. Games pick them up when they are next
+//! started.
+
+use ggez::graphics::Rect;
+use ggez::{Context, GameResult};
+
+use common::input::Nav;
+use common::ui;
+use common::window::{self, DisplayMode, DisplaySettings, FRAME_CAPS};
+
+const LABEL_X: f32 = 40.0;
+/// Where the first row's value goes, with the rest below it.
+const ROW_ORIGIN: (f32, f32) = (280.0, 150.0);
+const ROW_SIZE: (f32, f32) = (560.0, 36.0);
+const ROW_SPACING: f32 = 70.0;
+
+const ROWS: usize = 6;
+const MODE_ROW: usize = 0;
+const MONITOR_ROW: usize = 1;
+const VSYNC_ROW: usize = 2;
+const FRAME_CAP_ROW: usize = 3;
+const PAUSE_ROW: usize = 4;
+const MOTION_ROW: usize = 5;
+
+/// The display screen.
+pub struct DisplayScreen {
+    settings: DisplaySettings,
+    /// Whether the launcher's window was opened with vsync, to tell when it needs opening again.
+    vsync_opened: bool,
+    /// The names of the monitors, when the screen was opened.
+    monitors: Vec<String>,
+    /// The index of the focused row.
+    focused: usize,
+    message: String,
+}
+
+fn row_rect(index: usize) -> Rect {
+    Rect::new(
+        ROW_ORIGIN.0,
+        ROW_ORIGIN.1 + index as f32 * ROW_SPACING,
+        ROW_SIZE.0,
+        ROW_SIZE.1,
+    )
+}
+
+fn on_off(on: bool) -> String {
+    if on { "On" } else { "Off" }.to_string()
+}
+
+impl DisplayScreen {
+    /// Loads the current profile's display settings and the monitors there are.
+    pub fn load(ctx: &Context) -> Self {
+        DisplayScreen {
+            settings: DisplaySettings::load(),
+            vsync_opened: ctx.conf.window_setup.vsync,
+            monitors: window::monitors(ctx),
+            focused: 0,
+            message: String::new(),
+        }
+    }
+
+    pub fn settings(&self) -> &DisplaySettings {
+        &self.settings
+    }
+
+    /// Moves between the rows and changes their settings, returning false when the player
+    /// backs out of the screen.
+    pub fn navigate(&mut self, ctx: &mut Context, nav: Nav) -> bool {
+        match nav {
+            Nav::Back => return false,
+            Nav::Up => self.focused = self.focused.saturating_sub(1),
+            Nav::Down => self.focused = (self.focused + 1).min(ROWS - 1),
+            Nav::Left => self.step(ctx, self.focused, false),
+            Nav::Right | Nav::Confirm => self.step(ctx, self.focused, true),
+        }
+        true
+    }
+
+    fn row_at(x: f32, y: f32) -> Option<usize> {
+        (0..ROWS).find(|&index| row_rect(index).contains(ggez::mint::Point2 { x, y }))
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if let Some(index) = DisplayScreen::row_at(x, y) {
+            self.focused = index;
+        }
+    }
+
+    /// Moves the clicked row on to its next choice.
+    pub fn click(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        if let Some(index) = DisplayScreen::row_at(x, y) {
+            self.focused = index;
+            self.step(ctx, index, true);
+        }
+    }
+
+    /// Moves a row to its next or previous choice, wrapping around, then saves and applies the
+    /// settings.
+    fn step(&mut self, ctx: &mut Context, row: usize, forwards: bool) {
+        let step = |index: usize, count: usize| {
+            if forwards {
+                (index + 1) % count
+            } else {
+                (index + count - 1) % count
+            }
+        };
+        match row {
+            MODE_ROW => {
+                let modes = &DisplayMode::ALL;
+                let index = modes
+                    .iter()
+                    .position(|&mode| mode == self.settings.mode)
+                    .unwrap_or(0);
+                self.settings.mode = modes[step(index, modes.len())];
+            }
+            MONITOR_ROW => {
+                if self.monitors.is_empty() {
+                    return;
+                }
+                let index = self.settings.monitor.min(self.monitors.len() - 1);
+                self.settings.monitor = step(index, self.monitors.len());
+            }
+            VSYNC_ROW => self.settings.vsync = !self.settings.vsync,
+            FRAME_CAP_ROW => {
+                let index = FRAME_CAPS
+                    .iter()
+                    .position(|&cap| cap == self.settings.frame_cap)
+                    .unwrap_or(0);
+                self.settings.frame_cap = FRAME_CAPS[step(index, FRAME_CAPS.len())];
+            }
+            PAUSE_ROW => self.settings.pause_unfocused = !self.settings.pause_unfocused,
+            MOTION_ROW => self.settings.reduced_motion = !self.settings.reduced_motion,
+            _ => return,
+        }
+
+        self.message = match self.settings.save() {
+            Ok(()) => String::new(),
+            Err(e) => format!("Couldn't save the display settings: {}", e),
+        };
+        if let Err(e) = window::apply(ctx, &self.settings) {
+            self.message = format!("Couldn't change the display mode: {}", e);
+        }
+    }
+
+    fn monitor_title(&self) -> String {
+        match self.monitors.get(self.settings.monitor) {
+            Some(name) => format!("{}: {}", self.settings.monitor + 1, name),
+            None => format!("{}: not connected", self.settings.monitor + 1),
+        }
+    }
+
+    fn frame_cap_title(&self) -> String {
+        match self.settings.frame_cap {
+            0 => "Unlimited".to_string(),
+            cap => format!("{} a second", cap),
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Display", [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+
+        let rows = [
+            ("Mode", self.settings.mode.title().to_string()),
+            ("Monitor", self.monitor_title()),
+            ("VSync", on_off(self.settings.vsync)),
+            ("Frame cap", self.frame_cap_title()),
+            ("Pause in background", on_off(self.settings.pause_unfocused)),
+            ("Reduced motion", on_off(self.settings.reduced_motion)),
+        ];
+        for (index, (label, value)) in rows.iter().enumerate() {
+            let rect = row_rect(index);
+            ui::draw_text(
+                ctx,
+                label,
+                [0.8, 0.8, 0.85, 1.0],
+                22.0,
+                LABEL_X,
+                rect.y + 6.0,
+            )?;
+            ui::draw_text(
+                ctx,
+                &format!("< {} >", value),
+                [1.0, 1.0, 1.0, 1.0],
+                22.0,
+                rect.x + 8.0,
+                rect.y + 6.0,
+            )?;
+            if index == self.focused {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+
+        let note = if self.settings.vsync != self.vsync_opened {
+            "VSync changes when the launcher is next started."
+        } else if self.monitors.len() < 2 {
+            "There's only one monitor connected."
+        } else {
+            ""
+        };
+        ui::draw_text(
+            ctx,
+            note,
+            [0.8, 0.8, 0.85, 1.0],
+            20.0,
+            LABEL_X,
+            row_rect(ROWS).y,
+        )?;
+        ui::draw_text(
+            ctx,
+            &self.message,
+            [1.0, 0.85, 0.3, 1.0],
+            20.0,
+            LABEL_X,
+            row_rect(ROWS).y + 40.0,
+        )?;
+        Ok(())
+    }
+}
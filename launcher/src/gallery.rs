@@ -0,0 +1,413 @@
+//! The gallery of screenshots and clips saved from the games. Each is shown as a thumbnail and
+//! can be viewed full size, deleted or found in its folder.
+
+use ggez::graphics::{self, DrawMode, DrawParam, Image, Rect};
+use ggez::{Context, GameResult};
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use common::capture;
+use common::input::Nav;
+use common::registry;
+use common::stats::age;
+use common::ui::{self, GridLayout};
+
+const LAYOUT: GridLayout = GridLayout {
+    origin: (40.0, 100.0),
+    columns: 4,
+    cell: (230.0, 150.0),
+    spacing: 20.0,
+};
+
+/// The space under each thumbnail for its caption.
+const CAPTION_HEIGHT: f32 = 40.0;
+
+/// How many rows of thumbnails fit on screen at once.
+const VISIBLE_ROWS: usize = 3;
+
+/// Where a capture is shown when it is viewed full size.
+const VIEW_RECT: Rect = Rect {
+    x: 40.0,
+    y: 100.0,
+    w: 960.0,
+    h: 510.0,
+};
+
+const MESSAGE_Y: f32 = 625.0;
+
+const CELL_COLOR: [f32; 4] = [0.14, 0.14, 0.18, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const DETAIL_COLOR: [f32; 4] = [0.8, 0.8, 0.85, 1.0];
+const MESSAGE_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+
+/// The program that opens a folder in the system's file manager.
+#[cfg(target_os = "windows")]
+const FILE_MANAGER: &str = "explorer";
+#[cfg(target_os = "macos")]
+const FILE_MANAGER: &str = "open";
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const FILE_MANAGER: &str = "xdg-open";
+
+/// A saved screenshot or clip.
+struct Capture {
+    path: PathBuf,
+    name: String,
+    /// The game it was taken in, or its name if that isn't known.
+    title: String,
+    /// When it was saved, in seconds since the unix epoch.
+    saved: u64,
+    thumbnail: Option<Image>,
+}
+
+impl Capture {
+    fn load(ctx: &mut Context, path: PathBuf) -> Option<Self> {
+        if !capture::is_capture(&path) {
+            return None;
+        }
+        let name = path.file_name()?.to_string_lossy().into_owned();
+        let stem = path.file_stem()?.to_string_lossy().into_owned();
+        // Captures are named after the game they were taken in and when.
+        let title = stem
+            .rsplitn(2, '-')
+            .nth(1)
+            .and_then(registry::find)
+            .map_or(stem.clone(), |game| game.title.to_string());
+        let saved = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let thumbnail = capture::load_thumbnail(
+            ctx,
+            &path,
+            LAYOUT.cell.0 as u32,
+            (LAYOUT.cell.1 - CAPTION_HEIGHT) as u32,
+        )
+        .ok();
+        Some(Capture {
+            path,
+            name,
+            title,
+            saved,
+            thumbnail,
+        })
+    }
+
+    fn kind(&self) -> &'static str {
+        if capture::is_clip(&self.path) {
+            "clip"
+        } else {
+            "screenshot"
+        }
+    }
+}
+
+/// Every saved capture, newest first.
+fn read_captures(ctx: &mut Context) -> io::Result<Vec<Capture>> {
+    let mut captures: Vec<Capture> = fs::read_dir(capture::dir()?)?
+        .filter_map(|entry| Capture::load(ctx, entry.ok()?.path()))
+        .collect();
+    captures.sort_by(|a, b| b.saved.cmp(&a.saved));
+    Ok(captures)
+}
+
+/// A capture being viewed full size.
+struct Viewer {
+    /// Each frame and how long it is shown for.
+    frames: Vec<(Image, Duration)>,
+    opened: Instant,
+}
+
+impl Viewer {
+    /// The frame to show now, going round a clip's frames again once they have all been shown.
+    fn frame(&self) -> Option<&Image> {
+        let length: Duration = self.frames.iter().map(|(_, delay)| *delay).sum();
+        if length.as_millis() == 0 {
+            return self.frames.first().map(|(image, _)| image);
+        }
+        let mut time = self.opened.elapsed().as_millis() % length.as_millis();
+        for (image, delay) in &self.frames {
+            if time < delay.as_millis() {
+                return Some(image);
+            }
+            time -= delay.as_millis();
+        }
+        self.frames.last().map(|(image, _)| image)
+    }
+}
+
+/// Draws `image` as large as it fits in `area`, in the middle of it.
+fn draw_fitted(ctx: &mut Context, image: &Image, area: Rect) -> GameResult<()> {
+    let (width, height) = (image.width() as f32, image.height() as f32);
+    let scale = (area.w / width).min(area.h / height);
+    graphics::draw(
+        ctx,
+        image,
+        DrawParam::new()
+            .dest(ggez::mint::Point2 {
+                x: area.x + (area.w - width * scale) / 2.0,
+                y: area.y + (area.h - height * scale) / 2.0,
+            })
+            .scale(ggez::mint::Vector2 { x: scale, y: scale }),
+    )
+}
+
+/// The gallery screen.
+pub struct Gallery {
+    /// Newest first.
+    captures: Vec<Capture>,
+    /// The index of the focused capture.
+    focused: usize,
+    /// The first row of thumbnails on screen.
+    scroll: usize,
+    /// The focused capture at full size, while it is being viewed.
+    viewer: Option<Viewer>,
+    /// Whether deleting the focused capture is waiting to be confirmed.
+    confirm_delete: bool,
+    message: String,
+}
+
+impl Gallery {
+    /// Loads every saved capture's thumbnail.
+    pub fn load(ctx: &mut Context) -> Self {
+        let (captures, message) = match read_captures(ctx) {
+            Ok(captures) => (captures, String::new()),
+            Err(e) => (Vec::new(), format!("Couldn't read the captures: {}", e)),
+        };
+        Gallery {
+            captures,
+            focused: 0,
+            scroll: 0,
+            viewer: None,
+            confirm_delete: false,
+            message,
+        }
+    }
+
+    /// Moves through the captures and views them, returning false when the player backs out
+    /// of the gallery.
+    pub fn navigate(&mut self, ctx: &mut Context, nav: Nav) -> bool {
+        self.cancel_delete();
+        let count = self.captures.len();
+        match nav {
+            Nav::Back if self.viewer.is_some() => self.viewer = None,
+            Nav::Back => return false,
+            Nav::Confirm if self.viewer.is_some() => self.viewer = None,
+            Nav::Confirm => self.view(ctx),
+            _ if count == 0 => (),
+            Nav::Left if self.viewer.is_some() && self.focused > 0 => {
+                self.focused -= 1;
+                self.view(ctx);
+            }
+            Nav::Right if self.viewer.is_some() && self.focused + 1 < count => {
+                self.focused += 1;
+                self.view(ctx);
+            }
+            _ if self.viewer.is_some() => (),
+            _ => self.focused = LAYOUT.step(self.focused, nav, count),
+        }
+        self.scroll_to_focus();
+        true
+    }
+
+    /// Scrolls just far enough to show the focused capture.
+    fn scroll_to_focus(&mut self) {
+        let row = self.focused / LAYOUT.columns;
+        if row < self.scroll {
+            self.scroll = row;
+        } else if row >= self.scroll + VISIBLE_ROWS {
+            self.scroll = row + 1 - VISIBLE_ROWS;
+        }
+    }
+
+    /// The indices of the captures on screen.
+    fn visible(&self) -> std::ops::Range<usize> {
+        let first = self.scroll * LAYOUT.columns;
+        first..(first + VISIBLE_ROWS * LAYOUT.columns).min(self.captures.len())
+    }
+
+    /// The rectangle of a capture's cell on screen, taking the scroll into account.
+    fn cell_rect(&self, index: usize) -> Rect {
+        LAYOUT.rect(index - self.scroll * LAYOUT.columns)
+    }
+
+    fn capture_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.visible()
+            .find(|&index| self.cell_rect(index).contains(ggez::mint::Point2 { x, y }))
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if self.viewer.is_some() {
+            return;
+        }
+        if let Some(index) = self.capture_at(x, y) {
+            if index != self.focused {
+                self.focused = index;
+                self.cancel_delete();
+            }
+        }
+    }
+
+    /// Views the capture clicked, or closes the one being viewed.
+    pub fn click(&mut self, ctx: &mut Context, x: f32, y: f32) {
+        if self.viewer.is_some() {
+            self.viewer = None;
+        } else if let Some(index) = self.capture_at(x, y) {
+            self.focused = index;
+            self.cancel_delete();
+            self.view(ctx);
+        }
+    }
+
+    /// Views the focused capture full size.
+    fn view(&mut self, ctx: &mut Context) {
+        let capture = match self.captures.get(self.focused) {
+            Some(capture) => capture,
+            None => return,
+        };
+        match capture::load(ctx, &capture.path) {
+            Ok(frames) => {
+                self.viewer = Some(Viewer {
+                    frames,
+                    opened: Instant::now(),
+                })
+            }
+            Err(e) => self.message = format!("Couldn't open {}: {}", capture.name, e),
+        }
+    }
+
+    /// Deletes the focused capture. The first time it is asked for only asks to confirm.
+    pub fn delete(&mut self) {
+        let capture = match self.captures.get(self.focused) {
+            Some(capture) => capture,
+            None => return,
+        };
+        if !self.confirm_delete {
+            self.confirm_delete = true;
+            self.message = format!("Press Delete or X again to delete {}.", capture.name);
+            return;
+        }
+        self.confirm_delete = false;
+        self.message = match fs::remove_file(&capture.path) {
+            Ok(()) => {
+                let message = format!("Deleted {}.", capture.name);
+                self.captures.remove(self.focused);
+                self.focused = self.focused.min(self.captures.len().saturating_sub(1));
+                self.viewer = None;
+                self.scroll_to_focus();
+                message
+            }
+            Err(e) => format!("Couldn't delete {}: {}", capture.name, e),
+        };
+    }
+
+    /// Drops a delete that is waiting to be confirmed, along with the message asking for it.
+    fn cancel_delete(&mut self) {
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            self.message.clear();
+        }
+    }
+
+    /// Opens the folder the captures are saved in with the system's file manager.
+    pub fn open_folder(&mut self) {
+        let opened = capture::dir().and_then(|dir| Command::new(FILE_MANAGER).arg(dir).spawn());
+        if let Err(e) = opened {
+            self.message = format!("Couldn't open the captures folder: {}", e);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Gallery", TEXT_COLOR, 44.0, 40.0, 20.0)?;
+        let clips = self
+            .captures
+            .iter()
+            .filter(|capture| capture::is_clip(&capture.path))
+            .count();
+        let summary = format!(
+            "{} screenshots, {} clips",
+            self.captures.len() - clips,
+            clips
+        );
+        ui::draw_text(ctx, &summary, DETAIL_COLOR, 20.0, 700.0, 36.0)?;
+
+        match &self.viewer {
+            Some(viewer) => self.draw_viewer(ctx, viewer)?,
+            None => self.draw_thumbnails(ctx)?,
+        }
+
+        ui::draw_text(ctx, &self.message, MESSAGE_COLOR, 20.0, 40.0, MESSAGE_Y)
+    }
+
+    fn draw_thumbnails(&self, ctx: &mut Context) -> GameResult<()> {
+        if self.captures.is_empty() {
+            return ui::draw_wrapped(
+                ctx,
+                "Nothing saved yet. Press F12 in a game to take a screenshot, or F10 to start \
+                 and stop recording a clip.",
+                DETAIL_COLOR,
+                22.0,
+                40.0,
+                LAYOUT.origin.1,
+                960.0,
+            );
+        }
+
+        let mut builder = graphics::MeshBuilder::new();
+        for index in self.visible() {
+            builder.rectangle(DrawMode::fill(), self.cell_rect(index), CELL_COLOR.into());
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for index in self.visible() {
+            let capture = &self.captures[index];
+            let rect = self.cell_rect(index);
+            if let Some(thumbnail) = &capture.thumbnail {
+                let area = Rect::new(rect.x, rect.y, rect.w, rect.h - CAPTION_HEIGHT);
+                draw_fitted(ctx, thumbnail, area)?;
+            }
+            let caption = format!("{} {}", capture.title, capture.kind());
+            let caption_y = rect.y + rect.h - CAPTION_HEIGHT + 2.0;
+            ui::draw_text(ctx, &caption, TEXT_COLOR, 16.0, rect.x + 8.0, caption_y)?;
+            let saved = age(capture.saved);
+            ui::draw_text(
+                ctx,
+                &saved,
+                DETAIL_COLOR,
+                14.0,
+                rect.x + 8.0,
+                caption_y + 20.0,
+            )?;
+            if index == self.focused {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_viewer(&self, ctx: &mut Context, viewer: &Viewer) -> GameResult<()> {
+        if let Some(frame) = viewer.frame() {
+            draw_fitted(ctx, frame, VIEW_RECT)?;
+        }
+        if !self.message.is_empty() {
+            return Ok(());
+        }
+        if let Some(capture) = self.captures.get(self.focused) {
+            let caption = format!(
+                "{} {}, {} ({} of {})",
+                capture.title,
+                capture.kind(),
+                age(capture.saved),
+                self.focused + 1,
+                self.captures.len()
+            );
+            ui::draw_text(ctx, &caption, DETAIL_COLOR, 20.0, 40.0, MESSAGE_Y)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,401 @@
+//! The high score hub, gathering every game's best and most recent runs from the stats store.
+
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::achievements::Unlocks;
+use common::input::Nav;
+use common::registry::{self, GameInfo, ScoreKey, GAMES};
+use common::stats::{age, format_value, Record, StatsDb};
+use common::ui;
+
+/// The list of filters down the left of the hub.
+const FILTER_ORIGIN: (f32, f32) = (40.0, 100.0);
+const FILTER_SIZE: (f32, f32) = (250.0, 34.0);
+const FILTER_SPACING: f32 = 8.0;
+
+/// Where the scores for the chosen filter are listed.
+const TABLE_X: f32 = 330.0;
+
+/// How many recent runs are listed.
+const RECENT_RUNS: usize = 10;
+
+/// How many recent unlocks are listed.
+const RECENT_UNLOCKS: usize = 10;
+
+/// The filters before the one for each game.
+const FIXED_FILTERS: usize = 2;
+
+/// What the hub shows.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Filter {
+    All,
+    Achievements,
+    /// One game, by its index in the hub's games.
+    Game(usize),
+}
+
+/// The runs of a game that ranks its runs.
+struct GameScores {
+    game: &'static GameInfo,
+    key: ScoreKey,
+    best: Option<f64>,
+    /// Every run, newest first.
+    runs: Vec<Record>,
+}
+
+/// The high score screen.
+pub struct Hub {
+    games: Vec<GameScores>,
+    /// Every game's runs together, newest first.
+    recent: Vec<Record>,
+    /// The index of the chosen filter, in the order they are listed.
+    filter: usize,
+    /// The current profile's achievements, unless they couldn't be read.
+    unlocks: Option<Unlocks>,
+    error: Option<String>,
+}
+
+impl Hub {
+    /// Loads every run from the stats store.
+    pub fn load() -> Self {
+        let (mut records, error) = match StatsDb::open().and_then(|stats| stats.records()) {
+            Ok(records) => (records, None),
+            Err(e) => (Vec::new(), Some(format!("Couldn't read the scores: {}", e))),
+        };
+        records.reverse();
+
+        let games = GAMES
+            .iter()
+            .filter_map(|game| {
+                let key = game.score?;
+                let runs: Vec<Record> = records
+                    .iter()
+                    .filter(|record| record.game == game.name)
+                    .cloned()
+                    .collect();
+                let best = runs.iter().filter_map(|run| run.get(key.key)).fold(
+                    None,
+                    |best: Option<f64>, value| match best {
+                        Some(best) if !key.beats(value, best) => Some(best),
+                        _ => Some(value),
+                    },
+                );
+                Some(GameScores {
+                    game,
+                    key,
+                    best,
+                    runs,
+                })
+            })
+            .collect();
+
+        Hub {
+            games,
+            recent: records,
+            filter: 0,
+            unlocks: Unlocks::load().ok(),
+            error,
+        }
+    }
+
+    /// The sum of the best points from every game scored in points.
+    pub fn arcade_total(&self) -> f64 {
+        self.games
+            .iter()
+            .filter(|scores| scores.key.is_points())
+            .filter_map(|scores| scores.best)
+            .sum()
+    }
+
+    fn filter_count(&self) -> usize {
+        self.games.len() + FIXED_FILTERS
+    }
+
+    fn filter_kind(index: usize) -> Filter {
+        match index {
+            0 => Filter::All,
+            1 => Filter::Achievements,
+            _ => Filter::Game(index - FIXED_FILTERS),
+        }
+    }
+
+    fn filter_rect(index: usize) -> Rect {
+        Rect::new(
+            FILTER_ORIGIN.0,
+            FILTER_ORIGIN.1 + index as f32 * (FILTER_SIZE.1 + FILTER_SPACING),
+            FILTER_SIZE.0,
+            FILTER_SIZE.1,
+        )
+    }
+
+    fn filter_at(&self, x: f32, y: f32) -> Option<usize> {
+        (0..self.filter_count())
+            .find(|&index| Hub::filter_rect(index).contains(ggez::mint::Point2 { x, y }))
+    }
+
+    /// Moves through the filters, returning false when the player backs out of the hub.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        match nav {
+            Nav::Up | Nav::Left if self.filter > 0 => self.filter -= 1,
+            Nav::Down | Nav::Right if self.filter + 1 < self.filter_count() => self.filter += 1,
+            Nav::Back => return false,
+            _ => (),
+        }
+        true
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if let Some(index) = self.filter_at(x, y) {
+            self.filter = index;
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "HIGH SCORES", [1.0, 1.0, 1.0, 1.0], 44.0, 40.0, 20.0)?;
+        ui::draw_text(
+            ctx,
+            &format!("Arcade total: {}", format_value(self.arcade_total())),
+            [1.0, 0.85, 0.3, 1.0],
+            30.0,
+            TABLE_X + 300.0,
+            30.0,
+        )?;
+
+        let mut builder = graphics::MeshBuilder::new();
+        for index in 0..self.filter_count() {
+            let color = match Hub::filter_kind(index) {
+                Filter::All => [0.3, 0.3, 0.36, 1.0],
+                Filter::Achievements => [1.0, 0.85, 0.3, 1.0],
+                Filter::Game(game) => self.games[game].game.color,
+            };
+            let rect = Hub::filter_rect(index);
+            builder.rectangle(DrawMode::fill(), rect, [0.14, 0.14, 0.18, 1.0].into());
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(rect.x, rect.y, 6.0, rect.h),
+                color.into(),
+            );
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for index in 0..self.filter_count() {
+            let rect = Hub::filter_rect(index);
+            let title = match Hub::filter_kind(index) {
+                Filter::All => "All games",
+                Filter::Achievements => "Achievements",
+                Filter::Game(game) => self.games[game].game.title,
+            };
+            ui::draw_text(
+                ctx,
+                title,
+                [1.0, 1.0, 1.0, 1.0],
+                20.0,
+                rect.x + 16.0,
+                rect.y + 7.0,
+            )?;
+        }
+        ui::draw_focus(ctx, Hub::filter_rect(self.filter))?;
+
+        if let Some(error) = &self.error {
+            ui::draw_text(ctx, error, [1.0, 0.4, 0.4, 1.0], 20.0, TABLE_X, 100.0)?;
+            return Ok(());
+        }
+        match Hub::filter_kind(self.filter) {
+            Filter::All => self.draw_all(ctx),
+            Filter::Achievements => self.draw_achievements(ctx),
+            Filter::Game(game) => self.draw_game(ctx, &self.games[game]),
+        }
+    }
+
+    /// Draws every game's best next to the most recent runs across the collection.
+    fn draw_all(&self, ctx: &mut Context) -> GameResult<()> {
+        let header = [0.6, 0.6, 0.65, 1.0];
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let mut y = 100.0;
+        ui::draw_text(ctx, "Game", header, 18.0, TABLE_X, y)?;
+        ui::draw_text(ctx, "Best", header, 18.0, TABLE_X + 200.0, y)?;
+        ui::draw_text(ctx, "Runs", header, 18.0, TABLE_X + 360.0, y)?;
+        y += 28.0;
+        for scores in &self.games {
+            let best = match scores.best {
+                Some(best) => format!("{} {}", format_value(best), scores.key.label),
+                None => "-".to_string(),
+            };
+            ui::draw_text(ctx, scores.game.title, white, 18.0, TABLE_X, y)?;
+            ui::draw_text(ctx, &best, white, 18.0, TABLE_X + 200.0, y)?;
+            ui::draw_text(
+                ctx,
+                &scores.runs.len().to_string(),
+                white,
+                18.0,
+                TABLE_X + 360.0,
+                y,
+            )?;
+            y += 24.0;
+        }
+
+        y += 20.0;
+        ui::draw_text(ctx, "Recent runs", header, 18.0, TABLE_X, y)?;
+        y += 28.0;
+        if self.recent.is_empty() {
+            ui::draw_text(ctx, "Nothing played yet.", white, 18.0, TABLE_X, y)?;
+        }
+        for record in self.recent.iter().take(RECENT_RUNS / 2) {
+            let title = registry::find(&record.game).map_or(&record.game[..], |game| game.title);
+            ui::draw_text(ctx, title, white, 18.0, TABLE_X, y)?;
+            ui::draw_text(ctx, &record.describe(), white, 18.0, TABLE_X + 200.0, y)?;
+            y += 24.0;
+        }
+        Ok(())
+    }
+
+    /// Draws one game's best and its most recent runs.
+    fn draw_game(&self, ctx: &mut Context, scores: &GameScores) -> GameResult<()> {
+        let header = [0.6, 0.6, 0.65, 1.0];
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let best = match scores.best {
+            Some(best) => format_value(best),
+            None => "-".to_string(),
+        };
+        ui::draw_text(
+            ctx,
+            &format!("Best {}: {}", scores.key.label.to_lowercase(), best),
+            scores.game.color,
+            28.0,
+            TABLE_X,
+            100.0,
+        )?;
+        ui::draw_text(
+            ctx,
+            &format!("{} runs played", scores.runs.len()),
+            header,
+            18.0,
+            TABLE_X,
+            136.0,
+        )?;
+
+        let mut y = 180.0;
+        ui::draw_text(ctx, "Recent runs", header, 18.0, TABLE_X, y)?;
+        y += 28.0;
+        if scores.runs.is_empty() {
+            ui::draw_text(ctx, "No runs recorded yet.", white, 18.0, TABLE_X, y)?;
+            y += 24.0;
+        }
+        for record in scores.runs.iter().take(RECENT_RUNS) {
+            ui::draw_text(ctx, &record.describe(), white, 18.0, TABLE_X, y)?;
+            y += 24.0;
+        }
+
+        y += 20.0;
+        ui::draw_text(ctx, "Achievements", header, 18.0, TABLE_X, y)?;
+        y += 28.0;
+        for achievement in scores.game.achievements {
+            let unlocked = self.unlocks.as_ref().map_or(false, |unlocks| {
+                unlocks.is_unlocked(scores.game.name, achievement.id)
+            });
+            let (mark, color) = if unlocked {
+                ("*", [1.0, 0.85, 0.3, 1.0])
+            } else {
+                ("-", [0.5, 0.5, 0.55, 1.0])
+            };
+            ui::draw_text(
+                ctx,
+                &format!(
+                    "{} {}: {}",
+                    mark, achievement.title, achievement.description
+                ),
+                color,
+                18.0,
+                TABLE_X,
+                y,
+            )?;
+            y += 24.0;
+        }
+        Ok(())
+    }
+
+    /// Draws how much of the collection's achievements are unlocked and the latest unlocks.
+    fn draw_achievements(&self, ctx: &mut Context) -> GameResult<()> {
+        let header = [0.6, 0.6, 0.65, 1.0];
+        let white = [1.0, 1.0, 1.0, 1.0];
+        let unlocks = match &self.unlocks {
+            Some(unlocks) => unlocks,
+            None => {
+                return ui::draw_text(
+                    ctx,
+                    "Couldn't read the achievements.",
+                    [1.0, 0.4, 0.4, 1.0],
+                    20.0,
+                    TABLE_X,
+                    100.0,
+                )
+            }
+        };
+
+        let (unlocked, total) = unlocks.completion();
+        let fraction = if total == 0 {
+            0.0
+        } else {
+            unlocked as f32 / total as f32
+        };
+        ui::draw_text(
+            ctx,
+            &format!(
+                "{} of {} unlocked ({:.0}%)",
+                unlocked,
+                total,
+                fraction * 100.0
+            ),
+            [1.0, 0.85, 0.3, 1.0],
+            28.0,
+            TABLE_X,
+            100.0,
+        )?;
+        let bar = Rect::new(TABLE_X, 140.0, 640.0, 16.0);
+        let mut builder = graphics::MeshBuilder::new();
+        builder.rectangle(DrawMode::fill(), bar, [0.14, 0.14, 0.18, 1.0].into());
+        builder.rectangle(
+            DrawMode::fill(),
+            Rect::new(bar.x, bar.y, bar.w * fraction, bar.h),
+            [1.0, 0.85, 0.3, 1.0].into(),
+        );
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let mut y = 180.0;
+        ui::draw_text(ctx, "Recently unlocked", header, 18.0, TABLE_X, y)?;
+        y += 28.0;
+        let mut any = false;
+        for unlock in unlocks.recent(RECENT_UNLOCKS) {
+            let achievement = match unlock.achievement() {
+                Some(achievement) => achievement,
+                None => continue,
+            };
+            let game = registry::find(&unlock.game).map_or(&unlock.game[..], |game| game.title);
+            ui::draw_text(
+                ctx,
+                &format!("{}: {}", game, achievement.title),
+                white,
+                18.0,
+                TABLE_X,
+                y,
+            )?;
+            ui::draw_text(
+                ctx,
+                &format!("{}  ({})", achievement.description, age(unlock.timestamp)),
+                header,
+                16.0,
+                TABLE_X + 20.0,
+                y + 22.0,
+            )?;
+            y += 46.0;
+            any = true;
+        }
+        if !any {
+            ui::draw_text(ctx, "Nothing unlocked yet.", white, 18.0, TABLE_X, y)?;
+        }
+        Ok(())
+    }
+}
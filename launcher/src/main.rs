@@ -0,0 +1,1221 @@
+mod controls;
+mod crashes;
+mod daily;
+mod dashboard;
+mod display;
+mod gallery;
+mod hub;
+mod mixer;
+mod privacy;
+mod shop;
+
+use ggez::event::{Axis, Button, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{self, Image, Rect};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, Context, GameResult};
+
+use std::iter;
+use std::process::Child;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use common::achievements::Unlocks;
+use common::args;
+use common::assets;
+use common::crash;
+use common::input::{Nav, StickNav};
+use common::last_played::LastPlayed;
+use common::launch;
+use common::locale;
+use common::overlay::DamagedFiles;
+use common::plugins::{self, Plugin};
+use common::registry::{self, GameInfo, GAMES};
+use common::scanning;
+use common::session::Session;
+use common::settings::Settings;
+use common::speech;
+use common::sync::{self, Report, Server};
+use common::telemetry;
+use common::theme::{self, Palette, Theme, THEMES};
+use common::tokens::Wallet;
+use common::ui::{self, Card, GridLayout};
+use common::update::{self, Release};
+use common::window::{self, DisplaySettings, FrameLimiter, BACKGROUND_FRAME_CAP};
+
+const SCREEN_SIZE: (f32, f32) = (1040.0, 720.0);
+
+/// The space above the grid for the title.
+const HEADER_HEIGHT: f32 = 90.0;
+/// The space below the grid for the status line.
+const FOOTER_HEIGHT: f32 = 40.0;
+
+const LAYOUT: GridLayout = GridLayout {
+    origin: (40.0, HEADER_HEIGHT),
+    columns: 4,
+    cell: (230.0, 260.0),
+    spacing: 20.0,
+};
+
+/// Pixels scrolled for each notch of the mouse wheel.
+const SCROLL_STEP: f32 = 60.0;
+
+/// Where the button to open the high scores goes, in the header.
+const SCORES_RECT: Rect = Rect {
+    x: 360.0,
+    y: 18.0,
+    w: 160.0,
+    h: 48.0,
+};
+
+/// Where the button to open the daily challenge goes, in the header.
+const DAILY_RECT: Rect = Rect {
+    x: 535.0,
+    y: 18.0,
+    w: 120.0,
+    h: 48.0,
+};
+
+/// Where the button to carry on with the last played game goes, in the header.
+const CONTINUE_RECT: Rect = Rect {
+    x: 670.0,
+    y: 18.0,
+    w: 330.0,
+    h: 48.0,
+};
+
+/// The accent colour of plugins' cards, which have no thumbnails.
+const PLUGIN_COLOR: [f32; 4] = [0.45, 0.4, 0.75, 1.0];
+
+/// The launcher's own colours, for the original theme. It has no players, hazards or pickups,
+/// so only the background and text are used.
+const PALETTE: Palette = Palette {
+    background: [0.07, 0.07, 0.09, 1.0],
+    text: [1.0, 1.0, 1.0, 1.0],
+    player: [1.0, 1.0, 1.0, 1.0],
+    hazard: [1.0, 1.0, 1.0, 1.0],
+    pickup: [1.0, 1.0, 1.0, 1.0],
+};
+
+/// What is highlighted in the launcher.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Focus {
+    Scores,
+    Daily,
+    /// The button to carry on with the last played game.
+    Continue,
+    Card(usize),
+}
+
+/// The screen the launcher is showing.
+enum Screen {
+    Games,
+    Scores(hub::Hub),
+    Controls(controls::Controls),
+    Mixer(mixer::Mixer),
+    Display(display::DisplayScreen),
+    Shop(shop::Shop),
+    Daily(daily::DailyScreen),
+    Stats(dashboard::Dashboard),
+    Gallery(gallery::Gallery),
+    Privacy(privacy::Privacy),
+    Crashes(crashes::CrashReports),
+}
+
+/// The state for the launcher.
+struct LauncherState {
+    screen: Screen,
+    /// Each game's thumbnail, in the same order as `GAMES`, if it has one.
+    thumbnails: Vec<Option<Image>>,
+    /// The installed plugins, whose cards come after the games'.
+    plugins: Vec<Plugin>,
+    /// What is highlighted, by the mouse or by navigating with keys or a gamepad.
+    focused: Focus,
+    /// The last game played in this profile, if it is still in the registry.
+    last: Option<(&'static GameInfo, LastPlayed)>,
+    /// The current profile's achievements, unless they couldn't be read.
+    unlocks: Option<Unlocks>,
+    /// The current profile's tokens, unless they couldn't be read.
+    wallet: Option<Wallet>,
+    sticks: StickNav,
+    /// How far the grid has been scrolled down.
+    scroll: f32,
+    /// The process of the game that was last started, while it is still running.
+    running: Option<Child>,
+    /// How the sync in the background went, while it is running.
+    syncing: Option<Receiver<Result<Report, String>>>,
+    /// How the check for a newer version went, while it is running.
+    checking: Option<Receiver<Result<Option<Release>, String>>>,
+    /// The newer version there is, if the check found one.
+    release: Option<Release>,
+    frames: FrameLimiter,
+    /// Whether the window has focus. It's left in the background while games are played.
+    window_focused: bool,
+    background_frames: FrameLimiter,
+    message: String,
+    /// What was last read out, so it's only read out again once it changes.
+    spoken_focus: Option<Focus>,
+    spoken_message: String,
+    palette: Palette,
+    damaged: DamagedFiles,
+}
+
+impl LauncherState {
+    /// Creates a new launcher, loading whichever thumbnails are available. The last played
+    /// game starts off highlighted, unless the profile is still to be asked about sharing its
+    /// play statistics, which it's asked first, or there are crash reports to look over. Any
+    /// statistics it shares are sent in the background.
+    pub fn new(ctx: &mut Context) -> Self {
+        let thumbnails = GAMES
+            .iter()
+            .map(|game| Image::new(ctx, game.thumbnail).ok())
+            .collect();
+        let last = load_last_played();
+        let palette = theme::current().apply(PALETTE);
+        let plugins = plugins::list().unwrap_or_else(|e| {
+            eprintln!("Couldn't list the plugins: {}", e);
+            Vec::new()
+        });
+
+        let screen = if telemetry::consent().is_none() {
+            Screen::Privacy(privacy::Privacy::load())
+        } else {
+            crashes::CrashReports::load().map_or(Screen::Games, Screen::Crashes)
+        };
+        telemetry::send_in_background();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(update::check());
+        });
+
+        LauncherState {
+            screen,
+            thumbnails,
+            plugins,
+            focused: if last.is_some() {
+                Focus::Continue
+            } else {
+                Focus::Card(0)
+            },
+            last,
+            unlocks: Unlocks::load().ok(),
+            wallet: Wallet::load().ok(),
+            sticks: StickNav::new(),
+            scroll: 0.0,
+            running: None,
+            syncing: None,
+            checking: Some(receiver),
+            release: None,
+            frames: FrameLimiter::new(DisplaySettings::load().frame_cap),
+            window_focused: true,
+            background_frames: FrameLimiter::new(BACKGROUND_FRAME_CAP),
+            message: String::new(),
+            spoken_focus: None,
+            spoken_message: String::new(),
+            palette,
+            damaged: DamagedFiles::new(),
+        }
+    }
+
+    /// Switches to the next theme the profile owns, which every game started from now on will
+    /// use. Following the system comes before the themes themselves.
+    fn next_theme(&mut self) {
+        let choices: Vec<&'static Theme> = iter::once(&theme::SYSTEM).chain(THEMES).collect();
+        let chosen = theme::chosen();
+        let index = choices
+            .iter()
+            .position(|theme| theme.name == chosen.name)
+            .unwrap_or(0);
+        let wallet = &self.wallet;
+        let next = (1..=choices.len())
+            .map(|step| choices[(index + step) % choices.len()])
+            .find(|theme| match wallet {
+                Some(wallet) => theme.is_owned(wallet),
+                None => theme.price == 0,
+            })
+            .unwrap_or(&theme::SYSTEM);
+
+        let used = theme::resolve(next);
+        self.palette = used.apply(PALETTE);
+        self.message = match theme::save(next) {
+            Ok(()) if next.name == used.name => format!("Theme: {}", next.title),
+            Ok(()) => format!("Theme: {} ({})", next.title, used.title),
+            Err(e) => format!("Couldn't save the theme: {}", e),
+        };
+    }
+
+    /// Turns reading out the menus on or off.
+    fn toggle_speech(&mut self) {
+        let on = !speech::enabled();
+        self.message = match speech::set_enabled(on) {
+            Ok(()) if on => "Speech on.".to_string(),
+            Ok(()) => "Speech off.".to_string(),
+            Err(e) => format!("Couldn't save the speech setting: {}", e),
+        };
+    }
+
+    /// What a focus is called when it's read out.
+    fn focus_label(&self, focus: Focus) -> String {
+        match focus {
+            Focus::Scores => "High scores".to_string(),
+            Focus::Daily => "Today's challenge".to_string(),
+            Focus::Continue => match &self.last {
+                Some((game, _)) => format!("Continue {}", game.title),
+                None => "Continue".to_string(),
+            },
+            Focus::Card(index) => match GAMES.get(index) {
+                Some(game) => format!("{}. {}", game.title, game.description),
+                None => self
+                    .plugins
+                    .get(index - GAMES.len())
+                    .map(|plugin| format!("{}. {}", plugin.title, plugin.description))
+                    .unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Reads out the focus and the status message when they change, if speech is on.
+    fn speak_changes(&mut self) {
+        if let Screen::Games = self.screen {
+            if self.spoken_focus != Some(self.focused) {
+                speech::say(&self.focus_label(self.focused));
+                self.spoken_focus = Some(self.focused);
+            }
+        } else {
+            // Read out again on coming back to the games.
+            self.spoken_focus = None;
+        }
+        if self.message != self.spoken_message {
+            if !self.message.is_empty() {
+                speech::say_politely(&self.message);
+            }
+            self.spoken_message = self.message.clone();
+        }
+    }
+
+    /// Handles a key going down, whether it was pressed or picked with the switch.
+    fn press_key(&mut self, ctx: &mut Context, keycode: KeyCode, repeat: bool) {
+        if self.damaged.is_asking() {
+            if let Some(nav) = Nav::from_key(keycode) {
+                self.damaged.navigate(nav);
+            }
+            return;
+        }
+        if let Screen::Controls(controls) = &mut self.screen {
+            // A held key shouldn't bind the control it just picked.
+            if repeat || controls.key_down(keycode) {
+                return;
+            }
+        }
+        if let Screen::Gallery(gallery) = &mut self.screen {
+            match keycode {
+                KeyCode::Delete => return gallery.delete(),
+                KeyCode::O => return gallery.open_folder(),
+                _ => (),
+            }
+        }
+        if let Screen::Crashes(crashes) = &mut self.screen {
+            if keycode == KeyCode::Delete {
+                if !crashes.discard() {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+        }
+        match (keycode, &self.screen) {
+            (KeyCode::T, Screen::Games) => self.next_theme(),
+            (KeyCode::C, Screen::Games) => {
+                self.screen = Screen::Controls(controls::Controls::load())
+            }
+            (KeyCode::M, Screen::Games) => self.screen = Screen::Mixer(mixer::Mixer::load(ctx)),
+            (KeyCode::U, Screen::Games) => self.screen = Screen::Shop(shop::Shop::load()),
+            (KeyCode::I, Screen::Games) => {
+                self.screen = Screen::Stats(dashboard::Dashboard::load())
+            }
+            (KeyCode::G, Screen::Games) => {
+                self.screen = Screen::Gallery(gallery::Gallery::load(ctx))
+            }
+            (KeyCode::V, Screen::Games) => {
+                self.screen = Screen::Display(display::DisplayScreen::load(ctx))
+            }
+            (KeyCode::N, Screen::Games) => self.start_sync(),
+            (KeyCode::P, Screen::Games) => self.screen = Screen::Privacy(privacy::Privacy::load()),
+            (KeyCode::R, Screen::Games) => self.toggle_speech(),
+            _ => {
+                if let Some(nav) = Nav::from_key(keycode) {
+                    self.navigate(ctx, nav);
+                }
+            }
+        }
+    }
+
+    /// How many cards there are, for the games and then the plugins.
+    fn card_count(&self) -> usize {
+        GAMES.len() + self.plugins.len()
+    }
+
+    /// The rectangle of a game's card on screen, taking the scroll into account.
+    fn card_rect(&self, index: usize) -> Rect {
+        let mut rect = LAYOUT.rect(index);
+        rect.y -= self.scroll;
+        rect
+    }
+
+    fn focus_at(&self, x: f32, y: f32) -> Option<Focus> {
+        if SCORES_RECT.contains(ggez::mint::Point2 { x, y }) {
+            return Some(Focus::Scores);
+        }
+        if DAILY_RECT.contains(ggez::mint::Point2 { x, y }) {
+            return Some(Focus::Daily);
+        }
+        if self.last.is_some() && CONTINUE_RECT.contains(ggez::mint::Point2 { x, y }) {
+            return Some(Focus::Continue);
+        }
+        if y < HEADER_HEIGHT || y > SCREEN_SIZE.1 - FOOTER_HEIGHT {
+            return None;
+        }
+        LAYOUT
+            .index_at(x, y + self.scroll, self.card_count())
+            .map(Focus::Card)
+    }
+
+    /// The furthest the grid can scroll before the last row is at the bottom.
+    fn max_scroll(&self) -> f32 {
+        let rows = LAYOUT.rows(self.card_count()) as f32;
+        let height = rows * (LAYOUT.cell.1 + LAYOUT.spacing);
+        (height - (SCREEN_SIZE.1 - HEADER_HEIGHT - FOOTER_HEIGHT)).max(0.0)
+    }
+
+    /// Scrolls just far enough to show the whole of the focused card.
+    fn scroll_to_focus(&mut self) {
+        let index = match self.focused {
+            Focus::Card(index) => index,
+            _ => return,
+        };
+        let rect = LAYOUT.rect(index);
+        let top = rect.y - HEADER_HEIGHT;
+        let bottom = rect.y + rect.h - (SCREEN_SIZE.1 - FOOTER_HEIGHT);
+        self.scroll = self
+            .scroll
+            .min(top)
+            .max(bottom)
+            .max(0.0)
+            .min(self.max_scroll());
+    }
+
+    fn navigate(&mut self, ctx: &mut Context, nav: Nav) {
+        match &mut self.screen {
+            Screen::Games => (),
+            Screen::Scores(hub) => {
+                if !hub.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Controls(controls) => {
+                if !controls.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Mixer(mixer) => {
+                if !mixer.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Display(display) => {
+                let open = display.navigate(ctx, nav);
+                self.frames = FrameLimiter::new(display.settings().frame_cap);
+                if !open {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Shop(shop) => {
+                if !shop.navigate(nav) {
+                    self.close_shop();
+                }
+                return;
+            }
+            Screen::Stats(dashboard) => {
+                if !dashboard.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Gallery(gallery) => {
+                if !gallery.navigate(ctx, nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Privacy(privacy) => {
+                if !privacy.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Crashes(crashes) => {
+                if !crashes.navigate(nav) {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+            Screen::Daily(_) => {
+                match nav {
+                    Nav::Confirm => self.start_daily(),
+                    Nav::Back => self.screen = Screen::Games,
+                    _ => (),
+                }
+                return;
+            }
+        }
+
+        match (nav, self.focused) {
+            (Nav::Confirm, focused) => self.activate(focused),
+            (Nav::Back, _) => event::quit(ctx),
+            (Nav::Down, Focus::Scores) => {
+                self.focused = Focus::Card(0);
+                self.scroll_to_focus();
+            }
+            (Nav::Down, Focus::Daily) => {
+                self.focused = Focus::Card((LAYOUT.columns / 2).min(self.card_count() - 1));
+                self.scroll_to_focus();
+            }
+            (Nav::Down, Focus::Continue) => {
+                self.focused = Focus::Card((LAYOUT.columns - 1).min(self.card_count() - 1));
+                self.scroll_to_focus();
+            }
+            (Nav::Right, Focus::Scores) => self.focused = Focus::Daily,
+            (Nav::Right, Focus::Daily) if self.last.is_some() => self.focused = Focus::Continue,
+            (Nav::Left, Focus::Daily) => self.focused = Focus::Scores,
+            (Nav::Left, Focus::Continue) => self.focused = Focus::Daily,
+            // The header buttons sit above the grid from left to right: the high scores over
+            // the left half, then the daily challenge and the last played game.
+            (Nav::Up, Focus::Card(index)) if index < LAYOUT.columns => {
+                self.focused = if index < LAYOUT.columns / 2 {
+                    Focus::Scores
+                } else if index == LAYOUT.columns - 1 && self.last.is_some() {
+                    Focus::Continue
+                } else {
+                    Focus::Daily
+                };
+            }
+            (_, Focus::Card(index)) => {
+                self.focused = Focus::Card(LAYOUT.step(index, nav, self.card_count()));
+                self.scroll_to_focus();
+            }
+            _ => (),
+        }
+    }
+
+    fn activate(&mut self, focus: Focus) {
+        match focus {
+            Focus::Scores => self.screen = Screen::Scores(hub::Hub::load()),
+            Focus::Daily => self.screen = Screen::Daily(daily::DailyScreen::load()),
+            Focus::Continue => self.resume(),
+            Focus::Card(index) if index < GAMES.len() => self.start(&GAMES[index], None),
+            Focus::Card(index) => self.start_plugin(index - GAMES.len()),
+        }
+    }
+
+    /// Starts the last played game again, in the mode it was last played in.
+    fn resume(&mut self) {
+        if let Some((game, last)) = self.last.clone() {
+            self.start(game, last.mode.as_deref());
+        }
+    }
+
+    fn start(&mut self, game: &'static GameInfo, mode: Option<&str>) {
+        let args: Vec<String> = match mode {
+            Some(mode) => vec!["--mode".to_string(), mode.to_string()],
+            None => Vec::new(),
+        };
+        self.run_game(game, mode, &args);
+    }
+
+    /// Starts today's challenge.
+    fn start_daily(&mut self) {
+        let (daily, game) = match &self.screen {
+            Screen::Daily(screen) => (screen.daily, screen.game),
+            _ => return,
+        };
+        self.screen = Screen::Games;
+        match game {
+            Some(game) => self.run_game(game, None, &daily.args()),
+            None => self.message = format!("{} isn't installed.", daily.challenge.game),
+        }
+    }
+
+    /// Whether the games are paused for a break, saying so if they are.
+    fn on_break(&mut self) -> bool {
+        match Session::load().break_remaining() {
+            Some(remaining) => {
+                self.message = format!(
+                    "Taking a break. The games are back in {}.",
+                    locale::duration(remaining)
+                );
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Starts a game with the given arguments, remembering it as the last game played.
+    fn run_game(&mut self, game: &'static GameInfo, mode: Option<&str>, args: &[String]) {
+        if self.on_break() {
+            return;
+        }
+        match launch::launch(game, args) {
+            Ok(child) => {
+                self.message = format!("Playing {}...", game.title);
+                self.running = Some(child);
+                let last = LastPlayed::new(game.name, mode);
+                if let Err(e) = last.save() {
+                    eprintln!("Couldn't save the last played game: {}", e);
+                }
+                self.last = Some((game, last));
+            }
+            Err(e) => self.message = format!("Couldn't start {}: {}", game.title, e),
+        }
+    }
+
+    /// Starts one of the plugins. Plugins aren't remembered as the last game played, since they
+    /// aren't in the registry.
+    fn start_plugin(&mut self, index: usize) {
+        if self.on_break() {
+            return;
+        }
+        let plugin = &self.plugins[index];
+        match launch::launch_plugin(plugin, &[]) {
+            Ok(child) => {
+                self.message = format!("Playing {}...", plugin.title);
+                self.running = Some(child);
+            }
+            Err(e) => self.message = format!("Couldn't start {}: {}", plugin.title, e),
+        }
+    }
+
+    /// Starts syncing the profile's files with the server in its settings, in the background.
+    fn start_sync(&mut self) {
+        if self.syncing.is_some() {
+            return;
+        }
+        if self.running.is_some() {
+            self.message = "Close the game before syncing.".to_string();
+            return;
+        }
+        let settings = Settings::load();
+        let server = match Server::configured(&settings) {
+            Some(Ok(server)) => server,
+            Some(Err(e)) => {
+                self.message = format!("Couldn't sync: {}", e);
+                return;
+            }
+            None => {
+                self.message = match settings.path() {
+                    Some(path) => {
+                        format!("Set {} in {} to sync.", sync::URL_SETTING, path.display())
+                    }
+                    None => "There's no profile directory to sync.".to_string(),
+                };
+                return;
+            }
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(sync::sync(&server));
+        });
+        self.syncing = Some(receiver);
+        self.message = "Syncing...".to_string();
+    }
+
+    /// Notices when the sync has finished, reloading whatever it may have received.
+    fn poll_sync(&mut self) {
+        let result = match &self.syncing {
+            Some(receiver) => match receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Err("the sync stopped".to_string()),
+            },
+            None => return,
+        };
+        self.syncing = None;
+        self.message = match result {
+            Ok(report) => format!("Synced: {}.", report),
+            Err(e) => format!("Couldn't sync: {}", e),
+        };
+        self.last = load_last_played();
+        self.unlocks = Unlocks::load().ok();
+        self.wallet = Wallet::load().ok();
+        self.palette = theme::current().apply(PALETTE);
+    }
+
+    /// Notices when the check for a newer version has finished. Failing to check isn't worth
+    /// interrupting anyone for, so it's only logged.
+    fn poll_update_check(&mut self) {
+        let result = match &self.checking {
+            Some(receiver) => match receiver.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => Err("the check stopped".to_string()),
+            },
+            None => return,
+        };
+        self.checking = None;
+        match result {
+            Ok(release) => self.release = release,
+            Err(e) => eprintln!("Couldn't check for a newer version: {}", e),
+        }
+    }
+
+    /// Goes back to the games from the shop, which may have changed the theme and tokens.
+    fn close_shop(&mut self) {
+        self.screen = Screen::Games;
+        self.palette = theme::current().apply(PALETTE);
+        self.wallet = Wallet::load().ok();
+    }
+
+    /// Notices when the running game has been closed.
+    fn poll_running(&mut self) {
+        let finished = match &mut self.running {
+            Some(child) => child
+                .try_wait()
+                .map(|status| status.is_some())
+                .unwrap_or(true),
+            None => false,
+        };
+        if finished {
+            self.running = None;
+            self.message.clear();
+            // The game may have recorded the mode it was played in.
+            self.last = load_last_played();
+            self.unlocks = Unlocks::load().ok();
+            self.wallet = Wallet::load().ok();
+            // It may have crashed.
+            if let Screen::Games = self.screen {
+                if let Some(crashes) = crashes::CrashReports::load() {
+                    self.screen = Screen::Crashes(crashes);
+                }
+            }
+        }
+    }
+
+    /// Draws the grid of games with the header and status line around it.
+    fn draw_games(&self, ctx: &mut Context) -> GameResult<()> {
+        for (index, game) in GAMES.iter().enumerate() {
+            let rect = self.card_rect(index);
+            // Skip the cards scrolled out of view.
+            if rect.y + rect.h < HEADER_HEIGHT || rect.y > SCREEN_SIZE.1 - FOOTER_HEIGHT {
+                continue;
+            }
+            Card {
+                rect,
+                title: game.title,
+                description: game.description,
+                tags: game.tags,
+                thumbnail: self.thumbnails[index].as_ref(),
+                accent: game.color,
+                focused: self.focused == Focus::Card(index),
+            }
+            .draw(ctx)?;
+        }
+        for (offset, plugin) in self.plugins.iter().enumerate() {
+            let index = GAMES.len() + offset;
+            let rect = self.card_rect(index);
+            if rect.y + rect.h < HEADER_HEIGHT || rect.y > SCREEN_SIZE.1 - FOOTER_HEIGHT {
+                continue;
+            }
+            let tags: Vec<&str> = plugin.tags.iter().map(String::as_str).collect();
+            Card {
+                rect,
+                title: &plugin.title,
+                description: &plugin.description,
+                tags: &tags,
+                thumbnail: None,
+                accent: PLUGIN_COLOR,
+                focused: self.focused == Focus::Card(index),
+            }
+            .draw(ctx)?;
+        }
+
+        // Cover the cards scrolling under the header and footer.
+        let mut builder = graphics::MeshBuilder::new();
+        let background = self.palette.background;
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect::new(
+                0.0,
+                0.0,
+                SCREEN_SIZE.0,
+                HEADER_HEIGHT - LAYOUT.spacing / 2.0,
+            ),
+            background.into(),
+        );
+        builder.rectangle(
+            graphics::DrawMode::fill(),
+            Rect::new(
+                0.0,
+                SCREEN_SIZE.1 - FOOTER_HEIGHT,
+                SCREEN_SIZE.0,
+                FOOTER_HEIGHT,
+            ),
+            background.into(),
+        );
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        ui::draw_text(
+            ctx,
+            "QUICK GAMES",
+            self.palette.text,
+            44.0,
+            LAYOUT.origin.0,
+            20.0,
+        )?;
+        if let Some((game, last)) = &self.last {
+            let label = match &last.mode {
+                Some(mode) => format!("Continue: {} ({})", game.title, mode),
+                None => format!("Continue: {}", game.title),
+            };
+            let button = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                CONTINUE_RECT,
+                game.color.into(),
+            )?;
+            graphics::draw(ctx, &button, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+            ui::draw_text(
+                ctx,
+                &label,
+                [0.0, 0.0, 0.0, 1.0],
+                24.0,
+                CONTINUE_RECT.x + 14.0,
+                CONTINUE_RECT.y + 12.0,
+            )?;
+            if self.focused == Focus::Continue {
+                ui::draw_focus(ctx, CONTINUE_RECT)?;
+            }
+        }
+        if let Some(release) = &self.release {
+            let banner = match release.summary() {
+                Some(summary) => format!("Version {} is out: {}", release.version, summary),
+                None => format!("Version {} is out.", release.version),
+            };
+            ui::draw_text(
+                ctx,
+                &banner,
+                [1.0, 0.85, 0.3, 1.0],
+                16.0,
+                LAYOUT.origin.0,
+                HEADER_HEIGHT - LAYOUT.spacing - 4.0,
+            )?;
+        }
+
+        let button = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            SCORES_RECT,
+            [0.22, 0.22, 0.28, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &button, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        ui::draw_text(
+            ctx,
+            "High Scores",
+            self.palette.text,
+            24.0,
+            SCORES_RECT.x + 18.0,
+            SCORES_RECT.y + 12.0,
+        )?;
+        if self.focused == Focus::Scores {
+            ui::draw_focus(ctx, SCORES_RECT)?;
+        }
+
+        let button = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            DAILY_RECT,
+            [0.22, 0.22, 0.28, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &button, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        ui::draw_text(
+            ctx,
+            "Daily",
+            self.palette.text,
+            24.0,
+            DAILY_RECT.x + 18.0,
+            DAILY_RECT.y + 12.0,
+        )?;
+        if self.focused == Focus::Daily {
+            ui::draw_focus(ctx, DAILY_RECT)?;
+        }
+
+        let status = if self.message.is_empty() {
+            let mut status =
+                "T/Y theme, C/X keys, M/RB mixer, U/LB shop, I/LT stats, G/RT gallery, \
+                 V/LS display, N/RS sync, R speech, P privacy."
+                    .to_string();
+            if let Some(unlocks) = &self.unlocks {
+                let (unlocked, total) = unlocks.completion();
+                if total > 0 {
+                    status.push_str(&format!("  Achievements: {}%", unlocked * 100 / total));
+                }
+            }
+            if let Some(wallet) = &self.wallet {
+                status.push_str(&format!("  Tokens: {}", wallet.balance()));
+            }
+            status
+        } else {
+            self.message.clone()
+        };
+        ui::draw_text(
+            ctx,
+            &status,
+            [0.8, 0.8, 0.85, 1.0],
+            18.0,
+            LAYOUT.origin.0,
+            SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+        )?;
+        Ok(())
+    }
+}
+
+impl event::EventHandler for LauncherState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if self.window_focused {
+            self.frames.wait();
+        } else {
+            self.background_frames.wait();
+        }
+        self.poll_running();
+        self.poll_sync();
+        self.poll_update_check();
+        self.damaged.update();
+        if let Some(key) = scanning::update() {
+            self.press_key(ctx, key, false);
+        }
+        self.speak_changes();
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, self.palette.background.into());
+
+        match &self.screen {
+            Screen::Games => self.draw_games(ctx)?,
+            Screen::Scores(hub) => {
+                hub.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Up and down to filter by game, Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Controls(controls) => {
+                controls.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Enter or A to rebind, Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Mixer(mixer) => {
+                mixer.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Left and right to change, Enter or A to listen, Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Display(display) => {
+                display.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Left and right to change, Escape or B to go back. Games pick changes up when started.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Shop(shop) => {
+                shop.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Earn tokens from achievements and personal bests. Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Stats(dashboard) => {
+                dashboard.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Left and right to chart another game, Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Gallery(gallery) => {
+                gallery.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Enter or A to view, Delete or X to delete, O or Y to open the folder, \
+                     Escape or B to go back.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Daily(daily) => {
+                daily.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Enter or A to play, Escape or B to go back. A new challenge every day.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Privacy(privacy) => {
+                privacy.draw(ctx)?;
+                let hint = if privacy.is_asking() {
+                    "Enter or A to share, Escape or B not to. P changes it later."
+                } else {
+                    "Enter or A to turn sharing on or off, Escape or B to go back."
+                };
+                ui::draw_text(
+                    ctx,
+                    hint,
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+            Screen::Crashes(crashes) => {
+                crashes.draw(ctx)?;
+                ui::draw_text(
+                    ctx,
+                    "Enter or A to send, Delete or X to delete, up and down to scroll, \
+                     Escape or B to decide later.",
+                    [0.8, 0.8, 0.85, 1.0],
+                    20.0,
+                    LAYOUT.origin.0,
+                    SCREEN_SIZE.1 - FOOTER_HEIGHT + 10.0,
+                )?;
+            }
+        }
+        self.damaged.draw(ctx)?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if self.damaged.is_asking() {
+            return self.damaged.mouse_motion(ctx, x, y);
+        }
+        match &mut self.screen {
+            Screen::Games => {
+                if let Some(focus) = self.focus_at(x, y) {
+                    self.focused = focus;
+                }
+            }
+            Screen::Scores(hub) => hub.mouse_motion(x, y),
+            Screen::Controls(controls) => controls.mouse_motion(x, y),
+            Screen::Mixer(mixer) => mixer.mouse_motion(x, y),
+            Screen::Display(display) => display.mouse_motion(x, y),
+            Screen::Shop(shop) => shop.mouse_motion(x, y),
+            Screen::Gallery(gallery) => gallery.mouse_motion(x, y),
+            Screen::Daily(_) | Screen::Stats(_) | Screen::Privacy(_) | Screen::Crashes(_) => (),
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if self.damaged.is_asking() {
+            if button == MouseButton::Left {
+                self.damaged.click(ctx, x, y);
+            }
+            return;
+        }
+        match &mut self.screen {
+            Screen::Games => {
+                if button == MouseButton::Left {
+                    if let Some(focus) = self.focus_at(x, y) {
+                        self.activate(focus);
+                    }
+                }
+            }
+            Screen::Controls(controls) if button == MouseButton::Left => controls.click(x, y),
+            Screen::Mixer(mixer) if button == MouseButton::Left => mixer.click(x, y),
+            Screen::Display(display) if button == MouseButton::Left => {
+                display.click(ctx, x, y);
+                self.frames = FrameLimiter::new(display.settings().frame_cap);
+            }
+            Screen::Shop(shop) if button == MouseButton::Left => shop.click(x, y),
+            Screen::Shop(_) if button == MouseButton::Right => self.close_shop(),
+            Screen::Gallery(gallery) if button == MouseButton::Left => gallery.click(ctx, x, y),
+            Screen::Gallery(gallery) if button == MouseButton::Right => {
+                if !gallery.navigate(ctx, Nav::Back) {
+                    self.screen = Screen::Games;
+                }
+            }
+            Screen::Daily(daily) if button == MouseButton::Left => {
+                if daily.play_at(x, y) {
+                    self.start_daily();
+                }
+            }
+            Screen::Privacy(privacy) if button == MouseButton::Left => privacy.click(x, y),
+            Screen::Privacy(privacy) if button == MouseButton::Right => {
+                privacy.navigate(Nav::Back);
+                self.screen = Screen::Games;
+            }
+            _ => {
+                if button == MouseButton::Right {
+                    self.screen = Screen::Games;
+                }
+            }
+        }
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
+        self.scroll = (self.scroll - y * SCROLL_STEP)
+            .max(0.0)
+            .min(self.max_scroll());
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if scanning::key_down(keycode) {
+            return;
+        }
+        self.press_key(ctx, keycode, repeat);
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods) {
+        scanning::key_up(keycode);
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, _id: GamepadId) {
+        if scanning::button_down(btn) {
+            return;
+        }
+        if self.damaged.is_asking() {
+            if let Some(nav) = Nav::from_button(btn) {
+                self.damaged.navigate(nav);
+            }
+            return;
+        }
+        if let Screen::Controls(controls) = &mut self.screen {
+            if controls.button_down(btn) {
+                return;
+            }
+        }
+        if let Screen::Gallery(gallery) = &mut self.screen {
+            match btn {
+                Button::West => return gallery.delete(),
+                Button::North => return gallery.open_folder(),
+                _ => (),
+            }
+        }
+        if let Screen::Crashes(crashes) = &mut self.screen {
+            if btn == Button::West {
+                if !crashes.discard() {
+                    self.screen = Screen::Games;
+                }
+                return;
+            }
+        }
+        match (btn, &self.screen) {
+            (Button::North, Screen::Games) => self.next_theme(),
+            (Button::West, Screen::Games) => {
+                self.screen = Screen::Controls(controls::Controls::load())
+            }
+            (Button::RightTrigger, Screen::Games) => {
+                self.screen = Screen::Mixer(mixer::Mixer::load(ctx))
+            }
+            (Button::LeftTrigger, Screen::Games) => self.screen = Screen::Shop(shop::Shop::load()),
+            (Button::LeftTrigger2, Screen::Games) => {
+                self.screen = Screen::Stats(dashboard::Dashboard::load())
+            }
+            (Button::RightTrigger2, Screen::Games) => {
+                self.screen = Screen::Gallery(gallery::Gallery::load(ctx))
+            }
+            (Button::LeftThumb, Screen::Games) => {
+                self.screen = Screen::Display(display::DisplayScreen::load(ctx))
+            }
+            (Button::RightThumb, Screen::Games) => self.start_sync(),
+            _ => {
+                if let Some(nav) = Nav::from_button(btn) {
+                    self.navigate(ctx, nav);
+                }
+            }
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, _id: GamepadId) {
+        scanning::button_up(btn);
+    }
+
+    fn gamepad_axis_event(&mut self, ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        match self.sticks.gamepad_axis(axis, value, id) {
+            Some(nav) if self.damaged.is_asking() => self.damaged.navigate(nav),
+            Some(nav) => self.navigate(ctx, nav),
+            None => (),
+        }
+    }
+
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
+        self.window_focused = gained;
+        // The system's dark or light preference may have been changed while it was away.
+        if gained && theme::chosen().name == theme::SYSTEM.name {
+            theme::refresh_system_appearance();
+            self.palette = theme::current().apply(PALETTE);
+        }
+    }
+}
+
+/// Loads the last played game, dropping it if it is no longer in the registry.
+fn load_last_played() -> Option<(&'static GameInfo, LastPlayed)> {
+    let last = LastPlayed::load()?;
+    registry::find(&last.game).map(|game| (game, last))
+}
+
+fn main() -> GameResult {
+    crash::install();
+    let builder = ggez::ContextBuilder::new("launcher", "Sprial404")
+        .window_setup(window::setup("Quick Games"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "launcher", common::embedded_assets!()).build()?;
+    if let Err(e) = window::apply(ctx, &DisplaySettings::load()) {
+        eprintln!("Couldn't change the display mode: {}", e);
+    }
+    let state = &mut LauncherState::new(ctx);
+    if args::flag("resume") {
+        state.resume();
+    }
+    event::run(ctx, events_loop, state)
+}
@@ -0,0 +1,199 @@
+//! The mixer, where the master, music and sound effect volumes shared by every game are set.
+//!
+//! Changes are saved as soon as they are made, so any game that is running picks them up, and
+//! each change plays a preview at the new volume.
+
+use ggez::audio::{self, SoundSource};
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::audio::{Channel, Volumes};
+use common::input::Nav;
+use common::ui;
+
+const PREVIEW_MUSIC: &str = "/audio/Computer_Music_All-Stars_-_Albatross_v2.ogg";
+const PREVIEW_SFX: &str = "/audio/bounce.ogg";
+
+/// How much each step changes a volume, as a percentage.
+const STEP: u32 = 10;
+
+const LABEL_X: f32 = 40.0;
+/// Where the first slider goes, with the rest below it.
+const SLIDER_ORIGIN: (f32, f32) = (280.0, 150.0);
+const SLIDER_SIZE: (f32, f32) = (560.0, 28.0);
+const SLIDER_SPACING: f32 = 80.0;
+
+const TRACK_COLOR: [f32; 4] = [0.14, 0.14, 0.18, 1.0];
+const FILL_COLOR: [f32; 4] = [0.35, 0.7, 1.0, 1.0];
+
+/// The mixer screen.
+pub struct Mixer {
+    volumes: Volumes,
+    /// The index of the focused channel.
+    focused: usize,
+    /// The preview sounds, if they could be loaded. They stop when the mixer is closed.
+    music: Option<audio::Source>,
+    sfx: Option<audio::Source>,
+    message: String,
+}
+
+fn slider_rect(index: usize) -> Rect {
+    Rect::new(
+        SLIDER_ORIGIN.0,
+        SLIDER_ORIGIN.1 + index as f32 * SLIDER_SPACING,
+        SLIDER_SIZE.0,
+        SLIDER_SIZE.1,
+    )
+}
+
+impl Mixer {
+    /// Loads the current profile's volumes and the preview sounds.
+    pub fn load(ctx: &mut Context) -> Self {
+        let music = audio::Source::new(ctx, PREVIEW_MUSIC)
+            .ok()
+            .map(|mut music| {
+                music.set_repeat(true);
+                music
+            });
+        Mixer {
+            volumes: Volumes::load(),
+            focused: 0,
+            music,
+            sfx: audio::Source::new(ctx, PREVIEW_SFX).ok(),
+            message: String::new(),
+        }
+    }
+
+    /// Moves between the sliders and changes their volumes, returning false when the player
+    /// backs out of the mixer.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        let value = self.volumes.get(Channel::ALL[self.focused]);
+        match nav {
+            Nav::Back => return false,
+            Nav::Up => self.focused = self.focused.saturating_sub(1),
+            Nav::Down => self.focused = (self.focused + 1).min(Channel::ALL.len() - 1),
+            Nav::Left => self.set(self.focused, value.saturating_sub(STEP)),
+            Nav::Right => self.set(self.focused, value + STEP),
+            Nav::Confirm => self.preview(Channel::ALL[self.focused]),
+        }
+        true
+    }
+
+    fn slider_at(x: f32, y: f32) -> Option<usize> {
+        (0..Channel::ALL.len()).find(|&index| {
+            let mut rect = slider_rect(index);
+            // Give the thin sliders some room to be clicked.
+            rect.y -= SLIDER_SIZE.1 / 2.0;
+            rect.h *= 2.0;
+            rect.contains(ggez::mint::Point2 { x, y })
+        })
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if let Some(index) = Mixer::slider_at(x, y) {
+            self.focused = index;
+        }
+    }
+
+    /// Sets the volume of the slider under the mouse to wherever it was clicked.
+    pub fn click(&mut self, x: f32, y: f32) {
+        if let Some(index) = Mixer::slider_at(x, y) {
+            self.focused = index;
+            let fraction = (x - SLIDER_ORIGIN.0) / SLIDER_SIZE.0;
+            let value = (fraction * 100.0 / STEP as f32).round() as u32 * STEP;
+            self.set(index, value);
+        }
+    }
+
+    /// Sets a channel's volume, saving it and playing a preview.
+    fn set(&mut self, index: usize, value: u32) {
+        let channel = Channel::ALL[index];
+        if self.volumes.get(channel) == value.min(100) {
+            return;
+        }
+        self.volumes.set(channel, value);
+        self.message = match self.volumes.save() {
+            Ok(()) => String::new(),
+            Err(e) => format!("Couldn't save the volume: {}", e),
+        };
+        self.preview(channel);
+    }
+
+    /// Plays what the channel changes at its new volume. Music keeps playing while the mixer is
+    /// open so the other volumes can be heard against it.
+    fn preview(&mut self, channel: Channel) {
+        if let Some(music) = &mut self.music {
+            music.set_volume(self.volumes.music());
+            if channel != Channel::Sfx && !music.playing() {
+                let _ = music.play();
+            }
+        }
+        if channel != Channel::Music {
+            if let Some(sfx) = &mut self.sfx {
+                sfx.set_volume(self.volumes.sfx());
+                let _ = sfx.play();
+            }
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Volume", [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (index, &channel) in Channel::ALL.iter().enumerate() {
+            let rect = slider_rect(index);
+            let mut fill = rect;
+            fill.w *= self.volumes.get(channel) as f32 / 100.0;
+            builder.rectangle(DrawMode::fill(), rect, TRACK_COLOR.into());
+            if fill.w > 0.0 {
+                builder.rectangle(DrawMode::fill(), fill, FILL_COLOR.into());
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for (index, &channel) in Channel::ALL.iter().enumerate() {
+            let rect = slider_rect(index);
+            ui::draw_text(
+                ctx,
+                channel.title(),
+                [0.8, 0.8, 0.85, 1.0],
+                22.0,
+                LABEL_X,
+                rect.y + 2.0,
+            )?;
+            let value = format!("{}%", self.volumes.get(channel));
+            ui::draw_text(
+                ctx,
+                &value,
+                [1.0, 1.0, 1.0, 1.0],
+                22.0,
+                rect.x + rect.w + 24.0,
+                rect.y + 2.0,
+            )?;
+            if index == self.focused {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+
+        if self.music.is_none() && self.sfx.is_none() {
+            ui::draw_text(
+                ctx,
+                "The preview sounds couldn't be loaded.",
+                [0.8, 0.8, 0.85, 1.0],
+                20.0,
+                LABEL_X,
+                slider_rect(Channel::ALL.len()).y,
+            )?;
+        }
+        ui::draw_text(
+            ctx,
+            &self.message,
+            [1.0, 0.85, 0.3, 1.0],
+            20.0,
+            LABEL_X,
+            slider_rect(Channel::ALL.len()).y + 40.0,
+        )?;
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+//! The privacy screen, where the profile chooses whether to share anonymous play statistics,
+//! and can see exactly what would be sent. The launcher opens it by itself to ask, until the
+//! profile has answered.
+
+use ggez::graphics::Rect;
+use ggez::{Context, GameResult};
+
+use common::input::Nav;
+use common::telemetry;
+use common::ui;
+
+const LABEL_X: f32 = 40.0;
+const TEXT_WIDTH: f32 = 960.0;
+const ROW_RECT: Rect = Rect {
+    x: 340.0,
+    y: 190.0,
+    w: 240.0,
+    h: 36.0,
+};
+/// Where the report starts, with a line for each line of it below.
+const REPORT_Y: f32 = 300.0;
+const REPORT_LINE_HEIGHT: f32 = 20.0;
+/// How many of the report's lines fit above the footer.
+const REPORT_LINES: usize = 17;
+
+const EXPLANATION: &str = "Share anonymous play statistics? Only counts are kept: how many \
+                           times each game is played in each mode, and how many runs score in \
+                           each band, like 100-999. There are no times, names or anything else \
+                           about you.";
+
+/// The privacy screen.
+pub struct Privacy {
+    /// Whether the profile is still to answer.
+    asking: bool,
+    enabled: bool,
+    url: Option<String>,
+    /// What would be sent, exactly as it would be sent.
+    report: String,
+    message: String,
+}
+
+impl Privacy {
+    /// Loads whether the profile shares its statistics and what it would send.
+    pub fn load() -> Self {
+        let consent = telemetry::consent();
+        let mut privacy = Privacy {
+            asking: consent.is_none(),
+            enabled: consent == Some(true),
+            url: telemetry::url(),
+            report: String::new(),
+            message: String::new(),
+        };
+        privacy.load_report();
+        privacy
+    }
+
+    /// Whether the profile is still to answer, when the launcher opens the screen to ask.
+    pub fn is_asking(&self) -> bool {
+        self.asking
+    }
+
+    fn load_report(&mut self) {
+        self.report = match telemetry::load() {
+            Ok(counts) => telemetry::report(&counts),
+            Err(e) => format!("Couldn't read the statistics: {}", e),
+        };
+    }
+
+    /// Turns sharing on or off, returning false when the player backs out of the screen. Backing
+    /// out before answering is answering no.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        match nav {
+            Nav::Back => {
+                if self.asking {
+                    self.answer(false);
+                }
+                return false;
+            }
+            Nav::Confirm | Nav::Left | Nav::Right => {
+                let on = self.asking || !self.enabled;
+                self.answer(on);
+            }
+            Nav::Up | Nav::Down => (),
+        }
+        true
+    }
+
+    /// Turns sharing on or off when the row is clicked.
+    pub fn click(&mut self, x: f32, y: f32) {
+        if ROW_RECT.contains(ggez::mint::Point2 { x, y }) {
+            self.navigate(Nav::Confirm);
+        }
+    }
+
+    fn answer(&mut self, on: bool) {
+        self.message = match telemetry::set_enabled(on) {
+            Ok(()) => {
+                self.asking = false;
+                self.enabled = on;
+                String::new()
+            }
+            Err(e) => format!("Couldn't save the choice: {}", e),
+        };
+        self.load_report();
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Privacy", [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+        ui::draw_wrapped(
+            ctx,
+            EXPLANATION,
+            [0.8, 0.8, 0.85, 1.0],
+            20.0,
+            LABEL_X,
+            90.0,
+            TEXT_WIDTH,
+        )?;
+
+        ui::draw_text(
+            ctx,
+            "Share play statistics",
+            [0.8, 0.8, 0.85, 1.0],
+            22.0,
+            LABEL_X,
+            ROW_RECT.y + 6.0,
+        )?;
+        let value = match (self.asking, self.enabled) {
+            (true, _) => "Not answered",
+            (false, true) => "On",
+            (false, false) => "Off",
+        };
+        ui::draw_text(
+            ctx,
+            &format!("< {} >", value),
+            [1.0, 1.0, 1.0, 1.0],
+            22.0,
+            ROW_RECT.x + 8.0,
+            ROW_RECT.y + 6.0,
+        )?;
+        ui::draw_focus(ctx, ROW_RECT)?;
+
+        let destination = match &self.url {
+            Some(url) => format!("Sent to {} when the launcher starts, then cleared.", url),
+            None => format!(
+                "Nothing is sent anywhere until {} is set. Until then it's only kept here.",
+                telemetry::URL_SETTING
+            ),
+        };
+        let destination = if self.message.is_empty() {
+            destination
+        } else {
+            self.message.clone()
+        };
+        ui::draw_text(
+            ctx,
+            &destination,
+            [1.0, 0.85, 0.3, 1.0],
+            18.0,
+            LABEL_X,
+            ROW_RECT.y + 56.0,
+        )?;
+
+        let lines: Vec<&str> = self.report.lines().collect();
+        let shown = if lines.len() > REPORT_LINES {
+            REPORT_LINES - 1
+        } else {
+            lines.len()
+        };
+        for (index, line) in lines.iter().take(shown).enumerate() {
+            ui::draw_text(
+                ctx,
+                line,
+                [0.7, 0.9, 0.7, 1.0],
+                16.0,
+                LABEL_X,
+                REPORT_Y + index as f32 * REPORT_LINE_HEIGHT,
+            )?;
+        }
+        if shown < lines.len() {
+            ui::draw_text(
+                ctx,
+                &format!("...and {} more lines", lines.len() - shown),
+                [0.6, 0.6, 0.65, 1.0],
+                16.0,
+                LABEL_X,
+                REPORT_Y + shown as f32 * REPORT_LINE_HEIGHT,
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+//! The shop, where tokens earned in any game are spent on themes for every game.
+
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::input::Nav;
+use common::theme::{self, Theme, THEMES};
+use common::tokens::Wallet;
+use common::ui;
+
+const LABEL_X: f32 = 40.0;
+/// Where the first item goes, with the rest below it.
+const ITEM_ORIGIN: (f32, f32) = (40.0, 130.0);
+const ITEM_SIZE: (f32, f32) = (700.0, 56.0);
+const ITEM_SPACING: f32 = 10.0;
+const SWATCH_SIZE: f32 = 28.0;
+
+const ITEM_COLOR: [f32; 4] = [0.14, 0.14, 0.18, 1.0];
+
+/// The shop screen.
+pub struct Shop {
+    /// The current profile's tokens, unless they couldn't be read.
+    wallet: Option<Wallet>,
+    /// The themes for sale, in the order they are listed.
+    items: Vec<&'static Theme>,
+    /// The theme in use.
+    current: &'static Theme,
+    /// The index of the focused item.
+    focused: usize,
+    message: String,
+}
+
+fn item_rect(index: usize) -> Rect {
+    Rect::new(
+        ITEM_ORIGIN.0,
+        ITEM_ORIGIN.1 + index as f32 * (ITEM_SIZE.1 + ITEM_SPACING),
+        ITEM_SIZE.0,
+        ITEM_SIZE.1,
+    )
+}
+
+impl Shop {
+    /// Loads the current profile's tokens.
+    pub fn load() -> Self {
+        let (wallet, message) = match Wallet::load() {
+            Ok(wallet) => (Some(wallet), String::new()),
+            Err(e) => (None, format!("Couldn't read the tokens: {}", e)),
+        };
+        Shop {
+            wallet,
+            items: THEMES.iter().filter(|theme| theme.price > 0).collect(),
+            current: theme::current(),
+            focused: 0,
+            message,
+        }
+    }
+
+    /// Moves through the items, returning false when the player backs out of the shop.
+    pub fn navigate(&mut self, nav: Nav) -> bool {
+        match nav {
+            Nav::Back => return false,
+            Nav::Up => self.focused = self.focused.saturating_sub(1),
+            Nav::Down => self.focused = (self.focused + 1).min(self.items.len() - 1),
+            Nav::Confirm => self.activate(self.focused),
+            _ => (),
+        }
+        true
+    }
+
+    fn item_at(&self, x: f32, y: f32) -> Option<usize> {
+        (0..self.items.len()).find(|&index| item_rect(index).contains(ggez::mint::Point2 { x, y }))
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if let Some(index) = self.item_at(x, y) {
+            self.focused = index;
+        }
+    }
+
+    pub fn click(&mut self, x: f32, y: f32) {
+        if let Some(index) = self.item_at(x, y) {
+            self.focused = index;
+            self.activate(index);
+        }
+    }
+
+    /// Buys the item, or uses it if it is already owned.
+    fn activate(&mut self, index: usize) {
+        let theme = self.items[index];
+        let wallet = match &mut self.wallet {
+            Some(wallet) => wallet,
+            None => return,
+        };
+        if !theme.is_owned(wallet) {
+            self.message = match wallet.buy(&theme.item(), theme.price) {
+                Ok(true) => format!("Bought {}. Pick it again to use it.", theme.title),
+                Ok(false) => format!(
+                    "{} costs {} tokens, {} more than you have.",
+                    theme.title,
+                    theme.price,
+                    theme.price - wallet.balance()
+                ),
+                Err(e) => format!("Couldn't buy {}: {}", theme.title, e),
+            };
+            return;
+        }
+        self.message = match theme::save(theme) {
+            Ok(()) => {
+                self.current = theme;
+                format!("Theme: {}", theme.title)
+            }
+            Err(e) => format!("Couldn't save the theme: {}", e),
+        };
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        ui::draw_text(ctx, "Shop", [1.0, 1.0, 1.0, 1.0], 44.0, LABEL_X, 20.0)?;
+        if let Some(wallet) = &self.wallet {
+            let balance = format!("{} tokens", wallet.balance());
+            ui::draw_text(
+                ctx,
+                &balance,
+                [1.0, 0.85, 0.3, 1.0],
+                26.0,
+                ITEM_ORIGIN.0 + ITEM_SIZE.0 - 160.0,
+                34.0,
+            )?;
+        }
+
+        // Each theme's colours as swatches, after its name.
+        let mut builder = graphics::MeshBuilder::new();
+        for (index, theme) in self.items.iter().enumerate() {
+            let rect = item_rect(index);
+            builder.rectangle(DrawMode::fill(), rect, ITEM_COLOR.into());
+            if let Some(palette) = theme.palette {
+                let colors = [
+                    palette.background,
+                    palette.text,
+                    palette.player,
+                    palette.hazard,
+                    palette.pickup,
+                ];
+                for (i, color) in colors.iter().enumerate() {
+                    let swatch = Rect::new(
+                        rect.x + 220.0 + i as f32 * (SWATCH_SIZE + 6.0),
+                        rect.y + (rect.h - SWATCH_SIZE) / 2.0,
+                        SWATCH_SIZE,
+                        SWATCH_SIZE,
+                    );
+                    builder.rectangle(DrawMode::fill(), swatch, (*color).into());
+                }
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for (index, theme) in self.items.iter().enumerate() {
+            let rect = item_rect(index);
+            ui::draw_text(
+                ctx,
+                theme.title,
+                [1.0, 1.0, 1.0, 1.0],
+                22.0,
+                rect.x + 14.0,
+                rect.y + 16.0,
+            )?;
+            let owned = self
+                .wallet
+                .as_ref()
+                .map_or(false, |wallet| theme.is_owned(wallet));
+            let status = if theme.name == self.current.name {
+                "In use".to_string()
+            } else if owned {
+                "Owned".to_string()
+            } else {
+                format!("{} tokens", theme.price)
+            };
+            ui::draw_text(
+                ctx,
+                &status,
+                [0.8, 0.8, 0.85, 1.0],
+                20.0,
+                rect.x + rect.w - 160.0,
+                rect.y + 18.0,
+            )?;
+            if index == self.focused {
+                ui::draw_focus(ctx, rect)?;
+            }
+        }
+
+        ui::draw_wrapped(
+            ctx,
+            &self.message,
+            [1.0, 0.85, 0.3, 1.0],
+            20.0,
+            LABEL_X,
+            item_rect(self.items.len()).y + 20.0,
+            960.0,
+        )?;
+        Ok(())
+    }
+}
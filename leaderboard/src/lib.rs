@@ -0,0 +1,341 @@
+//! Signed submissions to the online leaderboard, shared by the games, which sign their runs, and
+//! the leaderboard server, which checks them.
+//!
+//! Each install makes itself a key the first time it submits a run, and registers the key's
+//! public half with the server before anything else. Every run it submits after that is signed
+//! with the key: the score, the seed it was played with and a hash of its replay, if the game
+//! keeps one. A server turns away runs that aren't signed by a registered key, or whose fields
+//! were changed after signing, so a score can't be forged by hand-writing a request.
+//!
+//! It doesn't stop someone pulling the key out of their own install, so the replay itself comes
+//! with the run, for a server to play again. Everything goes over the wire as text, a kind line
+//! followed by `name: value` lines, the last of which is the signature of everything before it.
+//! The replay, if there is one, follows after a blank line. For example:
+//!
+//! ```text
+//! submit
+//! game: snake
+//! mode: daily
+//! player: Sam
+//! seed: 11400714819323198485
+//! score: 42
+//! replay: 9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08
+//! key: 3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c
+//! signature: 6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac...
+//!
+//! #version 1
+//! wrap true
+//! ...
+//! ```
+//!
+//! Registering is the same, with `register` as its kind and only the key, signed by itself.
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use rand::rngs::OsRng;
+use sha2::{Digest, Sha256};
+
+use std::convert::TryFrom;
+use std::fmt::Write;
+
+const REGISTER: &str = "register";
+const SUBMIT: &str = "submit";
+const SIGNATURE_FIELD: &str = "signature";
+
+/// A run, as it's submitted to the leaderboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Submission {
+    /// The game's name, like "snake".
+    pub game: String,
+    /// Which board the run goes on, like "daily".
+    pub mode: String,
+    /// The name the run is shown under.
+    pub player: String,
+    /// The seed the run was played with.
+    pub seed: u64,
+    pub score: f64,
+    /// The hash of the run's replay, from `replay_hash`, for games that keep one.
+    pub replay: Option<String>,
+}
+
+/// A message a server has checked the signature of.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    /// An install registering its key, given as it's written in submissions.
+    Register { key: String },
+    /// A run, signed by `key`, and its replay if one came with it. The server should only take
+    /// it if the key is registered.
+    Submit {
+        key: String,
+        submission: Submission,
+        replay: Option<String>,
+    },
+}
+
+/// The hash a replay is signed as, so the server can tell it's the replay of the run.
+pub fn replay_hash(replay: &[u8]) -> String {
+    to_hex(&Sha256::digest(replay))
+}
+
+/// An install's key.
+pub struct Key(Keypair);
+
+impl Key {
+    pub fn generate() -> Self {
+        Key(Keypair::generate(&mut OsRng))
+    }
+
+    /// Reads a key written by `to_hex`.
+    pub fn parse(hex: &str) -> Option<Self> {
+        Keypair::from_bytes(&from_hex(hex.trim())?).ok().map(Key)
+    }
+
+    /// The key, both halves, for the install to keep. It's only ever kept, never sent.
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0.to_bytes())
+    }
+
+    /// The key's public half, as servers know it by.
+    pub fn public(&self) -> String {
+        to_hex(self.0.public.as_bytes())
+    }
+
+    /// The message registering the key.
+    pub fn register(&self) -> String {
+        let mut message = format!("{}\n", REGISTER);
+        field(&mut message, "key", &self.public());
+        self.sign(message)
+    }
+
+    /// The message submitting `submission`, signed with the key, with its replay after it.
+    pub fn submit(&self, submission: &Submission, replay: Option<&str>) -> String {
+        let mut message = format!("{}\n", SUBMIT);
+        field(&mut message, "game", &submission.game);
+        field(&mut message, "mode", &submission.mode);
+        field(&mut message, "player", &submission.player);
+        field(&mut message, "seed", &submission.seed.to_string());
+        field(&mut message, "score", &submission.score.to_string());
+        field(
+            &mut message,
+            "replay",
+            submission.replay.as_deref().unwrap_or("none"),
+        );
+        field(&mut message, "key", &self.public());
+        let mut message = self.sign(message);
+        if let Some(replay) = replay {
+            message.push('\n');
+            message.push_str(replay);
+        }
+        message
+    }
+
+    fn sign(&self, mut message: String) -> String {
+        let signature = self.0.sign(message.as_bytes());
+        field(
+            &mut message,
+            SIGNATURE_FIELD,
+            &to_hex(&signature.to_bytes()),
+        );
+        message
+    }
+}
+
+/// Adds a `name: value` line, with any line breaks in the value made spaces so they can't
+/// start lines of their own.
+fn field(message: &mut String, name: &str, value: &str) {
+    let value: String = value
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let _ = writeln!(message, "{}: {}", name, value.trim());
+}
+
+/// Checks a message's signature and reads it, for a server. The message is checked as it was
+/// sent, so it's never read differently from how it was signed. A replay that came with a run
+/// is checked against the hash that was signed.
+pub fn verify(message: &str) -> Result<Message, String> {
+    // No line of the signed part can start with the signature, so the first is the real one.
+    let start = message
+        .find(&format!("\n{}: ", SIGNATURE_FIELD))
+        .ok_or_else(|| "it isn't signed".to_string())?
+        + 1;
+    let (signed, rest) = message.split_at(start);
+    let (signature, attached) = match rest.find('\n') {
+        Some(end) => (&rest[..end], &rest[end + 1..]),
+        None => (rest, ""),
+    };
+    let signature = signature[SIGNATURE_FIELD.len() + 2..].trim();
+
+    let mut lines = signed.lines();
+    let kind = lines.next().unwrap_or("");
+    let mut fields = Vec::new();
+    for line in lines {
+        let mut parts = line.splitn(2, ": ");
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => fields.push((name, value)),
+            _ => return Err(format!("couldn't read \"{}\"", line)),
+        }
+    }
+    let get = |name: &str| {
+        fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| format!("there's no {}", name))
+    };
+
+    let key = get("key")?;
+    let public = from_hex(key)
+        .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| "the key isn't a key".to_string())?;
+    let signature = from_hex(signature)
+        .and_then(|bytes| Signature::try_from(&bytes[..]).ok())
+        .ok_or_else(|| "the signature isn't a signature".to_string())?;
+    public
+        .verify_strict(signed.as_bytes(), &signature)
+        .map_err(|_| "the signature doesn't match".to_string())?;
+
+    let key = key.to_string();
+    match kind {
+        REGISTER => Ok(Message::Register { key }),
+        SUBMIT => {
+            let score: f64 = get("score")?
+                .parse()
+                .map_err(|_| "the score isn't a number".to_string())?;
+            if !score.is_finite() {
+                return Err("the score isn't a number".to_string());
+            }
+            let hash = match get("replay")? {
+                "none" => None,
+                hash => Some(hash.to_string()),
+            };
+            let replay = match attached.strip_prefix('\n') {
+                Some(replay) if hash.as_deref() == Some(&replay_hash(replay.as_bytes())) => {
+                    Some(replay.to_string())
+                }
+                Some(_) => return Err("the replay isn't the one signed".to_string()),
+                None => None,
+            };
+            let submission = Submission {
+                game: get("game")?.to_string(),
+                mode: get("mode")?.to_string(),
+                player: get("player")?.to_string(),
+                seed: get("seed")?
+                    .parse()
+                    .map_err(|_| "the seed isn't a number".to_string())?,
+                score,
+                replay: hash,
+            };
+            Ok(Message::Submit {
+                key,
+                submission,
+                replay,
+            })
+        }
+        kind => Err(format!("there's no kind of message called \"{}\"", kind)),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&hex[at..at + 2], 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run() -> Submission {
+        Submission {
+            game: "snake".to_string(),
+            mode: "daily".to_string(),
+            player: "Sam".to_string(),
+            seed: 11_400_714_819_323_198_485,
+            score: 42.0,
+            replay: Some(replay_hash(b"replay")),
+        }
+    }
+
+    #[test]
+    fn signed_runs_verify() {
+        let key = Key::generate();
+        let message = key.submit(&run(), Some("replay"));
+        assert_eq!(
+            verify(&message),
+            Ok(Message::Submit {
+                key: key.public(),
+                submission: run(),
+                replay: Some("replay".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn runs_can_come_without_their_replay() {
+        match verify(&Key::generate().submit(&run(), None)) {
+            Ok(Message::Submit { replay, .. }) => assert_eq!(replay, None),
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn other_replays_are_turned_away() {
+        let message = Key::generate().submit(&run(), Some("another replay"));
+        assert!(verify(&message).is_err());
+    }
+
+    #[test]
+    fn registering_verifies() {
+        let key = Key::generate();
+        assert_eq!(
+            verify(&key.register()),
+            Ok(Message::Register { key: key.public() })
+        );
+    }
+
+    #[test]
+    fn changed_scores_are_turned_away() {
+        let message = Key::generate().submit(&run(), Some("replay"));
+        let forged = message.replace("score: 42\n", "score: 4200\n");
+        assert_ne!(forged, message);
+        assert!(verify(&forged).is_err());
+    }
+
+    #[test]
+    fn runs_signed_by_another_key_are_turned_away() {
+        let message = Key::generate().submit(&run(), Some("replay"));
+        let other = Key::generate().public();
+        let start = message.find("key: ").unwrap() + "key: ".len();
+        let forged = format!("{}{}{}", &message[..start], other, &message[start + 64..]);
+        assert!(verify(&forged).is_err());
+    }
+
+    #[test]
+    fn line_breaks_in_names_cant_add_fields() {
+        let mut submission = run();
+        submission.player = "Sam\nscore: 9000".to_string();
+        match verify(&Key::generate().submit(&submission, None)) {
+            Ok(Message::Submit { submission, .. }) => {
+                assert_eq!(submission.player, "Sam score: 9000");
+                assert_eq!(submission.score, 42.0);
+            }
+            other => panic!("expected a run, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keys_are_kept_as_they_were() {
+        let key = Key::generate();
+        let kept = Key::parse(&key.to_hex()).unwrap();
+        assert_eq!(kept.public(), key.public());
+        assert!(Key::parse("not a key").is_none());
+    }
+}
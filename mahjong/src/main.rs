@@ -0,0 +1,576 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, filesystem, graphics, timer, Context, GameError, GameResult};
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets;
+use common::level::Level;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::seq::SliceRandom;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+/// Positions are measured in half tiles, so tiles can sit across the middle of the ones below.
+const HALF_TILE: (f32, f32) = (20.0, 26.0);
+/// How far each layer is drawn up and to the left of the one below, for a bit of depth.
+const LAYER_OFFSET: f32 = 5.0;
+const BOARD_ORIGIN: (f32, f32) = (90.0, 90.0);
+
+const LAYOUTS_DIR: &str = "/layouts";
+
+/// The faces of the tiles: three suits of nine, four winds, three dragons, four flowers and four
+/// seasons.
+const SUIT_FACES: usize = 27;
+const WIND_FACES: usize = 4;
+const DRAGON_FACES: usize = 3;
+const FLOWER_FACES: usize = 4;
+/// The first flower face. Any flower matches any other flower, and the same for seasons.
+const FIRST_FLOWER: usize = SUIT_FACES + WIND_FACES + DRAGON_FACES;
+const FIRST_SEASON: usize = FIRST_FLOWER + FLOWER_FACES;
+
+/// How long a hint stays highlighted.
+const HINT_TIME: f32 = 2.0;
+
+/// The position of a tile as its layer and top left corner in half tiles.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Pos {
+    layer: i32,
+    x: i32,
+    y: i32,
+}
+
+impl Pos {
+    /// Whether a tile at `self` overlaps one at `other` when looking down on the board.
+    fn overlaps(&self, other: &Pos) -> bool {
+        (self.x - other.x).abs() < 2 && (self.y - other.y).abs() < 2
+    }
+
+    fn rect(&self) -> Rect {
+        let lift = self.layer as f32 * LAYER_OFFSET;
+        Rect::new(
+            BOARD_ORIGIN.0 + self.x as f32 * HALF_TILE.0 - lift,
+            BOARD_ORIGIN.1 + self.y as f32 * HALF_TILE.1 - lift,
+            HALF_TILE.0 * 2.0,
+            HALF_TILE.1 * 2.0,
+        )
+    }
+}
+
+/// Whether the tile at `pos` can be taken: nothing on top of it and at least one side open.
+fn is_free(pos: &Pos, board: &[Pos]) -> bool {
+    let covered = board
+        .iter()
+        .any(|other| other.layer == pos.layer + 1 && other.overlaps(pos));
+    let beside = |dx: i32| {
+        board.iter().any(|other| {
+            other.layer == pos.layer && other.x == pos.x + dx && (other.y - pos.y).abs() < 2
+        })
+    };
+    !covered && (!beside(-2) || !beside(2))
+}
+
+/// Which tiles match each other. Flowers all match each other, as do seasons.
+fn match_kind(face: usize) -> usize {
+    if face >= FIRST_SEASON {
+        FIRST_SEASON
+    } else if face >= FIRST_FLOWER {
+        FIRST_FLOWER
+    } else {
+        face
+    }
+}
+
+fn face_label(face: usize) -> (String, [f32; 4]) {
+    if face < SUIT_FACES {
+        let suits = [
+            ("B", [0.1, 0.5, 0.1, 1.0]),
+            ("C", [0.7, 0.1, 0.1, 1.0]),
+            ("D", [0.1, 0.2, 0.7, 1.0]),
+        ];
+        let (suit, color) = suits[face / 9];
+        (format!("{}{}", face % 9 + 1, suit), color)
+    } else if face < SUIT_FACES + WIND_FACES {
+        let winds = ["E", "S", "W", "N"];
+        (winds[face - SUIT_FACES].to_string(), [0.1, 0.1, 0.1, 1.0])
+    } else if face < FIRST_FLOWER {
+        let dragons = [
+            ("Rd", [0.8, 0.1, 0.1, 1.0]),
+            ("Gd", [0.1, 0.6, 0.1, 1.0]),
+            ("Wd", [0.3, 0.3, 0.6, 1.0]),
+        ];
+        let (name, color) = dragons[face - SUIT_FACES - WIND_FACES];
+        (name.to_string(), color)
+    } else if face < FIRST_SEASON {
+        (
+            format!("F{}", face - FIRST_FLOWER + 1),
+            [0.8, 0.3, 0.6, 1.0],
+        )
+    } else {
+        (
+            format!("S{}", face - FIRST_SEASON + 1),
+            [0.8, 0.5, 0.1, 1.0],
+        )
+    }
+}
+
+/// Every matching pair of faces in a full set of 144 tiles.
+fn all_pairs() -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for face in 0..FIRST_FLOWER {
+        // Four of each, so two pairs.
+        pairs.push((face, face));
+        pairs.push((face, face));
+    }
+    for &first in [FIRST_FLOWER, FIRST_SEASON].iter() {
+        pairs.push((first, first + 1));
+        pairs.push((first + 2, first + 3));
+    }
+    pairs
+}
+
+/// A named arrangement of tile positions.
+#[derive(Debug, Clone)]
+struct Layout {
+    name: String,
+    positions: Vec<Pos>,
+}
+
+impl Layout {
+    /// The classic turtle layout of 144 tiles.
+    pub fn turtle() -> Self {
+        let mut positions = Vec::new();
+        let mut add = |layer, x, y| positions.push(Pos { layer, x, y });
+
+        let rows = [
+            (1, 12),
+            (3, 10),
+            (2, 11),
+            (1, 12),
+            (1, 12),
+            (2, 11),
+            (3, 10),
+            (1, 12),
+        ];
+        for (y, &(first, last)) in rows.iter().enumerate() {
+            for x in first..=last {
+                add(0, x * 2, y as i32 * 2);
+            }
+        }
+        // The head and tail sit across the middle two rows.
+        add(0, 0, 7);
+        add(0, 26, 7);
+        add(0, 28, 7);
+
+        for (layer, first, size) in [(1, 4, 6), (2, 5, 4), (3, 6, 2)].iter() {
+            for y in 0..*size {
+                for x in 0..*size {
+                    add(*layer, (first + x) * 2, (first - 3 + y) * 2);
+                }
+            }
+        }
+        add(4, 13, 7);
+
+        Layout {
+            name: "Turtle".to_string(),
+            positions,
+        }
+    }
+
+    /// Reads a layout from the shared level format. Each layer is drawn with `#` for the tiles,
+    /// two characters wide and two rows tall, with the layers separated by blank lines from the
+    /// bottom up.
+    fn from_level(level: &Level) -> Result<Self, String> {
+        let tiles = &level.tiles;
+        let mut positions = Vec::new();
+        let mut claimed = vec![false; tiles.width() * tiles.height()];
+        let (mut layer, mut top) = (0, 0);
+
+        for y in 0..tiles.height() as i32 {
+            let blank = (0..tiles.width() as i32).all(|x| tiles.get(x, y) == Some(&' '));
+            if blank {
+                layer += 1;
+                top = y + 1;
+                continue;
+            }
+
+            for x in 0..tiles.width() as i32 {
+                let index = y as usize * tiles.width() + x as usize;
+                if tiles.get(x, y) != Some(&'#') || claimed[index] {
+                    continue;
+                }
+
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+                    if tiles.get(x + dx, y + dy) != Some(&'#') {
+                        return Err(format!("incomplete tile at {}, {}", x, y));
+                    }
+                    claimed[(y + dy) as usize * tiles.width() + (x + dx) as usize] = true;
+                }
+                positions.push(Pos {
+                    layer,
+                    x,
+                    y: y - top,
+                });
+            }
+        }
+
+        if positions.is_empty() || positions.len() % 2 != 0 {
+            return Err("layouts need an even number of tiles".to_string());
+        }
+        Ok(Layout {
+            name: level.name().to_string(),
+            positions,
+        })
+    }
+}
+
+/// Loads every layout in the assets `layouts` directory after the built in turtle.
+fn load_layouts(ctx: &mut Context) -> GameResult<Vec<Layout>> {
+    let mut layouts = vec![Layout::turtle()];
+
+    let paths = match assets::read_dir(ctx, LAYOUTS_DIR) {
+        Ok(paths) => paths,
+        Err(_) => return Ok(layouts),
+    };
+
+    for path in paths {
+        let mut contents = String::new();
+        filesystem::open(ctx, &path)?.read_to_string(&mut contents)?;
+
+        let layout = Level::parse(&contents)
+            .and_then(|level| Layout::from_level(&level))
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path.display(), e)))?;
+        layouts.push(layout);
+    }
+    Ok(layouts)
+}
+
+/// Deals `pairs` onto `positions` so that the board can always be cleared.
+///
+/// Works backwards from an empty board: repeatedly picks two tiles that would be free with only
+/// the undealt tiles in place, and gives them a matching pair. Taking the pairs off in the
+/// opposite order clears the board.
+fn deal(positions: &[Pos], pairs: &[(usize, usize)]) -> Vec<(Pos, usize)> {
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let mut remaining = positions.to_vec();
+        let mut dealt = Vec::new();
+
+        for &(a, b) in pairs {
+            let mut free: Vec<Pos> = remaining
+                .iter()
+                .filter(|pos| is_free(pos, &remaining))
+                .cloned()
+                .collect();
+            if free.len() < 2 {
+                break;
+            }
+            free.shuffle(&mut rng);
+
+            for &(pos, face) in [(free[0], a), (free[1], b)].iter() {
+                remaining.retain(|other| *other != pos);
+                dealt.push((pos, face));
+            }
+        }
+
+        // Dead ends are rare, so just start over if we hit one.
+        if remaining.is_empty() {
+            return dealt;
+        }
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    layouts: Vec<Layout>,
+    current: usize,
+    /// The tiles left on the board, in drawing order.
+    tiles: Vec<(Pos, usize)>,
+    selected: Option<Pos>,
+    hint: Option<(Pos, Pos, f32)>,
+    elapsed: f32,
+    shuffles: u32,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a deal of the turtle layout.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let mut state = GameState {
+            layouts: load_layouts(ctx)?,
+            current: 0,
+            tiles: Vec::new(),
+            selected: None,
+            hint: None,
+            elapsed: 0.0,
+            shuffles: 0,
+            stats: StatsDb::open().ok(),
+        };
+        state.new_deal();
+        Ok(state)
+    }
+
+    fn new_deal(&mut self) {
+        let positions = &self.layouts[self.current].positions;
+        let mut pairs = all_pairs();
+        pairs.shuffle(&mut rand::thread_rng());
+        let pairs: Vec<(usize, usize)> = pairs
+            .into_iter()
+            .cycle()
+            .take(positions.len() / 2)
+            .collect();
+
+        self.tiles = deal(positions, &pairs);
+        self.sort_tiles();
+        self.selected = None;
+        self.hint = None;
+        self.elapsed = 0.0;
+        self.shuffles = 0;
+    }
+
+    /// Sorts the tiles so the lower layers are drawn first.
+    fn sort_tiles(&mut self) {
+        self.tiles
+            .sort_by_key(|(pos, _)| (pos.layer, pos.y, -pos.x));
+    }
+
+    fn positions(&self) -> Vec<Pos> {
+        self.tiles.iter().map(|(pos, _)| *pos).collect()
+    }
+
+    fn face_at(&self, pos: Pos) -> Option<usize> {
+        self.tiles
+            .iter()
+            .find(|(other, _)| *other == pos)
+            .map(|(_, face)| *face)
+    }
+
+    /// Finds a pair of free tiles that match.
+    fn find_pair(&self) -> Option<(Pos, Pos)> {
+        let positions = self.positions();
+        let free: Vec<&(Pos, usize)> = self
+            .tiles
+            .iter()
+            .filter(|(pos, _)| is_free(pos, &positions))
+            .collect();
+
+        for (i, (a, face_a)) in free.iter().enumerate() {
+            for (b, face_b) in free[i + 1..].iter() {
+                if match_kind(*face_a) == match_kind(*face_b) {
+                    return Some((*a, *b));
+                }
+            }
+        }
+        None
+    }
+
+    /// Redeals the remaining tiles onto the remaining positions, keeping it clearable.
+    fn shuffle(&mut self) {
+        let positions = self.positions();
+        let mut faces: Vec<usize> = self.tiles.iter().map(|(_, face)| *face).collect();
+        faces.sort_by_key(|face| (match_kind(*face), *face));
+        let mut pairs: Vec<(usize, usize)> =
+            faces.chunks(2).map(|pair| (pair[0], pair[1])).collect();
+        pairs.shuffle(&mut rand::thread_rng());
+
+        self.tiles = deal(&positions, &pairs);
+        self.sort_tiles();
+        self.selected = None;
+        self.shuffles += 1;
+    }
+
+    fn click(&mut self, x: f32, y: f32) {
+        let positions = self.positions();
+        // The last tile drawn at the point is the one on top.
+        let clicked = self
+            .tiles
+            .iter()
+            .rev()
+            .find(|(pos, _)| pos.rect().contains(ggez::mint::Point2 { x, y }))
+            .map(|(pos, face)| (*pos, *face));
+        let (pos, face) = match clicked {
+            Some(clicked) if is_free(&clicked.0, &positions) => clicked,
+            _ => return,
+        };
+
+        match self.selected {
+            Some(selected) if selected == pos => self.selected = None,
+            Some(selected) if self.face_at(selected).map(match_kind) == Some(match_kind(face)) => {
+                self.tiles
+                    .retain(|(other, _)| *other != selected && *other != pos);
+                self.selected = None;
+                self.hint = None;
+                if self.tiles.is_empty() {
+                    self.record_clear();
+                }
+            }
+            _ => self.selected = Some(pos),
+        }
+    }
+
+    fn record_clear(&self) {
+        let values = [
+            ("time", f64::from(self.elapsed)),
+            ("shuffles", f64::from(self.shuffles)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("mahjong", &values);
+        }
+        let _ = achievements::check_run("mahjong", &values);
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.tiles.is_empty() {
+            self.elapsed += dt;
+        }
+        if let Some((a, b, time)) = self.hint {
+            self.hint = if time > dt {
+                Some((a, b, time - dt))
+            } else {
+                None
+            };
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.35, 0.2, 1.0].into());
+
+        let positions = self.positions();
+        for (pos, face) in self.tiles.iter() {
+            let rect = pos.rect();
+            let highlighted = self.selected == Some(*pos)
+                || self.hint.map_or(false, |(a, b, _)| a == *pos || b == *pos);
+            let free = is_free(pos, &positions);
+
+            let color = if highlighted {
+                [1.0, 0.9, 0.4, 1.0]
+            } else if free {
+                [0.98, 0.96, 0.88, 1.0]
+            } else {
+                [0.85, 0.83, 0.75, 1.0]
+            };
+
+            let mut builder = graphics::MeshBuilder::new();
+            // The side of the tile shows through the layer offset.
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(rect.x + 3.0, rect.y + 3.0, rect.w, rect.h),
+                [0.55, 0.45, 0.3, 1.0].into(),
+            );
+            builder.rectangle(DrawMode::fill(), rect, color.into());
+            builder.rectangle(DrawMode::stroke(1.0), rect, [0.3, 0.25, 0.2, 1.0].into());
+            let mesh = builder.build(ctx)?;
+            graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+            let (label, label_color) = face_label(*face);
+            self.draw_text(ctx, &label, label_color, 20.0, rect.x + 6.0, rect.y + 14.0)?;
+        }
+
+        let layout = &self.layouts[self.current];
+        self.draw_text(
+            ctx,
+            &format!(
+                "{}  Tiles: {}  Time: {:.0}s  Shuffles: {}",
+                layout.name,
+                self.tiles.len(),
+                self.elapsed,
+                self.shuffles
+            ),
+            [1.0, 1.0, 1.0, 1.0],
+            22.0,
+            8.0,
+            8.0,
+        )?;
+        self.draw_text(
+            ctx,
+            "H: hint  S: shuffle  N: new deal  L: next layout",
+            [0.8, 0.9, 0.8, 1.0],
+            18.0,
+            8.0,
+            SCREEN_SIZE.1 - 28.0,
+        )?;
+
+        let message = if self.tiles.is_empty() {
+            Some("Cleared! Press N for a new deal.")
+        } else if self.find_pair().is_none() {
+            Some("No moves left. Press S to shuffle.")
+        } else {
+            None
+        };
+        if let Some(message) = message {
+            self.draw_text(ctx, message, [1.0, 0.9, 0.3, 1.0], 32.0, 160.0, 40.0)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left {
+            self.click(x, y);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::H => {
+                self.hint = self.find_pair().map(|(a, b)| (a, b, HINT_TIME));
+            }
+            KeyCode::S if !self.tiles.is_empty() => self.shuffle(),
+            KeyCode::N => self.new_deal(),
+            KeyCode::L => {
+                self.current = (self.current + 1) % self.layouts.len();
+                self.new_deal();
+            }
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("mahjong", "Sprial404")
+        .window_setup(window::setup("Mahjong Solitaire"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "mahjong", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,654 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, filesystem, graphics, timer, Context, GameError, GameResult};
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets;
+use common::level::Level;
+use common::physics::{self, Vec2};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+const HUD_HEIGHT: f32 = 60.0;
+const TILE_SIZE: f32 = 24.0;
+
+/// The directory within the assets that holds the holes of the course, played in file order.
+const HOLES_DIR: &str = "/holes";
+
+const BALL_RADIUS: f32 = 5.0;
+const CUP_RADIUS: f32 = 8.0;
+/// The fastest the ball can be moving and still drop into the cup.
+const CUP_CAPTURE_SPEED: f32 = 260.0;
+
+/// How far the mouse has to be dragged for a full power putt.
+const MAX_DRAG: f32 = 150.0;
+const MAX_PUTT_SPEED: f32 = 650.0;
+
+/// How quickly the ball slows down rolling over each surface, in pixels per second squared.
+const FAIRWAY_FRICTION: f32 = 110.0;
+const SAND_FRICTION: f32 = 600.0;
+const SLOPE_ACCELERATION: f32 = 160.0;
+const WALL_RESTITUTION: f32 = 0.75;
+
+/// Below this speed the ball is considered to have stopped.
+const STOP_SPEED: f32 = 6.0;
+/// How long the ball has to stay slow before it counts as stopped, so it can settle on slopes.
+const REST_TIME: f32 = 0.3;
+/// The furthest the ball moves in one physics step, to keep it from passing through walls.
+const MAX_STEP: f32 = 2.0;
+
+/// Strokes after which the ball is picked up and the hole scored.
+const MAX_STROKES: u32 = 10;
+const WATER_PENALTY: u32 = 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Fairway,
+    Sand,
+    Water,
+    /// A slope pushing the ball in a direction.
+    Slope(i32, i32),
+}
+
+impl Tile {
+    fn from_char(c: char) -> Tile {
+        match c {
+            '.' | 'S' | 'O' => Tile::Fairway,
+            ':' => Tile::Sand,
+            '~' => Tile::Water,
+            '^' => Tile::Slope(0, -1),
+            'v' => Tile::Slope(0, 1),
+            '<' => Tile::Slope(-1, 0),
+            '>' => Tile::Slope(1, 0),
+            _ => Tile::Wall,
+        }
+    }
+}
+
+/// Loads every hole in the assets `holes` directory.
+fn load_holes(ctx: &mut Context) -> GameResult<Vec<Level>> {
+    let paths = assets::read_dir(ctx, HOLES_DIR)?;
+
+    let mut holes = Vec::new();
+    for path in paths {
+        let mut contents = String::new();
+        filesystem::open(ctx, &path)?.read_to_string(&mut contents)?;
+
+        let hole = Level::parse(&contents)
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path.display(), e)))?;
+        if hole.find('S').is_none() || hole.find('O').is_none() {
+            return Err(GameError::ResourceLoadError(format!(
+                "{}: a hole needs a tee `S` and a cup `O`",
+                path.display()
+            )));
+        }
+        holes.push(hole);
+    }
+
+    if holes.is_empty() {
+        return Err(GameError::ResourceLoadError(format!(
+            "no holes found in {}",
+            HOLES_DIR
+        )));
+    }
+    Ok(holes)
+}
+
+/// The name for a score relative to par.
+fn score_name(strokes: u32, par: u32) -> &'static str {
+    if strokes == 1 {
+        return "Hole in one!";
+    }
+    match strokes as i32 - par as i32 {
+        d if d <= -3 => "Albatross!",
+        -2 => "Eagle!",
+        -1 => "Birdie!",
+        0 => "Par",
+        1 => "Bogey",
+        2 => "Double bogey",
+        _ => "Ouch",
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    holes: Vec<Level>,
+    current: usize,
+    tiles: Vec<Vec<Tile>>,
+    /// Where the course is drawn, so it is centred in the window.
+    origin: Vec2,
+    ball: Vec2,
+    velocity: Vec2,
+    cup: Vec2,
+    /// Where the last putt was taken from, which the ball returns to after going in the water.
+    last_putt: Vec2,
+    rest_timer: f32,
+    moving: bool,
+    /// Where the player started dragging to aim, if they are aiming.
+    drag_start: Option<Vec2>,
+    mouse: Vec2,
+    strokes: u32,
+    /// The strokes taken on every finished hole.
+    card: Vec<u32>,
+    /// Whether the ball is in the cup and the game is waiting to move on.
+    holed: bool,
+    message: Option<String>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state, loading the course from the assets.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let mut state = GameState {
+            holes: load_holes(ctx)?,
+            current: 0,
+            tiles: Vec::new(),
+            origin: Vec2::ZERO,
+            ball: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            cup: Vec2::ZERO,
+            last_putt: Vec2::ZERO,
+            rest_timer: 0.0,
+            moving: false,
+            drag_start: None,
+            mouse: Vec2::ZERO,
+            strokes: 0,
+            card: Vec::new(),
+            holed: false,
+            message: None,
+            stats: StatsDb::open().ok(),
+        };
+        state.start_hole(0);
+        Ok(state)
+    }
+
+    fn start_hole(&mut self, index: usize) {
+        let hole = &self.holes[index];
+        let width = hole.tiles.width() as f32 * TILE_SIZE;
+        let height = hole.tiles.height() as f32 * TILE_SIZE;
+        self.origin = Vec2::new(
+            ((SCREEN_SIZE.0 - width) / 2.0).max(0.0),
+            HUD_HEIGHT + ((SCREEN_SIZE.1 - HUD_HEIGHT - height) / 2.0).max(0.0),
+        );
+
+        self.tiles = (0..hole.tiles.height() as i32)
+            .map(|y| {
+                (0..hole.tiles.width() as i32)
+                    .map(|x| Tile::from_char(*hole.tiles.get(x, y).unwrap_or(&'#')))
+                    .collect()
+            })
+            .collect();
+
+        let tee = hole.find('S').unwrap_or((1, 1));
+        let cup = hole.find('O').unwrap_or((1, 1));
+        self.ball = self.tile_center(tee.0, tee.1);
+        self.cup = self.tile_center(cup.0, cup.1);
+
+        self.current = index;
+        self.velocity = Vec2::ZERO;
+        self.last_putt = self.ball;
+        self.moving = false;
+        self.drag_start = None;
+        self.strokes = 0;
+        self.holed = false;
+        self.message = None;
+    }
+
+    fn par(&self, index: usize) -> u32 {
+        self.holes[index]
+            .property("par")
+            .and_then(|par| par.parse().ok())
+            .unwrap_or(3)
+    }
+
+    fn tile_center(&self, x: i32, y: i32) -> Vec2 {
+        self.origin + Vec2::new((x as f32 + 0.5) * TILE_SIZE, (y as f32 + 0.5) * TILE_SIZE)
+    }
+
+    fn tile_at(&self, x: i32, y: i32) -> Tile {
+        if x < 0 || y < 0 {
+            return Tile::Wall;
+        }
+        self.tiles
+            .get(y as usize)
+            .and_then(|row| row.get(x as usize))
+            .cloned()
+            .unwrap_or(Tile::Wall)
+    }
+
+    fn tile_under(&self, point: Vec2) -> Tile {
+        let local = point - self.origin;
+        self.tile_at(
+            (local.x / TILE_SIZE).floor() as i32,
+            (local.y / TILE_SIZE).floor() as i32,
+        )
+    }
+
+    fn putt(&mut self, drag: Vec2) {
+        let power = (drag.length() / MAX_DRAG).min(1.0);
+        if power <= 0.05 {
+            return;
+        }
+        self.velocity = drag.normalize() * (power * MAX_PUTT_SPEED);
+        self.last_putt = self.ball;
+        self.moving = true;
+        self.rest_timer = 0.0;
+        self.strokes += 1;
+        self.message = None;
+    }
+
+    /// The aim from the current drag: the ball goes the opposite way to the drag.
+    fn aim(&self) -> Option<Vec2> {
+        self.drag_start.map(|start| start - self.mouse)
+    }
+
+    /// Pushes the ball out of any wall tiles it overlaps and bounces it off them.
+    fn collide_walls(&mut self) {
+        let local = self.ball - self.origin;
+        let tx = (local.x / TILE_SIZE).floor() as i32;
+        let ty = (local.y / TILE_SIZE).floor() as i32;
+
+        for y in ty - 1..=ty + 1 {
+            for x in tx - 1..=tx + 1 {
+                if self.tile_at(x, y) != Tile::Wall {
+                    continue;
+                }
+                let min = self.origin + Vec2::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+                let closest = Vec2::new(
+                    self.ball.x.max(min.x).min(min.x + TILE_SIZE),
+                    self.ball.y.max(min.y).min(min.y + TILE_SIZE),
+                );
+                let offset = self.ball - closest;
+                let distance = offset.length();
+                if distance >= BALL_RADIUS || distance == 0.0 {
+                    continue;
+                }
+
+                let normal = offset * (1.0 / distance);
+                self.ball += normal * (BALL_RADIUS - distance);
+                self.velocity =
+                    physics::bounce(self.velocity, normal, WALL_RESTITUTION, Vec2::ZERO);
+            }
+        }
+    }
+
+    fn step(&mut self, dt: f32) {
+        let tile = self.tile_under(self.ball);
+        if let Tile::Slope(dx, dy) = tile {
+            self.velocity += Vec2::new(dx as f32, dy as f32) * (SLOPE_ACCELERATION * dt);
+        }
+
+        let friction = if tile == Tile::Sand {
+            SAND_FRICTION
+        } else {
+            FAIRWAY_FRICTION
+        };
+        let speed = self.velocity.length();
+        let slowed = (speed - friction * dt).max(0.0);
+        self.velocity = if speed > 0.0 {
+            self.velocity * (slowed / speed)
+        } else {
+            Vec2::ZERO
+        };
+
+        self.ball += self.velocity * dt;
+        self.collide_walls();
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if !self.moving {
+            return;
+        }
+
+        let steps = ((self.velocity.length() * dt / MAX_STEP).ceil() as usize).max(1);
+        for _ in 0..steps {
+            self.step(dt / steps as f32);
+
+            if (self.ball - self.cup).length() < CUP_RADIUS
+                && self.velocity.length() < CUP_CAPTURE_SPEED
+            {
+                self.finish_hole();
+                return;
+            }
+
+            if self.tile_under(self.ball) == Tile::Water {
+                self.ball = self.last_putt;
+                self.velocity = Vec2::ZERO;
+                self.moving = false;
+                self.strokes += WATER_PENALTY;
+                self.message = Some("Splash! One stroke penalty.".to_string());
+                self.check_stroke_limit();
+                return;
+            }
+        }
+
+        if self.velocity.length() < STOP_SPEED {
+            self.rest_timer += dt;
+            if self.rest_timer >= REST_TIME {
+                self.velocity = Vec2::ZERO;
+                self.moving = false;
+                self.check_stroke_limit();
+            }
+        } else {
+            self.rest_timer = 0.0;
+        }
+    }
+
+    fn check_stroke_limit(&mut self) {
+        if !self.holed && self.strokes >= MAX_STROKES {
+            self.strokes = MAX_STROKES;
+            self.finish_hole();
+            self.message = Some("Picked up.".to_string());
+        }
+    }
+
+    fn finish_hole(&mut self) {
+        self.ball = self.cup;
+        self.velocity = Vec2::ZERO;
+        self.moving = false;
+        self.holed = true;
+        self.card.push(self.strokes);
+        self.message = Some(score_name(self.strokes, self.par(self.current)).to_string());
+        if self.course_over() {
+            self.record_round();
+        }
+    }
+
+    fn record_round(&self) {
+        let values = [
+            ("strokes", f64::from(self.card.iter().sum::<u32>())),
+            ("to_par", f64::from(self.to_par())),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("minigolf", &values);
+        }
+        let _ = achievements::check_run("minigolf", &values);
+    }
+
+    fn course_over(&self) -> bool {
+        self.holed && self.current + 1 == self.holes.len()
+    }
+
+    fn next(&mut self) {
+        if !self.holed {
+            return;
+        }
+        if self.course_over() {
+            self.card.clear();
+            self.start_hole(0);
+        } else {
+            self.start_hole(self.current + 1);
+        }
+    }
+
+    /// The total strokes so far relative to the par of the finished holes.
+    fn to_par(&self) -> i32 {
+        self.card
+            .iter()
+            .enumerate()
+            .map(|(hole, &strokes)| strokes as i32 - self.par(hole) as i32)
+            .sum()
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_course(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        for (y, row) in self.tiles.iter().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let color = match tile {
+                    Tile::Wall => {
+                        // Only draw the walls next to the course, leaving the outside empty.
+                        let (x, y) = (x as i32, y as i32);
+                        let edge = (-1..=1).any(|dy| {
+                            (-1..=1).any(|dx| self.tile_at(x + dx, y + dy) != Tile::Wall)
+                        });
+                        if !edge {
+                            continue;
+                        }
+                        [0.45, 0.3, 0.2, 1.0]
+                    }
+                    Tile::Fairway => [0.2, 0.65, 0.25, 1.0],
+                    Tile::Sand => [0.9, 0.8, 0.5, 1.0],
+                    Tile::Water => [0.2, 0.4, 0.9, 1.0],
+                    Tile::Slope(..) => [0.15, 0.55, 0.2, 1.0],
+                };
+                let min = self.origin + Vec2::new(x as f32 * TILE_SIZE, y as f32 * TILE_SIZE);
+                builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(min.x, min.y, TILE_SIZE, TILE_SIZE),
+                    color.into(),
+                );
+
+                // Mark slopes with a little arrow head pointing downhill.
+                if let Tile::Slope(dx, dy) = tile {
+                    let center = min + Vec2::new(TILE_SIZE / 2.0, TILE_SIZE / 2.0);
+                    let dir = Vec2::new(*dx as f32, *dy as f32);
+                    let tip = center + dir * 5.0;
+                    let back = center - dir * 3.0;
+                    let side = dir.perp() * 4.0;
+                    builder.polygon(
+                        DrawMode::fill(),
+                        &[
+                            ggez::mint::Point2 { x: tip.x, y: tip.y },
+                            ggez::mint::Point2 {
+                                x: back.x + side.x,
+                                y: back.y + side.y,
+                            },
+                            ggez::mint::Point2 {
+                                x: back.x - side.x,
+                                y: back.y - side.y,
+                            },
+                        ],
+                        [0.1, 0.4, 0.15, 1.0].into(),
+                    )?;
+                }
+            }
+        }
+
+        builder.circle(
+            DrawMode::fill(),
+            ggez::mint::Point2 {
+                x: self.cup.x,
+                y: self.cup.y,
+            },
+            CUP_RADIUS,
+            0.5,
+            [0.05, 0.05, 0.05, 1.0].into(),
+        );
+        if !self.holed {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: self.ball.x,
+                    y: self.ball.y,
+                },
+                BALL_RADIUS,
+                0.3,
+                [1.0, 1.0, 1.0, 1.0].into(),
+            );
+        }
+
+        // Show the aim line and power while dragging.
+        if let Some(aim) = self.aim() {
+            let power = (aim.length() / MAX_DRAG).min(1.0);
+            if power > 0.05 {
+                let end = self.ball + aim.normalize() * (power * MAX_DRAG);
+                builder.line(
+                    &[
+                        ggez::mint::Point2 {
+                            x: self.ball.x,
+                            y: self.ball.y,
+                        },
+                        ggez::mint::Point2 { x: end.x, y: end.y },
+                    ],
+                    2.0,
+                    [1.0, 1.0 - power, 0.2, 1.0].into(),
+                )?;
+                builder.rectangle(
+                    DrawMode::fill(),
+                    Rect::new(SCREEN_SIZE.0 - 220.0, 20.0, 200.0 * power, 16.0),
+                    [1.0, 1.0 - power, 0.2, 1.0].into(),
+                );
+                builder.rectangle(
+                    DrawMode::stroke(2.0),
+                    Rect::new(SCREEN_SIZE.0 - 220.0, 20.0, 200.0, 16.0),
+                    [1.0, 1.0, 1.0, 1.0].into(),
+                );
+            }
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_card(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut text = String::from("Hole  Par  Strokes\n");
+        for (hole, strokes) in self.card.iter().enumerate() {
+            text.push_str(&format!(
+                "{:>4}  {:>3}  {:>7}\n",
+                hole + 1,
+                self.par(hole),
+                strokes
+            ));
+        }
+        let total: u32 = self.card.iter().sum();
+        let par: u32 = (0..self.holes.len()).map(|hole| self.par(hole)).sum();
+        text.push_str(&format!(
+            "\nTotal {} (par {}), {:+} to par\n\nPress Enter to play again.",
+            total,
+            par,
+            self.to_par()
+        ));
+        self.draw_text(
+            ctx,
+            &text,
+            [1.0, 1.0, 1.0, 1.0],
+            24.0,
+            260.0,
+            HUD_HEIGHT + 20.0,
+        )
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        self.tick(dt);
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.15, 0.1, 1.0].into());
+
+        if self.course_over() {
+            self.draw_card(ctx)?;
+        } else {
+            self.draw_course(ctx)?;
+        }
+
+        let hole = &self.holes[self.current];
+        let hud = format!(
+            "Hole {}/{}: {}  Par {}  Strokes {}  Total {:+}",
+            self.current + 1,
+            self.holes.len(),
+            hole.name(),
+            self.par(self.current),
+            self.strokes,
+            self.to_par()
+        );
+        self.draw_text(ctx, &hud, [1.0, 1.0, 1.0, 1.0], 22.0, 16.0, 16.0)?;
+
+        if let Some(message) = &self.message {
+            let prompt = if self.holed && !self.course_over() {
+                format!("{} Press Enter for the next hole.", message)
+            } else {
+                message.clone()
+            };
+            self.draw_text(
+                ctx,
+                &prompt,
+                [1.0, 0.9, 0.3, 1.0],
+                24.0,
+                16.0,
+                SCREEN_SIZE.1 - 36.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button == MouseButton::Left && !self.moving && !self.holed {
+            self.drag_start = Some(Vec2::new(x, y));
+            self.mouse = Vec2::new(x, y);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        self.mouse = Vec2::new(x, y);
+        if let Some(aim) = self.aim() {
+            self.drag_start = None;
+            self.putt(aim);
+        }
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.mouse = Vec2::new(x, y);
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return => self.next(),
+            KeyCode::Escape if self.drag_start.is_some() => self.drag_start = None,
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("minigolf", "Sprial404")
+        .window_setup(window::setup("Minigolf"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "minigolf", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
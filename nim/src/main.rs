@@ -0,0 +1,498 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+const STONE_RADIUS: f32 = 15.0;
+const STONE_SPACING: f32 = 36.0;
+const HEAP_SPACING: f32 = 70.0;
+const HEAPS_TOP: f32 = 120.0;
+const HEAPS_LEFT: f32 = 40.0;
+
+/// How long the computer waits before making its move.
+const AI_DELAY: f32 = 0.8;
+
+/// The take-away games on offer. In every one of them the player who takes the last stone wins.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Variant {
+    /// Take any number of stones from one heap.
+    Nim,
+    /// Count up to 21 by taking one to three stones from a single pile.
+    TwentyOne,
+}
+
+impl Variant {
+    fn name(self) -> &'static str {
+        match self {
+            Variant::Nim => "Classic Nim",
+            Variant::TwentyOne => "21",
+        }
+    }
+
+    fn rules(self) -> &'static str {
+        match self {
+            Variant::Nim => "Take any number of stones from one heap. Take the last stone to win.",
+            Variant::TwentyOne => "Take one, two or three stones. Take the last stone to win.",
+        }
+    }
+
+    fn heaps(self) -> Vec<u32> {
+        match self {
+            Variant::Nim => vec![3, 4, 5],
+            Variant::TwentyOne => vec![21],
+        }
+    }
+
+    /// The most stones that can be taken in one move, if there is a limit.
+    fn max_take(self) -> Option<u32> {
+        match self {
+            Variant::Nim => None,
+            Variant::TwentyOne => Some(3),
+        }
+    }
+
+    /// The Grundy value of a heap, which is what it is worth as a Nim heap.
+    ///
+    /// Without a limit that is the heap size itself; when at most `k` stones can be taken it
+    /// repeats every `k + 1` stones.
+    fn grundy(self, heap: u32) -> u32 {
+        match self.max_take() {
+            Some(max) => heap % (max + 1),
+            None => heap,
+        }
+    }
+
+    fn is_legal(self, heaps: &[u32], mv: Move) -> bool {
+        mv.take > 0
+            && heaps.get(mv.heap).map_or(false, |&heap| mv.take <= heap)
+            && self.max_take().map_or(true, |max| mv.take <= max)
+    }
+
+    /// The XOR of the Grundy values of every heap. The player to move loses against perfect
+    /// play exactly when this is zero.
+    fn nim_sum(self, heaps: &[u32]) -> u32 {
+        heaps.iter().fold(0, |sum, &heap| sum ^ self.grundy(heap))
+    }
+
+    /// Finds a move that leaves a nim-sum of zero, if there is one.
+    fn winning_move(self, heaps: &[u32]) -> Option<Move> {
+        for (i, &heap) in heaps.iter().enumerate() {
+            for take in 1..=heap {
+                let mv = Move { heap: i, take };
+                if !self.is_legal(heaps, mv) {
+                    break;
+                }
+
+                let mut after = heaps.to_vec();
+                after[i] -= take;
+                if self.nim_sum(&after) == 0 {
+                    return Some(mv);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Move {
+    heap: usize,
+    take: u32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Player {
+    Human,
+    Computer,
+}
+
+impl Player {
+    fn other(self) -> Player {
+        match self {
+            Player::Human => Player::Computer,
+            Player::Computer => Player::Human,
+        }
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    variant: Variant,
+    heaps: Vec<u32>,
+    turn: Player,
+    /// The move the mouse is over, shown before it is made.
+    hovered: Option<Move>,
+    /// Whether to show the nim-sum working and why moves win or lose.
+    explain: bool,
+    /// What happened on the last move, in words.
+    last_move: Option<String>,
+    winner: Option<Player>,
+    ai_timer: f32,
+    /// Whether the game is still on the menu choosing a variant.
+    menu: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the menu.
+    pub fn new() -> Self {
+        GameState {
+            variant: Variant::Nim,
+            heaps: Variant::Nim.heaps(),
+            turn: Player::Human,
+            hovered: None,
+            explain: true,
+            last_move: None,
+            winner: None,
+            ai_timer: 0.0,
+            menu: true,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start(&mut self, variant: Variant, first: Player) {
+        self.variant = variant;
+        self.heaps = variant.heaps();
+        self.turn = first;
+        self.hovered = None;
+        self.last_move = None;
+        self.winner = None;
+        self.ai_timer = AI_DELAY;
+        self.menu = false;
+    }
+
+    fn stone_position(&self, heap: usize, stone: u32) -> (f32, f32) {
+        (
+            HEAPS_LEFT + stone as f32 * STONE_SPACING,
+            HEAPS_TOP + heap as f32 * HEAP_SPACING,
+        )
+    }
+
+    /// The move that takes the stone under the point and every stone to its right.
+    fn move_at(&self, x: f32, y: f32) -> Option<Move> {
+        for (i, &heap) in self.heaps.iter().enumerate() {
+            for stone in 0..heap {
+                let (sx, sy) = self.stone_position(i, stone);
+                if (x - sx).powi(2) + (y - sy).powi(2) <= STONE_RADIUS.powi(2) {
+                    return Some(Move {
+                        heap: i,
+                        take: heap - stone,
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn describe(&self, player: Player, mv: Move) -> String {
+        let who = match player {
+            Player::Human => "You",
+            Player::Computer => "The computer",
+        };
+        let stones = if mv.take == 1 { "stone" } else { "stones" };
+        if self.heaps.len() > 1 {
+            format!(
+                "{} took {} {} from heap {}.",
+                who,
+                mv.take,
+                stones,
+                mv.heap + 1
+            )
+        } else {
+            format!("{} took {} {}.", who, mv.take, stones)
+        }
+    }
+
+    fn play(&mut self, mv: Move) {
+        if !self.variant.is_legal(&self.heaps, mv) {
+            return;
+        }
+
+        let mut description = self.describe(self.turn, mv);
+        let winning = self.variant.winning_move(&self.heaps).is_some();
+        self.heaps[mv.heap] -= mv.take;
+        if self.explain {
+            let after = self.variant.nim_sum(&self.heaps);
+            let verdict = if after == 0 {
+                " That left a nim-sum of 0, a losing position for the other player."
+            } else if winning {
+                " That left a nonzero nim-sum, giving the other player a winning move."
+            } else {
+                " There was no winning move, as every move leaves a nonzero nim-sum."
+            };
+            description.push_str(verdict);
+        }
+        self.last_move = Some(description);
+        self.hovered = None;
+
+        if self.heaps.iter().all(|&heap| heap == 0) {
+            self.winner = Some(self.turn);
+            self.record_game();
+        } else {
+            self.turn = self.turn.other();
+            self.ai_timer = AI_DELAY;
+        }
+    }
+
+    fn record_game(&self) {
+        let won = self.winner == Some(Player::Human);
+        let values = [("won", if won { 1.0 } else { 0.0 })];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("nim", &values);
+        }
+        let _ = achievements::check_run("nim", &values);
+    }
+
+    fn computer_move(&self) -> Option<Move> {
+        // With no winning move, take a single stone from the biggest heap and hope for a mistake.
+        self.variant.winning_move(&self.heaps).or_else(|| {
+            self.heaps
+                .iter()
+                .enumerate()
+                .max_by_key(|&(_, &heap)| heap)
+                .filter(|&(_, &heap)| heap > 0)
+                .map(|(i, _)| Move { heap: i, take: 1 })
+        })
+    }
+
+    /// The lines of the explanation panel for the current position.
+    fn explanation(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let width = self
+            .heaps
+            .iter()
+            .map(|&heap| 32 - self.variant.grundy(heap).leading_zeros())
+            .max()
+            .unwrap_or(1)
+            .max(1) as usize;
+
+        for (i, &heap) in self.heaps.iter().enumerate() {
+            let grundy = self.variant.grundy(heap);
+            let value = match self.variant.max_take() {
+                Some(max) => format!("{} mod {} = {}", heap, max + 1, grundy),
+                None => format!("{}", heap),
+            };
+            lines.push(format!(
+                "Heap {}: {:>12}  =  {:0width$b}",
+                i + 1,
+                value,
+                grundy,
+                width = width
+            ));
+        }
+
+        let sum = self.variant.nim_sum(&self.heaps);
+        lines.push(format!("Nim-sum (XOR) = {:0width$b}", sum, width = width));
+
+        if self.winner.is_some() {
+            return lines;
+        }
+        let mover = match self.turn {
+            Player::Human => "You are",
+            Player::Computer => "The computer is",
+        };
+        match self.variant.winning_move(&self.heaps) {
+            Some(mv) => {
+                let from = if self.heaps.len() > 1 {
+                    format!(" from heap {}", mv.heap + 1)
+                } else {
+                    String::new()
+                };
+                lines.push(format!(
+                    "{} winning: taking {}{} makes the nim-sum 0.",
+                    mover, mv.take, from
+                ));
+            }
+            None => lines.push(format!(
+                "{} losing: the nim-sum is 0, so every move makes it nonzero.",
+                mover
+            )),
+        }
+        lines
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.menu && self.winner.is_none() && self.turn == Player::Computer {
+            self.ai_timer -= dt;
+            if self.ai_timer <= 0.0 {
+                if let Some(mv) = self.computer_move() {
+                    self.play(mv);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.12, 0.1, 1.0].into());
+
+        if self.menu {
+            self.draw_text(ctx, "NIM", [1.0, 1.0, 1.0, 1.0], 64.0, 340.0, 120.0)?;
+            self.draw_text(
+                ctx,
+                "1: Classic Nim\n2: 21\n\nHold Shift to let the computer go first.\nE toggles explain mode.",
+                [0.8, 0.8, 0.8, 1.0],
+                28.0,
+                180.0,
+                240.0,
+            )?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        self.draw_text(
+            ctx,
+            &format!("{}: {}", self.variant.name(), self.variant.rules()),
+            [1.0, 1.0, 1.0, 1.0],
+            22.0,
+            20.0,
+            20.0,
+        )?;
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (i, &heap) in self.heaps.iter().enumerate() {
+            for stone in 0..heap {
+                let (x, y) = self.stone_position(i, stone);
+                let taking = self.hovered.map_or(false, |mv| {
+                    mv.heap == i
+                        && stone >= heap - mv.take
+                        && self.variant.is_legal(&self.heaps, mv)
+                });
+                let color = if taking {
+                    [1.0, 0.4, 0.3, 1.0]
+                } else {
+                    [0.85, 0.8, 0.65, 1.0]
+                };
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 { x, y },
+                    STONE_RADIUS,
+                    0.5,
+                    color.into(),
+                );
+            }
+        }
+        if self.heaps.iter().any(|&heap| heap > 0) {
+            let mesh = builder.build(ctx)?;
+            graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let status = match self.winner {
+            Some(Player::Human) => "You took the last stone and win! Press Enter.".to_string(),
+            Some(Player::Computer) => "The computer took the last stone. Press Enter.".to_string(),
+            None if self.turn == Player::Human => {
+                "Your move: click a stone to take it and everything to its right.".to_string()
+            }
+            None => "The computer is thinking...".to_string(),
+        };
+        let status_y = HEAPS_TOP + self.heaps.len() as f32 * HEAP_SPACING;
+        self.draw_text(ctx, &status, [1.0, 0.85, 0.3, 1.0], 22.0, 20.0, status_y)?;
+        if let Some(last_move) = &self.last_move {
+            self.draw_text(
+                ctx,
+                last_move,
+                [0.7, 0.9, 0.7, 1.0],
+                18.0,
+                20.0,
+                status_y + 30.0,
+            )?;
+        }
+
+        if self.explain {
+            let lines = self.explanation().join("\n");
+            self.draw_text(
+                ctx,
+                &lines,
+                [0.7, 0.8, 1.0, 1.0],
+                20.0,
+                20.0,
+                status_y + 80.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.hovered = self.move_at(x, y);
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left
+            || self.menu
+            || self.winner.is_some()
+            || self.turn != Player::Human
+        {
+            return;
+        }
+        if let Some(mv) = self.move_at(x, y) {
+            self.play(mv);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        let first = if keymod.contains(KeyMods::SHIFT) {
+            Player::Computer
+        } else {
+            Player::Human
+        };
+        match keycode {
+            KeyCode::Key1 if self.menu => self.start(Variant::Nim, first),
+            KeyCode::Key2 if self.menu => self.start(Variant::TwentyOne, first),
+            KeyCode::E => self.explain = !self.explain,
+            KeyCode::Return if self.winner.is_some() => self.menu = true,
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("nim", "Sprial404")
+        .window_setup(window::setup("Nim"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,557 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::f32::consts::PI;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::physics::{self, Segment, Vec2};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 820.0);
+
+/// Physics steps per frame, keeping the fast ball from tunnelling through thin walls.
+const SUBSTEPS: u32 = 10;
+const GRAVITY: f32 = 600.0;
+const MAX_BALL_SPEED: f32 = 2000.0;
+const BALL_RADIUS: f32 = 9.0;
+const WALL_RESTITUTION: f32 = 0.5;
+
+const FLIPPER_LENGTH: f32 = 70.0;
+const FLIPPER_THICKNESS: f32 = 8.0;
+/// How far the flippers swing either side of horizontal, and how fast.
+const FLIPPER_ANGLE: f32 = PI / 6.0;
+const FLIPPER_SPEED: f32 = 20.0;
+const FLIPPER_RESTITUTION: f32 = 0.3;
+
+const BUMPER_RADIUS: f32 = 22.0;
+const BUMPER_KICK: f32 = 350.0;
+const SLINGSHOT_KICK: f32 = 300.0;
+
+/// Where the ball rests on the plunger in the launch lane.
+const LAUNCH_POS: Vec2 = Vec2::new(440.0, 780.0 - BALL_RADIUS);
+const PLUNGER_TOP: f32 = 780.0;
+const PLUNGER_CHARGE_TIME: f32 = 1.0;
+const MIN_LAUNCH_SPEED: f32 = 700.0;
+const MAX_LAUNCH_SPEED: f32 = 1300.0;
+
+/// Balls lost within this many seconds of launching are given back.
+const BALL_SAVE_TIME: f32 = 8.0;
+const BALLS_PER_GAME: u32 = 3;
+
+const BUMPER_POINTS: u32 = 100;
+const SLINGSHOT_POINTS: u32 = 10;
+const ROLLOVER_POINTS: u32 = 50;
+/// End of ball bonus awarded per bonus count, times the bonus multiplier.
+const BONUS_VALUE: u32 = 100;
+const MAX_MULTIPLIER: u32 = 5;
+
+const ROLLOVERS: [Vec2; 3] = [
+    Vec2::new(160.0, 70.0),
+    Vec2::new(220.0, 70.0),
+    Vec2::new(280.0, 70.0),
+];
+const ROLLOVER_RADIUS: f32 = 12.0;
+
+const BUMPERS: [Vec2; 3] = [
+    Vec2::new(160.0, 200.0),
+    Vec2::new(260.0, 170.0),
+    Vec2::new(320.0, 250.0),
+];
+
+/// The fixed walls of the table, as a list of connected points per wall.
+const WALLS: &[&[(f32, f32)]] = &[
+    // The outer wall and top arch.
+    &[
+        (140.0, 668.0),
+        (20.0, 560.0),
+        (20.0, 120.0),
+        (60.0, 60.0),
+        (120.0, 30.0),
+        (360.0, 30.0),
+        (420.0, 50.0),
+        (460.0, 120.0),
+        (460.0, 790.0),
+    ],
+    // The right inlane and the wall between the table and the launch lane.
+    &[(300.0, 668.0), (420.0, 560.0), (420.0, 200.0)],
+    &[(420.0, 560.0), (420.0, 790.0)],
+    // The plunger.
+    &[(420.0, PLUNGER_TOP), (460.0, PLUNGER_TOP)],
+    // The back of the slingshots; their front faces are the kickers.
+    &[(70.0, 500.0), (70.0, 580.0), (115.0, 610.0)],
+    &[(370.0, 500.0), (370.0, 580.0), (325.0, 610.0)],
+];
+
+const SLINGSHOTS: [((f32, f32), (f32, f32)); 2] = [
+    ((70.0, 500.0), (115.0, 610.0)),
+    ((370.0, 500.0), (325.0, 610.0)),
+];
+
+/// A flipper rotating about its pivot.
+#[derive(Debug, Copy, Clone)]
+struct Flipper {
+    pivot: Vec2,
+    angle: f32,
+    rest: f32,
+    raised: f32,
+    /// The angular velocity of the last update in radians per second.
+    angular_velocity: f32,
+}
+
+impl Flipper {
+    fn left() -> Self {
+        Flipper {
+            pivot: Vec2::new(140.0, 680.0),
+            angle: FLIPPER_ANGLE,
+            rest: FLIPPER_ANGLE,
+            raised: -FLIPPER_ANGLE,
+            angular_velocity: 0.0,
+        }
+    }
+
+    fn right() -> Self {
+        Flipper {
+            pivot: Vec2::new(300.0, 680.0),
+            angle: PI - FLIPPER_ANGLE,
+            rest: PI - FLIPPER_ANGLE,
+            raised: PI + FLIPPER_ANGLE,
+            angular_velocity: 0.0,
+        }
+    }
+
+    fn segment(&self) -> Segment {
+        Segment::new(
+            self.pivot,
+            self.pivot + Vec2::from_angle(self.angle) * FLIPPER_LENGTH,
+        )
+    }
+
+    fn update(&mut self, pressed: bool, dt: f32) {
+        let target = if pressed { self.raised } else { self.rest };
+        let step = (target - self.angle)
+            .max(-FLIPPER_SPEED * dt)
+            .min(FLIPPER_SPEED * dt);
+        self.angle += step;
+        self.angular_velocity = step / dt;
+    }
+
+    /// The velocity of the flipper's surface at `point`.
+    fn surface_velocity(&self, point: Vec2) -> Vec2 {
+        (point - self.pivot).perp() * self.angular_velocity
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Phase {
+    /// The ball is sitting on the plunger waiting to be launched.
+    Ready,
+    Playing,
+    GameOver,
+}
+
+/// The state for the game.
+struct GameState {
+    walls: Vec<Segment>,
+    ball: Vec2,
+    velocity: Vec2,
+    flippers: [Flipper; 2],
+    plunger_charge: f32,
+    phase: Phase,
+    score: u32,
+    balls_left: u32,
+    /// Seconds since the current ball was launched.
+    ball_time: f32,
+    bonus: u32,
+    multiplier: u32,
+    rollovers_lit: [bool; 3],
+    /// A message shown briefly after events like a ball save.
+    message: Option<(String, f32)>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with the ball on the plunger.
+    pub fn new() -> Self {
+        let walls = WALLS
+            .iter()
+            .flat_map(|points| {
+                points
+                    .windows(2)
+                    .map(|pair| Segment::new(pair[0].into(), pair[1].into()))
+            })
+            .collect();
+
+        GameState {
+            walls,
+            ball: LAUNCH_POS,
+            velocity: Vec2::ZERO,
+            flippers: [Flipper::left(), Flipper::right()],
+            plunger_charge: 0.0,
+            phase: Phase::Ready,
+            score: 0,
+            balls_left: BALLS_PER_GAME,
+            ball_time: 0.0,
+            bonus: 0,
+            multiplier: 1,
+            rollovers_lit: [false; 3],
+            message: None,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn show_message(&mut self, message: &str) {
+        self.message = Some((message.to_string(), 2.0));
+    }
+
+    fn serve(&mut self) {
+        self.ball = LAUNCH_POS;
+        self.velocity = Vec2::ZERO;
+        self.plunger_charge = 0.0;
+        self.phase = Phase::Ready;
+    }
+
+    fn launch(&mut self) {
+        let speed = MIN_LAUNCH_SPEED + (MAX_LAUNCH_SPEED - MIN_LAUNCH_SPEED) * self.plunger_charge;
+        self.velocity = Vec2::new(0.0, -speed);
+        self.plunger_charge = 0.0;
+        self.ball_time = 0.0;
+        self.phase = Phase::Playing;
+    }
+
+    fn drain(&mut self) {
+        if self.ball_time < BALL_SAVE_TIME {
+            self.show_message("BALL SAVED");
+            self.serve();
+            return;
+        }
+
+        let bonus = self.bonus * BONUS_VALUE * self.multiplier;
+        self.score += bonus;
+        self.show_message(&format!(
+            "BONUS {} x{}",
+            self.bonus * BONUS_VALUE,
+            self.multiplier
+        ));
+        self.bonus = 0;
+        self.multiplier = 1;
+
+        self.balls_left -= 1;
+        if self.balls_left == 0 {
+            self.phase = Phase::GameOver;
+            self.record_game();
+        } else {
+            self.serve();
+        }
+    }
+
+    fn record_game(&self) {
+        let values = [("score", f64::from(self.score))];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("pinball", &values);
+        }
+        let _ = achievements::check_run("pinball", &values);
+    }
+
+    /// Pushes the ball out of a contact and bounces it, returning whether it was moving into it.
+    fn collide(&mut self, contact: physics::Contact, restitution: f32, surface: Vec2) -> bool {
+        self.ball += contact.normal * contact.depth;
+        let moving_in = (self.velocity - surface).dot(contact.normal) < 0.0;
+        self.velocity = physics::bounce(self.velocity, contact.normal, restitution, surface);
+        moving_in
+    }
+
+    fn step(&mut self, dt: f32) {
+        self.velocity.y += GRAVITY * dt;
+        self.velocity = self.velocity.clamp_length(MAX_BALL_SPEED);
+        self.ball += self.velocity * dt;
+
+        for i in 0..self.walls.len() {
+            let wall = self.walls[i];
+            if let Some(contact) = physics::circle_segment(self.ball, BALL_RADIUS, &wall, 0.0) {
+                self.collide(contact, WALL_RESTITUTION, Vec2::ZERO);
+            }
+        }
+
+        for (a, b) in SLINGSHOTS.iter() {
+            let kicker = Segment::new((*a).into(), (*b).into());
+            if let Some(contact) = physics::circle_segment(self.ball, BALL_RADIUS, &kicker, 0.0) {
+                self.collide(contact, WALL_RESTITUTION, Vec2::ZERO);
+
+                // Only the front face kicks, not the back of the slingshot.
+                let center: Vec2 = (SCREEN_SIZE.0 / 2.0, 600.0).into();
+                if (center - contact.point).dot(contact.normal) > 0.0 {
+                    self.velocity += contact.normal * SLINGSHOT_KICK;
+                    self.score += SLINGSHOT_POINTS;
+                }
+            }
+        }
+
+        for bumper in BUMPERS.iter() {
+            if let Some(contact) =
+                physics::circle_circle(self.ball, BALL_RADIUS, *bumper, BUMPER_RADIUS)
+            {
+                self.collide(contact, WALL_RESTITUTION, Vec2::ZERO);
+                self.velocity += contact.normal * BUMPER_KICK;
+                self.score += BUMPER_POINTS;
+                self.bonus += 1;
+            }
+        }
+
+        for i in 0..self.flippers.len() {
+            let flipper = self.flippers[i];
+            if let Some(contact) = physics::circle_segment(
+                self.ball,
+                BALL_RADIUS,
+                &flipper.segment(),
+                FLIPPER_THICKNESS,
+            ) {
+                let surface = flipper.surface_velocity(contact.point);
+                self.collide(contact, FLIPPER_RESTITUTION, surface);
+            }
+        }
+
+        for (i, rollover) in ROLLOVERS.iter().enumerate() {
+            if !self.rollovers_lit[i] && (self.ball - *rollover).length() < ROLLOVER_RADIUS {
+                self.rollovers_lit[i] = true;
+                self.score += ROLLOVER_POINTS;
+            }
+        }
+        // Lighting every rollover raises the bonus multiplier.
+        if self.rollovers_lit.iter().all(|lit| *lit) {
+            self.rollovers_lit = [false; 3];
+            if self.multiplier < MAX_MULTIPLIER {
+                self.multiplier += 1;
+                self.show_message(&format!("BONUS x{}", self.multiplier));
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        let left = keyboard::is_key_pressed(ctx, KeyCode::Z)
+            || keyboard::is_key_pressed(ctx, KeyCode::LShift);
+        let right = keyboard::is_key_pressed(ctx, KeyCode::Slash)
+            || keyboard::is_key_pressed(ctx, KeyCode::RShift);
+        self.flippers[0].update(left, dt);
+        self.flippers[1].update(right, dt);
+
+        if let Some((_, time)) = &mut self.message {
+            *time -= dt;
+            if *time <= 0.0 {
+                self.message = None;
+            }
+        }
+
+        match self.phase {
+            Phase::Ready => {
+                if keyboard::is_key_pressed(ctx, KeyCode::Space) {
+                    self.plunger_charge = (self.plunger_charge + dt / PLUNGER_CHARGE_TIME).min(1.0);
+                }
+            }
+            Phase::Playing => {
+                self.ball_time += dt;
+
+                let step = dt / SUBSTEPS as f32;
+                for _ in 0..SUBSTEPS {
+                    self.step(step);
+                }
+
+                if self.ball.y > SCREEN_SIZE.1 + BALL_RADIUS {
+                    self.drain();
+                }
+            }
+            Phase::GameOver => (),
+        }
+    }
+
+    fn draw_table(&self, ctx: &mut Context) -> GameResult<()> {
+        for wall in self.walls.iter() {
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::mint::Point2 {
+                        x: wall.a.x,
+                        y: wall.a.y,
+                    },
+                    ggez::mint::Point2 {
+                        x: wall.b.x,
+                        y: wall.b.y,
+                    },
+                ],
+                3.0,
+                [0.6, 0.6, 0.8, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        for (a, b) in SLINGSHOTS.iter() {
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::mint::Point2 { x: a.0, y: a.1 },
+                    ggez::mint::Point2 { x: b.0, y: b.1 },
+                ],
+                5.0,
+                [1.0, 0.4, 0.2, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        for bumper in BUMPERS.iter() {
+            self.draw_circle(ctx, *bumper, BUMPER_RADIUS, [0.9, 0.2, 0.5, 1.0])?;
+        }
+
+        for (rollover, lit) in ROLLOVERS.iter().zip(self.rollovers_lit.iter()) {
+            let color = if *lit {
+                [1.0, 1.0, 0.2, 1.0]
+            } else {
+                [0.3, 0.3, 0.1, 1.0]
+            };
+            self.draw_circle(ctx, *rollover, ROLLOVER_RADIUS / 2.0, color)?;
+        }
+
+        for flipper in self.flippers.iter() {
+            let segment = flipper.segment();
+            let line = graphics::Mesh::new_line(
+                ctx,
+                &[
+                    ggez::mint::Point2 {
+                        x: segment.a.x,
+                        y: segment.a.y,
+                    },
+                    ggez::mint::Point2 {
+                        x: segment.b.x,
+                        y: segment.b.y,
+                    },
+                ],
+                FLIPPER_THICKNESS * 2.0,
+                [0.2, 0.8, 1.0, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        // The plunger compresses as it charges.
+        let plunger = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(
+                424.0,
+                PLUNGER_TOP + self.plunger_charge * 20.0,
+                32.0,
+                SCREEN_SIZE.1 - PLUNGER_TOP,
+            ),
+            [0.7, 0.7, 0.7, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &plunger, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        if self.phase != Phase::GameOver {
+            let mut ball = self.ball;
+            if self.phase == Phase::Ready {
+                ball.y += self.plunger_charge * 20.0;
+            }
+            self.draw_circle(ctx, ball, BALL_RADIUS, [0.9, 0.9, 0.9, 1.0])?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_circle(
+        &self,
+        ctx: &mut Context,
+        pos: Vec2,
+        radius: f32,
+        color: [f32; 4],
+    ) -> GameResult<()> {
+        let circle = graphics::Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            ggez::mint::Point2 { x: pos.x, y: pos.y },
+            radius,
+            0.5,
+            color.into(),
+        )?;
+        graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // Clamp long frames so the physics doesn't explode after a stall.
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+        self.tick(ctx, dt);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.12, 1.0].into());
+        self.draw_table(ctx)?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Ball: {}  Bonus: {} x{}",
+                locale::integer(self.score),
+                (BALLS_PER_GAME - self.balls_left + 1).min(BALLS_PER_GAME),
+                self.bonus * BONUS_VALUE,
+                self.multiplier
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(20.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 4.0 },))?;
+
+        let message = match (&self.message, self.phase) {
+            (_, Phase::GameOver) => Some("GAME OVER! Press Enter."),
+            (Some((message, _)), _) => Some(message.as_str()),
+            (None, Phase::Ready) => Some("Hold Space to pull the plunger"),
+            _ => None,
+        };
+        if let Some(message) = message {
+            let text = Text::new(
+                TextFragment::new(message)
+                    .color([1.0, 0.9, 0.2, 1.0].into())
+                    .scale(Scale::uniform(24.0)),
+            );
+            graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 60.0, y: 400.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.phase == Phase::GameOver => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
+        if keycode == KeyCode::Space && self.phase == Phase::Ready {
+            self.launch();
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("pinball", "Sprial404")
+        .window_setup(window::setup("Pinball"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,626 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const GRID_SIZE: (i32, i32) = (10, 7);
+const CELL_SIZE: f32 = 64.0;
+/// The width of the panel left of the board showing the upcoming pieces.
+const PANEL_WIDTH: f32 = 96.0;
+const HUD_HEIGHT: f32 = 40.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    PANEL_WIDTH + GRID_SIZE.0 as f32 * CELL_SIZE,
+    HUD_HEIGHT + GRID_SIZE.1 as f32 * CELL_SIZE,
+);
+
+const QUEUE_LEN: usize = 5;
+const PIPE_WIDTH: f32 = 18.0;
+const WATER_WIDTH: f32 = 10.0;
+
+/// Seconds before the water starts flowing on the first level, and for each pipe to fill.
+const START_DELAY: f32 = 20.0;
+const START_FILL_TIME: f32 = 2.0;
+/// Each level the water flows this much faster.
+const FILL_TIME_FACTOR: f32 = 0.85;
+const MIN_FILL_TIME: f32 = 0.35;
+
+const START_TARGET: u32 = 8;
+const POINTS_PER_PIPE: i32 = 100;
+const REPLACE_PENALTY: i32 = 50;
+
+/// Directions out of a cell, used as bits in a pipe's openings.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    fn bit(self) -> u8 {
+        match self {
+            Direction::Up => 1,
+            Direction::Right => 2,
+            Direction::Down => 4,
+            Direction::Left => 8,
+        }
+    }
+
+    fn inverse(self) -> Self {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Right => Direction::Left,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+        }
+    }
+
+    fn offset(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Right => (1, 0),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+        }
+    }
+}
+
+/// The pipe pieces that can be placed on the board.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Pipe {
+    Horizontal,
+    Vertical,
+    Cross,
+    UpRight,
+    RightDown,
+    DownLeft,
+    LeftUp,
+    /// The source the water starts from, flowing out in the given direction.
+    Start(Direction),
+}
+
+impl Pipe {
+    fn random() -> Self {
+        match rand::thread_rng().gen_range(0, 7) {
+            0 => Pipe::Horizontal,
+            1 => Pipe::Vertical,
+            2 => Pipe::Cross,
+            3 => Pipe::UpRight,
+            4 => Pipe::RightDown,
+            5 => Pipe::DownLeft,
+            _ => Pipe::LeftUp,
+        }
+    }
+
+    fn openings(self) -> u8 {
+        use Direction::*;
+        match self {
+            Pipe::Horizontal => Left.bit() | Right.bit(),
+            Pipe::Vertical => Up.bit() | Down.bit(),
+            Pipe::Cross => Up.bit() | Right.bit() | Down.bit() | Left.bit(),
+            Pipe::UpRight => Up.bit() | Right.bit(),
+            Pipe::RightDown => Right.bit() | Down.bit(),
+            Pipe::DownLeft => Down.bit() | Left.bit(),
+            Pipe::LeftUp => Left.bit() | Up.bit(),
+            Pipe::Start(dir) => dir.bit(),
+        }
+    }
+
+    /// Returns the direction water entering from `entry` leaves in, or `None` if the pipe has no
+    /// opening on that side.
+    fn exit(self, entry: Direction) -> Option<Direction> {
+        let openings = self.openings();
+        if openings & entry.bit() == 0 {
+            return None;
+        }
+        if self == Pipe::Cross {
+            return Some(entry.inverse());
+        }
+        Direction::ALL
+            .iter()
+            .copied()
+            .find(|dir| *dir != entry && openings & dir.bit() != 0)
+    }
+
+    /// Draws the pipe centered on `center`.
+    fn draw(self, ctx: &mut Context, center: (f32, f32), size: f32) -> GameResult<()> {
+        let half = size / 2.0;
+        let width = PIPE_WIDTH * size / CELL_SIZE;
+
+        for dir in Direction::ALL.iter() {
+            if self.openings() & dir.bit() == 0 {
+                continue;
+            }
+            let rect = arm_rect(center, *dir, half, width);
+            let mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                rect,
+                [0.6, 0.6, 0.65, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        if let Pipe::Start(_) = self {
+            let mesh = graphics::Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: center.0,
+                    y: center.1,
+                },
+                half * 0.5,
+                0.5,
+                [0.2, 0.5, 1.0, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The rect from `center` out to the edge of a cell in `dir`, `length` long and `width` wide.
+fn arm_rect(center: (f32, f32), dir: Direction, length: f32, width: f32) -> Rect {
+    let (cx, cy) = center;
+    match dir {
+        Direction::Up => Rect::new(cx - width / 2.0, cy - length, width, length + width / 2.0),
+        Direction::Down => Rect::new(
+            cx - width / 2.0,
+            cy - width / 2.0,
+            width,
+            length + width / 2.0,
+        ),
+        Direction::Left => Rect::new(cx - length, cy - width / 2.0, length + width / 2.0, width),
+        Direction::Right => Rect::new(
+            cx - width / 2.0,
+            cy - width / 2.0,
+            length + width / 2.0,
+            width,
+        ),
+    }
+}
+
+fn cell_center(x: i32, y: i32) -> (f32, f32) {
+    (
+        PANEL_WIDTH + (x as f32 + 0.5) * CELL_SIZE,
+        HUD_HEIGHT + (y as f32 + 0.5) * CELL_SIZE,
+    )
+}
+
+/// A cell of the board the water has flowed through, and the directions it flowed.
+#[derive(Debug, Copy, Clone)]
+struct Flowed {
+    pos: (i32, i32),
+    entry: Option<Direction>,
+    exit: Direction,
+}
+
+/// The water front currently filling a pipe.
+#[derive(Debug, Copy, Clone)]
+struct Water {
+    pos: (i32, i32),
+    entry: Option<Direction>,
+    exit: Direction,
+    /// How full the current pipe is, from `0.0` to `1.0`.
+    progress: f32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Phase {
+    Playing,
+    LevelComplete,
+    GameOver,
+}
+
+/// The state for the game.
+struct GameState {
+    board: Vec<Option<Pipe>>,
+    queue: VecDeque<Pipe>,
+    /// Seconds until the water starts to flow.
+    delay: f32,
+    water: Water,
+    flowed: Vec<Flowed>,
+    level: u32,
+    score: i32,
+    phase: Phase,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the first level.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            board: Vec::new(),
+            queue: VecDeque::new(),
+            delay: 0.0,
+            water: Water {
+                pos: (0, 0),
+                entry: None,
+                exit: Direction::Right,
+                progress: 0.0,
+            },
+            flowed: Vec::new(),
+            level: 1,
+            score: 0,
+            phase: Phase::Playing,
+            stats: StatsDb::open().ok(),
+        };
+        state.start_level();
+        state
+    }
+
+    fn start_level(&mut self) {
+        let mut rng = rand::thread_rng();
+
+        self.board = vec![None; (GRID_SIZE.0 * GRID_SIZE.1) as usize];
+        self.queue = (0..QUEUE_LEN).map(|_| Pipe::random()).collect();
+        self.flowed.clear();
+        self.delay = START_DELAY;
+        self.phase = Phase::Playing;
+
+        // Keep the start away from the edges so it always has somewhere to flow.
+        let pos = (
+            rng.gen_range(2, GRID_SIZE.0 - 2),
+            rng.gen_range(2, GRID_SIZE.1 - 2),
+        );
+        let dir = Direction::ALL[rng.gen_range(0, 4)];
+        self.set(pos, Pipe::Start(dir));
+        self.water = Water {
+            pos,
+            entry: None,
+            exit: dir,
+            progress: 0.0,
+        };
+    }
+
+    fn index(pos: (i32, i32)) -> Option<usize> {
+        if pos.0 >= 0 && pos.1 >= 0 && pos.0 < GRID_SIZE.0 && pos.1 < GRID_SIZE.1 {
+            Some((pos.1 * GRID_SIZE.0 + pos.0) as usize)
+        } else {
+            None
+        }
+    }
+
+    fn get(&self, pos: (i32, i32)) -> Option<Pipe> {
+        GameState::index(pos).and_then(|i| self.board[i])
+    }
+
+    fn set(&mut self, pos: (i32, i32), pipe: Pipe) {
+        if let Some(i) = GameState::index(pos) {
+            self.board[i] = Some(pipe);
+        }
+    }
+
+    /// The number of pipes the water must flow through to finish the level.
+    fn target(&self) -> u32 {
+        START_TARGET + (self.level - 1) * 2
+    }
+
+    fn fill_time(&self) -> f32 {
+        (START_FILL_TIME * FILL_TIME_FACTOR.powi(self.level as i32 - 1)).max(MIN_FILL_TIME)
+    }
+
+    /// The number of pipes the water has flowed through, not counting the start.
+    fn flow_length(&self) -> u32 {
+        self.flowed
+            .iter()
+            .filter(|flowed| flowed.entry.is_some())
+            .count() as u32
+    }
+
+    fn has_water(&self, pos: (i32, i32)) -> bool {
+        self.water.pos == pos || self.flowed.iter().any(|flowed| flowed.pos == pos)
+    }
+
+    fn place(&mut self, pos: (i32, i32)) {
+        let existing = self.get(pos);
+        if let Some(Pipe::Start(_)) = existing {
+            return;
+        }
+        // Pipes with water in them are fixed in place.
+        if existing.is_some() && self.has_water(pos) {
+            return;
+        }
+        if existing.is_some() {
+            self.score -= REPLACE_PENALTY;
+        }
+
+        if let Some(pipe) = self.queue.pop_front() {
+            self.set(pos, pipe);
+            self.queue.push_back(Pipe::random());
+        }
+    }
+
+    /// Moves the water into the next pipe once the current one is full.
+    fn advance_water(&mut self) {
+        let water = self.water;
+        self.flowed.push(Flowed {
+            pos: water.pos,
+            entry: water.entry,
+            exit: water.exit,
+        });
+
+        let (dx, dy) = water.exit.offset();
+        let next = (water.pos.0 + dx, water.pos.1 + dy);
+        let entry = water.exit.inverse();
+
+        match self.get(next).and_then(|pipe| pipe.exit(entry)) {
+            Some(exit) => {
+                self.score += POINTS_PER_PIPE;
+                self.water = Water {
+                    pos: next,
+                    entry: Some(entry),
+                    exit,
+                    progress: 0.0,
+                };
+            }
+            None => {
+                // The water spilled out, so the level ends here.
+                self.phase = if self.flow_length() >= self.target() {
+                    Phase::LevelComplete
+                } else {
+                    Phase::GameOver
+                };
+                if self.phase == Phase::GameOver {
+                    self.record_run();
+                }
+            }
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("level", f64::from(self.level)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("pipes", &values);
+        }
+        let _ = achievements::check_run("pipes", &values);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if self.delay > 0.0 {
+            self.delay -= dt;
+            return;
+        }
+
+        self.water.progress += dt / self.fill_time();
+        if self.water.progress >= 1.0 {
+            self.advance_water();
+        }
+    }
+
+    /// Skips the remaining delay, for players who have finished laying their pipes.
+    fn flow_now(&mut self) {
+        self.delay = 0.0;
+    }
+
+    fn draw_water_arm(
+        &self,
+        ctx: &mut Context,
+        pos: (i32, i32),
+        dir: Direction,
+        fill: f32,
+        outwards: bool,
+    ) -> GameResult<()> {
+        let half = CELL_SIZE / 2.0;
+        let center = cell_center(pos.0, pos.1);
+        let mut rect = arm_rect(center, dir, half, WATER_WIDTH);
+
+        // Partially filled arms grow from the edge inwards, or from the center outwards.
+        let fill = fill.max(0.0).min(1.0);
+        let len = half * fill;
+        match (dir, outwards) {
+            (Direction::Up, true) | (Direction::Down, false) => {
+                rect.y = rect.bottom() - len - WATER_WIDTH / 2.0;
+                rect.h = len + WATER_WIDTH / 2.0;
+            }
+            (Direction::Down, true) | (Direction::Up, false) => rect.h = len + WATER_WIDTH / 2.0,
+            (Direction::Left, true) | (Direction::Right, false) => {
+                rect.x = rect.right() - len - WATER_WIDTH / 2.0;
+                rect.w = len + WATER_WIDTH / 2.0;
+            }
+            (Direction::Right, true) | (Direction::Left, false) => rect.w = len + WATER_WIDTH / 2.0,
+        }
+
+        let mesh = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            rect,
+            [0.2, 0.5, 1.0, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_board(&self, ctx: &mut Context) -> GameResult<()> {
+        for y in 0..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                let rect = Rect::new(
+                    PANEL_WIDTH + x as f32 * CELL_SIZE + 1.0,
+                    HUD_HEIGHT + y as f32 * CELL_SIZE + 1.0,
+                    CELL_SIZE - 2.0,
+                    CELL_SIZE - 2.0,
+                );
+                let mesh = graphics::Mesh::new_rectangle(
+                    ctx,
+                    DrawMode::fill(),
+                    rect,
+                    [0.15, 0.2, 0.15, 1.0].into(),
+                )?;
+                graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+                if let Some(pipe) = self.get((x, y)) {
+                    pipe.draw(ctx, cell_center(x, y), CELL_SIZE)?;
+                }
+            }
+        }
+
+        for flowed in self.flowed.iter() {
+            if let Some(entry) = flowed.entry {
+                self.draw_water_arm(ctx, flowed.pos, entry, 1.0, false)?;
+            }
+            self.draw_water_arm(ctx, flowed.pos, flowed.exit, 1.0, true)?;
+        }
+
+        // The water fills the entry half of the current pipe first, then the exit half.
+        if self.phase == Phase::Playing && self.delay <= 0.0 {
+            let water = self.water;
+            if let Some(entry) = water.entry {
+                self.draw_water_arm(ctx, water.pos, entry, water.progress * 2.0, false)?;
+                self.draw_water_arm(ctx, water.pos, water.exit, water.progress * 2.0 - 1.0, true)?;
+            } else {
+                self.draw_water_arm(ctx, water.pos, water.exit, water.progress, true)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw_queue(&self, ctx: &mut Context) -> GameResult<()> {
+        let size = PANEL_WIDTH * 0.7;
+        for (i, pipe) in self.queue.iter().enumerate() {
+            let center = (
+                PANEL_WIDTH / 2.0,
+                HUD_HEIGHT + size / 2.0 + i as f32 * (size + 8.0),
+            );
+            let color = if i == 0 {
+                [0.4, 0.4, 0.1, 1.0]
+            } else {
+                [0.15, 0.15, 0.15, 1.0]
+            };
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(center.0 - size / 2.0, center.1 - size / 2.0, size, size),
+                color.into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+            pipe.draw(ctx, center, size)?;
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if self.phase == Phase::Playing {
+            self.tick(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        self.draw_queue(ctx)?;
+        self.draw_board(ctx)?;
+
+        let status = if self.delay > 0.0 {
+            format!("Water in {:.0}s (Space to flow now)", self.delay.ceil())
+        } else {
+            "Flowing!".to_string()
+        };
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Level {}  Score: {}  Flow: {}/{}  {}",
+                self.level,
+                locale::integer(self.score),
+                self.flow_length(),
+                self.target(),
+                status
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(22.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        let message = match self.phase {
+            Phase::Playing => None,
+            Phase::LevelComplete => Some(("LEVEL COMPLETE! Press Enter.", [0.2, 1.0, 0.3, 1.0])),
+            Phase::GameOver => Some(("GAME OVER! Press Enter.", [1.0, 0.0, 0.0, 1.0])),
+        };
+        if let Some((message, color)) = message {
+            let text = Text::new(
+                TextFragment::new(message)
+                    .color(color.into())
+                    .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(
+                ctx,
+                &text,
+                (ggez::mint::Point2 {
+                    x: PANEL_WIDTH + 16.0,
+                    y: SCREEN_SIZE.1 / 2.0 - 20.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left || self.phase != Phase::Playing {
+            return;
+        }
+
+        let cell_x = ((x - PANEL_WIDTH) / CELL_SIZE).floor() as i32;
+        let cell_y = ((y - HUD_HEIGHT) / CELL_SIZE).floor() as i32;
+        if x >= PANEL_WIDTH && y >= HUD_HEIGHT && GameState::index((cell_x, cell_y)).is_some() {
+            self.place((cell_x, cell_y));
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match (keycode, self.phase) {
+            (KeyCode::Space, Phase::Playing) => self.flow_now(),
+            (KeyCode::Return, Phase::LevelComplete) => {
+                self.level += 1;
+                self.start_level();
+            }
+            (KeyCode::Return, Phase::GameOver) => *self = GameState::new(),
+            (KeyCode::Escape, _) => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("pipes", "Sprial404")
+        .window_setup(window::setup("Pipe Mania"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
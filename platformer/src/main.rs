@@ -0,0 +1,508 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, filesystem, graphics, timer, Context, GameError, GameResult};
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets::{self, Watcher};
+use common::cheats::Cheats;
+use common::level::Level;
+use common::stats::StatsDb;
+use common::tiled::TileMap;
+use common::ui;
+use common::window;
+
+const TILE_SIZE: f32 = 32.0;
+const LEVEL_SIZE: (usize, usize) = (20, 15);
+
+const SCREEN_SIZE: (f32, f32) = (
+    LEVEL_SIZE.0 as f32 * TILE_SIZE,
+    LEVEL_SIZE.1 as f32 * TILE_SIZE,
+);
+
+const LEVELS_DIR: &str = "/levels";
+
+/// The tile characters used in the level files.
+const SOLID: char = '#';
+const HAZARD: char = '^';
+const COIN: char = '*';
+const EXIT: char = 'E';
+const SPAWN: char = '@';
+
+/// The tile characters for the tile properties, or types, in levels made in Tiled.
+const TILED_TILES: &[(&str, char)] = &[("solid", SOLID), ("hazard", HAZARD), ("coin", COIN)];
+/// The tile characters for the object types in levels made in Tiled.
+const TILED_OBJECTS: &[(&str, char)] = &[("spawn", SPAWN), ("exit", EXIT), ("coin", COIN)];
+
+const PLAYER_SIZE: (f32, f32) = (22.0, 28.0);
+const RUN_SPEED: f32 = 220.0;
+const GRAVITY: f32 = 1800.0;
+const MAX_FALL_SPEED: f32 = 900.0;
+const JUMP_SPEED: f32 = 700.0;
+/// Releasing jump while still rising cuts the upwards speed by this factor, giving the player
+/// control over the jump height.
+const JUMP_CUT: f32 = 0.45;
+/// How long after running off a ledge the player can still jump.
+const COYOTE_TIME: f32 = 0.1;
+/// How long a jump press is remembered before landing.
+const JUMP_BUFFER: f32 = 0.1;
+
+/// The player character.
+#[derive(Debug, Copy, Clone)]
+struct Player {
+    pos: (f32, f32),
+    vel: (f32, f32),
+    on_ground: bool,
+    /// Seconds left in which a jump is still allowed after leaving the ground.
+    coyote: f32,
+    /// Seconds left for a buffered jump press.
+    jump_buffer: f32,
+}
+
+impl Player {
+    /// Creates a new player standing in the tile at `(x, y)`.
+    pub fn new(x: i32, y: i32) -> Self {
+        Player {
+            pos: (
+                x as f32 * TILE_SIZE + (TILE_SIZE - PLAYER_SIZE.0) / 2.0,
+                (y + 1) as f32 * TILE_SIZE - PLAYER_SIZE.1,
+            ),
+            vel: (0.0, 0.0),
+            on_ground: false,
+            coyote: 0.0,
+            jump_buffer: 0.0,
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::new(self.pos.0, self.pos.1, PLAYER_SIZE.0, PLAYER_SIZE.1)
+    }
+
+    fn update(&mut self, ctx: &Context, level: &Level, dt: f32) {
+        let mut dir = 0.0;
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) || keyboard::is_key_pressed(ctx, KeyCode::A)
+        {
+            dir -= 1.0;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right)
+            || keyboard::is_key_pressed(ctx, KeyCode::D)
+        {
+            dir += 1.0;
+        }
+        self.vel.0 = dir * RUN_SPEED;
+
+        self.coyote = if self.on_ground {
+            COYOTE_TIME
+        } else {
+            (self.coyote - dt).max(0.0)
+        };
+        self.jump_buffer = (self.jump_buffer - dt).max(0.0);
+
+        if self.jump_buffer > 0.0 && self.coyote > 0.0 {
+            self.vel.1 = -JUMP_SPEED;
+            self.jump_buffer = 0.0;
+            self.coyote = 0.0;
+        }
+
+        self.vel.1 = (self.vel.1 + GRAVITY * dt).min(MAX_FALL_SPEED);
+
+        // Move along each axis separately so collisions can be resolved one axis at a time.
+        self.pos.0 += self.vel.0 * dt;
+        if let Some(tile) = solid_overlap(level, self.rect()) {
+            if self.vel.0 > 0.0 {
+                self.pos.0 = tile.left() - PLAYER_SIZE.0;
+            } else if self.vel.0 < 0.0 {
+                self.pos.0 = tile.right();
+            }
+            self.vel.0 = 0.0;
+        }
+
+        self.on_ground = false;
+        self.pos.1 += self.vel.1 * dt;
+        if let Some(tile) = solid_overlap(level, self.rect()) {
+            if self.vel.1 > 0.0 {
+                self.pos.1 = tile.top() - PLAYER_SIZE.1;
+                self.on_ground = true;
+            } else if self.vel.1 < 0.0 {
+                self.pos.1 = tile.bottom();
+            }
+            self.vel.1 = 0.0;
+        }
+    }
+
+    fn jump_pressed(&mut self) {
+        self.jump_buffer = JUMP_BUFFER;
+    }
+
+    fn jump_released(&mut self) {
+        if self.vel.1 < 0.0 {
+            self.vel.1 *= JUMP_CUT;
+        }
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            self.rect(),
+            [0.3, 0.7, 1.0, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+/// The rect covered by the tile at `(x, y)`.
+fn tile_rect(x: i32, y: i32) -> Rect {
+    Rect::new(
+        x as f32 * TILE_SIZE,
+        y as f32 * TILE_SIZE,
+        TILE_SIZE,
+        TILE_SIZE,
+    )
+}
+
+/// Returns the tiles overlapped by `rect`.
+fn overlapped_tiles(rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+    // Shrink the rect slightly so touching a tile edge doesn't count as overlapping it.
+    let left = ((rect.left() + 0.01) / TILE_SIZE).floor() as i32;
+    let right = ((rect.right() - 0.01) / TILE_SIZE).floor() as i32;
+    let top = ((rect.top() + 0.01) / TILE_SIZE).floor() as i32;
+    let bottom = ((rect.bottom() - 0.01) / TILE_SIZE).floor() as i32;
+
+    (top..=bottom).flat_map(move |y| (left..=right).map(move |x| (x, y)))
+}
+
+/// Returns the first solid tile overlapped by `rect`. Anything outside the level is solid.
+fn solid_overlap(level: &Level, rect: Rect) -> Option<Rect> {
+    overlapped_tiles(rect)
+        .find(|(x, y)| level.tiles.get(*x, *y).map_or(true, |tile| *tile == SOLID))
+        .map(|(x, y)| tile_rect(x, y))
+}
+
+fn touches(level: &Level, rect: Rect, tile: char) -> bool {
+    overlapped_tiles(rect).any(|(x, y)| level.tiles.get(x, y) == Some(&tile))
+}
+
+fn read_asset(ctx: &mut Context, path: &Path) -> GameResult<String> {
+    let mut contents = String::new();
+    filesystem::open(ctx, path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Loads every level in the assets `levels` directory, in file name order. Levels are in the
+/// shared level format, or are Tiled maps ending in `.tmx`, with their tilesets beside them.
+fn load_levels(ctx: &mut Context) -> GameResult<Vec<Level>> {
+    let paths: Vec<PathBuf> = assets::read_dir(ctx, LEVELS_DIR)?
+        .into_iter()
+        .filter(|path| path.extension().map_or(true, |ext| ext != "tsx"))
+        .collect();
+
+    let mut levels = Vec::new();
+    for path in paths {
+        let contents = read_asset(ctx, &path)?;
+        let level = if path.extension().map_or(false, |ext| ext == "tmx") {
+            let dir = path.parent().unwrap_or_else(|| Path::new(LEVELS_DIR));
+            TileMap::parse(&contents, |source| {
+                read_asset(ctx, &dir.join(source)).map_err(|e| format!("{}: {}", source, e))
+            })
+            .map(|map| map.to_level(TILED_TILES, TILED_OBJECTS))
+        } else {
+            Level::parse(&contents)
+        };
+        let level = level
+            .map_err(|e| GameError::ResourceLoadError(format!("{}: {}", path.display(), e)))?;
+        levels.push(level);
+    }
+
+    if levels.is_empty() {
+        return Err(GameError::ResourceLoadError(format!(
+            "no levels found in {}",
+            LEVELS_DIR
+        )));
+    }
+    Ok(levels)
+}
+
+/// The state for the game.
+struct GameState {
+    levels: Vec<Level>,
+    current: usize,
+    /// The current level with any collected coins removed.
+    level: Level,
+    player: Player,
+    coins_total: u32,
+    deaths: u32,
+    /// Seconds spent across all levels.
+    elapsed: f32,
+    won: bool,
+    /// Notices level files being edited, in debug builds.
+    watcher: Watcher,
+    cheats: Cheats,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state starting at the first level, or the one from `--level`.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        let levels = load_levels(ctx)?;
+        let level = levels[0].clone();
+        let cheats = Cheats::requested();
+        let first = cheats.level_index(levels.len());
+
+        let mut state = GameState {
+            levels,
+            current: 0,
+            player: Player::new(0, 0),
+            level,
+            coins_total: 0,
+            deaths: 0,
+            elapsed: 0.0,
+            won: false,
+            watcher: Watcher::new(assets::dir()),
+            cheats,
+            stats: StatsDb::open().ok(),
+        };
+        state.load_level(first);
+        Ok(state)
+    }
+
+    fn load_level(&mut self, index: usize) {
+        self.current = index;
+        self.level = self.levels[index].clone();
+
+        let (x, y) = self.level.find(SPAWN).unwrap_or((1, 1));
+        self.level.tiles.set(x, y, ' ');
+        self.player = Player::new(x, y);
+    }
+
+    /// Loads the levels again after they've been edited, restarting the current one. The levels
+    /// being played are kept if the new ones don't load, like when a file is half saved.
+    fn reload_levels(&mut self, ctx: &mut Context) {
+        match load_levels(ctx) {
+            Ok(levels) => {
+                self.levels = levels;
+                self.load_level(self.current.min(self.levels.len() - 1));
+            }
+            Err(e) => eprintln!("Couldn't reload the levels: {}", e),
+        }
+    }
+
+    fn coins_left(&self) -> usize {
+        self.level
+            .tiles
+            .iter()
+            .filter(|(_, _, tile)| **tile == COIN)
+            .count()
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.elapsed += dt;
+        self.player.update(ctx, &self.level, dt);
+
+        let rect = self.player.rect();
+
+        // Fell onto a hazard: restart the level.
+        if touches(&self.level, rect, HAZARD) && !self.cheats.invincible {
+            self.deaths += 1;
+            self.load_level(self.current);
+            return;
+        }
+
+        let coins: Vec<(i32, i32)> = overlapped_tiles(rect)
+            .filter(|(x, y)| self.level.tiles.get(*x, *y) == Some(&COIN))
+            .collect();
+        for (x, y) in coins {
+            self.level.tiles.set(x, y, ' ');
+            self.coins_total += 1;
+        }
+
+        // The exit only opens once every coin has been collected.
+        if self.coins_left() == 0 && touches(&self.level, rect, EXIT) {
+            if self.current + 1 < self.levels.len() {
+                self.load_level(self.current + 1);
+            } else {
+                self.won = true;
+                self.record_run();
+            }
+        }
+    }
+
+    /// Records a finished run, unless cheats made it easier.
+    fn record_run(&self) {
+        if self.cheats != Cheats::default() {
+            return;
+        }
+        let values = [
+            ("time", f64::from(self.elapsed)),
+            ("deaths", f64::from(self.deaths)),
+            ("coins", f64::from(self.coins_total)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("platformer", &values);
+        }
+        let _ = achievements::check_run("platformer", &values);
+    }
+
+    fn draw_level(&self, ctx: &mut Context) -> GameResult<()> {
+        let exit_open = self.coins_left() == 0;
+
+        for (x, y, tile) in self.level.tiles.iter() {
+            let rect = tile_rect(x, y);
+            let mesh = match *tile {
+                SOLID => Some(graphics::Mesh::new_rectangle(
+                    ctx,
+                    DrawMode::fill(),
+                    rect,
+                    [0.45, 0.35, 0.25, 1.0].into(),
+                )?),
+                HAZARD => Some(graphics::Mesh::new_polygon(
+                    ctx,
+                    DrawMode::fill(),
+                    &[
+                        ggez::mint::Point2 {
+                            x: rect.left(),
+                            y: rect.bottom(),
+                        },
+                        ggez::mint::Point2 {
+                            x: rect.left() + TILE_SIZE / 2.0,
+                            y: rect.top() + TILE_SIZE / 3.0,
+                        },
+                        ggez::mint::Point2 {
+                            x: rect.right(),
+                            y: rect.bottom(),
+                        },
+                    ],
+                    [0.8, 0.8, 0.8, 1.0].into(),
+                )?),
+                COIN => Some(graphics::Mesh::new_circle(
+                    ctx,
+                    DrawMode::fill(),
+                    ggez::mint::Point2 {
+                        x: rect.left() + TILE_SIZE / 2.0,
+                        y: rect.top() + TILE_SIZE / 2.0,
+                    },
+                    TILE_SIZE / 4.0,
+                    0.5,
+                    [1.0, 0.85, 0.0, 1.0].into(),
+                )?),
+                EXIT => {
+                    let color = if exit_open {
+                        [0.2, 1.0, 0.3, 1.0]
+                    } else {
+                        [0.3, 0.3, 0.3, 1.0]
+                    };
+                    Some(graphics::Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        Rect::new(rect.x + 4.0, rect.y, TILE_SIZE - 8.0, TILE_SIZE),
+                        color.into(),
+                    )?)
+                }
+                _ => None,
+            };
+
+            if let Some(mesh) = mesh {
+                graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        // Clamp the frame time so a long frame can't tunnel the player through a tile.
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        let changed = self.watcher.changed();
+        if changed.iter().any(|path| path.starts_with(LEVELS_DIR)) {
+            self.reload_levels(ctx);
+        }
+
+        if !self.won {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.15, 1.0].into());
+        self.draw_level(ctx)?;
+        self.player.draw(ctx)?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "{} ({}/{})  Coins: {}  Deaths: {}  Time: {:.1}s",
+                self.level.name(),
+                self.current + 1,
+                self.levels.len(),
+                self.coins_total,
+                self.deaths,
+                self.elapsed
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(18.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 6.0 },))?;
+
+        if self.won {
+            let won = Text::new(
+                TextFragment::new("YOU WIN! Press Enter to play again.")
+                    .color([0.2, 1.0, 0.3, 1.0].into())
+                    .scale(Scale::uniform(32.0)),
+            );
+            graphics::draw(ctx, &won, (ggez::mint::Point2 { x: 40.0, y: 200.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space | KeyCode::Up | KeyCode::W if !repeat => self.player.jump_pressed(),
+            KeyCode::R => self.load_level(self.current),
+            KeyCode::Return if self.won => {
+                self.won = false;
+                self.coins_total = 0;
+                self.deaths = 0;
+                self.elapsed = 0.0;
+                self.load_level(0);
+            }
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
+        match keycode {
+            KeyCode::Space | KeyCode::Up | KeyCode::W => self.player.jump_released(),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("platformer", "Sprial404")
+        .window_setup(window::setup("Platformer"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "platformer", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
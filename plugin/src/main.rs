@@ -0,0 +1,256 @@
+//! Runs a game built as a WebAssembly module, given with `--plugin <path>`, through the host
+//! interface described in `common::plugins`.
+
+use ggez::event::{Button, KeyCode, KeyMods};
+use ggez::graphics::{self, DrawMode, MeshBuilder, Rect};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, timer, Context, GameError, GameResult};
+
+use std::cell::RefCell;
+use std::fmt;
+use std::process::ExitCode;
+use std::rc::Rc;
+
+use common::app::App;
+use common::args;
+use common::input::Nav;
+use common::plugins::{Plugin, SCREEN_SIZE};
+use common::ui;
+use common::window;
+use wasmtime::{Caller, Linker, Module, Store, Trap};
+
+/// Something the plugin asked for while drawing, kept until the frame is drawn.
+enum Draw {
+    Clear([f32; 4]),
+    Rect(Rect, [f32; 4]),
+    Circle(f32, f32, f32, [f32; 4]),
+    Text(String, f32, f32, f32, [f32; 4]),
+}
+
+type Commands = Rc<RefCell<Vec<Draw>>>;
+
+/// Unpacks a `0xRRGGBBAA` colour.
+fn color(packed: i32) -> [f32; 4] {
+    let packed = packed as u32;
+    [
+        (packed >> 24) as f32 / 255.0,
+        ((packed >> 16) & 0xff) as f32 / 255.0,
+        ((packed >> 8) & 0xff) as f32 / 255.0,
+        (packed & 0xff) as f32 / 255.0,
+    ]
+}
+
+/// The number `input` is given for a button, its place in `plugins::BUTTONS`.
+fn button(nav: Nav) -> Option<i32> {
+    match nav {
+        Nav::Up => Some(0),
+        Nav::Down => Some(1),
+        Nav::Left => Some(2),
+        Nav::Right => Some(3),
+        Nav::Confirm => Some(4),
+        Nav::Back => None,
+    }
+}
+
+fn load_error(e: impl fmt::Display) -> GameError {
+    GameError::ResourceLoadError(format!("Couldn't load the plugin: {}", e))
+}
+
+fn trap(e: Trap) -> GameError {
+    GameError::CustomError(format!("The plugin stopped: {}", e))
+}
+
+/// Reads a string out of the plugin's memory.
+fn read_string(caller: &Caller<'_>, ptr: i32, len: i32) -> Result<String, Trap> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| Trap::new("the plugin doesn't export its memory"))?;
+    let start = ptr as u32 as usize;
+    let end = start + len as u32 as usize;
+    // The slice is copied out before the plugin can run again and change its memory.
+    let bytes = unsafe { memory.data_unchecked() }
+        .get(start..end)
+        .ok_or_else(|| Trap::new("text outside the plugin's memory"))?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Gives the plugin the host's drawing functions, which queue up commands for the frame.
+fn link(store: &Store, commands: &Commands) -> anyhow::Result<Linker> {
+    let mut linker = Linker::new(store);
+
+    let queue = Rc::clone(commands);
+    linker.func("env", "clear", move |packed: i32| {
+        queue.borrow_mut().push(Draw::Clear(color(packed)));
+    })?;
+    let queue = Rc::clone(commands);
+    linker.func(
+        "env",
+        "rect",
+        move |x: f32, y: f32, w: f32, h: f32, packed: i32| {
+            queue
+                .borrow_mut()
+                .push(Draw::Rect(Rect::new(x, y, w, h), color(packed)));
+        },
+    )?;
+    let queue = Rc::clone(commands);
+    linker.func(
+        "env",
+        "circle",
+        move |x: f32, y: f32, radius: f32, packed: i32| {
+            queue
+                .borrow_mut()
+                .push(Draw::Circle(x, y, radius, color(packed)));
+        },
+    )?;
+    let queue = Rc::clone(commands);
+    linker.func(
+        "env",
+        "text",
+        move |caller: Caller<'_>,
+              ptr: i32,
+              len: i32,
+              x: f32,
+              y: f32,
+              size: f32,
+              packed: i32|
+              -> Result<(), Trap> {
+            let text = read_string(&caller, ptr, len)?;
+            queue
+                .borrow_mut()
+                .push(Draw::Text(text, x, y, size, color(packed)));
+            Ok(())
+        },
+    )?;
+    Ok(linker)
+}
+
+/// The state for the game: the plugin's exported functions and what it has asked to draw.
+struct GameState {
+    update: Box<dyn Fn(f32) -> Result<(), Trap>>,
+    draw: Box<dyn Fn() -> Result<(), Trap>>,
+    input: Box<dyn Fn(i32, i32) -> Result<(), Trap>>,
+    commands: Commands,
+}
+
+impl GameState {
+    /// Loads and starts the plugin at `path`.
+    pub fn new(path: &str) -> GameResult<Self> {
+        let store = Store::default();
+        let module = Module::from_file(store.engine(), path).map_err(load_error)?;
+        let commands: Commands = Rc::new(RefCell::new(Vec::new()));
+        let instance = link(&store, &commands)
+            .and_then(|linker| linker.instantiate(&module))
+            .map_err(load_error)?;
+        let export = |name: &str| {
+            instance
+                .get_func(name)
+                .ok_or_else(|| load_error(format!("it doesn't export `{}`", name)))
+        };
+
+        let init = export("init")?.get1::<i64, ()>().map_err(load_error)?;
+        let update = export("update")?.get1::<f32, ()>().map_err(load_error)?;
+        let draw = export("draw")?.get0::<()>().map_err(load_error)?;
+        let input = export("input")?
+            .get2::<i32, i32, ()>()
+            .map_err(load_error)?;
+
+        init(rand::random()).map_err(trap)?;
+        Ok(GameState {
+            update: Box::new(update),
+            draw: Box::new(draw),
+            input: Box::new(input),
+            commands,
+        })
+    }
+
+    fn press(&mut self, ctx: &mut Context, nav: Option<Nav>, pressed: bool) {
+        if nav == Some(Nav::Back) && pressed {
+            event::quit(ctx);
+            return;
+        }
+        if let Some(button) = nav.and_then(button) {
+            if let Err(e) = (self.input)(button, pressed as i32) {
+                eprintln!("The plugin stopped: {}", e);
+                event::quit(ctx);
+            }
+        }
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        (self.update)(timer::delta(ctx).as_secs_f32()).map_err(trap)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        (self.draw)().map_err(trap)?;
+
+        let commands: Vec<Draw> = self.commands.borrow_mut().drain(..).collect();
+        for command in commands {
+            match command {
+                Draw::Clear(color) => graphics::clear(ctx, color.into()),
+                Draw::Rect(rect, color) => {
+                    let mesh = MeshBuilder::new()
+                        .rectangle(DrawMode::fill(), rect, color.into())
+                        .build(ctx)?;
+                    graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+                }
+                Draw::Circle(x, y, radius, color) => {
+                    let mesh = MeshBuilder::new()
+                        .circle(
+                            DrawMode::fill(),
+                            ggez::mint::Point2 { x, y },
+                            radius,
+                            0.5,
+                            color.into(),
+                        )
+                        .build(ctx)?;
+                    graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+                }
+                Draw::Text(text, x, y, size, color) => {
+                    ui::draw_text(ctx, &text, color, size, x, y)?;
+                }
+            }
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _: KeyMods, repeat: bool) {
+        if !repeat {
+            self.press(ctx, Nav::from_key(keycode), true);
+        }
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, keycode: KeyCode, _: KeyMods) {
+        self.press(ctx, Nav::from_key(keycode), false);
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, _id: GamepadId) {
+        self.press(ctx, Nav::from_button(btn), true);
+    }
+
+    fn gamepad_button_up_event(&mut self, ctx: &mut Context, btn: Button, _id: GamepadId) {
+        self.press(ctx, Nav::from_button(btn), false);
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let path = args::value("plugin").ok_or_else(|| {
+        GameError::ResourceLoadError("Which plugin? Start it with --plugin <path>.".to_string())
+    })?;
+    let plugin = Plugin::load(path.as_ref());
+    let state = GameState::new(&path)?;
+
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("plugin", "Sprial404")
+        .window_setup(window::setup(&plugin.title))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(state);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,410 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, Context, GameResult};
+
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::cards::{self, Card, Deck, PokerHand, Rank};
+use common::migrate::{self, Format};
+use common::profile;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+const CARD_WIDTH: f32 = 120.0;
+const CARD_HEIGHT: f32 = 170.0;
+const CARD_SPACING: f32 = 140.0;
+const CARDS_LEFT: f32 = 50.0;
+const CARDS_TOP: f32 = 300.0;
+
+const STARTING_CREDITS: u32 = 100;
+const MAX_BET: u32 = 5;
+/// What a royal flush pays per credit when the maximum bet is played.
+const MAX_BET_ROYAL: u32 = 800;
+
+/// The file in the profile directory the bankroll is kept in.
+const CREDITS_FILE: &str = "poker_credits.txt";
+const CREDITS_FORMAT: Format = Format {
+    name: "credits",
+    migrations: &[migrate::unversioned],
+    compressed: true,
+};
+
+/// What each winning hand pays per credit bet, best hand first.
+const PAYTABLE: [(PokerHand, u32); 9] = [
+    (PokerHand::RoyalFlush, 250),
+    (PokerHand::StraightFlush, 50),
+    (PokerHand::FourOfAKind, 25),
+    (PokerHand::FullHouse, 9),
+    (PokerHand::Flush, 6),
+    (PokerHand::Straight, 4),
+    (PokerHand::ThreeOfAKind, 3),
+    (PokerHand::TwoPair, 2),
+    (PokerHand::Pair, 1),
+];
+
+/// Returns the paying hand of a Jacks or Better hand, if it pays at all.
+///
+/// Only pairs of jacks, queens, kings or aces pay.
+fn paying_hand(hand: &[Card; 5]) -> Option<PokerHand> {
+    let evaluation = cards::evaluate(hand);
+    match evaluation.hand {
+        PokerHand::HighCard => None,
+        PokerHand::Pair if evaluation.tiebreak[0] < Rank::Jack.high_value() => None,
+        hand => Some(hand),
+    }
+}
+
+fn payout(hand: PokerHand, bet: u32) -> u32 {
+    if hand == PokerHand::RoyalFlush && bet == MAX_BET {
+        return MAX_BET_ROYAL * bet;
+    }
+    PAYTABLE
+        .iter()
+        .find(|(paying, _)| *paying == hand)
+        .map_or(0, |(_, pays)| pays * bet)
+}
+
+fn credits_path() -> io::Result<PathBuf> {
+    Ok(profile::profile_dir()?.join(CREDITS_FILE))
+}
+
+/// Loads the current profile's bankroll, starting a new one if there isn't one saved.
+fn load_credits() -> u32 {
+    credits_path()
+        .and_then(|path| migrate::read(&path, &CREDITS_FORMAT))
+        .ok()
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(STARTING_CREDITS)
+}
+
+fn save_credits(credits: u32) -> io::Result<()> {
+    migrate::write(&credits_path()?, &CREDITS_FORMAT, &credits.to_string())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Phase {
+    /// Waiting for the player to pick a bet and deal.
+    Betting,
+    /// Five cards are dealt and the player is choosing which to hold.
+    Holding,
+}
+
+/// The state for the game.
+struct GameState {
+    phase: Phase,
+    profile: String,
+    credits: u32,
+    bet: u32,
+    deck: Deck,
+    hand: Option<[Card; 5]>,
+    held: [bool; 5],
+    /// The result of the last hand drawn, and what it paid.
+    result: Option<(Option<PokerHand>, u32)>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with the current profile's bankroll.
+    pub fn new() -> Self {
+        GameState {
+            phase: Phase::Betting,
+            profile: profile::current(),
+            credits: load_credits(),
+            bet: 1,
+            deck: Deck::shuffled(),
+            hand: None,
+            held: [false; 5],
+            result: None,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn deal(&mut self) {
+        if self.credits < self.bet {
+            return;
+        }
+
+        self.deck = Deck::shuffled();
+        let mut hand = [Card::new(Rank::Ace, cards::Suit::Spades); 5];
+        for card in hand.iter_mut() {
+            if let Some(dealt) = self.deck.deal() {
+                *card = dealt;
+            }
+        }
+
+        self.credits -= self.bet;
+        self.hand = Some(hand);
+        self.held = [false; 5];
+        self.result = None;
+        self.phase = Phase::Holding;
+        let _ = save_credits(self.credits);
+    }
+
+    fn draw_cards(&mut self) {
+        let mut hand = match self.hand {
+            Some(hand) => hand,
+            None => return,
+        };
+        for (card, held) in hand.iter_mut().zip(self.held.iter()) {
+            if !held {
+                if let Some(dealt) = self.deck.deal() {
+                    *card = dealt;
+                }
+            }
+        }
+
+        let paying = paying_hand(&hand);
+        let won = paying.map_or(0, |paying| payout(paying, self.bet));
+        self.credits += won;
+        self.hand = Some(hand);
+        self.result = Some((paying, won));
+        self.phase = Phase::Betting;
+        let _ = save_credits(self.credits);
+        self.record_hand(won);
+    }
+
+    /// Records a drawn hand, which is won if it paid anything.
+    fn record_hand(&self, paid: u32) {
+        let values = [
+            ("won", if paid > 0 { 1.0 } else { 0.0 }),
+            ("bet", f64::from(self.bet)),
+            ("paid", f64::from(paid)),
+            ("credits", f64::from(self.credits)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("poker", &values);
+        }
+        let _ = achievements::check_run("poker", &values);
+    }
+
+    /// Deals or draws, depending on where the hand is up to.
+    fn advance(&mut self) {
+        match self.phase {
+            Phase::Betting => self.deal(),
+            Phase::Holding => self.draw_cards(),
+        }
+    }
+
+    fn toggle_hold(&mut self, card: usize) {
+        if self.phase == Phase::Holding && card < self.held.len() {
+            self.held[card] = !self.held[card];
+        }
+    }
+
+    fn change_bet(&mut self, bet: u32) {
+        if self.phase == Phase::Betting {
+            self.bet = bet.max(1).min(MAX_BET);
+        }
+    }
+
+    fn card_rect(&self, card: usize) -> Rect {
+        Rect::new(
+            CARDS_LEFT + card as f32 * CARD_SPACING,
+            CARDS_TOP,
+            CARD_WIDTH,
+            CARD_HEIGHT,
+        )
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_paytable(&self, ctx: &mut Context) -> GameResult<()> {
+        let won = self.result.and_then(|(paying, _)| paying);
+        for (row, (hand, _)) in PAYTABLE.iter().enumerate() {
+            let y = 20.0 + row as f32 * 24.0;
+            let color = if won == Some(*hand) {
+                [1.0, 1.0, 0.3, 1.0]
+            } else {
+                [1.0, 0.85, 0.3, 1.0]
+            };
+            let name = if *hand == PokerHand::Pair {
+                "Jacks or Better"
+            } else {
+                hand.name()
+            };
+            self.draw_text(ctx, name, color, 20.0, 40.0, y)?;
+
+            for bet in 1..=MAX_BET {
+                // Highlight the column for the current bet.
+                let color = if bet == self.bet {
+                    [1.0, 1.0, 1.0, 1.0]
+                } else {
+                    [0.6, 0.6, 0.8, 1.0]
+                };
+                self.draw_text(
+                    ctx,
+                    &payout(*hand, bet).to_string(),
+                    color,
+                    20.0,
+                    280.0 + bet as f32 * 90.0,
+                    y,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_hand(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        for card in 0..5 {
+            let color = if self.hand.is_some() {
+                [1.0, 1.0, 1.0, 1.0]
+            } else {
+                [0.2, 0.3, 0.7, 1.0]
+            };
+            builder.rectangle(DrawMode::fill(), self.card_rect(card), color.into());
+            if self.held[card] && self.phase == Phase::Holding {
+                builder.rectangle(
+                    DrawMode::stroke(4.0),
+                    self.card_rect(card),
+                    [1.0, 0.85, 0.3, 1.0].into(),
+                );
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        if let Some(hand) = &self.hand {
+            for (i, card) in hand.iter().enumerate() {
+                let rect = self.card_rect(i);
+                let color = if card.suit.is_red() {
+                    [0.8, 0.1, 0.1, 1.0]
+                } else {
+                    [0.1, 0.1, 0.1, 1.0]
+                };
+                self.draw_text(
+                    ctx,
+                    &card.to_string(),
+                    color,
+                    48.0,
+                    rect.x + 12.0,
+                    rect.y + 12.0,
+                )?;
+                if self.held[i] && self.phase == Phase::Holding {
+                    self.draw_text(
+                        ctx,
+                        "HELD",
+                        [1.0, 0.85, 0.3, 1.0],
+                        24.0,
+                        rect.x + 30.0,
+                        rect.y - 30.0,
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.4, 1.0].into());
+
+        self.draw_paytable(ctx)?;
+        self.draw_hand(ctx)?;
+
+        let message = match (self.phase, self.result) {
+            (Phase::Holding, _) => {
+                "Click cards or press 1-5 to hold, then Space to draw.".to_string()
+            }
+            (Phase::Betting, _) if self.credits < self.bet => {
+                "Not enough credits. Lower the bet or press R to start over.".to_string()
+            }
+            (Phase::Betting, Some((Some(hand), won))) => {
+                format!("{}! You win {} credits.", hand.name(), won)
+            }
+            (Phase::Betting, Some((None, _))) => "No win. Space to deal again.".to_string(),
+            (Phase::Betting, None) => "Up/Down to bet, Space to deal.".to_string(),
+        };
+        self.draw_text(ctx, &message, [1.0, 1.0, 1.0, 1.0], 24.0, 40.0, 500.0)?;
+        self.draw_text(
+            ctx,
+            &format!(
+                "Profile: {}   Bet: {}   Credits: {}",
+                self.profile, self.bet, self.credits
+            ),
+            [1.0, 0.85, 0.3, 1.0],
+            24.0,
+            40.0,
+            SCREEN_SIZE.1 - 50.0,
+        )?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        if let Some(card) = (0..5).find(|&card| self.card_rect(card).contains([x, y])) {
+            self.toggle_hold(card);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if repeat {
+            return;
+        }
+        match keycode {
+            KeyCode::Space | KeyCode::Return => self.advance(),
+            KeyCode::Key1 => self.toggle_hold(0),
+            KeyCode::Key2 => self.toggle_hold(1),
+            KeyCode::Key3 => self.toggle_hold(2),
+            KeyCode::Key4 => self.toggle_hold(3),
+            KeyCode::Key5 => self.toggle_hold(4),
+            KeyCode::Up => self.change_bet(self.bet + 1),
+            KeyCode::Down => self.change_bet(self.bet - 1),
+            KeyCode::M => self.change_bet(MAX_BET),
+            KeyCode::R if self.phase == Phase::Betting && self.credits < self.bet => {
+                self.credits = STARTING_CREDITS;
+                let _ = save_credits(self.credits);
+            }
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("poker", "Sprial404")
+        .window_setup(window::setup("Video Poker"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,314 @@
+//! Games as environments for reinforcement learning, in the shape gym uses: reset for the first
+//! observation, then step with an action for the next observation, the reward and whether the
+//! game is over.
+//!
+//! Observations are grids of numbers, one grid per channel, so they can go straight into a
+//! convolutional network. Anything that implements `Env` can be stepped in a `Batch`, which
+//! steps lots of games at once and starts each one over as it ends.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use snake::{Ate, Direction, Simulation, GRID_SIZE};
+
+/// The reward for a step that eats food.
+pub const FOOD_REWARD: f64 = 1.0;
+/// The reward for the step that ends the game.
+pub const DEATH_REWARD: f64 = -1.0;
+
+/// A game an agent can be trained on, one step at a time.
+pub trait Env {
+    /// The shape of an observation: channels, then rows, then columns.
+    const SHAPE: [usize; 3];
+    /// How many actions there are, numbered from 0.
+    const ACTIONS: usize;
+
+    /// Starts a new game.
+    fn reset(&mut self);
+
+    /// Takes an action, returning the reward for it and whether the game is over. `action` is
+    /// less than `ACTIONS`.
+    fn step(&mut self, action: usize) -> (f64, bool);
+
+    /// Writes the game as it stands into `out`, which is as long as `SHAPE` multiplied out and
+    /// all zeros.
+    fn observe(&self, out: &mut [f32]);
+
+    /// How well the current game is going, like the snake's length.
+    fn score(&self) -> usize;
+
+    /// Whether the last step ended the game by running out of time rather than losing.
+    fn timed_out(&self) -> bool;
+
+    /// How many numbers are in an observation.
+    fn observation_len() -> usize {
+        Self::SHAPE.iter().product()
+    }
+}
+
+/// The directions, in the order of the actions that pick them.
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+/// How many steps the snake can go without eating before the game is called off, so an agent
+/// that has learnt to go round in circles still finishes.
+const STEPS_PER_FOOD: u32 = GRID_SIZE.0 as u32 * GRID_SIZE.1 as u32;
+
+/// Snake on an open board. Each action turns the snake up, right, down or left before it
+/// moves, and turning back on itself does nothing.
+///
+/// An observation has a channel each for the head, the rest of the body and the food, with a 1
+/// in each cell they're in.
+pub struct SnakeEnv {
+    sim: Simulation,
+    wrap: bool,
+    /// Seeds each game in turn.
+    seeds: StdRng,
+    hungry_steps: u32,
+    timed_out: bool,
+}
+
+impl SnakeEnv {
+    /// Creates an environment whose games are all decided by `seed`.
+    pub fn new(seed: u64, wrap: bool) -> Self {
+        let mut seeds = StdRng::seed_from_u64(seed);
+        SnakeEnv {
+            sim: Simulation::new(StdRng::seed_from_u64(seeds.gen()), wrap),
+            wrap,
+            seeds,
+            hungry_steps: 0,
+            timed_out: false,
+        }
+    }
+}
+
+impl Env for SnakeEnv {
+    const SHAPE: [usize; 3] = [3, GRID_SIZE.1 as usize, GRID_SIZE.0 as usize];
+    const ACTIONS: usize = DIRECTIONS.len();
+
+    fn reset(&mut self) {
+        self.sim = Simulation::new(StdRng::seed_from_u64(self.seeds.gen()), self.wrap);
+        self.hungry_steps = 0;
+        self.timed_out = false;
+    }
+
+    fn step(&mut self, action: usize) -> (f64, bool) {
+        self.sim.snake.turn(DIRECTIONS[action]);
+        let reward = match self.sim.step() {
+            Some(Ate::Food) => {
+                self.hungry_steps = 0;
+                FOOD_REWARD
+            }
+            Some(Ate::Itself) | Some(Ate::Wall) => DEATH_REWARD,
+            None => {
+                self.hungry_steps += 1;
+                0.0
+            }
+        };
+        self.timed_out = !self.sim.over && self.hungry_steps >= STEPS_PER_FOOD;
+        (reward, self.sim.over || self.timed_out)
+    }
+
+    fn observe(&self, out: &mut [f32]) {
+        let [_, rows, columns] = Self::SHAPE;
+        let mut mark = |channel: usize, x: i16, y: i16| {
+            // The head can be off the board once it has run into the edge, and so can food
+            // that wasn't placed.
+            if x >= 0 && y >= 0 && (x as usize) < columns && (y as usize) < rows {
+                out[(channel * rows + y as usize) * columns + x as usize] = 1.0;
+            }
+        };
+        let snake = &self.sim.snake;
+        mark(0, snake.head.pos.x, snake.head.pos.y);
+        for segment in snake.body.iter() {
+            mark(1, segment.pos.x, segment.pos.y);
+        }
+        mark(2, self.sim.food.pos.x, self.sim.food.pos.y);
+    }
+
+    fn score(&self) -> usize {
+        self.sim.snake.length()
+    }
+
+    fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+/// What a `Batch` gives back for a step of all its games, with each game's numbers one after
+/// another.
+pub struct BatchStep {
+    pub observations: Vec<f32>,
+    pub rewards: Vec<f64>,
+    pub dones: Vec<bool>,
+    /// The score each game finished with, for the games that just finished.
+    pub final_scores: Vec<Option<usize>>,
+    pub timed_out: Vec<bool>,
+}
+
+/// Lots of games stepped together. A game that finishes is started over straight away, and the
+/// observation given back for it is the new game's first.
+pub struct Batch<E> {
+    pub envs: Vec<E>,
+}
+
+impl<E: Env> Batch<E> {
+    pub fn new(envs: Vec<E>) -> Self {
+        Batch { envs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    /// Starts every game over, returning their first observations.
+    pub fn reset(&mut self) -> Vec<f32> {
+        for env in &mut self.envs {
+            env.reset();
+        }
+        self.observe()
+    }
+
+    /// Takes an action in each game, one for each in order.
+    pub fn step(&mut self, actions: &[usize]) -> BatchStep {
+        let mut rewards = Vec::with_capacity(self.len());
+        let mut dones = Vec::with_capacity(self.len());
+        let mut final_scores = Vec::with_capacity(self.len());
+        let mut timed_out = Vec::with_capacity(self.len());
+        for (env, &action) in self.envs.iter_mut().zip(actions) {
+            let (reward, done) = env.step(action);
+            rewards.push(reward);
+            dones.push(done);
+            timed_out.push(env.timed_out());
+            final_scores.push(if done { Some(env.score()) } else { None });
+            if done {
+                env.reset();
+            }
+        }
+        BatchStep {
+            observations: self.observe(),
+            rewards,
+            dones,
+            final_scores,
+            timed_out,
+        }
+    }
+
+    /// Every game as it stands, one observation after another.
+    pub fn observe(&self) -> Vec<f32> {
+        let len = E::observation_len();
+        let mut observations = vec![0.0; len * self.len()];
+        for (env, out) in self.envs.iter().zip(observations.chunks_mut(len)) {
+            env.observe(out);
+        }
+        observations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of cells set in each channel of an observation.
+    fn counts(observation: &[f32]) -> Vec<usize> {
+        let [channels, rows, columns] = SnakeEnv::SHAPE;
+        (0..channels)
+            .map(|channel| {
+                let start = channel * rows * columns;
+                observation[start..start + rows * columns]
+                    .iter()
+                    .filter(|&&cell| cell == 1.0)
+                    .count()
+            })
+            .collect()
+    }
+
+    fn observe(env: &SnakeEnv) -> Vec<f32> {
+        let mut observation = vec![0.0; SnakeEnv::observation_len()];
+        env.observe(&mut observation);
+        observation
+    }
+
+    /// Plays `env` with `actions`, giving back each step's reward and whether it ended the game
+    /// along with the observation after it.
+    fn play(env: &mut SnakeEnv, actions: &[usize]) -> Vec<(f64, bool, Vec<f32>)> {
+        actions
+            .iter()
+            .map(|&action| {
+                let (reward, done) = env.step(action);
+                if done {
+                    env.reset();
+                }
+                (reward, done, observe(env))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn observations_have_a_channel_each_for_the_head_body_and_food() {
+        assert_eq!(
+            SnakeEnv::observation_len(),
+            3 * GRID_SIZE.0 as usize * GRID_SIZE.1 as usize
+        );
+
+        let mut env = SnakeEnv::new(1, false);
+        env.reset();
+        assert_eq!(counts(&observe(&env)), vec![1, env.score() - 1, 1]);
+    }
+
+    #[test]
+    fn batches_give_back_a_value_for_each_game() {
+        let mut batch = Batch::new((0..4).map(|seed| SnakeEnv::new(seed, true)).collect());
+        assert_eq!(batch.len(), 4);
+        assert_eq!(batch.reset().len(), 4 * SnakeEnv::observation_len());
+
+        let step = batch.step(&[0, 1, 2, 3]);
+        assert_eq!(step.observations.len(), 4 * SnakeEnv::observation_len());
+        assert_eq!(step.rewards.len(), 4);
+        assert_eq!(step.dones.len(), 4);
+        assert_eq!(step.final_scores.len(), 4);
+        assert_eq!(step.timed_out.len(), 4);
+        for observation in step.observations.chunks(SnakeEnv::observation_len()) {
+            assert_eq!(counts(observation)[0], 1);
+        }
+    }
+
+    #[test]
+    fn finished_games_in_a_batch_start_over() {
+        let mut batch = Batch::new(vec![SnakeEnv::new(3, false)]);
+        batch.reset();
+        // Heading up without wrapping runs into the edge of the board.
+        let step = (0..GRID_SIZE.1)
+            .map(|_| batch.step(&[0]))
+            .find(|step| step.dones[0])
+            .unwrap();
+        assert_eq!(step.rewards[0], DEATH_REWARD);
+        assert!(!step.timed_out[0]);
+        assert!(step.final_scores[0].is_some());
+        assert_eq!(counts(&step.observations)[0], 1);
+        assert_eq!(step.observations, batch.observe());
+    }
+
+    #[test]
+    fn the_same_seed_plays_out_the_same() {
+        let actions: Vec<usize> = (0..200)
+            .map(|step| (step / 5) % SnakeEnv::ACTIONS)
+            .collect();
+        let mut first = SnakeEnv::new(7, true);
+        let mut second = SnakeEnv::new(7, true);
+        assert_eq!(observe(&first), observe(&second));
+        assert_eq!(play(&mut first, &actions), play(&mut second, &actions));
+
+        first.reset();
+        second.reset();
+        assert_eq!(observe(&first), observe(&second));
+        assert_ne!(
+            observe(&SnakeEnv::new(8, true)),
+            observe(&SnakeEnv::new(7, true))
+        );
+    }
+}
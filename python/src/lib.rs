@@ -0,0 +1,297 @@
+//! Python bindings for the games' rules, for training agents against the same logic the games
+//! run:
+//!
+//! ```python
+//! import quick_games
+//!
+//! env = quick_games.Snake(seed=1)
+//! observation = env.reset()
+//! while True:
+//!     observation, reward, done = env.step("up")
+//!     if done:
+//!         break
+//! ```
+//!
+//! For training, `SnakeEnv` gives observations as numpy arrays with a channel each for the
+//! snake's head, its body and the food, and `VecSnakeEnv` steps lots of games at once:
+//!
+//! ```python
+//! envs = quick_games.VecSnakeEnv(64, seed=1)
+//! observations = envs.reset()  # shaped (64, 3, 20, 30)
+//! while True:
+//!     actions = policy(observations)  # 0 to 3 for up, right, down and left
+//!     observations, rewards, dones, infos = envs.step(actions)
+//! ```
+//!
+//! Build and install them with `pip install ./python`, which uses maturin. It turns on the
+//! `extension-module` feature, which the module needs to be loaded by Python, but which leaves
+//! `cargo test` nothing to link against, so it's off otherwise.
+
+mod env;
+
+use std::iter;
+
+use numpy::{Element, PyArray};
+use pyo3::exceptions::ValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use snake::{Ate, Direction, GridPosition, Simulation, GRID_SIZE};
+
+use crate::env::{Batch, Env, SnakeEnv, DEATH_REWARD, FOOD_REWARD};
+
+fn direction(name: &str) -> PyResult<Direction> {
+    Direction::from_name(name).ok_or_else(|| {
+        ValueError::py_err(format!(
+            "`{}` isn't a direction. Try up, down, left or right.",
+            name
+        ))
+    })
+}
+
+fn point(pos: GridPosition) -> (i16, i16) {
+    (pos.x, pos.y)
+}
+
+/// Snake, stepped one move at a time. The same seed and actions always play out the same way.
+#[pyclass(name = Snake)]
+struct PySnake {
+    sim: Simulation,
+    seed: u64,
+}
+
+impl PySnake {
+    fn start(seed: u64, wrap: bool) -> Simulation {
+        Simulation::new(StdRng::seed_from_u64(seed), wrap)
+    }
+}
+
+#[pymethods]
+impl PySnake {
+    #[new]
+    #[args(seed = "0", wrap = "true")]
+    fn new(seed: u64, wrap: bool) -> Self {
+        PySnake {
+            sim: PySnake::start(seed, wrap),
+            seed,
+        }
+    }
+
+    /// Starts the game over from its seed, returning the first observation.
+    fn reset(&mut self, py: Python) -> PyResult<PyObject> {
+        self.sim = PySnake::start(self.seed, self.sim.wrap);
+        self.observe(py)
+    }
+
+    /// Turns the snake if given a direction, then moves it once. Returns the observation, the
+    /// reward for the move and whether the game is over.
+    #[args(action = "None")]
+    fn step(&mut self, py: Python, action: Option<&str>) -> PyResult<(PyObject, f64, bool)> {
+        if let Some(action) = action {
+            self.sim.snake.turn(direction(action)?);
+        }
+        let reward = match self.sim.step() {
+            Some(Ate::Food) => FOOD_REWARD,
+            Some(Ate::Itself) | Some(Ate::Wall) => DEATH_REWARD,
+            None => 0.0,
+        };
+        Ok((self.observe(py)?, reward, self.sim.over))
+    }
+
+    /// The game as it stands: the board's size, the snake from head to tail, the way it is
+    /// heading, the food and whether the game is over.
+    fn observe(&self, py: Python) -> PyResult<PyObject> {
+        let snake = &self.sim.snake;
+        let body: Vec<(i16, i16)> = iter::once(&snake.head)
+            .chain(snake.body.iter())
+            .map(|segment| point(segment.pos))
+            .collect();
+        let observation = PyDict::new(py);
+        observation.set_item("width", GRID_SIZE.0)?;
+        observation.set_item("height", GRID_SIZE.1)?;
+        observation.set_item("body", body)?;
+        observation.set_item("direction", snake.dir.name())?;
+        observation.set_item("food", point(self.sim.food.pos))?;
+        observation.set_item("length", snake.length())?;
+        observation.set_item("over", self.sim.over)?;
+        Ok(observation.into())
+    }
+}
+
+/// A numpy array of `data` in the given shape.
+fn array<T: Element>(py: Python, data: Vec<T>, shape: &[usize]) -> PyResult<PyObject> {
+    Ok(PyArray::from_vec(py, data).reshape(shape)?.to_object(py))
+}
+
+fn check_action<E: Env>(action: usize) -> PyResult<()> {
+    if action < E::ACTIONS {
+        Ok(())
+    } else {
+        Err(ValueError::py_err(format!(
+            "`{}` isn't an action. Try 0 to {}.",
+            action,
+            E::ACTIONS - 1
+        )))
+    }
+}
+
+/// Snake as a gym-style environment, with observations as numpy arrays shaped
+/// `observation_shape` and actions from 0 to 3 for up, right, down and left. A game is called
+/// off if the snake goes a long time without eating.
+#[pyclass(name = SnakeEnv)]
+struct PySnakeEnv {
+    env: SnakeEnv,
+}
+
+impl PySnakeEnv {
+    fn observe(&self, py: Python) -> PyResult<PyObject> {
+        let mut observation = vec![0.0; SnakeEnv::observation_len()];
+        self.env.observe(&mut observation);
+        array(py, observation, &SnakeEnv::SHAPE)
+    }
+}
+
+#[pymethods]
+impl PySnakeEnv {
+    #[new]
+    #[args(seed = "0", wrap = "true")]
+    fn new(seed: u64, wrap: bool) -> Self {
+        PySnakeEnv {
+            env: SnakeEnv::new(seed, wrap),
+        }
+    }
+
+    /// Channels, rows and columns.
+    #[getter]
+    fn observation_shape(&self) -> (usize, usize, usize) {
+        let [channels, rows, columns] = SnakeEnv::SHAPE;
+        (channels, rows, columns)
+    }
+
+    #[getter]
+    fn action_count(&self) -> usize {
+        SnakeEnv::ACTIONS
+    }
+
+    /// Starts a new game, returning its first observation. Each game is seeded in turn from
+    /// the environment's seed.
+    fn reset(&mut self, py: Python) -> PyResult<PyObject> {
+        self.env.reset();
+        self.observe(py)
+    }
+
+    /// Takes an action, returning the observation, the reward, whether the game is over and a
+    /// dict with the snake's `length` and whether the game `timed_out`.
+    fn step(&mut self, py: Python, action: usize) -> PyResult<(PyObject, f64, bool, PyObject)> {
+        check_action::<SnakeEnv>(action)?;
+        let (reward, done) = self.env.step(action);
+        let info = PyDict::new(py);
+        info.set_item("length", self.env.score())?;
+        info.set_item("timed_out", self.env.timed_out())?;
+        Ok((self.observe(py)?, reward, done, info.into()))
+    }
+}
+
+/// Lots of `SnakeEnv`s stepped together, the `n`th seeded with `seed + n`. Observations,
+/// rewards and dones are numpy arrays with a row for each game. A game that finishes starts
+/// over straight away, and its observation is the new game's first.
+#[pyclass(name = VecSnakeEnv)]
+struct PyVecSnakeEnv {
+    batch: Batch<SnakeEnv>,
+}
+
+impl PyVecSnakeEnv {
+    fn observations(&self, py: Python, observations: Vec<f32>) -> PyResult<PyObject> {
+        let [channels, rows, columns] = SnakeEnv::SHAPE;
+        array(
+            py,
+            observations,
+            &[self.batch.len(), channels, rows, columns],
+        )
+    }
+}
+
+#[pymethods]
+impl PyVecSnakeEnv {
+    #[new]
+    #[args(seed = "0", wrap = "true")]
+    fn new(count: usize, seed: u64, wrap: bool) -> Self {
+        let envs = (0..count)
+            .map(|index| SnakeEnv::new(seed.wrapping_add(index as u64), wrap))
+            .collect();
+        PyVecSnakeEnv {
+            batch: Batch::new(envs),
+        }
+    }
+
+    /// Games, channels, rows and columns.
+    #[getter]
+    fn observation_shape(&self) -> (usize, usize, usize, usize) {
+        let [channels, rows, columns] = SnakeEnv::SHAPE;
+        (self.batch.len(), channels, rows, columns)
+    }
+
+    #[getter]
+    fn action_count(&self) -> usize {
+        SnakeEnv::ACTIONS
+    }
+
+    fn __len__(&self) -> usize {
+        self.batch.len()
+    }
+
+    /// Starts every game over, returning their first observations.
+    fn reset(&mut self, py: Python) -> PyResult<PyObject> {
+        let observations = self.batch.reset();
+        self.observations(py, observations)
+    }
+
+    /// Takes an action in each game, returning the observations, rewards and dones, and a list
+    /// of dicts saying whether each game `timed_out` and, for games that just finished, the
+    /// `final_length` of the snake.
+    fn step(
+        &mut self,
+        py: Python,
+        actions: Vec<usize>,
+    ) -> PyResult<(PyObject, PyObject, PyObject, PyObject)> {
+        if actions.len() != self.batch.len() {
+            return Err(ValueError::py_err(format!(
+                "There are {} games, but {} actions.",
+                self.batch.len(),
+                actions.len()
+            )));
+        }
+        for &action in &actions {
+            check_action::<SnakeEnv>(action)?;
+        }
+
+        let batch = &mut self.batch;
+        let step = py.allow_threads(|| batch.step(&actions));
+        let mut infos = Vec::with_capacity(step.dones.len());
+        for (final_score, timed_out) in step.final_scores.iter().zip(&step.timed_out) {
+            let info = PyDict::new(py);
+            info.set_item("timed_out", *timed_out)?;
+            if let Some(length) = final_score {
+                info.set_item("final_length", *length)?;
+            }
+            infos.push(info);
+        }
+        let games = [self.batch.len()];
+        Ok((
+            self.observations(py, step.observations)?,
+            array(py, step.rewards, &games)?,
+            array(py, step.dones, &games)?,
+            infos.to_object(py),
+        ))
+    }
+}
+
+#[pymodule]
+fn quick_games(_py: Python, module: &PyModule) -> PyResult<()> {
+    module.add_class::<PySnake>()?;
+    module.add_class::<PySnakeEnv>()?;
+    module.add_class::<PyVecSnakeEnv>()?;
+    Ok(())
+}
@@ -0,0 +1,445 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::grid::Grid;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+/// The size of the playfield in cells.
+const GRID_SIZE: (i32, i32) = (120, 90);
+const CELL_SIZE: f32 = 6.0;
+/// The space above the playfield for the HUD.
+const HUD_HEIGHT: f32 = 40.0;
+const SCREEN_SIZE: (f32, f32) = (
+    GRID_SIZE.0 as f32 * CELL_SIZE,
+    GRID_SIZE.1 as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+/// Seconds between each step of the player, and while drawing a line.
+const MOVE_INTERVAL: f32 = 0.02;
+const DRAW_INTERVAL: f32 = 0.035;
+const LIVES: u32 = 3;
+/// The percentage of the playfield that must be claimed to clear a level.
+const TARGET_PERCENT: f32 = 75.0;
+
+/// The speed of the enemy in cells per second on the first level.
+const ENEMY_SPEED: f32 = 30.0;
+const ENEMY_SPEED_STEP: f32 = 6.0;
+/// The length of the enemy's spinning line, in cells.
+const ENEMY_LENGTH: f32 = 8.0;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Cell {
+    Open,
+    Claimed,
+    /// Part of the line the player is currently drawing.
+    Trail,
+}
+
+/// The enemy roaming the unclaimed area, drawn as a spinning line.
+#[derive(Debug, Copy, Clone)]
+struct Enemy {
+    x: f32,
+    y: f32,
+    dx: f32,
+    dy: f32,
+    spin: f32,
+}
+
+impl Enemy {
+    /// Creates a new enemy in the middle of the playfield heading in a random direction.
+    pub fn new(speed: f32) -> Self {
+        let angle = rand::thread_rng().gen_range(0.0, std::f32::consts::PI * 2.0);
+        Enemy {
+            x: GRID_SIZE.0 as f32 / 2.0,
+            y: GRID_SIZE.1 as f32 / 2.0,
+            dx: angle.cos() * speed,
+            dy: angle.sin() * speed,
+            spin: 0.0,
+        }
+    }
+
+    /// The cells covered by the enemy's line.
+    fn cells(&self) -> Vec<(i32, i32)> {
+        let (sin, cos) = self.spin.sin_cos();
+        (0..=ENEMY_LENGTH as i32)
+            .map(|i| {
+                let t = i as f32 - ENEMY_LENGTH / 2.0;
+                ((self.x + cos * t) as i32, (self.y + sin * t) as i32)
+            })
+            .collect()
+    }
+
+    fn update(&mut self, grid: &Grid<Cell>, dt: f32) {
+        let mut rng = rand::thread_rng();
+        self.spin += dt * 3.0;
+
+        // Now and then veer off in a new direction.
+        if rng.gen_bool(0.02) {
+            let speed = (self.dx * self.dx + self.dy * self.dy).sqrt();
+            let angle = self.dy.atan2(self.dx) + rng.gen_range(-1.0, 1.0);
+            self.dx = angle.cos() * speed;
+            self.dy = angle.sin() * speed;
+        }
+
+        // Bounce off anything that isn't open, axis by axis.
+        let open = |x: f32, y: f32| grid.get(x as i32, y as i32) != Some(&Cell::Claimed);
+        let next_x = self.x + self.dx * dt;
+        if open(next_x, self.y) {
+            self.x = next_x;
+        } else {
+            self.dx = -self.dx;
+        }
+        let next_y = self.y + self.dy * dt;
+        if open(self.x, next_y) {
+            self.y = next_y;
+        } else {
+            self.dy = -self.dy;
+        }
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    grid: Grid<Cell>,
+    player: (i32, i32),
+    /// The cells of the line being drawn, in order, starting from where it left the claimed area.
+    trail: Vec<(i32, i32)>,
+    enemy: Enemy,
+    move_timer: f32,
+    level: u32,
+    lives: u32,
+    score: u32,
+    /// The number of claimed cells inside the border.
+    claimed: usize,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the first level.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            grid: Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, Cell::Open),
+            player: (0, 0),
+            trail: Vec::new(),
+            enemy: Enemy::new(ENEMY_SPEED),
+            move_timer: 0.0,
+            level: 1,
+            lives: LIVES,
+            score: 0,
+            claimed: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.start_level();
+        state
+    }
+
+    /// Resets the playfield to a claimed border around an open area.
+    fn start_level(&mut self) {
+        self.grid.fill(Cell::Open);
+        for x in 0..GRID_SIZE.0 {
+            self.grid.set(x, 0, Cell::Claimed);
+            self.grid.set(x, GRID_SIZE.1 - 1, Cell::Claimed);
+        }
+        for y in 0..GRID_SIZE.1 {
+            self.grid.set(0, y, Cell::Claimed);
+            self.grid.set(GRID_SIZE.0 - 1, y, Cell::Claimed);
+        }
+
+        self.player = (GRID_SIZE.0 / 2, GRID_SIZE.1 - 1);
+        self.trail.clear();
+        self.claimed = 0;
+        self.enemy = Enemy::new(ENEMY_SPEED + (self.level - 1) as f32 * ENEMY_SPEED_STEP);
+    }
+
+    /// The percentage of the area inside the border that has been claimed.
+    fn percent(&self) -> f32 {
+        let inner = ((GRID_SIZE.0 - 2) * (GRID_SIZE.1 - 2)) as f32;
+        self.claimed as f32 / inner * 100.0
+    }
+
+    fn cell(&self, x: i32, y: i32) -> Option<Cell> {
+        self.grid.get(x, y).cloned()
+    }
+
+    /// Whether a claimed cell borders the open area, so the player can travel along it.
+    fn is_edge(&self, x: i32, y: i32) -> bool {
+        if self.cell(x, y) != Some(Cell::Claimed) {
+            return false;
+        }
+        (-1..=1).any(|dy| (-1..=1).any(|dx| self.cell(x + dx, y + dy) == Some(Cell::Open)))
+    }
+
+    fn step_player(&mut self, dir: (i32, i32), drawing: bool) {
+        let (x, y) = (self.player.0 + dir.0, self.player.1 + dir.1);
+
+        match self.cell(x, y) {
+            Some(Cell::Claimed) if self.trail.is_empty() => {
+                if self.is_edge(x, y) {
+                    self.player = (x, y);
+                }
+            }
+            Some(Cell::Claimed) => {
+                self.player = (x, y);
+                self.close_trail();
+            }
+            Some(Cell::Open) if drawing => {
+                self.grid.set(x, y, Cell::Trail);
+                self.trail.push((x, y));
+                self.player = (x, y);
+            }
+            _ => (),
+        }
+    }
+
+    /// Claims the trail, then every open region the enemy isn't in.
+    fn close_trail(&mut self) {
+        for &(x, y) in self.trail.iter() {
+            self.grid.set(x, y, Cell::Claimed);
+        }
+        let mut gained = self.trail.len();
+        self.trail.clear();
+
+        let enemy = (self.enemy.x as i32, self.enemy.y as i32);
+        let mut seen = Grid::new(GRID_SIZE.0 as usize, GRID_SIZE.1 as usize, false);
+        let mut regions = Vec::new();
+        for y in 0..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                if self.cell(x, y) != Some(Cell::Open) || seen.get(x, y) == Some(&true) {
+                    continue;
+                }
+                let region = self.grid.flood_fill(x, y, |cell| *cell == Cell::Open);
+                for &(x, y) in region.iter() {
+                    seen.set(x, y, true);
+                }
+                regions.push(region);
+            }
+        }
+
+        for region in regions {
+            if region.contains(&enemy) {
+                continue;
+            }
+            gained += region.len();
+            for (x, y) in region {
+                self.grid.set(x, y, Cell::Claimed);
+            }
+        }
+
+        self.claimed += gained;
+        // Bigger claims in one go are worth more per cell.
+        self.score += (gained * (1 + gained / 500)) as u32;
+
+        if self.percent() >= TARGET_PERCENT {
+            self.score += 1000 * self.level;
+            self.level += 1;
+            self.start_level();
+        }
+    }
+
+    /// Loses a life, erasing the trail and sending the player back to where it started.
+    fn lose_life(&mut self) {
+        if let Some(&(x, y)) = self.trail.first() {
+            // The trail always starts right next to the claimed cell it left from.
+            let start = self
+                .grid
+                .neighbours(x, y)
+                .find(|&(nx, ny)| self.cell(nx, ny) == Some(Cell::Claimed));
+            if let Some(start) = start {
+                self.player = start;
+            }
+        }
+        for &(x, y) in self.trail.iter() {
+            self.grid.set(x, y, Cell::Open);
+        }
+        self.trail.clear();
+
+        self.lives -= 1;
+        if self.lives == 0 {
+            self.gameover = true;
+            self.record_run();
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("level", f64::from(self.level)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("qix", &values);
+        }
+        let _ = achievements::check_run("qix", &values);
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.enemy.update(&self.grid, dt);
+
+        let drawing = keyboard::is_key_pressed(ctx, KeyCode::Space);
+        let dir = if keyboard::is_key_pressed(ctx, KeyCode::Left) {
+            Some((-1, 0))
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Right) {
+            Some((1, 0))
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Up) {
+            Some((0, -1))
+        } else if keyboard::is_key_pressed(ctx, KeyCode::Down) {
+            Some((0, 1))
+        } else {
+            None
+        };
+
+        self.move_timer -= dt;
+        if let Some(dir) = dir {
+            if self.move_timer <= 0.0 {
+                self.step_player(dir, drawing);
+                self.move_timer = if self.trail.is_empty() {
+                    MOVE_INTERVAL
+                } else {
+                    DRAW_INTERVAL
+                };
+            }
+        }
+
+        // The enemy touching the trail, or the player while they are drawing, costs a life.
+        if !self.trail.is_empty() {
+            let hit = self
+                .enemy
+                .cells()
+                .into_iter()
+                .any(|(x, y)| self.cell(x, y) == Some(Cell::Trail) || (x, y) == self.player);
+            if hit {
+                self.lose_life();
+            }
+        }
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (x, y, cell) in self.grid.iter() {
+            let color = match cell {
+                Cell::Open => continue,
+                Cell::Claimed if self.is_edge(x, y) => [1.0, 1.0, 1.0, 1.0],
+                Cell::Claimed => [0.2, 0.3, 0.7, 1.0],
+                Cell::Trail => [1.0, 0.5, 0.1, 1.0],
+            };
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    x as f32 * CELL_SIZE,
+                    HUD_HEIGHT + y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                ),
+                color.into(),
+            );
+        }
+        for (x, y) in self.enemy.cells() {
+            builder.rectangle(
+                DrawMode::fill(),
+                Rect::new(
+                    x as f32 * CELL_SIZE,
+                    HUD_HEIGHT + y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                ),
+                [1.0, 0.2, 0.8, 1.0].into(),
+            );
+        }
+        builder.circle(
+            DrawMode::fill(),
+            ggez::mint::Point2 {
+                x: (self.player.0 as f32 + 0.5) * CELL_SIZE,
+                y: HUD_HEIGHT + (self.player.1 as f32 + 0.5) * CELL_SIZE,
+            },
+            CELL_SIZE,
+            0.5,
+            [1.0, 0.9, 0.1, 1.0].into(),
+        );
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Level: {}  Lives: {}  Claimed: {:.1}% / {:.0}%",
+                locale::integer(self.score),
+                self.level,
+                self.lives,
+                self.percent(),
+                TARGET_PERCENT
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER! Press Enter to play again.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+
+            graphics::draw(
+                ctx,
+                &game_over,
+                (ggez::mint::Point2 {
+                    x: 40.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.gameover => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("qix", "Sprial404")
+        .window_setup(window::setup("Qix"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,681 @@
+use ggez::event::{Axis, Button, KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::input::{Controllers, MAX_PLAYERS};
+use common::physics::Vec2;
+use common::spectator;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (700.0, 700.0);
+
+/// The square blocks filling each corner, which the balls bounce off.
+const CORNER: f32 = 70.0;
+
+const PADDLE_LENGTH: f32 = 100.0;
+const PADDLE_THICKNESS: f32 = 14.0;
+/// How far each paddle sits in from its edge.
+const PADDLE_MARGIN: f32 = 20.0;
+const PADDLE_SPEED: f32 = 520.0;
+/// AI paddles are a little slower than players, so they can be beaten.
+const AI_SPEED: f32 = 380.0;
+/// How far off an AI paddle might aim from where the ball will arrive.
+const AI_ERROR: f32 = 40.0;
+
+const BALL_RADIUS: f32 = 8.0;
+const START_SPEED: f32 = 300.0;
+/// Each paddle hit speeds the ball up, to at most `MAX_SPEED`.
+const SPEEDUP: f32 = 20.0;
+const MAX_SPEED: f32 = 650.0;
+/// The steepest angle off straight out a paddle can send the ball, in radians.
+const MAX_BOUNCE_ANGLE: f32 = 1.0;
+/// Seconds a ball waits in the middle before it is served.
+const SERVE_DELAY: f32 = 1.0;
+
+/// The balls in play at once in chaos mode. Normal games have one.
+const CHAOS_BALLS: usize = 4;
+
+const START_LIVES: u32 = 3;
+
+const SIDE_NAMES: [&str; MAX_PLAYERS] = ["Bottom", "Top", "Left", "Right"];
+const SIDE_COLORS: [[f32; 4]; MAX_PLAYERS] = [
+    [0.3, 0.7, 1.0, 1.0],
+    [1.0, 0.55, 0.25, 1.0],
+    [0.45, 0.9, 0.4, 1.0],
+    [0.9, 0.4, 0.85, 1.0],
+];
+
+/// The unit vector pointing from a side's edge into the arena.
+fn side_normal(side: usize) -> Vec2 {
+    match side {
+        0 => Vec2::new(0.0, -1.0),
+        1 => Vec2::new(0.0, 1.0),
+        2 => Vec2::new(1.0, 0.0),
+        _ => Vec2::new(-1.0, 0.0),
+    }
+}
+
+/// Whether a side's paddle slides along the x axis.
+fn is_horizontal(side: usize) -> bool {
+    side < 2
+}
+
+/// A paddle guarding one edge of the arena.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Paddle {
+    /// The middle of the paddle along its edge.
+    pos: f32,
+    lives: u32,
+    human: bool,
+    /// How far off the AI is aiming, picked fresh after each hit.
+    aim_error: f32,
+}
+
+/// A ball in play, or waiting in the middle to be served.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Ball {
+    pos: Vec2,
+    vel: Vec2,
+    /// Seconds before the ball is served.
+    serve: Option<f32>,
+}
+
+impl Ball {
+    fn new() -> Self {
+        Ball {
+            pos: Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0),
+            vel: Vec2::ZERO,
+            serve: Some(SERVE_DELAY),
+        }
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    input: Controllers,
+    /// How many sides are played by people, the rest being AI.
+    humans: usize,
+    chaos: bool,
+    /// Whether the player is still picking the settings.
+    menu: bool,
+    paddles: [Paddle; MAX_PLAYERS],
+    balls: Vec<Ball>,
+    /// Sides in the order they were knocked out.
+    eliminated: Vec<usize>,
+    /// Rounds won by each side this session.
+    wins: [u32; MAX_PLAYERS],
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state on the settings menu.
+    pub fn new() -> Self {
+        let paddle = Paddle {
+            pos: 0.0,
+            lives: START_LIVES,
+            human: false,
+            aim_error: 0.0,
+        };
+        GameState {
+            input: Controllers::new(),
+            humans: 1,
+            chaos: false,
+            menu: true,
+            paddles: [paddle; MAX_PLAYERS],
+            balls: Vec::new(),
+            eliminated: Vec::new(),
+            wins: [0; MAX_PLAYERS],
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start_round(&mut self) {
+        for (side, paddle) in self.paddles.iter_mut().enumerate() {
+            *paddle = Paddle {
+                pos: if is_horizontal(side) {
+                    SCREEN_SIZE.0 / 2.0
+                } else {
+                    SCREEN_SIZE.1 / 2.0
+                },
+                lives: START_LIVES,
+                human: side < self.humans,
+                aim_error: 0.0,
+            };
+        }
+        let count = if self.chaos { CHAOS_BALLS } else { 1 };
+        self.balls = (0..count).map(|_| Ball::new()).collect();
+        // Stagger the chaos serves so the balls don't travel as a pack.
+        for (i, ball) in self.balls.iter_mut().enumerate() {
+            ball.serve = Some(SERVE_DELAY * (1.0 + i as f32 * 0.5));
+        }
+        self.eliminated.clear();
+        self.menu = false;
+        self.gameover = false;
+    }
+
+    fn alive(&self, side: usize) -> bool {
+        self.paddles[side].lives > 0
+    }
+
+    fn paddle_rect(&self, side: usize) -> Rect {
+        let pos = self.paddles[side].pos - PADDLE_LENGTH / 2.0;
+        match side {
+            0 => Rect::new(
+                pos,
+                SCREEN_SIZE.1 - PADDLE_MARGIN - PADDLE_THICKNESS,
+                PADDLE_LENGTH,
+                PADDLE_THICKNESS,
+            ),
+            1 => Rect::new(pos, PADDLE_MARGIN, PADDLE_LENGTH, PADDLE_THICKNESS),
+            2 => Rect::new(PADDLE_MARGIN, pos, PADDLE_THICKNESS, PADDLE_LENGTH),
+            _ => Rect::new(
+                SCREEN_SIZE.0 - PADDLE_MARGIN - PADDLE_THICKNESS,
+                pos,
+                PADDLE_THICKNESS,
+                PADDLE_LENGTH,
+            ),
+        }
+    }
+
+    fn corner_rects() -> [Rect; 4] {
+        let (w, h) = SCREEN_SIZE;
+        [
+            Rect::new(0.0, 0.0, CORNER, CORNER),
+            Rect::new(w - CORNER, 0.0, CORNER, CORNER),
+            Rect::new(0.0, h - CORNER, CORNER, CORNER),
+            Rect::new(w - CORNER, h - CORNER, CORNER, CORNER),
+        ]
+    }
+
+    /// The side whose edge a point has gone past, if any.
+    fn past_edge(pos: Vec2) -> Option<usize> {
+        if pos.y > SCREEN_SIZE.1 + BALL_RADIUS {
+            Some(0)
+        } else if pos.y < -BALL_RADIUS {
+            Some(1)
+        } else if pos.x < -BALL_RADIUS {
+            Some(2)
+        } else if pos.x > SCREEN_SIZE.0 + BALL_RADIUS {
+            Some(3)
+        } else {
+            None
+        }
+    }
+
+    /// Serves a ball from the middle towards a random side still in the round.
+    fn serve(&self, ball: &mut Ball) {
+        let mut rng = rand::thread_rng();
+        let alive: Vec<usize> = (0..MAX_PLAYERS).filter(|&side| self.alive(side)).collect();
+        let side = alive[rng.gen_range(0, alive.len())];
+        let angle = rng.gen_range(-0.5, 0.5);
+        let toward = -side_normal(side);
+        ball.vel = toward.rotate(angle) * START_SPEED;
+        ball.serve = None;
+    }
+
+    /// Moves the paddles, from the controls for people and by predicting the balls for the AI.
+    fn move_paddles(&mut self, ctx: &Context, dt: f32) {
+        for side in 0..MAX_PLAYERS {
+            let paddle = self.paddles[side];
+            if paddle.lives == 0 {
+                continue;
+            }
+
+            let delta = if paddle.human {
+                let (dx, dy) = self.input.direction(ctx, side);
+                let amount = if is_horizontal(side) { dx } else { dy };
+                amount * PADDLE_SPEED * dt
+            } else {
+                let target = self.ai_target(side) + paddle.aim_error;
+                let max = AI_SPEED * dt;
+                (target - paddle.pos).max(-max).min(max)
+            };
+
+            let length = if is_horizontal(side) {
+                SCREEN_SIZE.0
+            } else {
+                SCREEN_SIZE.1
+            };
+            let half = PADDLE_LENGTH / 2.0;
+            self.paddles[side].pos = (paddle.pos + delta)
+                .max(CORNER + half)
+                .min(length - CORNER - half);
+        }
+    }
+
+    /// Where along its edge an AI side wants its paddle, following the ball due to reach it soonest.
+    fn ai_target(&self, side: usize) -> f32 {
+        let normal = side_normal(side);
+        let edge = if is_horizontal(side) {
+            SCREEN_SIZE.1
+        } else {
+            SCREEN_SIZE.0
+        };
+        let middle = if is_horizontal(side) {
+            SCREEN_SIZE.0 / 2.0
+        } else {
+            SCREEN_SIZE.1 / 2.0
+        };
+
+        let soonest = self
+            .balls
+            .iter()
+            .filter(|ball| ball.serve.is_none())
+            .filter_map(|ball| {
+                let approach = -ball.vel.dot(normal);
+                if approach <= 0.0 {
+                    return None;
+                }
+                // The distance left to the side's edge.
+                let distance = match side {
+                    0 => edge - ball.pos.y,
+                    1 => ball.pos.y,
+                    2 => ball.pos.x,
+                    _ => edge - ball.pos.x,
+                };
+                let time = distance / approach;
+                let arrival = ball.pos + ball.vel * time;
+                let along = if is_horizontal(side) {
+                    arrival.x
+                } else {
+                    arrival.y
+                };
+                Some((time, along))
+            })
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        soonest.map_or(middle, |(_, along)| along)
+    }
+
+    /// Bounces a ball off the corner blocks, the walls left by knocked out sides and the paddles.
+    fn collide(&mut self, ball: &mut Ball) {
+        for rect in GameState::corner_rects().iter() {
+            let closest = Vec2::new(
+                ball.pos.x.max(rect.x).min(rect.x + rect.w),
+                ball.pos.y.max(rect.y).min(rect.y + rect.h),
+            );
+            let offset = ball.pos - closest;
+            if offset.length_squared() < BALL_RADIUS * BALL_RADIUS {
+                if offset.x.abs() > offset.y.abs() {
+                    ball.vel.x = offset.x.signum() * ball.vel.x.abs();
+                } else {
+                    ball.vel.y = offset.y.signum() * ball.vel.y.abs();
+                }
+            }
+        }
+
+        for side in 0..MAX_PLAYERS {
+            let normal = side_normal(side);
+            // Only bounce balls heading out through this side.
+            if ball.vel.dot(normal) >= 0.0 {
+                continue;
+            }
+
+            if !self.alive(side) {
+                let out = match side {
+                    0 => ball.pos.y > SCREEN_SIZE.1 - BALL_RADIUS,
+                    1 => ball.pos.y < BALL_RADIUS,
+                    2 => ball.pos.x < BALL_RADIUS,
+                    _ => ball.pos.x > SCREEN_SIZE.0 - BALL_RADIUS,
+                };
+                if out {
+                    ball.vel -= normal * (2.0 * ball.vel.dot(normal));
+                }
+                continue;
+            }
+
+            let rect = self.paddle_rect(side);
+            let closest = Vec2::new(
+                ball.pos.x.max(rect.x).min(rect.x + rect.w),
+                ball.pos.y.max(rect.y).min(rect.y + rect.h),
+            );
+            if (ball.pos - closest).length_squared() >= BALL_RADIUS * BALL_RADIUS {
+                continue;
+            }
+
+            let tangent = normal.perp();
+            let center = Vec2::new(rect.x + rect.w / 2.0, rect.y + rect.h / 2.0);
+            let offset = ((ball.pos - center).dot(tangent) / (PADDLE_LENGTH / 2.0))
+                .max(-1.0)
+                .min(1.0);
+            let angle = offset * MAX_BOUNCE_ANGLE;
+            let speed = (ball.vel.length() + SPEEDUP).min(MAX_SPEED);
+            ball.vel = (normal * angle.cos() + tangent * angle.sin()) * speed;
+
+            let mut rng = rand::thread_rng();
+            for paddle in self.paddles.iter_mut().filter(|paddle| !paddle.human) {
+                paddle.aim_error = rng.gen_range(-AI_ERROR, AI_ERROR);
+            }
+        }
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        self.move_paddles(ctx, dt);
+
+        let mut balls = std::mem::replace(&mut self.balls, Vec::new());
+        let mut lost = Vec::new();
+        for ball in balls.iter_mut() {
+            if let Some(serve) = ball.serve {
+                let serve = serve - dt;
+                ball.serve = Some(serve);
+                if serve <= 0.0 {
+                    self.serve(ball);
+                }
+                continue;
+            }
+
+            // Move in steps shorter than the ball so it can't pass through a paddle.
+            let travel = ball.vel.length() * dt;
+            let steps = (travel / BALL_RADIUS).ceil().max(1.0) as usize;
+            let step_dt = dt / steps as f32;
+            for _ in 0..steps {
+                ball.pos += ball.vel * step_dt;
+                self.collide(ball);
+            }
+
+            if let Some(side) = GameState::past_edge(ball.pos) {
+                lost.push(side);
+                *ball = Ball::new();
+            }
+        }
+        self.balls = balls;
+
+        for side in lost {
+            let paddle = &mut self.paddles[side];
+            if paddle.lives == 0 {
+                continue;
+            }
+            paddle.lives -= 1;
+            if paddle.lives == 0 {
+                self.eliminated.push(side);
+            }
+        }
+
+        let remaining: Vec<usize> = (0..MAX_PLAYERS).filter(|&side| self.alive(side)).collect();
+        if remaining.len() <= 1 {
+            self.end_round(remaining.first().cloned());
+        }
+    }
+
+    fn end_round(&mut self, winner: Option<usize>) {
+        self.gameover = true;
+        if let Some(winner) = winner {
+            self.wins[winner] += 1;
+        }
+
+        // Player one's finishing place, counting back from the last side knocked out.
+        let place = if winner == Some(0) {
+            1
+        } else {
+            let knocked_out = self
+                .eliminated
+                .iter()
+                .position(|&side| side == 0)
+                .unwrap_or(0);
+            MAX_PLAYERS - knocked_out
+        };
+        let values = [
+            ("place", place as f64),
+            ("won", if place == 1 { 1.0 } else { 0.0 }),
+            ("humans", self.humans as f64),
+            ("chaos", if self.chaos { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("quadpong", &values);
+        }
+        let _ = achievements::check_run("quadpong", &values);
+    }
+
+    /// The side that won the last round.
+    fn winner(&self) -> Option<usize> {
+        (0..MAX_PLAYERS).find(|&side| self.alive(side))
+    }
+
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut builder = graphics::MeshBuilder::new();
+        let wall = [0.35, 0.35, 0.4, 1.0];
+
+        for rect in GameState::corner_rects().iter() {
+            builder.rectangle(DrawMode::fill(), *rect, wall.into());
+        }
+
+        for side in 0..MAX_PLAYERS {
+            if self.alive(side) {
+                builder.rectangle(
+                    DrawMode::fill(),
+                    self.paddle_rect(side),
+                    SIDE_COLORS[side].into(),
+                );
+                continue;
+            }
+
+            // A knocked out side is walled off.
+            let (w, h) = SCREEN_SIZE;
+            let rect = match side {
+                0 => Rect::new(CORNER, h - 4.0, w - 2.0 * CORNER, 4.0),
+                1 => Rect::new(CORNER, 0.0, w - 2.0 * CORNER, 4.0),
+                2 => Rect::new(0.0, CORNER, 4.0, h - 2.0 * CORNER),
+                _ => Rect::new(w - 4.0, CORNER, 4.0, h - 2.0 * CORNER),
+            };
+            builder.rectangle(DrawMode::fill(), rect, wall.into());
+        }
+
+        for ball in self.balls.iter() {
+            let color = if ball.serve.is_some() {
+                [0.6, 0.6, 0.6, 1.0]
+            } else {
+                [1.0, 1.0, 1.0, 1.0]
+            };
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: ball.pos.x,
+                    y: ball.pos.y,
+                },
+                BALL_RADIUS,
+                0.5,
+                color.into(),
+            );
+        }
+
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_menu(&self, ctx: &mut Context) -> GameResult<()> {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        self.draw_text(ctx, "QUAD PONG", white, 48.0, 220.0, 120.0)?;
+        self.draw_text(
+            ctx,
+            &format!("Players: {}  (1-4)", self.humans),
+            white,
+            26.0,
+            150.0,
+            210.0,
+        )?;
+        let chaos = if self.chaos { "on" } else { "off" };
+        self.draw_text(
+            ctx,
+            &format!("Multi-ball chaos: {}  (C)", chaos),
+            white,
+            26.0,
+            150.0,
+            250.0,
+        )?;
+
+        for side in 0..MAX_PLAYERS {
+            let controls = if side < self.humans {
+                self.input.describe(side)
+            } else {
+                "AI".to_string()
+            };
+            self.draw_text(
+                ctx,
+                &format!("{}: {}", SIDE_NAMES[side], controls),
+                SIDE_COLORS[side],
+                22.0,
+                150.0,
+                320.0 + side as f32 * 32.0,
+            )?;
+        }
+
+        self.draw_text(
+            ctx,
+            "Press Enter or Start to play.",
+            [1.0, 0.8, 0.3, 1.0],
+            26.0,
+            150.0,
+            480.0,
+        )
+    }
+
+    fn draw_hud(&self, ctx: &mut Context) -> GameResult<()> {
+        for side in 0..MAX_PLAYERS {
+            let label = format!(
+                "{}: {} lives  {} wins",
+                SIDE_NAMES[side], self.paddles[side].lives, self.wins[side]
+            );
+            self.draw_text(
+                ctx,
+                &label,
+                SIDE_COLORS[side],
+                18.0,
+                CORNER + 40.0,
+                CORNER + 40.0 + side as f32 * 22.0,
+            )?;
+        }
+
+        if self.gameover {
+            let message = match self.winner() {
+                Some(side) => format!("{} wins the round!", SIDE_NAMES[side]),
+                None => "Nobody is left standing!".to_string(),
+            };
+            self.draw_text(
+                ctx,
+                &message,
+                [1.0, 0.8, 0.3, 1.0],
+                32.0,
+                CORNER + 40.0,
+                SCREEN_SIZE.1 / 2.0,
+            )?;
+            self.draw_text(
+                ctx,
+                "Enter: next round  M: menu",
+                [1.0, 1.0, 1.0, 1.0],
+                22.0,
+                CORNER + 40.0,
+                SCREEN_SIZE.1 / 2.0 + 44.0,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+        self.input.update(ctx);
+
+        if !self.menu && !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.08, 1.0].into());
+
+        if self.menu {
+            self.draw_menu(ctx)?;
+        } else {
+            self.draw_world(ctx)?;
+            spectator::world_drawn(ctx)?;
+            self.draw_hud(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        } else if self.menu {
+            match keycode {
+                KeyCode::Key1 => self.humans = 1,
+                KeyCode::Key2 => self.humans = 2,
+                KeyCode::Key3 => self.humans = 3,
+                KeyCode::Key4 => self.humans = 4,
+                KeyCode::C => self.chaos = !self.chaos,
+                KeyCode::Return => self.start_round(),
+                _ => (),
+            }
+        } else if self.gameover {
+            match keycode {
+                KeyCode::Return => self.start_round(),
+                KeyCode::M => self.menu = true,
+                _ => (),
+            }
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, true);
+        if btn == Button::Start && (self.menu || self.gameover) {
+            self.start_round();
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.input.gamepad_button(btn, id, false);
+    }
+
+    fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, id: GamepadId) {
+        self.input.gamepad_axis(axis, value, id);
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("quadpong", "Sprial404")
+        .window_setup(window::setup("Quad Pong"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
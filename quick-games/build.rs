@@ -0,0 +1,3 @@
+fn main() {
+    tonic_build::compile_protos("../proto/bot.proto").expect("couldn't build the bot service");
+}
@@ -0,0 +1,306 @@
+//! `quick-games bots`, a gRPC server on localhost for bots to play the games from any language,
+//! with the `Bot` service in `proto/bot.proto`.
+//!
+//! A bot starts a session with `ObserveState`, and is sent the game once a tick. It answers each
+//! tick through `SubmitAction`, naming the session and the tick. Each game is played on a thread
+//! of its own with `headless::play_against_clock`, so a bot that doesn't answer before the
+//! tick's deadline forfeits, and is sent the final state with `OUTCOME_FORFEIT`. So does a bot
+//! that stops reading its observations, once they've backed up and the next can't be sent
+//! within the deadline either. `SubmitAction` returns the summary once the game has ended,
+//! however it ended.
+//!
+//! Only the games in `headless` can be played, as the others only run in their windows.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::watch;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use snake::GridPosition;
+
+use crate::headless::{self, Ending};
+
+// The generated code isn't written to pass clippy.
+#[allow(clippy::all)]
+mod proto {
+    tonic::include_proto!("quick_games.bot");
+}
+
+use proto::bot_server::{Bot, BotServer};
+use proto::{action, observation};
+use proto::{Action, Observation, ObserveRequest, Outcome, Position, SnakeState, Summary};
+
+/// The port used when no `--port` is given.
+pub const DEFAULT_PORT: u16 = 50051;
+
+/// How long a bot has to answer each tick if it doesn't ask for a deadline.
+const DEFAULT_DEADLINE: Duration = Duration::from_millis(100);
+/// The longest a bot can ask to be given, so a game with a bot that's stopped answering ends.
+const MAX_DEADLINE: Duration = Duration::from_secs(5);
+
+/// How many observations are sent ahead of a bot reading them.
+const OBSERVATIONS_BUFFERED: usize = 4;
+
+/// How long to wait before trying again to send an observation when the buffer is full.
+const SEND_RETRY: Duration = Duration::from_millis(1);
+
+/// A game being played by a bot.
+struct Session {
+    /// Where the bot's answers go, as the tick and the way to turn.
+    answers: mpsc::Sender<(u64, Option<snake::Direction>)>,
+    /// The summary, once the game has ended.
+    summary: watch::Receiver<Option<Summary>>,
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Session>>>;
+
+#[derive(Default)]
+struct Service {
+    sessions: Sessions,
+    /// The number given to the next session.
+    next: AtomicUsize,
+}
+
+fn position(pos: GridPosition) -> Position {
+    Position {
+        x: i32::from(pos.x),
+        y: i32::from(pos.y),
+    }
+}
+
+fn heading(direction: snake::Direction) -> proto::Direction {
+    match direction {
+        snake::Direction::Up => proto::Direction::Up,
+        snake::Direction::Down => proto::Direction::Down,
+        snake::Direction::Left => proto::Direction::Left,
+        snake::Direction::Right => proto::Direction::Right,
+    }
+}
+
+/// The way an action turns the snake, if it does.
+fn turn(action: &Action) -> Option<snake::Direction> {
+    let turn = match &action.game {
+        Some(action::Game::Snake(snake)) => proto::Direction::from_i32(snake.turn)?,
+        None => return None,
+    };
+    match turn {
+        proto::Direction::Up => Some(snake::Direction::Up),
+        proto::Direction::Down => Some(snake::Direction::Down),
+        proto::Direction::Left => Some(snake::Direction::Left),
+        proto::Direction::Right => Some(snake::Direction::Right),
+        proto::Direction::Unspecified => None,
+    }
+}
+
+fn observation_of(
+    session: &str,
+    snake: &headless::Snake,
+    deadline: Duration,
+    outcome: Outcome,
+) -> Observation {
+    let sim = snake.sim();
+    let body = std::iter::once(&sim.snake.head)
+        .chain(sim.snake.body.iter())
+        .map(|segment| position(segment.pos))
+        .collect();
+    Observation {
+        session: session.to_string(),
+        tick: snake.ticks(),
+        deadline_ms: deadline.as_millis() as u32,
+        outcome: outcome as i32,
+        score: snake.length() as f64,
+        game: Some(observation::Game::Snake(SnakeState {
+            width: i32::from(snake::GRID_SIZE.0),
+            height: i32::from(snake::GRID_SIZE.1),
+            body,
+            direction: heading(sim.snake.dir) as i32,
+            food: Some(position(sim.food.pos)),
+            wraps: sim.wrap,
+        })),
+    }
+}
+
+/// Sends an observation, giving up if the bot hasn't read enough of the earlier ones to make
+/// room for it within `deadline`, or has gone. Returns whether it was sent.
+fn send_within(
+    observations: &tokio::sync::mpsc::Sender<Result<Observation, Status>>,
+    observation: Observation,
+    deadline: Duration,
+) -> bool {
+    let due = Instant::now() + deadline;
+    let mut observation = Ok(observation);
+    loop {
+        match observations.try_send(observation) {
+            Ok(()) => return true,
+            Err(TrySendError::Full(unsent)) if Instant::now() < due => {
+                observation = unsent;
+                thread::sleep(SEND_RETRY);
+            }
+            Err(_) => return false,
+        }
+    }
+}
+
+fn invalid(message: &str) -> Status {
+    Status::invalid_argument(message)
+}
+
+#[tonic::async_trait]
+impl Bot for Service {
+    type ObserveStateStream = ReceiverStream<Result<Observation, Status>>;
+
+    async fn observe_state(
+        &self,
+        request: Request<ObserveRequest>,
+    ) -> Result<Response<Self::ObserveStateStream>, Status> {
+        let request = request.into_inner();
+        if !headless::GAMES.contains(&request.game.as_str()) {
+            return Err(invalid(&format!(
+                "There's no game called `{}` that bots can play. Try {}.",
+                request.game,
+                headless::GAMES.join(" or ")
+            )));
+        }
+        let seed = match request.options.get("seed") {
+            Some(seed) => seed
+                .parse()
+                .map_err(|_| invalid("The seed isn't a number."))?,
+            None => rand::random(),
+        };
+        let wrap = match request.options.get("wrap") {
+            Some(wrap) => wrap
+                .parse()
+                .map_err(|_| invalid("wrap is either true or false."))?,
+            None => true,
+        };
+        let deadline = match request.tick_deadline_ms {
+            0 => DEFAULT_DEADLINE,
+            ms => Duration::from_millis(u64::from(ms)).min(MAX_DEADLINE),
+        };
+
+        let id = (self.next.fetch_add(1, Ordering::Relaxed) + 1).to_string();
+        let (answers, answered) = mpsc::channel();
+        let (summary_sender, summary) = watch::channel(None);
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(id.clone(), Session { answers, summary });
+
+        let (observations, stream) = tokio::sync::mpsc::channel(OBSERVATIONS_BUFFERED);
+        let sessions = Arc::clone(&self.sessions);
+        thread::spawn(move || {
+            let mut snake = headless::Snake::new(seed, wrap);
+            let ending = headless::play_against_clock(&mut snake, deadline, &answered, |snake| {
+                let observation = observation_of(&id, snake, deadline, Outcome::Unspecified);
+                send_within(&observations, observation, deadline)
+            });
+            let outcome = match ending {
+                // Snake can't be won, only lasted in.
+                Ending::Over => Outcome::Lost,
+                Ending::Forfeit => Outcome::Forfeit,
+            };
+            let last = observation_of(&id, &snake, deadline, outcome);
+            send_within(&observations, last, deadline);
+            let _ = summary_sender.send(Some(Summary {
+                session: id.clone(),
+                outcome: outcome as i32,
+                score: snake.length() as f64,
+                ticks: snake.ticks(),
+            }));
+            sessions.lock().unwrap().remove(&id);
+        });
+        Ok(Response::new(ReceiverStream::new(stream)))
+    }
+
+    async fn submit_action(
+        &self,
+        request: Request<Streaming<Action>>,
+    ) -> Result<Response<Summary>, Status> {
+        let mut actions = request.into_inner();
+        let first = actions
+            .message()
+            .await?
+            .ok_or_else(|| invalid("No actions were sent."))?;
+        let id = first.session.clone();
+        let (answers, mut summary) = {
+            let sessions = self.sessions.lock().unwrap();
+            let session = sessions
+                .get(&id)
+                .ok_or_else(|| Status::not_found(format!("There's no session {}.", id)))?;
+            (session.answers.clone(), session.summary.clone())
+        };
+
+        let mut next = Some(first);
+        // Whether the bot has stopped sending actions. The game carries on until it forfeits.
+        let mut finished_sending = false;
+        loop {
+            if let Some(action) = next.take() {
+                if action.session == id {
+                    // The game only stops listening once it's ended, which the summary says.
+                    let _ = answers.send((action.tick, turn(&action)));
+                }
+            }
+            let ended = summary.borrow().clone();
+            if let Some(summary) = ended {
+                return Ok(Response::new(summary));
+            }
+            tokio::select! {
+                action = actions.message(), if !finished_sending => match action? {
+                    Some(action) => next = Some(action),
+                    None => finished_sending = true,
+                },
+                changed = summary.changed() => {
+                    if changed.is_err() {
+                        return Err(Status::internal("The game stopped without a summary."));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Serves bots until the process is stopped.
+pub fn serve(port: u16) -> Result<(), String> {
+    let runtime =
+        tokio::runtime::Runtime::new().map_err(|e| format!("Couldn't start the server: {}", e))?;
+    let address = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Serving bots on {}", address);
+    runtime
+        .block_on(
+            Server::builder()
+                .add_service(BotServer::new(Service::default()))
+                .serve(address),
+        )
+        .map_err(|e| format!("Couldn't serve bots on port {}: {}", port, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn observations_are_given_up_on_once_the_bot_stops_reading() {
+        let (observations, mut stream) = tokio::sync::mpsc::channel(1);
+        let deadline = Duration::from_millis(20);
+        let send = || send_within(&observations, Observation::default(), deadline);
+        assert!(send());
+
+        let started = Instant::now();
+        assert!(!send());
+        assert!(started.elapsed() >= deadline);
+
+        assert!(stream.blocking_recv().is_some());
+        assert!(send());
+        drop(stream);
+        assert!(!send());
+    }
+}
@@ -0,0 +1,226 @@
+//! Completion scripts for bash, zsh and fish, printed by `quick-games completions <shell>`.
+//!
+//! The scripts complete the commands and options themselves, and ask `quick-games __complete`
+//! for the games, each game's modes and the profiles, so they stay up to date as games are
+//! added without being generated again.
+
+const BASH: &str = r#"# bash completion for quick-games
+
+# Finds the command and game typed so far, skipping options and their values.
+_quick_games_positional() {
+    _quick_games_command=""
+    _quick_games_game=""
+    local i=1 word
+    while [ "$i" -lt "$COMP_CWORD" ]; do
+        word="${COMP_WORDS[i]}"
+        case "$word" in
+            --*=*) ;;
+            --*) i=$((i + 1)) ;;
+            *)
+                if [ -z "$_quick_games_command" ]; then
+                    _quick_games_command="$word"
+                elif [ -z "$_quick_games_game" ]; then
+                    _quick_games_game="$word"
+                fi
+                ;;
+        esac
+        i=$((i + 1))
+    done
+}
+
+_quick_games() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD - 1]}"
+    _quick_games_positional
+
+    case "$prev" in
+        --profile)
+            COMPREPLY=($(compgen -W "$(quick-games __complete profiles)" -- "$cur"))
+            return
+            ;;
+        --mode)
+            COMPREPLY=($(compgen -W "$(quick-games __complete modes "$_quick_games_game")" -- "$cur"))
+            return
+            ;;
+        --output | --maze | --edit | --record)
+            COMPREPLY=($(compgen -f -- "$cur"))
+            return
+            ;;
+        --layout)
+            COMPREPLY=($(compgen -W "scatter maze rooms" -- "$cur"))
+            return
+            ;;
+        --seed | --speed | --difficulty | --port | --score)
+            return
+            ;;
+    esac
+
+    if [[ "$cur" == --* ]]; then
+        COMPREPLY=($(compgen -W "--profile --mode --seed --speed --maze --layout --difficulty --edit --record --score --start-length --invincible --level --no-spawn --console --output --script --port --spectator" -- "$cur"))
+        return
+    fi
+
+    if [ -n "$_quick_games_game" ]; then
+        return
+    fi
+    case "$_quick_games_command" in
+        "")
+            COMPREPLY=($(compgen -W "list play scores serve bots tray export import verify completions help" -- "$cur"))
+            ;;
+        play | scores)
+            COMPREPLY=($(compgen -W "$(quick-games __complete games)" -- "$cur"))
+            ;;
+        export)
+            COMPREPLY=($(compgen -W "daily $(quick-games __complete games)" -- "$cur"))
+            ;;
+        import | verify)
+            COMPREPLY=($(compgen -f -- "$cur"))
+            ;;
+        completions)
+            COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur"))
+            ;;
+    esac
+}
+
+complete -F _quick_games quick-games
+"#;
+
+const ZSH: &str = r#"#compdef quick-games
+
+_quick_games() {
+    local curcontext="$curcontext" state line
+    typeset -A opt_args
+
+    _arguments -C \
+        '--profile[use a profile other than the default]:profile:->profiles' \
+        '--mode[start the game in a mode]:mode:->modes' \
+        '--seed[start the game with a fixed seed]:seed:' \
+        '--speed[moves a second, for snake]:speed:' \
+        '--maze[play snake in a maze from a level file]:file:_files' \
+        '--layout[play snake in a generated layout]:layout:(scatter maze rooms)' \
+        '--difficulty[how hard a generated layout is, from 0 to 1]:difficulty:' \
+        '--edit[open snake'"'"'s maze editor on a level file]:file:_files' \
+        '--record[write a replay of each game of snake]:file:_files' \
+        '--score[the score a replay should come to, for verify]:score:' \
+        '--start-length[start snake this long, in debug builds]:length:' \
+        '--invincible[don'"'"'t die, in debug builds]' \
+        '--level[start on this level, in debug builds]:level:' \
+        '--no-spawn[don'"'"'t spawn food or monsters, in debug builds]' \
+        '--console[allow the developer console]' \
+        '--output[write a summary of the session as JSON]:file:_files' \
+        '--port[the port for serve or bots]:port:' \
+        '--script[print the last run'"'"'s score and exit with whether it was won]' \
+        '--spectator[open a spectator window without the HUD]' \
+        '1:command:((list\:"list the games" play\:"start a game" scores\:"print scores" serve\:"serve games over HTTP" bots\:"let bots play over gRPC" tray\:"put the games in the system tray" export\:"print a share code" import\:"install a shared level" verify\:"check a run or replay" completions\:"print a completion script" help\:"show the usage"))' \
+        '2:argument:->argument'
+
+    case $state in
+        profiles)
+            compadd -- ${(f)"$(quick-games __complete profiles)"}
+            ;;
+        modes)
+            compadd -- ${(f)"$(quick-games __complete modes ${line[2]})"}
+            ;;
+        argument)
+            case $line[1] in
+                play | scores)
+                    compadd -- ${(f)"$(quick-games __complete games)"}
+                    ;;
+                export)
+                    compadd daily ${(f)"$(quick-games __complete games)"}
+                    ;;
+                import | verify)
+                    _files
+                    ;;
+                completions)
+                    compadd bash zsh fish
+                    ;;
+            esac
+            ;;
+    esac
+}
+
+_quick_games "$@"
+"#;
+
+const FISH: &str = r#"# fish completion for quick-games
+
+# Prints the command and game typed so far, skipping options and their values.
+function __quick_games_positional
+    set -l skip 0
+    for word in (commandline -opc)[2..-1]
+        if test $skip = 1
+            set skip 0
+        else if string match -q -- '--*=*' $word
+            continue
+        else if string match -q -- '--*' $word
+            set skip 1
+        else
+            echo $word
+        end
+    end
+end
+
+function __quick_games_wants_command
+    test (count (__quick_games_positional)) -eq 0
+end
+
+function __quick_games_wants
+    set -l positional (__quick_games_positional)
+    test (count $positional) -eq 1; and contains -- $positional[1] $argv
+end
+
+function __quick_games_modes
+    set -l positional (__quick_games_positional)
+    quick-games __complete modes $positional[2]
+end
+
+complete -c quick-games -f
+complete -c quick-games -n __quick_games_wants_command -a list -d 'List the games'
+complete -c quick-games -n __quick_games_wants_command -a play -d 'Start a game'
+complete -c quick-games -n __quick_games_wants_command -a scores -d 'Print scores'
+complete -c quick-games -n __quick_games_wants_command -a serve -d 'Serve games over HTTP'
+complete -c quick-games -n __quick_games_wants_command -a bots -d 'Let bots play over gRPC'
+complete -c quick-games -n __quick_games_wants_command -a tray -d 'Put the games in the system tray'
+complete -c quick-games -n __quick_games_wants_command -a export -d 'Print a share code'
+complete -c quick-games -n __quick_games_wants_command -a import -d 'Install a shared level'
+complete -c quick-games -n __quick_games_wants_command -a verify -d 'Check a run or replay'
+complete -c quick-games -n __quick_games_wants_command -a completions -d 'Print a completion script'
+complete -c quick-games -n __quick_games_wants_command -a help -d 'Show the usage'
+complete -c quick-games -n '__quick_games_wants play scores' -a '(quick-games __complete games)'
+complete -c quick-games -n '__quick_games_wants completions' -a 'bash zsh fish'
+complete -c quick-games -n '__quick_games_wants export' -a 'daily (quick-games __complete games)'
+complete -c quick-games -n '__quick_games_wants import verify' -F
+complete -c quick-games -l profile -x -a '(quick-games __complete profiles)' -d 'Use a profile other than the default'
+complete -c quick-games -l mode -x -a '(__quick_games_modes)' -d 'Start the game in a mode'
+complete -c quick-games -l seed -x -d 'Start the game with a fixed seed'
+complete -c quick-games -l speed -x -d 'Moves a second, for snake'
+complete -c quick-games -l maze -r -F -d 'Play snake in a maze from a level file'
+complete -c quick-games -l layout -x -a 'scatter maze rooms' -d 'Play snake in a generated layout'
+complete -c quick-games -l difficulty -x -d 'How hard a generated layout is, from 0 to 1'
+complete -c quick-games -l edit -r -F -d "Open snake's maze editor on a level file"
+complete -c quick-games -l record -r -F -d 'Write a replay of each game of snake'
+complete -c quick-games -l score -x -d 'The score a replay should come to, for verify'
+complete -c quick-games -l start-length -x -d 'Start snake this long, in debug builds'
+complete -c quick-games -l invincible -d "Don't die, in debug builds"
+complete -c quick-games -l level -x -d 'Start on this level, in debug builds'
+complete -c quick-games -l no-spawn -d "Don't spawn food or monsters, in debug builds"
+complete -c quick-games -l console -d 'Allow the developer console'
+complete -c quick-games -l port -x -d 'The port for serve or bots'
+complete -c quick-games -l output -r -F -d 'Write a summary of the session as JSON'
+complete -c quick-games -l script -d "Print the last run's score and exit with whether it was won"
+complete -c quick-games -l spectator -d 'Open a spectator window without the HUD'
+"#;
+
+/// The shells there are completion scripts for.
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// The completion script for a shell, by its name.
+pub fn script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH),
+        "zsh" => Some(ZSH),
+        "fish" => Some(FISH),
+        _ => None,
+    }
+}
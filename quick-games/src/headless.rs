@@ -0,0 +1,140 @@
+//! Games played without a window, stepped by whatever is driving them rather than by a clock,
+//! for `serve` and `bots`.
+//!
+//! Only snake's rules run without a window so far, in the `snake` library, so it's the only
+//! game that can be played this way. The other games are played in their windows.
+
+use std::iter;
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use common::json::quote;
+use snake::{Direction, GridPosition, Simulation, GRID_SIZE};
+
+/// The games that can be played without a window.
+pub const GAMES: &[&str] = &["snake"];
+
+/// The most moves one input can step a game, so one request can't hold the rest up for long.
+pub const MAX_STEPS: u32 = 1000;
+
+fn point(pos: GridPosition) -> String {
+    format!("[{}, {}]", pos.x, pos.y)
+}
+
+/// A game of snake without a window. The same seed and inputs always play out the same way.
+pub struct Snake {
+    sim: Simulation,
+    seed: u64,
+    /// How many times the snake has moved.
+    ticks: u64,
+}
+
+impl Snake {
+    pub fn new(seed: u64, wrap: bool) -> Self {
+        Snake {
+            sim: Simulation::new(StdRng::seed_from_u64(seed), wrap),
+            seed,
+            ticks: 0,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn over(&self) -> bool {
+        self.sim.over
+    }
+
+    pub fn length(&self) -> usize {
+        self.sim.snake.length()
+    }
+
+    /// The game's rules, for reading its state.
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    /// Turns the snake if given a direction, then moves it `steps` times, stopping early if the
+    /// game ends. Turning back on itself is ignored, as it is from the keyboard.
+    pub fn play(&mut self, turn: Option<Direction>, steps: u32) {
+        if let Some(turn) = turn {
+            self.sim.snake.turn(turn);
+        }
+        for _ in 0..steps.min(MAX_STEPS) {
+            if self.sim.over {
+                break;
+            }
+            self.sim.step();
+            self.ticks += 1;
+        }
+    }
+
+    /// The game as it stands: the board's size, the snake from head to tail, the way it's
+    /// heading, the food, and whether the game is over.
+    pub fn to_json(&self) -> String {
+        let snake = &self.sim.snake;
+        let body: Vec<String> = iter::once(&snake.head)
+            .chain(snake.body.iter())
+            .map(|segment| point(segment.pos))
+            .collect();
+        format!(
+            "{{\"width\": {}, \"height\": {}, \"body\": [{}], \"direction\": {}, \"food\": {}, \
+             \"wraps\": {}, \"length\": {}, \"ticks\": {}, \"over\": {}}}",
+            GRID_SIZE.0,
+            GRID_SIZE.1,
+            body.join(", "),
+            quote(snake.dir.name()),
+            point(self.sim.food.pos),
+            self.sim.wrap,
+            snake.length(),
+            self.ticks,
+            self.sim.over
+        )
+    }
+}
+
+/// How a game played against the clock ended.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Ending {
+    /// The game was over.
+    Over,
+    /// A tick wasn't answered in time, or the player stopped watching.
+    Forfeit,
+}
+
+/// Plays a game a tick at a time for a player that isn't in the room, like a bot, until it's
+/// over. Each tick the game is shown to `observe`, which returns whether the player is still
+/// watching, then the player has `deadline` to answer it with the tick and the way to turn, if
+/// any. Answers to other ticks are ignored.
+pub fn play_against_clock(
+    snake: &mut Snake,
+    deadline: Duration,
+    answers: &Receiver<(u64, Option<Direction>)>,
+    mut observe: impl FnMut(&Snake) -> bool,
+) -> Ending {
+    while !snake.over() {
+        if !observe(snake) {
+            return Ending::Forfeit;
+        }
+        let due = Instant::now() + deadline;
+        let turn = loop {
+            let left = due.saturating_duration_since(Instant::now());
+            match answers.recv_timeout(left) {
+                Ok((tick, turn)) if tick == snake.ticks() => break turn,
+                Ok(_) => (),
+                // Answers stop coming if the player has gone.
+                Err(_) => return Ending::Forfeit,
+            }
+        };
+        snake.play(turn, 1);
+    }
+    Ending::Over
+}
@@ -0,0 +1,384 @@
+//! The `quick-games` command line tool, for listing the games, starting one and printing its
+//! scores without going through the launcher.
+
+mod bots;
+mod completions;
+mod headless;
+mod serve;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+
+use common::args::Args;
+use common::daily::Daily;
+use common::launch;
+use common::leaderboard::{self, Message};
+use common::level::Level;
+use common::profile;
+use common::registry::{self, GameInfo, GAMES};
+use common::share::{self, Shared};
+use common::stats::{format_value, Record, StatsDb};
+use snake::replay::{self, Script};
+
+const USAGE: &str = "Usage:
+  quick-games list                 List the games.
+  quick-games play <game> [...]    Start a game, passing any options on to it.
+  quick-games scores [game]        Print every game's best, or one game's runs.
+  quick-games serve                Start, play and follow games over HTTP on localhost.
+  quick-games bots                 Let bots play games over gRPC on localhost, with the
+                                   service in proto/bot.proto.
+  quick-games tray                 Put a menu of the games in the system tray.
+  quick-games export <game> <file> Print a share code for a level.
+  quick-games export daily [day]   Print a share code for today's or a day's challenge.
+  quick-games import <code|file>   Install a shared level, or show a shared challenge.
+  quick-games verify <run|replay>  Play a run submitted to the leaderboard again, or a snake
+                                   replay from --seed, and check its score. Replays with a
+                                   day's seed have to be on that day's board.
+  quick-games completions <shell>  Print a completion script for bash, zsh or fish.
+
+Options:
+  --profile <name>    Use a profile other than the default.
+  --seed <number>     Start a game with a fixed seed, in games that use one.
+  --speed <moves>     Moves a second, for snake.
+  --maze <path>       Play snake in a maze from a level file.
+  --layout <kind>     Play snake in a generated layout: scatter, maze or rooms.
+  --difficulty <0-1>  How hard a generated layout is, 0.5 by default.
+  --edit [path]       Open snake's maze editor, saving to the level file given or
+                      levels/snake/maze.txt in the data directory. Tab playtests it.
+  --record <path>     Write a replay of each game of snake to a file, for its tests.
+  --score <number>    The score a replay should come to, for `verify`.
+  --start-length <n>  Start snake n segments long, in debug builds.
+  --invincible        Don't die, in debug builds of snake, platformer and roguelike.
+  --level <n>         Start on the nth level or floor, in debug builds.
+  --no-spawn          Don't spawn food or monsters, in debug builds.
+  --console           Allow the developer console in a release build; backtick
+                      opens it in games that have one.
+  --output <path>     Write a summary of the session as JSON when the game closes, or
+                      write the share code to a file for `export`.
+  --port <number>     The port for `serve`, 7878 by default, or `bots`, 50051.
+  --script            Print the last run's score when the game closes, exiting 0 if it
+                      was won, 1 if it was lost and 2 if the game failed.
+  --spectator [port]  Open a second window showing the game without its HUD, for a
+                      projector or stream, in snake, asteroids, quadpong and go.
+                      The frames are sent on port 7879 by default.
+
+Any option also overrides the setting of the same name for the run, like --theme neon.";
+
+/// How many of a game's runs `scores` prints.
+const RECENT_RUNS: usize = 10;
+
+fn find_game(name: &str) -> Result<&'static GameInfo, String> {
+    registry::find(name)
+        .ok_or_else(|| format!("There's no game called `{}`. Try `quick-games list`.", name))
+}
+
+fn list() -> Result<(), String> {
+    for game in GAMES {
+        println!(
+            "{:<14} {:<24} {}",
+            game.name,
+            game.title,
+            game.tags.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Starts a game with the options given, waiting for it to close.
+fn play(args: &Args) -> Result<(), String> {
+    let name = args
+        .positional
+        .get(1)
+        .ok_or("Which game? Try `quick-games list`.")?;
+    let game = find_game(name)?;
+    // The profile is passed on by `launch` itself.
+    let mut options = Vec::new();
+    for (option, value) in args.options().filter(|(option, _)| *option != "profile") {
+        options.push(format!("--{}", option));
+        options.extend(value.map(str::to_string));
+    }
+
+    let status = launch::launch(game, &options)
+        .and_then(|mut child| child.wait())
+        .map_err(|e| format!("Couldn't start {}: {}", game.title, e))?;
+    if !status.success() {
+        process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// The best of some runs, by the game's score key.
+fn best<'a>(game: &GameInfo, runs: &'a [Record]) -> Option<(&'a Record, f64)> {
+    let key = game.score?;
+    runs.iter()
+        .filter_map(|run| Some((run, run.get(key.key)?)))
+        .fold(None, |best, (run, value)| match best {
+            Some((_, best_value)) if !key.beats(value, best_value) => best,
+            _ => Some((run, value)),
+        })
+}
+
+fn scores(args: &Args) -> Result<(), String> {
+    let stats = StatsDb::open().map_err(|e| format!("Couldn't open the scores: {}", e))?;
+    let read_error = |e: io::Error| format!("Couldn't read the scores: {}", e);
+
+    let name = match args.positional.get(1) {
+        Some(name) => name,
+        None => {
+            let records = stats.records().map_err(read_error)?;
+            for game in GAMES {
+                let runs: Vec<Record> = records
+                    .iter()
+                    .filter(|record| record.game == game.name)
+                    .cloned()
+                    .collect();
+                if let (Some(key), Some((_, value))) = (game.score, best(game, &runs)) {
+                    println!("{:<24} {} {}", game.title, format_value(value), key.label);
+                }
+            }
+            return Ok(());
+        }
+    };
+
+    let game = find_game(name)?;
+    let runs = stats.records_for(game.name).map_err(read_error)?;
+    println!("{}: {} runs", game.title, runs.len());
+    if let (Some(key), Some((run, value))) = (game.score, best(game, &runs)) {
+        println!("Best {}: {}", key.label, format_value(value));
+        println!("  {}", run.describe());
+    }
+    if !runs.is_empty() {
+        println!("Recent:");
+    }
+    for run in runs.iter().rev().take(RECENT_RUNS) {
+        println!("  {}", run.describe());
+    }
+    Ok(())
+}
+
+/// Prints a share code for a level or a daily challenge, or writes it to the `--output` file.
+fn export(args: &Args) -> Result<(), String> {
+    let shared = match args.positional.get(1).map(String::as_str) {
+        Some("daily") => match args.positional.get(2) {
+            Some(day) => Shared::Daily(Daily::for_day(
+                day.parse().map_err(|_| format!("`{}` isn't a day.", day))?,
+            )),
+            None => Shared::Daily(Daily::today()),
+        },
+        Some(name) => {
+            let game = find_game(name)?;
+            let path = args
+                .positional
+                .get(2)
+                .ok_or("Which level? Give the path to its file.")?;
+            let contents =
+                fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+            let level = Level::parse(&contents).map_err(|e| format!("{}: {}", path, e))?;
+            Shared::Level {
+                game: game.name.to_string(),
+                level,
+            }
+        }
+        None => {
+            return Err("What should be shared? Try a game and a level file, or `daily`.".into())
+        }
+    };
+
+    let code = shared.encode();
+    match args.value("output") {
+        Some(path) => {
+            fs::write(path, format!("{}\n", code))
+                .map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+            println!("Wrote the share code to {}.", path);
+        }
+        None => println!("{}", code),
+    }
+    Ok(())
+}
+
+/// Installs a level from a share code, or from a file holding one, or describes a challenge.
+fn import(args: &Args) -> Result<(), String> {
+    let given = args
+        .positional
+        .get(1)
+        .ok_or("Import what? Give a share code or a file with one in it.")?;
+    let code = if Path::new(given).is_file() {
+        fs::read_to_string(given).map_err(|e| format!("Couldn't read {}: {}", given, e))?
+    } else {
+        given.clone()
+    };
+    let shared = Shared::decode(&code).map_err(|e| format!("Couldn't import it: {}.", e))?;
+
+    match shared {
+        Shared::Level { game, level } => {
+            let path = share::install(&game, &level)
+                .map_err(|e| format!("Couldn't install the level: {}", e))?;
+            let title = registry::find(&game).map_or(game.as_str(), |info| info.title);
+            println!(
+                "Installed the {} level \"{}\" at {}.",
+                title,
+                level.name(),
+                path.display()
+            );
+        }
+        Shared::Daily(daily) => {
+            println!("Daily challenge for day {}: {}.", daily.day, daily.title());
+            println!(
+                "Play it with `quick-games play {} --daily {}`.",
+                daily.challenge.game, daily.day
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The day's snake challenge played with `seed`, if it's a day's seed and the day is snake's.
+fn snake_daily(seed: u64) -> Option<Daily> {
+    Daily::for_seed(seed).filter(|daily| daily.challenge.game == "snake")
+}
+
+/// Plays a run again without drawing it, checking it comes to the score it was said to. The
+/// file is either a run as the leaderboard server was sent it, whose signature, seed, score and
+/// replay are all checked, or a snake replay played from `--seed` and checked against `--score`.
+/// A run has to be of a day's snake challenge, on that day's board, and so does a replay whose
+/// seed is a day's.
+fn verify(args: &Args) -> Result<(), String> {
+    let path = args
+        .positional
+        .get(1)
+        .ok_or("Verify what? Give a submitted run or a replay.")?;
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+
+    let (sim, seed) = match leaderboard::verify(&contents) {
+        Ok(Message::Submit {
+            submission,
+            replay: Some(replay),
+            ..
+        }) => {
+            let daily = snake_daily(submission.seed)
+                .ok_or("The run doesn't hold up: its seed isn't any day's snake challenge's.")?;
+            let sim = replay::verify_run(&submission, &replay, !daily.has("no_wrap"))
+                .map_err(|e| format!("The run doesn't hold up: {}.", e))?;
+            (sim, submission.seed)
+        }
+        Ok(Message::Submit { replay: None, .. }) => {
+            return Err(
+                "The run was submitted without a replay, so there's nothing to play.".into(),
+            )
+        }
+        Ok(Message::Register { .. }) => {
+            return Err(format!(
+                "{} registers a key rather than submitting a run.",
+                path
+            ))
+        }
+        Err(e) if contents.contains("\nsignature: ") => {
+            return Err(format!("The run doesn't hold up: {}.", e))
+        }
+        // Anything unsigned is taken to be a replay by itself.
+        Err(_) => {
+            let seed = args
+                .get("seed")
+                .ok_or("Which seed was it played with? Give it with --seed.")?;
+            let script = Script::parse(&contents).map_err(|e| format!("{}: {}", path, e))?;
+            // A replay with a day's seed is checked as that day's run.
+            let sim = match snake_daily(seed) {
+                Some(daily) => script
+                    .check_daily(!daily.has("no_wrap"))
+                    .and_then(|()| script.verify(seed)),
+                None => script.verify(seed),
+            }
+            .map_err(|e| format!("The replay doesn't hold up: {}.", e))?;
+            if let Some(score) = args.get::<f64>("score") {
+                if score != sim.snake.length() as f64 {
+                    return Err(format!(
+                        "The replay comes to a length of {}, not {}.",
+                        sim.snake.length(),
+                        score
+                    ));
+                }
+            }
+            (sim, seed)
+        }
+    };
+    println!(
+        "It holds up: a length of {} with seed {}.",
+        sim.snake.length(),
+        seed
+    );
+    Ok(())
+}
+
+/// Prints a completion script, to be sourced by the shell.
+fn completions(args: &Args) -> Result<(), String> {
+    let shells = completions::SHELLS.join(", ");
+    let shell = args
+        .positional
+        .get(1)
+        .ok_or_else(|| format!("Which shell? Try one of {}.", shells))?;
+    let script = completions::script(shell).ok_or_else(|| {
+        format!(
+            "There's no completion script for `{}`. Try one of {}.",
+            shell, shells
+        )
+    })?;
+    print!("{}", script);
+    Ok(())
+}
+
+/// Prints the words the completion scripts complete from, one a line: the games, a game's
+/// modes or the profiles. Left out of the usage since it's only for the scripts.
+fn complete(args: &Args) -> Result<(), String> {
+    match args.positional.get(1).map(String::as_str) {
+        Some("games") => {
+            for game in GAMES {
+                println!("{}", game.name);
+            }
+        }
+        Some("modes") => {
+            let game = args.positional.get(2).and_then(|name| registry::find(name));
+            for mode in game.map_or(&[][..], |game| game.modes) {
+                println!("{}", mode);
+            }
+        }
+        Some("profiles") => {
+            // No profiles yet is nothing to complete, not an error.
+            for name in profile::list().unwrap_or_default() {
+                println!("{}", name);
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = Args::from_env();
+    let result = match args.positional.first().map(String::as_str) {
+        Some("list") => list(),
+        Some("play") => play(&args),
+        Some("scores") => scores(&args),
+        Some("serve") => serve::serve(args.get("port").unwrap_or(serve::DEFAULT_PORT)),
+        Some("bots") => bots::serve(args.get("port").unwrap_or(bots::DEFAULT_PORT)),
+        Some("tray") => launch::launch_tray()
+            .map(|_| ())
+            .map_err(|e| format!("Couldn't start the tray: {}", e)),
+        Some("export") => export(&args),
+        Some("import") => import(&args),
+        Some("verify") => verify(&args),
+        Some("completions") => completions(&args),
+        Some("__complete") => complete(&args),
+        Some("help") | None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command `{}`.\n\n{}", command, USAGE)),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,584 @@
+//! `quick-games serve`, a small HTTP server on localhost for starting games, playing them and
+//! following their sessions from a web page or a script.
+//!
+//! Games that can be played without a window, from `headless`, are played in the server. They
+//! only move when told to, so a session's state is the game's own, and its inputs come from
+//! requests. The others are played in their own windows, so a session is a game process started
+//! with `--output` and `--script`: its state is whether it is still running, and once it closes,
+//! how it ended and the summary it wrote. The endpoints are:
+//!
+//! - `GET /games` lists the games, and whether each can be played without a window.
+//! - `POST /sessions/<game>?mode=Hard&seed=42` starts a game and returns the session. A windowed
+//!   game is given the `mode` and `seed`. A headless one takes `seed` and `wrap`, and
+//!   `window=true` plays it in its window instead. Any other option is turned away.
+//! - `GET /sessions` lists the sessions and `GET /sessions/<id>` returns one.
+//! - `POST /sessions/<id>/input?turn=up&steps=1` plays a headless game: it turns the snake, if
+//!   given a direction, then moves it the number of steps, 1 unless given. It returns the session.
+//!   Windowed games can only be played in their windows, so their input answers `409 Conflict`.
+//! - `DELETE /sessions/<id>` closes a session's game.
+//!
+//! Each connection is handled on a thread of its own and closed after its response. Any page the
+//! player has open could send requests to localhost, so requests from pages that aren't the
+//! server's own are turned away with `403 Forbidden`, and responses don't let other pages read
+//! them.
+
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{self, Child};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use common::json::{optional, quote};
+use common::launch;
+use common::registry::{self, GameInfo, GAMES};
+use common::results::{EXIT_FAILED, EXIT_LOST, EXIT_WON};
+
+use snake::Direction;
+
+use crate::headless::{self, MAX_STEPS};
+
+/// The port used when no `--port` is given.
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// How a session's game is played.
+enum Play {
+    /// In its own window.
+    Window {
+        child: Child,
+        /// Where the game writes its summary once it closes.
+        output: PathBuf,
+        /// The game's exit code, once it has closed.
+        exit: Option<i32>,
+    },
+    /// In the server, by requests.
+    Headless {
+        snake: Box<headless::Snake>,
+        /// Whether the session was closed before the game was over.
+        closed: bool,
+    },
+}
+
+/// A game started by the server.
+struct Session {
+    id: usize,
+    game: &'static GameInfo,
+    play: Play,
+}
+
+impl Session {
+    /// Notes whether the game has closed since it was last checked.
+    fn poll(&mut self) {
+        if let Play::Window {
+            child,
+            exit: exit @ None,
+            ..
+        } = &mut self.play
+        {
+            if let Ok(Some(status)) = child.try_wait() {
+                *exit = Some(status.code().unwrap_or(EXIT_FAILED));
+            }
+        }
+    }
+
+    fn running(&self) -> bool {
+        match &self.play {
+            Play::Window { exit, .. } => exit.is_none(),
+            Play::Headless { snake, closed } => !snake.over() && !closed,
+        }
+    }
+
+    /// Closes the game if it's still going. It counts as having failed.
+    fn close(&mut self) {
+        self.poll();
+        match &mut self.play {
+            Play::Window {
+                child,
+                exit: exit @ None,
+                ..
+            } => {
+                let _ = child.kill();
+                let _ = child.wait();
+                *exit = Some(EXIT_FAILED);
+            }
+            Play::Headless { snake, closed } if !snake.over() => *closed = true,
+            _ => (),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let (outcome, results, state) = match &self.play {
+            Play::Window { output, exit, .. } => {
+                let outcome = exit.map(|code| match code {
+                    EXIT_WON => "won",
+                    EXIT_LOST => "lost",
+                    _ => "failed",
+                });
+                let results = match exit {
+                    Some(_) => fs::read_to_string(output)
+                        .ok()
+                        .map(|results| results.trim().to_string()),
+                    None => None,
+                };
+                (outcome, results, None)
+            }
+            Play::Headless { snake, closed } => {
+                let outcome = if snake.over() {
+                    Some("lost")
+                } else if *closed {
+                    Some("failed")
+                } else {
+                    None
+                };
+                let results = outcome.map(|_| {
+                    format!(
+                        "{{\"seed\": {}, \"length\": {}, \"ticks\": {}}}",
+                        snake.seed(),
+                        snake.length(),
+                        snake.ticks()
+                    )
+                });
+                (outcome, results, Some(snake.to_json()))
+            }
+        };
+        format!(
+            "{{\"id\": {}, \"game\": {}, \"headless\": {}, \"running\": {}, \"outcome\": {}, \
+             \"state\": {}, \"results\": {}}}",
+            self.id,
+            quote(self.game.name),
+            state.is_some(),
+            self.running(),
+            optional(outcome),
+            state.as_deref().unwrap_or("null"),
+            results.as_deref().unwrap_or("null")
+        )
+    }
+}
+
+type Sessions = Arc<Mutex<Vec<Session>>>;
+
+/// A response's status line and JSON body.
+struct Response {
+    status: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Response {
+            status: "200 OK",
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Response {
+            status,
+            body: format!("{{\"error\": {}}}", quote(message)),
+        }
+    }
+
+    fn not_found() -> Self {
+        Response::error("404 Not Found", "There's nothing here.")
+    }
+}
+
+fn list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(", "))
+}
+
+fn games() -> Response {
+    Response::ok(list(GAMES.iter().map(|game| {
+        format!(
+            "{{\"name\": {}, \"title\": {}, \"modes\": {}, \"headless\": {}}}",
+            quote(game.name),
+            quote(game.title),
+            list(game.modes.iter().map(|mode| quote(mode))),
+            headless::GAMES.contains(&game.name)
+        )
+    })))
+}
+
+/// The options a session can be started with. Anything else is turned away, so a request can't
+/// have a game do more than be played, like writing to a file with `record`.
+const START_OPTIONS: &[&str] = &["mode", "seed", "wrap", "window"];
+
+/// Decodes a query string's `%` escapes, and `+` as a space, as browsers encode them. Returns
+/// nothing if an escape is broken or the result isn't UTF-8.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.bytes();
+    while let Some(byte) = rest.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let high = (rest.next()? as char).to_digit(16)?;
+                let low = (rest.next()? as char).to_digit(16)?;
+                (high * 16 + low) as u8
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The names and values in a query string like `mode=Hard&seed=42`, decoded. Names without a
+/// value, like `invincible`, have none.
+struct Query(Vec<(String, Option<String>)>);
+
+impl Query {
+    /// Reads a query string, or returns nothing if it isn't escaped properly.
+    fn parse(query: &str) -> Option<Self> {
+        query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let name = percent_decode(parts.next().unwrap_or_default())?;
+                let value = match parts.next() {
+                    Some(value) => Some(percent_decode(value)?),
+                    None => None,
+                };
+                Some((name, value))
+            })
+            .collect::<Option<_>>()
+            .map(Query)
+    }
+
+    /// The value given for `name`, if there is one.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(option, _)| option == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    /// Checks that only `START_OPTIONS` are given, each with a value the game can take.
+    fn check_start(&self, game: &GameInfo) -> Result<(), String> {
+        for (name, value) in &self.0 {
+            if !START_OPTIONS.contains(&name.as_str()) {
+                return Err(format!(
+                    "`{}` isn't an option sessions can be started with. Try {}.",
+                    name,
+                    START_OPTIONS.join(", ")
+                ));
+            }
+            let value = value.as_deref().unwrap_or("");
+            match name.as_str() {
+                "mode" if game.modes.is_empty() => {
+                    return Err(format!("{} has no modes.", game.title))
+                }
+                "mode" if !game.modes.contains(&value) => {
+                    return Err(format!(
+                        "{} has no mode `{}`. Try {}.",
+                        game.title,
+                        value,
+                        game.modes.join(", ")
+                    ))
+                }
+                "seed" if value.parse::<u64>().is_err() => {
+                    return Err("The seed isn't a number.".to_string())
+                }
+                "wrap" | "window" if value.parse::<bool>().is_err() => {
+                    return Err(format!("{} is either true or false.", name))
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// The options a windowed game is started with, from the checked query.
+    fn game_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+        for name in &["mode", "seed"] {
+            if let Some(value) = self.value(name) {
+                options.push(format!("--{}", name));
+                options.push(value.to_string());
+            }
+        }
+        options
+    }
+}
+
+/// Starts a game in the server, with the seed and whether it wraps from the checked query.
+fn start_headless(query: &Query) -> Play {
+    let seed = query
+        .value("seed")
+        .and_then(|seed| seed.parse().ok())
+        .unwrap_or_else(rand::random);
+    let wrap = query
+        .value("wrap")
+        .and_then(|wrap| wrap.parse().ok())
+        .unwrap_or(true);
+    Play::Headless {
+        snake: Box::new(headless::Snake::new(seed, wrap)),
+        closed: false,
+    }
+}
+
+/// Starts a game in its window, finding out how it went from its exit code and summary.
+fn start_window(game: &GameInfo, query: &Query, id: usize) -> Result<Play, Response> {
+    let output = env::temp_dir().join(format!("quick-games-session-{}-{}.json", process::id(), id));
+    let mut args = query.game_options();
+    args.push("--output".to_string());
+    args.push(output.to_string_lossy().into_owned());
+    args.push("--script".to_string());
+    match launch::launch(game, &args) {
+        Ok(child) => Ok(Play::Window {
+            child,
+            output,
+            exit: None,
+        }),
+        Err(e) => Err(Response::error(
+            "500 Internal Server Error",
+            &format!("Couldn't start {}: {}", game.title, e),
+        )),
+    }
+}
+
+fn start(sessions: &Sessions, name: &str, query: &Query) -> Response {
+    let game = match registry::find(name) {
+        Some(game) => game,
+        None => return Response::not_found(),
+    };
+    if let Err(message) = query.check_start(game) {
+        return Response::error("400 Bad Request", &message);
+    }
+    let mut sessions = sessions.lock().unwrap();
+    let id = sessions.len() + 1;
+    let window = query.value("window") == Some("true");
+    let play = if headless::GAMES.contains(&game.name) && !window {
+        Ok(start_headless(query))
+    } else {
+        start_window(game, query, id)
+    };
+    match play {
+        Ok(play) => {
+            let session = Session { id, game, play };
+            let response = Response {
+                status: "201 Created",
+                body: session.to_json(),
+            };
+            sessions.push(session);
+            response
+        }
+        Err(response) => response,
+    }
+}
+
+/// Plays a headless game: turns it as the query says, then steps it.
+fn input(session: &mut Session, query: &Query) -> Response {
+    let bad_request = |message: &str| Response::error("400 Bad Request", message);
+    let running = session.running();
+    let snake = match &mut session.play {
+        Play::Headless { snake, .. } => snake,
+        Play::Window { .. } => {
+            return Response::error(
+                "409 Conflict",
+                &format!("{} can only be played in its window.", session.game.title),
+            )
+        }
+    };
+    if !running {
+        return Response::error("409 Conflict", "The game is over.");
+    }
+    let turn = match query.value("turn") {
+        Some(name) => match Direction::from_name(name) {
+            Some(turn) => Some(turn),
+            None => return bad_request("turn is up, down, left or right."),
+        },
+        None => None,
+    };
+    let steps = match query.value("steps").map(str::parse::<u32>) {
+        Some(Ok(steps)) if steps <= MAX_STEPS => steps,
+        Some(_) => {
+            return bad_request(&format!("steps is a number of moves, up to {}.", MAX_STEPS))
+        }
+        None => 1,
+    };
+    snake.play(turn, steps);
+    Response::ok(session.to_json())
+}
+
+fn route(sessions: &Sessions, method: &str, target: &str) -> Response {
+    let (path, query) = match target.find('?') {
+        Some(question) => (&target[..question], &target[question + 1..]),
+        None => (target, ""),
+    };
+    let query = match Query::parse(query) {
+        Some(query) => query,
+        None => return Response::error("400 Bad Request", "The query isn't escaped properly."),
+    };
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    match (method, parts.as_slice()) {
+        ("GET", ["games"]) => games(),
+        ("GET", ["sessions"]) => {
+            let mut sessions = sessions.lock().unwrap();
+            sessions.iter_mut().for_each(Session::poll);
+            Response::ok(list(sessions.iter().map(Session::to_json)))
+        }
+        ("POST", ["sessions", game]) => start(sessions, game, &query),
+        (method, ["sessions", id, rest @ ..]) => {
+            let mut sessions = sessions.lock().unwrap();
+            let session = match id
+                .parse::<usize>()
+                .ok()
+                .and_then(|id| sessions.iter_mut().find(|session| session.id == id))
+            {
+                Some(session) => session,
+                None => return Response::not_found(),
+            };
+            match (method, rest) {
+                ("GET", []) => {
+                    session.poll();
+                    Response::ok(session.to_json())
+                }
+                ("DELETE", []) => {
+                    session.close();
+                    Response::ok(session.to_json())
+                }
+                ("POST", ["input"]) => input(session, &query),
+                _ => Response::not_found(),
+            }
+        }
+        _ => Response::not_found(),
+    }
+}
+
+/// Whether a request from a page at `origin` is let in. Browsers say which page a request is
+/// from, and only the server's own are. Requests that don't say, from scripts, are.
+fn allowed_origin(origin: Option<&str>, port: u16) -> bool {
+    origin.map_or(true, |origin| {
+        origin == format!("http://127.0.0.1:{}", port)
+            || origin == format!("http://localhost:{}", port)
+    })
+}
+
+/// Reads a request and answers it. Bodies are never needed, so they aren't read.
+fn handle(mut stream: TcpStream, sessions: &Sessions, port: u16) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut origin = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        let mut parts = header.splitn(2, ':');
+        if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+            if name.trim().eq_ignore_ascii_case("origin") {
+                origin = Some(value.trim().to_string());
+            }
+        }
+        header.clear();
+    }
+
+    let mut words = request_line.split_whitespace();
+    let response = match (words.next(), words.next()) {
+        _ if !allowed_origin(origin.as_deref(), port) => Response::error(
+            "403 Forbidden",
+            "Only scripts and this server's own pages can use it.",
+        ),
+        (Some(method), Some(target)) => route(sessions, method, target),
+        _ => Response::error("400 Bad Request", "That isn't an HTTP request."),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Serves until the process is stopped.
+pub fn serve(port: u16) -> Result<(), String> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Couldn't listen on port {}: {}", port, e))?;
+    println!("Serving on http://127.0.0.1:{}/", port);
+    let sessions: Sessions = Arc::new(Mutex::new(Vec::new()));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Couldn't accept a connection: {}", e);
+                continue;
+            }
+        };
+        let sessions = Arc::clone(&sessions);
+        thread::spawn(move || {
+            if let Err(e) = handle(stream, &sessions, port) {
+                eprintln!("Couldn't answer a request: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(name: &str) -> &'static GameInfo {
+        registry::find(name).unwrap()
+    }
+
+    fn check(name: &str, query: &str) -> Result<(), String> {
+        Query::parse(query).unwrap().check_start(game(name))
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(percent_decode("a%20b+c%2Fd").as_deref(), Some("a b c/d"));
+        assert_eq!(percent_decode("%e2%86%91").as_deref(), Some("\u{2191}"));
+        assert_eq!(percent_decode("%2"), None);
+        assert_eq!(percent_decode("%zz"), None);
+        assert_eq!(percent_decode("%ff"), None);
+    }
+
+    #[test]
+    fn games_are_started_with_their_mode_and_seed() {
+        let query = Query::parse("mode=Hard&seed=42&window=true").unwrap();
+        assert_eq!(query.check_start(game("airhockey")), Ok(()));
+        assert_eq!(query.game_options(), ["--mode", "Hard", "--seed", "42"]);
+        assert_eq!(check("snake", "seed=7&wrap=false"), Ok(()));
+    }
+
+    #[test]
+    fn options_that_do_more_than_play_are_turned_away() {
+        assert!(check("snake", "window=true&record=/home/player/.bashrc").is_err());
+        assert!(check("snake", "edit=level.txt").is_err());
+        assert!(check("snake", "output=/tmp/x&script").is_err());
+        assert!(check("airhockey", "mode=Hard&profile=other").is_err());
+    }
+
+    #[test]
+    fn names_are_decoded_before_they_are_checked() {
+        assert!(check("snake", "re%63ord=/tmp/x").is_err());
+        assert!(check("snake", "%72ecord=/tmp/x").is_err());
+    }
+
+    #[test]
+    fn values_have_to_be_ones_the_game_takes() {
+        assert!(check("airhockey", "mode=Impossible").is_err());
+        assert!(check("airhockey", "mode=--record").is_err());
+        assert!(check("snake", "mode=Hard").is_err());
+        assert!(check("snake", "seed=-1").is_err());
+        assert!(check("snake", "seed=42%20--record").is_err());
+        assert!(check("snake", "wrap=yes").is_err());
+        assert!(check("snake", "window").is_err());
+    }
+
+    #[test]
+    fn only_the_servers_own_pages_are_let_in() {
+        assert!(allowed_origin(None, 7878));
+        assert!(allowed_origin(Some("http://127.0.0.1:7878"), 7878));
+        assert!(allowed_origin(Some("http://localhost:7878"), 7878));
+        assert!(!allowed_origin(Some("http://localhost:8080"), 7878));
+        assert!(!allowed_origin(Some("https://example.com"), 7878));
+        assert!(!allowed_origin(Some("null"), 7878));
+    }
+}
@@ -0,0 +1,3 @@
+fn main() {
+    quick_games_embed::assets();
+}
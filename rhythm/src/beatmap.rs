@@ -0,0 +1,190 @@
+//! Loading of the simple text beatmap format.
+//!
+//! A beatmap starts with `key: value` header lines, followed by a `notes:` line and one note per
+//! line as `<beat> <lane>`. Blank lines and lines starting with `#` are ignored. Numbers have to
+//! be finite, so `inf` and `NaN` are turned away like any other bad number.
+//!
+//! ```text
+//! title: Where's My Jetpack
+//! music: /music/jetpack.ogg
+//! bpm: 120
+//! offset: 0.0
+//! notes:
+//! 4 1
+//! 4.5 2
+//! ```
+
+use ggez::{filesystem, Context, GameError, GameResult};
+
+use std::cmp::Ordering;
+use std::io::Read;
+use std::path::Path;
+
+/// The number of lanes notes can fall in.
+pub const LANES: usize = 4;
+
+/// A single note the player has to hit.
+#[derive(Debug, Copy, Clone)]
+pub struct Note {
+    /// The time of the note in seconds from the start of the music.
+    pub time: f64,
+    pub lane: usize,
+}
+
+/// A parsed beatmap.
+#[derive(Debug, Clone)]
+pub struct Beatmap {
+    pub title: String,
+    /// The path of the music track within the assets.
+    pub music: String,
+    pub bpm: f64,
+    /// Seconds from the start of the track to the first beat.
+    pub offset: f64,
+    /// The notes sorted by time.
+    pub notes: Vec<Note>,
+}
+
+impl Beatmap {
+    /// Loads and parses the beatmap at `path` within the assets.
+    pub fn load<P: AsRef<Path>>(ctx: &mut Context, path: P) -> GameResult<Self> {
+        let mut contents = String::new();
+        filesystem::open(ctx, path.as_ref())?.read_to_string(&mut contents)?;
+
+        Beatmap::parse(&contents).map_err(|e| {
+            GameError::ResourceLoadError(format!("{}: {}", path.as_ref().display(), e))
+        })
+    }
+
+    /// Parses a beatmap from its text representation.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut title = None;
+        let mut music = None;
+        let mut bpm = None;
+        let mut offset = 0.0;
+        let mut beats = Vec::new();
+        let mut in_notes = false;
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if in_notes {
+                let mut parts = line.split_whitespace();
+                let beat = parts.next().and_then(|beat| beat.parse::<f64>().ok());
+                let lane = parts.next().and_then(|lane| lane.parse::<usize>().ok());
+                match (beat, lane) {
+                    (Some(beat), Some(lane)) if beat.is_finite() && lane < LANES => {
+                        beats.push((beat, lane, number + 1))
+                    }
+                    _ => return Err(format!("invalid note on line {}", number + 1)),
+                }
+                continue;
+            }
+
+            if line == "notes:" {
+                in_notes = true;
+                continue;
+            }
+
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            match key {
+                "title" => title = Some(value.to_string()),
+                "music" => music = Some(value.to_string()),
+                "bpm" => {
+                    bpm = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+                }
+                "offset" => {
+                    offset = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|offset| offset.is_finite())
+                        .ok_or_else(|| format!("invalid offset on line {}", number + 1))?
+                }
+                _ => return Err(format!("unknown key `{}` on line {}", key, number + 1)),
+            }
+        }
+
+        let bpm = bpm.ok_or("missing or invalid bpm")?;
+        let seconds_per_beat = 60.0 / bpm;
+        let mut notes = Vec::with_capacity(beats.len());
+        for (beat, lane, line) in beats {
+            // A huge beat at a slow tempo can still come to a time too big to hold.
+            let time = offset + beat * seconds_per_beat;
+            if !time.is_finite() {
+                return Err(format!("note on line {} is too late to play", line));
+            }
+            notes.push(Note { time, lane });
+        }
+        // The times are all finite, so they always compare.
+        notes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+
+        Ok(Beatmap {
+            title: title.ok_or("missing title")?,
+            music: music.ok_or("missing music")?,
+            bpm,
+            offset,
+            notes,
+        })
+    }
+
+    /// The time in seconds of the last note.
+    pub fn length(&self) -> f64 {
+        self.notes.last().map(|note| note.time).unwrap_or(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn beatmap(bpm: &str, offset: &str, notes: &str) -> Result<Beatmap, String> {
+        Beatmap::parse(&format!(
+            "title: Test\nmusic: /music/test.ogg\nbpm: {}\noffset: {}\nnotes:\n{}",
+            bpm, offset, notes
+        ))
+    }
+
+    #[test]
+    fn notes_are_sorted_by_time() {
+        let beatmap = beatmap("120", "1.0", "4 1\n2 3\n4.5 0\n").unwrap();
+        let times: Vec<f64> = beatmap.notes.iter().map(|note| note.time).collect();
+        assert_eq!(times, vec![2.0, 3.0, 3.25]);
+        assert_eq!(beatmap.notes[0].lane, 3);
+    }
+
+    #[test]
+    fn beats_that_arent_finite_name_their_line() {
+        for beat in &["inf", "-inf", "NaN"] {
+            let notes = format!("1 0\n{} 1\n", beat);
+            assert_eq!(
+                beatmap("120", "0", &notes).unwrap_err(),
+                "invalid note on line 7"
+            );
+        }
+    }
+
+    #[test]
+    fn tempos_and_offsets_that_arent_finite_are_turned_away() {
+        assert!(beatmap("inf", "0", "1 0\n").is_err());
+        assert!(beatmap("NaN", "0", "1 0\n").is_err());
+        assert_eq!(
+            beatmap("120", "inf", "1 0\n").unwrap_err(),
+            "invalid offset on line 4"
+        );
+    }
+
+    #[test]
+    fn notes_too_late_to_hold_name_their_line() {
+        assert_eq!(
+            beatmap("1e-300", "0", "1 0\n1e300 1\n").unwrap_err(),
+            "note on line 7 is too late to play"
+        );
+    }
+}
@@ -0,0 +1,855 @@
+mod beatmap;
+
+use ggez::audio::{self, SoundSource};
+use ggez::event::{Button, KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::gamepad::GamepadId;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets::{self, Watcher};
+use common::audio::Mixer;
+use common::input::Nav;
+use common::locale;
+use common::migrate::{self, Format};
+use common::rumble::{Pulse, Rumble};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+use crate::beatmap::{Beatmap, LANES};
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 720.0);
+
+const BEATMAPS_DIR: &str = "/beatmaps";
+
+const LANE_WIDTH: f32 = SCREEN_SIZE.0 / LANES as f32;
+const LANE_KEYS: [KeyCode; LANES] = [KeyCode::D, KeyCode::F, KeyCode::J, KeyCode::K];
+const LANE_BUTTONS: [Button; LANES] = [
+    Button::DPadLeft,
+    Button::DPadRight,
+    Button::West,
+    Button::East,
+];
+const LANE_COLORS: [[f32; 4]; LANES] = [
+    [1.0, 0.3, 0.3, 1.0],
+    [0.3, 1.0, 0.3, 1.0],
+    [0.3, 0.6, 1.0, 1.0],
+    [1.0, 0.9, 0.3, 1.0],
+];
+
+const NOTE_HEIGHT: f32 = 16.0;
+/// The y coordinate of the line notes should be hit on.
+const HIT_LINE: f32 = SCREEN_SIZE.1 - 80.0;
+/// How many pixels a note falls per second.
+const SCROLL_SPEED: f32 = 400.0;
+
+/// Timing windows in seconds either side of a note.
+const PERFECT_WINDOW: f64 = 0.05;
+const GOOD_WINDOW: f64 = 0.12;
+
+const PERFECT_POINTS: u32 = 300;
+const GOOD_POINTS: u32 = 100;
+/// Every this many combo the score multiplier goes up by one.
+const COMBO_STEP: u32 = 25;
+const MAX_MULTIPLIER: u32 = 4;
+
+/// The audio clock is trusted over the frame timer once they drift further apart than this.
+const MAX_CLOCK_DRIFT: f64 = 0.03;
+
+const CALIBRATION_BPM: f64 = 100.0;
+const CALIBRATION_TAPS: usize = 16;
+const LATENCY_FILE: &str = "rhythm_latency.txt";
+const LATENCY_FORMAT: Format = Format {
+    name: "latency",
+    migrations: &[migrate::unversioned],
+    compressed: true,
+};
+
+/// The judgment given to a note.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Judgment {
+    Perfect,
+    Good,
+    Miss,
+}
+
+impl Judgment {
+    fn color(self) -> graphics::Color {
+        match self {
+            Judgment::Perfect => [0.3, 1.0, 1.0, 1.0].into(),
+            Judgment::Good => [0.3, 1.0, 0.3, 1.0].into(),
+            Judgment::Miss => [1.0, 0.2, 0.2, 1.0].into(),
+        }
+    }
+
+    /// The rumble played for the judgment: a sharp tap for a perfect hit, a softer one for a
+    /// good hit and a double buzz for a miss.
+    fn rumble(self) -> &'static [Pulse] {
+        match self {
+            Judgment::Perfect => &[Pulse {
+                strength: 0.5,
+                delay: 0,
+                length: 40,
+            }],
+            Judgment::Good => &[Pulse {
+                strength: 0.25,
+                delay: 0,
+                length: 40,
+            }],
+            Judgment::Miss => &[
+                Pulse {
+                    strength: 0.6,
+                    delay: 0,
+                    length: 50,
+                },
+                Pulse {
+                    strength: 0.6,
+                    delay: 100,
+                    length: 50,
+                },
+            ],
+        }
+    }
+}
+
+/// Keeps the song position in sync with the audio playback.
+///
+/// The frame timer is smooth but drifts from the audio, while the audio position is accurate but
+/// only updates in chunks. The clock advances with the frame timer and is continually nudged
+/// towards the audio position, snapping to it if the two drift too far apart.
+struct SongClock {
+    time: f64,
+    music: Option<audio::Source>,
+}
+
+impl SongClock {
+    fn new(music: Option<audio::Source>) -> Self {
+        SongClock { time: 0.0, music }
+    }
+
+    fn start(&mut self) -> GameResult<()> {
+        self.time = 0.0;
+        if let Some(music) = &mut self.music {
+            music.play()?;
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(music) = &mut self.music {
+            music.stop();
+        }
+    }
+
+    /// Pauses the music until `resume`, like when the window loses focus.
+    fn pause(&mut self) {
+        if let Some(music) = &mut self.music {
+            if music.playing() {
+                music.pause();
+            }
+        }
+    }
+
+    fn resume(&mut self) {
+        if let Some(music) = &mut self.music {
+            if music.paused() {
+                music.resume();
+            }
+        }
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        if let Some(music) = &mut self.music {
+            music.set_volume(volume);
+        }
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.time += dt;
+
+        if let Some(music) = &self.music {
+            if music.playing() {
+                let audio_time = music.elapsed().as_secs_f64();
+                let drift = audio_time - self.time;
+                if drift.abs() > MAX_CLOCK_DRIFT {
+                    self.time = audio_time;
+                } else {
+                    self.time += drift * 0.1;
+                }
+            }
+        }
+    }
+}
+
+/// The per note state while playing.
+#[derive(Debug, Copy, Clone)]
+struct NoteState {
+    time: f64,
+    lane: usize,
+    judgment: Option<Judgment>,
+}
+
+/// A song being played.
+struct Play {
+    beatmap: Beatmap,
+    notes: Vec<NoteState>,
+    clock: SongClock,
+    score: u32,
+    combo: u32,
+    max_combo: u32,
+    perfects: u32,
+    goods: u32,
+    misses: u32,
+    last_judgment: Option<Judgment>,
+    held: [bool; LANES],
+}
+
+impl Play {
+    fn new(ctx: &mut Context, beatmap: Beatmap, volume: f32) -> GameResult<Self> {
+        // Play on without music if the track is missing, using just the frame timer.
+        let music = audio::Source::new(ctx, &beatmap.music).ok();
+        let notes = beatmap
+            .notes
+            .iter()
+            .map(|note| NoteState {
+                time: note.time,
+                lane: note.lane,
+                judgment: None,
+            })
+            .collect();
+
+        let mut clock = SongClock::new(music);
+        clock.set_volume(volume);
+        clock.start()?;
+
+        Ok(Play {
+            beatmap,
+            notes,
+            clock,
+            score: 0,
+            combo: 0,
+            max_combo: 0,
+            perfects: 0,
+            goods: 0,
+            misses: 0,
+            last_judgment: None,
+            held: [false; LANES],
+        })
+    }
+
+    /// The song position corrected by the calibrated input latency.
+    fn song_time(&self, latency: f64) -> f64 {
+        self.clock.time - latency
+    }
+
+    fn multiplier(&self) -> u32 {
+        (1 + self.combo / COMBO_STEP).min(MAX_MULTIPLIER)
+    }
+
+    fn judge(&mut self, judgment: Judgment) {
+        match judgment {
+            Judgment::Perfect => {
+                self.score += PERFECT_POINTS * self.multiplier();
+                self.perfects += 1;
+                self.combo += 1;
+            }
+            Judgment::Good => {
+                self.score += GOOD_POINTS * self.multiplier();
+                self.goods += 1;
+                self.combo += 1;
+            }
+            Judgment::Miss => {
+                self.misses += 1;
+                self.combo = 0;
+            }
+        }
+        self.max_combo = self.max_combo.max(self.combo);
+        self.last_judgment = Some(judgment);
+    }
+
+    /// Moves the song on, returning how many notes went by without being hit.
+    fn update(&mut self, dt: f64, latency: f64) -> u32 {
+        self.clock.update(dt);
+        let now = self.song_time(latency);
+
+        let mut missed = 0;
+        for note in self.notes.iter_mut() {
+            if note.judgment.is_none() && now - note.time > GOOD_WINDOW {
+                note.judgment = Some(Judgment::Miss);
+                missed += 1;
+            }
+        }
+        for _ in 0..missed {
+            self.judge(Judgment::Miss);
+        }
+        missed
+    }
+
+    /// Hits a lane, returning the judgment if a note was hit.
+    fn hit(&mut self, lane: usize, latency: f64) -> Option<Judgment> {
+        let now = self.song_time(latency);
+
+        // Hit the earliest unjudged note in the lane that is within the timing window.
+        let note = self.notes.iter_mut().find(|note| {
+            note.lane == lane && note.judgment.is_none() && (note.time - now).abs() <= GOOD_WINDOW
+        });
+
+        if let Some(note) = note {
+            let judgment = if (note.time - now).abs() <= PERFECT_WINDOW {
+                Judgment::Perfect
+            } else {
+                Judgment::Good
+            };
+            note.judgment = Some(judgment);
+            self.judge(judgment);
+            Some(judgment)
+        } else {
+            None
+        }
+    }
+
+    fn finished(&self, latency: f64) -> bool {
+        self.song_time(latency) > self.beatmap.length() + 2.0
+    }
+
+    /// The percentage of notes hit, with good hits counting for half.
+    fn accuracy(&self) -> f64 {
+        let judged = self.perfects + self.goods + self.misses;
+        if judged == 0 {
+            return 100.0;
+        }
+        (self.perfects as f64 + self.goods as f64 * 0.5) / judged as f64 * 100.0
+    }
+
+    fn draw(&self, ctx: &mut Context, latency: f64) -> GameResult<()> {
+        let now = self.song_time(latency);
+
+        for lane in 0..LANES {
+            let color = if self.held[lane] {
+                [0.25, 0.25, 0.25, 1.0]
+            } else {
+                [0.1, 0.1, 0.1, 1.0]
+            };
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    lane as f32 * LANE_WIDTH + 2.0,
+                    0.0,
+                    LANE_WIDTH - 4.0,
+                    SCREEN_SIZE.1,
+                ),
+                color.into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let hit_line = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0.0, HIT_LINE - 2.0, SCREEN_SIZE.0, 4.0),
+            [1.0, 1.0, 1.0, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &hit_line, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        for note in self.notes.iter().filter(|note| note.judgment.is_none()) {
+            let y = HIT_LINE - (note.time - now) as f32 * SCROLL_SPEED;
+            if y < -NOTE_HEIGHT || y > SCREEN_SIZE.1 {
+                continue;
+            }
+
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    note.lane as f32 * LANE_WIDTH + 6.0,
+                    y - NOTE_HEIGHT / 2.0,
+                    LANE_WIDTH - 12.0,
+                    NOTE_HEIGHT,
+                ),
+                LANE_COLORS[note.lane].into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "{}\nScore: {}  Combo: {}  x{}",
+                self.beatmap.title,
+                locale::integer(self.score),
+                self.combo,
+                self.multiplier()
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if let Some(judgment) = self.last_judgment {
+            let text = Text::new(
+                TextFragment::new(format!("{:?}", judgment).to_uppercase())
+                    .color(judgment.color())
+                    .scale(Scale::uniform(40.0)),
+            );
+            graphics::draw(
+                ctx,
+                &text,
+                (ggez::mint::Point2 {
+                    x: SCREEN_SIZE.0 / 2.0 - 70.0,
+                    y: SCREEN_SIZE.1 / 2.0,
+                },),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Measures the player's input latency by having them tap along to a visual metronome.
+struct Calibration {
+    time: f64,
+    offsets: Vec<f64>,
+}
+
+impl Calibration {
+    fn new() -> Self {
+        Calibration {
+            time: 0.0,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn beat_length() -> f64 {
+        60.0 / CALIBRATION_BPM
+    }
+
+    fn tap(&mut self) {
+        // The offset from the nearest beat, early taps being negative.
+        let beat = Calibration::beat_length();
+        let nearest = (self.time / beat).round() * beat;
+        // Ignore taps before the first beat.
+        if nearest > 0.0 {
+            self.offsets.push(self.time - nearest);
+        }
+    }
+
+    fn done(&self) -> bool {
+        self.offsets.len() >= CALIBRATION_TAPS
+    }
+
+    /// The average offset of the taps.
+    fn latency(&self) -> f64 {
+        if self.offsets.is_empty() {
+            return 0.0;
+        }
+        self.offsets.iter().sum::<f64>() / self.offsets.len() as f64
+    }
+
+    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+        // Flash on every beat.
+        let beat = Calibration::beat_length();
+        let phase = (self.time / beat).fract();
+        let brightness = (1.0 - phase * 4.0).max(0.0) as f32;
+
+        let flash = graphics::Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            ggez::mint::Point2 {
+                x: SCREEN_SIZE.0 / 2.0,
+                y: SCREEN_SIZE.1 / 2.0,
+            },
+            60.0,
+            0.5,
+            [brightness, brightness, brightness, 1.0].into(),
+        )?;
+        graphics::draw(ctx, &flash, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let text = Text::new(
+            TextFragment::new(format!(
+                "Tap Space on every flash\n{} / {}  Latency: {:.0}ms",
+                self.offsets.len(),
+                CALIBRATION_TAPS,
+                self.latency() * 1000.0
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))
+    }
+}
+
+/// Reads the saved latency calibration.
+fn load_latency() -> f64 {
+    common::data_dir()
+        .ok()
+        .and_then(|dir| migrate::read(&dir.join(LATENCY_FILE), &LATENCY_FORMAT).ok())
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+fn save_latency(latency: f64) {
+    if let Ok(dir) = common::data_dir() {
+        let _ = migrate::write(
+            &dir.join(LATENCY_FILE),
+            &LATENCY_FORMAT,
+            &latency.to_string(),
+        );
+    }
+}
+
+/// The screen the game is currently showing.
+enum Screen {
+    SongSelect,
+    Playing(Play),
+    Results(Play),
+    Calibrating(Calibration),
+}
+
+/// Loads every beatmap in the assets, in file name order.
+fn load_beatmaps(ctx: &mut Context) -> GameResult<Vec<Beatmap>> {
+    let paths = assets::read_dir(ctx, BEATMAPS_DIR)?;
+
+    let mut beatmaps = Vec::new();
+    for path in paths {
+        beatmaps.push(Beatmap::load(ctx, path)?);
+    }
+    Ok(beatmaps)
+}
+
+/// The state for the game.
+struct GameState {
+    beatmaps: Vec<Beatmap>,
+    selected: usize,
+    screen: Screen,
+    /// The calibrated input latency in seconds.
+    latency: f64,
+    stats: Option<StatsDb>,
+    mixer: Mixer,
+    rumble: Rumble,
+    /// Notices beatmaps being edited, in debug builds. Music is loaded each time a song starts,
+    /// so a changed track is heard the next time it's played.
+    watcher: Watcher,
+    /// Whether the game has focus, which it doesn't while the window is in the background or
+    /// the game is paused. The music is muted without it.
+    focused: bool,
+}
+
+impl GameState {
+    /// Creates a new game state, loading every beatmap from the assets.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        Ok(GameState {
+            beatmaps: load_beatmaps(ctx)?,
+            selected: 0,
+            screen: Screen::SongSelect,
+            latency: load_latency(),
+            stats: StatsDb::open().ok(),
+            mixer: Mixer::load(),
+            rumble: Rumble::load(),
+            watcher: Watcher::new(assets::dir()),
+            focused: true,
+        })
+    }
+
+    /// Loads the beatmaps again after they've been edited, keeping the ones there were if the
+    /// new ones don't load, like when a file is half saved.
+    fn reload_beatmaps(&mut self, ctx: &mut Context) {
+        match load_beatmaps(ctx) {
+            Ok(beatmaps) => {
+                self.selected = self.selected.min(beatmaps.len().saturating_sub(1));
+                self.beatmaps = beatmaps;
+            }
+            Err(e) => eprintln!("Couldn't reload the beatmaps: {}", e),
+        }
+    }
+
+    fn music_volume(&self) -> f32 {
+        if self.focused {
+            self.mixer.volumes().music()
+        } else {
+            0.0
+        }
+    }
+
+    fn start_selected(&mut self, ctx: &mut Context) {
+        if let Some(beatmap) = self.beatmaps.get(self.selected).cloned() {
+            if let Ok(play) = Play::new(ctx, beatmap, self.music_volume()) {
+                self.screen = Screen::Playing(play);
+            }
+        }
+    }
+
+    fn select(&mut self, nav: Nav) {
+        match nav {
+            Nav::Up => self.selected = self.selected.saturating_sub(1),
+            Nav::Down => {
+                self.selected = (self.selected + 1).min(self.beatmaps.len().saturating_sub(1))
+            }
+            _ => (),
+        }
+    }
+
+    /// Hits a lane while playing, rumbling for how it was judged.
+    fn hit(&mut self, ctx: &mut Context, lane: usize) {
+        if let Screen::Playing(play) = &mut self.screen {
+            play.held[lane] = true;
+            if let Some(judgment) = play.hit(lane, self.latency) {
+                self.rumble.play(ctx, judgment.rumble());
+            }
+        }
+    }
+
+    fn release(&mut self, lane: usize) {
+        if let Screen::Playing(play) = &mut self.screen {
+            play.held[lane] = false;
+        }
+    }
+
+    fn stop_playing(&mut self) {
+        if let Screen::Playing(play) = &mut self.screen {
+            play.clock.stop();
+            self.screen = Screen::SongSelect;
+        }
+    }
+
+    fn tap_calibration(&mut self) {
+        if let Screen::Calibrating(calibration) = &mut self.screen {
+            calibration.tap();
+            if calibration.done() {
+                self.latency = calibration.latency();
+                save_latency(self.latency);
+                self.screen = Screen::SongSelect;
+            }
+        }
+    }
+
+    fn record_stats(&self, play: &Play) {
+        let values = [
+            ("score", play.score as f64),
+            ("max_combo", play.max_combo as f64),
+            ("accuracy", play.accuracy()),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("rhythm", &values);
+        }
+        let _ = achievements::check_run("rhythm", &values);
+    }
+
+    fn draw_song_select(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut text = Text::new(
+            TextFragment::new("Select a song\n\n")
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(32.0)),
+        );
+        for (i, beatmap) in self.beatmaps.iter().enumerate() {
+            let color = if i == self.selected {
+                [1.0, 0.8, 0.0, 1.0]
+            } else {
+                [0.7, 0.7, 0.7, 1.0]
+            };
+            text.add(
+                TextFragment::new(format!("{} ({} BPM)\n", beatmap.title, beatmap.bpm))
+                    .color(color.into())
+                    .scale(Scale::uniform(24.0)),
+            );
+        }
+        text.add(
+            TextFragment::new(format!(
+                "\nEnter: play  C: calibrate ({:.0}ms)\nLanes: D F J K, or Left Right X B",
+                self.latency * 1000.0
+            ))
+            .color([0.7, 0.7, 0.7, 1.0].into())
+            .scale(Scale::uniform(20.0)),
+        );
+
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 16.0, y: 16.0 },))
+    }
+
+    fn draw_results(&self, ctx: &mut Context, play: &Play) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(format!(
+                "{}\n\nScore: {}\nMax combo: {}\nPerfect: {}\nGood: {}\nMiss: {}\nAccuracy: {:.1}%\n\nPress Enter",
+                play.beatmap.title,
+                locale::integer(play.score),
+                play.max_combo,
+                play.perfects,
+                play.goods,
+                play.misses,
+                play.accuracy()
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(28.0)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x: 16.0, y: 16.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f64();
+
+        let volume_changed = self.mixer.update();
+        let volume = self.music_volume();
+        if let Screen::Playing(play) = &mut self.screen {
+            if volume_changed {
+                play.clock.set_volume(volume);
+            }
+            // The music stops when the window loses focus or the game is paused, and carries on
+            // with the game, which isn't updated while it's paused.
+            play.clock.resume();
+        }
+
+        let changed = self.watcher.changed();
+        if changed.iter().any(|path| path.starts_with(BEATMAPS_DIR)) {
+            self.reload_beatmaps(ctx);
+        }
+
+        let finished = match &mut self.screen {
+            Screen::Playing(play) => {
+                if play.update(dt, self.latency) > 0 {
+                    self.rumble.play(ctx, Judgment::Miss.rumble());
+                }
+                play.finished(self.latency)
+            }
+            Screen::Calibrating(calibration) => {
+                calibration.time += dt;
+                false
+            }
+            _ => false,
+        };
+
+        if finished {
+            if let Screen::Playing(mut play) =
+                std::mem::replace(&mut self.screen, Screen::SongSelect)
+            {
+                play.clock.stop();
+                self.record_stats(&play);
+                self.screen = Screen::Results(play);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+
+        match &self.screen {
+            Screen::SongSelect => self.draw_song_select(ctx)?,
+            Screen::Playing(play) => play.draw(ctx, self.latency)?,
+            Screen::Results(play) => self.draw_results(ctx, play)?,
+            Screen::Calibrating(calibration) => calibration.draw(ctx)?,
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if repeat {
+            return;
+        }
+
+        match &self.screen {
+            Screen::SongSelect => match keycode {
+                KeyCode::Up => self.select(Nav::Up),
+                KeyCode::Down => self.select(Nav::Down),
+                KeyCode::Return => self.start_selected(ctx),
+                KeyCode::C => self.screen = Screen::Calibrating(Calibration::new()),
+                KeyCode::Escape => event::quit(ctx),
+                _ => (),
+            },
+            Screen::Playing(_) => {
+                if keycode == KeyCode::Escape {
+                    self.stop_playing();
+                } else if let Some(lane) = LANE_KEYS.iter().position(|key| *key == keycode) {
+                    self.hit(ctx, lane);
+                }
+            }
+            Screen::Results(_) => {
+                if keycode == KeyCode::Return || keycode == KeyCode::Escape {
+                    self.screen = Screen::SongSelect;
+                }
+            }
+            Screen::Calibrating(_) => match keycode {
+                KeyCode::Space => self.tap_calibration(),
+                KeyCode::Escape => self.screen = Screen::SongSelect,
+                _ => (),
+            },
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
+        if let Some(lane) = LANE_KEYS.iter().position(|key| *key == keycode) {
+            self.release(lane);
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.rumble.gamepad_used(id);
+        let nav = Nav::from_button(btn);
+        match &self.screen {
+            Screen::SongSelect => match nav {
+                Some(Nav::Confirm) => self.start_selected(ctx),
+                Some(Nav::Back) => event::quit(ctx),
+                Some(nav) => self.select(nav),
+                None => (),
+            },
+            Screen::Playing(_) => {
+                if let Some(lane) = LANE_BUTTONS.iter().position(|button| *button == btn) {
+                    self.hit(ctx, lane);
+                } else if btn == Button::Start {
+                    self.stop_playing();
+                }
+            }
+            Screen::Results(_) => {
+                if nav == Some(Nav::Confirm) || nav == Some(Nav::Back) {
+                    self.screen = Screen::SongSelect;
+                }
+            }
+            Screen::Calibrating(_) => match btn {
+                Button::South => self.tap_calibration(),
+                Button::East => self.screen = Screen::SongSelect,
+                _ => (),
+            },
+        }
+    }
+
+    fn gamepad_button_up_event(&mut self, _ctx: &mut Context, btn: Button, _id: GamepadId) {
+        if let Some(lane) = LANE_BUTTONS.iter().position(|button| *button == btn) {
+            self.release(lane);
+        }
+    }
+
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
+        self.focused = gained;
+        let volume = self.music_volume();
+        if let Screen::Playing(play) = &mut self.screen {
+            if !gained {
+                play.clock.pause();
+            }
+            play.clock.set_volume(volume);
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("rhythm", "Sprial404")
+        .window_setup(window::setup("Rhythm Tapper"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "rhythm", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
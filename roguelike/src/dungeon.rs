@@ -0,0 +1,110 @@
+//! Procedural generation of dungeon floors.
+
+use common::generate::{Cell, LevelGenerator, Params, Room, RoomsAndCorridors};
+use common::grid::Grid;
+use rand::rngs::StdRng;
+
+pub const MAP_SIZE: (usize, usize) = (60, 36);
+
+/// How many floors down the rooms stop getting smaller.
+const HARDEST_DEPTH: u32 = 11;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Tile {
+    Wall,
+    Floor,
+    Stairs,
+}
+
+impl Tile {
+    pub fn blocks_sight(self) -> bool {
+        self == Tile::Wall
+    }
+
+    pub fn walkable(self) -> bool {
+        self != Tile::Wall
+    }
+}
+
+/// A generated floor of the dungeon.
+pub struct Floor {
+    pub tiles: Grid<Tile>,
+    /// The rooms in the order they were carved. The player starts in the first one.
+    pub rooms: Vec<Room>,
+}
+
+/// Generates a floor of rooms joined by L-shaped corridors, with the stairs down in the last room.
+/// The deeper the floor, the smaller its rooms.
+pub fn generate(rng: &mut StdRng, depth: u32) -> Floor {
+    let params = Params {
+        width: MAP_SIZE.0,
+        height: MAP_SIZE.1,
+        difficulty: depth.saturating_sub(1) as f32 / (HARDEST_DEPTH - 1) as f32,
+    };
+    let layout = RoomsAndCorridors::default().generate_with(&params, rng);
+
+    let mut tiles = Grid::new(MAP_SIZE.0, MAP_SIZE.1, Tile::Wall);
+    for (x, y, cell) in layout.cells.iter() {
+        if *cell == Cell::Open {
+            tiles.set(x, y, Tile::Floor);
+        }
+    }
+    if let Some(last) = layout.rooms.last() {
+        let (x, y) = last.center();
+        tiles.set(x, y, Tile::Stairs);
+    }
+
+    Floor {
+        tiles,
+        rooms: layout.rooms,
+    }
+}
+
+/// Computes the tiles visible from `origin` within `radius` by casting a line to every tile in
+/// range. Walls are visible but block sight beyond them.
+pub fn field_of_view(tiles: &Grid<Tile>, origin: (i32, i32), radius: i32) -> Grid<bool> {
+    let mut visible = Grid::new(tiles.width(), tiles.height(), false);
+
+    for y in origin.1 - radius..=origin.1 + radius {
+        for x in origin.0 - radius..=origin.0 + radius {
+            let (dx, dy) = (x - origin.0, y - origin.1);
+            if dx * dx + dy * dy > radius * radius || !tiles.in_bounds(x, y) {
+                continue;
+            }
+
+            for (lx, ly) in line(origin, (x, y)) {
+                visible.set(lx, ly, true);
+                if tiles.get(lx, ly).map_or(true, |tile| tile.blocks_sight()) {
+                    break;
+                }
+            }
+        }
+    }
+
+    visible
+}
+
+/// Returns the tiles on the Bresenham line from `from` to `to`, inclusive.
+fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let sx = if x < to.0 { 1 } else { -1 };
+    let sy = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+
+    let mut points = vec![(x, y)];
+    while (x, y) != to {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+        points.push((x, y));
+    }
+    points
+}
@@ -0,0 +1,613 @@
+mod dungeon;
+
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::fs;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use common::achievements;
+use common::app::App;
+use common::args;
+use common::cheats::Cheats;
+use common::grid::Grid;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use quick_games_ai::path::{self, Steps};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::dungeon::{Floor, Tile, MAP_SIZE};
+
+const CELL_SIZE: f32 = 16.0;
+const HUD_HEIGHT: f32 = 112.0;
+
+const SCREEN_SIZE: (f32, f32) = (
+    MAP_SIZE.0 as f32 * CELL_SIZE,
+    MAP_SIZE.1 as f32 * CELL_SIZE + HUD_HEIGHT,
+);
+
+const FOV_RADIUS: i32 = 8;
+const MESSAGE_LOG_LEN: usize = 4;
+
+const PLAYER_HP: i32 = 20;
+const PLAYER_ATTACK: i32 = 3;
+const POTION_HEAL: i32 = 8;
+
+/// The kinds of monsters roaming the dungeon.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum MonsterKind {
+    Rat,
+    Goblin,
+    Orc,
+}
+
+impl MonsterKind {
+    fn name(self) -> &'static str {
+        match self {
+            MonsterKind::Rat => "rat",
+            MonsterKind::Goblin => "goblin",
+            MonsterKind::Orc => "orc",
+        }
+    }
+
+    fn glyph(self) -> &'static str {
+        match self {
+            MonsterKind::Rat => "r",
+            MonsterKind::Goblin => "g",
+            MonsterKind::Orc => "O",
+        }
+    }
+
+    /// Returns the hit points and attack of the monster.
+    fn stats(self) -> (i32, i32) {
+        match self {
+            MonsterKind::Rat => (3, 1),
+            MonsterKind::Goblin => (6, 2),
+            MonsterKind::Orc => (12, 4),
+        }
+    }
+
+    /// Picks a random monster, with tougher monsters becoming more common deeper down.
+    fn random(rng: &mut StdRng, depth: u32) -> Self {
+        let roll = rng.gen_range(0, 10) + depth as i32;
+        if roll < 7 {
+            MonsterKind::Rat
+        } else if roll < 11 {
+            MonsterKind::Goblin
+        } else {
+            MonsterKind::Orc
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Monster {
+    kind: MonsterKind,
+    pos: (i32, i32),
+    hp: i32,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ItemKind {
+    /// Heals the player when quaffed.
+    Potion,
+    /// Permanently raises the player's attack when picked up.
+    Sword,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Item {
+    kind: ItemKind,
+    pos: (i32, i32),
+}
+
+/// The player's adventurer.
+#[derive(Debug, Copy, Clone)]
+struct Player {
+    pos: (i32, i32),
+    hp: i32,
+    max_hp: i32,
+    attack: i32,
+    potions: u32,
+}
+
+/// The state of a single run, from the first floor until death.
+struct Run {
+    seed: u64,
+    rng: StdRng,
+    depth: u32,
+    floor: Floor,
+    /// The tiles the player has seen on this floor.
+    explored: Grid<bool>,
+    visible: Grid<bool>,
+    player: Player,
+    monsters: Vec<Monster>,
+    items: Vec<Item>,
+    turns: u32,
+    kills: u32,
+    messages: VecDeque<String>,
+    /// What killed the player, once the run is over.
+    killed_by: Option<&'static str>,
+    cheats: Cheats,
+}
+
+impl Run {
+    /// Starts a new run on the first floor, or the one from `--level`, generated from `seed`.
+    fn new(seed: u64, cheats: Cheats) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let floor = dungeon::generate(&mut rng, 1);
+
+        let mut run = Run {
+            seed,
+            rng,
+            depth: cheats.level.map_or(0, |level| level as u32 - 1),
+            explored: Grid::new(MAP_SIZE.0, MAP_SIZE.1, false),
+            visible: Grid::new(MAP_SIZE.0, MAP_SIZE.1, false),
+            floor,
+            player: Player {
+                pos: (0, 0),
+                hp: PLAYER_HP,
+                max_hp: PLAYER_HP,
+                attack: PLAYER_ATTACK,
+                potions: 0,
+            },
+            monsters: Vec::new(),
+            items: Vec::new(),
+            turns: 0,
+            kills: 0,
+            messages: VecDeque::new(),
+            killed_by: None,
+            cheats,
+        };
+        run.descend();
+        run.log(format!("Welcome to the dungeon! (seed {})", seed));
+        run
+    }
+
+    fn log(&mut self, message: String) {
+        self.messages.push_back(message);
+        while self.messages.len() > MESSAGE_LOG_LEN {
+            self.messages.pop_front();
+        }
+    }
+
+    /// Generates and populates the next floor down.
+    fn descend(&mut self) {
+        self.depth += 1;
+        self.floor = dungeon::generate(&mut self.rng, self.depth);
+        self.explored = Grid::new(MAP_SIZE.0, MAP_SIZE.1, false);
+        self.monsters.clear();
+        self.items.clear();
+
+        self.player.pos = self.floor.rooms[0].center();
+
+        // Leave the starting room empty so the player isn't ambushed on arrival.
+        let rooms = self.floor.rooms[1..].to_vec();
+        for room in rooms {
+            let monsters = if self.cheats.no_spawn {
+                0
+            } else {
+                self.rng.gen_range(0, 3)
+            };
+            for _ in 0..monsters {
+                let pos = room.random_pos(&mut self.rng);
+                if self.monster_at(pos).is_none() {
+                    let kind = MonsterKind::random(&mut self.rng, self.depth);
+                    let (hp, _) = kind.stats();
+                    self.monsters.push(Monster { kind, pos, hp });
+                }
+            }
+
+            if self.rng.gen_range(0, 3) == 0 {
+                let kind = if self.rng.gen_range(0, 4) == 0 {
+                    ItemKind::Sword
+                } else {
+                    ItemKind::Potion
+                };
+                let pos = room.random_pos(&mut self.rng);
+                self.items.push(Item { kind, pos });
+            }
+        }
+
+        self.update_fov();
+    }
+
+    fn update_fov(&mut self) {
+        self.visible = dungeon::field_of_view(&self.floor.tiles, self.player.pos, FOV_RADIUS);
+        for (x, y, visible) in self.visible.iter() {
+            if *visible {
+                self.explored.set(x, y, true);
+            }
+        }
+    }
+
+    fn monster_at(&self, pos: (i32, i32)) -> Option<usize> {
+        self.monsters.iter().position(|monster| monster.pos == pos)
+    }
+
+    fn walkable(&self, pos: (i32, i32)) -> bool {
+        self.floor
+            .tiles
+            .get(pos.0, pos.1)
+            .map_or(false, |tile| tile.walkable())
+    }
+
+    fn is_over(&self) -> bool {
+        self.killed_by.is_some()
+    }
+
+    /// Moves the player by `(dx, dy)`, attacking a monster in the way, then lets the monsters act.
+    fn player_move(&mut self, dx: i32, dy: i32) {
+        let target = (self.player.pos.0 + dx, self.player.pos.1 + dy);
+
+        if let Some(i) = self.monster_at(target) {
+            let monster = &mut self.monsters[i];
+            monster.hp -= self.player.attack;
+            let name = monster.kind.name();
+            if monster.hp <= 0 {
+                self.monsters.remove(i);
+                self.kills += 1;
+                self.log(format!("You slay the {}.", name));
+            } else {
+                self.log(format!("You hit the {}.", name));
+            }
+        } else if self.walkable(target) {
+            self.player.pos = target;
+            self.pick_up();
+        } else {
+            // Bumping into a wall doesn't use up a turn.
+            return;
+        }
+
+        self.end_turn();
+    }
+
+    fn pick_up(&mut self) {
+        let pos = self.player.pos;
+        if let Some(i) = self.items.iter().position(|item| item.pos == pos) {
+            match self.items.remove(i).kind {
+                ItemKind::Potion => {
+                    self.player.potions += 1;
+                    self.log("You pick up a potion.".to_string());
+                }
+                ItemKind::Sword => {
+                    self.player.attack += 1;
+                    self.log(format!(
+                        "You wield a finer sword. (attack {})",
+                        self.player.attack
+                    ));
+                }
+            }
+        }
+    }
+
+    fn quaff(&mut self) {
+        if self.player.potions == 0 {
+            self.log("You have no potions.".to_string());
+            return;
+        }
+
+        self.player.potions -= 1;
+        self.player.hp = (self.player.hp + POTION_HEAL).min(self.player.max_hp);
+        self.log("You feel better.".to_string());
+        self.end_turn();
+    }
+
+    fn take_stairs(&mut self) {
+        let (x, y) = self.player.pos;
+        if self.floor.tiles.get(x, y) == Some(&Tile::Stairs) {
+            self.descend();
+            // Reaching a new floor restores a little health.
+            self.player.max_hp += 2;
+            self.player.hp = (self.player.hp + 5).min(self.player.max_hp);
+            self.log(format!("You descend to depth {}.", self.depth));
+        } else {
+            self.log("There are no stairs here.".to_string());
+        }
+    }
+
+    fn end_turn(&mut self) {
+        self.turns += 1;
+        self.update_fov();
+        self.monsters_act();
+    }
+
+    /// Monsters that can see the player chase and attack them, the rest wander aimlessly.
+    fn monsters_act(&mut self) {
+        for i in 0..self.monsters.len() {
+            let monster = self.monsters[i];
+            let (px, py) = self.player.pos;
+            let (mx, my) = monster.pos;
+            let dist = (px - mx).abs().max((py - my).abs());
+
+            if dist == 1 {
+                let (_, attack) = monster.kind.stats();
+                self.player.hp -= attack;
+                if self.cheats.invincible {
+                    self.player.hp = self.player.hp.max(1);
+                }
+                self.log(format!("The {} hits you.", monster.kind.name()));
+
+                if self.player.hp <= 0 {
+                    self.killed_by = Some(monster.kind.name());
+                    self.log("You die...".to_string());
+                    return;
+                }
+                continue;
+            }
+
+            let sees_player = self.visible.get(mx, my) == Some(&true);
+            let step = if sees_player {
+                // The first step of the shortest way round the walls to the player.
+                let floor = |cell: (i32, i32)| if self.walkable(cell) { Some(1) } else { None };
+                match path::a_star(&floor, Steps::Diagonal, monster.pos, self.player.pos)
+                    .and_then(|path| path.first().copied())
+                {
+                    Some((x, y)) => (x - mx, y - my),
+                    None => ((px - mx).signum(), (py - my).signum()),
+                }
+            } else {
+                (self.rng.gen_range(-1, 2), self.rng.gen_range(-1, 2))
+            };
+
+            // Try the direct step first, then each axis alone to slide around corners.
+            for &(dx, dy) in &[step, (step.0, 0), (0, step.1)] {
+                let target = (mx + dx, my + dy);
+                if (dx, dy) != (0, 0)
+                    && target != self.player.pos
+                    && self.walkable(target)
+                    && self.monster_at(target).is_none()
+                {
+                    self.monsters[i].pos = target;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Writes a morgue file describing the finished run to the shared data directory.
+    fn write_morgue(&self) -> std::io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let dir = common::data_dir()?.join("morgue");
+        fs::create_dir_all(&dir)?;
+
+        let mut morgue = format!(
+            "Killed by a {} on depth {} after {} turns.\n\nSeed: {}\nKills: {}\nAttack: {}\nMax HP: {}\nPotions left: {}\n\nLast messages:\n",
+            self.killed_by.unwrap_or("mystery"),
+            self.depth,
+            self.turns,
+            self.seed,
+            self.kills,
+            self.player.attack,
+            self.player.max_hp,
+            self.player.potions
+        );
+        for message in self.messages.iter() {
+            morgue.push_str(message);
+            morgue.push('\n');
+        }
+
+        fs::write(dir.join(format!("{}.txt", timestamp)), morgue)
+    }
+}
+
+/// Draws a single character glyph in the map cell at `pos`.
+fn draw_glyph(ctx: &mut Context, glyph: &str, pos: (i32, i32), color: [f32; 4]) -> GameResult<()> {
+    let text = Text::new(
+        TextFragment::new(glyph)
+            .color(color.into())
+            .scale(Scale::uniform(CELL_SIZE)),
+    );
+    graphics::draw(
+        ctx,
+        &text,
+        (ggez::mint::Point2 {
+            x: pos.0 as f32 * CELL_SIZE + 3.0,
+            y: pos.1 as f32 * CELL_SIZE,
+        },),
+    )
+}
+
+/// The state for the game.
+struct GameState {
+    run: Run,
+    cheats: Cheats,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state, using the `--seed` argument for the first run if given.
+    pub fn new() -> Self {
+        let seed = args::get("seed").unwrap_or_else(|| rand::thread_rng().gen());
+
+        let cheats = Cheats::requested();
+
+        GameState {
+            run: Run::new(seed, cheats),
+            cheats,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    /// Records the finished run, unless cheats made it easier.
+    fn record_run(&self) {
+        if self.cheats != Cheats::default() {
+            return;
+        }
+        let values = [
+            ("depth", f64::from(self.run.depth)),
+            ("kills", f64::from(self.run.kills)),
+            ("turns", f64::from(self.run.turns)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("roguelike", &values);
+        }
+        let _ = achievements::check_run("roguelike", &values);
+    }
+
+    fn draw_map(&self, ctx: &mut Context) -> GameResult<()> {
+        let run = &self.run;
+
+        for (x, y, tile) in run.floor.tiles.iter() {
+            if run.explored.get(x, y) != Some(&true) {
+                continue;
+            }
+            let lit = run.visible.get(x, y) == Some(&true);
+
+            let color = match (tile, lit) {
+                (Tile::Wall, true) => [0.5, 0.45, 0.4, 1.0],
+                (Tile::Wall, false) => [0.2, 0.2, 0.25, 1.0],
+                (_, true) => [0.2, 0.18, 0.15, 1.0],
+                (_, false) => [0.08, 0.08, 0.1, 1.0],
+            };
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(
+                    x as f32 * CELL_SIZE,
+                    y as f32 * CELL_SIZE,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                ),
+                color.into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+            if *tile == Tile::Stairs {
+                draw_glyph(ctx, ">", (x, y), [1.0, 1.0, 1.0, 1.0])?;
+            }
+        }
+
+        for item in run.items.iter() {
+            if run.visible.get(item.pos.0, item.pos.1) == Some(&true) {
+                let (glyph, color) = match item.kind {
+                    ItemKind::Potion => ("!", [1.0, 0.3, 0.8, 1.0]),
+                    ItemKind::Sword => ("/", [0.6, 0.8, 1.0, 1.0]),
+                };
+                draw_glyph(ctx, glyph, item.pos, color)?;
+            }
+        }
+
+        for monster in run.monsters.iter() {
+            if run.visible.get(monster.pos.0, monster.pos.1) == Some(&true) {
+                draw_glyph(ctx, monster.kind.glyph(), monster.pos, [1.0, 0.3, 0.2, 1.0])?;
+            }
+        }
+
+        draw_glyph(ctx, "@", run.player.pos, [1.0, 1.0, 0.3, 1.0])
+    }
+
+    fn draw_hud(&self, ctx: &mut Context) -> GameResult<()> {
+        let run = &self.run;
+
+        let mut hud = format!(
+            "Depth: {}  HP: {}/{}  Attack: {}  Potions: {}  Kills: {}  Turn: {}\n",
+            run.depth,
+            run.player.hp.max(0),
+            run.player.max_hp,
+            run.player.attack,
+            run.player.potions,
+            run.kills,
+            run.turns
+        );
+        for message in run.messages.iter() {
+            hud.push_str(message);
+            hud.push('\n');
+        }
+        if run.is_over() {
+            hud.push_str("Your morgue file has been written. Press Enter to start a new run.");
+        }
+
+        let text = Text::new(
+            TextFragment::new(hud)
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(16.0)),
+        );
+        graphics::draw(
+            ctx,
+            &text,
+            (ggez::mint::Point2 {
+                x: 8.0,
+                y: MAP_SIZE.1 as f32 * CELL_SIZE + 4.0,
+            },),
+        )
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        // The game only advances in response to the player's input.
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
+        self.draw_map(ctx)?;
+        self.draw_hud(ctx)?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+            return;
+        }
+
+        if self.run.is_over() {
+            if keycode == KeyCode::Return {
+                self.run = Run::new(rand::thread_rng().gen(), self.cheats);
+            }
+            return;
+        }
+
+        match keycode {
+            KeyCode::Up | KeyCode::K => self.run.player_move(0, -1),
+            KeyCode::Down | KeyCode::J => self.run.player_move(0, 1),
+            KeyCode::Left | KeyCode::H => self.run.player_move(-1, 0),
+            KeyCode::Right | KeyCode::L => self.run.player_move(1, 0),
+            KeyCode::Y => self.run.player_move(-1, -1),
+            KeyCode::U => self.run.player_move(1, -1),
+            KeyCode::B => self.run.player_move(-1, 1),
+            KeyCode::N => self.run.player_move(1, 1),
+            KeyCode::Period => self.run.end_turn(),
+            KeyCode::Q => self.run.quaff(),
+            KeyCode::Return => self.run.take_stairs(),
+            _ => (),
+        }
+
+        // Permadeath: once the run is over its record is written and it can't be continued.
+        if self.run.is_over() {
+            if let Err(e) = self.run.write_morgue() {
+                eprintln!("Failed to write morgue file: {}", e);
+            }
+            self.record_run();
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("roguelike", "Sprial404")
+        .window_setup(window::setup("Roguelike"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
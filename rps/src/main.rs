@@ -0,0 +1,382 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{Scale, Text, TextFragment};
+use ggez::{event, graphics, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+
+mod strategy;
+
+use strategy::{Analysis, Hand, Strategy};
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+/// The match lengths that can be picked on the menu.
+const BEST_OF: [u32; 4] = [3, 5, 7, 9];
+
+/// The opponents of a tournament, in the order they are faced.
+const OPPONENTS: [Strategy; 4] = [
+    Strategy::Random,
+    Strategy::Frequency,
+    Strategy::Markov,
+    Strategy::Adaptive,
+];
+
+/// How many past rounds are shown in the history strip.
+const SHOWN_ROUNDS: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Phase {
+    /// Choosing the match length.
+    Menu,
+    Playing,
+    /// A match has been decided and the player can carry on to the next one.
+    MatchOver,
+    TournamentOver,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Round {
+    player: Hand,
+    computer: Hand,
+}
+
+impl Round {
+    fn player_won(&self) -> bool {
+        self.player.beats(self.computer)
+    }
+
+    fn computer_won(&self) -> bool {
+        self.computer.beats(self.player)
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    phase: Phase,
+    best_of_index: usize,
+    /// The opponent currently being played, as an index into `OPPONENTS`.
+    opponent: usize,
+    /// Rounds won in the current match by the player and the computer.
+    match_score: (u32, u32),
+    /// Every round played this session, which the computer learns from across matches.
+    rounds: Vec<Round>,
+    /// Every hand the player has shown this session.
+    history: Vec<Hand>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the menu.
+    pub fn new() -> Self {
+        GameState {
+            phase: Phase::Menu,
+            best_of_index: 1,
+            opponent: 0,
+            match_score: (0, 0),
+            rounds: Vec::new(),
+            history: Vec::new(),
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn best_of(&self) -> u32 {
+        BEST_OF[self.best_of_index]
+    }
+
+    fn wins_needed(&self) -> u32 {
+        self.best_of() / 2 + 1
+    }
+
+    fn start_tournament(&mut self) {
+        self.opponent = 0;
+        self.match_score = (0, 0);
+        self.phase = Phase::Playing;
+    }
+
+    fn throw(&mut self, hand: Hand) {
+        let strategy = OPPONENTS[self.opponent];
+        let computer = strategy.choose(&self.history);
+        let round = Round {
+            player: hand,
+            computer,
+        };
+        self.history.push(hand);
+        self.rounds.push(round);
+
+        // Drawn rounds don't count towards the match.
+        if round.player_won() {
+            self.match_score.0 += 1;
+        } else if round.computer_won() {
+            self.match_score.1 += 1;
+        }
+
+        let needed = self.wins_needed();
+        if self.match_score.0 >= needed {
+            if self.opponent + 1 == OPPONENTS.len() {
+                self.finish_tournament();
+            } else {
+                self.phase = Phase::MatchOver;
+            }
+        } else if self.match_score.1 >= needed {
+            self.finish_tournament();
+        }
+    }
+
+    fn next_match(&mut self) {
+        self.opponent += 1;
+        self.match_score = (0, 0);
+        self.phase = Phase::Playing;
+    }
+
+    fn champion(&self) -> bool {
+        self.opponent + 1 == OPPONENTS.len() && self.match_score.0 >= self.wins_needed()
+    }
+
+    fn finish_tournament(&mut self) {
+        self.phase = Phase::TournamentOver;
+
+        let matches_won = if self.champion() {
+            OPPONENTS.len()
+        } else {
+            self.opponent
+        };
+        let analysis = Analysis::new(&self.history);
+        let values = [
+            ("matches_won", matches_won as f64),
+            ("best_of", self.best_of() as f64),
+            ("randomness", analysis.randomness as f64),
+            ("exploitability", analysis.pattern_exploit as f64),
+            ("won", if self.champion() { 1.0 } else { 0.0 }),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("rps", &values);
+        }
+        let _ = achievements::check_run("rps", &values);
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_statistics(&self, ctx: &mut Context) -> GameResult<()> {
+        let analysis = Analysis::new(&self.history);
+        let [rock, paper, scissors] = analysis.frequencies;
+        let text = format!(
+            "YOUR STATISTICS\n\nRounds: {}\nRock {:.0}%  Paper {:.0}%  Scissors {:.0}%\n\
+             Randomness: {:.0}%\nFrequency guesses you: {:.0}%\nMarkov guesses you: {:.0}%\n\n\
+             Exploitable by a fixed hand: {:+.2}\nExploitable by your patterns: {:+.2}\n\
+             (expected points per round, 0 is unexploitable)",
+            analysis.rounds,
+            rock * 100.0,
+            paper * 100.0,
+            scissors * 100.0,
+            analysis.randomness * 100.0,
+            analysis.frequency_hits * 100.0,
+            analysis.markov_hits * 100.0,
+            analysis.fixed_exploit,
+            analysis.pattern_exploit,
+        );
+        self.draw_text(ctx, &text, [0.7, 0.85, 1.0, 1.0], 20.0, 420.0, 140.0)
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.12, 0.1, 0.15, 1.0].into());
+
+        if self.phase == Phase::Menu {
+            self.draw_text(
+                ctx,
+                "ROCK PAPER SCISSORS",
+                [1.0, 1.0, 1.0, 1.0],
+                48.0,
+                160.0,
+                120.0,
+            )?;
+            let opponents: Vec<String> = OPPONENTS
+                .iter()
+                .map(|strategy| format!("{}: {}", strategy.name(), strategy.description()))
+                .collect();
+            self.draw_text(
+                ctx,
+                &format!(
+                    "Best of < {} >\n\nPress Enter to start the tournament against:\n{}",
+                    self.best_of(),
+                    opponents.join("\n")
+                ),
+                [0.8, 0.8, 0.8, 1.0],
+                24.0,
+                120.0,
+                220.0,
+            )?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        let strategy = OPPONENTS[self.opponent];
+        self.draw_text(
+            ctx,
+            &format!(
+                "Match {} of {}: {} (best of {})\n{}",
+                self.opponent + 1,
+                OPPONENTS.len(),
+                strategy.name(),
+                self.best_of(),
+                strategy.description()
+            ),
+            [1.0, 1.0, 1.0, 1.0],
+            24.0,
+            20.0,
+            20.0,
+        )?;
+        self.draw_text(
+            ctx,
+            &format!("You {} - {} Them", self.match_score.0, self.match_score.1),
+            [1.0, 0.85, 0.3, 1.0],
+            40.0,
+            20.0,
+            100.0,
+        )?;
+
+        if let Some(round) = self.rounds.last() {
+            let result = if round.player_won() {
+                "You win the round!"
+            } else if round.computer_won() {
+                "They win the round."
+            } else {
+                "A draw."
+            };
+            self.draw_text(
+                ctx,
+                &format!(
+                    "You: {}\nThem: {}\n{}",
+                    round.player.name(),
+                    round.computer.name(),
+                    result
+                ),
+                [1.0, 1.0, 1.0, 1.0],
+                28.0,
+                20.0,
+                170.0,
+            )?;
+        }
+
+        let recent: Vec<String> = self
+            .rounds
+            .iter()
+            .rev()
+            .take(SHOWN_ROUNDS)
+            .map(|round| {
+                let mark = if round.player_won() {
+                    "W"
+                } else if round.computer_won() {
+                    "L"
+                } else {
+                    "D"
+                };
+                format!(
+                    "{} vs {} {}",
+                    round.player.name(),
+                    round.computer.name(),
+                    mark
+                )
+            })
+            .collect();
+        self.draw_text(
+            ctx,
+            &recent.join("\n"),
+            [0.6, 0.6, 0.6, 1.0],
+            18.0,
+            20.0,
+            290.0,
+        )?;
+
+        self.draw_statistics(ctx)?;
+
+        let prompt = match self.phase {
+            Phase::MatchOver => format!(
+                "You beat {}! Press Enter for the next match.",
+                strategy.name()
+            ),
+            Phase::TournamentOver if self.champion() => {
+                "You won the tournament! Press Enter.".to_string()
+            }
+            Phase::TournamentOver => format!("{} knocked you out. Press Enter.", strategy.name()),
+            _ => "R: Rock  P: Paper  S: Scissors".to_string(),
+        };
+        self.draw_text(
+            ctx,
+            &prompt,
+            [1.0, 0.85, 0.3, 1.0],
+            28.0,
+            20.0,
+            SCREEN_SIZE.1 - 50.0,
+        )?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if repeat {
+            return;
+        }
+        match (self.phase, keycode) {
+            (Phase::Menu, KeyCode::Left) => {
+                self.best_of_index = self.best_of_index.saturating_sub(1);
+            }
+            (Phase::Menu, KeyCode::Right) => {
+                self.best_of_index = (self.best_of_index + 1).min(BEST_OF.len() - 1);
+            }
+            (Phase::Menu, KeyCode::Return) => self.start_tournament(),
+            (Phase::Playing, KeyCode::R) => self.throw(Hand::Rock),
+            (Phase::Playing, KeyCode::P) => self.throw(Hand::Paper),
+            (Phase::Playing, KeyCode::S) => self.throw(Hand::Scissors),
+            (Phase::MatchOver, KeyCode::Return) => self.next_match(),
+            (Phase::TournamentOver, KeyCode::Return) => self.phase = Phase::Menu,
+            (_, KeyCode::Escape) => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("rps", "Sprial404")
+        .window_setup(window::setup("Rock Paper Scissors"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
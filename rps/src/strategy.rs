@@ -0,0 +1,240 @@
+//! The hands, the computer's prediction strategies and the analysis of how predictable the
+//! player is.
+
+use rand::seq::SliceRandom;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hand {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+pub const HANDS: [Hand; 3] = [Hand::Rock, Hand::Paper, Hand::Scissors];
+
+impl Hand {
+    pub fn index(self) -> usize {
+        match self {
+            Hand::Rock => 0,
+            Hand::Paper => 1,
+            Hand::Scissors => 2,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Hand::Rock => "Rock",
+            Hand::Paper => "Paper",
+            Hand::Scissors => "Scissors",
+        }
+    }
+
+    /// The hand that beats this one.
+    pub fn counter(self) -> Hand {
+        match self {
+            Hand::Rock => Hand::Paper,
+            Hand::Paper => Hand::Scissors,
+            Hand::Scissors => Hand::Rock,
+        }
+    }
+
+    pub fn beats(self, other: Hand) -> bool {
+        other.counter() == self
+    }
+
+    pub fn random() -> Hand {
+        *HANDS.choose(&mut rand::thread_rng()).unwrap_or(&Hand::Rock)
+    }
+}
+
+/// How the computer picks its hand, given every hand the player has shown so far.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Strategy {
+    /// Plays uniformly at random and can't be exploited, or exploit anyone.
+    Random,
+    /// Counters the hand the player has shown most often recently.
+    Frequency,
+    /// Counters the hand the player most often follows their last hand with.
+    Markov,
+    /// Uses whichever of the other predictors has been right most often lately.
+    Adaptive,
+}
+
+/// How many recent hands the frequency predictor looks at.
+const FREQUENCY_WINDOW: usize = 20;
+/// How many recent rounds the adaptive strategy scores its predictors over.
+const ADAPTIVE_WINDOW: usize = 12;
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Random => "Random Randy",
+            Strategy::Frequency => "Frequency Fran",
+            Strategy::Markov => "Markov Max",
+            Strategy::Adaptive => "Adaptive Ada",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Strategy::Random => "Throws at random.",
+            Strategy::Frequency => "Counters your favourite hand.",
+            Strategy::Markov => "Learns what you play after each hand.",
+            Strategy::Adaptive => "Switches to whichever prediction is working.",
+        }
+    }
+
+    /// Picks the computer's hand against a player who has shown `history`.
+    pub fn choose(self, history: &[Hand]) -> Hand {
+        let prediction = match self {
+            Strategy::Random => None,
+            Strategy::Frequency => predict_frequency(history),
+            Strategy::Markov => predict_markov(history),
+            Strategy::Adaptive => predict_adaptive(history),
+        };
+        prediction.map_or_else(Hand::random, Hand::counter)
+    }
+}
+
+/// The index of the largest count, or `None` if every count is zero.
+fn most_common(counts: [u32; 3]) -> Option<Hand> {
+    let (index, &count) = counts.iter().enumerate().max_by_key(|&(_, count)| *count)?;
+    if count == 0 {
+        None
+    } else {
+        Some(HANDS[index])
+    }
+}
+
+fn predict_frequency(history: &[Hand]) -> Option<Hand> {
+    let mut counts = [0; 3];
+    for hand in history.iter().rev().take(FREQUENCY_WINDOW) {
+        counts[hand.index()] += 1;
+    }
+    most_common(counts)
+}
+
+/// Counts how often each hand followed each other hand.
+fn transitions(history: &[Hand]) -> [[u32; 3]; 3] {
+    let mut counts = [[0; 3]; 3];
+    for pair in history.windows(2) {
+        counts[pair[0].index()][pair[1].index()] += 1;
+    }
+    counts
+}
+
+fn predict_markov(history: &[Hand]) -> Option<Hand> {
+    let last = history.last()?;
+    most_common(transitions(history)[last.index()])
+}
+
+/// Predicts the player will play what they just played.
+fn predict_repeat(history: &[Hand]) -> Option<Hand> {
+    history.last().cloned()
+}
+
+fn predict_adaptive(history: &[Hand]) -> Option<Hand> {
+    let predictors: [fn(&[Hand]) -> Option<Hand>; 3] =
+        [predict_frequency, predict_markov, predict_repeat];
+    let start = history.len().saturating_sub(ADAPTIVE_WINDOW);
+
+    let best = predictors.iter().max_by_key(|predict| {
+        (start..history.len())
+            .filter(|&i| predict(&history[..i]) == Some(history[i]))
+            .count()
+    })?;
+    best(history)
+}
+
+/// How predictable the player has been over their whole history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub rounds: usize,
+    /// How often the player showed each hand, as fractions.
+    pub frequencies: [f32; 3],
+    /// The entropy of the player's hands relative to playing perfectly at random, from 0 to 1.
+    pub randomness: f32,
+    /// How often the frequency and Markov predictors would have guessed the next hand.
+    pub frequency_hits: f32,
+    pub markov_hits: f32,
+    /// The expected score per round of the best fixed hand against the player, from 0 to 1.
+    pub fixed_exploit: f32,
+    /// The expected score per round of the best reply to each of the player's previous hands.
+    pub pattern_exploit: f32,
+}
+
+/// The best expected score per round of a single hand against the given counts of hands.
+fn best_reply_edge(counts: [u32; 3]) -> f32 {
+    let total: u32 = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    HANDS
+        .iter()
+        .map(|reply| {
+            let wins: u32 = HANDS
+                .iter()
+                .filter(|hand| reply.beats(**hand))
+                .map(|hand| counts[hand.index()])
+                .sum();
+            let losses: u32 = HANDS
+                .iter()
+                .filter(|hand| hand.beats(*reply))
+                .map(|hand| counts[hand.index()])
+                .sum();
+            (wins as f32 - losses as f32) / total as f32
+        })
+        .fold(0.0, f32::max)
+}
+
+/// How often `predict` would have guessed each of the player's hands from the ones before it.
+fn hit_rate(history: &[Hand], predict: fn(&[Hand]) -> Option<Hand>) -> f32 {
+    if history.len() < 2 {
+        return 0.0;
+    }
+    let hits = (1..history.len())
+        .filter(|&i| predict(&history[..i]) == Some(history[i]))
+        .count();
+    hits as f32 / (history.len() - 1) as f32
+}
+
+impl Analysis {
+    pub fn new(history: &[Hand]) -> Self {
+        let mut counts = [0; 3];
+        for hand in history {
+            counts[hand.index()] += 1;
+        }
+
+        let total = history.len().max(1) as f32;
+        let mut frequencies = [0.0; 3];
+        for (frequency, &count) in frequencies.iter_mut().zip(counts.iter()) {
+            *frequency = count as f32 / total;
+        }
+        let entropy: f32 = frequencies
+            .iter()
+            .filter(|&&p| p > 0.0)
+            .map(|&p| -p * p.log2())
+            .sum();
+
+        // Weight the best reply after each hand by how often that hand came up.
+        let transitions = transitions(history);
+        let pairs = history.len().saturating_sub(1).max(1) as f32;
+        let pattern_exploit = transitions
+            .iter()
+            .map(|row| {
+                let count: u32 = row.iter().sum();
+                best_reply_edge(*row) * count as f32 / pairs
+            })
+            .sum();
+
+        Analysis {
+            rounds: history.len(),
+            frequencies,
+            randomness: entropy / 3f32.log2(),
+            frequency_hits: hit_rate(history, predict_frequency),
+            markov_hits: hit_rate(history, predict_markov),
+            fixed_exploit: best_reply_edge(counts),
+            pattern_exploit,
+        }
+    }
+}
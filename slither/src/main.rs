@@ -0,0 +1,702 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::physics::{self, Segment, Vec2};
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (1024.0, 768.0);
+
+const ARENA_RADIUS: f32 = 1600.0;
+
+const AI_SNAKES: usize = 10;
+const PELLETS: usize = 800;
+
+const SPEED: f32 = 150.0;
+const BOOST_SPEED: f32 = 300.0;
+/// How quickly snakes can turn, in radians per second.
+const TURN_RATE: f32 = 4.0;
+/// How much mass boosting burns per second.
+const BOOST_COST: f32 = 4.0;
+/// Snakes can't boost below this mass.
+const MIN_BOOST_MASS: f32 = 12.0;
+
+const START_MASS: f32 = 10.0;
+/// The distance between the points of a snake's body polyline.
+const POINT_SPACING: f32 = 8.0;
+const BASE_LENGTH: f32 = 60.0;
+const LENGTH_PER_MASS: f32 = 6.0;
+const BASE_RADIUS: f32 = 7.0;
+
+/// How far from its head a snake pulls pellets in, as a multiple of its radius.
+const SUCTION_RANGE: f32 = 5.0;
+const SUCTION_SPEED: f32 = 350.0;
+/// How much of a dead snake's mass is left behind as pellets.
+const DEATH_DROP: f32 = 0.8;
+
+/// How far ahead AI snakes look for danger.
+const AI_LOOKAHEAD: [f32; 3] = [30.0, 70.0, 120.0];
+/// How far away AI snakes notice pellets.
+const AI_SIGHT: f32 = 400.0;
+
+const SNAKE_COLORS: [[f32; 4]; 6] = [
+    [0.3, 0.9, 0.4, 1.0],
+    [0.9, 0.3, 0.3, 1.0],
+    [0.3, 0.6, 1.0, 1.0],
+    [1.0, 0.8, 0.2, 1.0],
+    [0.8, 0.4, 1.0, 1.0],
+    [0.2, 0.9, 0.9, 1.0],
+];
+
+const AI_NAMES: [&str; 10] = [
+    "Hissy", "Noodle", "Slinky", "Wiggles", "Zigzag", "Ribbon", "Sprout", "Twister", "Loop", "Dash",
+];
+
+fn random_point(rng: &mut impl Rng, radius: f32) -> Vec2 {
+    // Take the square root so the points are spread evenly over the disc.
+    let distance = radius * rng.gen_range(0.0f32, 1.0).sqrt();
+    Vec2::from_angle(rng.gen_range(0.0, 2.0 * PI)) * distance
+}
+
+/// The signed difference between two angles, wrapped to `-PI..PI`.
+fn angle_difference(from: f32, to: f32) -> f32 {
+    let mut diff = (to - from) % (2.0 * PI);
+    if diff > PI {
+        diff -= 2.0 * PI;
+    } else if diff < -PI {
+        diff += 2.0 * PI;
+    }
+    diff
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Pellet {
+    pos: Vec2,
+    mass: f32,
+    color: [f32; 4],
+}
+
+/// A snake moving freely around the arena.
+#[derive(Debug, Clone)]
+struct Snake {
+    name: String,
+    /// The body as a polyline from the head to the tail.
+    body: VecDeque<Vec2>,
+    heading: f32,
+    /// The heading the snake is turning towards.
+    target_heading: f32,
+    mass: f32,
+    boosting: bool,
+    color: [f32; 4],
+    is_ai: bool,
+    alive: bool,
+    /// How much boosting has burned since the last pellet was dropped behind the snake.
+    burned: f32,
+}
+
+impl Snake {
+    fn new(name: &str, pos: Vec2, color: [f32; 4], is_ai: bool) -> Self {
+        let heading = rand::thread_rng().gen_range(0.0, 2.0 * PI);
+        let mut body = VecDeque::new();
+        body.push_back(pos);
+        Snake {
+            name: name.to_string(),
+            body,
+            heading,
+            target_heading: heading,
+            mass: START_MASS,
+            boosting: false,
+            color,
+            is_ai,
+            alive: true,
+            burned: 0.0,
+        }
+    }
+
+    fn head(&self) -> Vec2 {
+        self.body[0]
+    }
+
+    fn radius(&self) -> f32 {
+        BASE_RADIUS + self.mass.sqrt() * 0.6
+    }
+
+    fn length(&self) -> f32 {
+        BASE_LENGTH + self.mass * LENGTH_PER_MASS
+    }
+
+    fn segments(&self) -> impl Iterator<Item = Segment> + '_ {
+        self.body
+            .iter()
+            .zip(self.body.iter().skip(1))
+            .map(|(a, b)| Segment::new(*a, *b))
+    }
+
+    /// Turns and moves the snake, returning the position of a pellet to drop if boosting
+    /// burned enough mass for one.
+    fn advance(&mut self, dt: f32) -> Option<Pellet> {
+        let turn = angle_difference(self.heading, self.target_heading);
+        let max_turn = TURN_RATE * dt;
+        self.heading += turn.max(-max_turn).min(max_turn);
+
+        let boosting = self.boosting && self.mass > MIN_BOOST_MASS;
+        let speed = if boosting { BOOST_SPEED } else { SPEED };
+        let head = self.head() + Vec2::from_angle(self.heading) * (speed * dt);
+
+        // Only lay down a new point once the head has moved far enough from the last one.
+        if self.body.len() > 1 && (self.body[1] - head).length() < POINT_SPACING {
+            self.body[0] = head;
+        } else {
+            self.body.push_front(head);
+        }
+        self.trim();
+
+        if !boosting {
+            return None;
+        }
+        self.mass -= BOOST_COST * dt;
+        self.burned += BOOST_COST * dt;
+        if self.burned < 1.0 {
+            return None;
+        }
+        self.burned -= 1.0;
+        self.body.back().map(|&pos| Pellet {
+            pos,
+            mass: 1.0,
+            color: self.color,
+        })
+    }
+
+    /// Cuts the tail so the body is no longer than the snake's length.
+    fn trim(&mut self) {
+        let length = self.length();
+        let mut travelled = 0.0;
+        for i in 1..self.body.len() {
+            let span = (self.body[i] - self.body[i - 1]).length();
+            if travelled + span > length {
+                let direction = (self.body[i] - self.body[i - 1]).normalize();
+                self.body[i] = self.body[i - 1] + direction * (length - travelled);
+                self.body.truncate(i + 1);
+                return;
+            }
+            travelled += span;
+        }
+    }
+
+    /// Whether a circle touches this snake's body.
+    fn touches(&self, center: Vec2, radius: f32) -> bool {
+        let reach = radius + self.radius();
+        // Skip snakes that are too far away for any part of their body to touch.
+        if (self.head() - center).length() > self.length() + reach {
+            return false;
+        }
+        self.segments().any(|segment| {
+            physics::circle_segment(center, radius, &segment, self.radius()).is_some()
+        })
+    }
+}
+
+/// The state for the game.
+struct GameState {
+    snakes: Vec<Snake>,
+    pellets: Vec<Pellet>,
+    /// The mouse position relative to the centre of the screen.
+    mouse: Vec2,
+    boost_held: bool,
+    /// The player's best mass this life.
+    best_mass: f32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a fresh arena.
+    pub fn new() -> Self {
+        let mut state = GameState {
+            snakes: Vec::new(),
+            pellets: Vec::new(),
+            mouse: Vec2::new(1.0, 0.0),
+            boost_held: false,
+            best_mass: START_MASS,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        };
+        state.restart();
+        state
+    }
+
+    fn restart(&mut self) {
+        let mut rng = rand::thread_rng();
+        self.snakes.clear();
+        self.snakes
+            .push(Snake::new("You", Vec2::ZERO, SNAKE_COLORS[0], false));
+        for name in AI_NAMES.iter().take(AI_SNAKES) {
+            let color = SNAKE_COLORS[rng.gen_range(1, SNAKE_COLORS.len())];
+            let pos = random_point(&mut rng, ARENA_RADIUS * 0.9);
+            self.snakes.push(Snake::new(name, pos, color, true));
+        }
+
+        self.pellets.clear();
+        self.fill_pellets();
+        self.best_mass = START_MASS;
+        self.gameover = false;
+    }
+
+    fn fill_pellets(&mut self) {
+        let mut rng = rand::thread_rng();
+        while self.pellets.len() < PELLETS {
+            let color = SNAKE_COLORS[rng.gen_range(0, SNAKE_COLORS.len())];
+            self.pellets.push(Pellet {
+                pos: random_point(&mut rng, ARENA_RADIUS),
+                mass: rng.gen_range(0.5, 1.5),
+                color,
+            });
+        }
+    }
+
+    fn player(&self) -> &Snake {
+        &self.snakes[0]
+    }
+
+    /// Whether a circle at `point` would hit the arena wall or any snake other than `index`.
+    fn is_dangerous(&self, index: usize, point: Vec2, radius: f32) -> bool {
+        point.length() + radius > ARENA_RADIUS
+            || self
+                .snakes
+                .iter()
+                .enumerate()
+                .any(|(i, snake)| i != index && snake.alive && snake.touches(point, radius))
+    }
+
+    /// Steers an AI snake towards food, turning away from anything in its path.
+    fn steer_ai(&mut self, index: usize) {
+        let snake = &self.snakes[index];
+        let head = snake.head();
+        let radius = snake.radius();
+
+        let mut target = snake.heading;
+        if let Some(pellet) = self
+            .pellets
+            .iter()
+            .filter(|pellet| (pellet.pos - head).length() < AI_SIGHT)
+            .min_by(|a, b| {
+                let a = (a.pos - head).length() / a.mass;
+                let b = (b.pos - head).length() / b.mass;
+                a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+            })
+        {
+            target = (pellet.pos - head).angle();
+        } else if head.length() > ARENA_RADIUS * 0.7 {
+            target = (-head).angle();
+        }
+
+        // Try the desired heading first, then increasingly sharp turns either way.
+        let clear = |heading: f32| {
+            AI_LOOKAHEAD.iter().all(|&distance| {
+                let probe = head + Vec2::from_angle(heading) * distance;
+                !self.is_dangerous(index, probe, radius)
+            })
+        };
+        let options = [0.0, 0.5, -0.5, 1.0, -1.0, 1.6, -1.6, 2.4, -2.4];
+        let heading = options
+            .iter()
+            .map(|offset| target + offset)
+            .find(|&heading| clear(heading))
+            .unwrap_or(target);
+
+        // Boost towards big meals now and then.
+        let boosting =
+            snake.mass > MIN_BOOST_MASS * 2.0 && rand::thread_rng().gen_range(0, 200) == 0;
+        let snake = &mut self.snakes[index];
+        snake.target_heading = heading;
+        snake.boosting = boosting || (snake.boosting && snake.mass > MIN_BOOST_MASS * 1.5);
+    }
+
+    fn tick(&mut self, dt: f32) {
+        let player_heading = self.mouse.angle();
+        {
+            let boost_held = self.boost_held;
+            let player = &mut self.snakes[0];
+            player.target_heading = player_heading;
+            player.boosting = boost_held;
+        }
+        for i in 1..self.snakes.len() {
+            if self.snakes[i].alive {
+                self.steer_ai(i);
+            }
+        }
+
+        for snake in self.snakes.iter_mut().filter(|snake| snake.alive) {
+            if let Some(pellet) = snake.advance(dt) {
+                self.pellets.push(pellet);
+            }
+        }
+
+        self.absorb_pellets(dt);
+        self.check_deaths();
+        self.respawn_ai();
+        self.fill_pellets();
+
+        let mass = self.player().mass;
+        self.best_mass = self.best_mass.max(mass);
+    }
+
+    /// Pulls nearby pellets towards the snakes' heads and eats the ones that reach them.
+    fn absorb_pellets(&mut self, dt: f32) {
+        let heads: Vec<(Vec2, f32)> = self
+            .snakes
+            .iter()
+            .map(|snake| {
+                if snake.alive {
+                    (snake.head(), snake.radius())
+                } else {
+                    (Vec2::new(f32::INFINITY, f32::INFINITY), 0.0)
+                }
+            })
+            .collect();
+
+        let mut eaten = vec![0.0; self.snakes.len()];
+        let mut i = 0;
+        while i < self.pellets.len() {
+            let pellet = &mut self.pellets[i];
+            let closest = heads
+                .iter()
+                .enumerate()
+                .map(|(s, &(head, radius))| (s, head, radius, (head - pellet.pos).length()))
+                .filter(|&(_, _, radius, distance)| distance < radius * SUCTION_RANGE)
+                .min_by(|a, b| a.3.partial_cmp(&b.3).unwrap_or(std::cmp::Ordering::Equal));
+
+            if let Some((snake, head, radius, distance)) = closest {
+                if distance < radius {
+                    eaten[snake] += pellet.mass;
+                    self.pellets.swap_remove(i);
+                    continue;
+                }
+                let pull = (SUCTION_SPEED * dt).min(distance);
+                pellet.pos += (head - pellet.pos).normalize() * pull;
+            }
+            i += 1;
+        }
+
+        for (snake, mass) in self.snakes.iter_mut().zip(eaten) {
+            snake.mass += mass;
+        }
+    }
+
+    /// Kills every snake whose head ran into another snake's body or out of the arena.
+    fn check_deaths(&mut self) {
+        let dead: Vec<usize> = (0..self.snakes.len())
+            .filter(|&i| {
+                let snake = &self.snakes[i];
+                snake.alive && self.is_dangerous(i, snake.head(), snake.radius() * 0.6)
+            })
+            .collect();
+
+        for i in dead {
+            let snake = &mut self.snakes[i];
+            snake.alive = false;
+
+            // Scatter what the snake was made of along its body.
+            let points = snake.body.len().max(1);
+            let per_point = snake.mass * DEATH_DROP / points as f32;
+            let mut rng = rand::thread_rng();
+            for &pos in snake.body.iter() {
+                let jitter = Vec2::new(rng.gen_range(-6.0, 6.0), rng.gen_range(-6.0, 6.0));
+                self.pellets.push(Pellet {
+                    pos: pos + jitter,
+                    mass: per_point,
+                    color: snake.color,
+                });
+            }
+
+            if i == 0 {
+                self.gameover = true;
+            }
+        }
+        if self.gameover {
+            self.record_life();
+        }
+    }
+
+    fn record_life(&self) {
+        // Recorded as the length shown when the player is eaten.
+        let values = [("length", f64::from(self.best_mass * 10.0).round())];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("slither", &values);
+        }
+        let _ = achievements::check_run("slither", &values);
+    }
+
+    fn respawn_ai(&mut self) {
+        let mut rng = rand::thread_rng();
+        for i in 1..self.snakes.len() {
+            if self.snakes[i].alive {
+                continue;
+            }
+            // Find a spot away from everyone else.
+            for _ in 0..10 {
+                let pos = random_point(&mut rng, ARENA_RADIUS * 0.9);
+                if !self.is_dangerous(i, pos, 100.0) {
+                    let name = self.snakes[i].name.clone();
+                    let color = self.snakes[i].color;
+                    self.snakes[i] = Snake::new(&name, pos, color, true);
+                    break;
+                }
+            }
+        }
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    /// Draws the whole arena as one batched mesh, skipping anything off screen.
+    fn draw_world(&self, ctx: &mut Context) -> GameResult<()> {
+        let camera = self.player().head();
+        let half = Vec2::new(SCREEN_SIZE.0 / 2.0, SCREEN_SIZE.1 / 2.0);
+        let on_screen = |pos: Vec2, margin: f32| {
+            (pos.x - camera.x).abs() < half.x + margin && (pos.y - camera.y).abs() < half.y + margin
+        };
+
+        let mut builder = graphics::MeshBuilder::new();
+        builder.circle(
+            DrawMode::fill(),
+            ggez::mint::Point2 { x: 0.0, y: 0.0 },
+            ARENA_RADIUS,
+            2.0,
+            [0.08, 0.1, 0.12, 1.0].into(),
+        );
+        builder.circle(
+            DrawMode::stroke(6.0),
+            ggez::mint::Point2 { x: 0.0, y: 0.0 },
+            ARENA_RADIUS,
+            2.0,
+            [0.8, 0.2, 0.2, 1.0].into(),
+        );
+
+        for pellet in self
+            .pellets
+            .iter()
+            .filter(|pellet| on_screen(pellet.pos, 10.0))
+        {
+            builder.circle(
+                DrawMode::fill(),
+                ggez::mint::Point2 {
+                    x: pellet.pos.x,
+                    y: pellet.pos.y,
+                },
+                2.0 + pellet.mass * 2.0,
+                0.5,
+                pellet.color.into(),
+            );
+        }
+
+        for snake in self.snakes.iter().filter(|snake| snake.alive) {
+            let radius = snake.radius();
+            let [r, g, b, _] = snake.color;
+            // Draw from the tail so the head ends up on top.
+            for (i, pos) in snake.body.iter().enumerate().rev() {
+                if !on_screen(*pos, radius) {
+                    continue;
+                }
+                let shade = if i % 4 < 2 { 1.0 } else { 0.8 };
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 { x: pos.x, y: pos.y },
+                    radius,
+                    0.5,
+                    [r * shade, g * shade, b * shade, 1.0].into(),
+                );
+            }
+
+            let head = snake.head();
+            let forward = Vec2::from_angle(snake.heading);
+            for side in &[-1.0, 1.0] {
+                let eye = head + forward * (radius * 0.4) + forward.perp() * (radius * 0.45 * side);
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 { x: eye.x, y: eye.y },
+                    radius * 0.3,
+                    0.3,
+                    [1.0, 1.0, 1.0, 1.0].into(),
+                );
+            }
+        }
+
+        let mesh = builder.build(ctx)?;
+        let offset = half - camera;
+        graphics::draw(
+            ctx,
+            &mesh,
+            (ggez::mint::Point2 {
+                x: offset.x,
+                y: offset.y,
+            },),
+        )?;
+
+        // Names over the AI snakes.
+        for snake in self
+            .snakes
+            .iter()
+            .filter(|snake| snake.alive && snake.is_ai)
+        {
+            let head = snake.head();
+            if on_screen(head, 0.0) {
+                let pos = head + offset;
+                self.draw_text(
+                    ctx,
+                    &snake.name,
+                    [1.0, 1.0, 1.0, 0.7],
+                    16.0,
+                    pos.x - 20.0,
+                    pos.y - 30.0,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32().min(1.0 / 30.0);
+
+        if !self.gameover {
+            self.tick(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.02, 0.02, 0.03, 1.0].into());
+
+        self.draw_world(ctx)?;
+
+        let mut ranking: Vec<&Snake> = self.snakes.iter().filter(|snake| snake.alive).collect();
+        ranking.sort_by(|a, b| {
+            b.mass
+                .partial_cmp(&a.mass)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let leaderboard: Vec<String> = ranking
+            .iter()
+            .take(5)
+            .enumerate()
+            .map(|(i, snake)| format!("{}. {} {:.0}", i + 1, snake.name, snake.mass * 10.0))
+            .collect();
+        self.draw_text(
+            ctx,
+            &leaderboard.join("\n"),
+            [1.0, 1.0, 1.0, 0.8],
+            20.0,
+            SCREEN_SIZE.0 - 200.0,
+            10.0,
+        )?;
+
+        self.draw_text(
+            ctx,
+            &format!("Length: {:.0}", self.player().mass * 10.0),
+            [1.0, 1.0, 1.0, 1.0],
+            24.0,
+            10.0,
+            SCREEN_SIZE.1 - 34.0,
+        )?;
+
+        if self.gameover {
+            self.draw_text(
+                ctx,
+                &format!(
+                    "You were eaten! Best length: {:.0}\nPress Enter to try again.",
+                    self.best_mass * 10.0
+                ),
+                [1.0, 0.3, 0.3, 1.0],
+                40.0,
+                220.0,
+                320.0,
+            )?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let offset = Vec2::new(x - SCREEN_SIZE.0 / 2.0, y - SCREEN_SIZE.1 / 2.0);
+        if offset.length() > 1.0 {
+            self.mouse = offset;
+        }
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        if button == MouseButton::Left {
+            self.boost_held = true;
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        if button == MouseButton::Left {
+            self.boost_held = false;
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space => self.boost_held = true,
+            KeyCode::Return if self.gameover => self.restart(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _keymod: KeyMods) {
+        if keycode == KeyCode::Space {
+            self.boost_held = false;
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("slither", "Sprial404")
+        .window_setup(window::setup("Slither"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
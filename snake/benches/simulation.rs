@@ -0,0 +1,58 @@
+//! How many steps a second the simulation manages at different lengths of snake, to catch
+//! changes that slow the rules down. Run with `cargo bench -p snake`.
+//!
+//! The board is always `GRID_SIZE`, so only the snake's length changes. It wraps round the
+//! board and can't die, and no more food appears, so it stays the same length throughout.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use snake::{Direction, Simulation, GRID_SIZE};
+
+/// The cells on the board.
+const BOARD: usize = GRID_SIZE.0 as usize * GRID_SIZE.1 as usize;
+/// The lengths of snake to time, up to one filling the board.
+const LENGTHS: &[usize] = &[2, 16, 64, 256, BOARD];
+/// How many steps are timed at once.
+const STEPS: u64 = 100;
+
+fn simulation(length: usize) -> Simulation {
+    let mut sim = Simulation::new(StdRng::seed_from_u64(0), true);
+    sim.snake.grow(length.saturating_sub(sim.snake.length()));
+    sim.god = true;
+    sim.no_spawn = true;
+    sim
+}
+
+fn step(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step");
+    group.throughput(Throughput::Elements(STEPS));
+    for &length in LENGTHS {
+        let sim = simulation(length);
+        group.bench_with_input(BenchmarkId::from_parameter(length), &sim, |b, sim| {
+            b.iter_batched(
+                || sim.clone(),
+                |mut sim| {
+                    for step in 0..STEPS {
+                        // Turn now and then, so the snake doesn't only run along one row.
+                        if step % 10 == 0 {
+                            let dir = if step % 20 == 0 {
+                                Direction::Down
+                            } else {
+                                Direction::Right
+                            };
+                            sim.snake.turn(dir);
+                        }
+                        sim.step();
+                    }
+                    sim
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, step);
+criterion_main!(benches);
@@ -0,0 +1,193 @@
+//! The maze editor, opened with `--edit`, for painting walls, portals, the spawn point and food
+//! spots onto the board with the mouse and saving them in the shared level format.
+
+use ggez::event::{KeyCode, MouseButton};
+use ggez::{graphics, Context, GameResult};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use common::grid::Grid;
+use common::level::Level;
+use common::share;
+use common::theme::Palette;
+use common::ui;
+use snake::{GridPosition, Maze, Tile, GRID_SIZE};
+
+use crate::{draw_maze, GRID_CELL_SIZE, SCREEN_SIZE};
+
+/// Where `--edit` saves when it isn't given a file.
+pub fn default_path() -> io::Result<PathBuf> {
+    Ok(share::levels_dir("snake")?.join("maze.txt"))
+}
+
+/// Reads a maze from a level file.
+pub fn load(path: &Path) -> Result<Maze, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let level = Level::parse(&contents)?;
+    let rows: Vec<Vec<char>> = (0..level.tiles.height() as i32)
+        .map(|y| {
+            (0..level.tiles.width() as i32)
+                .filter_map(|x| level.tiles.get(x, y).copied())
+                .collect()
+        })
+        .collect();
+    Maze::from_rows(&rows)
+}
+
+/// The tiles the number keys pick, in order. Portals take the next free letter as they're placed.
+const BRUSHES: [(&str, Tile); 4] = [
+    ("Wall", Tile::Wall),
+    ("Portal", Tile::Portal('a')),
+    ("Spawn", Tile::Spawn),
+    ("Food", Tile::Food),
+];
+
+/// The editor's state: the maze being drawn and where it's saved.
+pub struct Editor {
+    pub maze: Maze,
+    path: PathBuf,
+    /// Which of the `BRUSHES` the left mouse button paints with.
+    brush: usize,
+    /// The mouse button being dragged across the board, if any.
+    painting: Option<MouseButton>,
+    /// What happened when the maze was last loaded or saved.
+    status: String,
+}
+
+impl Editor {
+    /// Opens the maze at `path`, or an empty one if there isn't a file there yet.
+    pub fn open(path: PathBuf) -> Self {
+        let (maze, status) = if path.exists() {
+            match load(&path) {
+                Ok(maze) => (maze, format!("Opened {}", path.display())),
+                Err(e) => (Maze::empty(), format!("Couldn't open the maze: {}", e)),
+            }
+        } else {
+            (Maze::empty(), format!("New maze at {}", path.display()))
+        };
+        Editor {
+            maze,
+            path,
+            brush: 0,
+            painting: None,
+            status,
+        }
+    }
+
+    /// Saves the maze as a level.
+    fn save(&mut self) {
+        let name = self
+            .path
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        let level = Level {
+            properties: vec![("name".to_string(), name)],
+            tiles: Grid::from_rows(self.maze.rows()).expect("maze rows are the same length"),
+        };
+        let saved = match self.path.parent() {
+            Some(dir) => fs::create_dir_all(dir),
+            None => Ok(()),
+        }
+        .and_then(|()| fs::write(&self.path, level.to_string()));
+        self.status = match saved {
+            Ok(()) => format!("Saved {}", self.path.display()),
+            Err(e) => format!("Couldn't save the maze: {}", e),
+        };
+    }
+
+    /// The first portal letter not already used by a pair, not counting the portal at `pos`.
+    fn free_letter(&self, pos: GridPosition) -> Option<char> {
+        (b'a'..=b'z').map(char::from).find(|&letter| {
+            self.maze
+                .iter()
+                .filter(|&(other, tile)| other != pos && tile == Tile::Portal(letter))
+                .count()
+                < 2
+        })
+    }
+
+    /// Paints the cell under the mouse: the brush with the left button or open ground with the
+    /// right. Portals and the spawn point only go down on a click, not while dragging.
+    fn paint(&mut self, x: f32, y: f32, button: MouseButton, dragging: bool) {
+        let pos = GridPosition::new(
+            (x / f32::from(GRID_CELL_SIZE.0)) as i16,
+            (y / f32::from(GRID_CELL_SIZE.1)) as i16,
+        );
+        if pos.x < 0 || pos.y < 0 || pos.x >= GRID_SIZE.0 || pos.y >= GRID_SIZE.1 {
+            return;
+        }
+        let tile = match (button, BRUSHES[self.brush].1) {
+            (MouseButton::Right, _) => Tile::Open,
+            (MouseButton::Left, Tile::Portal(_)) if !dragging => match self.free_letter(pos) {
+                Some(letter) => Tile::Portal(letter),
+                None => return,
+            },
+            (MouseButton::Left, Tile::Spawn) if !dragging => {
+                let spawn = self.maze.spawn();
+                if self.maze.get(spawn) == Tile::Spawn {
+                    self.maze.set(spawn, Tile::Open);
+                }
+                Tile::Spawn
+            }
+            (MouseButton::Left, Tile::Portal(_)) | (MouseButton::Left, Tile::Spawn) => return,
+            (MouseButton::Left, brush) => brush,
+            _ => return,
+        };
+        self.maze.set(pos, tile);
+    }
+
+    pub fn key_down(&mut self, keycode: KeyCode) {
+        match keycode {
+            KeyCode::Key1 => self.brush = 0,
+            KeyCode::Key2 => self.brush = 1,
+            KeyCode::Key3 => self.brush = 2,
+            KeyCode::Key4 => self.brush = 3,
+            KeyCode::S => self.save(),
+            _ => (),
+        }
+    }
+
+    pub fn mouse_button_down(&mut self, button: MouseButton, x: f32, y: f32) {
+        self.painting = Some(button);
+        self.paint(x, y, button, false);
+    }
+
+    pub fn mouse_button_up(&mut self, button: MouseButton) {
+        if self.painting == Some(button) {
+            self.painting = None;
+        }
+    }
+
+    pub fn mouse_motion(&mut self, x: f32, y: f32) {
+        if let Some(button) = self.painting {
+            self.paint(x, y, button, true);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, palette: &Palette) -> GameResult<()> {
+        graphics::clear(ctx, palette.background.into());
+        draw_maze(ctx, &self.maze, palette, true)?;
+
+        let mut faint = palette.text;
+        faint[3] = 0.6;
+        let brushes: Vec<String> = BRUSHES
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                if i == self.brush {
+                    format!("[{} {}]", i + 1, name)
+                } else {
+                    format!("{} {}", i + 1, name)
+                }
+            })
+            .collect();
+        let hint = format!(
+            "{}   Right click: erase   S: save   Tab: playtest",
+            brushes.join("  ")
+        );
+        ui::draw_text(ctx, &hint, faint, 16.0, 8.0, SCREEN_SIZE.1 - 48.0)?;
+        ui::draw_text(ctx, &self.status, faint, 16.0, 8.0, SCREEN_SIZE.1 - 24.0)
+    }
+}
@@ -0,0 +1,487 @@
+//! Snake's rules, without any drawing or input, so they can be stepped by anything: the game
+//! itself, the Python bindings, and tests.
+//!
+//! A `Maze` lays walls and portals over the board, and marks where the snake starts and where
+//! food can appear. Without one the board is open and food can appear anywhere.
+//!
+//! A `Simulation` is deterministic: started from the same random numbers and given the same
+//! turns before each step, it plays out the same way. `replay` checks that it keeps playing out
+//! the same way as recorded games.
+
+pub mod replay;
+
+use std::collections::LinkedList;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+/// The size of the board in cells.
+pub const GRID_SIZE: (i16, i16) = (30, 20);
+
+/// How many places `Simulation::step_with` tries for new food before settling for the last.
+pub const FOOD_TRIES: usize = 100;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct GridPosition {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl GridPosition {
+    /// Creates a new grid position.
+    pub fn new(x: i16, y: i16) -> Self {
+        GridPosition { x, y }
+    }
+
+    /// Creates a new random grid position from the range of `(0, 0)` to `(max_x, max_y)`.
+    pub fn random(rng: &mut impl Rng, max_x: i16, max_y: i16) -> Self {
+        (
+            rng.gen_range::<i16, i16, i16>(0, max_x),
+            rng.gen_range::<i16, i16, i16>(0, max_y),
+        )
+            .into()
+    }
+
+    /// Move grid position by the given direction and wrap arround the board.
+    pub fn wrapped_move(pos: GridPosition, dir: Direction) -> Self {
+        match dir {
+            Direction::Up => GridPosition::new(pos.x, (pos.y - 1).rem_euclid(GRID_SIZE.1)),
+            Direction::Down => GridPosition::new(pos.x, (pos.y + 1).rem_euclid(GRID_SIZE.1)),
+            Direction::Left => GridPosition::new((pos.x - 1).rem_euclid(GRID_SIZE.0), pos.y),
+            Direction::Right => GridPosition::new((pos.x + 1).rem_euclid(GRID_SIZE.0), pos.y),
+        }
+    }
+
+    /// Move grid position by the given direction, or `None` if it would leave the board.
+    pub fn bounded_move(pos: GridPosition, dir: Direction) -> Option<Self> {
+        let (x, y) = match dir {
+            Direction::Up => (pos.x, pos.y - 1),
+            Direction::Down => (pos.x, pos.y + 1),
+            Direction::Left => (pos.x - 1, pos.y),
+            Direction::Right => (pos.x + 1, pos.y),
+        };
+        if x < 0 || y < 0 || x >= GRID_SIZE.0 || y >= GRID_SIZE.1 {
+            None
+        } else {
+            Some(GridPosition::new(x, y))
+        }
+    }
+}
+
+impl From<(i16, i16)> for GridPosition {
+    fn from(pos: (i16, i16)) -> Self {
+        GridPosition { x: pos.0, y: pos.1 }
+    }
+}
+
+/// Represents all possible directions that our snake can move.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Returns the inverse `Direction` of the current.
+    pub fn inverse(&self) -> Self {
+        match *self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Reads a direction by its name: up, down, left or right.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// The direction's name, as `from_name` reads it.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+        }
+    }
+}
+
+/// What is in a cell of a maze.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Tile {
+    Open,
+    /// Kills the snake running into it.
+    Wall,
+    /// Takes the snake to the other portal with the same letter.
+    Portal(char),
+    /// Where the snake starts, heading right.
+    Spawn,
+    /// A place food can appear. If a maze has any, food only appears on them.
+    Food,
+}
+
+impl Tile {
+    /// The tile a character stands for in a level file: a space or `.` for open, `#` for a
+    /// wall, a lowercase letter for a portal, `@` for the spawn point and `*` for food.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            ' ' | '.' => Some(Tile::Open),
+            '#' => Some(Tile::Wall),
+            'a'..='z' => Some(Tile::Portal(c)),
+            '@' => Some(Tile::Spawn),
+            '*' => Some(Tile::Food),
+            _ => None,
+        }
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Open => '.',
+            Tile::Wall => '#',
+            Tile::Portal(letter) => letter,
+            Tile::Spawn => '@',
+            Tile::Food => '*',
+        }
+    }
+}
+
+/// Walls, portals and food spots laid over the board, which is `GRID_SIZE` cells.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Maze {
+    /// The tiles in rows, from the top left.
+    tiles: Vec<Tile>,
+}
+
+impl Maze {
+    /// An open board.
+    pub fn empty() -> Self {
+        Maze {
+            tiles: vec![Tile::Open; (GRID_SIZE.0 * GRID_SIZE.1) as usize],
+        }
+    }
+
+    /// Reads a maze from the rows of a level. Smaller mazes are padded with open cells.
+    pub fn from_rows(rows: &[Vec<char>]) -> Result<Self, String> {
+        if rows.len() > GRID_SIZE.1 as usize
+            || rows.iter().any(|row| row.len() > GRID_SIZE.0 as usize)
+        {
+            return Err(format!(
+                "mazes can't be bigger than {} by {}",
+                GRID_SIZE.0, GRID_SIZE.1
+            ));
+        }
+        let mut maze = Maze::empty();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &c) in row.iter().enumerate() {
+                let tile = Tile::from_char(c).ok_or_else(|| format!("unknown tile `{}`", c))?;
+                maze.set(GridPosition::new(x as i16, y as i16), tile);
+            }
+        }
+        Ok(maze)
+    }
+
+    /// The maze's rows, to save in a level.
+    pub fn rows(&self) -> Vec<Vec<char>> {
+        self.tiles
+            .chunks(GRID_SIZE.0 as usize)
+            .map(|row| row.iter().map(|tile| tile.to_char()).collect())
+            .collect()
+    }
+
+    fn index(pos: GridPosition) -> Option<usize> {
+        if pos.x < 0 || pos.y < 0 || pos.x >= GRID_SIZE.0 || pos.y >= GRID_SIZE.1 {
+            None
+        } else {
+            Some(pos.y as usize * GRID_SIZE.0 as usize + pos.x as usize)
+        }
+    }
+
+    /// The tile at a position, with anything off the board open.
+    pub fn get(&self, pos: GridPosition) -> Tile {
+        Maze::index(pos).map_or(Tile::Open, |index| self.tiles[index])
+    }
+
+    pub fn set(&mut self, pos: GridPosition, tile: Tile) {
+        if let Some(index) = Maze::index(pos) {
+            self.tiles[index] = tile;
+        }
+    }
+
+    /// Every position and its tile, from the top left.
+    pub fn iter(&self) -> impl Iterator<Item = (GridPosition, Tile)> + '_ {
+        self.tiles.iter().enumerate().map(|(index, &tile)| {
+            let x = (index % GRID_SIZE.0 as usize) as i16;
+            let y = (index / GRID_SIZE.0 as usize) as i16;
+            (GridPosition::new(x, y), tile)
+        })
+    }
+
+    /// Where the snake starts: the spawn point if there is one, or else left of the middle.
+    pub fn spawn(&self) -> GridPosition {
+        self.iter()
+            .find(|(_, tile)| *tile == Tile::Spawn)
+            .map_or((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into(), |(pos, _)| pos)
+    }
+
+    /// Where the snake comes out after entering a portal at `pos`, if it is one with a partner.
+    pub fn exit(&self, pos: GridPosition) -> Option<GridPosition> {
+        let letter = match self.get(pos) {
+            Tile::Portal(letter) => letter,
+            _ => return None,
+        };
+        self.iter()
+            .find(|(other, tile)| *other != pos && *tile == Tile::Portal(letter))
+            .map(|(other, _)| other)
+    }
+
+    /// Whether food can appear at a position.
+    pub fn allows_food(&self, pos: GridPosition) -> bool {
+        match self.get(pos) {
+            Tile::Food => true,
+            Tile::Open | Tile::Spawn => !self.tiles.contains(&Tile::Food),
+            Tile::Wall | Tile::Portal(_) => false,
+        }
+    }
+}
+
+/// A segment of the snake.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Segment {
+    pub pos: GridPosition,
+}
+
+impl Segment {
+    /// Creates a new `Segment` at the `pos`.
+    pub fn new(pos: GridPosition) -> Self {
+        Segment { pos }
+    }
+}
+
+/// A piece of food the snake can eat.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Food {
+    pub pos: GridPosition,
+}
+
+impl Food {
+    /// Creates a new `Food` at the given `pos`.
+    pub fn new(pos: GridPosition) -> Self {
+        Food { pos }
+    }
+}
+
+/// Represents all possible things the snake could have "eaten" during an update. Either being a
+/// piece of `Food`, or it may have eaten `Itself` if it ran into its body, or the `Wall` if it
+/// ran off a board that doesn't wrap.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Ate {
+    Itself,
+    Food,
+    Wall,
+}
+
+/// The snake entity that the player controls to direct it to the food to grow the snake and avoid
+/// hitting into itself and dying.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Snake {
+    /// The head of the snake.
+    pub head: Segment,
+    /// The current direction the snake will move in the next `update`.
+    pub dir: Direction,
+    /// The body of the snake.
+    pub body: LinkedList<Segment>,
+    /// The last update of whether the snake ate Itself (`Some(Ate::Itself)`), Food
+    /// (`Some(Ate::Food)`), or nothing (`None`).
+    pub ate: Option<Ate>,
+    /// The direction the snake previously travelled in the last `update`. Used to determine the
+    /// possible valid directions of the next move.
+    pub last_update_dir: Direction,
+    /// Stores the next direction that the snake will travel in the next `update` after. Used to
+    /// allow the user to choose two directions (e.g., left than up).
+    pub next_dir: Option<Direction>,
+}
+
+impl Snake {
+    /// Creates a new snake from the pos with one head and body segment moving to the right.
+    pub fn new(pos: GridPosition) -> Self {
+        let mut body = LinkedList::new();
+
+        body.push_back(Segment::new((pos.x - 1, pos.y).into()));
+        Snake {
+            head: Segment::new((pos.x, pos.y).into()),
+            dir: Direction::Right,
+            last_update_dir: Direction::Right,
+            body,
+            ate: None,
+            next_dir: None,
+        }
+    }
+
+    /// How many segments long the snake is, counting its head.
+    pub fn length(&self) -> usize {
+        self.body.len() + 1
+    }
+
+    /// Makes the snake `segments` longer at its tail.
+    pub fn grow(&mut self, segments: usize) {
+        let tail = *self.body.back().unwrap_or(&self.head);
+        for _ in 0..segments {
+            self.body.push_back(tail);
+        }
+    }
+
+    /// Turns the snake, unless it would turn back on itself. A second turn before the next
+    /// `update` is kept for the one after, so quick turns like left then up aren't lost.
+    pub fn turn(&mut self, dir: Direction) {
+        if self.dir != self.last_update_dir && dir.inverse() != self.dir {
+            self.next_dir = Some(dir);
+        } else if dir.inverse() != self.last_update_dir {
+            self.dir = dir;
+        }
+    }
+
+    fn eats(&self, food: &Food) -> bool {
+        self.head.pos == food.pos
+    }
+
+    fn eats_self(&self) -> bool {
+        for seg in self.body.iter() {
+            if self.head.pos == seg.pos {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn update(&mut self, food: &Food, wrap: bool, maze: &Maze) {
+        if self.last_update_dir == self.dir && self.next_dir.is_some() {
+            self.dir = self.next_dir.unwrap();
+            self.next_dir = None;
+        }
+
+        let new_head_pos = if wrap {
+            GridPosition::wrapped_move(self.head.pos, self.dir)
+        } else {
+            match GridPosition::bounded_move(self.head.pos, self.dir) {
+                Some(pos) => pos,
+                None => {
+                    self.ate = Some(Ate::Wall);
+                    return;
+                }
+            }
+        };
+        let new_head_pos = match maze.get(new_head_pos) {
+            Tile::Wall => {
+                self.ate = Some(Ate::Wall);
+                return;
+            }
+            _ => maze.exit(new_head_pos).unwrap_or(new_head_pos),
+        };
+        let new_head = Segment::new(new_head_pos);
+
+        // Grow the snake by pushing the current head `Segment` to the front of our body.
+        self.body.push_front(self.head);
+        self.head = new_head;
+
+        self.ate = if self.eats_self() {
+            Some(Ate::Itself)
+        } else if self.eats(food) {
+            Some(Ate::Food)
+        } else {
+            None
+        };
+
+        // If we didn't eat anything this `update`, we remove the last segment from our body, which
+        // gives the illusion that the snake is moving.
+        if self.ate.is_none() {
+            self.body.pop_back();
+        }
+
+        self.last_update_dir = self.dir;
+    }
+}
+
+/// A game of snake, from the first move until the snake runs into something.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub snake: Snake,
+    pub food: Food,
+    /// Whether the snake wraps around the edges of the board rather than running into them.
+    pub wrap: bool,
+    pub maze: Maze,
+    /// Whether the snake has eaten itself or run into a wall.
+    pub over: bool,
+    /// Whether the game goes on when the snake runs into something, for testing.
+    pub god: bool,
+    /// Whether no more food is placed once the food is eaten, for testing.
+    pub no_spawn: bool,
+    rng: StdRng,
+}
+
+impl Simulation {
+    /// Starts a game on an open board, placing the food with `rng`.
+    pub fn new(rng: StdRng, wrap: bool) -> Self {
+        Simulation::with_maze(rng, wrap, Maze::empty())
+    }
+
+    /// Starts a game in a maze.
+    pub fn with_maze(rng: StdRng, wrap: bool, maze: Maze) -> Self {
+        let mut sim = Simulation {
+            snake: Snake::new(maze.spawn()),
+            food: Food::new(GridPosition::new(0, 0)),
+            wrap,
+            maze,
+            over: false,
+            god: false,
+            no_spawn: false,
+            rng,
+        };
+        sim.place_food(|_| true);
+        sim
+    }
+
+    /// Moves the food somewhere the maze and `allow_food` let it be, if it finds such a place in
+    /// `FOOD_TRIES` tries. With `no_spawn` it's taken off the board instead.
+    fn place_food(&mut self, mut allow_food: impl FnMut(GridPosition) -> bool) {
+        if self.no_spawn {
+            // Off the board, where it can't be eaten.
+            self.food.pos = GridPosition::new(-1, -1);
+            return;
+        }
+        for _ in 0..FOOD_TRIES {
+            self.food.pos = GridPosition::random(&mut self.rng, GRID_SIZE.0, GRID_SIZE.1);
+            if self.maze.allows_food(self.food.pos) && allow_food(self.food.pos) {
+                break;
+            }
+        }
+    }
+
+    /// Moves the snake once, returning what it ate. Does nothing once the game is over.
+    pub fn step(&mut self) -> Option<Ate> {
+        self.step_with(|_| true)
+    }
+
+    /// Moves the snake once like `step`, only placing new food where `allow_food` lets it.
+    pub fn step_with(&mut self, allow_food: impl FnMut(GridPosition) -> bool) -> Option<Ate> {
+        if self.over {
+            return None;
+        }
+        self.snake.update(&self.food, self.wrap, &self.maze);
+        match self.snake.ate {
+            Some(Ate::Food) => self.place_food(allow_food),
+            Some(Ate::Itself) | Some(Ate::Wall) => self.over = !self.god,
+            None => (),
+        }
+        self.snake.ate
+    }
+}
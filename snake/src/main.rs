@@ -1,16 +1,43 @@
 use ggez;
-use rand;
 
-use ggez::event::{KeyCode, KeyMods};
-use ggez::graphics::{DrawMode, Scale, Text, TextFragment};
+use ggez::event::{Button, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, MeshBuilder};
+use ggez::input::gamepad::GamepadId;
 use ggez::{event, graphics, Context, GameResult};
+use rand::rngs::StdRng;
 
-use std::collections::LinkedList;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
 use std::time::{Duration, Instant};
 
-use rand::Rng;
+use common::achievements;
+use common::app::App;
+use common::args;
+use common::cheats::Cheats;
+use common::console::{self, Commands};
+use common::daily::{self, Daily};
+use common::generate::{self, Cell, LevelGenerator, Params};
+use common::input::Nav;
+use common::locale;
+use common::mods::Mods;
+use common::overlay::{Overlay, GAME_OVER_ACTIONS};
+use common::rumble::{self, Rumble};
+use common::spectator;
+use common::stats::StatsDb;
+use common::theme::{self, Palette};
+use common::title;
+use common::ui;
+use common::window;
+use snake::replay::Recorder;
+use snake::{Ate, Direction, Food, GridPosition, Maze, Simulation, Snake, Tile, GRID_SIZE};
+
+mod editor;
+#[cfg(test)]
+mod snapshots;
+
+use editor::Editor;
 
-const GRID_SIZE: (i16, i16) = (30, 20);
 const GRID_CELL_SIZE: (i16, i16) = (32, 32);
 
 const SCREEN_SIZE: (f32, f32) = (
@@ -18,276 +45,411 @@ const SCREEN_SIZE: (f32, f32) = (
     GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1 as f32,
 );
 
-const UPDATES_PER_SECOND: f32 = 8.0;
-const MILLIS_PER_UPDATE: u64 = (1.0 / UPDATES_PER_SECOND * 1000.0) as u64;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-struct GridPosition {
-    x: i16,
-    y: i16,
-}
+/// How hard a generated layout is, unless the `--difficulty` argument says otherwise.
+const DIFFICULTY: f32 = 0.5;
 
-impl GridPosition {
-    /// Creates a new grid position.
-    pub fn new(x: i16, y: i16) -> Self {
-        GridPosition { x, y }
-    }
+/// How many cells in front of the snake are kept clear where it starts in a generated layout.
+const SPAWN_CLEARANCE: i16 = 3;
 
-    /// Creates a new random grid position from the range of `(0, 0)` to `(max_x, max_y)`.
-    pub fn random(max_x: i16, max_y: i16) -> Self {
-        let mut rng = rand::thread_rng();
-
-        (
-            rng.gen_range::<i16, i16, i16>(0, max_x),
-            rng.gen_range::<i16, i16, i16>(0, max_y),
-        )
-            .into()
-    }
+/// How many times a second the snake moves, unless the `--speed` argument says otherwise.
+const UPDATES_PER_SECOND: f32 = 8.0;
 
-    /// Move grid position by the given direction and wrap arround the board.
-    pub fn wrapped_move(pos: GridPosition, dir: Direction) -> Self {
-        match dir {
-            Direction::Up => GridPosition::new(pos.x, (pos.y - 1).rem_euclid(GRID_SIZE.1)),
-            Direction::Down => GridPosition::new(pos.x, (pos.y + 1).rem_euclid(GRID_SIZE.1)),
-            Direction::Left => GridPosition::new((pos.x - 1).rem_euclid(GRID_SIZE.0), pos.y),
-            Direction::Right => GridPosition::new((pos.x + 1).rem_euclid(GRID_SIZE.0), pos.y),
-        }
-    }
+/// The game's own colours, for the original theme.
+const PALETTE: Palette = Palette {
+    background: [0.0, 0.0, 0.0, 1.0],
+    text: [1.0, 1.0, 1.0, 1.0],
+    player: [1.0, 1.0, 1.0, 1.0],
+    hazard: [1.0, 0.0, 0.0, 1.0],
+    pickup: [1.0, 0.0, 0.0, 1.0],
+};
+
+/// The cell on screen of a grid position.
+fn cell(pos: GridPosition) -> graphics::Rect {
+    graphics::Rect::new_i32(
+        pos.x as i32 * GRID_CELL_SIZE.0 as i32,
+        pos.y as i32 * GRID_CELL_SIZE.1 as i32,
+        GRID_CELL_SIZE.0 as i32,
+        GRID_CELL_SIZE.1 as i32,
+    )
 }
 
-/// Implement `From` trait for `graphics::Rect` so it easily converts a grid position
-/// into a grid cell.
-impl From<GridPosition> for graphics::Rect {
-    fn from(pos: GridPosition) -> Self {
-        graphics::Rect::new_i32(
-            pos.x as i32 * GRID_CELL_SIZE.0 as i32,
-            pos.y as i32 * GRID_CELL_SIZE.1 as i32,
-            GRID_CELL_SIZE.0 as i32,
-            GRID_CELL_SIZE.1 as i32,
-        )
+/// Converts from `ggez::Keycode` to a `Direction` that it represents, or it returns `None`.
+fn direction_from_keycode(key: KeyCode) -> Option<Direction> {
+    match key {
+        KeyCode::Up => Some(Direction::Up),
+        KeyCode::Down => Some(Direction::Down),
+        KeyCode::Left => Some(Direction::Left),
+        KeyCode::Right => Some(Direction::Right),
+        _ => None,
     }
 }
 
-impl From<(i16, i16)> for GridPosition {
-    fn from(pos: (i16, i16)) -> Self {
-        GridPosition { x: pos.0, y: pos.1 }
+/// The direction a gamepad button turns the snake, if it turns it.
+fn direction_from_button(button: Button) -> Option<Direction> {
+    match button {
+        Button::DPadUp => Some(Direction::Up),
+        Button::DPadDown => Some(Direction::Down),
+        Button::DPadLeft => Some(Direction::Left),
+        Button::DPadRight => Some(Direction::Right),
+        _ => None,
     }
 }
 
-/// Represents all possible directions that our snake can move.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+fn draw_food(ctx: &mut Context, food: &Food, palette: &Palette) -> GameResult<()> {
+    let color = palette.pickup.into();
+
+    let rect = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), cell(food.pos), color)?;
+    graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
 }
 
-impl Direction {
-    /// Returns the inverse `Direction` of the current.
-    pub fn inverse(&self) -> Self {
-        match *self {
-            Direction::Up => Direction::Down,
-            Direction::Down => Direction::Up,
-            Direction::Left => Direction::Right,
-            Direction::Right => Direction::Left,
+/// Generates a maze with one of the built in generators, as hard as the `--difficulty` argument
+/// says and from the `--seed` argument's seed if there is one.
+fn generate_maze(name: &str) -> Option<Maze> {
+    let generator = generate::by_name(name)?;
+    let params = Params {
+        width: GRID_SIZE.0 as usize,
+        height: GRID_SIZE.1 as usize,
+        difficulty: args::get("difficulty").unwrap_or(DIFFICULTY),
+    };
+    let layout = generator.generate(&params, daily::seed(None).unwrap_or_else(rand::random));
+
+    let mut maze = Maze::empty();
+    for (x, y, cell) in layout.cells.iter() {
+        if *cell == Cell::Wall {
+            maze.set(GridPosition::new(x as i16, y as i16), Tile::Wall);
         }
     }
-
-    /// Converts from `ggez::Keycode` to a `Direction` that it represents, or it returns `None`.
-    pub fn from_keycode(key: KeyCode) -> Option<Self> {
-        match key {
-            KeyCode::Up => Some(Direction::Up),
-            KeyCode::Down => Some(Direction::Down),
-            KeyCode::Left => Some(Direction::Left),
-            KeyCode::Right => Some(Direction::Right),
-            _ => None,
-        }
+    // Start the snake as near the usual place as there's room for it, with a few cells clear in
+    // front so it isn't straight into a wall.
+    let start = maze.spawn();
+    let clear = |pos: GridPosition| {
+        (-1..=SPAWN_CLEARANCE).all(|dx| {
+            maze.get(GridPosition::new(pos.x + dx, pos.y)) == Tile::Open
+                && pos.x + dx >= 0
+                && pos.x + dx < GRID_SIZE.0
+        })
+    };
+    let spawn = maze
+        .iter()
+        .map(|(pos, _)| pos)
+        .filter(|&pos| clear(pos))
+        .min_by_key(|pos| (pos.x - start.x).abs() + (pos.y - start.y).abs());
+    if let Some(spawn) = spawn {
+        maze.set(spawn, Tile::Spawn);
     }
+    Some(maze)
 }
 
-/// A segment of the snake.
-#[derive(Debug, Copy, Clone)]
-struct Segment {
-    pos: GridPosition,
+/// Draws the maze's walls and portals, and its spawn point and food spots too when `editing`.
+fn draw_maze(ctx: &mut Context, maze: &Maze, palette: &Palette, editing: bool) -> GameResult<()> {
+    let mut marker = palette.text;
+    marker[3] = 0.4;
+    let mut mesh = MeshBuilder::new();
+    let mut letters = Vec::new();
+    let mut empty = true;
+    for (pos, tile) in maze.iter() {
+        let rect = cell(pos);
+        match tile {
+            Tile::Open => continue,
+            Tile::Wall => mesh.rectangle(DrawMode::fill(), rect, palette.text.into()),
+            Tile::Portal(letter) => {
+                letters.push((letter, rect));
+                mesh.rectangle(DrawMode::stroke(3.0), rect, palette.hazard.into())
+            }
+            Tile::Spawn if editing => mesh.rectangle(DrawMode::stroke(5.0), rect, marker.into()),
+            Tile::Food if editing => mesh.rectangle(DrawMode::stroke(2.0), rect, marker.into()),
+            Tile::Spawn | Tile::Food => continue,
+        };
+        empty = false;
+    }
+    if !empty {
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+    }
+    for (letter, rect) in letters {
+        ui::draw_text(
+            ctx,
+            &letter.to_string(),
+            palette.hazard,
+            16.0,
+            rect.x + 10.0,
+            rect.y + 6.0,
+        )?;
+    }
+    Ok(())
 }
 
-impl Segment {
-    /// Creates a new `Segment` with the `col` and at the `pos`.
-    pub fn new(pos: GridPosition) -> Self {
-        Segment { pos }
+fn draw_snake(ctx: &mut Context, snake: &Snake, palette: &Palette) -> GameResult<()> {
+    for seg in snake.body.iter() {
+        let rect = graphics::Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            cell(seg.pos),
+            palette.player.into(),
+        )?;
+        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
     }
-}
 
-/// A piece of food the snake can eat.
-#[derive(Debug, Copy, Clone)]
-struct Food {
-    pos: GridPosition,
+    let rect = graphics::Mesh::new_rectangle(
+        ctx,
+        DrawMode::stroke(5.0),
+        cell(snake.head.pos),
+        palette.player.into(),
+    )?;
+    graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
 }
 
-impl Food {
-    /// Creates a new `Food` at the given `pos`.
-    pub fn new(pos: GridPosition) -> Self {
-        Food { pos }
-    }
-
-    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
-        let color = [1.0, 0.0, 0.0, 1.0].into();
+/// Draws the board: the maze, the snake and its food.
+fn draw_game(
+    ctx: &mut Context,
+    maze: &Maze,
+    sim: &Simulation,
+    palette: &Palette,
+) -> GameResult<()> {
+    graphics::clear(ctx, palette.background.into());
+    draw_maze(ctx, maze, palette, false)?;
+    draw_snake(ctx, &sim.snake, palette)?;
+    draw_food(ctx, &sim.food, palette)
+}
 
-        let rect = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), self.pos.into(), color)?;
-        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+/// Starts a game in `maze`, with any cheats it was started with.
+fn start(rng: StdRng, wrap: bool, maze: Maze, cheats: &Cheats) -> Simulation {
+    let mut sim = Simulation::with_maze(rng, wrap, maze);
+    if let Some(length) = cheats.start_length {
+        sim.snake.grow(length.saturating_sub(sim.snake.length()));
     }
+    sim.god = cheats.invincible;
+    sim.no_spawn = cheats.no_spawn;
+    sim
 }
 
-/// Represents all possible things the snake could have "eaten" during an update. Either being a
-/// piece of `Food`, or it may have eaten `Itself` if it ran into its body.
-#[derive(Debug, Copy, Clone)]
-enum Ate {
-    Itself,
-    Food,
+/// The state for the game.
+struct GameState {
+    sim: Simulation,
+    /// The maze played in, from `--maze`, `--layout` or the editor.
+    maze: Maze,
+    /// The maze editor, when the game was started with `--edit`.
+    editor: Option<Editor>,
+    /// Whether the editor is showing, rather than a playtest of its maze.
+    editing: bool,
+    /// The game over screen, once the snake has eaten itself.
+    overlay: Option<Overlay>,
+    last_update: Instant,
+    /// How long between each move of the snake.
+    update_interval: Duration,
+    /// Today's challenge, when the game was started for it.
+    daily: Option<Daily>,
+    /// The Lua mods from the mods folder, which can move the food and change what it's worth.
+    mods: Mods,
+    /// The points from the food eaten, which is only shown when mods can change it.
+    score: f64,
+    /// How many times the snake has moved.
+    ticks: u64,
+    palette: Palette,
+    /// The cheats from the command line, which are set up again on each restart.
+    cheats: Cheats,
+    /// Where to write a replay script of each game, from `--record`.
+    record_path: Option<PathBuf>,
+    /// Records each game when it's written to `record_path`, or is a daily run, whose replay
+    /// is signed with its score for the online leaderboard.
+    recorder: Option<Recorder>,
+    rumble: Rumble,
+    stats: Option<StatsDb>,
 }
 
-/// The snake entity that the player controls to direct it to the food to grow the snake and avoid
-/// hitting into itself and dying.
-#[derive(Debug)]
-struct Snake {
-    /// The head of the snake.
-    head: Segment,
-    /// The current direction the snake will move in the next `update`.
-    dir: Direction,
-    /// The body of the snake.
-    body: LinkedList<Segment>,
-    /// The last update of whether the snake ate Itself (`Some(Ate::Itself)`), Food
-    /// (`Some(Ate::Food)`), or nothing (`None`).
-    ate: Option<Ate>,
-    /// The direction the snake previously travelled in the last `update`. Used to determine the
-    /// possible valid directions of the next move.
-    last_update_dir: Direction,
-    /// Stores the next direction that the snake will travel in the next `update` after. Used to
-    /// allow the user to choose two directions (e.g., left than up).
-    next_dir: Option<Direction>,
-}
+impl GameState {
+    /// Creates a new game state, with the daily challenge's modifiers if it was started for it.
+    pub fn new() -> Self {
+        let daily = daily::requested("snake");
+        // Everyone plays a daily challenge at the same speed.
+        let speed = match daily {
+            Some(daily) if daily.has("double_speed") => UPDATES_PER_SECOND * 2.0,
+            Some(_) => UPDATES_PER_SECOND,
+            None => args::get("speed")
+                .filter(|&speed: &f32| speed > 0.0)
+                .unwrap_or(UPDATES_PER_SECOND),
+        };
+        let wrap = !daily.map_or(false, |daily| daily.has("no_wrap"));
+        // Everyone plays a daily challenge without mods too.
+        let mods = match daily {
+            Some(_) => Mods::none(),
+            None => Mods::load(
+                "snake",
+                &[
+                    ("width", f64::from(GRID_SIZE.0)),
+                    ("height", f64::from(GRID_SIZE.1)),
+                ],
+            ),
+        };
 
-impl Snake {
-    /// Creates a new snake from the pos with one head and body segment moving to the right.
-    pub fn new(pos: GridPosition) -> Self {
-        let mut body = LinkedList::new();
-
-        body.push_back(Segment::new((pos.x - 1, pos.y).into()));
-        Snake {
-            head: Segment::new((pos.x, pos.y).into()),
-            dir: Direction::Right,
-            last_update_dir: Direction::Right,
-            body,
-            ate: None,
-            next_dir: None,
+        // Daily challenges are always played on the open board.
+        let editor = match daily {
+            Some(_) => None,
+            None if args::flag("edit") => {
+                let path = args::value("edit").map(PathBuf::from).or_else(|| {
+                    editor::default_path()
+                        .map_err(|e| eprintln!("Couldn't find the data directory: {}", e))
+                        .ok()
+                });
+                path.map(Editor::open)
+            }
+            None => None,
+        };
+        let maze = match (daily, &editor, args::value("maze")) {
+            (Some(_), _, _) => Maze::empty(),
+            (None, Some(editor), _) => editor.maze.clone(),
+            (None, None, Some(path)) => editor::load(path.as_ref()).unwrap_or_else(|e| {
+                eprintln!("Couldn't load the maze {}: {}", path, e);
+                Maze::empty()
+            }),
+            (None, None, None) => match args::value("layout") {
+                Some(name) => generate_maze(&name).unwrap_or_else(|| {
+                    let names = generate::NAMES.join(", ");
+                    eprintln!("There's no layout called `{}`. Try one of {}.", name, names);
+                    Maze::empty()
+                }),
+                None => Maze::empty(),
+            },
+        };
+
+        // Nor with cheats.
+        let cheats = match daily {
+            Some(_) => Cheats::default(),
+            None => Cheats::requested(),
+        };
+
+        let sim = start(daily::rng(daily.as_ref()), wrap, maze.clone(), &cheats);
+        let record_path = args::value("record").map(PathBuf::from);
+
+        GameState {
+            recorder: if record_path.is_some() || daily.is_some() {
+                Some(Recorder::new(&sim))
+            } else {
+                None
+            },
+            record_path,
+            sim,
+            maze,
+            editing: editor.is_some(),
+            editor,
+            overlay: None,
+            last_update: Instant::now(),
+            update_interval: Duration::from_secs_f32(1.0 / speed),
+            daily,
+            mods,
+            score: 0.0,
+            ticks: 0,
+            palette: theme::palette(PALETTE),
+            cheats,
+            rumble: Rumble::load(),
+            stats: StatsDb::open().ok(),
         }
     }
 
-    fn eats(&self, food: &Food) -> bool {
-        self.head.pos == food.pos
+    fn game_over_action(&mut self, ctx: &mut Context, action: Option<usize>) {
+        match action {
+            Some(0) => self.restart(),
+            Some(_) => event::quit(ctx),
+            None => (),
+        }
     }
 
-    fn eats_self(&self) -> bool {
-        for seg in self.body.iter() {
-            if self.head.pos == seg.pos {
-                return true;
-            }
+    fn restart(&mut self) {
+        // A daily challenge starts over with the same food each time.
+        self.sim = start(
+            daily::rng(self.daily.as_ref()),
+            self.sim.wrap,
+            self.maze.clone(),
+            &self.cheats,
+        );
+        if self.record_path.is_some() || self.daily.is_some() {
+            self.recorder = Some(Recorder::new(&self.sim));
         }
-        false
+        self.score = 0.0;
+        self.ticks = 0;
+        self.overlay = None;
     }
 
-    fn update(&mut self, food: &Food) {
-        if self.last_update_dir == self.dir && self.next_dir.is_some() {
-            self.dir = self.next_dir.unwrap();
-            self.next_dir = None;
+    fn turn(&mut self, dir: Direction) {
+        self.sim.snake.turn(dir);
+        if let Some(recorder) = &mut self.recorder {
+            recorder.turn(dir);
         }
+    }
 
-        let new_head_pos = GridPosition::wrapped_move(self.head.pos, self.dir);
-        let new_head = Segment::new(new_head_pos);
-
-        // Grow the snake by pushing the current head `Segment` to the front of our body.
-        self.body.push_front(self.head);
-        self.head = new_head;
-
-        self.ate = if self.eats_self() {
-            Some(Ate::Itself)
-        } else if self.eats(food) {
-            Some(Ate::Food)
-        } else {
-            None
+    /// Switches between the editor and a fresh playtest of its maze.
+    fn toggle_playtest(&mut self) {
+        let maze = match &self.editor {
+            Some(editor) => editor.maze.clone(),
+            None => return,
         };
-
-        // If we didn't eat anything this `update`, we remove the last segment from our body, which
-        // gives the illusion that the snake is moving.
-        if self.ate.is_none() {
-            self.body.pop_back();
+        self.editing = !self.editing;
+        if !self.editing {
+            self.maze = maze;
+            self.restart();
         }
-
-        self.last_update_dir = self.dir;
     }
 
-    fn draw(&self, ctx: &mut Context) -> GameResult<()> {
-        for seg in self.body.iter() {
-            let rect = graphics::Mesh::new_rectangle(
-                ctx,
-                DrawMode::fill(),
-                seg.pos.into(),
-                [1.0, 1.0, 1.0, 1.0].into(),
-            )?;
-            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+    fn game_over(&mut self) {
+        let replay = self
+            .recorder
+            .take()
+            .map(|recorder| recorder.finish().to_string());
+        if let (Some(path), Some(replay)) = (&self.record_path, &replay) {
+            if let Err(e) = fs::write(path, replay) {
+                eprintln!("Couldn't write the replay to {}: {}", path.display(), e);
+            }
         }
-
-        let rect = graphics::Mesh::new_rectangle(
-            ctx,
-            DrawMode::stroke(5.0),
-            self.head.pos.into(),
-            [1.0, 1.0, 1.0, 1.0].into(),
-        )?;
-        graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
-    }
-}
-
-/// The state for the game.
-struct GameState {
-    snake: Snake,
-    food: Food,
-    gameover: bool,
-    last_update: Instant,
-}
-
-impl GameState {
-    /// Creates a new game state.
-    pub fn new() -> Self {
-        let snake_pos = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
-        let food_pos = GridPosition::random(GRID_SIZE.0, GRID_SIZE.1);
-
-        GameState {
-            snake: Snake::new(snake_pos),
-            food: Food::new(food_pos),
-            gameover: false,
-            last_update: Instant::now(),
+        let length = self.sim.snake.length();
+        let mut summary = vec![format!("Length: {}", length)];
+        if !self.mods.is_empty() {
+            summary.push(format!("Score: {}", locale::number(self.score)));
+            let names: Vec<&str> = self.mods.names().collect();
+            summary.push(format!("Mods: {}", names.join(", ")));
         }
+        if let Some(daily) = &self.daily {
+            let _ = daily.record(length as f64, replay.as_deref());
+            if let Some((rank, total)) = daily.rank() {
+                summary.push(format!("Daily rank: {} of {}", rank, total));
+            }
+        } else if self.editor.is_none() && self.cheats == Cheats::default() {
+            // Daily runs have a leaderboard of their own, and playtests and runs with cheats
+            // aren't real runs.
+            let values = [("length", length as f64)];
+            if let Some(stats) = &self.stats {
+                let _ = stats.record("snake", &values);
+            }
+            let _ = achievements::check_run("snake", &values);
+        }
+        self.overlay = Some(Overlay::new("Game over", summary, GAME_OVER_ACTIONS));
     }
 }
 
 impl event::EventHandler for GameState {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
         // Check if enough time has elapsed since the last update.
-        if Instant::now() - self.last_update >= Duration::from_millis(MILLIS_PER_UPDATE) {
-            if !self.gameover {
-                self.snake.update(&self.food);
-
-                if let Some(ate) = self.snake.ate {
-                    match ate {
-                        Ate::Food => {
-                            let new_food_pos = GridPosition::random(GRID_SIZE.0, GRID_SIZE.1);
-                            self.food.pos = new_food_pos;
-                        }
-                        Ate::Itself => {
-                            self.gameover = true;
-                        }
+        if Instant::now() - self.last_update >= self.update_interval {
+            if !self.sim.over && !self.editing {
+                let mods = &mut self.mods;
+                let ate = self
+                    .sim
+                    .step_with(|pos| mods.allows_spawn("food", pos.x.into(), pos.y.into()));
+                self.ticks += 1;
+                self.mods.tick(self.ticks);
+                if let Some(recorder) = &mut self.recorder {
+                    recorder.step(&self.sim);
+                }
+                match ate {
+                    Some(Ate::Food) => {
+                        let head = self.sim.snake.head.pos;
+                        self.score += self.mods.score(1.0, head.x.into(), head.y.into());
+                        self.rumble.play(ctx, rumble::TAP);
+                    }
+                    Some(Ate::Itself) | Some(Ate::Wall) if self.sim.over => {
+                        self.rumble.play(ctx, rumble::CRASH);
+                        self.game_over();
                     }
+                    _ => (),
                 }
             }
 
@@ -298,55 +460,158 @@ impl event::EventHandler for GameState {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        graphics::clear(ctx, [0.0, 0.0, 0.0, 1.0].into());
-        self.snake.draw(ctx)?;
-        self.food.draw(ctx)?;
-
-        if self.gameover {
-            let game_over = Text::new(
-                TextFragment::new("GAME OVER!")
-                    .color([1.0, 0.0, 0.0, 1.0].into())
-                    .scale(Scale::uniform(40.0)),
-            );
-
-            graphics::draw(ctx, &game_over, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        if let (Some(editor), true) = (&self.editor, self.editing) {
+            editor.draw(ctx, &self.palette)?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
         }
 
-        graphics::present(ctx)?;
+        draw_game(ctx, &self.maze, &self.sim, &self.palette)?;
+        spectator::world_drawn(ctx)?;
+        title::show_score(self.sim.snake.length());
+
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(ctx)?;
+        }
+
+        ui::present(ctx)?;
         ggez::timer::yield_now();
         Ok(())
     }
 
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         keycode: KeyCode,
         _keymod: KeyMods,
         _repeat: bool,
     ) {
-        if let Some(dir) = Direction::from_keycode(keycode) {
-            if self.snake.dir != self.snake.last_update_dir && dir.inverse() != self.snake.dir {
-                self.snake.next_dir = Some(dir);
-            } else if dir.inverse() != self.snake.last_update_dir {
-                self.snake.dir = dir;
-            }
+        if keycode == KeyCode::Tab {
+            self.toggle_playtest();
+            return;
+        }
+        if let (Some(editor), true) = (&mut self.editor, self.editing) {
+            editor.key_down(keycode);
+            return;
         }
 
-        if self.gameover {
-            let snake_pos = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
-            let food_pos = GridPosition::random(GRID_SIZE.0, GRID_SIZE.1);
-            self.snake = Snake::new(snake_pos);
-            self.food = Food::new(food_pos);
-            self.gameover = false;
+        if let Some(dir) = direction_from_keycode(keycode) {
+            self.turn(dir);
+        }
+
+        if let Some(overlay) = &mut self.overlay {
+            let action = Nav::from_key(keycode).and_then(|nav| overlay.navigate(nav));
+            self.game_over_action(ctx, action);
+        }
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, btn: Button, id: GamepadId) {
+        self.rumble.gamepad_used(id);
+        if self.editing {
+            return;
+        }
+
+        if let Some(dir) = direction_from_button(btn) {
+            self.turn(dir);
+        }
+
+        if let Some(overlay) = &mut self.overlay {
+            let action = Nav::from_button(btn).and_then(|nav| overlay.navigate(nav));
+            self.game_over_action(ctx, action);
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let (Some(editor), true) = (&mut self.editor, self.editing) {
+            editor.mouse_motion(x, y);
+        } else if let Some(overlay) = &mut self.overlay {
+            overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if let (Some(editor), true) = (&mut self.editor, self.editing) {
+            editor.mouse_button_down(button, x, y);
+        } else if let (Some(overlay), MouseButton::Left) = (&self.overlay, button) {
+            let action = overlay.action_at(ctx, x, y);
+            self.game_over_action(ctx, action);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        if let Some(editor) = &mut self.editor {
+            editor.mouse_button_up(button);
         }
     }
 }
 
-fn main() -> GameResult {
+/// The developer console's commands.
+fn commands() -> Commands<GameState> {
+    Commands::new()
+        .add(
+            "set",
+            "speed <moves per second>",
+            "change how fast the snake moves",
+            |game, args| match args.first() {
+                Some(&"speed") => {
+                    let speed: f32 = console::arg(args, 1, "speed")?;
+                    if speed <= 0.0 {
+                        return Err("the speed has to be above 0".to_string());
+                    }
+                    game.update_interval = Duration::from_secs_f32(1.0 / speed);
+                    Ok(format!("speed is {}", speed))
+                }
+                _ => Err("there's only `speed` to set".to_string()),
+            },
+        )
+        .add(
+            "spawn",
+            "food <x> <y>",
+            "move the food",
+            |game, args| match args.first() {
+                Some(&"food") => {
+                    let x: i16 = console::arg(args, 1, "x")?;
+                    let y: i16 = console::arg(args, 2, "y")?;
+                    if x < 0 || y < 0 || x >= GRID_SIZE.0 || y >= GRID_SIZE.1 {
+                        return Err(format!("the board is {} by {}", GRID_SIZE.0, GRID_SIZE.1));
+                    }
+                    game.sim.food = Food::new(GridPosition::new(x, y));
+                    Ok(format!("food at {}, {}", x, y))
+                }
+                _ => Err("there's only `food` to spawn".to_string()),
+            },
+        )
+        .add(
+            "grow",
+            "<segments>",
+            "make the snake longer",
+            |game, args| {
+                let segments: usize = console::arg(args, 0, "number of segments")?;
+                game.sim.snake.grow(segments);
+                Ok(format!("the snake is {} long", game.sim.snake.length()))
+            },
+        )
+        .add("god", "", "stop the snake dying", |game, _| {
+            game.sim.god = !game.sim.god;
+            Ok(format!(
+                "god mode is {}",
+                if game.sim.god { "on" } else { "off" }
+            ))
+        })
+}
+
+fn main() -> GameResult<ExitCode> {
     let (ctx, events_loop) = &mut ggez::ContextBuilder::new("snake", "Sprial404")
-        .window_setup(ggez::conf::WindowSetup::default().title("Snake"))
+        .window_setup(window::setup("Snake"))
         .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
         .build()?;
-    let state = &mut GameState::new();
-    event::run(ctx, events_loop, state)
+    let game = GameState::new();
+    // Daily challenges are ranked, so they can't be changed from the console.
+    let mut state = match game.daily {
+        Some(_) => App::new(game),
+        None => App::new(game).with_console(commands()),
+    };
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
 }
@@ -0,0 +1,422 @@
+//! Replaying recorded games, to check the rules still play out the same way after they're
+//! changed.
+//!
+//! A `Script` holds the turns given before each step of a game, and a hash of the game after
+//! each step from `state_hash`. Replaying it fails on the first step that hashes differently.
+//! Where the food lands is recorded too and put back after each step, so the scripts cover how
+//! the snake moves, grows and dies rather than the random numbers placing the food.
+//!
+//! Scripts are written as text, like:
+//!
+//! ```text
+//! #version 1
+//! wrap true
+//! god false
+//! length 2
+//! food 12,10
+//! maze ..............................|..#...........................|...
+//! tick - 12,10 3f5a0b2c9d1e8f47
+//! tick U 12,10 09b1c2d3e4f5a6b7
+//! ```
+//!
+//! The maze is only there for games in one, with its rows split by `|`. Each tick lists its
+//! turns as the letters `U`, `D`, `L` and `R`, or `-` for none, then where the food is and the
+//! hash. Scripts recorded before they had a version line are version 0, and are otherwise the
+//! same. The version line is read like those of the games' saved files in `common::migrate`, but
+//! here, so the rules can be built without the games' window and sound.
+//!
+//! New scripts come from real games: snake started with `--record <path>` writes one once the
+//! snake dies. Commands from the developer console aren't recorded.
+//!
+//! `Script::verify` checks a script is a real game rather than the rules: it plays the script
+//! from the seed the game was said to be played with, letting the food land where that seed puts
+//! it, so a script that was changed, or recorded with another seed or with cheats, doesn't hold
+//! up. `verify_run` checks a run submitted to the online leaderboard against its replay too,
+//! including that it was played on the day's board, and `quick-games verify` does either from the
+//! command line. Which day a run was for is worked out in `common::daily`, so the board's
+//! wrapping is passed in rather than looked up here.
+
+use std::fmt;
+
+use quick_games_leaderboard::{replay_hash, Submission};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::{Direction, GridPosition, Maze, Simulation};
+
+/// The FNV-1a offset basis and prime.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// The version scripts are written with.
+pub const VERSION: u32 = 1;
+const VERSION_PREFIX: &str = "#version ";
+
+/// A script without its version line, failing for scripts from a newer version.
+fn strip_version(contents: &str) -> Result<&str, String> {
+    let first = contents.lines().next().unwrap_or("");
+    let version = match first.strip_prefix(VERSION_PREFIX) {
+        Some(version) => version,
+        None => return Ok(contents),
+    };
+    let version: u32 = version
+        .trim()
+        .parse()
+        .map_err(|_| format!("bad version line `{}`", first))?;
+    if version > VERSION {
+        return Err(format!(
+            "it's from a newer version of the games, with replay version {} rather than {}",
+            version, VERSION
+        ));
+    }
+    let rest = &contents[first.len()..];
+    Ok(rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+        .unwrap_or(rest))
+}
+
+/// A hash of everything about a game the rules decide: the snake, the food and whether the
+/// game is over. It's the same on every platform and version of Rust, so it can be saved.
+pub fn state_hash(sim: &Simulation) -> u64 {
+    let snake = &sim.snake;
+    let positions = std::iter::once(snake.head.pos)
+        .chain(snake.body.iter().map(|segment| segment.pos))
+        .chain(std::iter::once(sim.food.pos));
+    let mut bytes = Vec::new();
+    for pos in positions {
+        bytes.extend_from_slice(&pos.x.to_le_bytes());
+        bytes.extend_from_slice(&pos.y.to_le_bytes());
+    }
+    bytes.push(direction_to_char(snake.dir) as u8);
+    bytes.push(direction_to_char(snake.last_update_dir) as u8);
+    bytes.push(
+        snake
+            .next_dir
+            .map_or(b'-', |dir| direction_to_char(dir) as u8),
+    );
+    bytes.push(sim.over as u8);
+
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+fn direction_to_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+fn direction_from_char(c: char) -> Option<Direction> {
+    match c {
+        'U' => Some(Direction::Up),
+        'D' => Some(Direction::Down),
+        'L' => Some(Direction::Left),
+        'R' => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn parse_pos(text: &str) -> Result<GridPosition, String> {
+    let mut parts = text.splitn(2, ',');
+    let mut coordinate = || {
+        parts
+            .next()
+            .and_then(|part| part.parse().ok())
+            .ok_or_else(|| format!("bad position `{}`", text))
+    };
+    Ok(GridPosition::new(coordinate()?, coordinate()?))
+}
+
+/// A step of a recorded game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tick {
+    /// The turns given to the snake before the step, in order.
+    pub turns: Vec<Direction>,
+    /// Where the food was after the step.
+    pub food: GridPosition,
+    /// The `state_hash` after the step.
+    pub hash: u64,
+}
+
+/// A recorded game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    pub wrap: bool,
+    /// Whether the snake couldn't die, from `--invincible`.
+    pub god: bool,
+    /// How long the snake started.
+    pub length: usize,
+    pub maze: Maze,
+    /// Where the food started.
+    pub food: GridPosition,
+    pub ticks: Vec<Tick>,
+}
+
+/// Where a replay first went differently from its script.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Mismatch {
+    /// The step, counting from 0.
+    pub tick: usize,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "tick {} hashed to {:016x} rather than {:016x}",
+            self.tick, self.actual, self.expected
+        )
+    }
+}
+
+impl Script {
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut script = Script {
+            wrap: true,
+            god: false,
+            length: 2,
+            maze: Maze::empty(),
+            food: GridPosition::new(0, 0),
+            ticks: Vec::new(),
+        };
+        let contents = strip_version(contents)?;
+        for (number, line) in contents.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            match words.as_slice() {
+                [] => (),
+                ["wrap", wrap] => {
+                    script.wrap = wrap.parse().map_err(|_| error("bad wrap".into()))?
+                }
+                ["god", god] => script.god = god.parse().map_err(|_| error("bad god".into()))?,
+                ["length", length] => {
+                    script.length = length.parse().map_err(|_| error("bad length".into()))?
+                }
+                ["food", pos] => script.food = parse_pos(pos).map_err(error)?,
+                ["maze", rows] => {
+                    let rows: Vec<Vec<char>> =
+                        rows.split('|').map(|row| row.chars().collect()).collect();
+                    script.maze = Maze::from_rows(&rows).map_err(error)?;
+                }
+                ["tick", turns, food, hash] => {
+                    let turns = match *turns {
+                        "-" => Vec::new(),
+                        turns => turns
+                            .chars()
+                            .map(direction_from_char)
+                            .collect::<Option<_>>()
+                            .ok_or_else(|| error(format!("bad turns `{}`", turns)))?,
+                    };
+                    script.ticks.push(Tick {
+                        turns,
+                        food: parse_pos(food).map_err(error)?,
+                        hash: u64::from_str_radix(hash, 16)
+                            .map_err(|_| error(format!("bad hash `{}`", hash)))?,
+                    });
+                }
+                _ => return Err(error(format!("don't know `{}`", line.trim()))),
+            }
+        }
+        Ok(script)
+    }
+
+    /// The game as it was when the script was recorded.
+    pub fn start(&self) -> Simulation {
+        // The food is put where it was recorded, so the random numbers don't matter.
+        let mut sim = Simulation::with_maze(StdRng::seed_from_u64(0), self.wrap, self.maze.clone());
+        sim.snake
+            .grow(self.length.saturating_sub(sim.snake.length()));
+        sim.god = self.god;
+        sim.food.pos = self.food;
+        sim
+    }
+
+    /// Plays the script, checking the game after every step. Returns the game at the end.
+    pub fn replay(&self) -> Result<Simulation, Mismatch> {
+        let mut sim = self.start();
+        for (index, tick) in self.ticks.iter().enumerate() {
+            for &dir in &tick.turns {
+                sim.snake.turn(dir);
+            }
+            sim.step();
+            sim.food.pos = tick.food;
+            let actual = state_hash(&sim);
+            if actual != tick.hash {
+                return Err(Mismatch {
+                    tick: index,
+                    expected: tick.hash,
+                    actual,
+                });
+            }
+        }
+        Ok(sim)
+    }
+
+    /// Plays the script from `seed`, checking it's a whole game played with that seed and
+    /// without cheats. Returns the game at the end, whose snake's length is the run's score.
+    pub fn verify(&self, seed: u64) -> Result<Simulation, String> {
+        if self.god {
+            return Err("it was played with --invincible".to_string());
+        }
+        let mut sim =
+            Simulation::with_maze(StdRng::seed_from_u64(seed), self.wrap, self.maze.clone());
+        if self.length != sim.snake.length() {
+            return Err(format!(
+                "the snake started {} long rather than {}",
+                self.length,
+                sim.snake.length()
+            ));
+        }
+        if self.food != sim.food.pos {
+            return Err(format!("the food didn't start where seed {} puts it", seed));
+        }
+        for (index, tick) in self.ticks.iter().enumerate() {
+            if sim.over {
+                return Err(format!(
+                    "it goes on after the game ended, at tick {}",
+                    index
+                ));
+            }
+            for &dir in &tick.turns {
+                sim.snake.turn(dir);
+            }
+            sim.step();
+            let actual = state_hash(&sim);
+            if actual != tick.hash {
+                let mismatch = Mismatch {
+                    tick: index,
+                    expected: tick.hash,
+                    actual,
+                };
+                return Err(mismatch.to_string());
+            }
+        }
+        if !sim.over {
+            return Err("it ends before the game does".to_string());
+        }
+        Ok(sim)
+    }
+
+    /// Checks the script was played on a daily challenge's board: the open one, without a maze,
+    /// wrapping at its edges if the day's does.
+    pub fn check_daily(&self, wrap: bool) -> Result<(), String> {
+        if self.wrap != wrap {
+            return Err(if wrap {
+                "the day's board wraps, but it was played without wrapping".to_string()
+            } else {
+                "it was played wrapping at the edges, but the day's board doesn't".to_string()
+            });
+        }
+        if self.maze != Maze::empty() {
+            return Err("it was played in a maze, but daily challenges aren't".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}{}", VERSION_PREFIX, VERSION)?;
+        writeln!(f, "wrap {}", self.wrap)?;
+        writeln!(f, "god {}", self.god)?;
+        writeln!(f, "length {}", self.length)?;
+        writeln!(f, "food {},{}", self.food.x, self.food.y)?;
+        if self.maze != Maze::empty() {
+            let rows: Vec<String> = self
+                .maze
+                .rows()
+                .iter()
+                .map(|row| row.iter().collect())
+                .collect();
+            writeln!(f, "maze {}", rows.join("|"))?;
+        }
+        for tick in &self.ticks {
+            let turns: String = tick
+                .turns
+                .iter()
+                .map(|&dir| direction_to_char(dir))
+                .collect();
+            writeln!(
+                f,
+                "tick {} {},{} {:016x}",
+                if turns.is_empty() { "-" } else { &turns },
+                tick.food.x,
+                tick.food.y,
+                tick.hash
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks `replay` is the replay of `submission`, a daily run submitted to the online
+/// leaderboard, and that it comes to the score submitted with the seed submitted, on the day's
+/// board, which wraps if `wrap`. Returns the game at the end.
+pub fn verify_run(submission: &Submission, replay: &str, wrap: bool) -> Result<Simulation, String> {
+    if submission.game != "snake" {
+        return Err(format!("it's a run of {}, not snake", submission.game));
+    }
+    if submission.replay.as_deref() != Some(replay_hash(replay.as_bytes()).as_str()) {
+        return Err("it isn't the replay submitted with the run".to_string());
+    }
+    let script = Script::parse(replay)?;
+    script.check_daily(wrap)?;
+    let sim = script.verify(submission.seed)?;
+    let length = sim.snake.length();
+    if submission.score != length as f64 {
+        return Err(format!(
+            "it comes to a length of {} rather than {}",
+            length, submission.score
+        ));
+    }
+    Ok(sim)
+}
+
+/// Records a game being played into a `Script`.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    script: Script,
+    turns: Vec<Direction>,
+}
+
+impl Recorder {
+    /// Starts recording a game that hasn't been stepped yet.
+    pub fn new(sim: &Simulation) -> Self {
+        Recorder {
+            script: Script {
+                wrap: sim.wrap,
+                god: sim.god,
+                length: sim.snake.length(),
+                maze: sim.maze.clone(),
+                food: sim.food.pos,
+                ticks: Vec::new(),
+            },
+            turns: Vec::new(),
+        }
+    }
+
+    /// Notes a turn given to the snake.
+    pub fn turn(&mut self, dir: Direction) {
+        self.turns.push(dir);
+    }
+
+    /// Notes a step of the game, after it's been stepped.
+    pub fn step(&mut self, sim: &Simulation) {
+        self.script.ticks.push(Tick {
+            turns: std::mem::take(&mut self.turns),
+            food: sim.food.pos,
+            hash: state_hash(sim),
+        });
+    }
+
+    pub fn finish(self) -> Script {
+        self.script
+    }
+}
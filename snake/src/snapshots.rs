@@ -0,0 +1,58 @@
+//! Golden image tests of the board in every theme, from `common::snapshot`. They need a display,
+//! so they only run with `cargo test -p snake -- --ignored`.
+
+use std::path::Path;
+
+use common::snapshot::{self, Tolerance};
+use common::theme::THEMES;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use snake::{Direction, GridPosition, Maze, Simulation};
+
+use super::{draw_game, PALETTE, SCREEN_SIZE};
+
+/// A game partway through, in a maze with walls and a pair of portals.
+fn game() -> Simulation {
+    let mut rows = vec![vec!['.'; 30]; 20];
+    for wall in &mut rows[4][3..12] {
+        *wall = '#';
+    }
+    for row in &mut rows[12..18] {
+        row[22] = '#';
+    }
+    rows[2][25] = 'a';
+    rows[16][4] = 'a';
+    let maze = Maze::from_rows(&rows).unwrap();
+
+    let mut sim = Simulation::with_maze(StdRng::seed_from_u64(0), true, maze);
+    sim.food.pos = GridPosition::new(20, 6);
+    sim.snake.grow(4);
+    for _ in 0..4 {
+        sim.step();
+    }
+    sim.snake.turn(Direction::Up);
+    for _ in 0..3 {
+        sim.step();
+    }
+    sim
+}
+
+#[test]
+#[ignore]
+fn board_in_every_theme() {
+    let (ctx, _) = &mut ggez::ContextBuilder::new("snake-snapshots", "Sprial404")
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()
+        .unwrap();
+    let sim = game();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots");
+    for theme in THEMES {
+        let palette = theme.apply(PALETTE);
+        let image = snapshot::render(ctx, SCREEN_SIZE.0 as u16, SCREEN_SIZE.1 as u16, |ctx| {
+            draw_game(ctx, &sim.maze, &sim, &palette)
+        })
+        .unwrap();
+        let path = dir.join(format!("{}.png", theme.name));
+        snapshot::assert_matches(&image, &path, Tolerance::default());
+    }
+}
@@ -0,0 +1,166 @@
+//! Replays the recorded games in `tests/replays`, which are checked after every step.
+//!
+//! To add one, play snake with `--record tests/replays/<name>.txt` until the snake dies.
+
+use std::fs;
+use std::path::Path;
+
+use quick_games_leaderboard::{replay_hash, Submission};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use snake::replay::{verify_run, Recorder, Script};
+use snake::{Direction, Simulation};
+
+fn load(name: &str) -> Script {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/replays")
+        .join(name);
+    let contents = fs::read_to_string(&path).unwrap();
+    Script::parse(&contents).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+#[test]
+fn recorded_games_play_out_the_same() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/replays");
+    let mut replayed = 0;
+    for entry in fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        if let Err(mismatch) = load(&name).replay() {
+            panic!("{}: {}", name, mismatch);
+        }
+        replayed += 1;
+    }
+    assert!(replayed > 0);
+}
+
+#[test]
+fn replays_end_where_they_were_recorded() {
+    assert!(load("off_the_edge.txt").replay().unwrap().over);
+    assert!(load("eats_itself.txt").replay().unwrap().over);
+    assert!(load("portal_and_wall.txt").replay().unwrap().over);
+    let sim = load("grow_and_wrap.txt").replay().unwrap();
+    assert!(!sim.over);
+    assert_eq!(sim.snake.length(), 6);
+}
+
+#[test]
+fn a_changed_step_is_caught() {
+    let mut script = load("grow_and_wrap.txt");
+    script.ticks[3].turns = vec![Direction::Up];
+    assert_eq!(script.replay().unwrap_err().tick, 3);
+}
+
+#[test]
+fn recordings_are_written_and_read_back() {
+    let mut sim = Simulation::new(StdRng::seed_from_u64(7), true);
+    let mut recorder = Recorder::new(&sim);
+    for step in 0..30 {
+        if step % 7 == 3 {
+            let dir = if step % 2 == 0 {
+                Direction::Up
+            } else {
+                Direction::Down
+            };
+            sim.snake.turn(dir);
+            recorder.turn(dir);
+        }
+        sim.step();
+        recorder.step(&sim);
+    }
+
+    let script = recorder.finish();
+    let read = Script::parse(&script.to_string()).unwrap();
+    assert_eq!(read, script);
+    assert_eq!(read.replay().unwrap().snake, sim.snake);
+}
+
+#[test]
+fn scripts_from_before_versions_are_upgraded() {
+    let script = load("quick_turns.txt");
+    let old = script.to_string().split_once('\n').unwrap().1.to_string();
+    assert_eq!(Script::parse(&old).unwrap(), script);
+    assert!(Script::parse(&format!("#version 99\n{}", old)).is_err());
+}
+
+/// A whole game played from `seed` on a bounded board, running right until it leaves it.
+fn record_from_seed(seed: u64) -> (Script, Simulation) {
+    let mut sim = Simulation::new(StdRng::seed_from_u64(seed), false);
+    let mut recorder = Recorder::new(&sim);
+    while !sim.over {
+        sim.step();
+        recorder.step(&sim);
+    }
+    (recorder.finish(), sim)
+}
+
+#[test]
+fn games_are_verified_against_their_seed() {
+    let (script, sim) = record_from_seed(11);
+    let verified = script.verify(11).unwrap();
+    assert_eq!(verified.snake, sim.snake);
+    assert!(script.verify(12).is_err());
+}
+
+#[test]
+fn changed_or_unfinished_games_dont_verify() {
+    let (script, _) = record_from_seed(11);
+
+    let mut longer = script.clone();
+    longer.length += 3;
+    assert!(longer.verify(11).is_err());
+
+    let mut unfinished = script.clone();
+    unfinished.ticks.pop();
+    assert!(unfinished.verify(11).is_err());
+
+    let mut cheated = script;
+    cheated.god = true;
+    assert!(cheated.verify(11).is_err());
+}
+
+/// `script` submitted as a daily run played with `seed`, coming to `length`.
+fn submit(script: &Script, seed: u64, length: usize) -> (Submission, String) {
+    let replay = script.to_string();
+    let submission = Submission {
+        game: "snake".to_string(),
+        mode: "daily".to_string(),
+        player: "Sam".to_string(),
+        seed,
+        score: length as f64,
+        replay: Some(replay_hash(replay.as_bytes())),
+    };
+    (submission, replay)
+}
+
+#[test]
+fn daily_runs_verify_on_the_days_board() {
+    let (script, sim) = record_from_seed(11);
+    let (submission, replay) = submit(&script, 11, sim.snake.length());
+    assert_eq!(
+        verify_run(&submission, &replay, false).unwrap().snake,
+        sim.snake
+    );
+}
+
+#[test]
+fn daily_runs_with_the_wrong_wrapping_dont_verify() {
+    let (script, sim) = record_from_seed(11);
+    let (submission, replay) = submit(&script, 11, sim.snake.length());
+    let error = verify_run(&submission, &replay, true).unwrap_err();
+    assert!(error.contains("wrap"), "{}", error);
+
+    let mut wrapping = script;
+    wrapping.wrap = true;
+    let (submission, replay) = submit(&wrapping, 11, sim.snake.length());
+    let error = verify_run(&submission, &replay, false).unwrap_err();
+    assert!(error.contains("wrap"), "{}", error);
+}
+
+#[test]
+fn daily_runs_in_a_maze_dont_verify() {
+    let (mut script, sim) = record_from_seed(11);
+    script.maze = load("portal_and_wall.txt").maze;
+    let (submission, replay) = submit(&script, 11, sim.snake.length());
+    let error = verify_run(&submission, &replay, false).unwrap_err();
+    assert!(error.contains("maze"), "{}", error);
+}
@@ -0,0 +1,388 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::input::keyboard;
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::collections::VecDeque;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+const LANES: usize = 5;
+const LANE_WIDTH: f32 = 80.0;
+const SCREEN_SIZE: (f32, f32) = (LANES as f32 * LANE_WIDTH, 720.0);
+
+/// The fixed height of the snake's head on screen; the world scrolls past it.
+const HEAD_Y: f32 = 520.0;
+const BALL_RADIUS: f32 = 10.0;
+/// How many recorded head positions apart each body ball trails.
+const BALL_SPACING: usize = 6;
+const STEER_SPEED: f32 = 420.0;
+
+const SCROLL_SPEED: f32 = 220.0;
+/// Vertical distance between rows of blocks.
+const ROW_SPACING: f32 = 360.0;
+const BLOCK_HEIGHT: f32 = 72.0;
+/// Seconds between each point of damage while pushing against a block.
+const SMASH_INTERVAL: f32 = 0.06;
+
+const PICKUP_RADIUS: f32 = 14.0;
+const START_LENGTH: u32 = 4;
+
+/// A numbered block, costing one ball of length per point to smash through.
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    lane: usize,
+    /// The y coordinate of the block's bottom edge in world space.
+    y: f32,
+    value: u32,
+}
+
+impl Block {
+    fn rect(&self, scroll: f32) -> Rect {
+        Rect::new(
+            self.lane as f32 * LANE_WIDTH + 2.0,
+            self.y - BLOCK_HEIGHT + scroll,
+            LANE_WIDTH - 4.0,
+            BLOCK_HEIGHT - 4.0,
+        )
+    }
+}
+
+/// A pickup adding `value` balls to the snake.
+#[derive(Debug, Copy, Clone)]
+struct Pickup {
+    x: f32,
+    y: f32,
+    value: u32,
+}
+
+/// The state for the game.
+struct GameState {
+    /// The horizontal position of the head.
+    head_x: f32,
+    /// The recent head positions, newest first, that the body balls follow.
+    trail: VecDeque<(f32, f32)>,
+    length: u32,
+    /// How far the world has scrolled down so far.
+    scroll: f32,
+    blocks: Vec<Block>,
+    pickups: Vec<Pickup>,
+    /// The world y coordinate the next row spawns at.
+    next_row: f32,
+    smash_timer: f32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state.
+    pub fn new() -> Self {
+        GameState {
+            head_x: SCREEN_SIZE.0 / 2.0,
+            trail: VecDeque::new(),
+            length: START_LENGTH,
+            scroll: 0.0,
+            blocks: Vec::new(),
+            pickups: Vec::new(),
+            next_row: 0.0,
+            smash_timer: 0.0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [("distance", f64::from(self.distance()))];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("snakeblocks", &values);
+        }
+        let _ = achievements::check_run("snakeblocks", &values);
+    }
+
+    /// The distance travelled, used as the score.
+    fn distance(&self) -> u32 {
+        (self.scroll / 100.0) as u32
+    }
+
+    /// Spawns a row of blocks, always leaving at least one lane the snake can afford.
+    fn spawn_row(&mut self) {
+        let mut rng = rand::thread_rng();
+        let y = -self.next_row;
+        let max_value = (self.length + self.distance() / 10 + 2).max(3);
+        let cheap_lane = rng.gen_range(0, LANES);
+
+        for lane in 0..LANES {
+            if lane != cheap_lane && rng.gen_bool(0.4) {
+                continue;
+            }
+            let value = if lane == cheap_lane {
+                rng.gen_range(1, self.length.max(2))
+            } else {
+                rng.gen_range(1, max_value + 1)
+            };
+            self.blocks.push(Block { lane, y, value });
+        }
+
+        // Scatter pickups in the gap before the next row.
+        for _ in 0..rng.gen_range(0, 3) {
+            self.pickups.push(Pickup {
+                x: rng.gen_range(PICKUP_RADIUS, SCREEN_SIZE.0 - PICKUP_RADIUS),
+                y: y - rng.gen_range(BLOCK_HEIGHT + PICKUP_RADIUS, ROW_SPACING - PICKUP_RADIUS),
+                value: rng.gen_range(1, 6),
+            });
+        }
+
+        self.next_row += ROW_SPACING;
+    }
+
+    /// Returns the index of the block directly in front of the head, if it is touching it.
+    fn blocking(&self) -> Option<usize> {
+        let lane = ((self.head_x / LANE_WIDTH) as usize).min(LANES - 1);
+        self.blocks.iter().position(|block| {
+            let bottom = block.y + self.scroll;
+            block.lane == lane && bottom >= HEAD_Y - BALL_RADIUS && bottom - BLOCK_HEIGHT < HEAD_Y
+        })
+    }
+
+    fn tick(&mut self, ctx: &Context, dt: f32) {
+        let mut dir = 0.0;
+        if keyboard::is_key_pressed(ctx, KeyCode::Left) || keyboard::is_key_pressed(ctx, KeyCode::A)
+        {
+            dir -= 1.0;
+        }
+        if keyboard::is_key_pressed(ctx, KeyCode::Right)
+            || keyboard::is_key_pressed(ctx, KeyCode::D)
+        {
+            dir += 1.0;
+        }
+        let new_x = (self.head_x + dir * STEER_SPEED * dt)
+            .max(BALL_RADIUS)
+            .min(SCREEN_SIZE.0 - BALL_RADIUS);
+
+        // Don't allow steering sideways into the middle of a block.
+        let old_x = self.head_x;
+        self.head_x = new_x;
+        if let Some(i) = self.blocking() {
+            let bottom = self.blocks[i].y + self.scroll;
+            if bottom > HEAD_Y + BALL_RADIUS {
+                self.head_x = old_x;
+            }
+        }
+
+        match self.blocking() {
+            Some(i) => {
+                // Push against the block, trading one ball for one point until either runs out.
+                self.smash_timer -= dt;
+                if self.smash_timer <= 0.0 {
+                    self.smash_timer = SMASH_INTERVAL;
+                    self.blocks[i].value -= 1;
+                    self.length -= 1;
+                    if self.blocks[i].value == 0 {
+                        self.blocks.remove(i);
+                    }
+                    if self.length == 0 {
+                        self.gameover = true;
+                        self.record_run();
+                    }
+                }
+            }
+            None => {
+                self.smash_timer = 0.0;
+                self.scroll += SCROLL_SPEED * dt;
+            }
+        }
+
+        while self.next_row < self.scroll + SCREEN_SIZE.1 {
+            self.spawn_row();
+        }
+
+        let (head_x, scroll) = (self.head_x, self.scroll);
+        let mut gained = 0;
+        self.pickups.retain(|pickup| {
+            let dx = pickup.x - head_x;
+            let dy = pickup.y + scroll - HEAD_Y;
+            let hit = dx * dx + dy * dy <= (PICKUP_RADIUS + BALL_RADIUS).powi(2);
+            if hit {
+                gained += pickup.value;
+            }
+            !hit
+        });
+        self.length += gained;
+
+        self.blocks
+            .retain(|block| block.y - BLOCK_HEIGHT + scroll < SCREEN_SIZE.1);
+        self.pickups
+            .retain(|pickup| pickup.y + scroll < SCREEN_SIZE.1);
+
+        self.trail.push_front((self.head_x, self.scroll));
+        self.trail.truncate(self.length as usize * BALL_SPACING + 1);
+    }
+
+    fn draw_snake(&self, ctx: &mut Context) -> GameResult<()> {
+        // Each ball sits where the head was a few updates ago, offset by how far the world has
+        // scrolled since.
+        for i in (0..self.length as usize).rev() {
+            let (x, scroll) = self
+                .trail
+                .get(i * BALL_SPACING)
+                .copied()
+                .unwrap_or((self.head_x, self.scroll - i as f32 * BALL_RADIUS * 2.0));
+            let y = HEAD_Y + (self.scroll - scroll);
+            if y > SCREEN_SIZE.1 {
+                continue;
+            }
+
+            let color = if i == 0 {
+                [1.0, 0.9, 0.2, 1.0]
+            } else {
+                [1.0, 0.6, 0.1, 1.0]
+            };
+            let ball = graphics::Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                ggez::mint::Point2 { x, y },
+                BALL_RADIUS,
+                0.5,
+                color.into(),
+            )?;
+            graphics::draw(ctx, &ball, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        let label = Text::new(
+            TextFragment::new(self.length.to_string())
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(20.0)),
+        );
+        graphics::draw(
+            ctx,
+            &label,
+            (ggez::mint::Point2 {
+                x: self.head_x - 6.0,
+                y: HEAD_Y - BALL_RADIUS * 3.0,
+            },),
+        )
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.gameover {
+            self.tick(ctx, dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.1, 0.1, 0.15, 1.0].into());
+
+        for block in self.blocks.iter() {
+            // Blocks get redder the more they would cost relative to the snake's length.
+            let danger = (block.value as f32 / self.length.max(1) as f32).min(1.0);
+            let rect = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                block.rect(self.scroll),
+                [0.3 + danger * 0.7, 0.8 - danger * 0.6, 0.3, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &rect, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+            let rect = block.rect(self.scroll);
+            let value = Text::new(
+                TextFragment::new(block.value.to_string())
+                    .color([0.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(28.0)),
+            );
+            graphics::draw(
+                ctx,
+                &value,
+                (ggez::mint::Point2 {
+                    x: rect.x + rect.w / 2.0 - 10.0,
+                    y: rect.y + rect.h / 2.0 - 14.0,
+                },),
+            )?;
+        }
+
+        for pickup in self.pickups.iter() {
+            let y = pickup.y + self.scroll;
+            let circle = graphics::Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                ggez::mint::Point2 { x: pickup.x, y },
+                PICKUP_RADIUS,
+                0.5,
+                [0.9, 0.9, 0.2, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &circle, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+            let value = Text::new(
+                TextFragment::new(format!("+{}", pickup.value))
+                    .color([1.0, 1.0, 1.0, 1.0].into())
+                    .scale(Scale::uniform(16.0)),
+            );
+            graphics::draw(
+                ctx,
+                &value,
+                (ggez::mint::Point2 {
+                    x: pickup.x - 10.0,
+                    y: y - PICKUP_RADIUS - 18.0,
+                },),
+            )?;
+        }
+
+        self.draw_snake(ctx)?;
+
+        let hud = Text::new(
+            TextFragment::new(format!("Distance: {}", self.distance()))
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER!")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+
+            graphics::draw(ctx, &game_over, (ggez::mint::Point2 { x: 8.0, y: 40.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Space if self.gameover => *self = GameState::new(),
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("snakeblocks", "Sprial404")
+        .window_setup(window::setup("Snake vs. Blocks"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
@@ -0,0 +1,191 @@
+//! The spectator window, showing a game without its HUD for a projector or a stream. Games
+//! open it themselves when they are started with `--spectator`, and send it frames over
+//! localhost; see `common::spectator`.
+//!
+//! It can be started on its own too, with `--port` for a game started with `--spectator` and a
+//! port, and `--fullscreen` to fill a screen.
+//!
+//! Beside the game it shows a panel of the stream's chat and votes, sent to it by a bridge for
+//! the stream's chat on the port given with `--events`; see `overlay`.
+
+mod overlay;
+
+use std::io::{self, Read};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use ggez::conf::{FullscreenType, WindowMode};
+use ggez::event::{self, EventHandler, KeyCode, KeyMods};
+use ggez::graphics::{self, DrawParam, FilterMode, Image, Rect};
+use ggez::{Context, GameResult};
+
+use common::args;
+use common::spectator::{DEFAULT_PORT, MAGIC};
+use common::ui;
+use common::window;
+
+use overlay::Overlay;
+
+const SCREEN_SIZE: (f32, f32) = (960.0, 540.0);
+const BACKGROUND_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+const TEXT_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+
+/// How long to wait before trying to connect to the game again.
+const RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A frame from the game: its width, height and RGBA pixels.
+type Frame = (u16, u16, Vec<u8>);
+
+/// Reads one frame from the game.
+fn read_frame(stream: &mut TcpStream) -> io::Result<Frame> {
+    let mut header = [0; 12];
+    stream.read_exact(&mut header)?;
+    if &header[..4] != MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "that isn't a game's spectator feed",
+        ));
+    }
+    let size = |bytes: &[u8]| {
+        let mut le = [0; 4];
+        le.copy_from_slice(bytes);
+        u32::from_le_bytes(le)
+    };
+    let (width, height) = (size(&header[4..8]), size(&header[8..12]));
+    if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("a {}x{} frame is too big", width, height),
+        ));
+    }
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+    stream.read_exact(&mut pixels)?;
+    Ok((width as u16, height as u16, pixels))
+}
+
+/// Keeps `latest` up to date with the frames the game sends, connecting again whenever the game
+/// isn't running or closes.
+fn receive(port: u16, latest: Arc<Mutex<Option<Frame>>>) {
+    loop {
+        if let Ok(mut stream) = TcpStream::connect(("127.0.0.1", port)) {
+            loop {
+                match read_frame(&mut stream) {
+                    Ok(frame) => *latest.lock().unwrap() = Some(frame),
+                    Err(e) => {
+                        if e.kind() != io::ErrorKind::UnexpectedEof {
+                            eprintln!("Lost the game's frames: {}", e);
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+        thread::sleep(RETRY_INTERVAL);
+    }
+}
+
+struct Spectator {
+    port: u16,
+    /// The newest frame received, until it's drawn.
+    latest: Arc<Mutex<Option<Frame>>>,
+    /// The frame being shown.
+    image: Option<Image>,
+    overlay: Overlay,
+}
+
+impl Spectator {
+    fn new(port: u16, events_port: u16) -> Self {
+        let latest = Arc::new(Mutex::new(None));
+        let receiving = Arc::clone(&latest);
+        thread::spawn(move || receive(port, receiving));
+        Spectator {
+            port,
+            latest,
+            image: None,
+            overlay: Overlay::new(events_port),
+        }
+    }
+}
+
+impl EventHandler for Spectator {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let frame = self.latest.lock().unwrap().take();
+        if let Some((width, height, pixels)) = frame {
+            let mut image = Image::from_rgba8(ctx, width, height, &pixels)?;
+            image.set_filter(FilterMode::Nearest);
+            self.image = Some(image);
+        }
+        self.overlay.update();
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, BACKGROUND_COLOR.into());
+        let screen = graphics::screen_coordinates(ctx);
+        let area = self.overlay.game_area(screen);
+        match &self.image {
+            Some(image) => {
+                // Fitted beside the panel, with bars at the sides or the top and bottom.
+                let (width, height) = (image.width() as f32, image.height() as f32);
+                let scale = (area.w / width).min(area.h / height);
+                graphics::draw(
+                    ctx,
+                    image,
+                    DrawParam::new()
+                        .dest(ggez::mint::Point2 {
+                            x: area.x + (area.w - width * scale) / 2.0,
+                            y: area.y + (area.h - height * scale) / 2.0,
+                        })
+                        .scale(ggez::mint::Vector2 { x: scale, y: scale }),
+                )?;
+            }
+            None => {
+                let waiting = format!("Waiting for a game on port {}...", self.port);
+                ui::draw_text(
+                    ctx,
+                    &waiting,
+                    TEXT_COLOR,
+                    24.0,
+                    area.x + 40.0,
+                    area.h - 64.0,
+                )?;
+            }
+        }
+        self.overlay.draw(ctx, screen)?;
+        graphics::present(ctx)
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        if let Err(e) = graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, width, height)) {
+            eprintln!("Couldn't resize the spectator view: {}", e);
+        }
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, key: KeyCode, _mods: KeyMods, _repeat: bool) {
+        if key == KeyCode::Escape {
+            event::quit(ctx);
+        } else {
+            self.overlay.key_down(key);
+        }
+    }
+}
+
+fn main() -> GameResult {
+    let port = args::get("port").unwrap_or(DEFAULT_PORT);
+    let events_port = args::get("events").unwrap_or_else(|| port.wrapping_add(1));
+    let mut window_mode = WindowMode::default()
+        .dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1)
+        .resizable(true);
+    if args::flag("fullscreen") {
+        window_mode = window_mode.fullscreen_type(FullscreenType::Desktop);
+    }
+
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("spectator", "Sprial404")
+        .window_setup(window::setup("Spectator"))
+        .window_mode(window_mode)
+        .build()?;
+    let state = &mut Spectator::new(port, events_port);
+    event::run(ctx, events_loop, state)
+}
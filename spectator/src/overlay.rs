@@ -0,0 +1,333 @@
+//! The chat and vote panel beside the game in the spectator window, for streams that want to
+//! show what their audience is saying.
+//!
+//! The window has no chat of its own. A bot or bridge for the stream's chat connects to the
+//! events port on localhost, the one after the frames' port unless `--events` gives one, and
+//! writes a line for each thing to show:
+//!
+//! ```text
+//! chat Sam that was close
+//! votes left=4 right=11 wait=2
+//! ```
+//!
+//! A `chat` line is the sender's name then their message, and a `votes` line is the whole tally
+//! so far, replacing the last. Each connection is read on a thread of its own, which queues what
+//! it reads for the draw loop to take each update.
+//!
+//! The panel is on the right, or the left if `spectator_overlay` is `left`, and left out if
+//! it's `off`. What it shows fades out after `spectator_overlay_fade` seconds, 20 by default,
+//! and `spectator_overlay_key`, `O` by default, shows and hides it.
+
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ggez::event::KeyCode;
+use ggez::graphics::{self, DrawMode, Rect};
+use ggez::{Context, GameResult};
+
+use common::bindings;
+use common::settings::Settings;
+use common::ui;
+
+/// The setting for which side the panel is on, or `off`.
+pub const POSITION_SETTING: &str = "spectator_overlay";
+/// The setting for how many seconds messages and votes are shown.
+pub const FADE_SETTING: &str = "spectator_overlay_fade";
+/// The setting for the key showing and hiding the panel.
+pub const KEY_SETTING: &str = "spectator_overlay_key";
+
+const DEFAULT_FADE: f32 = 20.0;
+/// The longest the fade setting can make it, an hour.
+const MAX_FADE: f32 = 60.0 * 60.0;
+const DEFAULT_KEY: KeyCode = KeyCode::O;
+/// How long messages take to fade out at the end of their time.
+const FADE_OUT: Duration = Duration::from_secs(1);
+
+const PANEL_WIDTH: f32 = 300.0;
+const PANEL_COLOR: [f32; 4] = [0.08, 0.08, 0.1, 1.0];
+const MARGIN: f32 = 16.0;
+const HEADING_COLOR: [f32; 4] = [0.6, 0.6, 0.65, 1.0];
+const NAME_COLOR: [f32; 4] = [1.0, 0.85, 0.3, 1.0];
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BAR_COLOR: [f32; 4] = [0.3, 0.6, 1.0, 1.0];
+const VOTE_HEIGHT: f32 = 26.0;
+
+/// The most messages kept, oldest dropped first.
+const MAX_MESSAGES: usize = 12;
+/// The longest message or name shown, in characters.
+const MAX_CHARS: usize = 200;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Side {
+    Left,
+    Right,
+}
+
+/// Something to show, as read from the events port.
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Chat {
+        name: String,
+        text: String,
+    },
+    /// Each option and how many votes it has.
+    Votes(Vec<(String, u32)>),
+}
+
+impl Event {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let mut parts = line.splitn(2, ' ');
+        match parts.next()? {
+            "chat" => {
+                let mut parts = parts.next()?.trim_start().splitn(2, ' ');
+                let name = parts.next()?;
+                let text = parts.next()?.trim();
+                Some(Event::Chat {
+                    name: name.chars().take(MAX_CHARS).collect(),
+                    text: text.chars().take(MAX_CHARS).collect(),
+                })
+            }
+            "votes" => {
+                let tally = parts
+                    .next()
+                    .unwrap_or("")
+                    .split_whitespace()
+                    .filter_map(|vote| {
+                        let mut parts = vote.splitn(2, '=');
+                        let option = parts.next()?;
+                        let count = parts.next()?.parse().ok()?;
+                        Some((option.chars().take(MAX_CHARS).collect(), count))
+                    })
+                    .collect();
+                Some(Event::Votes(tally))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Accepts connections on the events port, reading each on a thread of its own.
+fn listen(port: u16, events: Sender<Event>) {
+    let listener = match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("Couldn't listen for chat on port {}: {}", port, e);
+            return;
+        }
+    };
+    for stream in listener.incoming().filter_map(Result::ok) {
+        let events = events.clone();
+        thread::spawn(move || read_events(stream, events));
+    }
+}
+
+/// Queues each line of a connection that's something to show, until it closes.
+fn read_events(stream: TcpStream, events: Sender<Event>) {
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => return,
+        };
+        if let Some(event) = Event::parse(&line) {
+            if events.send(event).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+struct Message {
+    name: String,
+    text: String,
+    arrived: Instant,
+}
+
+/// The chat and vote panel.
+pub struct Overlay {
+    /// The side the panel is on, or `None` if it's turned off.
+    side: Option<Side>,
+    fade: Duration,
+    key: KeyCode,
+    shown: bool,
+    events: Receiver<Event>,
+    messages: VecDeque<Message>,
+    /// The latest tally and when it arrived.
+    votes: Option<(Vec<(String, u32)>, Instant)>,
+}
+
+impl Overlay {
+    /// Loads the panel's settings and starts listening on `port`, unless the panel is off.
+    pub fn new(port: u16) -> Self {
+        let settings = Settings::load();
+        let side = match settings.get(POSITION_SETTING) {
+            Some("off") => None,
+            Some("left") => Some(Side::Left),
+            _ => Some(Side::Right),
+        };
+        let fade = settings
+            .get_or(FADE_SETTING, DEFAULT_FADE)
+            .max(1.0)
+            .min(MAX_FADE);
+        let key = settings
+            .get(KEY_SETTING)
+            .and_then(bindings::key_from_name)
+            .unwrap_or(DEFAULT_KEY);
+
+        let (sender, events) = mpsc::channel();
+        if side.is_some() {
+            thread::spawn(move || listen(port, sender));
+        }
+        Overlay {
+            side,
+            fade: Duration::from_secs_f32(fade),
+            key,
+            shown: true,
+            events,
+            messages: VecDeque::new(),
+            votes: None,
+        }
+    }
+
+    /// Takes whatever has arrived since the last update, and lets go of what's faded out.
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        for event in self.events.try_iter() {
+            match event {
+                Event::Chat { name, text } => {
+                    self.messages.push_back(Message {
+                        name,
+                        text,
+                        arrived: now,
+                    });
+                    if self.messages.len() > MAX_MESSAGES {
+                        self.messages.pop_front();
+                    }
+                }
+                Event::Votes(tally) => self.votes = Some((tally, now)),
+            }
+        }
+        let fade = self.fade;
+        self.messages
+            .retain(|message| now.duration_since(message.arrived) < fade);
+        if self
+            .votes
+            .as_ref()
+            .map_or(false, |(_, arrived)| now.duration_since(*arrived) >= fade)
+        {
+            self.votes = None;
+        }
+    }
+
+    /// Shows or hides the panel if `key` is its key.
+    pub fn key_down(&mut self, key: KeyCode) {
+        if key == self.key {
+            self.shown = !self.shown;
+        }
+    }
+
+    /// Where the panel goes, if it's shown. It's left out of windows too narrow for the game
+    /// beside it.
+    fn panel(&self, screen: Rect) -> Option<Rect> {
+        if !self.shown || screen.w < PANEL_WIDTH * 2.0 {
+            return None;
+        }
+        let x = match self.side? {
+            Side::Left => screen.x,
+            Side::Right => screen.x + screen.w - PANEL_WIDTH,
+        };
+        Some(Rect::new(x, screen.y, PANEL_WIDTH, screen.h))
+    }
+
+    /// The part of the screen the game is fitted into, beside the panel.
+    pub fn game_area(&self, screen: Rect) -> Rect {
+        match (self.panel(screen), self.side) {
+            (Some(_), Some(Side::Left)) => Rect::new(
+                screen.x + PANEL_WIDTH,
+                screen.y,
+                screen.w - PANEL_WIDTH,
+                screen.h,
+            ),
+            (Some(_), _) => Rect::new(screen.x, screen.y, screen.w - PANEL_WIDTH, screen.h),
+            (None, _) => screen,
+        }
+    }
+
+    /// How visible something that arrived at `arrived` is, fading to nothing at the end of
+    /// its time.
+    fn opacity(&self, arrived: Instant, now: Instant) -> f32 {
+        let left = self.fade.checked_sub(now.duration_since(arrived));
+        left.map_or(0.0, |left| {
+            (left.as_secs_f32() / FADE_OUT.as_secs_f32()).min(1.0)
+        })
+    }
+
+    pub fn draw(&self, ctx: &mut Context, screen: Rect) -> GameResult<()> {
+        let panel = match self.panel(screen) {
+            Some(panel) => panel,
+            None => return Ok(()),
+        };
+        let background =
+            graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), panel, PANEL_COLOR.into())?;
+        graphics::draw(ctx, &background, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+
+        let now = Instant::now();
+        let faded = |color: [f32; 4], opacity: f32| [color[0], color[1], color[2], opacity];
+        let x = panel.x + MARGIN;
+        let width = panel.w - MARGIN * 2.0;
+        let mut y = panel.y + MARGIN;
+
+        if let Some((tally, arrived)) = &self.votes {
+            let opacity = self.opacity(*arrived, now);
+            ui::draw_text(ctx, "Votes", faded(HEADING_COLOR, opacity), 18.0, x, y)?;
+            y += VOTE_HEIGHT;
+            let most = tally
+                .iter()
+                .map(|(_, count)| *count)
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            for (option, count) in tally {
+                let bar = Rect::new(x, y, width * *count as f32 / most as f32, VOTE_HEIGHT - 4.0);
+                if bar.w > 0.0 {
+                    let mesh = graphics::Mesh::new_rectangle(
+                        ctx,
+                        DrawMode::fill(),
+                        bar,
+                        faded(BAR_COLOR, opacity * 0.5).into(),
+                    )?;
+                    graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+                }
+                let label = format!("{}  {}", option, count);
+                ui::draw_text(ctx, &label, faded(TEXT_COLOR, opacity), 18.0, x + 4.0, y)?;
+                y += VOTE_HEIGHT;
+            }
+            y += MARGIN;
+        }
+
+        // Newest first, so what doesn't fit is the oldest.
+        for message in self.messages.iter().rev() {
+            if y >= panel.y + panel.h - MARGIN {
+                break;
+            }
+            let opacity = self.opacity(message.arrived, now);
+            ui::draw_text(ctx, &message.name, faded(NAME_COLOR, opacity), 16.0, x, y)?;
+            y += 20.0;
+            y += ui::draw_wrapped(
+                ctx,
+                &message.text,
+                faded(TEXT_COLOR, opacity),
+                18.0,
+                x,
+                y,
+                width,
+            )?;
+            y += 8.0;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,347 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect};
+use ggez::{event, graphics, timer, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::input::Nav;
+use common::locale;
+use common::motion;
+use common::overlay::{Overlay, GAME_OVER_ACTIONS};
+use common::stats::StatsDb;
+use common::text::TextStyle;
+use common::tween;
+use common::ui;
+use common::window;
+
+const SCREEN_SIZE: (f32, f32) = (480.0, 720.0);
+
+const BLOCK_HEIGHT: f32 = 32.0;
+const START_WIDTH: f32 = 200.0;
+/// The height of the top of the stack on screen the camera tries to keep.
+const STACK_TOP_ON_SCREEN: f32 = SCREEN_SIZE.1 * 0.55;
+/// How quickly the camera catches up with the stack, per second.
+const CAMERA_SPEED: f32 = 6.0;
+
+const START_SWING_SPEED: f32 = 180.0;
+const SWING_SPEED_STEP: f32 = 8.0;
+const MAX_SWING_SPEED: f32 = 520.0;
+
+/// Drops within this distance of the block below count as perfect and snap into place.
+const PERFECT_TOLERANCE: f32 = 4.0;
+/// After this many perfect drops in a row, each further perfect drop widens the block again.
+const STREAK_FOR_GROWTH: u32 = 3;
+const STREAK_GROWTH: f32 = 10.0;
+const PERFECT_BONUS: u32 = 5;
+
+const GRAVITY: f32 = 1200.0;
+
+/// A block resting on the stack.
+#[derive(Debug, Copy, Clone)]
+struct Block {
+    x: f32,
+    width: f32,
+}
+
+/// The piece sliced off a drop, falling off screen.
+#[derive(Debug, Copy, Clone)]
+struct Debris {
+    rect: Rect,
+    velocity: f32,
+}
+
+/// The state for the game.
+struct GameState {
+    stack: Vec<Block>,
+    /// The block swinging above the stack.
+    swinging: Block,
+    swing_dir: f32,
+    debris: Vec<Debris>,
+    /// The world height shown at the bottom of the screen.
+    camera: f32,
+    score: u32,
+    streak: u32,
+    best_streak: u32,
+    gameover: bool,
+    /// The game over screen, once a block has missed the stack.
+    overlay: Option<Overlay>,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state with a single base block.
+    pub fn new() -> Self {
+        let base = Block {
+            x: (SCREEN_SIZE.0 - START_WIDTH) / 2.0,
+            width: START_WIDTH,
+        };
+
+        GameState {
+            stack: vec![base],
+            swinging: Block {
+                x: 0.0,
+                width: START_WIDTH,
+            },
+            swing_dir: 1.0,
+            debris: Vec::new(),
+            camera: 0.0,
+            score: 0,
+            streak: 0,
+            best_streak: 0,
+            gameover: false,
+            overlay: None,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn record_run(&self) {
+        let values = [
+            ("score", f64::from(self.score)),
+            ("height", (self.stack.len() - 1) as f64),
+            ("best_streak", f64::from(self.best_streak)),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("stacker", &values);
+        }
+        let _ = achievements::check_run("stacker", &values);
+    }
+
+    fn swing_speed(&self) -> f32 {
+        (START_SWING_SPEED + self.stack.len() as f32 * SWING_SPEED_STEP).min(MAX_SWING_SPEED)
+    }
+
+    /// Converts a block's stack level into its rect on screen.
+    fn rect(&self, level: usize, block: &Block) -> Rect {
+        Rect::new(
+            block.x,
+            SCREEN_SIZE.1 - (level + 1) as f32 * BLOCK_HEIGHT + self.camera,
+            block.width,
+            BLOCK_HEIGHT,
+        )
+    }
+
+    fn drop_block(&mut self) {
+        let top = *self.stack.last().unwrap();
+        let mut block = self.swinging;
+        let level = self.stack.len();
+
+        if (block.x - top.x).abs() <= PERFECT_TOLERANCE {
+            block.x = top.x;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.score += PERFECT_BONUS * self.streak;
+
+            if self.streak >= STREAK_FOR_GROWTH {
+                let grown = (block.width + STREAK_GROWTH).min(START_WIDTH);
+                block.x -= (grown - block.width) / 2.0;
+                block.width = grown;
+            }
+        } else {
+            self.streak = 0;
+
+            let left = block.x.max(top.x);
+            let right = (block.x + block.width).min(top.x + top.width);
+            let overlap = right - left;
+
+            // Whatever hangs over the edge of the stack is sliced off and falls, unless motion
+            // is reduced, when it just disappears.
+            let y = SCREEN_SIZE.1 - (level + 1) as f32 * BLOCK_HEIGHT + self.camera;
+            let mut debris = Vec::new();
+            if overlap <= 0.0 {
+                debris.push(Rect::new(block.x, y, block.width, BLOCK_HEIGHT));
+                self.gameover = true;
+                self.record_run();
+                let summary = vec![
+                    format!("Score: {}", locale::integer(self.score)),
+                    format!("Height: {}", self.stack.len() - 1),
+                    format!("Best streak: {}", self.best_streak),
+                ];
+                self.overlay = Some(Overlay::new("Game over", summary, GAME_OVER_ACTIONS));
+            } else {
+                if block.x < left {
+                    debris.push(Rect::new(block.x, y, left - block.x, BLOCK_HEIGHT));
+                }
+                if block.x + block.width > right {
+                    debris.push(Rect::new(
+                        right,
+                        y,
+                        block.x + block.width - right,
+                        BLOCK_HEIGHT,
+                    ));
+                }
+            }
+            if !motion::reduced() {
+                self.debris.extend(debris.into_iter().map(|rect| Debris {
+                    rect,
+                    velocity: 0.0,
+                }));
+            }
+            if self.gameover {
+                return;
+            }
+
+            block.x = left;
+            block.width = overlap;
+        }
+
+        self.stack.push(block);
+        self.score += 1;
+
+        // The next block comes in from alternating sides.
+        self.swing_dir = if self.stack.len() % 2 == 0 { 1.0 } else { -1.0 };
+        self.swinging = Block {
+            x: if self.swing_dir > 0.0 {
+                -block.width
+            } else {
+                SCREEN_SIZE.0
+            },
+            width: block.width,
+        };
+    }
+
+    fn game_over_action(&mut self, ctx: &mut Context, action: Option<usize>) {
+        match action {
+            Some(0) => *self = GameState::new(),
+            Some(_) => event::quit(ctx),
+            None => (),
+        }
+    }
+
+    fn tick(&mut self, dt: f32) {
+        if !self.gameover {
+            let speed = self.swing_speed();
+            self.swinging.x += self.swing_dir * speed * dt;
+
+            // Bounce back and forth across the screen.
+            if self.swinging.x + self.swinging.width > SCREEN_SIZE.0 && self.swing_dir > 0.0 {
+                self.swing_dir = -1.0;
+            } else if self.swinging.x < 0.0 && self.swing_dir < 0.0 {
+                self.swing_dir = 1.0;
+            }
+        }
+
+        // Scroll the camera up so the top of the stack stays at the same place on screen.
+        let stack_height = self.stack.len() as f32 * BLOCK_HEIGHT;
+        let target = (stack_height - (SCREEN_SIZE.1 - STACK_TOP_ON_SCREEN)).max(0.0);
+        let step = tween::approach(self.camera, target, CAMERA_SPEED, dt) - self.camera;
+        self.camera += step;
+        for debris in self.debris.iter_mut() {
+            debris.rect.y += step;
+            debris.velocity += GRAVITY * dt;
+            debris.rect.y += debris.velocity * dt;
+        }
+        self.debris.retain(|debris| debris.rect.y < SCREEN_SIZE.1);
+    }
+
+    fn draw_block(&self, ctx: &mut Context, rect: Rect, hue: f32) -> GameResult<()> {
+        let color = [
+            0.5 + 0.5 * (hue).sin(),
+            0.5 + 0.5 * (hue + 2.1).sin(),
+            0.5 + 0.5 * (hue + 4.2).sin(),
+            1.0,
+        ];
+        let mesh = graphics::Mesh::new_rectangle(ctx, DrawMode::fill(), rect, color.into())?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+        self.tick(dt);
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.05, 0.1, 1.0].into());
+
+        for (level, block) in self.stack.iter().enumerate() {
+            let rect = self.rect(level, block);
+            if rect.y < SCREEN_SIZE.1 {
+                self.draw_block(ctx, rect, level as f32 * 0.3)?;
+            }
+        }
+
+        for debris in self.debris.iter() {
+            let mesh = graphics::Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                debris.rect,
+                [0.5, 0.5, 0.5, 1.0].into(),
+            )?;
+            graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))?;
+        }
+
+        if !self.gameover {
+            let level = self.stack.len();
+            let rect = self.rect(level, &self.swinging);
+            self.draw_block(ctx, rect, level as f32 * 0.3)?;
+        }
+
+        let streak = if self.streak > 0 {
+            format!("  Perfect x{}", self.streak)
+        } else {
+            String::new()
+        };
+        let hud = format!("Score: {}{}", locale::integer(self.score), streak);
+        TextStyle::Hud.draw(ctx, hud, [1.0, 1.0, 1.0, 1.0], 8.0, 8.0)?;
+
+        if let Some(overlay) = &self.overlay {
+            overlay.draw(ctx)?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        repeat: bool,
+    ) {
+        if keycode == KeyCode::Escape {
+            event::quit(ctx);
+        } else if let Some(overlay) = &mut self.overlay {
+            if !repeat {
+                let action = Nav::from_key(keycode).and_then(|nav| overlay.navigate(nav));
+                self.game_over_action(ctx, action);
+            }
+        } else if keycode == KeyCode::Space && !repeat {
+            self.drop_block();
+        }
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        if let Some(overlay) = &mut self.overlay {
+            overlay.mouse_motion(ctx, x, y);
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left {
+            return;
+        }
+        match &self.overlay {
+            Some(overlay) => {
+                let action = overlay.action_at(ctx, x, y);
+                self.game_over_action(ctx, action);
+            }
+            None => self.drop_block(),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("stacker", "Sprial404")
+        .window_setup(window::setup("Tower Stacker"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
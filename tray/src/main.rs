@@ -0,0 +1,138 @@
+//! A tray icon with a menu of the games, for starting one, or today's challenge, in a click
+//! without opening the launcher.
+//!
+//! It's started with `quick-games tray` and stays in the tray until Quit is picked from its
+//! menu. Games started from it are remembered as the last played, like from the launcher, and
+//! aren't started while it's time for a break.
+
+use std::process::{self, Child};
+use std::sync::{Arc, Mutex};
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+use std::thread;
+
+use tray_item::TrayItem;
+
+use common::daily::Daily;
+use common::last_played::LastPlayed;
+use common::launch;
+use common::locale;
+use common::registry::{self, GameInfo, GAMES};
+use common::session::Session;
+
+/// The name of the tray icon in the desktop's icon theme, where there is one.
+const ICON: &str = "applications-games";
+
+/// The processes started from the menu, kept until they close.
+type Running = Arc<Mutex<Vec<Child>>>;
+
+/// Forgets the games that have closed.
+fn reap(running: &mut Vec<Child>) {
+    let still_running = running
+        .drain(..)
+        .filter_map(|mut child| match child.try_wait() {
+            Ok(Some(_)) => None,
+            _ => Some(child),
+        })
+        .collect();
+    *running = still_running;
+}
+
+/// Starts a game with `args`, the same way as the launcher.
+fn start(running: &Running, game: &'static GameInfo, args: &[String]) {
+    if let Some(remaining) = Session::load().break_remaining() {
+        eprintln!(
+            "Taking a break. The games are back in {}.",
+            locale::duration(remaining)
+        );
+        return;
+    }
+    let mut running = running.lock().unwrap();
+    reap(&mut running);
+    match launch::launch(game, args) {
+        Ok(child) => {
+            running.push(child);
+            if let Err(e) = LastPlayed::new(game.name, None).save() {
+                eprintln!("Couldn't save the last played game: {}", e);
+            }
+        }
+        Err(e) => eprintln!("Couldn't start {}: {}", game.title, e),
+    }
+}
+
+/// Starts today's challenge, which is worked out when it's picked since the tray can be left
+/// running overnight.
+fn start_daily(running: &Running) {
+    let daily = Daily::today();
+    match registry::find(daily.challenge.game) {
+        Some(game) => start(running, game, &daily.args()),
+        None => eprintln!("{} isn't installed.", daily.challenge.game),
+    }
+}
+
+fn build_menu(tray: &mut TrayItem, running: &Running) -> Result<(), String> {
+    let error = |e| format!("Couldn't build the tray menu: {:?}", e);
+    tray.add_label("Quick Games").map_err(error)?;
+
+    let daily_running = Arc::clone(running);
+    tray.add_menu_item("Today's challenge", move || start_daily(&daily_running))
+        .map_err(error)?;
+    let launcher_running = Arc::clone(running);
+    tray.add_menu_item("Open the launcher", move || {
+        match launch::launch_launcher() {
+            Ok(child) => launcher_running.lock().unwrap().push(child),
+            Err(e) => eprintln!("Couldn't open the launcher: {}", e),
+        }
+    })
+    .map_err(error)?;
+
+    tray.add_label("Games").map_err(error)?;
+    for game in GAMES {
+        let running = Arc::clone(running);
+        tray.add_menu_item(game.title, move || start(&running, game, &[]))
+            .map_err(error)?;
+    }
+
+    // The games carry on running on their own.
+    tray.add_menu_item("Quit", || process::exit(0))
+        .map_err(error)?;
+    Ok(())
+}
+
+fn run() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    gtk::init().map_err(|e| format!("Couldn't start GTK for the tray: {}", e))?;
+
+    let mut tray = TrayItem::new("Quick Games", ICON)
+        .map_err(|e| format!("Couldn't add the tray icon: {:?}", e))?;
+    let running = Running::default();
+    build_menu(&mut tray, &running)?;
+
+    run_menu(&mut tray);
+    Ok(())
+}
+
+/// Handles the menu until Quit is picked, with the platform's own event loop.
+#[cfg(target_os = "linux")]
+fn run_menu(_tray: &mut TrayItem) {
+    gtk::main();
+}
+
+#[cfg(target_os = "macos")]
+fn run_menu(tray: &mut TrayItem) {
+    tray.inner_mut().display();
+}
+
+/// Windows handles the menu on a thread of its own.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn run_menu(_tray: &mut TrayItem) {
+    loop {
+        thread::park();
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,358 @@
+use ggez::event::{KeyCode, KeyMods};
+use ggez::graphics::{Scale, Text, TextFragment};
+use ggez::{event, filesystem, graphics, timer, Context, GameError, GameResult};
+
+use std::io::Read;
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::assets;
+use common::locale;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+const SCREEN_SIZE: (f32, f32) = (800.0, 600.0);
+
+/// The directory within the assets that holds the word lists, one word per line.
+const WORDS_DIR: &str = "/words";
+
+const WORD_SCALE: f32 = 28.0;
+/// The approximate width of a character at `WORD_SCALE`, used to keep words on screen.
+const CHAR_WIDTH: f32 = 16.0;
+
+const START_FALL_SPEED: f32 = 40.0;
+const START_SPAWN_INTERVAL: f32 = 2.5;
+/// How much faster words fall for every word destroyed.
+const FALL_SPEED_STEP: f32 = 1.5;
+const MIN_SPAWN_INTERVAL: f32 = 0.8;
+
+const LIVES: u32 = 3;
+
+/// A word falling down the screen.
+#[derive(Debug, Clone)]
+struct FallingWord {
+    text: String,
+    x: f32,
+    y: f32,
+}
+
+impl FallingWord {
+    fn draw(&self, ctx: &mut Context, typed: &str) -> GameResult<()> {
+        // Highlight the part of the word already typed by the player.
+        let typed_len = if self.text.starts_with(typed) {
+            typed.len()
+        } else {
+            0
+        };
+
+        let mut text = Text::new(
+            TextFragment::new(&self.text[..typed_len])
+                .color([1.0, 0.8, 0.0, 1.0].into())
+                .scale(Scale::uniform(WORD_SCALE)),
+        );
+        text.add(
+            TextFragment::new(&self.text[typed_len..])
+                .color([1.0, 1.0, 1.0, 1.0].into())
+                .scale(Scale::uniform(WORD_SCALE)),
+        );
+
+        graphics::draw(
+            ctx,
+            &text,
+            (ggez::mint::Point2 {
+                x: self.x,
+                y: self.y,
+            },),
+        )
+    }
+}
+
+/// Loads every word list in the assets `words` directory.
+fn load_words(ctx: &mut Context) -> GameResult<Vec<String>> {
+    let mut words = Vec::new();
+
+    let paths = assets::read_dir(ctx, WORDS_DIR)?;
+    for path in paths {
+        let mut contents = String::new();
+        filesystem::open(ctx, &path)?.read_to_string(&mut contents)?;
+
+        words.extend(
+            contents
+                .lines()
+                .map(|line| line.trim().to_lowercase())
+                .filter(|word| !word.is_empty()),
+        );
+    }
+
+    if words.is_empty() {
+        return Err(GameError::ResourceLoadError(format!(
+            "no words found in {}",
+            WORDS_DIR
+        )));
+    }
+    Ok(words)
+}
+
+/// The state for the game.
+struct GameState {
+    words: Vec<String>,
+    falling: Vec<FallingWord>,
+    /// The characters typed so far towards the next word.
+    typed: String,
+    lives: u32,
+    score: u32,
+    /// Seconds left until the next word spawns.
+    spawn_timer: f32,
+    /// Seconds spent typing in the current run.
+    elapsed: f32,
+    correct_keystrokes: u32,
+    total_keystrokes: u32,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state, loading the word lists from the assets.
+    pub fn new(ctx: &mut Context) -> GameResult<Self> {
+        Ok(GameState {
+            words: load_words(ctx)?,
+            falling: Vec::new(),
+            typed: String::new(),
+            lives: LIVES,
+            score: 0,
+            spawn_timer: 0.0,
+            elapsed: 0.0,
+            correct_keystrokes: 0,
+            total_keystrokes: 0,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        })
+    }
+
+    fn restart(&mut self) {
+        self.falling.clear();
+        self.typed.clear();
+        self.lives = LIVES;
+        self.score = 0;
+        self.spawn_timer = 0.0;
+        self.elapsed = 0.0;
+        self.correct_keystrokes = 0;
+        self.total_keystrokes = 0;
+        self.gameover = false;
+    }
+
+    fn fall_speed(&self) -> f32 {
+        START_FALL_SPEED + self.score as f32 * FALL_SPEED_STEP
+    }
+
+    fn spawn_interval(&self) -> f32 {
+        (START_SPAWN_INTERVAL * START_FALL_SPEED / self.fall_speed()).max(MIN_SPAWN_INTERVAL)
+    }
+
+    /// Words per minute, counting every five correctly typed characters as a word.
+    fn wpm(&self) -> f32 {
+        if self.elapsed <= 0.0 {
+            return 0.0;
+        }
+        (self.correct_keystrokes as f32 / 5.0) / (self.elapsed / 60.0)
+    }
+
+    /// The percentage of keystrokes that were correct.
+    fn accuracy(&self) -> f32 {
+        if self.total_keystrokes == 0 {
+            return 100.0;
+        }
+        self.correct_keystrokes as f32 / self.total_keystrokes as f32 * 100.0
+    }
+
+    fn spawn_word(&mut self) {
+        let mut rng = rand::thread_rng();
+        let text = self.words.choose(&mut rng).cloned().unwrap_or_default();
+        let max_x = (SCREEN_SIZE.0 - text.len() as f32 * CHAR_WIDTH).max(1.0);
+
+        self.falling.push(FallingWord {
+            x: rng.gen_range(0.0, max_x),
+            y: -WORD_SCALE,
+            text,
+        });
+    }
+
+    fn tick(&mut self, dt: f32) {
+        self.elapsed += dt;
+
+        self.spawn_timer -= dt;
+        if self.spawn_timer <= 0.0 {
+            self.spawn_word();
+            self.spawn_timer += self.spawn_interval();
+        }
+
+        let speed = self.fall_speed();
+        for word in self.falling.iter_mut() {
+            word.y += speed * dt;
+        }
+
+        let landed = self
+            .falling
+            .iter()
+            .filter(|word| word.y >= SCREEN_SIZE.1)
+            .count() as u32;
+        if landed > 0 {
+            self.falling.retain(|word| word.y < SCREEN_SIZE.1);
+            // Don't leave a half-typed word from a landed word behind.
+            if !self
+                .falling
+                .iter()
+                .any(|word| word.text.starts_with(&self.typed))
+            {
+                self.typed.clear();
+            }
+
+            self.lives = self.lives.saturating_sub(landed);
+            if self.lives == 0 {
+                self.gameover = true;
+                self.record_stats();
+            }
+        }
+    }
+
+    fn type_char(&mut self, ch: char) {
+        self.total_keystrokes += 1;
+
+        let mut candidate = self.typed.clone();
+        candidate.push(ch);
+
+        // Only accept characters that continue one of the falling words.
+        if !self
+            .falling
+            .iter()
+            .any(|word| word.text.starts_with(&candidate))
+        {
+            return;
+        }
+        self.correct_keystrokes += 1;
+        self.typed = candidate;
+
+        // Destroy the lowest word that was completely typed.
+        let completed = self
+            .falling
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| word.text == self.typed)
+            .max_by(|(_, a), (_, b)| a.y.partial_cmp(&b.y).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(i, _)| i);
+        if let Some(i) = completed {
+            self.falling.remove(i);
+            self.typed.clear();
+            self.score += 1;
+        }
+    }
+
+    fn record_stats(&self) {
+        let values = [
+            ("score", self.score as f64),
+            ("wpm", self.wpm() as f64),
+            ("accuracy", self.accuracy() as f64),
+            ("duration", self.elapsed as f64),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("typing", &values);
+        }
+        let _ = achievements::check_run("typing", &values);
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let dt = timer::delta(ctx).as_secs_f32();
+
+        if !self.gameover {
+            self.tick(dt);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.0, 0.0, 0.1, 1.0].into());
+
+        for word in self.falling.iter() {
+            word.draw(ctx, &self.typed)?;
+        }
+
+        let hud = Text::new(
+            TextFragment::new(format!(
+                "Score: {}  Lives: {}  WPM: {:.0}  Accuracy: {:.0}%",
+                locale::integer(self.score),
+                self.lives,
+                self.wpm(),
+                self.accuracy()
+            ))
+            .color([1.0, 1.0, 1.0, 1.0].into())
+            .scale(Scale::uniform(24.0)),
+        );
+        graphics::draw(ctx, &hud, (ggez::mint::Point2 { x: 8.0, y: 8.0 },))?;
+
+        let typed = Text::new(
+            TextFragment::new(format!("> {}", self.typed))
+                .color([1.0, 0.8, 0.0, 1.0].into())
+                .scale(Scale::uniform(WORD_SCALE)),
+        );
+        graphics::draw(
+            ctx,
+            &typed,
+            (ggez::mint::Point2 {
+                x: 8.0,
+                y: SCREEN_SIZE.1 - WORD_SCALE - 8.0,
+            },),
+        )?;
+
+        if self.gameover {
+            let game_over = Text::new(
+                TextFragment::new("GAME OVER! Press Enter to play again.")
+                    .color([1.0, 0.0, 0.0, 1.0].into())
+                    .scale(Scale::uniform(40.0)),
+            );
+
+            graphics::draw(ctx, &game_over, (ggez::mint::Point2 { x: 8.0, y: 40.0 },))?;
+        }
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Return if self.gameover => self.restart(),
+            KeyCode::Escape | KeyCode::Back => self.typed.clear(),
+            _ => (),
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) {
+        if !self.gameover && character.is_alphabetic() {
+            self.type_char(character.to_ascii_lowercase());
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let builder = ggez::ContextBuilder::new("typing", "Sprial404")
+        .window_setup(window::setup("Typing Trainer"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1));
+    let (ctx, events_loop) =
+        &mut assets::add_to(builder, "typing", common::embedded_assets!()).build()?;
+    let mut state = App::new(GameState::new(ctx)?);
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
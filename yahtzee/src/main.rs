@@ -0,0 +1,501 @@
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{DrawMode, Rect, Scale, Text, TextFragment};
+use ggez::{event, graphics, Context, GameResult};
+
+use std::process::ExitCode;
+
+use common::achievements;
+use common::app::App;
+use common::stats::StatsDb;
+use common::ui;
+use common::window;
+use rand::Rng;
+
+mod probability;
+mod scorecard;
+
+use probability::{Advice, Advisor, Dice};
+use scorecard::{Category, Scorecard, CATEGORIES};
+
+const SCREEN_SIZE: (f32, f32) = (960.0, 720.0);
+
+const MAX_PLAYERS: usize = 4;
+const ROLLS_PER_TURN: u32 = 3;
+
+const DIE_SIZE: f32 = 72.0;
+const DIE_SPACING: f32 = 88.0;
+const DICE_LEFT: f32 = 30.0;
+const DICE_TOP: f32 = 80.0;
+/// How far held dice are lifted above the others.
+const HELD_OFFSET: f32 = 14.0;
+
+const CARD_LEFT: f32 = 480.0;
+const CARD_TOP: f32 = 60.0;
+const ROW_HEIGHT: f32 = 30.0;
+const NAME_WIDTH: f32 = 150.0;
+const COLUMN_WIDTH: f32 = 75.0;
+
+/// Where the pips go on a die for each face, on a three by three grid.
+const PIPS: [&[(f32, f32)]; 6] = [
+    &[(1.0, 1.0)],
+    &[(0.0, 0.0), (2.0, 2.0)],
+    &[(0.0, 0.0), (1.0, 1.0), (2.0, 2.0)],
+    &[(0.0, 0.0), (2.0, 0.0), (0.0, 2.0), (2.0, 2.0)],
+    &[(0.0, 0.0), (2.0, 0.0), (1.0, 1.0), (0.0, 2.0), (2.0, 2.0)],
+    &[
+        (0.0, 0.0),
+        (2.0, 0.0),
+        (0.0, 1.0),
+        (2.0, 1.0),
+        (0.0, 2.0),
+        (2.0, 2.0),
+    ],
+];
+
+/// A row of the scorecard table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Row {
+    Category(Category),
+    UpperBonus,
+    YahtzeeBonus,
+    Total,
+}
+
+const ROWS: [Row; 16] = [
+    Row::Category(Category::Ones),
+    Row::Category(Category::Twos),
+    Row::Category(Category::Threes),
+    Row::Category(Category::Fours),
+    Row::Category(Category::Fives),
+    Row::Category(Category::Sixes),
+    Row::UpperBonus,
+    Row::Category(Category::ThreeOfAKind),
+    Row::Category(Category::FourOfAKind),
+    Row::Category(Category::FullHouse),
+    Row::Category(Category::SmallStraight),
+    Row::Category(Category::LargeStraight),
+    Row::Category(Category::Yahtzee),
+    Row::Category(Category::Chance),
+    Row::YahtzeeBonus,
+    Row::Total,
+];
+
+/// The state for the game.
+struct GameState {
+    /// Whether the game is still on the menu choosing the number of players.
+    menu: bool,
+    player_count: usize,
+    cards: Vec<Scorecard>,
+    current: usize,
+    dice: Dice,
+    held: [bool; 5],
+    rolls_left: u32,
+    /// Whether to show the expected value hint for the current dice.
+    hints: bool,
+    advisor: Advisor,
+    /// The hint for the current dice, worked out once per roll.
+    advice: Option<Advice>,
+    gameover: bool,
+    stats: Option<StatsDb>,
+}
+
+impl GameState {
+    /// Creates a new game state at the menu.
+    pub fn new() -> Self {
+        GameState {
+            menu: true,
+            player_count: 1,
+            cards: Vec::new(),
+            current: 0,
+            dice: [1; 5],
+            held: [false; 5],
+            rolls_left: ROLLS_PER_TURN,
+            hints: false,
+            advisor: Advisor::new(),
+            advice: None,
+            gameover: false,
+            stats: StatsDb::open().ok(),
+        }
+    }
+
+    fn start(&mut self) {
+        self.cards = vec![Scorecard::new(); self.player_count];
+        self.current = 0;
+        self.gameover = false;
+        self.menu = false;
+        self.start_turn();
+    }
+
+    fn start_turn(&mut self) {
+        self.held = [false; 5];
+        self.rolls_left = ROLLS_PER_TURN;
+        self.advice = None;
+    }
+
+    fn has_rolled(&self) -> bool {
+        self.rolls_left < ROLLS_PER_TURN
+    }
+
+    fn roll(&mut self) {
+        if self.rolls_left == 0 || self.gameover {
+            return;
+        }
+
+        let mut rng = rand::thread_rng();
+        for (die, held) in self.dice.iter_mut().zip(self.held.iter()) {
+            if !held {
+                *die = rng.gen_range(1, 7);
+            }
+        }
+        self.rolls_left -= 1;
+        if self.rolls_left == 0 {
+            self.held = [false; 5];
+        }
+        self.update_advice();
+    }
+
+    fn update_advice(&mut self) {
+        if !self.hints || !self.has_rolled() {
+            self.advice = None;
+            return;
+        }
+        let card = &self.cards[self.current];
+        self.advice = Some(
+            self.advisor
+                .advise(&self.dice, self.rolls_left, |dice| card.best_score(dice)),
+        );
+    }
+
+    fn toggle_hold(&mut self, die: usize) {
+        if self.has_rolled() && self.rolls_left > 0 && die < self.held.len() {
+            self.held[die] = !self.held[die];
+        }
+    }
+
+    fn score(&mut self, category: Category) {
+        if !self.has_rolled() || self.gameover {
+            return;
+        }
+        let card = &mut self.cards[self.current];
+        if !card.can_score(category, &self.dice) {
+            return;
+        }
+        card.record(category, &self.dice);
+
+        if self.cards.iter().all(Scorecard::is_full) {
+            self.gameover = true;
+            self.record_game();
+        } else {
+            self.current = (self.current + 1) % self.cards.len();
+            self.start_turn();
+        }
+    }
+
+    /// Records a finished game by the first player's scorecard.
+    fn record_game(&self) {
+        let values = [
+            ("score", f64::from(self.cards[0].total())),
+            ("players", self.cards.len() as f64),
+        ];
+        if let Some(stats) = &self.stats {
+            let _ = stats.record("yahtzee", &values);
+        }
+        let _ = achievements::check_run("yahtzee", &values);
+    }
+
+    fn die_rect(&self, die: usize) -> Rect {
+        let lift = if self.held[die] { HELD_OFFSET } else { 0.0 };
+        Rect::new(
+            DICE_LEFT + die as f32 * DIE_SPACING,
+            DICE_TOP + HELD_OFFSET - lift,
+            DIE_SIZE,
+            DIE_SIZE,
+        )
+    }
+
+    fn row_at(&self, x: f32, y: f32) -> Option<Row> {
+        let width = NAME_WIDTH + COLUMN_WIDTH * self.cards.len() as f32;
+        if x < CARD_LEFT || x > CARD_LEFT + width || y < CARD_TOP {
+            return None;
+        }
+        ROWS.get(((y - CARD_TOP) / ROW_HEIGHT) as usize).cloned()
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        color: [f32; 4],
+        scale: f32,
+        x: f32,
+        y: f32,
+    ) -> GameResult<()> {
+        let text = Text::new(
+            TextFragment::new(text)
+                .color(color.into())
+                .scale(Scale::uniform(scale)),
+        );
+        graphics::draw(ctx, &text, (ggez::mint::Point2 { x, y },))
+    }
+
+    fn draw_dice(&self, ctx: &mut Context) -> GameResult<()> {
+        let suggested = self
+            .advice
+            .filter(|advice| !advice.score_now())
+            .map(|advice| advice.hold);
+
+        let mut builder = graphics::MeshBuilder::new();
+        for (i, &value) in self.dice.iter().enumerate() {
+            let rect = self.die_rect(i);
+            let face = if self.held[i] {
+                [1.0, 0.95, 0.7, 1.0]
+            } else {
+                [1.0, 1.0, 1.0, 1.0]
+            };
+            builder.rectangle(DrawMode::fill(), rect, face.into());
+            if suggested.map_or(false, |hold| hold[i]) {
+                builder.rectangle(DrawMode::stroke(4.0), rect, [0.2, 0.9, 0.3, 1.0].into());
+            }
+
+            if !self.has_rolled() {
+                continue;
+            }
+            let step = DIE_SIZE / 4.0;
+            for (px, py) in PIPS[value as usize - 1].iter() {
+                builder.circle(
+                    DrawMode::fill(),
+                    ggez::mint::Point2 {
+                        x: rect.x + step * (px + 1.0),
+                        y: rect.y + step * (py + 1.0),
+                    },
+                    DIE_SIZE / 11.0,
+                    0.5,
+                    [0.1, 0.1, 0.1, 1.0].into(),
+                );
+            }
+        }
+        let mesh = builder.build(ctx)?;
+        graphics::draw(ctx, &mesh, (ggez::mint::Point2 { x: 0.0, y: 0.0 },))
+    }
+
+    fn draw_scorecard(&self, ctx: &mut Context) -> GameResult<()> {
+        let white = [1.0, 1.0, 1.0, 1.0];
+        for (player, _) in self.cards.iter().enumerate() {
+            let color = if player == self.current && !self.gameover {
+                [1.0, 0.85, 0.3, 1.0]
+            } else {
+                white
+            };
+            self.draw_text(
+                ctx,
+                &format!("P{}", player + 1),
+                color,
+                22.0,
+                CARD_LEFT + NAME_WIDTH + player as f32 * COLUMN_WIDTH,
+                CARD_TOP - ROW_HEIGHT,
+            )?;
+        }
+
+        let best = if self.has_rolled() && !self.gameover {
+            self.cards[self.current].best_category(&self.dice)
+        } else {
+            None
+        };
+
+        for (r, row) in ROWS.iter().enumerate() {
+            let y = CARD_TOP + r as f32 * ROW_HEIGHT;
+            let name = match row {
+                Row::Category(category) => category.name(),
+                Row::UpperBonus => "Upper bonus",
+                Row::YahtzeeBonus => "Yahtzee bonus",
+                Row::Total => "TOTAL",
+            };
+            self.draw_text(ctx, name, [0.8, 0.8, 0.8, 1.0], 20.0, CARD_LEFT, y)?;
+
+            for (player, card) in self.cards.iter().enumerate() {
+                let x = CARD_LEFT + NAME_WIDTH + player as f32 * COLUMN_WIDTH;
+                let (text, color) = match row {
+                    Row::Category(category) => match card.get(*category) {
+                        Some(score) => (score.to_string(), white),
+                        None if player == self.current
+                            && self.has_rolled()
+                            && !self.gameover
+                            && card.can_score(*category, &self.dice) =>
+                        {
+                            // Show what the dice would score, highlighting the best option when
+                            // hints are on.
+                            let color = if self.hints && best == Some(*category) {
+                                [0.2, 0.9, 0.3, 1.0]
+                            } else {
+                                [0.5, 0.5, 0.6, 1.0]
+                            };
+                            (card.potential(*category, &self.dice).to_string(), color)
+                        }
+                        None => (String::new(), white),
+                    },
+                    Row::UpperBonus => (
+                        format!("{} ({})", card.upper_bonus(), card.upper_subtotal()),
+                        white,
+                    ),
+                    Row::YahtzeeBonus => (card.yahtzee_bonus().to_string(), white),
+                    Row::Total => (card.total().to_string(), [1.0, 0.85, 0.3, 1.0]),
+                };
+                self.draw_text(ctx, &text, color, 20.0, x, y)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        if self.gameover {
+            let best = self.cards.iter().map(Scorecard::total).max().unwrap_or(0);
+            let winners: Vec<String> = self
+                .cards
+                .iter()
+                .enumerate()
+                .filter(|(_, card)| card.total() == best)
+                .map(|(player, _)| format!("P{}", player + 1))
+                .collect();
+            return format!(
+                "Game over! {} wins with {}.\nPress Enter to play again.",
+                winners.join(" and "),
+                best
+            );
+        }
+
+        let mut status = format!(
+            "Player {}'s turn. Rolls left: {}\nSpace: roll  1-5 or click: hold\nClick a box to score  H: hints {}",
+            self.current + 1,
+            self.rolls_left,
+            if self.hints { "on" } else { "off" }
+        );
+        if let Some(advice) = self.advice {
+            status.push_str("\n\n");
+            if advice.score_now() {
+                status.push_str(&format!(
+                    "Hint: score now for {:.0} points.",
+                    advice.expected
+                ));
+            } else {
+                let held: Vec<String> = self
+                    .dice
+                    .iter()
+                    .zip(advice.hold.iter())
+                    .filter(|(_, &hold)| hold)
+                    .map(|(die, _)| die.to_string())
+                    .collect();
+                let keep = if held.is_empty() {
+                    "reroll everything".to_string()
+                } else {
+                    format!("hold {}", held.join(" "))
+                };
+                status.push_str(&format!(
+                    "Hint: {}, expecting {:.1} points this turn.",
+                    keep, advice.expected
+                ));
+            }
+        } else if self.hints && !self.has_rolled() {
+            status.push_str("\n\nHint: roll the dice.");
+        }
+        status
+    }
+}
+
+impl event::EventHandler for GameState {
+    fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, [0.05, 0.3, 0.15, 1.0].into());
+
+        if self.menu {
+            self.draw_text(ctx, "YAHTZEE", [1.0, 1.0, 1.0, 1.0], 64.0, 340.0, 160.0)?;
+            self.draw_text(
+                ctx,
+                &format!(
+                    "Players: < {} >\n\nPress Enter to start.\nH toggles expected value hints.",
+                    self.player_count
+                ),
+                [0.9, 0.9, 0.9, 1.0],
+                28.0,
+                320.0,
+                280.0,
+            )?;
+            ui::present(ctx)?;
+            ggez::timer::yield_now();
+            return Ok(());
+        }
+
+        self.draw_dice(ctx)?;
+        self.draw_scorecard(ctx)?;
+        let status = self.status();
+        self.draw_text(
+            ctx,
+            &status,
+            [1.0, 1.0, 1.0, 1.0],
+            22.0,
+            DICE_LEFT,
+            DICE_TOP + DIE_SIZE + 60.0,
+        )?;
+
+        ui::present(ctx)?;
+        ggez::timer::yield_now();
+        Ok(())
+    }
+
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if button != MouseButton::Left || self.menu {
+            return;
+        }
+
+        if let Some(die) = (0..self.dice.len()).find(|&die| self.die_rect(die).contains([x, y])) {
+            self.toggle_hold(die);
+        } else if let Some(Row::Category(category)) = self.row_at(x, y) {
+            self.score(category);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Left if self.menu => {
+                self.player_count = (self.player_count - 1).max(1);
+            }
+            KeyCode::Right if self.menu => {
+                self.player_count = (self.player_count + 1).min(MAX_PLAYERS);
+            }
+            KeyCode::Return if self.menu => self.start(),
+            KeyCode::Return if self.gameover => self.menu = true,
+            KeyCode::Space if !self.menu => self.roll(),
+            KeyCode::Key1 => self.toggle_hold(0),
+            KeyCode::Key2 => self.toggle_hold(1),
+            KeyCode::Key3 => self.toggle_hold(2),
+            KeyCode::Key4 => self.toggle_hold(3),
+            KeyCode::Key5 => self.toggle_hold(4),
+            KeyCode::H => {
+                self.hints = !self.hints;
+                if !self.menu {
+                    self.update_advice();
+                }
+            }
+            KeyCode::Escape => event::quit(ctx),
+            _ => (),
+        }
+    }
+}
+
+fn main() -> GameResult<ExitCode> {
+    let (ctx, events_loop) = &mut ggez::ContextBuilder::new("yahtzee", "Sprial404")
+        .window_setup(window::setup("Yahtzee"))
+        .window_mode(ggez::conf::WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .build()?;
+    let mut state = App::new(GameState::new());
+    let result = event::run(ctx, events_loop, &mut state);
+    state.exit_code(result)
+}
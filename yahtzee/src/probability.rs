@@ -0,0 +1,147 @@
+//! Dice probabilities behind the expected value hints.
+
+use std::collections::{HashMap, HashSet};
+
+pub type Dice = [u8; 5];
+
+/// Every distinct result of rolling `count` dice, sorted, along with its probability.
+fn outcomes(count: usize) -> Vec<(Vec<u8>, f64)> {
+    let total = 6u32.pow(count as u32);
+    let mut counts: HashMap<Vec<u8>, u32> = HashMap::new();
+    for mut n in 0..total {
+        let mut roll = Vec::with_capacity(count);
+        for _ in 0..count {
+            roll.push((n % 6) as u8 + 1);
+            n /= 6;
+        }
+        roll.sort();
+        *counts.entry(roll).or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(roll, count)| (roll, count as f64 / total as f64))
+        .collect()
+}
+
+/// What to do with the dice to get the most points this turn.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Advice {
+    /// Which dice to keep. Holding every die means scoring the dice as they are.
+    pub hold: [bool; 5],
+    /// The points expected by the end of the turn when following the advice.
+    pub expected: f64,
+}
+
+impl Advice {
+    pub fn score_now(&self) -> bool {
+        self.hold.iter().all(|&held| held)
+    }
+}
+
+/// Works out the expected points for every way of holding the dice.
+pub struct Advisor {
+    /// The outcomes of rolling each number of dice from zero to five.
+    outcomes: Vec<Vec<(Vec<u8>, f64)>>,
+}
+
+impl Advisor {
+    pub fn new() -> Self {
+        Advisor {
+            outcomes: (0..=5).map(outcomes).collect(),
+        }
+    }
+
+    /// Finds the hold that maximises the expected points for the rest of the turn, where `score`
+    /// is the most the dice can score right now.
+    pub fn advise<F>(&self, dice: &Dice, rolls_left: u32, score: F) -> Advice
+    where
+        F: Fn(&Dice) -> u32,
+    {
+        let mut best = Advice {
+            hold: [true; 5],
+            expected: score(dice) as f64,
+        };
+        if rolls_left == 0 {
+            return best;
+        }
+
+        let mut memo = HashMap::new();
+        for mask in 0..31u8 {
+            let mut hold = [false; 5];
+            let mut held = Vec::new();
+            for (i, keep) in hold.iter_mut().enumerate() {
+                if mask & (1 << i) != 0 {
+                    *keep = true;
+                    held.push(dice[i]);
+                }
+            }
+
+            let expected = self.expected_after_roll(&held, rolls_left - 1, &score, &mut memo);
+            // Prefer holding more dice when the expectation is the same.
+            if expected > best.expected + 1e-9 {
+                best = Advice { hold, expected };
+            }
+        }
+        best
+    }
+
+    /// The expected points after rolling every die that isn't `held`.
+    fn expected_after_roll<F>(
+        &self,
+        held: &[u8],
+        rolls_left: u32,
+        score: &F,
+        memo: &mut HashMap<(Dice, u32), f64>,
+    ) -> f64
+    where
+        F: Fn(&Dice) -> u32,
+    {
+        self.outcomes[5 - held.len()]
+            .iter()
+            .map(|(roll, probability)| {
+                let mut dice = [0; 5];
+                for (die, &value) in dice.iter_mut().zip(held.iter().chain(roll.iter())) {
+                    *die = value;
+                }
+                dice.sort();
+                probability * self.value(dice, rolls_left, score, memo)
+            })
+            .sum()
+    }
+
+    /// The expected points of sorted `dice` when played perfectly with `rolls_left` rolls left.
+    fn value<F>(
+        &self,
+        dice: Dice,
+        rolls_left: u32,
+        score: &F,
+        memo: &mut HashMap<(Dice, u32), f64>,
+    ) -> f64
+    where
+        F: Fn(&Dice) -> u32,
+    {
+        if let Some(&value) = memo.get(&(dice, rolls_left)) {
+            return value;
+        }
+
+        let mut best = score(&dice) as f64;
+        if rolls_left > 0 {
+            // Holding the same values is the same choice whichever dice show them.
+            let mut tried = HashSet::new();
+            for mask in 0..31u8 {
+                let held: Vec<u8> = (0..5)
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| dice[i])
+                    .collect();
+                if tried.insert(held.clone()) {
+                    let expected = self.expected_after_roll(&held, rolls_left - 1, score, memo);
+                    best = best.max(expected);
+                }
+            }
+        }
+
+        memo.insert((dice, rolls_left), best);
+        best
+    }
+}
@@ -0,0 +1,248 @@
+//! The Yahtzee scorecard and its scoring rules.
+
+use crate::probability::Dice;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Category {
+    Ones,
+    Twos,
+    Threes,
+    Fours,
+    Fives,
+    Sixes,
+    ThreeOfAKind,
+    FourOfAKind,
+    FullHouse,
+    SmallStraight,
+    LargeStraight,
+    Yahtzee,
+    Chance,
+}
+
+pub const CATEGORIES: [Category; 13] = [
+    Category::Ones,
+    Category::Twos,
+    Category::Threes,
+    Category::Fours,
+    Category::Fives,
+    Category::Sixes,
+    Category::ThreeOfAKind,
+    Category::FourOfAKind,
+    Category::FullHouse,
+    Category::SmallStraight,
+    Category::LargeStraight,
+    Category::Yahtzee,
+    Category::Chance,
+];
+
+const FULL_HOUSE: u32 = 25;
+const SMALL_STRAIGHT: u32 = 30;
+const LARGE_STRAIGHT: u32 = 40;
+const YAHTZEE: u32 = 50;
+/// The points for every Yahtzee after the first, if the first was scored as a Yahtzee.
+const YAHTZEE_BONUS: u32 = 100;
+/// The upper section total needed for the upper bonus.
+const UPPER_BONUS_THRESHOLD: u32 = 63;
+const UPPER_BONUS: u32 = 35;
+
+impl Category {
+    pub fn index(self) -> usize {
+        CATEGORIES.iter().position(|&c| c == self).unwrap_or(0)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Ones => "Ones",
+            Category::Twos => "Twos",
+            Category::Threes => "Threes",
+            Category::Fours => "Fours",
+            Category::Fives => "Fives",
+            Category::Sixes => "Sixes",
+            Category::ThreeOfAKind => "3 of a kind",
+            Category::FourOfAKind => "4 of a kind",
+            Category::FullHouse => "Full house",
+            Category::SmallStraight => "Sm. straight",
+            Category::LargeStraight => "Lg. straight",
+            Category::Yahtzee => "Yahtzee",
+            Category::Chance => "Chance",
+        }
+    }
+
+    /// The face counted by an upper section category.
+    pub fn face(self) -> Option<u8> {
+        if self.index() < 6 {
+            Some(self.index() as u8 + 1)
+        } else {
+            None
+        }
+    }
+
+    /// The score of `dice` in this category by the normal rules.
+    pub fn base_score(self, dice: &Dice) -> u32 {
+        let counts = counts(dice);
+        let sum: u32 = dice.iter().map(|&die| die as u32).sum();
+        let has_run = |len: usize| {
+            (1..=7 - len).any(|start| (start..start + len).all(|face| counts[face] > 0))
+        };
+
+        if let Some(face) = self.face() {
+            return counts[face as usize] as u32 * face as u32;
+        }
+        match self {
+            Category::ThreeOfAKind if counts.iter().any(|&n| n >= 3) => sum,
+            Category::FourOfAKind if counts.iter().any(|&n| n >= 4) => sum,
+            Category::FullHouse
+                if counts.iter().any(|&n| n == 3) && counts.iter().any(|&n| n == 2) =>
+            {
+                FULL_HOUSE
+            }
+            Category::SmallStraight if has_run(4) => SMALL_STRAIGHT,
+            Category::LargeStraight if has_run(5) => LARGE_STRAIGHT,
+            Category::Yahtzee if is_yahtzee(dice) => YAHTZEE,
+            Category::Chance => sum,
+            _ => 0,
+        }
+    }
+
+    fn is_upper(self) -> bool {
+        self.face().is_some()
+    }
+}
+
+/// How many dice show each face, indexed by the face.
+fn counts(dice: &Dice) -> [u8; 7] {
+    let mut counts = [0; 7];
+    for &die in dice {
+        counts[die as usize] += 1;
+    }
+    counts
+}
+
+pub fn is_yahtzee(dice: &Dice) -> bool {
+    dice.iter().all(|&die| die == dice[0])
+}
+
+/// One player's scorecard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scorecard {
+    scores: [Option<u32>; 13],
+    yahtzee_bonus: u32,
+}
+
+impl Scorecard {
+    pub fn new() -> Self {
+        Scorecard {
+            scores: [None; 13],
+            yahtzee_bonus: 0,
+        }
+    }
+
+    pub fn get(&self, category: Category) -> Option<u32> {
+        self.scores[category.index()]
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.scores.iter().all(Option::is_some)
+    }
+
+    /// Whether `dice` are a Yahtzee that has to be played as a joker, because the Yahtzee box
+    /// has already been filled.
+    fn is_joker(&self, dice: &Dice) -> bool {
+        is_yahtzee(dice) && self.get(Category::Yahtzee).is_some()
+    }
+
+    /// Whether `dice` can be scored in `category`.
+    ///
+    /// A joker has to go in the upper box for its face if that is open, then in any open lower
+    /// box, and only then in another upper box.
+    pub fn can_score(&self, category: Category, dice: &Dice) -> bool {
+        if self.get(category).is_some() {
+            return false;
+        }
+        if !self.is_joker(dice) {
+            return true;
+        }
+
+        let face_category = CATEGORIES[dice[0] as usize - 1];
+        if self.get(face_category).is_none() {
+            return category == face_category;
+        }
+        let lower_open = CATEGORIES
+            .iter()
+            .any(|c| !c.is_upper() && self.get(*c).is_none());
+        !lower_open || !category.is_upper()
+    }
+
+    /// The points `dice` would score in `category`, not counting any Yahtzee bonus.
+    pub fn potential(&self, category: Category, dice: &Dice) -> u32 {
+        if self.is_joker(dice) {
+            match category {
+                Category::FullHouse => return FULL_HOUSE,
+                Category::SmallStraight => return SMALL_STRAIGHT,
+                Category::LargeStraight => return LARGE_STRAIGHT,
+                _ => (),
+            }
+        }
+        category.base_score(dice)
+    }
+
+    /// The Yahtzee bonus scoring `dice` would earn.
+    pub fn bonus_for(&self, dice: &Dice) -> u32 {
+        if self.is_joker(dice) && self.get(Category::Yahtzee) == Some(YAHTZEE) {
+            YAHTZEE_BONUS
+        } else {
+            0
+        }
+    }
+
+    /// The most `dice` could score in any allowed category, including any Yahtzee bonus.
+    pub fn best_score(&self, dice: &Dice) -> u32 {
+        CATEGORIES
+            .iter()
+            .filter(|c| self.can_score(**c, dice))
+            .map(|c| self.potential(*c, dice))
+            .max()
+            .unwrap_or(0)
+            + self.bonus_for(dice)
+    }
+
+    /// The allowed category `dice` score the most in.
+    pub fn best_category(&self, dice: &Dice) -> Option<Category> {
+        CATEGORIES
+            .iter()
+            .cloned()
+            .filter(|c| self.can_score(*c, dice))
+            .max_by_key(|c| (self.potential(*c, dice), std::cmp::Reverse(c.index())))
+    }
+
+    /// Scores `dice` in `category`. Does nothing if they can't be scored there.
+    pub fn record(&mut self, category: Category, dice: &Dice) {
+        if !self.can_score(category, dice) {
+            return;
+        }
+        self.yahtzee_bonus += self.bonus_for(dice);
+        self.scores[category.index()] = Some(self.potential(category, dice));
+    }
+
+    pub fn upper_subtotal(&self) -> u32 {
+        self.scores[..6].iter().filter_map(|score| *score).sum()
+    }
+
+    pub fn upper_bonus(&self) -> u32 {
+        if self.upper_subtotal() >= UPPER_BONUS_THRESHOLD {
+            UPPER_BONUS
+        } else {
+            0
+        }
+    }
+
+    pub fn yahtzee_bonus(&self) -> u32 {
+        self.yahtzee_bonus
+    }
+
+    pub fn total(&self) -> u32 {
+        self.scores.iter().filter_map(|score| *score).sum::<u32>()
+            + self.upper_bonus()
+            + self.yahtzee_bonus
+    }
+}